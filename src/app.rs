@@ -1,11 +1,99 @@
 use egui::Color32;
-use log::{error, info};
+use log::{error, info, Level, LevelFilter};
+use std::collections::HashMap;
 use std::env;
+use std::fs;
 use std::io;
-use std::path::PathBuf;
-
-use crate::helpers::sanitize_string;
-use crate::workfiles::Dcc;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+
+#[cfg(feature = "deliveries")]
+use crate::archive::archive_files;
+use crate::archive::ArchiveSettings;
+#[cfg(feature = "deliveries")]
+use crate::atomic::write_bytes_atomic;
+use crate::autosave::{find_autosaves, Autosave};
+use crate::batch::{version_up_tasks, BatchReport};
+use crate::cache::ScanCache;
+use crate::clients::{client_projects, ClientProject};
+use crate::commands::{handle, Command, CommandChannel, CommandContext, Event};
+use crate::compare::{diff_lines, is_text_workfile, DiffLine, FileStats};
+use crate::crash::{crash_dir, set_crash_state, take_pending_report};
+#[cfg(feature = "deliveries")]
+use crate::deliveries::{find_interrupted, DeliveryManifest, DeliveryPackage, TimelineFormat};
+#[cfg(feature = "deliveries")]
+use crate::delivery_notes::{load_template, note_path, DeliveryNote, NoteFormat};
+use crate::detect::detect_executables;
+use crate::fuzzy::fuzzy_filter;
+use crate::health::{check_project, HealthIssue};
+use crate::helpers::PIPELINE_TEMPLATE_DIR_NAME;
+use crate::helpers::{
+    current_user, format_date, format_size, format_timestamp, now_timestamp, open_folder,
+    read_config_file, sanitize_string, set_transliterations, user_config_dir, EXPLORER, FINDER,
+};
+use crate::hooks::{HookContext, HookEvent, Hooks};
+use crate::i18n::{set_language, tr, trf, LANGUAGES};
+use crate::ignore::IgnorePatterns;
+use crate::importer::{import_tasks, ImportResult, ImportRow};
+use crate::ingest::{ingest, ingest_dir, plan_ingest, IngestItem, IngestSettings};
+use crate::ipc::{IpcContext, IpcEvent, IpcServer};
+use crate::jobs::{JobQueue, JobState};
+use crate::links::{find_task, register_scheme, task_key, OpenTarget, LINK_SCHEME};
+use crate::lint::{lint_tasks, rename_issues, NamingIssue, NamingProblem};
+use crate::logging::{clear_lines, format_lines, recent_lines, LogLine};
+use crate::metadata::{MetadataColumn, MetadataStore, APP_VERSION_KEY};
+use crate::mirror::{MirrorLog, MirrorProfile};
+use crate::naming::{default_filename_template, FilenameTemplate};
+use crate::notes::{FileNote, FileNotes};
+use crate::notifications::{Notifications, Severity};
+use crate::offline::{is_reachable, not_cached_error, Connectivity, RECONNECT_INTERVAL};
+use crate::pathmap::{
+    detect_mapped_drives, map_path, set_mappings, set_projects_dir, to_local, with_detected,
+    PathMapping, PathStyle,
+};
+use crate::permissions::{set_create_permissions, CreatePermissions};
+use crate::pipeline::PipelineContext;
+use crate::player::{default_player_args, Player};
+use crate::plugins::{Plugin, PluginPayload, PluginTarget};
+use crate::preferences::{
+    push_recent, FileDoubleClick, PanelLayout, Preferences, UiPrefs, MAX_UI_SCALE, MIN_UI_SCALE,
+};
+use crate::presence::{Session, HEARTBEAT_INTERVAL};
+use crate::projects::{
+    episode_names, group_projects, ProjectAdoption, ProjectGrouping, ProjectSorting,
+    PROJECT_STATUSES,
+};
+use crate::publishes::{Publish, ReviewState};
+use crate::report::write_project_report;
+use crate::retry::{set_retry_policy, RetryPolicy};
+use crate::roles::{current_groups, project_access, Access, ProjectAccess, Role};
+use crate::schedule::{
+    group_by_month, parse_date, project_items, task_items, ScheduleItem, ScheduleKind,
+};
+#[cfg(feature = "python")]
+use crate::scripting::{Script, ScriptContext};
+use crate::sequences::{collapse, find_files, OutputItem};
+use crate::structure::{
+    copy_tree, create_structure, StructureEntry, StructureNode, StructureResult,
+};
+use crate::sync::CloudSync;
+use crate::tasks::{Milestone, TaskDetails};
+use crate::templates::{
+    check_setup, fill_executables, import_template, restore_template, scan_templates, TemplateDir,
+};
+use crate::theme::Theme;
+use crate::transcode::{default_framerate, default_proxy_args, proxy_path, Transcoder};
+use crate::trash::{Trash, TrashItem, TrashKind};
+#[cfg(feature = "deliveries")]
+use crate::uploads::UploadTarget;
+use crate::validation::{
+    validate_child_name, validate_child_names, validate_file_path, validate_name,
+    validate_project_name, ValidationError,
+};
+use crate::vfs::RealFs;
+use crate::watch::{remove_entry, InboxWatcher, INBOX_SCAN_INTERVAL};
+use crate::webhooks::{send_all, Webhook, WebhookContext, WebhookEvent};
+use crate::workfiles::{Dcc, FileGroup, Retained, RetentionPolicy, VersionTransfer};
 use crate::Client;
 use crate::File;
 use crate::Project;
@@ -14,17 +102,21 @@ use crate::TaskTreeNode;
 pub const SPACING: f32 = 5.;
 pub const TEXTEDIT_WIDTH: f32 = 125.;
 const CONFIG_ENV_VAR: &str = "RCLAMP_CONFIG";
-
-#[derive(serde::Deserialize, serde::Serialize, Debug)]
-struct Message {
-    text: String,
-    message_type: MessageType,
-}
-
-#[derive(serde::Deserialize, serde::Serialize, Debug)]
-enum MessageType {
-    Info,
-    Warning,
+/// Config file names looked for in the fallback locations, in order.
+const CONFIG_FILE_NAMES: [&str; 3] = ["config.yaml", "config.toml", "config.json"];
+/// Unchanged lines shown around each change when comparing versions.
+const DIFF_CONTEXT_LINES: usize = 3;
+/// Larger files are versioned up as jobs, with progress and a cancel button.
+const BACKGROUND_COPY_SIZE: u64 = 64 * 1024 * 1024;
+
+/// The views of the central panel.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Default, PartialEq, Clone, Copy)]
+enum CentralTab {
+    #[default]
+    Workfiles,
+    Outputs,
+    Publishes,
+    Schedule,
 }
 
 #[derive(serde::Deserialize, serde::Serialize, Debug)]
@@ -33,8 +125,50 @@ struct RclampAppConfig {
     projects_dir: Option<PathBuf>,
     templates_dir: PathBuf,
     template_project: Project,
-    ignore_extensions: Vec<String>,
+    #[serde(default)]
+    ignore_patterns: Vec<String>,
     clients_path: PathBuf,
+    #[serde(default)]
+    filename_template: FilenameTemplate,
+    #[serde(default)]
+    transliterations: HashMap<String, String>,
+    #[serde(default)]
+    plugins_dir: Option<PathBuf>,
+    #[serde(default)]
+    scripts_dir: Option<PathBuf>,
+    #[serde(default)]
+    player: Option<Player>,
+    #[serde(default)]
+    transcoder: Option<Transcoder>,
+    #[serde(default)]
+    path_mappings: Vec<PathMapping>,
+    #[serde(default)]
+    theme: Theme,
+    #[serde(default)]
+    webhooks: Vec<Webhook>,
+    #[serde(default)]
+    cloud_sync: Option<CloudSync>,
+    #[serde(default)]
+    mirror_profiles: Vec<MirrorProfile>,
+    #[serde(default)]
+    file_columns: Vec<MetadataColumn>,
+    #[serde(default)]
+    ingest: IngestSettings,
+    #[serde(default)]
+    retention: RetentionPolicy,
+    #[serde(default)]
+    role: Role,
+    #[serde(default)]
+    task_presets: Vec<String>,
+    #[serde(default)]
+    create_permissions: CreatePermissions,
+    #[serde(default)]
+    retry: RetryPolicy,
+    #[serde(default)]
+    archive: ArchiveSettings,
+    #[cfg(feature = "deliveries")]
+    #[serde(default)]
+    upload_targets: Vec<UploadTarget>,
 }
 
 #[derive(serde::Deserialize, serde::Serialize, Debug)]
@@ -49,44 +183,387 @@ struct RclampConfig {
     deliveries_dir_name: String,
     extra_dir_names: Vec<String>,
     work_sub_dirs: Vec<String>,
+    /// The dir of the workfiles in each task, the first work sub dir if not set.
+    #[serde(default)]
+    task_work_dir_name: String,
+    /// The dir of the outputs in each task, the second work sub dir if not set.
+    #[serde(default)]
+    task_output_dir_name: String,
+    /// Deprecated, use ignore_patterns. Each extension is read as `*.<extension>`.
+    #[serde(default)]
     ignore_extensions: Vec<String>,
+    #[serde(default)]
+    ignore_patterns: Vec<String>,
     clients_path_win: String,
     clients_path_mac: String,
+    #[serde(default = "default_filename_template")]
+    filename_template: FilenameTemplate,
+    #[serde(default)]
+    transliterations: HashMap<String, String>,
+    #[serde(default)]
+    plugins_dir_win: String,
+    #[serde(default)]
+    plugins_dir_mac: String,
+    #[serde(default)]
+    scripts_dir_win: String,
+    #[serde(default)]
+    scripts_dir_mac: String,
+    #[serde(default)]
+    player_win: String,
+    #[serde(default)]
+    player_mac: String,
+    #[serde(default = "default_player_args")]
+    player_args: Vec<String>,
+    #[serde(default)]
+    ffmpeg_win: String,
+    #[serde(default)]
+    ffmpeg_mac: String,
+    #[serde(default = "default_framerate")]
+    proxy_framerate: u32,
+    #[serde(default = "default_proxy_args")]
+    proxy_args: Vec<String>,
+    #[serde(default)]
+    path_mappings: Vec<PathMapping>,
+    /// Overrides the studio theme in the pipeline template dir.
+    #[serde(default)]
+    theme: Option<Theme>,
+    #[serde(default)]
+    webhooks: Vec<Webhook>,
+    #[serde(default)]
+    aws_win: String,
+    #[serde(default)]
+    aws_mac: String,
+    #[serde(default)]
+    sync_remote: String,
+    #[serde(default)]
+    sync_endpoint: String,
+    #[serde(default)]
+    mirror_profiles: Vec<MirrorProfile>,
+    #[serde(default)]
+    file_columns: Vec<MetadataColumn>,
+    #[serde(default)]
+    ingest: IngestSettings,
+    /// Which workfile versions can't be moved to the trash.
+    #[serde(default)]
+    retention: RetentionPolicy,
+    /// Viewers can't create, delete or version anything. The `RCLAMP_ROLE` environment variable overrides it.
+    #[serde(default)]
+    role: Role,
+    /// Task names created with one click in the create task dialog, e.g. `comp` or `grade`.
+    #[serde(default)]
+    task_presets: Vec<String>,
+    /// Permissions of created dirs and copied files.
+    #[serde(default)]
+    create_permissions: CreatePermissions,
+    /// How often file operations failing with transient errors are tried.
+    #[serde(default)]
+    retry: RetryPolicy,
+    /// Archives of delivery packages and projects.
+    #[serde(default)]
+    archive: ArchiveSettings,
+    #[serde(default)]
+    seven_zip_win: String,
+    #[serde(default)]
+    seven_zip_mac: String,
+    /// Client endpoints finished delivery packages can be uploaded to.
+    #[cfg(feature = "deliveries")]
+    #[serde(default)]
+    upload_targets: Vec<UploadTarget>,
 }
 
-#[derive(serde::Deserialize, serde::Serialize, Debug)]
-#[serde(default)]
+impl RclampAppConfig {
+    /// The parts of the config that project, task and file operations need.
+    fn pipeline_context(&self) -> PipelineContext {
+        PipelineContext {
+            projects_dir: self.projects_dir.clone().unwrap_or_default(),
+            templates_dir: self.templates_dir.clone(),
+            template_project: self.template_project.clone(),
+            filename_template: self.filename_template.clone(),
+            ignore_patterns: self.ignore_patterns.clone(),
+        }
+    }
+}
+
+#[derive(Debug)]
 pub struct Rclamp {
     current_project: Option<Project>,
-    current_project_task_tree: Option<TaskTreeNode>,
+    /// Shared with the frames that render it, so they don't clone the tree.
+    current_project_task_tree: Option<Rc<TaskTreeNode>>,
+    /// The tree filtered by the filter text, with the text and the tree it was filtered from.
+    filtered_task_tree: Option<(String, Rc<TaskTreeNode>, Option<Rc<TaskTreeNode>>)>,
     current_task: Option<TaskTreeNode>,
     projects: Vec<Project>,
     projects_filtered: Vec<Project>,
-    files: Option<Vec<File>>,
+    /// Groups of the logged in user, matched against the access lists of projects.
+    user_groups: Vec<String>,
+    files: Option<Rc<Vec<File>>>,
+    /// The versions of the files grouped by name.
+    file_groups: Rc<Vec<FileGroup>>,
+    outputs: Option<Vec<OutputItem>>,
+    central_tab: CentralTab,
+    layout: PanelLayout,
+    preferences: Preferences,
     dcc: Vec<Dcc>,
     config: RclampAppConfig,
     clients: Vec<Client>,
-
-    message: Option<Message>,
+    plugins: Vec<Plugin>,
+    ipc: Option<IpcServer>,
+    /// Intents of the widgets, run after the frame.
+    commands: CommandChannel,
+    jobs: Option<JobQueue>,
+    /// Version-ups of large files running as jobs, the files are refreshed when they finish.
+    version_up_jobs: Vec<u64>,
+    notifications: Notifications,
+
+    show_notifications: bool,
+    show_preferences: bool,
     show_create_project: bool,
     show_create_task: bool,
     show_create_folder: bool,
     show_clients_panel: bool,
+    /// The client picked in the client view, by short name, and its projects.
+    client_view: Option<(String, Vec<ClientProject>)>,
+    show_create_structure: bool,
+    show_import_tasks: bool,
+    show_trash: bool,
     new_project_name: String,
     new_project_number: String,
     new_project_client: Client,
+    /// Episodes of a program are created as one project each, none creates a single project.
+    new_project_episodes: u32,
+    new_project_season: u32,
     new_task_name: String,
+    /// Task names pasted into the create task dialog, one per line, and the results of creating them.
+    new_task_list: Option<(String, Vec<StructureResult>)>,
     new_folder_name: String,
     new_task_parent: TaskTreeNode,
     new_folder_parent: TaskTreeNode,
+    new_structure_text: String,
+    new_structure_parent: TaskTreeNode,
+    structure_results: Vec<StructureResult>,
+    import_tasks_path: String,
+    import_tasks_parent: TaskTreeNode,
+    import_results: Vec<ImportResult>,
+    trash_items: Vec<TrashItem>,
+    client_trash_items: Vec<TrashItem>,
     new_file_name: String,
     new_file_type: Dcc,
     new_client_fullname: String,
     new_client_shortname: String,
     remove_client: Client,
     project_filter: String,
+    task_filter: String,
+    custom_font_path: String,
+    /// Project names shown first in the project list.
+    pinned_projects: Vec<String>,
+    /// Task dirs, most recent first.
+    recent_tasks: Vec<PathBuf>,
+    /// The file app state is exported to and imported from.
+    state_path: String,
+    /// A project and task to open on the next frame, from the command line or a link.
+    pending_open: Option<OpenTarget>,
+    /// The project and task dirs of the last session, reopened once the projects are listed.
+    last_session: Option<(PathBuf, Option<PathBuf>)>,
+    /// A tree node to expand the ancestors of and scroll to on the next frame, set from the breadcrumbs.
+    reveal_node: Option<PathBuf>,
+    project_grouping: ProjectGrouping,
+    project_sorting: ProjectSorting,
+    /// The grouped and sorted filtered projects, cleared when the list or the options change.
+    project_groups: Option<Rc<Vec<ProjectGroup>>>,
+    /// The pinned ones of the filtered projects, rebuilt along with `project_groups`.
+    pinned_filtered: Rc<Vec<Project>>,
+    /// The project and issues shown in the health check window.
+    health_check: Option<(Project, Vec<HealthIssue>)>,
+    /// The project whose access list is edited, with the owners, members and viewers one per line.
+    edit_project_access: Option<(Project, [String; 3])>,
+    /// The project being duplicated, the new name and whether its folders and tasks are copied.
+    duplicate_project: Option<(Project, String, bool)>,
+    /// The project whose client, program and due date are edited, with the due date as typed.
+    edit_project_metadata: Option<(Project, String)>,
+    show_adopt_project: bool,
+    /// Folders in the projects dir without a project file.
+    adoption_candidates: Vec<PathBuf>,
+    adoption: Option<ProjectAdoption>,
+    /// The projects on the cloud remote, listed when the restore window opens.
+    remote_projects: Option<Vec<String>>,
+    /// The last mirror run and its output.
+    mirror_log: Option<(MirrorProfile, MirrorLog)>,
+    naming_lint: Option<NamingLint>,
+    batch_version_up: Option<BatchVersionUp>,
+    footage_ingest: Option<FootageIngest>,
+    inbox: InboxReview,
+    templates_manager: Option<TemplatesManager>,
+    /// The delivery being packaged, with the outputs of the task, whether each is included, the name
+    /// of the upload target and the notes for the delivery note.
+    #[cfg(feature = "deliveries")]
+    delivery_package: Option<(DeliveryPackage, Vec<bool>, Option<String>, String)>,
+    /// Package jobs with the archive or dir to upload once they're done, and where.
+    #[cfg(feature = "deliveries")]
+    delivery_uploads: Vec<(u64, PathBuf, UploadTarget)>,
+    /// Package jobs with the note to list their checksums in once they're done, the package and its dir.
+    #[cfg(feature = "deliveries")]
+    pending_delivery_notes: Vec<(u64, DeliveryNote, DeliveryPackage, PathBuf)>,
+    /// The note of a finished package with the package dir, the shown format and the text, which can be
+    /// edited before it's copied or saved.
+    #[cfg(feature = "deliveries")]
+    delivery_note: Option<(DeliveryNote, PathBuf, NoteFormat, String)>,
+    file_transfer: Option<FileTransfer>,
+    compare_versions: Option<VersionComparison>,
+    /// The workfile to duplicate as a new variant and the name of the variant.
+    duplicate_file: Option<(File, String)>,
+    /// The action, the old version and the latest version, shown in a confirm dialog.
+    stale_action: Option<(StaleAction, File, File)>,
+    /// The workfile to choose an app for.
+    open_with: Option<File>,
+    /// A workfile saved with a newer app version than the project uses, the version it was saved
+    /// with and the project version, shown in a confirm dialog.
+    newer_app_version: Option<(File, String, String)>,
+    /// Autosaves of the workfiles in the current task, while the recovery window is open.
+    autosaves: Option<Vec<Autosave>>,
+    /// The least severe level shown in the log panel.
+    log_level: LevelFilter,
+    /// Where the log panel saves the log.
+    log_path: String,
+    /// The report of a crash in the last session and its text, offered at start.
+    crash_report: Option<(PathBuf, String)>,
+    /// What's wrong with the templates and the config, shown together at start.
+    setup_problems: Vec<String>,
+    /// Projects and task trees of earlier scans, so refreshes only rescan what changed.
+    scan_cache: ScanCache,
+    /// Whether the projects dir can be reached, and the changes queued while it can't.
+    connectivity: Connectivity,
+    /// Notes and tags of the workfiles in the current task.
+    file_notes: FileNotes,
+    /// Description, links and references of the current task.
+    task_details: TaskDetails,
+    edit_task_details: Option<TaskDetailsEdit>,
+    /// Due dates and milestones, with the name of the project they were collected for.
+    schedule: Option<(Option<String>, Vec<ScheduleItem>)>,
+    /// Metadata of the files in the current task, shown in the extra columns of the files table.
+    file_metadata: MetadataStore,
+    /// The file and column being edited inline, and the edited value.
+    edit_metadata: Option<(PathBuf, String, String)>,
+    /// The folder, task or workfile renamed in the tree or the files table, and the name typed so far.
+    inline_rename: Option<(RenameTarget, String)>,
+    /// Only files with this tag are listed.
+    file_tag_filter: Option<String>,
+    /// The workfile whose note is edited, the edited note and the tag being typed.
+    edit_note: Option<(File, FileNote, String)>,
+    /// Publishes of the current task, newest first.
+    publishes: Vec<Publish>,
+    publish_state_filter: Option<ReviewState>,
+    /// The publish being reviewed, the new state and the review comment.
+    review_publish: Option<(Publish, ReviewState, String)>,
+    /// The own session and the pipeline dir of the project it was written to.
+    session: Option<(PathBuf, Session)>,
+    /// Other users with the current project open.
+    sessions: Vec<Session>,
+}
+
+/// A workfile being moved or copied to another task of the current project.
+#[derive(Debug, Clone)]
+struct FileTransfer {
+    file: File,
+    keep_source: bool,
+    target: Option<TaskTreeNode>,
+    filter: String,
+    version: VersionTransfer,
+}
+
+/// Two versions of a workfile being compared, the older one first.
+#[derive(Debug, Clone)]
+struct VersionComparison {
+    files: [File; 2],
+    stats: [Option<FileStats>; 2],
+    /// The text diff once asked for, or why there is none.
+    diff: Option<Result<Vec<DiffLine>, String>>,
+}
+
+/// The misnamed workfiles of the current project, and the report of the last (dry) rename.
+#[derive(Debug, Clone, Default)]
+struct NamingLint {
+    issues: Vec<NamingIssue>,
+    report: Vec<String>,
+}
+
+/// The tasks picked for a batch version-up, and the job and its report once it runs.
+#[derive(Debug, Clone, Default)]
+struct BatchVersionUp {
+    tasks: Vec<(TaskTreeNode, bool)>,
+    filter: String,
+    job: Option<(u64, BatchReport)>,
+}
+
+/// The details of the current task being edited, with the link and reference path being typed.
+#[derive(Debug, Clone, Default)]
+struct TaskDetailsEdit {
+    details: TaskDetails,
+    due_date: String,
+    new_link: String,
+    new_reference: String,
+}
+
+/// The sources and template of an ingest, with the planned names or the error listing the sources.
+#[derive(Debug, Clone)]
+struct FootageIngest {
+    sources: Vec<PathBuf>,
+    new_source: String,
+    template: String,
+    items: Result<Vec<IngestItem>, String>,
+}
+
+/// The DCC dirs of the templates dir, and the DCC name and file of a template to import.
+#[derive(Debug, Clone)]
+struct TemplatesManager {
+    dirs: Result<Vec<TemplateDir>, String>,
+    import_name: String,
+    import_path: String,
+}
+
+/// The watch folder and its review queue, with whether each entry is included and the project it goes to.
+#[derive(Debug, Default)]
+struct InboxReview {
+    watcher: InboxWatcher,
+    scanned_at: u64,
+    show: bool,
+    choices: HashMap<PathBuf, (bool, String)>,
+    /// Running ingests and the inbox entries they ingest.
+    jobs: Vec<(u64, Vec<PathBuf>)>,
+}
+
+/// A folder, task or workfile being renamed in place.
+#[derive(Debug, Clone)]
+enum RenameTarget {
+    Node(TaskTreeNode),
+    File(File),
+}
+
+impl RenameTarget {
+    fn path(&self) -> &Path {
+        match self {
+            RenameTarget::Node(n) => &n.path,
+            RenameTarget::File(f) => &f.path,
+        }
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            RenameTarget::Node(n) => &n.name,
+            RenameTarget::File(f) => &f.name,
+        }
+    }
+}
+
+/// An action on an old version of a workfile, waiting for confirmation.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum StaleAction {
+    Open,
+    VersionUp,
 }
 
+/// A group name and its projects.
+type ProjectGroup = (String, Vec<Project>);
+
 impl Default for Rclamp {
     fn default() -> Self {
         let projects_dir = PathBuf::new();
@@ -110,7 +587,6 @@ impl Default for Rclamp {
             ]),
         );
 
-        let message: Option<Message> = None;
         let projects: Vec<Project> = Vec::new();
         let projects_filtered = projects.clone();
         let dcc = Vec::new();
@@ -121,40 +597,94 @@ impl Default for Rclamp {
             current_project: None,
             projects,
             projects_filtered,
+            user_groups: current_groups(),
             current_project_task_tree: None,
+            filtered_task_tree: None,
             current_task: None,
             files: None,
+            file_groups: Rc::default(),
+            outputs: None,
+            central_tab: CentralTab::Workfiles,
+            layout: PanelLayout::default(),
+            preferences: Preferences::default(),
             dcc,
             config: RclampAppConfig {
                 dark_mode: true,
                 projects_dir: None,
                 templates_dir,
                 template_project,
-                ignore_extensions: Vec::new(),
+                ignore_patterns: Vec::new(),
                 clients_path: PathBuf::new(),
+                filename_template: FilenameTemplate::default(),
+                transliterations: HashMap::new(),
+                plugins_dir: None,
+                scripts_dir: None,
+                player: None,
+                transcoder: None,
+                path_mappings: Vec::new(),
+                theme: Theme::default(),
+                webhooks: Vec::new(),
+                cloud_sync: None,
+                mirror_profiles: Vec::new(),
+                file_columns: Vec::new(),
+                ingest: IngestSettings::default(),
+                retention: RetentionPolicy::default(),
+                role: Role::from_env(Role::default()),
+                task_presets: Vec::new(),
+                create_permissions: CreatePermissions::default(),
+                retry: RetryPolicy::default(),
+                archive: ArchiveSettings::default(),
+                #[cfg(feature = "deliveries")]
+                upload_targets: Vec::new(),
             },
             clients: Vec::new(),
-
-            message,
+            plugins: Vec::new(),
+            ipc: None,
+            commands: CommandChannel::default(),
+            jobs: None,
+            version_up_jobs: Vec::new(),
+
+            notifications: Notifications::default(),
+            show_notifications: false,
+            show_preferences: false,
             show_create_project: false,
             show_create_task: false,
             show_create_folder: false,
             show_clients_panel: false,
+            client_view: None,
+            show_create_structure: false,
+            show_import_tasks: false,
+            show_trash: false,
             new_project_name: String::new(),
             new_project_client: Client {
                 name: String::new(),
                 short_name: String::new(),
             },
             new_project_number: String::new(),
+            new_project_episodes: 0,
+            new_project_season: 1,
             new_task_name: String::new(),
+            new_task_list: None,
             new_folder_name: String::new(),
             new_task_parent: empty_task.clone(),
             new_folder_parent: empty_task.clone(),
+            new_structure_text: String::new(),
+            new_structure_parent: empty_task.clone(),
+            structure_results: Vec::new(),
+            import_tasks_path: String::new(),
+            import_tasks_parent: empty_task.clone(),
+            import_results: Vec::new(),
+            trash_items: Vec::new(),
+            client_trash_items: Vec::new(),
             new_file_name: String::new(),
             new_file_type: Dcc {
                 name: String::new(),
                 extension: String::new(),
                 template_path: PathBuf::from("does_not_exist"),
+                substitute_tokens: false,
+                executable: None,
+                version: None,
+                autosave_dirs: Vec::new(),
             },
             new_client_fullname: String::new(),
             new_client_shortname: String::new(),
@@ -163,36 +693,121 @@ impl Default for Rclamp {
                 short_name: String::new(),
             },
             project_filter: String::new(),
+            task_filter: String::new(),
+            custom_font_path: String::new(),
+            pinned_projects: Vec::new(),
+            recent_tasks: Vec::new(),
+            state_path: String::new(),
+            pending_open: None,
+            last_session: None,
+            reveal_node: None,
+            project_grouping: ProjectGrouping::default(),
+            project_sorting: ProjectSorting::default(),
+            project_groups: None,
+            pinned_filtered: Rc::default(),
+            health_check: None,
+            edit_project_access: None,
+            duplicate_project: None,
+            edit_project_metadata: None,
+            show_adopt_project: false,
+            adoption_candidates: Vec::new(),
+            adoption: None,
+            remote_projects: None,
+            mirror_log: None,
+            naming_lint: None,
+            batch_version_up: None,
+            footage_ingest: None,
+            inbox: InboxReview::default(),
+            templates_manager: None,
+            #[cfg(feature = "deliveries")]
+            delivery_package: None,
+            #[cfg(feature = "deliveries")]
+            delivery_uploads: Vec::new(),
+            #[cfg(feature = "deliveries")]
+            pending_delivery_notes: Vec::new(),
+            #[cfg(feature = "deliveries")]
+            delivery_note: None,
+            file_transfer: None,
+            compare_versions: None,
+            duplicate_file: None,
+            stale_action: None,
+            open_with: None,
+            newer_app_version: None,
+            autosaves: None,
+            log_level: LevelFilter::Info,
+            log_path: String::new(),
+            crash_report: None,
+            setup_problems: Vec::new(),
+            scan_cache: ScanCache::default(),
+            connectivity: Connectivity::default(),
+            file_notes: FileNotes::default(),
+            task_details: TaskDetails::default(),
+            schedule: None,
+            edit_task_details: None,
+            file_metadata: MetadataStore::default(),
+            edit_metadata: None,
+            inline_rename: None,
+            file_tag_filter: None,
+            edit_note: None,
+            publishes: Vec::new(),
+            publish_state_filter: None,
+            review_publish: None,
+            session: None,
+            sessions: Vec::new(),
         }
     }
 }
 
 impl Rclamp {
-    /// Called once before the first frame.
-    pub fn new(cc: &eframe::CreationContext<'_>) -> Self {
-        // This is also where you can customize the look and feel of egui using
-        // `cc.egui_ctx.set_visuals` and `cc.egui_ctx.set_fonts`.
-
-        // Load previous app state (if any).
-        // Note that you must enable the `persistence` feature for this to work.
+    /// Called once before the first frame. The target is opened once the projects are listed.
+    pub fn new(cc: &eframe::CreationContext<'_>, open: Option<OpenTarget>) -> Self {
         info!("Initializing app.");
-
-        if let Some(storage) = cc.storage {
-            info!("Reading stored app state.");
-            return eframe::get_value(storage, eframe::APP_KEY).unwrap_or_default();
+        let mut rclamp = Self::load_or_default();
+
+        // Note that you must enable the `persistence` feature for this to work. Older versions stored
+        // the whole app under the same key, the UI state is read from it and the rest is ignored.
+        let prefs: UiPrefs = cc
+            .storage
+            .and_then(|s| eframe::get_value(s, eframe::APP_KEY))
+            .unwrap_or_default();
+        let last_session = prefs
+            .last_project
+            .clone()
+            .map(|p| (p, prefs.last_task.clone()));
+        rclamp.apply_ui_prefs(prefs, &cc.egui_ctx);
+        // A link or a task on the command line is opened instead of the last session.
+        if open.is_none() {
+            rclamp.last_session = last_session;
         }
+        rclamp.pending_open = open;
+        rclamp.crash_report = crash_dir()
+            .and_then(|d| take_pending_report(&d))
+            .and_then(|p| fs::read_to_string(&p).ok().map(|t| (p, t)));
+
+        let ctx = cc.egui_ctx.clone();
+        rclamp.ipc = match IpcServer::start(move || ctx.request_repaint()) {
+            Ok(s) => Some(s),
+            Err(e) => {
+                error!("Failed to start IPC server: {}", e);
+                None
+            }
+        };
+        rclamp.update_ipc_context();
+        rclamp
+    }
 
+    /// Reads the config and scans the projects, or uses the defaults if there is no config.
+    fn load_or_default() -> Self {
         match Rclamp::load_config() {
             Ok(mut r) => {
+                // A missing templates dir is one of the setup problems, so it isn't notified too.
+                r.setup_problems = check_setup(&r.config.templates_dir, &r.config.template_project);
+                for problem in &r.setup_problems {
+                    error!("Pipeline setup problem: {}", problem);
+                }
                 match Dcc::find_dcc(&r.config.templates_dir) {
                     Ok(d) => r.dcc = d,
-                    Err(e) => {
-                        error!("Error finding DCC:s: {}", e);
-                        r.message = Some(Message {
-                            text: String::from(format!("Error finding DCC:s: {}", e)),
-                            message_type: MessageType::Warning,
-                        });
-                    }
+                    Err(e) => error!("Error finding DCC:s: {}", e),
                 };
 
                 let projects_dir = match &r.config.projects_dir {
@@ -203,19 +818,22 @@ impl Rclamp {
                     }
                 };
 
-                match Project::find_projects(projects_dir, r.config.template_project.clone()) {
+                if let Some(path) = ScanCache::path() {
+                    r.scan_cache = ScanCache::load(&path);
+                }
+                match r.scan_projects(&projects_dir) {
                     Ok(p) => {
+                        let p = r.listed_projects(p);
                         r.projects = p.clone();
                         r.project_filter = String::new();
                         r.projects_filtered = p;
+                        r.project_groups = None;
                     }
 
                     Err(e) => {
                         error!("Error finding projects: {}", e);
-                        r.message = Some(Message {
-                            text: String::from(format!("Error finding projects: {}", e)),
-                            message_type: MessageType::Warning,
-                        });
+                        r.notifications
+                            .push(Severity::Error, trf("Error finding projects: {}", &[&e]));
                     }
                 }
 
@@ -229,69 +847,491 @@ impl Rclamp {
     /// Simply sets the current project.
     fn set_current_project(&mut self, project: Project) {
         self.current_project = Some(project);
+        self.update_ipc_context();
     }
 
-    fn set_current_task(&mut self, task: TaskTreeNode) {
-        let work_subdir = match &self.current_project {
-            Some(p) => p
-                .work_sub_dirs
-                .first()
-                .clone()
-                .unwrap_or(&String::new())
-                .to_owned(),
+    /// Keeps the own session file of the current project up to date and reads the other sessions.
+    /// The file is written when the project or task changes, and otherwise every heartbeat interval.
+    fn heartbeat(&mut self) {
+        let pipeline_path = match (&self.current_project, &self.config.projects_dir) {
+            (Some(p), Some(d)) => Some(p.get_pipeline_path(d)),
+            _ => None,
+        };
+        let task_path = self.current_task.as_ref().map(|t| t.path.clone());
+        let now = now_timestamp();
+
+        let mut started_at = now;
+        if let Some((path, session)) = &self.session {
+            if Some(path) == pipeline_path.as_ref() {
+                if session.task_path == task_path && now < session.updated_at + HEARTBEAT_INTERVAL {
+                    return;
+                }
+                started_at = session.started_at;
+            } else {
+                self.end_session();
+            }
+        }
+        let pipeline_path = match pipeline_path {
+            Some(p) => p,
             None => return,
         };
 
-        self.current_task = Some(task);
+        let mut session = Session::new(self.current_task.as_ref());
+        session.started_at = started_at;
+        if let Err(e) = session.write(&pipeline_path) {
+            error!("Failed to write session: {}", e);
+        }
+        self.sessions = match Session::list_active(&pipeline_path, now) {
+            Ok(s) => s
+                .into_iter()
+                .filter(|s| s.user != session.user || s.host != session.host)
+                .collect(),
+            Err(e) => {
+                error!("Failed to read sessions: {}", e);
+                Vec::new()
+            }
+        };
+        self.session = Some((pipeline_path, session));
+    }
 
-        let mut files = match &self.current_task {
-            Some(t) => match t.find_workfiles(work_subdir) {
-                Ok(v) => v,
-                Err(e) => {
-                    error!("Error opening task: {}", e);
-                    self.message = Some(Message {
-                        text: String::from(format!("Error opening task: {}", e)),
-                        message_type: MessageType::Warning,
-                    });
-                    self.current_task = None;
-                    return;
+    fn end_session(&mut self) {
+        if let Some((path, session)) = self.session.take() {
+            if let Err(e) = session.remove(&path) {
+                error!("Failed to remove session: {}", e);
+            }
+        }
+        self.sessions.clear();
+    }
+
+    /// Shares the current selection with DCC plugins connected over IPC, and keeps it for crash reports.
+    fn update_ipc_context(&self) {
+        set_crash_state(format!(
+            "Project: {}\nTask: {}\nRole: {:?}",
+            self.current_project
+                .as_ref()
+                .map_or("None", |p| p.name.as_str()),
+            self.current_task
+                .as_ref()
+                .map_or(String::from("None"), |t| t.path.display().to_string()),
+            self.config.role
+        ));
+        if let Some(ipc) = &self.ipc {
+            ipc.set_context(IpcContext {
+                project: self.current_project.clone(),
+                projects_dir: self.config.projects_dir.clone(),
+                task: self.current_task.clone(),
+                template: self.config.filename_template.clone(),
+            });
+        }
+    }
+
+    /// Refreshes the files after version-ups and publishes from DCC plugins, and opens links forwarded
+    /// by other instances.
+    fn command_context(&self) -> CommandContext {
+        CommandContext {
+            project: self.current_project.clone(),
+            projects_dir: self.config.projects_dir.clone(),
+            task: self.current_task.clone(),
+            dccs: self.dcc.clone(),
+        }
+    }
+
+    /// Runs the commands the widgets sent during the frame and applies their events. Returns whether
+    /// there were any.
+    fn handle_commands(&mut self) -> bool {
+        let commands = self.commands.pending();
+        for command in &commands {
+            let changes_share =
+                matches!(command, Command::CreateTask { .. } | Command::VersionUp(_));
+            if changes_share && self.connectivity.is_offline() {
+                self.connectivity.queue(command.clone());
+                self.notifications.push(
+                    Severity::Warning,
+                    tr("Offline, the change runs when the projects dir is back."),
+                );
+                continue;
+            }
+            if let Command::VersionUp(f) = command {
+                let size = fs::metadata(&f.path).map_or(0, |m| m.len());
+                if size >= BACKGROUND_COPY_SIZE {
+                    self.version_up_in_background(f);
+                    continue;
                 }
-            },
+            }
+            for event in handle(command, &self.command_context()) {
+                self.apply_event(command, event);
+            }
+        }
+        !commands.is_empty()
+    }
+
+    fn apply_event(&mut self, command: &Command, event: Event) {
+        match event {
+            Event::TaskCreated(_p) => {
+                self.notifications
+                    .push(Severity::Info, tr("Successfully created task."));
+                if let Err(e) = self.rescan_task_tree() {
+                    error!("Error creating task tree: {}", e);
+                    self.notifications
+                        .push(Severity::Error, trf("Error creating task tree: {}", &[&e]));
+                }
+            }
+            Event::FileOpened(_p) => {
+                if let Command::OpenFile(file) | Command::OpenFileWith { file, .. } = command {
+                    let context = self.command_context();
+                    self.queue_post_hooks(HookEvent::PostFileOpen, context, Some(file.clone()));
+                }
+            }
+            Event::VersionedUp(_v, _p) => self.refresh_files(),
+            Event::Cancelled(e) => self
+                .notifications
+                .push(Severity::Warning, trf("Opening file cancelled: {}", &[&e])),
+            Event::Failed(e) => {
+                let message = match command {
+                    Command::CreateTask { .. } => trf("Error creating task: {}", &[&e]),
+                    Command::OpenFile(..) | Command::OpenFileWith { .. } => {
+                        trf("Error opening file: {}", &[&e])
+                    }
+                    Command::VersionUp(_f) => e,
+                };
+                self.notifications.push(Severity::Error, message);
+                if let Command::VersionUp(_f) = command {
+                    self.refresh_files();
+                }
+            }
+        }
+    }
+
+    fn handle_ipc_events(&mut self) {
+        let events = match &self.ipc {
+            Some(ipc) => ipc.events(),
             None => return,
         };
-        Self::filter_files(&mut files, self.config.ignore_extensions.clone());
-        files.sort();
-        files.reverse();
-        self.files = Some(files);
+        for event in events {
+            let text = match &event {
+                IpcEvent::Open(t) => {
+                    self.pending_open = Some(t.clone());
+                    continue;
+                }
+                IpcEvent::VersionUp(p) => trf("Versioned up from DCC: {}", &[&p.display()]),
+                IpcEvent::Published(p) => {
+                    self.fire_webhooks(self.publish_webhook_context(p));
+                    self.refresh_publishes();
+                    trf("Published from DCC: {}", &[&p.display()])
+                }
+            };
+            self.notifications.push(Severity::Info, text);
+            self.refresh_files();
+        }
     }
 
-    fn filter_files(files: &mut Vec<File>, ignore_extensions: Vec<String>) {
-        files.retain(|i| !ignore_extensions.contains(&i.extension));
+    /// Makes the task current without listing its files, outputs and publishes, they're read once the
+    /// files are loaded from the files panel.
+    fn select_task(&mut self, task: TaskTreeNode) {
+        if self.current_project.is_none() {
+            return;
+        }
+        if self.current_task.as_ref().map(|t| &t.path) != Some(&task.path) {
+            self.edit_task_details = None;
+        }
+        self.current_task = Some(task);
+        self.files = None;
+        self.outputs = None;
+        self.publishes = Vec::new();
+        self.file_notes = FileNotes::default();
+        self.update_ipc_context();
+        self.refresh_task_details();
     }
 
-    fn load_config() -> Result<Rclamp, String> {
-        info!("Checking env var for config.");
-        let config_path: String = match env::var(CONFIG_ENV_VAR) {
-            Ok(s) => s,
+    fn set_current_task(&mut self, task: TaskTreeNode) {
+        if self.current_project.is_none() {
+            return;
+        }
+
+        let changed = self.current_task.as_ref().map(|t| &t.path) != Some(&task.path);
+        if changed {
+            self.edit_task_details = None;
+        }
+        self.current_task = Some(task);
+        self.update_ipc_context();
+
+        self.refresh_files();
+        match &self.current_task {
+            Some(t) => push_recent(&mut self.recent_tasks, &t.path),
+            None => return,
+        }
+        if changed {
+            self.preselect_dcc();
+        }
+        self.refresh_task_details();
+        self.refresh_file_notes();
+        self.refresh_publishes();
+        let ignore = self.ignore_patterns();
+        self.refresh_outputs(&ignore);
+    }
+
+    /// Reloads everything shown for the current task: the files, details, notes, publishes and outputs.
+    fn refresh_task(&mut self) {
+        if let Some(task) = self.current_task.clone() {
+            self.set_current_task(task);
+        }
+    }
+
+    /// Picks the DCC for new workfiles in the current task, so e.g. a Houdini task doesn't get a Nuke
+    /// script by accident.
+    fn preselect_dcc(&mut self) {
+        let last = match &self.current_task {
+            Some(t) => t.last_dcc().unwrap_or_else(|e| {
+                error!("Error reading last DCC: {}", e);
+                None
+            }),
+            None => return,
+        };
+        let files = self.files.clone().unwrap_or_default();
+        if let Some(d) = Dcc::preferred(&self.dcc, last.as_deref(), &files) {
+            self.new_file_type = d.clone();
+        }
+    }
+
+    /// Sends the context to the configured webhooks as a background job.
+    fn fire_webhooks(&mut self, context: WebhookContext) {
+        let webhooks: Vec<Webhook> = self
+            .config
+            .webhooks
+            .iter()
+            .filter(|w| w.handles(context.event))
+            .cloned()
+            .collect();
+        if webhooks.is_empty() {
+            return;
+        }
+        self.jobs().push(&tr("Send notifications"), move |_job| {
+            send_all(&webhooks, &context).map(|n| trf("Sent {} notifications", &[&n]))
+        });
+    }
+
+    fn publish_webhook_context(&self, path: &Path) -> WebhookContext {
+        let mut context = WebhookContext::new(WebhookEvent::Publish);
+        if let Some(p) = &self.current_project {
+            context.project = p.name.clone();
+        }
+        if let Some(t) = Publish::find_task_dir(path) {
+            context.task = t
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+        }
+        context.file = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        if let Ok(f) = File::from_path(path.to_path_buf(), &self.config.filename_template) {
+            context.version = f.fmt_version();
+        }
+        context
+    }
+
+    fn refresh_publishes(&mut self) {
+        let task_path = match &self.current_task {
+            Some(t) => t.path.clone(),
+            None => return,
+        };
+        self.publishes = match Publish::list(&task_path) {
+            Ok(p) => p.into_iter().rev().collect(),
             Err(e) => {
-                let message = format!("Could not load config: {}", e);
-                error!("{}", message);
-                return Err(message);
+                self.notifications
+                    .push(Severity::Error, trf("Error reading publishes: {}", &[&e]));
+                Vec::new()
+            }
+        };
+    }
+
+    fn refresh_file_notes(&mut self) {
+        let task_path = match &self.current_task {
+            Some(t) => t.path.clone(),
+            None => return,
+        };
+        self.file_notes = match FileNotes::load(&task_path) {
+            Ok(n) => n,
+            Err(e) => {
+                self.notifications
+                    .push(Severity::Error, trf("Error reading notes: {}", &[&e]));
+                FileNotes::default()
             }
         };
+        if let Some(tag) = &self.file_tag_filter {
+            if !self.file_notes.tags().contains(tag) {
+                self.file_tag_filter = None;
+            }
+        }
+    }
 
-        info!("Found config path: {}", config_path);
+    fn refresh_task_details(&mut self) {
+        let task = match &self.current_task {
+            Some(t) => t,
+            None => return,
+        };
+        self.task_details = match task.details() {
+            Ok(d) => d,
+            Err(e) => {
+                self.notifications.push(
+                    Severity::Error,
+                    trf("Error reading task details: {}", &[&e]),
+                );
+                TaskDetails::default()
+            }
+        };
+    }
 
-        let f = match std::fs::File::open(config_path) {
-            Ok(f) => f,
+    fn refresh_file_metadata(&mut self) {
+        if self.config.file_columns.is_empty() {
+            return;
+        }
+        let task_path = match &self.current_task {
+            Some(t) => t.path.clone(),
+            None => return,
+        };
+        self.file_metadata = match MetadataStore::load(&task_path) {
+            Ok(m) => m,
             Err(e) => {
-                let message = format!("Could not load config: {}", e);
-                error!("{}", message);
-                return Err(message);
+                self.notifications
+                    .push(Severity::Error, trf("Error reading metadata: {}", &[&e]));
+                MetadataStore::default()
+            }
+        };
+    }
+
+    /// Sets a metadata field of a file in the current task.
+    fn set_file_metadata(&mut self, file: &Path, key: &str, value: &str) {
+        let task_path = match &self.current_task {
+            Some(t) => t.path.clone(),
+            None => return,
+        };
+        match MetadataStore::update(&task_path, file, key, value) {
+            Ok(m) => self.file_metadata = m,
+            Err(e) => self
+                .notifications
+                .push(Severity::Error, trf("Error saving metadata: {}", &[&e])),
+        }
+    }
+
+    /// A cell of an extra column in the files table. Click to edit, Enter or clicking elsewhere saves.
+    fn metadata_cell(&mut self, ui: &mut egui::Ui, file: &File, column: &MetadataColumn) {
+        let task_path = match &self.current_task {
+            Some(t) => t.path.clone(),
+            None => return,
+        };
+        let file_key = MetadataStore::file_key(&task_path, &file.path);
+        let value = self
+            .file_metadata
+            .get(&file_key, &column.key)
+            .unwrap_or_default()
+            .to_string();
+
+        if !self.can_edit() {
+            ui.label(value);
+            return;
+        }
+        if !column.choices.is_empty() {
+            let mut selected = value.clone();
+            egui::ComboBox::from_id_source((&file_key, &column.key))
+                .selected_text(&selected)
+                .width(ui.available_width())
+                .show_ui(ui, |ui| {
+                    ui.selectable_value(&mut selected, String::new(), tr("None"));
+                    for choice in &column.choices {
+                        ui.selectable_value(&mut selected, choice.clone(), choice);
+                    }
+                });
+            if selected != value {
+                self.set_file_metadata(&file.path, &column.key, &selected);
+            }
+            return;
+        }
+
+        match &mut self.edit_metadata {
+            Some((path, key, edited)) if *path == file.path && *key == column.key => {
+                let response = ui.add(egui::TextEdit::singleline(edited));
+                // Focused when the edit starts, losing focus afterwards saves.
+                if !response.has_focus() && !response.lost_focus() {
+                    response.request_focus();
+                }
+                if ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+                    self.edit_metadata = None;
+                } else if response.lost_focus() {
+                    let edited = edited.clone();
+                    self.edit_metadata = None;
+                    if edited != value {
+                        self.set_file_metadata(&file.path, &column.key, &edited);
+                    }
+                }
+            }
+            _ => {
+                let text = if value.is_empty() {
+                    egui::RichText::new("–").weak()
+                } else {
+                    egui::RichText::new(&value)
+                };
+                if ui
+                    .add(egui::Label::new(text).sense(egui::Sense::click()))
+                    .on_hover_text(tr("Click to edit"))
+                    .clicked()
+                {
+                    self.edit_metadata = Some((file.path.clone(), column.key.clone(), value));
+                }
+            }
+        }
+    }
+
+    /// Lists the files in the current task's output dir, with image sequences collapsed.
+    fn refresh_outputs(&mut self, ignore: &IgnorePatterns) {
+        let output_dir = match &self.current_task {
+            Some(t) => t.path.join(&t.metadata.output_dir_name),
+            None => return,
+        };
+        if !output_dir.exists() {
+            self.outputs = Some(Vec::new());
+            return;
+        }
+
+        self.outputs = match find_files(&output_dir, &|n: &str| ignore.is_ignored(n)) {
+            Ok(f) => Some(collapse(f)),
+            Err(e) => {
+                error!("Error finding outputs: {}", e);
+                self.notifications
+                    .push(Severity::Error, trf("Error finding outputs: {}", &[&e]));
+                None
             }
         };
+    }
+
+    fn filter_files(files: &mut Vec<File>, ignore: &IgnorePatterns) {
+        files.retain(|i| {
+            let filename = i.path.file_name().unwrap_or_default().to_string_lossy();
+            !ignore.is_ignored(&filename)
+        });
+    }
+
+    /// The global ignore patterns combined with the ones of the current project.
+    fn ignore_patterns(&mut self) -> IgnorePatterns {
+        let context = self.config.pipeline_context();
+        match context.ignore_patterns(self.current_project.as_ref()) {
+            Ok(i) => i,
+            Err(e) => {
+                error!("{}", e);
+                self.notifications.push(Severity::Error, e);
+                IgnorePatterns::default()
+            }
+        }
+    }
+
+    fn load_config() -> Result<Rclamp, String> {
+        let config_path = Rclamp::find_config()?;
+        info!("Found config path: {}", config_path.display());
 
-        let config: RclampConfig = match serde_yaml::from_reader(f) {
+        let config: RclampConfig = match read_config_file(&config_path) {
             Ok(c) => c,
             Err(e) => {
                 let message = format!("Could not load config: {}", e);
@@ -310,7 +1350,7 @@ impl Rclamp {
             PathBuf::from(&config.projects_dir_mac)
         };
 
-        let template_project = Project::new(
+        let mut template_project = Project::new(
             String::new(),
             projects_dir,
             config.pipeline_dir_name,
@@ -320,6 +1360,13 @@ impl Rclamp {
             config.extra_dir_names,
             config.work_sub_dirs,
         );
+        template_project.task_work_dir_name = config.task_work_dir_name;
+        template_project.task_output_dir_name = config.task_output_dir_name;
+        if let Err(e) = template_project.check_task_dirs() {
+            let message = format!("Invalid task dirs in the config: {}", e);
+            error!("{}", message);
+            return Err(message);
+        }
 
         rclamp.config.template_project = template_project;
         if cfg!(windows) {
@@ -329,8 +1376,38 @@ impl Rclamp {
             rclamp.config.projects_dir = Some(PathBuf::from(config.projects_dir_mac));
             rclamp.config.templates_dir = PathBuf::from(config.templates_dir_mac);
         }
+        if let Some(dir) = &rclamp.config.projects_dir {
+            set_projects_dir(dir);
+        }
 
-        rclamp.config.ignore_extensions = config.ignore_extensions;
+        rclamp.config.theme = match config.theme {
+            Some(t) => t,
+            None => {
+                let pipeline_template =
+                    rclamp.config.templates_dir.join(PIPELINE_TEMPLATE_DIR_NAME);
+                match Theme::load(&pipeline_template) {
+                    Ok(t) => t.unwrap_or_default(),
+                    Err(e) => {
+                        rclamp.notifications.push(Severity::Error, e);
+                        Theme::default()
+                    }
+                }
+            }
+        };
+
+        let mut ignore_patterns = config.ignore_patterns;
+        ignore_patterns.extend(IgnorePatterns::from_extensions(&config.ignore_extensions));
+        if let Err(e) = IgnorePatterns::new(&ignore_patterns) {
+            rclamp.notifications.push(Severity::Error, e);
+        }
+        rclamp.config.ignore_patterns = ignore_patterns;
+        rclamp.config.filename_template = config.filename_template;
+        set_transliterations(&config.transliterations);
+        set_mappings(&with_detected(
+            &config.path_mappings,
+            detect_mapped_drives(),
+        ));
+        rclamp.config.transliterations = config.transliterations;
 
         let clients_path = if cfg!(windows) {
             PathBuf::from(&config.clients_path_win)
@@ -340,49 +1417,175 @@ impl Rclamp {
 
         rclamp.config.clients_path = clients_path.clone();
 
-        rclamp.clients = match Client::get_clients(clients_path) {
-            Ok(c) => {
-                info!("Read client list successfully.");
-                c
-            }
-            Err(e) => {
-                rclamp.message = Some(Message {
-                    text: e.clone(),
-                    message_type: MessageType::Warning,
-                });
-                error!("Failed to read client list: {}", e);
-                Vec::new()
-            }
+        let plugins_dir = if cfg!(windows) {
+            config.plugins_dir_win
+        } else {
+            config.plugins_dir_mac
         };
-        Ok(rclamp)
-    }
+        if !plugins_dir.is_empty() {
+            let plugins_dir = PathBuf::from(plugins_dir);
+            rclamp.plugins = match Plugin::find_plugins(&plugins_dir) {
+                Ok(p) => p,
+                Err(e) => {
+                    error!("Error finding plugins: {}", e);
+                    rclamp
+                        .notifications
+                        .push(Severity::Error, trf("Error finding plugins: {}", &[&e]));
+                    Vec::new()
+                }
+            };
+            rclamp.config.plugins_dir = Some(plugins_dir);
+        }
 
-    fn load_config_refresh(&mut self) -> Result<(), String> {
-        let rclamp = match Rclamp::load_config() {
-            Ok(r) => r,
-            Err(e) => return Err(e),
+        let scripts_dir = if cfg!(windows) {
+            config.scripts_dir_win
+        } else {
+            config.scripts_dir_mac
+        };
+        if !scripts_dir.is_empty() {
+            rclamp.config.scripts_dir = Some(PathBuf::from(scripts_dir));
+        }
+
+        let player = if cfg!(windows) {
+            config.player_win
+        } else {
+            config.player_mac
+        };
+        if !player.is_empty() {
+            rclamp.config.player = Some(Player {
+                command: PathBuf::from(player),
+                args: config.player_args,
+            });
+        }
+
+        let ffmpeg = if cfg!(windows) {
+            config.ffmpeg_win
+        } else {
+            config.ffmpeg_mac
+        };
+        if !ffmpeg.is_empty() {
+            rclamp.config.transcoder = Some(Transcoder {
+                ffmpeg: PathBuf::from(ffmpeg),
+                framerate: config.proxy_framerate,
+                args: config.proxy_args,
+            });
+        }
+        rclamp.config.path_mappings = config.path_mappings;
+        rclamp.config.webhooks = config.webhooks;
+        rclamp.config.mirror_profiles = config.mirror_profiles;
+        rclamp.config.file_columns = config.file_columns;
+        rclamp.config.ingest = config.ingest;
+        rclamp.config.retention = config.retention;
+        rclamp.config.role = Role::from_env(config.role);
+        rclamp.config.task_presets = config.task_presets;
+        if let Err(e) = config.create_permissions.umask_bits() {
+            rclamp.notifications.push(Severity::Error, e);
+        }
+        set_create_permissions(&config.create_permissions);
+        rclamp.config.create_permissions = config.create_permissions;
+        set_retry_policy(config.retry);
+        rclamp.config.retry = config.retry;
+        let seven_zip = if cfg!(windows) {
+            config.seven_zip_win
+        } else {
+            config.seven_zip_mac
+        };
+        rclamp.config.archive = ArchiveSettings {
+            seven_zip: Some(PathBuf::from(seven_zip)).filter(|s| !s.as_os_str().is_empty()),
+            ..config.archive
+        };
+        #[cfg(feature = "deliveries")]
+        {
+            rclamp.config.upload_targets = config.upload_targets;
+        }
+
+        let aws = if cfg!(windows) {
+            config.aws_win
+        } else {
+            config.aws_mac
+        };
+        if !aws.is_empty() && !config.sync_remote.is_empty() {
+            rclamp.config.cloud_sync = Some(CloudSync {
+                aws: PathBuf::from(aws),
+                remote: config.sync_remote,
+                endpoint: Some(config.sync_endpoint).filter(|e| !e.is_empty()),
+            });
+        }
+
+        rclamp.clients = match Client::get_clients(clients_path) {
+            Ok(c) => {
+                info!("Read client list successfully.");
+                c
+            }
+            Err(e) => {
+                rclamp.notifications.push(Severity::Error, e.clone());
+                error!("Failed to read client list: {}", e);
+                Vec::new()
+            }
         };
+        Ok(rclamp)
+    }
+
+    /// Config locations in order of precedence, after the env var: the user config dir
+    /// (`%APPDATA%\rclamp` on Windows, `$XDG_CONFIG_HOME/rclamp` or `~/.config/rclamp` elsewhere),
+    /// then the dir of the executable.
+    fn config_locations() -> Vec<PathBuf> {
+        let mut locations = Vec::new();
+        let exe_dir = env::current_exe()
+            .ok()
+            .and_then(|e| e.parent().map(Path::to_path_buf));
+        for dir in [user_config_dir(), exe_dir].into_iter().flatten() {
+            locations.extend(CONFIG_FILE_NAMES.iter().map(|n| dir.join(n)));
+        }
+        locations
+    }
+
+    /// Finds the config file. The env var always wins when set, otherwise the first existing
+    /// file of `config_locations` is used.
+    fn find_config() -> Result<PathBuf, String> {
+        info!("Checking env var for config.");
+        if let Some(p) = env::var_os(CONFIG_ENV_VAR) {
+            return Ok(PathBuf::from(p));
+        }
+
+        let locations = Rclamp::config_locations();
+        match locations.iter().find(|p| p.is_file()) {
+            Some(p) => Ok(p.clone()),
+            None => {
+                let message = format!(
+                    "Could not load config: {} is not set and no config was found in {}",
+                    CONFIG_ENV_VAR,
+                    locations
+                        .iter()
+                        .map(|p| p.display().to_string())
+                        .collect::<Vec<String>>()
+                        .join(", ")
+                );
+                error!("{}", message);
+                Err(message)
+            }
+        }
+    }
+
+    fn load_config_refresh(&mut self) -> Result<(), String> {
+        let rclamp = Rclamp::load_config()?;
         self.clients = rclamp.clients;
+        self.plugins = rclamp.plugins;
         self.config = rclamp.config;
+        self.apply_theme_preferences();
 
         Ok(())
     }
 
     fn refresh_all(&mut self, ui: &mut egui::Ui) {
-        self.message = None;
         match self.load_config_refresh() {
             Ok(()) => (),
-            Err(e) => {
-                self.message = Some(Message {
-                    text: String::from(e),
-                    message_type: MessageType::Warning,
-                })
-            }
+            Err(e) => self.notifications.push(Severity::Error, e),
         }
         self.refresh_dcc();
         self.refresh_projects();
         self.refresh_tasks(ui);
-        self.refresh_files();
+        self.refresh_task();
     }
 
     /// Refreshes the list of DCC:s
@@ -392,85 +1595,320 @@ impl Rclamp {
             Ok(d) => dcc = d,
             Err(e) => {
                 error!("Error finding DCC:s: {}", e);
-                self.message = Some(Message {
-                    text: String::from(format!("Error finding DCC:s: {}", e)),
-                    message_type: MessageType::Warning,
-                });
+                self.notifications
+                    .push(Severity::Error, trf("Error finding DCC:s: {}", &[&e]));
             }
         };
         self.dcc = dcc;
     }
 
-    /// Refreshes the list of projects by calling find_projects.
+    /// Refreshes the list of projects through the scan cache. The current project is closed if it's gone,
+    /// and its task tree only rescanned if its work dirs changed.
     fn refresh_projects(&mut self) {
         let projects_dir = match &self.config.projects_dir {
             Some(d) => d.clone(),
             None => return,
         };
 
-        match Project::find_projects(projects_dir, self.config.template_project.clone()) {
+        match self.scan_projects(&projects_dir) {
             Ok(p) => {
+                let p = self.listed_projects(p);
+                if let Some(current) = self.current_project.clone() {
+                    match p.iter().find(|x| x.name == current.name) {
+                        None => self.close_project(),
+                        Some(updated) if *updated != current => {
+                            let rescan = updated.work_dir_name != current.work_dir_name
+                                || updated.task_sub_dirs() != current.task_sub_dirs();
+                            self.set_current_project(updated.clone());
+                            if rescan {
+                                self.refresh_moved_work_dirs();
+                            }
+                        }
+                        Some(_unchanged) => (),
+                    }
+                }
                 self.projects = p.clone();
                 self.project_filter = String::new();
                 self.projects_filtered = p;
+                self.project_groups = None;
             }
             Err(e) => {
                 error!("Error finding projects: {}", e);
-                self.message = Some(Message {
-                    text: String::from(format!("Error finding projects: {}", e)),
-                    message_type: MessageType::Warning,
-                });
-                self.current_project_task_tree = None;
-                self.current_project = None;
-                self.current_task = None;
+                self.notifications
+                    .push(Severity::Error, trf("Error finding projects: {}", &[&e]));
+                self.close_project();
             }
         }
     }
 
-    /// Refreshes task tree.
-    fn refresh_tasks(&mut self, ui: &mut egui::Ui) {
-        let project = match &self.current_project {
-            Some(p) => p.clone(),
-            None => return,
+    /// Rescans the task tree and the files after the work dirs of the current project changed.
+    fn refresh_moved_work_dirs(&mut self) {
+        match self.rescan_task_tree() {
+            Ok(()) => self.refresh_files(),
+            Err(e) => {
+                error!("Error creating task tree: {}", e);
+                self.notifications
+                    .push(Severity::Error, trf("Error creating task tree: {}", &[&e]));
+                self.close_project();
+            }
+        }
+    }
+
+    /// Finds the projects, reading only what changed since the last scan, and saves the scan cache.
+    /// Offline the projects of the last scan are used.
+    fn scan_projects(&mut self, projects_dir: &Path) -> Result<Vec<Project>, io::Error> {
+        let cached = self.scan_cache.cached_projects(projects_dir);
+        if self.connectivity.is_offline() {
+            return cached.ok_or_else(not_cached_error);
+        }
+        let projects = match self.scan_cache.projects(&RealFs, projects_dir) {
+            Ok(p) => p,
+            Err(e) => return self.offline_fallback(e, cached),
+        };
+        self.save_scan_cache();
+        Ok(projects)
+    }
+
+    /// Scans the task tree of the project through the scan cache. Offline the tree of the last scan is
+    /// used.
+    fn scan_task_tree(&mut self, project: &Project) -> Result<TaskTreeNode, io::Error> {
+        let context = self.config.pipeline_context();
+        let work_path = context.work_path(project);
+        let cached = self.scan_cache.cached_tree(&work_path);
+        if self.connectivity.is_offline() {
+            return cached.ok_or_else(not_cached_error);
+        }
+        let tree = match self.scan_cache.task_tree(
+            &RealFs,
+            &work_path,
+            context.work_dir_name(project),
+            context.output_dir_name(project),
+        ) {
+            Ok(t) => t,
+            Err(e) => return self.offline_fallback(e, cached),
+        };
+        self.save_scan_cache();
+        Ok(tree)
+    }
+
+    /// Switches to offline mode if a scan failed because the projects dir can't be reached, returning
+    /// the cached data instead of the error.
+    fn offline_fallback<T>(&mut self, error: io::Error, cached: Option<T>) -> Result<T, io::Error> {
+        let projects_dir = match &self.config.projects_dir {
+            Some(d) if !is_reachable(d) => d.clone(),
+            _ => return Err(error),
         };
+        self.connectivity
+            .set_offline(&projects_dir, now_timestamp());
+        self.notifications.push(
+            Severity::Warning,
+            tr("The projects dir can't be reached, working offline."),
+        );
+        cached.ok_or(error)
+    }
 
+    /// While offline, checks whether the projects dir is back, then refreshes and runs the queued
+    /// commands.
+    fn poll_connectivity(&mut self) {
         let projects_dir = match &self.config.projects_dir {
             Some(d) => d.clone(),
             None => return,
         };
+        if !self.connectivity.poll(&projects_dir, now_timestamp()) {
+            return;
+        }
+        self.notifications
+            .push(Severity::Info, tr("The projects dir is back online."));
+        self.refresh_projects();
+        if let Err(e) = self.rescan_task_tree() {
+            error!("Error creating task tree: {}", e);
+        }
+        self.refresh_files();
+        for command in self.connectivity.take_queued() {
+            for event in handle(&command, &self.command_context()) {
+                self.apply_event(&command, event);
+            }
+        }
+    }
 
-        let tree = match TaskTreeNode::from_path(
-            project.get_work_path(&projects_dir),
-            &project.work_sub_dirs[0],
-            &project.work_sub_dirs[1],
-            0,
-        ) {
-            Ok(t) => t,
-            Err(e) => {
-                error!("Error creating task tree: {}", e);
-                self.render_task_tree_error(ui, e);
-                self.current_project_task_tree = None;
-                self.current_project = None;
-                self.current_task = None;
-                return;
+    fn save_scan_cache(&self) {
+        if let Some(path) = ScanCache::path() {
+            if let Err(e) = self.scan_cache.save(&path) {
+                error!("Failed to save the scan cache: {}", e);
             }
+        }
+    }
+
+    /// Refreshes task tree. The current task stays open unless it's gone, its files aren't rescanned.
+    fn refresh_tasks(&mut self, ui: &mut egui::Ui) {
+        if let Err(e) = self.rescan_task_tree() {
+            error!("Error creating task tree: {}", e);
+            self.render_task_tree_error(ui, e);
+            self.close_project();
+        }
+    }
+
+    fn rescan_task_tree(&mut self) -> Result<(), io::Error> {
+        let project = match (&self.current_project, &self.config.projects_dir) {
+            (Some(p), Some(_d)) => p.clone(),
+            _ => return Ok(()),
         };
-        self.current_project_task_tree = Some(tree);
+        let tree = self.scan_task_tree(&project)?;
+        let task_gone = self
+            .current_task
+            .as_ref()
+            .map_or(false, |t| tree.path_to(&t.path).is_empty());
+        if task_gone {
+            self.current_task = None;
+            self.files = None;
+            self.update_ipc_context();
+        }
+        self.current_project_task_tree = Some(Rc::new(tree));
+        Ok(())
     }
 
-    /// Refreshes file list.
+    fn close_project(&mut self) {
+        self.current_project_task_tree = None;
+        self.current_project = None;
+        self.current_task = None;
+    }
+
+    /// Rescans the workfiles of the current task and their metadata, keeping the rest of the task.
     fn refresh_files(&mut self) {
-        let task = match &self.current_task {
-            Some(t) => t.clone(),
+        if self.current_project.is_none() {
+            return;
+        }
+        let ignore = self.ignore_patterns();
+        let mut files = match &self.current_task {
+            Some(t) => match t.find_workfiles(
+                t.metadata.work_dir_name.clone(),
+                &self.config.filename_template,
+                &ignore,
+            ) {
+                Ok(v) => v,
+                Err(e) => {
+                    error!("Error opening task: {}", e);
+                    self.notifications
+                        .push(Severity::Error, trf("Error opening task: {}", &[&e]));
+                    self.current_task = None;
+                    return;
+                }
+            },
             None => return,
         };
-        self.set_current_task(task);
+        Self::filter_files(&mut files, &ignore);
+        files.sort();
+        files.reverse();
+        self.file_groups = Rc::new(FileGroup::group(&files));
+        self.files = Some(Rc::new(files));
+        self.refresh_file_metadata();
+    }
+
+    /// Group and sort options of the project list.
+    fn project_list_options(&mut self, ui: &mut egui::Ui) {
+        let (grouping, sorting) = (self.project_grouping, self.project_sorting);
+        ui.label(tr("Group"));
+        egui::ComboBox::from_id_source("project_grouping")
+            .selected_text(tr(&format!("{:?}", self.project_grouping)))
+            .show_ui(ui, |ui| {
+                for g in [
+                    ProjectGrouping::None,
+                    ProjectGrouping::Client,
+                    ProjectGrouping::Status,
+                    ProjectGrouping::Year,
+                    ProjectGrouping::Program,
+                ] {
+                    ui.selectable_value(&mut self.project_grouping, g, tr(&format!("{:?}", g)));
+                }
+            });
+        ui.label(tr("Sort"));
+        egui::ComboBox::from_id_source("project_sorting")
+            .selected_text(match self.project_sorting {
+                ProjectSorting::Name => tr("Name"),
+                ProjectSorting::LastActivity => tr("Last activity"),
+                ProjectSorting::DueDate => tr("Due date"),
+            })
+            .show_ui(ui, |ui| {
+                ui.selectable_value(&mut self.project_sorting, ProjectSorting::Name, tr("Name"));
+                ui.selectable_value(
+                    &mut self.project_sorting,
+                    ProjectSorting::LastActivity,
+                    tr("Last activity"),
+                );
+                ui.selectable_value(
+                    &mut self.project_sorting,
+                    ProjectSorting::DueDate,
+                    tr("Due date"),
+                );
+            });
+        if (grouping, sorting) != (self.project_grouping, self.project_sorting) {
+            self.project_groups = None;
+        }
     }
 
-    /// Renders the list of projects.
+    /// Renders the list of projects, in collapsible groups when grouping is set. Pinned projects are
+    /// also listed first.
     fn render_projects(&mut self, ui: &mut egui::Ui) {
-        let projects = &self.projects_filtered.clone();
+        let groups = match &self.project_groups {
+            Some(g) => Rc::clone(g),
+            None => {
+                let groups = group_projects(
+                    &self.projects_filtered,
+                    self.project_grouping,
+                    // Filter results stay ordered by relevance.
+                    if self.project_filter.is_empty() {
+                        Some(self.project_sorting)
+                    } else {
+                        None
+                    },
+                    &self.config.projects_dir.clone().unwrap_or_default(),
+                );
+                let groups = Rc::new(groups);
+                self.project_groups = Some(Rc::clone(&groups));
+                self.pinned_filtered = Rc::new(
+                    self.projects_filtered
+                        .iter()
+                        .filter(|p| self.pinned_projects.contains(&p.name))
+                        .cloned()
+                        .collect(),
+                );
+                groups
+            }
+        };
+
+        let pinned = Rc::clone(&self.pinned_filtered);
+        if !pinned.is_empty() {
+            egui::CollapsingHeader::new(trf("Pinned ({})", &[&pinned.len()]))
+                .id_source("pinned_projects")
+                .default_open(true)
+                .show(ui, |ui| {
+                    self.render_project_rows(ui, &pinned);
+                });
+        }
+
+        if self.project_grouping == ProjectGrouping::None {
+            for (_, projects) in groups.iter() {
+                self.render_project_rows(ui, projects);
+            }
+            return;
+        }
+        for (name, projects) in groups.iter() {
+            let title = if name.is_empty() {
+                let grouping = tr(&format!("{:?}", self.project_grouping)).to_lowercase();
+                trf("No {} ({})", &[&grouping, &projects.len()])
+            } else {
+                format!("{} ({})", name, projects.len())
+            };
+            egui::CollapsingHeader::new(title)
+                .id_source(("project_group", name))
+                .default_open(true)
+                .show(ui, |ui| {
+                    self.render_project_rows(ui, projects);
+                });
+        }
+    }
 
+    fn render_project_rows(&mut self, ui: &mut egui::Ui, projects: &[Project]) {
         for p in projects {
             let title = format!("📁 {}", p.name);
             ui.add_space(SPACING);
@@ -480,23 +1918,162 @@ impl Rclamp {
                     if name_label.clicked() {
                         let _ = &self.open_project(p.clone(), ui);
                     }
+                    name_label.context_menu(|ui| {
+                        let pinned = self.pinned_projects.contains(&p.name);
+                        if ui
+                            .button(if pinned { tr("Unpin") } else { tr("Pin") })
+                            .clicked()
+                        {
+                            if pinned {
+                                self.pinned_projects.retain(|n| *n != p.name);
+                            } else {
+                                self.pinned_projects.push(p.name.clone());
+                            }
+                            self.project_groups = None;
+                            ui.close_menu();
+                        }
+                        if let Some(projects_dir) = self.config.projects_dir.clone() {
+                            if ui.button(tr("Open project root")).clicked() {
+                                p.open_root(projects_dir.clone());
+                                ui.close_menu();
+                            }
+                            if ui.button(tr("Open pipeline folder")).clicked() {
+                                p.open_pipeline_folder(projects_dir.clone());
+                                ui.close_menu();
+                            }
+                            if ui.button(tr("Open dailies folder")).clicked() {
+                                p.open_dailies_folder(projects_dir.clone());
+                                ui.close_menu();
+                            }
+                            if ui.button(tr("Open deliveries folder")).clicked() {
+                                p.open_deliveries_folder(projects_dir.clone());
+                                ui.close_menu();
+                            }
+                            self.copy_path_menu(ui, &p.get_path(&projects_dir));
+                        }
+                        if ui.button(tr("Project health check")).clicked() {
+                            self.run_health_check(p);
+                            ui.close_menu();
+                        }
+                        let report_btn = ui
+                            .add_enabled(
+                                self.config.role.can_edit(),
+                                egui::Button::new(tr("Export report")),
+                            )
+                            .on_hover_text(tr(
+                                "Writes the tasks with their status, due date and workfiles to a CSV file in the pipeline folder.",
+                            ));
+                        if report_btn.clicked() {
+                            self.export_report(p);
+                            ui.close_menu();
+                        }
+                        let duplicate_btn = ui.add_enabled(
+                            self.config.role.can_edit(),
+                            egui::Button::new(tr("Duplicate project…")),
+                        );
+                        if duplicate_btn.clicked() {
+                            self.duplicate_project = Some((p.clone(), String::new(), true));
+                            ui.close_menu();
+                        }
+                        let owner = self.project_access(p) == Access::Owner;
+                        ui.set_enabled(self.config.role.can_edit() && owner);
+                        if ui.button(tr("Edit metadata…")).clicked() {
+                            let due_date = p.due_date.clone().unwrap_or_default();
+                            self.edit_project_metadata = Some((p.clone(), due_date));
+                            ui.close_menu();
+                        }
+                        if ui.button(tr("Access…")).clicked() {
+                            self.open_project_access(p);
+                            ui.close_menu();
+                        }
+                        let archived = p.status.as_deref() == Some("archived");
+                        if ui
+                            .add_enabled(!archived, egui::Button::new(tr("Archive")))
+                            .clicked()
+                        {
+                            self.set_project_status(p, Some(String::from("archived")));
+                            ui.close_menu();
+                        }
+                        if self.config.cloud_sync.is_some() {
+                            let archived = p.status.as_deref() == Some("archived");
+                            let push_btn = ui
+                                .add_enabled(archived, egui::Button::new(tr("Push to cloud")))
+                                .on_disabled_hover_text(tr(
+                                    "Only archived projects can be pushed.",
+                                ));
+                            if push_btn.clicked() {
+                                self.push_project(p);
+                                ui.close_menu();
+                            }
+                        }
+                        let extension = self.config.archive.format.extension();
+                        let archive_btn = ui
+                            .button(trf("Create {} archive", &[&extension]))
+                            .on_hover_text(tr("Archives the project next to its folder."));
+                        if archive_btn.clicked() {
+                            self.archive_project(p);
+                            ui.close_menu();
+                        }
+                        ui.menu_button(tr("Status"), |ui| {
+                            for status in PROJECT_STATUSES {
+                                let selected = p.status.as_deref() == Some(status);
+                                if ui.selectable_label(selected, status).clicked() {
+                                    self.set_project_status(p, Some(String::from(status)));
+                                    ui.close_menu();
+                                }
+                            }
+                            if ui.button(tr("Clear")).clicked() {
+                                self.set_project_status(p, None);
+                                ui.close_menu();
+                            }
+                        });
+                        let payload = PluginPayload::new(
+                            PluginTarget::Project,
+                            Some(p),
+                            self.config.projects_dir.as_deref(),
+                            None,
+                        );
+                        self.plugins_menu(ui, &payload);
+                    });
                 });
 
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::RIGHT), |ui| {
-                    let open_deliveries_button = ui.add(egui::Button::new("Deliveries"));
-                    let open_dailies_button = ui.add(egui::Button::new("Dailies"));
+                    let open_deliveries_button = ui.add(egui::Button::new(tr("Deliveries")));
+                    let open_dailies_button = ui.add(egui::Button::new(tr("Dailies")));
+                    let open_pipeline_button = ui.add(egui::Button::new(tr("Pipeline")));
+                    let open_root_button = ui.add(egui::Button::new(tr("Project")));
+                    let open_dailies_button = open_dailies_button.context_menu(|ui| {
+                        let dailies = self.latest_dailies(p);
+                        if dailies.is_empty() {
+                            ui.label(tr("No dailies found."));
+                        }
+                        for item in &dailies {
+                            if ui.button(format!("▶ {}", item.name())).clicked() {
+                                self.play_output(item);
+                                ui.close_menu();
+                            }
+                        }
+                    });
 
                     if open_dailies_button.clicked() {
-                        match &self.config.projects_dir {
-                            Some(d) => p.open_dailies_folder(d.clone()),
-                            None => (),
-                        };
+                        if let Some(d) = &self.config.projects_dir {
+                            p.open_dailies_folder(d.clone());
+                        }
                     }
                     if open_deliveries_button.clicked() {
-                        match &self.config.projects_dir {
-                            Some(d) => p.open_deliveries_folder(d.clone()),
-                            None => (),
-                        };
+                        if let Some(d) = &self.config.projects_dir {
+                            p.open_deliveries_folder(d.clone());
+                        }
+                    }
+                    if open_pipeline_button.clicked() {
+                        if let Some(d) = &self.config.projects_dir {
+                            p.open_pipeline_folder(d.clone());
+                        }
+                    }
+                    if open_root_button.clicked() {
+                        if let Some(d) = &self.config.projects_dir {
+                            p.open_root(d.clone());
+                        }
                     }
                 });
             });
@@ -505,21 +2082,141 @@ impl Rclamp {
         }
     }
 
-    /// First sets the current project, then creates a task tree and assigns it as the current task tree.
-    fn open_project(&mut self, project: Project, ui: &mut egui::Ui) {
-        self.set_current_project(project.clone());
+    /// Saves the status to the project file and updates the project list.
+    fn set_project_status(&mut self, project: &Project, status: Option<String>) {
+        let mut project = project.clone();
+        project.status = status;
+        if !self.save_project(&project) {
+            return;
+        }
+
+        let mut context = WebhookContext::new(WebhookEvent::StatusChange);
+        context.project = project.name.clone();
+        context.status = project.status.unwrap_or_default();
+        self.fire_webhooks(context);
+    }
 
-        let project_dir = match &self.config.projects_dir {
+    /// Writes the project file and updates the project in the lists. False if it couldn't be saved.
+    fn save_project(&mut self, project: &Project) -> bool {
+        let projects_dir = match &self.config.projects_dir {
             Some(d) => d.clone(),
+            None => return false,
+        };
+        if let Err(e) = project.save(&projects_dir) {
+            self.notifications
+                .push(Severity::Error, trf("Error saving project: {}", &[&e]));
+            return false;
+        }
+
+        for p in self
+            .projects
+            .iter_mut()
+            .chain(self.projects_filtered.iter_mut())
+            .chain(self.current_project.iter_mut())
+            .filter(|p| p.name_sanitized == project.name_sanitized)
+        {
+            *p = project.clone();
+        }
+        self.project_groups = None;
+        true
+    }
+
+    fn open_project_access(&mut self, project: &Project) {
+        let access = project.access.clone().unwrap_or_else(|| ProjectAccess {
+            owners: vec![current_user()],
+            ..Default::default()
+        });
+        let lists = [&access.owners, &access.members, &access.viewers].map(|l| l.join("\n"));
+        self.edit_project_access = Some((project.clone(), lists));
+    }
+
+    /// Edits who may see and change a project. Owners can't remove themselves, so there's always
+    /// someone left to manage it.
+    fn project_access_window(&mut self, ctx: &egui::Context) {
+        let (project, mut lists) = match &self.edit_project_access {
+            Some((p, l)) => (p.clone(), l.clone()),
             None => return,
         };
 
-        let tree = match TaskTreeNode::from_path(
-            project.get_work_path(&project_dir),
-            &project.work_sub_dirs[0],
-            &project.work_sub_dirs[1],
-            0,
-        ) {
+        let mut open = true;
+        let mut save: Option<Option<ProjectAccess>> = None;
+        egui::Window::new(trf("Access: {}", &[&project.name]))
+            .id(egui::Id::new("project_access"))
+            .open(&mut open)
+            .resizable(true)
+            .show(ctx, |ui| {
+                ui.label(tr(
+                    "One user name or @group per line. Users not listed don't see the project.",
+                ));
+                ui.add_space(SPACING);
+                egui::Grid::new("project_access_lists").show(ui, |ui| {
+                    let labels = [tr("Owners"), tr("Members"), tr("Viewers")];
+                    for (label, list) in labels.iter().zip(lists.iter_mut()) {
+                        ui.label(label);
+                        ui.add(egui::TextEdit::multiline(list).desired_rows(3));
+                        ui.end_row();
+                    }
+                });
+                ui.add_space(SPACING);
+                ui.horizontal(|ui| {
+                    if ui.button(tr("Save")).clicked() {
+                        let [owners, members, viewers] = lists.clone().map(|l| {
+                            l.lines()
+                                .map(|e| e.trim().to_string())
+                                .filter(|e| !e.is_empty())
+                                .collect()
+                        });
+                        save = Some(Some(ProjectAccess {
+                            owners,
+                            members,
+                            viewers,
+                        }));
+                    }
+                    if project.access.is_some()
+                        && ui
+                            .button(tr("Remove access list"))
+                            .on_hover_text(tr("Everyone will have access to the project."))
+                            .clicked()
+                    {
+                        save = Some(None);
+                    }
+                });
+            });
+
+        if let Some(access) = save {
+            let owner = access.as_ref().map_or(true, |a| {
+                a.access(&current_user(), &self.user_groups) == Access::Owner
+            });
+            if !owner {
+                self.notifications.push(
+                    Severity::Error,
+                    tr("You can't remove yourself from the owners."),
+                );
+            } else {
+                let mut project = project.clone();
+                project.access = access;
+                if self.save_project(&project) {
+                    self.notifications.push(
+                        Severity::Info,
+                        trf("Saved the access of {}", &[&project.name]),
+                    );
+                    self.edit_project_access = None;
+                    return;
+                }
+            }
+        }
+        self.edit_project_access = if open { Some((project, lists)) } else { None };
+    }
+
+    /// First sets the current project, then creates a task tree and assigns it as the current task tree.
+    fn open_project(&mut self, project: Project, ui: &mut egui::Ui) {
+        self.set_current_project(project.clone());
+
+        if self.config.projects_dir.is_none() {
+            return;
+        }
+
+        let tree = match self.scan_task_tree(&project) {
             Ok(t) => t,
             Err(e) => {
                 error!("Error creating task tree: {}", e);
@@ -527,116 +2224,266 @@ impl Rclamp {
                 return;
             }
         };
-        self.current_project_task_tree = Some(tree);
+        self.current_project_task_tree = Some(Rc::new(tree));
     }
 
     /// Shows a dialog for creating a task.
     fn create_task_dialog(&mut self, ui: &mut egui::Ui) {
         ui.add_space(SPACING);
+        let field_id = egui::Id::new("new_task_name");
+        // Pasting several lines switches to the list, before the field takes them as one name.
+        let pasted = ui.input(|i| {
+            i.events.iter().find_map(|e| match e {
+                egui::Event::Paste(t) if t.trim().contains('\n') => Some(t.clone()),
+                _ => None,
+            })
+        });
+        if let Some(text) = pasted.filter(|_t| ui.memory(|m| m.has_focus(field_id))) {
+            let text = format!("{}{}", self.new_task_name, text);
+            self.new_task_name = String::new();
+            self.new_task_list = Some((text, Vec::new()));
+        }
+        if self.new_task_list.is_some() {
+            self.create_task_list(ui);
+            ui.add_space(SPACING);
+            return;
+        }
+
         ui.horizontal(|ui| {
-            ui.label("Task name: ");
-            let new_task_name_field = ui.add(
-                egui::TextEdit::singleline(&mut self.new_task_name).desired_width(TEXTEDIT_WIDTH),
-            );
-            let create_task_btn = ui.add(egui::Button::new("Create"));
-            let cancel_btn = ui.add(egui::Button::new("❌ Cancel"));
-            ui.label(egui::RichText::new(sanitize_string(
-                self.new_task_name.clone(),
-            )));
+            ui.label(tr("Task name: "));
+            let new_task_name_field = ui
+                .add(
+                    egui::TextEdit::singleline(&mut self.new_task_name)
+                        .id(field_id)
+                        .desired_width(TEXTEDIT_WIDTH),
+                )
+                .on_hover_text(tr("Paste several lines to create a task for each."));
+            let task_name = sanitize_string(self.new_task_name.clone());
+            let validation = validate_child_name(&task_name, &self.new_task_parent.path);
+            let create_task_btn =
+                ui.add_enabled(validation.is_ok(), egui::Button::new(tr("Create")));
+            let cancel_btn = ui.add(egui::Button::new(tr("❌ Cancel")));
+            Self::name_preview_label(ui, &task_name, &validation, !self.new_task_name.is_empty());
 
             ui.add_space(SPACING);
 
             if cancel_btn.clicked() {
                 self.show_create_task = false;
-                self.message = None;
             }
 
-            if create_task_btn.clicked()
-                || (new_task_name_field.lost_focus()
-                    && ui.input(|i| i.key_pressed(egui::Key::Enter)))
+            if validation.is_ok()
+                && (create_task_btn.clicked()
+                    || (new_task_name_field.lost_focus()
+                        && ui.input(|i| i.key_pressed(egui::Key::Enter))))
             {
-                let project = match &self.current_project {
-                    Some(p) => p.clone(),
-                    None => {
-                        self.message = Some(Message {
-                            text: String::from("No project open."),
-                            message_type: MessageType::Warning,
-                        });
-                        return;
+                self.send_create_task(task_name);
+            }
+        });
+        if !self.config.task_presets.is_empty() {
+            ui.horizontal_wrapped(|ui| {
+                ui.label(tr("Presets: "));
+                for preset in self.config.task_presets.clone() {
+                    let name = sanitize_string(preset.clone());
+                    let validation = validate_child_name(&name, &self.new_task_parent.path);
+                    let mut preset_btn =
+                        ui.add_enabled(validation.is_ok(), egui::Button::new(&preset));
+                    if let Err(e) = &validation {
+                        preset_btn = preset_btn.on_disabled_hover_text(e.to_string());
+                    }
+                    if preset_btn.clicked() {
+                        self.send_create_task(name);
                     }
-                };
-
-                let task_name = sanitize_string(self.new_task_name.clone());
-
-                if task_name.is_empty() {
-                    return;
                 }
-
-                match self.new_task_parent.create_task(task_name, project) {
-                    Ok(()) => {
-                        self.message = Some(Message {
-                            text: String::from("Successfully created task."),
-                            message_type: MessageType::Info,
-                        });
-                    }
-                    Err(e) => {
-                        self.message = Some(Message {
-                            text: String::from(format!("Error creating task: {}", e)),
-                            message_type: MessageType::Warning,
-                        });
-                    }
+            });
+        }
+        ui.add_space(SPACING);
+    }
+
+    /// The pasted task names with a preview of the sanitized names, created under the parent picked for
+    /// new tasks. Names that can't be used are left out and listed in the results.
+    fn create_task_list(&mut self, ui: &mut egui::Ui) {
+        let (mut text, mut results) = match self.new_task_list.take() {
+            Some(l) => l,
+            None => return,
+        };
+        ui.label(trf(
+            "One task per line. Parent: {}",
+            &[&self.new_task_parent.name],
+        ));
+        ui.add(
+            egui::TextEdit::multiline(&mut text)
+                .desired_rows(6)
+                .desired_width(TEXTEDIT_WIDTH * 2.),
+        );
+        let names: Vec<String> = text
+            .lines()
+            .map(|l| sanitize_string(l.trim().to_string()))
+            .filter(|n| !n.is_empty())
+            .collect();
+        let validations = validate_child_names(&names, &self.new_task_parent.path);
+
+        ui.strong(trf("Preview ({} tasks)", &[&names.len()]));
+        egui::ScrollArea::vertical()
+            .id_source("task_list_preview")
+            .max_height(200.)
+            .show(ui, |ui| {
+                for (name, validation) in names.iter().zip(&validations) {
+                    match validation {
+                        Ok(()) => ui.label(name),
+                        Err(e) => ui.label(
+                            egui::RichText::new(format!("{}: {}", name, e)).color(Color32::RED),
+                        ),
+                    };
                 }
-                self.refresh_tasks(ui);
+            });
+
+        let valid = validations.iter().filter(|v| v.is_ok()).count();
+        let mut open = true;
+        ui.horizontal(|ui| {
+            let create_btn = ui.add_enabled(
+                valid > 0,
+                egui::Button::new(trf("Create {} tasks", &[&valid])),
+            );
+            if ui.button(tr("❌ Cancel")).clicked() {
+                open = false;
+            }
+            if !create_btn.clicked() {
+                return;
+            }
+            let project = match &self.current_project {
+                Some(p) => p.clone(),
+                None => {
+                    self.notifications
+                        .push(Severity::Warning, tr("No project open."));
+                    return;
+                }
+            };
+            results = names
+                .iter()
+                .zip(&validations)
+                .map(|(name, validation)| {
+                    let created = match validation {
+                        Ok(()) => self
+                            .new_task_parent
+                            .create_task(name.clone(), project.clone())
+                            .map_err(|e| e.to_string()),
+                        Err(e) => Err(e.to_string()),
+                    };
+                    StructureResult {
+                        entry: name.clone(),
+                        error: created.err(),
+                    }
+                })
+                .collect();
+            let failed = results.iter().filter(|r| r.error.is_some()).count();
+            if failed == 0 {
+                self.notifications
+                    .push(Severity::Info, trf("Created {} tasks.", &[&results.len()]));
+            } else {
+                self.notifications.push(
+                    Severity::Error,
+                    trf(
+                        "Failed to create {} of {} tasks.",
+                        &[&failed, &results.len()],
+                    ),
+                );
             }
+            text = String::new();
+            self.refresh_tasks(ui);
         });
-        ui.add_space(SPACING);
+
+        if !results.is_empty() {
+            ui.strong(tr("Results"));
+            for r in &results {
+                match &r.error {
+                    Some(e) => ui.label(
+                        egui::RichText::new(format!("❌ {}: {}", r.entry, e)).color(Color32::RED),
+                    ),
+                    None => ui.label(format!("✔ {}", r.entry)),
+                };
+            }
+        }
+        if open {
+            self.new_task_list = Some((text, results));
+        }
+    }
+
+    /// Creates a task with the name under the parent picked for new tasks.
+    fn send_create_task(&mut self, name: String) {
+        let project = match &self.current_project {
+            Some(p) => p.clone(),
+            None => {
+                self.notifications
+                    .push(Severity::Warning, tr("No project open."));
+                return;
+            }
+        };
+
+        self.commands.send(Command::CreateTask {
+            parent: self.new_task_parent.clone(),
+            name,
+            project: Box::new(project),
+        });
+    }
+
+    /// Shows the sanitized name, or why it can't be used. Errors are only shown once the user has typed something.
+    fn name_preview_label(
+        ui: &mut egui::Ui,
+        sanitized: &str,
+        validation: &Result<(), ValidationError>,
+        show_errors: bool,
+    ) {
+        match validation {
+            Err(e) if show_errors => {
+                ui.label(egui::RichText::new(e.to_string()).color(Color32::RED));
+            }
+            _ => {
+                ui.label(egui::RichText::new(sanitized));
+            }
+        }
     }
 
     /// Shows a dialog for creating a folder.
     fn create_folder_dialog(&mut self, ui: &mut egui::Ui) {
         ui.add_space(SPACING);
         ui.horizontal(|ui| {
-            ui.label("Folder name: ");
+            ui.label(tr("Folder name: "));
             let new_folder_name_field = ui.add(
                 egui::TextEdit::singleline(&mut self.new_folder_name).desired_width(TEXTEDIT_WIDTH),
             );
 
-            let create_folder_btn = ui.add(egui::Button::new("Create"));
-            let cancel_btn = ui.add(egui::Button::new("❌ Cancel"));
-            ui.label(egui::RichText::new(sanitize_string(
-                self.new_folder_name.clone(),
-            )));
+            let folder_name = sanitize_string(self.new_folder_name.clone());
+            let validation = validate_child_name(&folder_name, &self.new_folder_parent.path);
+            let create_folder_btn =
+                ui.add_enabled(validation.is_ok(), egui::Button::new(tr("Create")));
+            let cancel_btn = ui.add(egui::Button::new(tr("❌ Cancel")));
+            Self::name_preview_label(
+                ui,
+                &folder_name,
+                &validation,
+                !self.new_folder_name.is_empty(),
+            );
 
             ui.add_space(SPACING);
 
             if cancel_btn.clicked() {
                 self.show_create_folder = false;
-                self.message = None;
             }
 
-            if create_folder_btn.clicked()
-                || (new_folder_name_field.lost_focus()
-                    && ui.input(|i| i.key_pressed(egui::Key::Enter)))
+            if validation.is_ok()
+                && (create_folder_btn.clicked()
+                    || (new_folder_name_field.lost_focus()
+                        && ui.input(|i| i.key_pressed(egui::Key::Enter))))
             {
-                let folder_name = sanitize_string(self.new_folder_name.clone());
-
-                if folder_name.is_empty() {
-                    return;
-                }
-
                 match self.new_folder_parent.create_folder(folder_name) {
                     Ok(()) => {
-                        self.message = Some(Message {
-                            text: String::from("Successfully created folder."),
-                            message_type: MessageType::Info,
-                        });
+                        self.notifications
+                            .push(Severity::Info, tr("Successfully created folder."));
                     }
                     Err(e) => {
                         error!("Error creating folder: {}", e);
-                        self.message = Some(Message {
-                            text: String::from(format!("Error creating folder: {}", e)),
-                            message_type: MessageType::Warning,
-                        });
+                        self.notifications
+                            .push(Severity::Error, trf("Error creating folder: {}", &[&e]));
                     }
                 }
                 self.refresh_tasks(ui);
@@ -645,6 +2492,209 @@ impl Rclamp {
         ui.add_space(SPACING);
     }
 
+    /// Shows a window for creating a folder/task hierarchy from a pasted shot list, with a dry-run preview.
+    fn create_structure_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_create_structure;
+        egui::Window::new(tr("Create structure"))
+            .open(&mut open)
+            .resizable(true)
+            .default_width(400.)
+            .show(ctx, |ui| {
+                ui.label(trf(
+                    "Paste one task per line, e.g. ep01/sq010/sh0010/comp, or CSV. Parent: {}",
+                    &[&self.new_structure_parent.name],
+                ));
+                ui.add_space(SPACING);
+                ui.add(
+                    egui::TextEdit::multiline(&mut self.new_structure_text)
+                        .desired_rows(8)
+                        .desired_width(f32::INFINITY),
+                );
+                ui.add_space(SPACING);
+
+                let entries = StructureEntry::parse(&self.new_structure_text);
+                let preview = StructureNode::preview(&entries, &self.new_structure_parent);
+
+                ui.strong(trf("Preview ({} tasks)", &[&entries.len()]));
+                egui::ScrollArea::vertical()
+                    .id_source("structure_preview")
+                    .max_height(250.)
+                    .show(ui, |ui| {
+                        for c in &preview.children {
+                            Self::structure_preview_child(ui, c);
+                        }
+                    });
+                ui.add_space(SPACING);
+
+                ui.horizontal(|ui| {
+                    let create_btn =
+                        ui.add_enabled(!entries.is_empty(), egui::Button::new(tr("Create")));
+                    if ui.button(tr("❌ Cancel")).clicked() {
+                        self.show_create_structure = false;
+                    }
+
+                    if create_btn.clicked() {
+                        let project = match &self.current_project {
+                            Some(p) => p.clone(),
+                            None => {
+                                self.notifications
+                                    .push(Severity::Warning, tr("No project open."));
+                                return;
+                            }
+                        };
+
+                        self.structure_results =
+                            create_structure(&entries, &self.new_structure_parent, &project);
+                        let failed = self
+                            .structure_results
+                            .iter()
+                            .filter(|r| r.error.is_some())
+                            .count();
+
+                        if failed == 0 {
+                            self.notifications
+                                .push(Severity::Info, tr("Successfully created structure."));
+                        } else {
+                            self.notifications.push(
+                                Severity::Error,
+                                trf(
+                                    "Failed to create {} of {} tasks.",
+                                    &[&failed, &entries.len()],
+                                ),
+                            );
+                        }
+                        self.new_structure_text = String::new();
+                        self.refresh_tasks(ui);
+                    }
+                });
+
+                if !self.structure_results.is_empty() {
+                    ui.add(egui::Separator::default());
+                    ui.strong(tr("Results"));
+                    for r in &self.structure_results {
+                        match &r.error {
+                            Some(e) => ui.label(
+                                egui::RichText::new(format!("❌ {}: {}", r.entry, e))
+                                    .color(Color32::RED),
+                            ),
+                            None => ui.label(format!("✔ {}", r.entry)),
+                        };
+                    }
+                }
+            });
+        self.show_create_structure = open && self.show_create_structure;
+    }
+
+    /// Shows a window for importing tasks from a CSV file, with per-row results.
+    fn import_tasks_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_import_tasks;
+        egui::Window::new(tr("Import tasks"))
+            .open(&mut open)
+            .resizable(true)
+            .default_width(400.)
+            .show(ctx, |ui| {
+                ui.label(trf(
+                    "CSV with a task name or path column and an optional status column. Parent: {}",
+                    &[&self.import_tasks_parent.name],
+                ));
+                ui.add_space(SPACING);
+                ui.horizontal(|ui| {
+                    ui.label(tr("CSV file: "));
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.import_tasks_path).desired_width(250.),
+                    );
+                    let import_btn = ui.add_enabled(
+                        !self.import_tasks_path.is_empty(),
+                        egui::Button::new(tr("Import")),
+                    );
+
+                    if import_btn.clicked() {
+                        let project = match &self.current_project {
+                            Some(p) => p.clone(),
+                            None => {
+                                self.notifications
+                                    .push(Severity::Warning, tr("No project open."));
+                                return;
+                            }
+                        };
+
+                        let path = PathBuf::from(self.import_tasks_path.trim());
+                        let rows = match ImportRow::read_csv(&path) {
+                            Ok(r) => r,
+                            Err(e) => {
+                                self.notifications.push(Severity::Error, e);
+                                return;
+                            }
+                        };
+
+                        self.import_results =
+                            import_tasks(&rows, &self.import_tasks_parent, &project);
+                        let failed = self
+                            .import_results
+                            .iter()
+                            .filter(|r| r.error.is_some())
+                            .count();
+
+                        if failed == 0 {
+                            self.notifications.push(
+                                Severity::Info,
+                                trf("Successfully imported {} tasks.", &[&rows.len()]),
+                            );
+                        } else {
+                            self.notifications.push(
+                                Severity::Error,
+                                trf("Failed to import {} of {} rows.", &[&failed, &rows.len()]),
+                            );
+                        }
+                        self.refresh_tasks(ui);
+                    }
+                });
+
+                if !self.import_results.is_empty() {
+                    ui.add(egui::Separator::default());
+                    ui.strong(tr("Results"));
+                    egui::ScrollArea::vertical()
+                        .id_source("import_results")
+                        .max_height(300.)
+                        .show(ui, |ui| {
+                            for r in &self.import_results {
+                                match &r.error {
+                                    Some(e) => ui.label(
+                                        egui::RichText::new(format!(
+                                            "❌ Row {}: {}: {}",
+                                            r.row, r.entry, e
+                                        ))
+                                        .color(Color32::RED),
+                                    ),
+                                    None => ui.label(format!("✔ Row {}: {}", r.row, r.entry)),
+                                };
+                            }
+                        });
+                }
+            });
+        self.show_import_tasks = open;
+    }
+
+    fn structure_preview_child(ui: &mut egui::Ui, node: &StructureNode) {
+        let text = if node.exists {
+            egui::RichText::new(format!("{} (exists)", node.name)).weak()
+        } else {
+            egui::RichText::new(&node.name)
+        };
+
+        if node.is_task {
+            ui.label(text);
+        } else {
+            egui::CollapsingHeader::new(text)
+                .default_open(true)
+                .show(ui, |ui| {
+                    for c in &node.children {
+                        Self::structure_preview_child(ui, c);
+                    }
+                });
+        }
+    }
+
     /// Shows a dialog for creating projects.
     fn create_project_dialog(
         &mut self,
@@ -654,12 +2704,12 @@ impl Rclamp {
     ) {
         ui.add_space(SPACING);
         ui.horizontal(|ui| {
-            ui.label("Project number: ");
+            ui.label(tr("Project number: "));
             ui.add(egui::TextEdit::singleline(&mut self.new_project_number).desired_width(75.));
 
-            ui.label("Client: ");
+            ui.label(tr("Client: "));
             egui::ComboBox::from_id_source("client_select")
-                .selected_text(format!("{}", self.new_project_client.name))
+                .selected_text(self.new_project_client.name.to_string())
                 .show_ui(ui, |ui| {
                     for c in &self.clients {
                         ui.selectable_value(
@@ -670,15 +2720,13 @@ impl Rclamp {
                     }
                 });
 
-            ui.label("Project name: ");
+            ui.label(tr("Project name: "));
             let project_name_field = ui.add(
                 egui::TextEdit::singleline(&mut self.new_project_name)
                     .desired_width(TEXTEDIT_WIDTH),
             );
-            let create_project_btn = ui.add(egui::Button::new("Create"));
-
             let mut new_project_full_name = String::new();
-            if self.new_project_name.len() > 0 || self.new_project_number.len() > 0 {
+            if !self.new_project_name.is_empty() || !self.new_project_number.is_empty() {
                 new_project_full_name = format!(
                     "{}_{}_{}",
                     self.new_project_number,
@@ -687,12 +2735,47 @@ impl Rclamp {
                 );
             }
 
-            ui.label(egui::RichText::new(sanitize_string(
-                new_project_full_name.clone(),
-            )));
+            ui.label(tr("Episodes: "))
+                .on_hover_text(tr("Creates a project for each episode of the season."));
+            ui.add(egui::DragValue::new(&mut self.new_project_episodes).clamp_range(0..=99));
+            if self.new_project_episodes > 0 {
+                ui.label(tr("Season: "));
+                ui.add(egui::DragValue::new(&mut self.new_project_season).clamp_range(1..=99));
+            }
+
+            let project_name = sanitize_string(new_project_full_name.clone());
+            let names = match self.new_project_episodes {
+                0 => vec![project_name.clone()],
+                n => episode_names(&project_name, self.new_project_season, n),
+            };
+            let existing: Vec<String> = self
+                .projects
+                .iter()
+                .map(|p| p.name_sanitized.clone())
+                .collect();
+            let validation = if self.new_project_name.is_empty() {
+                Err(ValidationError::Empty)
+            } else {
+                names.iter().try_for_each(|name| {
+                    validate_project_name(name, &existing).and_then(|()| {
+                        match &self.config.projects_dir {
+                            Some(d) => validate_child_name(name, d),
+                            None => Ok(()),
+                        }
+                    })
+                })
+            };
+
+            let create_project_btn =
+                ui.add_enabled(validation.is_ok(), egui::Button::new(tr("Create")));
+            let preview = match names.as_slice() {
+                [first, .., last] => format!("{} … {}", first, last),
+                _ => project_name.clone(),
+            };
+            Self::name_preview_label(ui, &preview, &validation, !self.new_project_name.is_empty());
 
             ui.with_layout(egui::Layout::right_to_left(egui::Align::LEFT), |ui| {
-                if ui.button("Manage clients").clicked() {
+                if ui.button(tr("Manage clients")).clicked() {
                     self.open_manage_clients();
                 }
             });
@@ -704,66 +2787,311 @@ impl Rclamp {
                 None => return,
             };
 
-            if create_project_btn.clicked()
+            if (create_project_btn.clicked()
                 || (project_name_field.lost_focus()
-                    && ctx.input(|i| i.key_pressed(egui::Key::Enter)))
+                    && ctx.input(|i| i.key_pressed(egui::Key::Enter))))
+                && validation.is_ok()
             {
-                if self.new_project_name.len() > 0 {
-                    match Project::new(
-                        sanitize_string(new_project_full_name.clone()),
-                        projects_dir.clone(),
-                        self.config.template_project.pipeline_dir_name.clone(),
-                        self.config.template_project.work_dir_name.clone(),
-                        self.config.template_project.dailies_dir_name.clone(),
-                        self.config.template_project.deliveries_dir_name.clone(),
-                        self.config.template_project.extra_dir_names.clone(),
-                        self.config.template_project.work_sub_dirs.clone(),
-                    )
-                    .create(projects_dir.clone())
-                    {
-                        Ok(()) => {
-                            self.message = Some(Message {
-                                text: String::from("Successfully created new project"),
-                                message_type: MessageType::Info,
-                            });
-                        }
-                        Err(e) => {
-                            error!("Error creating project: {}", e);
-                            self.message = Some(Message {
-                                text: String::from(format!("Error creating project: {}", e)),
-                                message_type: MessageType::Warning,
-                            });
-                        }
+                // Episodes share the client and the template, and are grouped by the program.
+                let mut project = self.config.template_project.duplicate(&project_name);
+                project.client = self.new_project_client.short_name.clone();
+                for name in &names {
+                    let mut episode = project.duplicate(name);
+                    if self.new_project_episodes > 0 {
+                        episode.program = project_name.clone();
+                    }
+                    if !self.create_project(episode, projects_dir.clone()) {
+                        break;
                     }
-                    self.refresh_projects();
                 }
+                self.refresh_projects();
             }
         });
         ui.add_space(SPACING);
     }
 
+    /// Creates the project on drive, running the pre- and post-create hooks. Returns false if it wasn't
+    /// created. Pre-create hooks come from the pipeline template, since the project doesn't exist yet.
+    fn create_project(&mut self, project: Project, projects_dir: PathBuf) -> bool {
+        let template_hooks_dir = self.config.templates_dir.join(PIPELINE_TEMPLATE_DIR_NAME);
+        let pre_hooks = Hooks::load(&template_hooks_dir).and_then(|h| {
+            h.run(
+                HookEvent::PreProjectCreate,
+                &HookContext {
+                    project: Some(&project),
+                    projects_dir: Some(&projects_dir),
+                    ..Default::default()
+                },
+            )
+        });
+        if let Err(e) = pre_hooks {
+            self.notifications.push(
+                Severity::Warning,
+                trf("Project creation cancelled: {}", &[&e]),
+            );
+            return false;
+        }
+
+        match project.create(projects_dir.clone(), &self.config.templates_dir) {
+            Ok(()) => {
+                self.notifications
+                    .push(Severity::Info, tr("Successfully created new project"));
+            }
+            Err(e) => {
+                error!("Error creating project: {}", e);
+                self.notifications
+                    .push(Severity::Error, trf("Error creating project: {}", &[&e]));
+                return false;
+            }
+        }
+
+        let context = CommandContext {
+            project: Some(project),
+            projects_dir: Some(projects_dir),
+            ..Default::default()
+        };
+        self.queue_post_hooks(HookEvent::PostProjectCreate, context, None);
+        true
+    }
+
+    /// Runs the post-hooks of the event as a job. Nothing waits for them, so the ui doesn't either.
+    fn queue_post_hooks(&mut self, event: HookEvent, context: CommandContext, file: Option<File>) {
+        if !context.has_hooks(event) {
+            return;
+        }
+        self.jobs().push(&tr("Run hooks"), move |_job| {
+            context
+                .run_hooks(event, file.as_ref())
+                .map(|()| tr("Hooks finished"))
+        });
+    }
+
+    /// Creates a project with the layout of another, optionally with the same folders and tasks.
+    fn duplicate_project_window(&mut self, ctx: &egui::Context) {
+        let (source, mut name, mut include_tasks) = match &self.duplicate_project {
+            Some(d) => d.clone(),
+            None => return,
+        };
+        let projects_dir = match &self.config.projects_dir {
+            Some(d) => d.clone(),
+            None => {
+                self.duplicate_project = None;
+                return;
+            }
+        };
+
+        let mut open = true;
+        let mut duplicate = false;
+        egui::Window::new(tr("Duplicate project"))
+            .id(egui::Id::new("duplicate_project"))
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                ui.label(trf(
+                    "Creates a project with the folders of {}. Workfiles and outputs aren't copied.",
+                    &[&source.name],
+                ));
+                ui.add_space(SPACING);
+                let sanitized = sanitize_string(name.clone());
+                let existing: Vec<String> = self
+                    .projects
+                    .iter()
+                    .map(|p| p.name_sanitized.clone())
+                    .collect();
+                let validation = validate_project_name(&sanitized, &existing)
+                    .and_then(|()| validate_child_name(&sanitized, &projects_dir));
+                ui.horizontal(|ui| {
+                    ui.label(tr("Project name: "));
+                    ui.add(egui::TextEdit::singleline(&mut name).desired_width(TEXTEDIT_WIDTH));
+                    Self::name_preview_label(ui, &sanitized, &validation, !name.is_empty());
+                });
+                ui.checkbox(&mut include_tasks, tr("Include folders and tasks"));
+                ui.add_space(SPACING);
+                duplicate = ui
+                    .add_enabled(validation.is_ok(), egui::Button::new(tr("Duplicate")))
+                    .clicked();
+            });
+
+        if duplicate {
+            self.duplicate_project = None;
+            let project = source.duplicate(&sanitize_string(name));
+            if self.create_project(project.clone(), projects_dir) && include_tasks {
+                self.copy_task_tree(&source, &project);
+            }
+            self.refresh_projects();
+            return;
+        }
+        self.duplicate_project = if open {
+            Some((source, name, include_tasks))
+        } else {
+            None
+        };
+    }
+
+    /// Edits the client, program and due date of a project, set on creation.
+    fn edit_project_metadata_window(&mut self, ctx: &egui::Context) {
+        let (mut project, mut due_date) = match &self.edit_project_metadata {
+            Some(e) => e.clone(),
+            None => return,
+        };
+
+        let mut open = true;
+        let mut save = false;
+        egui::Window::new(trf("Project metadata: {}", &[&project.name]))
+            .id(egui::Id::new("edit_project_metadata"))
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("project_metadata_grid")
+                    .num_columns(2)
+                    .show(ui, |ui| {
+                        ui.label(tr("Client: "));
+                        let selected = self
+                            .clients
+                            .iter()
+                            .find(|c| c.short_name == project.client)
+                            .map_or(project.client.clone(), |c| c.name.clone());
+                        egui::ComboBox::from_id_source("project_metadata_client")
+                            .selected_text(selected)
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut project.client, String::new(), "–");
+                                for c in &self.clients {
+                                    ui.selectable_value(
+                                        &mut project.client,
+                                        c.short_name.clone(),
+                                        &c.name,
+                                    );
+                                }
+                            });
+                        ui.end_row();
+                        ui.label(tr("Program: "));
+                        ui.add(
+                            egui::TextEdit::singleline(&mut project.program)
+                                .desired_width(TEXTEDIT_WIDTH),
+                        );
+                        ui.end_row();
+                        ui.label(tr("Due date: "));
+                        ui.add(
+                            egui::TextEdit::singleline(&mut due_date)
+                                .hint_text("YYYY-MM-DD")
+                                .desired_width(TEXTEDIT_WIDTH),
+                        );
+                        ui.end_row();
+                    });
+                let due_date = due_date.trim();
+                let valid = due_date.is_empty() || parse_date(due_date).is_some();
+                if !valid {
+                    ui.label(
+                        egui::RichText::new(tr("The due date must be YYYY-MM-DD."))
+                            .color(Color32::RED),
+                    );
+                }
+                ui.add_space(SPACING);
+                save = ui
+                    .add_enabled(valid, egui::Button::new(tr("Save")))
+                    .clicked();
+            });
+
+        if save {
+            project.program = project.program.trim().to_string();
+            project.due_date = Some(due_date.trim().to_string()).filter(|d| !d.is_empty());
+            if self.save_project(&project) {
+                self.edit_project_metadata = None;
+                return;
+            }
+        }
+        self.edit_project_metadata = if open {
+            Some((project, due_date))
+        } else {
+            None
+        };
+    }
+
+    /// Writes the report of the project into its pipeline folder.
+    fn export_report(&mut self, project: &Project) {
+        match write_project_report(&self.config.pipeline_context(), project) {
+            Ok(p) => self
+                .notifications
+                .push(Severity::Info, trf("Wrote {}", &[&p.display()])),
+            Err(e) => self.notifications.push(
+                Severity::Error,
+                trf("Error writing the report of {}: {}", &[&project.name, &e]),
+            ),
+        }
+    }
+
+    /// Makes the folders and tasks of one project in another, without their files.
+    fn copy_task_tree(&mut self, source: &Project, target: &Project) {
+        let context = self.config.pipeline_context();
+        let tree = match context.task_tree(source) {
+            Ok(t) => t,
+            Err(e) => {
+                error!("Error reading the tasks of {}: {}", source.name, e);
+                self.notifications.push(
+                    Severity::Error,
+                    trf("Error reading the tasks of {}: {}", &[&source.name, &e]),
+                );
+                return;
+            }
+        };
+        let root = TaskTreeNode::new(
+            target.name.clone(),
+            context.work_path(target),
+            context.work_dir_name(target),
+            context.output_dir_name(target),
+        );
+        let failed: Vec<String> = copy_tree(&tree, &root, target)
+            .into_iter()
+            .filter_map(|r| Some(format!("{}: {}", r.entry, r.error?)))
+            .collect();
+        if failed.is_empty() {
+            self.notifications
+                .push(Severity::Info, tr("Copied the folders and tasks."));
+        } else {
+            self.notifications.push(
+                Severity::Warning,
+                trf(
+                    "Some folders and tasks weren't copied: {}",
+                    &[&failed.join(", ")],
+                ),
+            );
+        }
+    }
+
     fn create_file_dialog(&mut self, ui: &mut egui::Ui) {
         ui.horizontal(|ui| {
-            ui.label("New workfile name: ");
+            ui.label(tr("New workfile name: "));
 
             let new_file_name_field = ui.add(
                 egui::TextEdit::singleline(&mut self.new_file_name).desired_width(TEXTEDIT_WIDTH),
             );
-            ui.label("File type: ");
+            ui.label(tr("File type: "));
             egui::ComboBox::from_id_source("filetype_select")
-                .selected_text(format!("{}", self.new_file_type.name))
+                .selected_text(self.new_file_type.name.to_string())
                 .show_ui(ui, |ui| {
                     for d in &self.dcc {
                         ui.selectable_value(&mut self.new_file_type, d.clone(), d.name.clone());
                     }
                 });
-            let create_file_btn = ui.add(egui::Button::new("Create"));
-            ui.label(egui::RichText::new(sanitize_string(
-                self.new_file_name.clone(),
-            )));
+            let file_name = sanitize_string(self.new_file_name.clone());
+            let validation = match (&self.current_project, &self.current_task) {
+                (Some(p), Some(t)) => validate_file_path(&File::new_file_path(
+                    &file_name,
+                    t,
+                    p,
+                    &self.new_file_type,
+                    &self.config.filename_template,
+                )),
+                _ => Ok(()),
+            };
+            let create_file_btn =
+                ui.add_enabled(validation.is_ok(), egui::Button::new(tr("Create")));
+            Self::name_preview_label(ui, &file_name, &validation, true);
 
-            if (new_file_name_field.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter)))
-                || create_file_btn.clicked()
+            if validation.is_ok()
+                && ((new_file_name_field.lost_focus()
+                    && ui.input(|i| i.key_pressed(egui::Key::Enter)))
+                    || create_file_btn.clicked())
             {
                 if self.current_project.is_none() {
                     return;
@@ -772,21 +3100,23 @@ impl Rclamp {
                     return;
                 }
 
-                let file_name = sanitize_string(self.new_file_name.clone());
-
                 match File::create_file(
                     file_name,
                     self.current_task.clone().unwrap(),
                     self.current_project.clone().unwrap(),
                     self.new_file_type.clone(),
+                    &self.config.filename_template,
                 ) {
-                    Ok(()) => (),
+                    Ok(()) => {
+                        let task = self.current_task.clone().unwrap();
+                        if let Err(e) = task.set_last_dcc(&self.new_file_type.name) {
+                            error!("Error saving last DCC: {}", e);
+                        }
+                    }
                     Err(e) => {
                         error!("Error creating task: {}", e);
-                        self.message = Some(Message {
-                            text: String::from(format!("Error creating task: {}", e)),
-                            message_type: MessageType::Warning,
-                        });
+                        self.notifications
+                            .push(Severity::Error, trf("Error creating task: {}", &[&e]));
                     }
                 }
                 self.refresh_files();
@@ -799,44 +3129,80 @@ impl Rclamp {
         egui::menu::bar(ui, |ui| {
             ui.horizontal(|ui| {
                 ui.with_layout(egui::Layout::left_to_right(egui::Align::RIGHT), |ui| {
-                    let text: String;
-                    if !self.show_create_project {
-                        text = String::from("Create project");
+                    let text = if !self.show_create_project {
+                        tr("Create project")
                     } else {
-                        text = String::from("❌ Close");
-                    }
-                    if ui.add(egui::Button::new(text)).clicked() {
-                        self.new_project_name = String::new();
-                        self.message = None;
-                        self.open_or_close_create_project();
-                    }
-                });
-                ui.with_layout(
-                    egui::Layout::centered_and_justified(egui::Direction::RightToLeft),
-                    |ui| {
-                        ui.with_layout(egui::Layout::left_to_right(egui::Align::LEFT), |ui| {
-                            match &self.message {
-                                Some(m) => {
-                                    match m.message_type {
-                                        MessageType::Info => ui.label(&m.text),
-                                        MessageType::Warning => ui.label(
-                                            egui::RichText::new(&m.text).color(Color32::RED),
-                                        ),
-                                    };
-                                }
-                                None => (),
-                            }
-                        });
-                    },
-                );
-
+                        tr("❌ Close")
+                    };
+                    if self.config.role.can_edit() {
+                        if ui.add(egui::Button::new(text)).clicked() {
+                            self.new_project_name = String::new();
+                            self.open_or_close_create_project();
+                        }
+                        if ui.button(tr("Adopt project…")).clicked() {
+                            self.open_adopt_project();
+                        }
+                        if self.config.cloud_sync.is_some()
+                            && ui.button(tr("Restore from cloud…")).clicked()
+                        {
+                            self.open_restore_project();
+                        }
+                    } else {
+                        ui.label(format!("👁 {}", tr("Viewer"))).on_hover_text(tr(
+                            "Read-only, creating, deleting and versioning are turned off.",
+                        ));
+                    }
+                    if self.connectivity.is_offline() {
+                        let queued = self.connectivity.queued().len().to_string();
+                        ui.label(
+                            egui::RichText::new(format!("⚠ {}", tr("Offline"))).color(Color32::RED),
+                        )
+                        .on_hover_text(trf(
+                            "The projects dir can't be reached, showing the last scanned projects and tasks. {} changes are queued.",
+                            &[&queued],
+                        ));
+                    }
+                    self.tools_menu(ui);
+                    self.recent_menu(ui);
+                    let pending = self.inbox.watcher.pending().len();
+                    if pending > 0
+                        && self.can_edit()
+                        && ui
+                            .button(format!("📥 {}", pending))
+                            .on_hover_text(tr("New files in the inbox"))
+                            .clicked()
+                    {
+                        self.inbox.show = !self.inbox.show;
+                    }
+                });
                 ui.with_layout(egui::Layout::right_to_left(egui::Align::RIGHT), |ui| {
                     let theme_icon = if self.config.dark_mode { "☀" } else { "🌙" };
                     let refresh_btn = ui.add(egui::Button::new("🔄"));
                     let theme_btn = ui.add(egui::Button::new(theme_icon));
+                    let trash_btn = ui.add(egui::Button::new(tr("🗑 Trash")));
+                    ui.toggle_value(&mut self.layout.show_log, tr("Log"));
+                    ui.toggle_value(&mut self.layout.show_tasks, tr("Tasks"));
+                    ui.toggle_value(&mut self.layout.show_projects, tr("Projects"));
+                    if ui.button("⚙").on_hover_text(tr("Preferences")).clicked() {
+                        self.show_preferences = !self.show_preferences;
+                    }
+                    let notifications_btn = ui
+                        .add(egui::Button::new("🔔"))
+                        .on_hover_text(tr("Notifications"));
+                    #[cfg(feature = "python")]
+                    self.scripts_menu(ui);
+
+                    if trash_btn.clicked() {
+                        self.refresh_trash();
+                        self.show_trash = !self.show_trash;
+                    }
 
+                    if notifications_btn.clicked() {
+                        self.show_notifications = !self.show_notifications;
+                    }
                     if theme_btn.clicked() {
                         self.config.dark_mode = !self.config.dark_mode;
+                        self.preferences.dark_mode = Some(self.config.dark_mode);
                     }
                     if refresh_btn.clicked() {
                         self.refresh_all(ui);
@@ -846,225 +3212,4509 @@ impl Rclamp {
         });
     }
 
-    /// Show task tree
-    fn render_task_tree(&mut self, ui: &mut egui::Ui) {
-        let task = match &self.current_project_task_tree {
-            Some(t) => t.clone(),
-            None => return,
-        };
-
-        ui.horizontal(|ui| {
-            ui.with_layout(egui::Layout::right_to_left(egui::Align::RIGHT), |ui| {
-                let new_folder_btn = ui.add(egui::Button::new("+ Folder"));
-                let new_task_btn = ui.add(egui::Button::new("+ Task"));
-                ui.add_space(SPACING);
-
-                if new_folder_btn.clicked() {
-                    self.message = None;
-                    self.new_folder_name = String::new();
-                    self.new_folder_parent = task.clone();
-                    self.open_create_folder();
+    fn tools_menu(&mut self, ui: &mut egui::Ui) {
+        ui.menu_button(tr("Tools"), |ui| {
+            let lint_btn = ui
+                .add_enabled(
+                    self.current_project_task_tree.is_some(),
+                    egui::Button::new(tr("Check file names…")),
+                )
+                .on_disabled_hover_text(tr("Open a project first."));
+            if lint_btn.clicked() {
+                self.run_naming_lint();
+                ui.close_menu();
+            }
+            if ui
+                .button(trf("Register {}:// links", &[&LINK_SCHEME]))
+                .on_hover_text(tr("Open shared links to projects and tasks in this app."))
+                .clicked()
+            {
+                match register_scheme() {
+                    Ok(()) => self.notifications.push(
+                        Severity::Info,
+                        trf("Registered {}:// links.", &[&LINK_SCHEME]),
+                    ),
+                    Err(e) => self
+                        .notifications
+                        .push(Severity::Error, trf("Error registering links: {}", &[&e])),
                 }
-                if new_task_btn.clicked() {
-                    self.message = None;
-                    self.new_task_name = String::new();
-                    self.new_task_parent = task.clone();
-                    self.open_create_task();
+                ui.close_menu();
+            }
+            if ui.button(tr("Client view…")).clicked() {
+                self.client_view = Some((String::new(), Vec::new()));
+                ui.close_menu();
+            }
+            if !self.can_edit() {
+                return;
+            }
+            ui.separator();
+            let batch_btn = ui
+                .add_enabled(
+                    self.current_project_task_tree.is_some(),
+                    egui::Button::new(tr("Version up tasks…")),
+                )
+                .on_disabled_hover_text(tr("Open a project first."));
+            if batch_btn.clicked() {
+                self.open_batch_version_up();
+                ui.close_menu();
+            }
+            let ingest_btn = ui
+                .add_enabled(
+                    self.current_project.is_some(),
+                    egui::Button::new(tr("Ingest footage…")),
+                )
+                .on_disabled_hover_text(tr("Open a project first."));
+            if ingest_btn.clicked() {
+                self.footage_ingest = Some(FootageIngest {
+                    sources: Vec::new(),
+                    new_source: String::new(),
+                    template: self.config.ingest.template.clone(),
+                    items: Ok(Vec::new()),
+                });
+                ui.close_menu();
+            }
+            if ui.button(tr("DCC templates…")).clicked() {
+                self.templates_manager = Some(TemplatesManager {
+                    dirs: scan_templates(&self.config.templates_dir).map_err(|e| e.to_string()),
+                    import_name: String::new(),
+                    import_path: String::new(),
+                });
+                ui.close_menu();
+            }
+            if !self.config.mirror_profiles.is_empty() {
+                ui.separator();
+            }
+            for profile in self.config.mirror_profiles.clone() {
+                if ui
+                    .button(trf("Mirror {}", &[&profile.name]))
+                    .on_hover_text(profile.command_line())
+                    .clicked()
+                {
+                    self.run_mirror(profile);
+                    ui.close_menu();
                 }
-            });
+            }
         });
-        for c in &task.children {
-            let child = c.clone();
-            let _ = &self.tree_child(ui, child);
-        }
     }
 
-    fn tree_child(&mut self, ui: &mut egui::Ui, task: TaskTreeNode) {
-        if !task.metadata.is_task {
-            egui::CollapsingHeader::new(task.name.clone())
-                .id_source(task.path.clone())
+    fn recent_menu(&mut self, ui: &mut egui::Ui) {
+        ui.menu_button(tr("Recent"), |ui| {
+            if self.recent_tasks.is_empty() {
+                ui.weak(tr("No recent tasks."));
+            }
+            for path in self.recent_tasks.clone() {
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                if ui
+                    .button(name)
+                    .on_hover_text(path.display().to_string())
+                    .clicked()
+                {
+                    self.open_task_path(&path, ui);
+                    ui.close_menu();
+                }
+            }
+        });
+    }
+
+    /// Reopens the project and task the app was closed with. Nothing is opened if they're gone.
+    fn reopen_last_session(
+        &mut self,
+        project_path: &Path,
+        task_path: Option<&Path>,
+        ui: &mut egui::Ui,
+    ) {
+        let projects_dir = match &self.config.projects_dir {
+            Some(d) => d.clone(),
+            None => return,
+        };
+        let project = self
+            .projects
+            .iter()
+            .find(|p| p.get_path(&projects_dir) == project_path)
+            .filter(|p| self.project_access(p) != Access::None)
+            .cloned();
+        let project = match project {
+            Some(p) => p,
+            None => {
+                info!("Last project not found: {}", project_path.display());
+                return;
+            }
+        };
+        info!("Reopening {}", project.name);
+        self.open_project(project, ui);
+
+        let task = match (task_path, &self.current_project_task_tree) {
+            (Some(p), Some(tree)) => tree.path_to(p).last().map(|t| (*t).clone()),
+            _ => None,
+        };
+        match task {
+            Some(t) if t.metadata.is_task => {
+                self.reveal_node = Some(t.path.clone());
+                self.set_current_task(t);
+            }
+            _ => {
+                if let Some(p) = task_path {
+                    info!("Last task not found: {}", p.display());
+                }
+            }
+        }
+    }
+
+    /// Opens the project and task of a link or the command line.
+    fn open_target(&mut self, target: OpenTarget, ui: &mut egui::Ui) {
+        info!("Opening {}", target.to_url());
+        let project = self
+            .projects
+            .iter()
+            .find(|p| p.name == target.project || p.name_sanitized == target.project)
+            .cloned();
+        let project = match project {
+            Some(p) => p,
+            None => {
+                self.notifications.push(
+                    Severity::Error,
+                    trf("Project not found: {}", &[&target.project]),
+                );
+                return;
+            }
+        };
+        self.open_project(project.clone(), ui);
+
+        let (task, projects_dir) = match (&target.task, &self.config.projects_dir) {
+            (Some(t), Some(d)) => (t, d),
+            _ => return,
+        };
+        let work_path = project.get_work_path(projects_dir);
+        let node = self
+            .current_project_task_tree
+            .as_ref()
+            .and_then(|tree| find_task(tree, &work_path, task))
+            .cloned();
+        match node {
+            Some(node) => {
+                self.reveal_node = Some(node.path.clone());
+                self.set_current_task(node);
+            }
+            None => self
+                .notifications
+                .push(Severity::Error, trf("Task not found: {}", &[task])),
+        }
+    }
+
+    /// A `rclamp://` link to the task in the current project.
+    fn task_link(&self, task: &TaskTreeNode) -> Option<String> {
+        let (project, projects_dir) = match (&self.current_project, &self.config.projects_dir) {
+            (Some(p), Some(d)) => (p, d),
+            _ => return None,
+        };
+        let target = OpenTarget {
+            project: project.name.clone(),
+            task: Some(task_key(&project.get_work_path(projects_dir), &task.path)),
+        };
+        Some(target.to_url())
+    }
+
+    /// Opens the project the task dir is in, unless it's already open, then the task.
+    fn open_task_path(&mut self, path: &Path, ui: &mut egui::Ui) {
+        let projects_dir = match &self.config.projects_dir {
+            Some(d) => d.clone(),
+            None => return,
+        };
+        let project = self
+            .projects
+            .iter()
+            .find(|p| path.starts_with(p.get_work_path(&projects_dir)))
+            .cloned();
+        if let Some(project) = project {
+            if self.current_project.as_ref().map(|p| &p.name) != Some(&project.name) {
+                self.open_project(project, ui);
+            }
+        }
+        let node = self
+            .current_project_task_tree
+            .as_ref()
+            .and_then(|t| t.path_to(path).last().map(|n| (*n).clone()));
+        match node {
+            Some(node) => {
+                self.reveal_node = Some(path.to_path_buf());
+                self.set_current_task(node);
+            }
+            None => self.notifications.push(
+                Severity::Error,
+                trf("Task not found: {}", &[&path.display()]),
+            ),
+        }
+    }
+
+    /// Checks all workfiles of the current project against the filename template.
+    fn run_naming_lint(&mut self) {
+        let (tree, project) = match (&self.current_project_task_tree, &self.current_project) {
+            (Some(t), Some(p)) => (t.clone(), p.clone()),
+            _ => return,
+        };
+        let ignore = self.ignore_patterns();
+        match lint_tasks(&tree, &project, &self.config.filename_template, &ignore) {
+            Ok(issues) => {
+                self.naming_lint = Some(NamingLint {
+                    issues,
+                    report: Vec::new(),
+                })
+            }
+            Err(e) => self
+                .notifications
+                .push(Severity::Error, trf("Error checking file names: {}", &[&e])),
+        }
+    }
+
+    fn naming_lint_window(&mut self, ctx: &egui::Context) {
+        let mut lint = match &self.naming_lint {
+            Some(l) => l.clone(),
+            None => return,
+        };
+
+        let mut open = true;
+        let mut rename = None;
+        egui::Window::new(tr("Check file names"))
+            .id(egui::Id::new("naming_lint"))
+            .open(&mut open)
+            .resizable(true)
+            .default_width(700.)
+            .show(ctx, |ui| {
+                if lint.issues.is_empty() {
+                    ui.label(tr("All workfiles follow the filename template."));
+                    return;
+                }
+                ui.label(trf(
+                    "{} workfiles don't follow {}",
+                    &[&lint.issues.len(), &self.config.filename_template.as_str()],
+                ));
+                ui.add_space(SPACING);
+                egui::ScrollArea::vertical()
+                    .max_height(300.)
+                    .id_source("naming_issues")
+                    .show(ui, |ui| {
+                        egui::Grid::new("naming_issues_grid")
+                            .striped(true)
+                            .show(ui, |ui| {
+                                for title in ["Task", "File", "Problem", "Suggestion"] {
+                                    ui.strong(tr(title));
+                                }
+                                ui.end_row();
+                                for issue in &lint.issues {
+                                    let problem = match &issue.problem {
+                                        NamingProblem::NoVersion => tr("No version number"),
+                                        NamingProblem::NotTemplate => {
+                                            tr("Doesn't follow the template")
+                                        }
+                                        NamingProblem::WrongProject(p) => {
+                                            trf("Other project: {}", &[p])
+                                        }
+                                        NamingProblem::WrongTask(t) => trf("Other task: {}", &[t]),
+                                        NamingProblem::Format => tr("Formatted differently"),
+                                    };
+                                    ui.label(&issue.task);
+                                    ui.label(
+                                        issue
+                                            .path
+                                            .file_name()
+                                            .unwrap_or_default()
+                                            .to_string_lossy(),
+                                    );
+                                    ui.label(problem);
+                                    ui.label(issue.suggestion.clone().unwrap_or_default());
+                                    ui.end_row();
+                                }
+                            });
+                    });
+
+                ui.add_space(SPACING);
+                ui.horizontal(|ui| {
+                    if ui.button(tr("Dry run")).clicked() {
+                        rename = Some(true);
+                    }
+                    if ui
+                        .add_enabled(self.can_edit(), egui::Button::new(tr("Rename all")))
+                        .clicked()
+                    {
+                        rename = Some(false);
+                    }
+                });
+                if !lint.report.is_empty() {
+                    ui.add_space(SPACING);
+                    egui::ScrollArea::vertical()
+                        .max_height(200.)
+                        .id_source("naming_report")
+                        .show(ui, |ui| {
+                            for line in &lint.report {
+                                ui.monospace(line);
+                            }
+                        });
+                }
+            });
+
+        match rename {
+            Some(true) => lint.report = rename_issues(&lint.issues, true),
+            Some(false) => {
+                let report = rename_issues(&lint.issues, false);
+                self.refresh_files();
+                self.run_naming_lint();
+                if let Some(l) = &mut self.naming_lint {
+                    l.report = report;
+                }
+                return;
+            }
+            None => (),
+        }
+        self.naming_lint = if open { Some(lint) } else { None };
+    }
+
+    fn open_batch_version_up(&mut self) {
+        let tree = match &self.current_project_task_tree {
+            Some(t) => t,
+            None => return,
+        };
+        let tasks = tree
+            .tasks()
+            .into_iter()
+            .map(|t| (t.clone(), false))
+            .collect();
+        self.batch_version_up = Some(BatchVersionUp {
+            tasks,
+            ..Default::default()
+        });
+    }
+
+    /// Picks tasks whose latest workfiles all get a new version, e.g. at the start of a new client round.
+    fn batch_version_up_window(&mut self, ctx: &egui::Context) {
+        let mut batch = match &self.batch_version_up {
+            Some(b) => b.clone(),
+            None => return,
+        };
+        let root = match &self.current_project_task_tree {
+            Some(t) => t.path.clone(),
+            None => PathBuf::new(),
+        };
+
+        let mut open = true;
+        let mut run = false;
+        egui::Window::new(tr("Version up tasks"))
+            .id(egui::Id::new("batch_version_up"))
+            .open(&mut open)
+            .resizable(true)
+            .default_width(400.)
+            .show(ctx, |ui| {
+                if let Some((_id, report)) = &batch.job {
+                    let lines = match report.lock() {
+                        Ok(l) => l.clone(),
+                        Err(_e) => Vec::new(),
+                    };
+                    egui::ScrollArea::both()
+                        .stick_to_bottom(true)
+                        .max_height(400.)
+                        .show(ui, |ui| {
+                            for line in &lines {
+                                ui.monospace(line);
+                            }
+                        });
+                    return;
+                }
+
+                ui.label(tr(
+                    "Copies the latest version of every workfile in the tasks as a new version.",
+                ));
+                ui.add_space(SPACING);
+                ui.horizontal(|ui| {
+                    ui.label(tr("Filter"));
+                    ui.add(
+                        egui::TextEdit::singleline(&mut batch.filter).desired_width(TEXTEDIT_WIDTH),
+                    );
+                });
+                let relative = |t: &TaskTreeNode| {
+                    let path = t.path.strip_prefix(&root).unwrap_or(&t.path);
+                    path.display().to_string().replace('\\', "/")
+                };
+                let filter = batch.filter.to_lowercase();
+                let mut shown: Vec<&mut (TaskTreeNode, bool)> = batch
+                    .tasks
+                    .iter_mut()
+                    .filter(|(t, _s)| relative(t).to_lowercase().contains(&filter))
+                    .collect();
+                ui.horizontal(|ui| {
+                    for (label, value) in [(tr("Select all"), true), (tr("Select none"), false)] {
+                        if ui.button(label).clicked() {
+                            for (_t, s) in shown.iter_mut() {
+                                *s = value;
+                            }
+                        }
+                    }
+                });
+                egui::ScrollArea::vertical()
+                    .max_height(300.)
+                    .show(ui, |ui| {
+                        if shown.is_empty() {
+                            ui.label(tr("No matching tasks."));
+                        }
+                        for (t, s) in shown.iter_mut() {
+                            ui.checkbox(s, relative(t));
+                        }
+                    });
+
+                ui.add_space(SPACING);
+                let count = batch.tasks.iter().filter(|(_t, s)| *s).count();
+                if ui
+                    .add_enabled(
+                        count > 0,
+                        egui::Button::new(trf("Version up {} tasks", &[&count])),
+                    )
+                    .clicked()
+                {
+                    run = true;
+                }
+            });
+
+        if run {
+            let tasks: Vec<TaskTreeNode> = batch
+                .tasks
+                .iter()
+                .filter(|(_t, s)| *s)
+                .map(|(t, _s)| t.clone())
+                .collect();
+            let template = self.config.filename_template.clone();
+            let ignore = self.ignore_patterns();
+            let report = BatchReport::default();
+            let job_report = report.clone();
+            let id = self
+                .jobs()
+                .push(&trf("Version up {} tasks", &[&tasks.len()]), move |job| {
+                    version_up_tasks(&tasks, &template, &ignore, &job_report, job)
+                });
+            batch.job = Some((id, report));
+        }
+        self.batch_version_up = if open { Some(batch) } else { None };
+    }
+
+    /// Copies client footage into the project, renamed with the template. Sources can be typed or dropped.
+    fn footage_ingest_window(&mut self, ctx: &egui::Context) {
+        let mut ingest_state = match &self.footage_ingest {
+            Some(i) => i.clone(),
+            None => return,
+        };
+        let (project, project_path) = match (&self.current_project, &self.config.projects_dir) {
+            (Some(p), Some(d)) => (p.name_sanitized.clone(), p.get_path(d)),
+            _ => {
+                self.footage_ingest = None;
+                return;
+            }
+        };
+        let now = now_timestamp();
+        let dir = ingest_dir(&project_path, &self.config.ingest, now);
+
+        let mut open = true;
+        let mut done = false;
+        let mut changed = false;
+        egui::Window::new(tr("Ingest footage"))
+            .id(egui::Id::new("footage_ingest"))
+            .open(&mut open)
+            .resizable(true)
+            .default_width(600.)
+            .show(ctx, |ui| {
+                ui.label(trf("Copies into {}", &[&dir.display()]));
+                ui.add_space(SPACING);
+
+                ui.strong(tr("Sources"));
+                let count = ingest_state.sources.len();
+                ingest_state.sources.retain(|source| {
+                    ui.horizontal(|ui| {
+                        ui.label(source.display().to_string());
+                        !ui.small_button("✖").on_hover_text(tr("Remove")).clicked()
+                    })
+                    .inner
+                });
+                changed |= count != ingest_state.sources.len();
+                let dropped: Vec<PathBuf> = ui.input(|i| {
+                    i.raw
+                        .dropped_files
+                        .iter()
+                        .filter_map(|f| f.path.clone())
+                        .collect()
+                });
+                changed |= !dropped.is_empty();
+                ingest_state.sources.extend(dropped);
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::TextEdit::singleline(&mut ingest_state.new_source)
+                            .hint_text(tr("Path to a file or folder, or drop them here"))
+                            .desired_width(TEXTEDIT_WIDTH),
+                    );
+                    let source = ingest_state.new_source.trim().to_string();
+                    if ui
+                        .add_enabled(!source.is_empty(), egui::Button::new(tr("Add")))
+                        .clicked()
+                    {
+                        ingest_state.sources.push(PathBuf::from(source));
+                        ingest_state.new_source.clear();
+                        changed = true;
+                    }
+                });
+
+                ui.add_space(SPACING);
+                ui.horizontal(|ui| {
+                    ui.label(tr("Rename to"));
+                    changed |= ui
+                        .add(
+                            egui::TextEdit::singleline(&mut ingest_state.template)
+                                .desired_width(TEXTEDIT_WIDTH),
+                        )
+                        .changed();
+                });
+
+                ui.add_space(SPACING);
+                match &ingest_state.items {
+                    Err(e) => {
+                        ui.label(egui::RichText::new(e).color(Color32::RED));
+                    }
+                    Ok(items) => {
+                        egui::ScrollArea::vertical()
+                            .max_height(300.)
+                            .id_source("ingest_items")
+                            .show(ui, |ui| {
+                                egui::Grid::new("ingest_items_grid")
+                                    .striped(true)
+                                    .show(ui, |ui| {
+                                        for item in items {
+                                            ui.label(item.item.name());
+                                            ui.label("→");
+                                            ui.label(item.target_name());
+                                            ui.label(format_size(item.item.size()));
+                                            ui.end_row();
+                                        }
+                                    });
+                            });
+                        ui.add_space(SPACING);
+                        if ui
+                            .add_enabled(!items.is_empty(), egui::Button::new(tr("Ingest")))
+                            .clicked()
+                        {
+                            done = true;
+                        }
+                    }
+                }
+            });
+
+        if changed {
+            ingest_state.items =
+                plan_ingest(&ingest_state.sources, &ingest_state.template, &project, now)
+                    .map_err(|e| e.to_string());
+        }
+        if done {
+            let items = ingest_state.items.clone().unwrap_or_default();
+            self.jobs()
+                .push(&trf("Ingest footage into {}", &[&project]), move |job| {
+                    ingest(&items, &dir, job)
+                });
+        }
+        self.footage_ingest = if open && !done {
+            Some(ingest_state)
+        } else {
+            None
+        };
+    }
+
+    /// Lists the DCC templates with their problems, and imports and restores templates.
+    fn templates_window(&mut self, ctx: &egui::Context) {
+        let mut state = match &self.templates_manager {
+            Some(t) => t.clone(),
+            None => return,
+        };
+        let templates_dir = self.config.templates_dir.clone();
+
+        let mut open = true;
+        let mut rescan = false;
+        egui::Window::new(tr("DCC templates"))
+            .id(egui::Id::new("templates_manager"))
+            .open(&mut open)
+            .resizable(true)
+            .default_width(600.)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(templates_dir.display().to_string());
+                    if ui.small_button("🔄").on_hover_text(tr("Refresh")).clicked() {
+                        rescan = true;
+                    }
+                    if ui
+                        .button(tr("Detect installed DCCs"))
+                        .on_hover_text(tr(
+                            "Looks for Maya, Houdini, Nuke, Blender and After Effects and sets the executable of the DCCs that don't have one.",
+                        ))
+                        .clicked()
+                    {
+                        match fill_executables(&templates_dir, &detect_executables()) {
+                            Ok(names) if names.is_empty() => self
+                                .notifications
+                                .push(Severity::Info, tr("No new DCC executables found")),
+                            Ok(names) => self.notifications.push(
+                                Severity::Info,
+                                trf("Set the executable of {}", &[&names.join(", ")]),
+                            ),
+                            Err(e) => self.notifications.push(
+                                Severity::Error,
+                                trf("Error detecting DCCs: {}", &[&e]),
+                            ),
+                        }
+                        rescan = true;
+                    }
+                });
+                ui.add_space(SPACING);
+
+                match &state.dirs {
+                    Err(e) => {
+                        ui.label(egui::RichText::new(e).color(Color32::RED));
+                    }
+                    Ok(dirs) => {
+                        egui::Grid::new("templates_grid")
+                            .striped(true)
+                            .show(ui, |ui| {
+                                for d in dirs {
+                                    ui.label(d.name());
+                                    ui.label(
+                                        d.dcc
+                                            .as_ref()
+                                            .map(|d| d.extension.clone())
+                                            .unwrap_or_default(),
+                                    );
+                                    match d.dcc.as_ref().and_then(|d| d.executable.as_ref()) {
+                                        Some(e) => ui
+                                            .label(
+                                                e.file_name()
+                                                    .unwrap_or_default()
+                                                    .to_string_lossy(),
+                                            )
+                                            .on_hover_text(e.display().to_string()),
+                                        None => ui
+                                            .weak(tr("Default app"))
+                                            .on_hover_text(tr("Opened with the app for the extension")),
+                                    };
+                                    if d.problems.is_empty() {
+                                        ui.label("✔");
+                                    } else {
+                                        ui.label(egui::RichText::new("⚠").color(Color32::RED));
+                                    }
+                                    let template = d.template_path();
+                                    if ui
+                                        .add_enabled(
+                                            template.is_some(),
+                                            egui::Button::new(tr("Open")),
+                                        )
+                                        .clicked()
+                                    {
+                                        if let Err(e) = open::that(template.unwrap_or_default()) {
+                                            self.notifications.push(
+                                                Severity::Error,
+                                                trf("Error opening file: {}", &[&e]),
+                                            );
+                                        }
+                                    }
+                                    if ui.button(tr("Folder")).clicked() {
+                                        open_folder(d.dir.clone());
+                                    }
+                                    ui.add_enabled_ui(!d.versions.is_empty(), |ui| {
+                                        ui.menu_button(
+                                            trf("Versions ({})", &[&d.versions.len()]),
+                                            |ui| {
+                                                for v in d.versions.iter().rev() {
+                                                    let name = v
+                                                        .file_name()
+                                                        .unwrap_or_default()
+                                                        .to_string_lossy();
+                                                    if ui
+                                                        .button(trf("Restore {}", &[&name]))
+                                                        .clicked()
+                                                    {
+                                                        match restore_template(&d.dir, v) {
+                                                            Ok(()) => self.notifications.push(
+                                                                Severity::Info,
+                                                                trf("Restored {}", &[&name]),
+                                                            ),
+                                                            Err(e) => self.notifications.push(
+                                                                Severity::Error,
+                                                                trf(
+                                                                    "Error restoring template: {}",
+                                                                    &[&e],
+                                                                ),
+                                                            ),
+                                                        }
+                                                        rescan = true;
+                                                        ui.close_menu();
+                                                    }
+                                                }
+                                            },
+                                        );
+                                    });
+                                    ui.end_row();
+                                }
+                            });
+                        for d in dirs {
+                            for problem in &d.problems {
+                                ui.label(
+                                    egui::RichText::new(format!("{}: {}", d.name(), problem))
+                                        .color(Color32::RED),
+                                );
+                            }
+                        }
+                    }
+                }
+
+                ui.separator();
+                ui.strong(tr("Import a template"));
+                let dropped = ui.input(|i| i.raw.dropped_files.iter().find_map(|f| f.path.clone()));
+                if let Some(path) = dropped {
+                    state.import_path = path.display().to_string();
+                }
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::TextEdit::singleline(&mut state.import_name)
+                            .hint_text(tr("DCC name, e.g. nuke"))
+                            .desired_width(TEXTEDIT_WIDTH),
+                    );
+                    ui.add(
+                        egui::TextEdit::singleline(&mut state.import_path)
+                            .hint_text(tr("Path to a template file, or drop it here"))
+                            .desired_width(250.),
+                    );
+                    let name = sanitize_string(state.import_name.trim().to_string());
+                    let path = PathBuf::from(state.import_path.trim());
+                    let valid = validate_name(&name).is_ok() && path.is_file();
+                    if ui
+                        .add_enabled(valid, egui::Button::new(tr("Import")))
+                        .clicked()
+                    {
+                        match import_template(&templates_dir, &name, &path) {
+                            Ok(dcc) => {
+                                self.notifications.push(
+                                    Severity::Info,
+                                    trf("Imported the {} template", &[&dcc.name]),
+                                );
+                                state.import_name.clear();
+                                state.import_path.clear();
+                            }
+                            Err(e) => self
+                                .notifications
+                                .push(Severity::Error, trf("Error importing template: {}", &[&e])),
+                        }
+                        rescan = true;
+                    }
+                });
+                ui.weak(tr(
+                    "Importing over an existing template keeps the old one as a version.",
+                ));
+            });
+
+        if rescan {
+            state.dirs = scan_templates(&templates_dir).map_err(|e| e.to_string());
+            self.refresh_dcc();
+        }
+        self.templates_manager = if open { Some(state) } else { None };
+    }
+
+    /// Scans the inbox every few seconds and adds new entries to the review queue.
+    fn poll_inbox(&mut self) {
+        let inbox = match &self.config.ingest.inbox {
+            Some(i) => to_local(i),
+            None => return,
+        };
+        let now = now_timestamp();
+        if now < self.inbox.scanned_at + INBOX_SCAN_INTERVAL {
+            return;
+        }
+        self.inbox.scanned_at = now;
+
+        let projects: Vec<String> = self
+            .projects
+            .iter()
+            .map(|p| p.name_sanitized.clone())
+            .collect();
+        let new = match self.inbox.watcher.poll(&inbox, &projects) {
+            Ok(n) => n,
+            Err(e) => {
+                error!("Failed to scan inbox {}: {}", inbox.display(), e);
+                return;
+            }
+        };
+        let current = self
+            .current_project
+            .as_ref()
+            .map(|p| p.name_sanitized.clone())
+            .unwrap_or_default();
+        for entry in self.inbox.watcher.pending() {
+            self.inbox
+                .choices
+                .entry(entry.path.clone())
+                .or_insert_with(|| (true, entry.project.clone().unwrap_or(current.clone())));
+        }
+        if new > 0 {
+            self.notifications
+                .push(Severity::Info, trf("{} new in the inbox", &[&new]));
+        }
+    }
+
+    /// Lists the entries of the inbox for review, and ingests the picked ones into their projects.
+    fn inbox_window(&mut self, ctx: &egui::Context) {
+        let pending = self.inbox.watcher.pending().to_vec();
+        let projects: Vec<String> = self
+            .projects
+            .iter()
+            .map(|p| p.name_sanitized.clone())
+            .collect();
+        let mut choices = self.inbox.choices.clone();
+
+        let mut open = true;
+        let mut dismissed = Vec::new();
+        let mut ingest_picked = false;
+        egui::Window::new(tr("Inbox"))
+            .id(egui::Id::new("inbox"))
+            .open(&mut open)
+            .resizable(true)
+            .default_width(600.)
+            .show(ctx, |ui| {
+                if pending.is_empty() {
+                    ui.label(tr("The inbox is empty."));
+                    return;
+                }
+                egui::ScrollArea::vertical()
+                    .max_height(300.)
+                    .show(ui, |ui| {
+                        egui::Grid::new("inbox_grid").striped(true).show(ui, |ui| {
+                            for entry in &pending {
+                                let (picked, project) = choices
+                                    .entry(entry.path.clone())
+                                    .or_insert((false, String::new()));
+                                let name = entry
+                                    .path
+                                    .file_name()
+                                    .unwrap_or_default()
+                                    .to_string_lossy()
+                                    .to_string();
+                                ui.checkbox(picked, name)
+                                    .on_hover_text(entry.path.display().to_string());
+                                ui.label(format_size(entry.size));
+                                egui::ComboBox::from_id_source(&entry.path)
+                                    .selected_text(project.as_str())
+                                    .show_ui(ui, |ui| {
+                                        for p in &projects {
+                                            ui.selectable_value(project, p.clone(), p);
+                                        }
+                                    });
+                                if ui.small_button(tr("Dismiss")).clicked() {
+                                    dismissed.push(entry.path.clone());
+                                }
+                                ui.end_row();
+                            }
+                        });
+                    });
+                ui.add_space(SPACING);
+                let any = pending.iter().any(|e| {
+                    choices
+                        .get(&e.path)
+                        .map_or(false, |(p, n)| *p && !n.is_empty())
+                });
+                if ui
+                    .add_enabled(any, egui::Button::new(tr("Ingest selected")))
+                    .clicked()
+                {
+                    ingest_picked = true;
+                }
+            });
+
+        for path in dismissed {
+            self.inbox.watcher.dismiss(&path);
+            choices.remove(&path);
+        }
+        if ingest_picked {
+            let mut by_project: Vec<(String, Vec<PathBuf>)> = Vec::new();
+            for entry in &pending {
+                match choices.get(&entry.path) {
+                    Some((true, project)) if !project.is_empty() => {
+                        match by_project.iter_mut().find(|(p, _e)| p == project) {
+                            Some((_p, paths)) => paths.push(entry.path.clone()),
+                            None => by_project.push((project.clone(), vec![entry.path.clone()])),
+                        }
+                    }
+                    _ => (),
+                }
+            }
+            for (project, paths) in by_project {
+                self.ingest_inbox_entries(&project, paths);
+            }
+        }
+        self.inbox.choices = choices;
+        self.inbox.show = open;
+    }
+
+    /// Ingests inbox entries into the project as a job, removing them from the inbox when done.
+    fn ingest_inbox_entries(&mut self, project_name: &str, paths: Vec<PathBuf>) {
+        let project_path = match (
+            self.projects
+                .iter()
+                .find(|p| p.name_sanitized == project_name),
+            &self.config.projects_dir,
+        ) {
+            (Some(p), Some(d)) => p.get_path(d),
+            _ => return,
+        };
+        let now = now_timestamp();
+        let items = match plan_ingest(&paths, &self.config.ingest.template, project_name, now) {
+            Ok(i) => i,
+            Err(e) => {
+                self.notifications
+                    .push(Severity::Error, trf("Error reading the inbox: {}", &[&e]));
+                return;
+            }
+        };
+        let dir = ingest_dir(&project_path, &self.config.ingest, now);
+
+        for path in &paths {
+            self.inbox.watcher.take(path);
+            self.inbox.choices.remove(path);
+        }
+        let job_paths = paths.clone();
+        let id = self.jobs().push(
+            &trf("Ingest footage into {}", &[&project_name]),
+            move |job| {
+                let message = ingest(&items, &dir, job)?;
+                for path in &job_paths {
+                    remove_entry(path)
+                        .map_err(|e| format!("Failed to remove {}: {}", path.display(), e))?;
+                }
+                Ok(message)
+            },
+        );
+        self.inbox.jobs.push((id, paths));
+    }
+
+    /// Runs the mirror as a background job and shows its output in the log window.
+    fn run_mirror(&mut self, profile: MirrorProfile) {
+        let log = MirrorLog::default();
+        let job_log = log.clone();
+        let job_profile = profile.clone();
+        self.jobs()
+            .push(&trf("Mirror {}", &[&profile.name]), move |job| {
+                job_profile.run(&job_log, job)
+            });
+        self.mirror_log = Some((profile, log));
+    }
+
+    fn mirror_log_window(&mut self, ctx: &egui::Context) {
+        let (profile, log) = match &self.mirror_log {
+            Some((p, l)) => (p.clone(), l.clone()),
+            None => return,
+        };
+        let lines = match log.lock() {
+            Ok(l) => l.clone(),
+            Err(_e) => Vec::new(),
+        };
+
+        let mut open = true;
+        egui::Window::new(trf("Mirror {}", &[&profile.name]))
+            .id(egui::Id::new("mirror_log"))
+            .open(&mut open)
+            .resizable(true)
+            .default_size([700., 400.])
+            .show(ctx, |ui| {
+                egui::ScrollArea::both()
+                    .stick_to_bottom(true)
+                    .auto_shrink([false, false])
+                    .show(ui, |ui| {
+                        for line in &lines {
+                            ui.monospace(line);
+                        }
+                    });
+            });
+        if !open {
+            self.mirror_log = None;
+        }
+    }
+
+    /// Lists the python scripts in the scripts dir, the list is read each time the menu opens.
+    #[cfg(feature = "python")]
+    fn scripts_menu(&mut self, ui: &mut egui::Ui) {
+        let scripts_dir = match &self.config.scripts_dir {
+            Some(d) if self.can_edit() => d.clone(),
+            _ => return,
+        };
+
+        ui.menu_button(tr("Scripts"), |ui| {
+            let scripts = match Script::find_scripts(&scripts_dir) {
+                Ok(s) => s,
+                Err(e) => {
+                    ui.label(trf("Error finding scripts: {}", &[&e]));
+                    return;
+                }
+            };
+            if scripts.is_empty() {
+                ui.label(tr("No scripts found."));
+            }
+
+            for script in scripts {
+                if ui.button(&script.name).clicked() {
+                    ui.close_menu();
+                    self.run_script(&script, ui);
+                }
+            }
+        });
+    }
+
+    #[cfg(feature = "python")]
+    fn run_script(&mut self, script: &Script, ui: &mut egui::Ui) {
+        let context = ScriptContext {
+            projects: self.projects.clone(),
+            project: self.current_project.clone(),
+            task: self.current_task.clone(),
+            files: self.files.as_deref().cloned().unwrap_or_default(),
+            pipeline: self.config.pipeline_context(),
+        };
+
+        match script.run(&context) {
+            Ok(()) => self
+                .notifications
+                .push(Severity::Info, trf("Script {} finished.", &[&script.name])),
+            Err(e) => self.notifications.push(Severity::Error, e),
+        }
+        self.refresh_tasks(ui);
+        self.refresh_task();
+    }
+
+    /// Show task tree
+    fn render_task_tree(&mut self, ui: &mut egui::Ui) {
+        let task = match &self.current_project_task_tree {
+            Some(t) => Rc::clone(t),
+            None => return,
+        };
+
+        ui.horizontal(|ui| {
+            ui.label(tr("Filter"));
+            ui.add(egui::TextEdit::singleline(&mut self.task_filter).desired_width(TEXTEDIT_WIDTH));
+            if !self.task_filter.is_empty() && ui.small_button("✖").clicked() {
+                self.task_filter = String::new();
+            }
+        });
+        ui.add_space(SPACING);
+
+        if self.can_edit() {
+            ui.horizontal(|ui| {
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::RIGHT), |ui| {
+                    let import_btn = ui.add(egui::Button::new(tr("Import")));
+                    let new_structure_btn = ui.add(egui::Button::new(tr("+ Structure")));
+                    let new_folder_btn = ui.add(egui::Button::new(tr("+ Folder")));
+                    let new_task_btn = ui.add(egui::Button::new(tr("+ Task")));
+                    ui.add_space(SPACING);
+
+                    if import_btn.clicked() {
+                        self.open_import_tasks((*task).clone());
+                    }
+                    if new_structure_btn.clicked() {
+                        self.open_create_structure((*task).clone());
+                    }
+                    if new_folder_btn.clicked() {
+                        self.new_folder_name = String::new();
+                        self.new_folder_parent = (*task).clone();
+                        self.open_create_folder();
+                    }
+                    if new_task_btn.clicked() {
+                        self.new_task_name = String::new();
+                        self.new_task_parent = (*task).clone();
+                        self.open_create_task();
+                    }
+                });
+            });
+        }
+
+        let filtered = match self.filter_task_tree(&task) {
+            Some(t) => t,
+            None => {
+                ui.label(tr("No matching tasks."));
+                return;
+            }
+        };
+        for c in &filtered.children {
+            self.tree_child(ui, c);
+        }
+        self.reveal_node = None;
+    }
+
+    /// The tree filtered by the filter text. It's only filtered again when the text or the tree changed.
+    fn filter_task_tree(&mut self, tree: &Rc<TaskTreeNode>) -> Option<Rc<TaskTreeNode>> {
+        if self.task_filter.is_empty() {
+            return Some(Rc::clone(tree));
+        }
+        if let Some((filter, source, filtered)) = &self.filtered_task_tree {
+            if *filter == self.task_filter && Rc::ptr_eq(source, tree) {
+                return filtered.clone();
+            }
+        }
+        let filtered = tree.filtered(&self.task_filter).map(Rc::new);
+        self.filtered_task_tree =
+            Some((self.task_filter.clone(), Rc::clone(tree), filtered.clone()));
+        filtered
+    }
+
+    /// Project ▸ folder ▸ … ▸ task crumbs of the current task. Clicking a folder reveals it in the tree,
+    /// clicking the project deselects the task.
+    fn breadcrumb_bar(&mut self, ui: &mut egui::Ui) {
+        let project = match &self.current_project {
+            Some(p) => p.name.clone(),
+            None => return,
+        };
+        let crumbs: Vec<(String, PathBuf, bool)> =
+            match (&self.current_project_task_tree, &self.current_task) {
+                (Some(tree), Some(task)) => tree
+                    .path_to(&task.path)
+                    .iter()
+                    .map(|n| (n.name.clone(), n.path.clone(), n.metadata.is_task))
+                    .collect(),
+                _ => Vec::new(),
+            };
+
+        ui.horizontal(|ui| {
+            let project_btn = ui.add(egui::Button::new(format!("📁 {}", project)).frame(false));
+            if project_btn.clicked() {
+                self.current_task = None;
+                self.files = None;
+                self.outputs = None;
+                self.update_ipc_context();
+            }
+            for (i, (name, path, is_task)) in crumbs.iter().enumerate() {
+                ui.label("▸");
+                let text = if i + 1 == crumbs.len() {
+                    egui::RichText::new(name).strong()
+                } else {
+                    egui::RichText::new(name)
+                };
+                if ui.add(egui::Button::new(text).frame(false)).clicked() {
+                    self.reveal_node = Some(path.clone());
+                    let node = match (is_task, &self.current_project_task_tree) {
+                        (true, Some(tree)) => tree.path_to(path).last().map(|n| (*n).clone()),
+                        _ => None,
+                    };
+                    if let Some(node) = node {
+                        self.set_current_task(node);
+                    }
+                }
+            }
+
+            if !self.sessions.is_empty() {
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    ui.label(format!("👥 {}", self.sessions.len()))
+                        .on_hover_ui(|ui| {
+                            ui.strong(tr("Also in this project"));
+                            for s in &self.sessions {
+                                let task = s.task.clone().unwrap_or_else(|| tr("No task"));
+                                ui.label(trf("{} on {}: {}", &[&s.user, &s.host, &task]));
+                            }
+                        });
+                });
+            }
+        });
+    }
+
+    /// Takes up the space of a task or closed folder row without laying it out if it's scrolled out of
+    /// view, so trees with thousands of tasks stay fast. Returns whether the row was skipped.
+    fn skip_hidden_row(&self, ui: &mut egui::Ui, path: &Path) -> bool {
+        if self.reveal_node.as_deref() == Some(path) {
+            return false;
+        }
+        let text_height = ui.text_style_height(&egui::TextStyle::Button);
+        let height =
+            (text_height + 2. * ui.spacing().button_padding.y).max(ui.spacing().interact_size.y);
+        let size = egui::vec2(ui.available_width(), height);
+        if ui.is_rect_visible(egui::Rect::from_min_size(ui.cursor().min, size)) {
+            return false;
+        }
+        ui.allocate_space(size);
+        true
+    }
+
+    fn tree_child(&mut self, ui: &mut egui::Ui, task: &TaskTreeNode) {
+        if !task.metadata.is_task {
+            // Folders are expanded while filtering, to show the matches, and to reveal a node.
+            let revealed = self
+                .reveal_node
+                .as_ref()
+                .map_or(false, |r| r.starts_with(&task.path));
+            let open = (!self.task_filter.is_empty() || revealed).then_some(true);
+            let id = ui.make_persistent_id(&task.path);
+            let closed = open.is_none()
+                && egui::collapsing_header::CollapsingState::load(ui.ctx(), id)
+                    .map_or(true, |s| s.openness(ui.ctx()) == 0.);
+            if closed && self.skip_hidden_row(ui, &task.path) {
+                return;
+            }
+            if self.is_renaming(&task.path) {
+                ui.horizontal(|ui| self.inline_rename_field(ui));
+                return;
+            }
+            let header = egui::CollapsingHeader::new(&task.name)
+                .id_source(&task.path)
+                .open(open)
                 .show(ui, |ui| {
+                    if self.can_edit() {
+                        ui.horizontal(|ui| {
+                            ui.with_layout(egui::Layout::right_to_left(egui::Align::RIGHT), |ui| {
+                                let new_structure_btn =
+                                    ui.add(egui::Button::new(tr("+ Structure")));
+                                let new_folder_btn = ui.add(egui::Button::new(tr("+ Folder")));
+                                let new_task_btn = ui.add(egui::Button::new(tr("+ Task")));
+                                ui.add_space(SPACING);
+
+                                if new_structure_btn.clicked() {
+                                    self.open_create_structure(task.clone());
+                                }
+                                if new_folder_btn.clicked() {
+                                    self.new_folder_name = String::new();
+                                    self.new_folder_parent = task.clone();
+                                    self.open_create_folder();
+                                }
+                                if new_task_btn.clicked() {
+                                    self.new_task_name = String::new();
+                                    self.new_task_parent = task.clone();
+                                    self.open_create_task();
+                                }
+                            });
+                        });
+                    }
+                    for c in &task.children {
+                        self.tree_child(ui, c);
+                    }
+                    ui.add_space(SPACING);
+                });
+            if self.reveal_node.as_ref() == Some(&task.path) {
+                header
+                    .header_response
+                    .scroll_to_me(Some(egui::Align::Center));
+            }
+            if header.header_response.double_clicked() && self.can_edit() {
+                self.start_inline_rename(RenameTarget::Node(task.clone()));
+            }
+            header.header_response.context_menu(|ui| {
+                self.copy_path_menu(ui, &task.path);
+                ui.set_enabled(self.can_edit());
+                if ui.button(tr("Rename")).clicked() {
+                    self.start_inline_rename(RenameTarget::Node(task.clone()));
+                    ui.close_menu();
+                }
+                // The tree isn't read below tasks, so folders with tasks inside would hide them.
+                let convert_btn = ui
+                    .add_enabled(!task.has_tasks(), egui::Button::new(tr("Convert to task")))
+                    .on_disabled_hover_text(tr("The folder contains tasks."));
+                if convert_btn.clicked() {
+                    self.convert_to_task(task, ui);
+                    ui.close_menu();
+                }
+                if ui.button(tr("Import tasks from CSV…")).clicked() {
+                    self.open_import_tasks(task.clone());
+                    ui.close_menu();
+                }
+                if ui.button(tr("Move to trash")).clicked() {
+                    self.move_to_trash(task, ui);
+                    ui.close_menu();
+                }
+                self.task_plugins_menu(ui, task);
+            });
+        } else {
+            ui.add_space(SPACING);
+            if self.skip_hidden_row(ui, &task.path) {
+                return;
+            }
+            ui.horizontal(|ui| {
+                if self.is_renaming(&task.path) {
+                    self.inline_rename_field(ui);
+                    return;
+                }
+                let task_label = ui.add(egui::Label::new(&task.name).sense(egui::Sense::click()));
+                if task_label.clicked() && self.preferences.task_click_loads_files {
+                    self.set_current_task(task.clone())
+                } else if task_label.clicked() {
+                    self.select_task(task.clone())
+                }
+                if task_label.double_clicked() && self.can_edit() {
+                    self.start_inline_rename(RenameTarget::Node(task.clone()));
+                }
+                if self.reveal_node.as_ref() == Some(&task.path) {
+                    task_label.scroll_to_me(Some(egui::Align::Center));
+                }
+                let users: Vec<&str> = self
+                    .sessions
+                    .iter()
+                    .filter(|s| s.task_path.as_ref() == Some(&task.path))
+                    .map(|s| s.user.as_str())
+                    .collect();
+                if !users.is_empty() {
+                    ui.label(egui::RichText::new(format!("👤 {}", users.join(", "))).weak())
+                        .on_hover_text(tr("Has the task open"));
+                }
+                task_label.context_menu(|ui| {
+                    self.copy_path_menu(ui, &task.path);
+                    if let Some(link) = self.task_link(task) {
+                        if ui.button(tr("Copy link")).on_hover_text(&link).clicked() {
+                            ui.output_mut(|o| o.copied_text = link.clone());
+                            self.notifications
+                                .push(Severity::Info, trf("Copied {}", &[&link]));
+                            ui.close_menu();
+                        }
+                    }
+                    ui.set_enabled(self.can_edit());
+                    if ui.button(tr("Rename")).clicked() {
+                        self.start_inline_rename(RenameTarget::Node(task.clone()));
+                        ui.close_menu();
+                    }
+                    if ui.button(tr("Move to trash")).clicked() {
+                        self.move_to_trash(task, ui);
+                        ui.close_menu();
+                    }
+                    self.task_plugins_menu(ui, task);
+                });
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::RIGHT), |ui| {
+                    let assets_btn = ui.add(egui::Button::new(tr("Assets")));
+                    let output_btn = ui.add(egui::Button::new(tr("Output")));
+                    let work_btn = ui.add(egui::Button::new(tr("Work")));
+                    ui.add_space(SPACING);
+
+                    if work_btn.clicked() {
+                        task.open_directory(task.metadata.work_dir_name.clone());
+                    }
+
+                    if output_btn.clicked() {
+                        task.open_directory(task.metadata.output_dir_name.clone());
+                    }
+
+                    if assets_btn.clicked() {
+                        task.open_directory(match &self.current_project {
+                            Some(p) => p.work_sub_dirs.get(2).cloned().unwrap_or_default(),
+                            None => String::new(),
+                        });
+                    }
+                });
+            });
+            ui.add_space(SPACING);
+        }
+    }
+
+    fn is_renaming(&self, path: &Path) -> bool {
+        self.inline_rename
+            .as_ref()
+            .map_or(false, |(t, _n)| t.path() == path)
+    }
+
+    fn start_inline_rename(&mut self, target: RenameTarget) {
+        let name = String::from(target.name());
+        self.inline_rename = Some((target, name));
+    }
+
+    /// The name being typed in place of a label, with the sanitized name or why it can't be used next to
+    /// it. Enter renames, Escape or clicking elsewhere cancels.
+    fn inline_rename_field(&mut self, ui: &mut egui::Ui) {
+        let (target, name) = match &mut self.inline_rename {
+            Some(r) => r,
+            None => return,
+        };
+        let response = ui.add(egui::TextEdit::singleline(name).desired_width(TEXTEDIT_WIDTH));
+        let sanitized = sanitize_string(name.clone());
+        let changed = sanitized != target.name();
+        let (preview, validation) = match &*target {
+            RenameTarget::Node(n) => {
+                let parent = n.path.parent().unwrap_or(Path::new(""));
+                (sanitized.clone(), validate_child_name(&sanitized, parent))
+            }
+            RenameTarget::File(f) => match f.renamed_path(&sanitized) {
+                Ok(p) if !sanitized.is_empty() => (
+                    p.file_name()
+                        .unwrap_or_default()
+                        .to_string_lossy()
+                        .to_string(),
+                    validate_file_path(&p),
+                ),
+                _ => (sanitized.clone(), Err(ValidationError::Empty)),
+            },
+        };
+        Self::name_preview_label(ui, &preview, &validation, changed);
+
+        // Focused when the rename starts, Enter with an invalid name keeps editing.
+        let enter = ui.input(|i| i.key_pressed(egui::Key::Enter));
+        if ui.input(|i| i.key_pressed(egui::Key::Escape)) {
+            self.inline_rename = None;
+        } else if response.lost_focus() && enter && changed && validation.is_err() {
+            response.request_focus();
+        } else if response.lost_focus() {
+            let target = target.clone();
+            self.inline_rename = None;
+            if enter && changed {
+                self.rename(&target, &sanitized, ui);
+            }
+        } else if !response.has_focus() {
+            response.request_focus();
+        }
+    }
+
+    /// Renames the folder, task or workfile, keeping the task open if it was in the renamed folder.
+    fn rename(&mut self, target: &RenameTarget, name: &str, ui: &mut egui::Ui) {
+        let result = match target {
+            RenameTarget::Node(n) => n.rename(name),
+            RenameTarget::File(f) => f.rename(name),
+        };
+        let path = match result {
+            Ok(p) => p,
+            Err(e) => {
+                error!("Error renaming {}: {}", target.path().display(), e);
+                self.notifications
+                    .push(Severity::Error, trf("Error renaming: {}", &[&e]));
+                return;
+            }
+        };
+        self.notifications.push(
+            Severity::Info,
+            trf("Renamed {} to {}", &[&target.name(), &name]),
+        );
+
+        match target {
+            RenameTarget::Node(n) => {
+                let current = self
+                    .current_task
+                    .as_ref()
+                    .and_then(|t| t.path.strip_prefix(&n.path).ok())
+                    .map(|rest| path.join(rest));
+                self.refresh_tasks(ui);
+                let task = match (&current, &self.current_project_task_tree) {
+                    (Some(p), Some(tree)) => tree.path_to(p).last().map(|t| (*t).clone()),
+                    _ => None,
+                };
+                if let Some(t) = task {
+                    self.set_current_task(t);
+                }
+            }
+            RenameTarget::File(_f) => self.refresh_files(),
+        }
+    }
+
+    /// Moves a task or folder into the project trash.
+    /// Adopts a folder created outside rclamp as a task and selects it.
+    fn convert_to_task(&mut self, folder: &TaskTreeNode, ui: &mut egui::Ui) {
+        let project = match &self.current_project {
+            Some(p) => p.clone(),
+            None => return,
+        };
+
+        match TaskTreeNode::convert_to_task(&folder.path, &project.task_sub_dirs()) {
+            Ok(()) => {
+                self.notifications.push(
+                    Severity::Info,
+                    trf("Converted {} to a task.", &[&folder.name]),
+                );
+                self.refresh_tasks(ui);
+                let mut task = folder.clone();
+                task.metadata.is_task = true;
+                task.children.clear();
+                self.set_current_task(task);
+            }
+            Err(e) => {
+                error!(
+                    "Error converting {} to a task: {}",
+                    folder.path.display(),
+                    e
+                );
+                self.notifications
+                    .push(Severity::Error, trf("Error converting to task: {}", &[&e]));
+            }
+        }
+    }
+
+    fn move_to_trash(&mut self, task: &TaskTreeNode, ui: &mut egui::Ui) {
+        let project_path = match (&self.current_project, &self.config.projects_dir) {
+            (Some(p), Some(d)) => p.get_path(d),
+            _ => return,
+        };
+
+        match task.move_to_trash(&project_path) {
+            Ok(_i) => {
+                self.notifications
+                    .push(Severity::Info, trf("Moved {} to trash.", &[&task.name]));
+            }
+            Err(e) => {
+                error!("Error moving to trash: {}", e);
+                self.notifications
+                    .push(Severity::Error, trf("Error moving to trash: {}", &[&e]));
+            }
+        }
+
+        if let Some(t) = &self.current_task {
+            if t.path.starts_with(&task.path) {
+                self.current_task = None;
+                self.files = None;
+            }
+        }
+        self.refresh_trash();
+        self.refresh_tasks(ui);
+    }
+
+    fn move_file_to_trash(&mut self, file: &File) {
+        let project_path = match (&self.current_project, &self.config.projects_dir) {
+            (Some(p), Some(d)) => p.get_path(d),
+            _ => return,
+        };
+        let name = file.path.file_name().unwrap_or_default().to_string_lossy();
+        match file.move_to_trash(&project_path) {
+            Ok(_i) => self
+                .notifications
+                .push(Severity::Info, trf("Moved {} to trash.", &[&name])),
+            Err(e) => {
+                error!("Error moving to trash: {}", e);
+                self.notifications
+                    .push(Severity::Error, trf("Error moving to trash: {}", &[&e]));
+            }
+        }
+        self.refresh_trash();
+        self.refresh_files();
+    }
+
+    /// False for viewers, who can't create, delete or version anything, and in projects where the
+    /// user isn't a member.
+    fn can_edit(&self) -> bool {
+        match &self.current_project {
+            Some(p) => self.can_edit_project(p),
+            None => self.config.role.can_edit(),
+        }
+    }
+
+    fn can_edit_project(&self, project: &Project) -> bool {
+        self.config.role.can_edit() && self.project_access(project) >= Access::Member
+    }
+
+    /// What the logged in user may do in the project by its access list.
+    fn project_access(&self, project: &Project) -> Access {
+        project_access(project.access.as_ref(), &current_user(), &self.user_groups)
+    }
+
+    /// Leaves out the projects the user has no access to.
+    fn listed_projects(&self, projects: Vec<Project>) -> Vec<Project> {
+        projects
+            .into_iter()
+            .filter(|p| self.project_access(p) != Access::None)
+            .collect()
+    }
+
+    /// The trash of the current project, if a project is open.
+    fn project_trash(&self) -> Option<Trash> {
+        match (&self.current_project, &self.config.projects_dir) {
+            (Some(p), Some(d)) => Some(Trash::new(&p.get_path(d))),
+            _ => None,
+        }
+    }
+
+    /// Re-reads the project and client trash.
+    fn refresh_trash(&mut self) {
+        self.trash_items = match self.project_trash().map(|t| t.list()) {
+            Some(Ok(i)) => i,
+            Some(Err(e)) => {
+                error!("Error reading trash: {}", e);
+                Vec::new()
+            }
+            None => Vec::new(),
+        };
+        self.client_trash_items = match Client::trash(&self.config.clients_path).list() {
+            Ok(i) => i,
+            Err(e) => {
+                error!("Error reading client trash: {}", e);
+                Vec::new()
+            }
+        };
+    }
+
+    /// Shows the project and client trash, with restore and purge actions.
+    /// UI scale and font settings, applied as they are changed.
+    fn preferences_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_preferences;
+        egui::Window::new(tr("Preferences"))
+            .open(&mut open)
+            .resizable(false)
+            .show(ctx, |ui| {
+                egui::Grid::new("preferences_grid")
+                    .num_columns(2)
+                    .spacing([SPACING * 2., SPACING])
+                    .show(ui, |ui| {
+                        ui.label(tr("Language"));
+                        let language = LANGUAGES
+                            .iter()
+                            .find(|(c, _)| *c == self.preferences.language)
+                            .map_or(self.preferences.language.as_str(), |(_, n)| n);
+                        egui::ComboBox::from_id_source("language_select")
+                            .selected_text(language)
+                            .show_ui(ui, |ui| {
+                                for (code, name) in LANGUAGES {
+                                    let selected = ui.selectable_value(
+                                        &mut self.preferences.language,
+                                        String::from(code),
+                                        name,
+                                    );
+                                    if selected.clicked() {
+                                        set_language(code);
+                                    }
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label(tr("UI scale"));
+                        ui.horizontal(|ui| {
+                            let mut system_scale = self.preferences.ui_scale.is_none();
+                            if ui
+                                .checkbox(&mut system_scale, tr("Monitor default"))
+                                .changed()
+                            {
+                                self.preferences.ui_scale = if system_scale {
+                                    None
+                                } else {
+                                    Some(ctx.pixels_per_point())
+                                };
+                                self.preferences.apply_scale(ctx);
+                            }
+                            if let Some(scale) = self.preferences.ui_scale.as_mut() {
+                                let slider = ui.add(
+                                    egui::Slider::new(scale, MIN_UI_SCALE..=MAX_UI_SCALE)
+                                        .step_by(0.05),
+                                );
+                                // Rescaling while dragging moves the slider under the pointer.
+                                if slider.drag_released() || slider.lost_focus() {
+                                    self.preferences.apply_scale(ctx);
+                                }
+                            }
+                        });
+                        ui.end_row();
+
+                        ui.label(tr("Font size"));
+                        let slider = ui.add(egui::Slider::new(
+                            &mut self.preferences.font_size,
+                            8.0..=32.0,
+                        ));
+                        if slider.changed() {
+                            self.preferences.apply_font_size(ctx);
+                        }
+                        ui.end_row();
+
+                        ui.label(tr("Custom font"));
+                        ui.horizontal(|ui| {
+                            ui.add(
+                                egui::TextEdit::singleline(&mut self.custom_font_path)
+                                    .hint_text(tr("Path to a .ttf or .otf file"))
+                                    .desired_width(250.),
+                            );
+                            if ui.button(tr("Load")).clicked() {
+                                self.set_custom_font(
+                                    ctx,
+                                    Some(PathBuf::from(&self.custom_font_path)),
+                                );
+                            }
+                            if self.preferences.custom_font.is_some()
+                                && ui.button(tr("Reset")).clicked()
+                            {
+                                self.custom_font_path = String::new();
+                                self.set_custom_font(ctx, None);
+                            }
+                        });
+                        ui.end_row();
+
+                        ui.label(tr("Double-clicking a file"));
+                        let label = |a: FileDoubleClick| match a {
+                            FileDoubleClick::Open => tr("Opens it"),
+                            FileDoubleClick::OpenWith => tr("Asks which app to open it with"),
+                            FileDoubleClick::Reveal => tr("Reveals it in Explorer"),
+                        };
+                        egui::ComboBox::from_id_source("file_double_click_select")
+                            .selected_text(label(self.preferences.file_double_click))
+                            .show_ui(ui, |ui| {
+                                for action in [
+                                    FileDoubleClick::Open,
+                                    FileDoubleClick::OpenWith,
+                                    FileDoubleClick::Reveal,
+                                ] {
+                                    ui.selectable_value(
+                                        &mut self.preferences.file_double_click,
+                                        action,
+                                        label(action),
+                                    );
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label(tr("Clicking a task"));
+                        ui.checkbox(
+                            &mut self.preferences.task_click_loads_files,
+                            tr("Lists its files"),
+                        )
+                        .on_hover_text(tr(
+                            "Turn off on slow network drives, the files are then listed with a button.",
+                        ));
+                        ui.end_row();
+                    });
+                if ui
+                    .checkbox(&mut self.config.theme.high_contrast, tr("High contrast"))
+                    .changed()
+                {
+                    self.preferences.high_contrast = Some(self.config.theme.high_contrast);
+                }
+                ui.add_space(SPACING);
+                if ui.button(tr("Restore defaults")).clicked() {
+                    self.preferences = Preferences::default();
+                    self.custom_font_path = String::new();
+                    if let Err(e) = self.preferences.apply(ctx) {
+                        self.notifications.push(Severity::Error, e);
+                    }
+                }
+
+                ui.separator();
+                ui.strong(tr("Move to another workstation"));
+                ui.label(tr("Pinned projects, recent tasks and preferences."));
+                ui.horizontal(|ui| {
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.state_path)
+                            .hint_text(tr("Path to a .yaml file"))
+                            .desired_width(250.),
+                    );
+                    let path = PathBuf::from(self.state_path.trim());
+                    let has_path = !self.state_path.trim().is_empty();
+                    if ui
+                        .add_enabled(has_path, egui::Button::new(tr("Export")))
+                        .clicked()
+                    {
+                        match self.ui_prefs().export(&path) {
+                            Ok(()) => self
+                                .notifications
+                                .push(Severity::Info, trf("Exported to {}", &[&path.display()])),
+                            Err(e) => self
+                                .notifications
+                                .push(Severity::Error, trf("Error exporting: {}", &[&e])),
+                        }
+                    }
+                    if ui
+                        .add_enabled(has_path, egui::Button::new(tr("Import")))
+                        .clicked()
+                    {
+                        match UiPrefs::import(&path) {
+                            Ok(prefs) => self.apply_ui_prefs(prefs, ctx),
+                            Err(e) => self
+                                .notifications
+                                .push(Severity::Error, trf("Error importing: {}", &[&e])),
+                        }
+                    }
+                });
+            });
+        self.show_preferences = open;
+    }
+
+    fn ui_prefs(&self) -> UiPrefs {
+        UiPrefs {
+            preferences: self.preferences.clone(),
+            layout: self.layout.clone(),
+            project_grouping: self.project_grouping,
+            project_sorting: self.project_sorting,
+            pinned_projects: self.pinned_projects.clone(),
+            recent_tasks: self.recent_tasks.clone(),
+            last_project: self
+                .current_project
+                .as_ref()
+                .zip(self.config.projects_dir.as_ref())
+                .map(|(p, d)| p.get_path(d)),
+            last_task: self.current_task.as_ref().map(|t| t.path.clone()),
+        }
+    }
+
+    fn apply_ui_prefs(&mut self, prefs: UiPrefs, ctx: &egui::Context) {
+        self.custom_font_path = prefs
+            .preferences
+            .custom_font
+            .as_ref()
+            .map(|p| p.display().to_string())
+            .unwrap_or_default();
+        self.preferences = prefs.preferences;
+        self.layout = prefs.layout;
+        self.project_grouping = prefs.project_grouping;
+        self.project_sorting = prefs.project_sorting;
+        self.pinned_projects = prefs.pinned_projects;
+        self.recent_tasks = prefs.recent_tasks;
+        self.project_groups = None;
+        self.apply_theme_preferences();
+        if let Err(e) = self.preferences.apply(ctx) {
+            self.notifications.push(Severity::Error, e);
+        }
+    }
+
+    /// Applies theme changes made in the app over the theme of the config.
+    fn apply_theme_preferences(&mut self) {
+        if let Some(dark_mode) = self.preferences.dark_mode {
+            self.config.dark_mode = dark_mode;
+        }
+        if let Some(high_contrast) = self.preferences.high_contrast {
+            self.config.theme.high_contrast = high_contrast;
+        }
+    }
+
+    fn set_custom_font(&mut self, ctx: &egui::Context, path: Option<PathBuf>) {
+        let previous = std::mem::replace(&mut self.preferences.custom_font, path);
+        if let Err(e) = self.preferences.apply_fonts(ctx) {
+            self.notifications.push(Severity::Error, e);
+            self.preferences.custom_font = previous;
+        }
+    }
+
+    fn tag_chip(ui: &mut egui::Ui, tag: &str) -> egui::Response {
+        let color = ui.visuals().selection.bg_fill;
+        egui::Frame::none()
+            .fill(color.linear_multiply(0.4))
+            .rounding(6.)
+            .inner_margin(egui::style::Margin::symmetric(4., 0.))
+            .show(ui, |ui| ui.label(egui::RichText::new(tag).small()))
+            .inner
+    }
+
+    /// Edits the notes and tags of a workfile, saved to the notes file of the task.
+    fn file_note_window(&mut self, ctx: &egui::Context) {
+        let task_path = match &self.current_task {
+            Some(t) => t.path.clone(),
+            None => {
+                self.edit_note = None;
+                return;
+            }
+        };
+        let (file, mut note, mut new_tag) = match self.edit_note.clone() {
+            Some(e) => e,
+            None => return,
+        };
+
+        let mut open = true;
+        let mut done = false;
+        egui::Window::new(trf("Notes: {}", &[&file.name]))
+            .id(egui::Id::new("file_note"))
+            .open(&mut open)
+            .show(ctx, |ui| {
+                ui.label(file.path.file_name().unwrap_or_default().to_string_lossy());
+                ui.add(egui::TextEdit::multiline(&mut note.notes).desired_rows(4));
+                ui.horizontal_wrapped(|ui| {
+                    let mut removed = None;
+                    for (i, tag) in note.tags.iter().enumerate() {
+                        if Self::tag_chip(ui, &format!("{} ✖", tag))
+                            .interact(egui::Sense::click())
+                            .on_hover_text(tr("Remove tag"))
+                            .clicked()
+                        {
+                            removed = Some(i);
+                        }
+                    }
+                    if let Some(i) = removed {
+                        note.tags.remove(i);
+                    }
+                });
+                ui.horizontal(|ui| {
+                    let tag_field = ui.add(
+                        egui::TextEdit::singleline(&mut new_tag)
+                            .hint_text(tr("approved, wip, client_v2…"))
+                            .desired_width(TEXTEDIT_WIDTH),
+                    );
+                    let entered =
+                        tag_field.lost_focus() && ui.input(|i| i.key_pressed(egui::Key::Enter));
+                    if ui.button(tr("Add tag")).clicked() || entered {
+                        note.add_tag(&new_tag);
+                        new_tag.clear();
+                    }
+                });
+                ui.add_space(SPACING);
+                if ui.button(tr("Save")).clicked() {
+                    let file_name = file.path.file_name().unwrap_or_default().to_string_lossy();
+                    self.file_notes.set(&file_name, note.clone());
+                    if let Err(e) = self.file_notes.save(&task_path) {
+                        self.notifications
+                            .push(Severity::Error, trf("Error saving notes: {}", &[&e]));
+                    }
+                    done = true;
+                }
+            });
+
+        if done {
+            self.refresh_file_notes();
+        }
+        self.edit_note = if open && !done {
+            Some((file, note, new_tag))
+        } else {
+            None
+        };
+    }
+
+    /// The brief of the current task: description, links and reference images, editable in place.
+    /// Files dropped on the window while editing are attached as references.
+    fn task_details_pane(&mut self, ui: &mut egui::Ui) {
+        let task = match &self.current_task {
+            Some(t) => t.clone(),
+            None => return,
+        };
+
+        egui::CollapsingHeader::new(tr("Details"))
+            .id_source("task_details")
+            .show(ui, |ui| match self.edit_task_details.clone() {
+                Some(edit) => self.task_details_editor(ui, &task, edit),
+                None => {
+                    let details = self.task_details.clone();
+                    if details == TaskDetails::default() {
+                        ui.weak(tr("No description."));
+                    }
+                    if !details.description.is_empty() {
+                        ui.label(&details.description);
+                    }
+                    if let Some(date) = &details.due_date {
+                        ui.label(trf("Due {}", &[date]));
+                    }
+                    for m in &details.milestones {
+                        let mark = if m.done { "✔" } else { "○" };
+                        ui.label(format!("{} {}  {}", mark, m.date, m.name));
+                    }
+                    for link in &details.links {
+                        ui.hyperlink(link);
+                    }
+                    ui.horizontal_wrapped(|ui| {
+                        for name in &details.references {
+                            let path = task.reference_path(name);
+                            if ui
+                                .link(name)
+                                .on_hover_text(path.display().to_string())
+                                .clicked()
+                            {
+                                if let Err(e) = open::that(&path) {
+                                    self.notifications.push(
+                                        Severity::Error,
+                                        trf("Error opening file: {}", &[&e]),
+                                    );
+                                }
+                            }
+                        }
+                    });
+                    if self.can_edit() && ui.button(tr("Edit")).clicked() {
+                        self.edit_task_details = Some(TaskDetailsEdit {
+                            due_date: details.due_date.clone().unwrap_or_default(),
+                            details,
+                            ..Default::default()
+                        });
+                    }
+                }
+            });
+    }
+
+    fn task_details_editor(
+        &mut self,
+        ui: &mut egui::Ui,
+        task: &TaskTreeNode,
+        mut edit: TaskDetailsEdit,
+    ) {
+        let mut done = false;
+        ui.add(
+            egui::TextEdit::multiline(&mut edit.details.description)
+                .hint_text(tr("Description"))
+                .desired_rows(4)
+                .desired_width(f32::INFINITY),
+        );
+
+        ui.add_space(SPACING);
+        ui.horizontal(|ui| {
+            ui.label(tr("Due date"));
+            ui.add(
+                egui::TextEdit::singleline(&mut edit.due_date)
+                    .hint_text("YYYY-MM-DD")
+                    .desired_width(TEXTEDIT_WIDTH),
+            );
+        });
+
+        ui.add_space(SPACING);
+        ui.strong(tr("Milestones"));
+        edit.details.milestones.retain_mut(|m| {
+            ui.horizontal(|ui| {
+                ui.checkbox(&mut m.done, "");
+                ui.add(
+                    egui::TextEdit::singleline(&mut m.name)
+                        .hint_text(tr("Name"))
+                        .desired_width(TEXTEDIT_WIDTH),
+                );
+                ui.add(
+                    egui::TextEdit::singleline(&mut m.date)
+                        .hint_text("YYYY-MM-DD")
+                        .desired_width(TEXTEDIT_WIDTH),
+                );
+                !ui.small_button("✖").on_hover_text(tr("Remove")).clicked()
+            })
+            .inner
+        });
+        if ui.button(tr("Add milestone")).clicked() {
+            edit.details.milestones.push(Milestone {
+                date: format_date(now_timestamp()),
+                ..Default::default()
+            });
+        }
+
+        ui.add_space(SPACING);
+        ui.strong(tr("Links"));
+        edit.details.links.retain(|link| {
+            ui.horizontal(|ui| {
+                ui.label(link);
+                !ui.small_button("✖").on_hover_text(tr("Remove")).clicked()
+            })
+            .inner
+        });
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut edit.new_link)
+                    .hint_text("https://")
+                    .desired_width(TEXTEDIT_WIDTH),
+            );
+            let link = edit.new_link.trim().to_string();
+            if ui
+                .add_enabled(!link.is_empty(), egui::Button::new(tr("Add")))
+                .clicked()
+            {
+                edit.details.links.push(link);
+                edit.new_link.clear();
+            }
+        });
+
+        ui.add_space(SPACING);
+        ui.strong(tr("References"));
+        edit.details.references.retain(|name| {
+            ui.horizontal(|ui| {
+                ui.label(name);
+                !ui.small_button("✖").on_hover_text(tr("Remove")).clicked()
+            })
+            .inner
+        });
+        let mut attach: Vec<PathBuf> = ui.input(|i| {
+            i.raw
+                .dropped_files
+                .iter()
+                .filter_map(|f| f.path.clone())
+                .collect()
+        });
+        ui.horizontal(|ui| {
+            ui.add(
+                egui::TextEdit::singleline(&mut edit.new_reference)
+                    .hint_text(tr("Path to an image, or drop files here"))
+                    .desired_width(TEXTEDIT_WIDTH),
+            );
+            let path = edit.new_reference.trim().to_string();
+            if ui
+                .add_enabled(!path.is_empty(), egui::Button::new(tr("Attach")))
+                .clicked()
+            {
+                attach.push(PathBuf::from(path));
+                edit.new_reference.clear();
+            }
+        });
+        for path in attach {
+            match task.attach_reference(&path) {
+                Ok(name) => edit.details.references.push(name),
+                Err(e) => self
+                    .notifications
+                    .push(Severity::Error, trf("Error attaching reference: {}", &[&e])),
+            }
+        }
+
+        let due_date = edit.due_date.trim().to_string();
+        let valid = (due_date.is_empty() || parse_date(&due_date).is_some())
+            && edit
+                .details
+                .milestones
+                .iter()
+                .all(|m| !m.name.trim().is_empty() && parse_date(&m.date).is_some());
+        edit.details.due_date = Some(due_date).filter(|d| !d.is_empty());
+
+        ui.add_space(SPACING);
+        if !valid {
+            ui.label(
+                egui::RichText::new(tr(
+                    "Dates must be valid and written as YYYY-MM-DD, and milestones need a name.",
+                ))
+                .color(Color32::RED),
+            );
+        }
+        ui.horizontal(|ui| {
+            if ui
+                .add_enabled(valid, egui::Button::new(tr("Save")))
+                .clicked()
+            {
+                match task.set_details(edit.details.clone()) {
+                    Ok(()) => done = true,
+                    Err(e) => self
+                        .notifications
+                        .push(Severity::Error, trf("Error saving task details: {}", &[&e])),
+                }
+            }
+            if ui.button(tr("Cancel")).clicked() {
+                done = true;
+            }
+        });
+
+        if done {
+            self.edit_task_details = None;
+            self.refresh_task_details();
+        } else {
+            self.edit_task_details = Some(edit);
+        }
+    }
+
+    /// Opens the file, or asks for confirmation first if there's a newer version.
+    fn open_file_checked(&mut self, file: &File, latest: Option<&File>) {
+        match latest {
+            Some(l) => self.stale_action = Some((StaleAction::Open, file.clone(), l.clone())),
+            None => self.open_file(file),
+        }
+    }
+
+    /// Confirms opening or versioning up an old version, since branching from one by mistake is easy.
+    fn stale_version_window(&mut self, ctx: &egui::Context) {
+        let (action, file, latest) = match self.stale_action.clone() {
+            Some(a) => a,
+            None => return,
+        };
+
+        let mut open = true;
+        let mut done = false;
+        egui::Window::new(tr("Old version"))
+            .id(egui::Id::new("stale_version"))
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.label(trf(
+                    "{} {} is not the latest version, {} is newer.",
+                    &[&file.name, &file.fmt_version(), &latest.fmt_version()],
+                ));
+                ui.add_space(SPACING);
+                ui.horizontal(|ui| {
+                    if ui
+                        .button(trf("Open {}", &[&latest.fmt_version()]))
+                        .clicked()
+                    {
+                        self.open_file(&latest);
+                        done = true;
+                    }
+                    match action {
+                        StaleAction::Open => {
+                            if ui.button(tr("Open anyway")).clicked() {
+                                self.open_file(&file);
+                                done = true;
+                            }
+                        }
+                        StaleAction::VersionUp => {
+                            let version = latest.version + 1;
+                            let branch = File {
+                                version,
+                                ..latest.clone()
+                            };
+                            let label = trf("Branch to {}", &[&branch.fmt_version()]);
+                            if ui.button(label).clicked() {
+                                if let Err(e) = file.copy_as_version(version) {
+                                    self.notifications.push(Severity::Error, e.to_string());
+                                }
+                                self.refresh_files();
+                                done = true;
+                            }
+                        }
+                    }
+                    if ui.button(tr("❌ Cancel")).clicked() {
+                        done = true;
+                    }
+                });
+            });
+
+        if !open || done {
+            self.stale_action = None;
+        }
+    }
+
+    /// Confirms opening a workfile saved with a newer app version, which the older one may not read or
+    /// would silently downgrade on save.
+    fn newer_app_version_window(&mut self, ctx: &egui::Context) {
+        let (file, saved_with, version) = match self.newer_app_version.clone() {
+            Some(n) => n,
+            None => return,
+        };
+
+        let mut open = true;
+        let mut done = false;
+        egui::Window::new(tr("Newer app version"))
+            .id(egui::Id::new("newer_app_version"))
+            .open(&mut open)
+            .collapsible(false)
+            .show(ctx, |ui| {
+                ui.label(trf(
+                    "{} {} was saved with version {}, the project uses {}.",
+                    &[&file.name, &file.fmt_version(), &saved_with, &version],
+                ));
+                ui.label(tr("Saving it with the older version may lose data."));
+                ui.add_space(SPACING);
+                ui.horizontal(|ui| {
+                    if ui.button(tr("Open anyway")).clicked() {
+                        self.launch_file(&file);
+                        done = true;
+                    }
+                    if ui.button(tr("❌ Cancel")).clicked() {
+                        done = true;
+                    }
+                });
+            });
+
+        if !open || done {
+            self.newer_app_version = None;
+        }
+    }
+
+    /// Offers the report of a crash in the last session, so it can be sent to whoever looks after the
+    /// pipeline.
+    fn crash_report_window(&mut self, ctx: &egui::Context) {
+        let (path, text) = match &self.crash_report {
+            Some((p, t)) => (p.clone(), t.clone()),
+            None => return,
+        };
+
+        let mut open = true;
+        let mut done = false;
+        egui::Window::new(tr("Rclamp closed unexpectedly"))
+            .id(egui::Id::new("crash_report"))
+            .open(&mut open)
+            .resizable(true)
+            .default_width(600.)
+            .show(ctx, |ui| {
+                ui.label(trf("A crash report was written to {}", &[&path.display()]));
+                ui.add_space(SPACING);
+                egui::ScrollArea::vertical()
+                    .max_height(300.)
+                    .show(ui, |ui| {
+                        ui.add(
+                            egui::Label::new(egui::RichText::new(text.as_str()).monospace())
+                                .wrap(false),
+                        );
+                    });
+                ui.add_space(SPACING);
+                ui.horizontal(|ui| {
+                    if ui.button(tr("Open report")).clicked() {
+                        if let Err(e) = open::that(&path) {
+                            error!("Failed to open {}: {}", path.display(), e);
+                            self.notifications
+                                .push(Severity::Error, trf("Failed to open {}", &[&e]));
+                        }
+                    }
+                    if ui.button(tr("Copy report")).clicked() {
+                        ui.output_mut(|o| o.copied_text = text.clone());
+                        self.notifications
+                            .push(Severity::Info, tr("Copied the crash report."));
+                    }
+                    if ui.button(tr("Open folder")).clicked() {
+                        if let Some(dir) = path.parent() {
+                            open_folder(dir.to_path_buf());
+                        }
+                    }
+                    if ui.button(tr("Close")).clicked() {
+                        done = true;
+                    }
+                });
+            });
+
+        if !open || done {
+            self.crash_report = None;
+        }
+    }
+
+    /// Lists what's wrong with the pipeline setup, so it can be fixed in one go.
+    fn setup_problems_window(&mut self, ctx: &egui::Context) {
+        let mut open = true;
+        let mut done = false;
+        egui::Window::new(tr("Pipeline setup problems"))
+            .id(egui::Id::new("setup_problems"))
+            .open(&mut open)
+            .resizable(true)
+            .default_width(500.)
+            .show(ctx, |ui| {
+                ui.label(tr(
+                    "Rclamp may not work as expected until these are fixed in the config or the templates dir:",
+                ));
+                ui.add_space(SPACING);
+                egui::ScrollArea::vertical()
+                    .max_height(300.)
+                    .show(ui, |ui| {
+                        for problem in &self.setup_problems {
+                            ui.label(problem.as_str());
+                        }
+                    });
+                ui.add_space(SPACING);
+                ui.horizontal(|ui| {
+                    if ui.button(tr("Copy")).clicked() {
+                        ui.output_mut(|o| o.copied_text = self.setup_problems.join("\n"));
+                    }
+                    if ui.button(tr("Close")).clicked() {
+                        done = true;
+                    }
+                });
+            });
+
+        if !open || done {
+            self.setup_problems.clear();
+        }
+    }
+
+    fn refresh_autosaves(&mut self) {
+        let files = self.files.clone().unwrap_or_default();
+        self.autosaves = Some(find_autosaves(&self.dcc, &files));
+    }
+
+    /// Lists the autosaves of the workfiles in the current task, to open one or recover it as the
+    /// next version.
+    fn autosaves_window(&mut self, ctx: &egui::Context) {
+        let autosaves = match self.autosaves.clone() {
+            Some(a) => a,
+            None => return,
+        };
+
+        let mut open = true;
+        let mut rescan = false;
+        egui::Window::new(tr("Recover autosaves"))
+            .id(egui::Id::new("autosaves"))
+            .open(&mut open)
+            .resizable(true)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(trf("{} autosaves", &[&autosaves.len()]));
+                    if ui.small_button("🔄").on_hover_text(tr("Refresh")).clicked() {
+                        rescan = true;
+                    }
+                });
+                ui.add_space(SPACING);
+                if autosaves.is_empty() {
+                    ui.weak(tr("No autosaves of the workfiles in this task."));
+                    return;
+                }
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    egui::Grid::new("autosaves_grid")
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for a in &autosaves {
+                                ui.label(a.path.file_name().unwrap_or_default().to_string_lossy())
+                                    .on_hover_text(a.path.display().to_string());
+                                ui.label(format_timestamp(a.modified));
+                                let next = File {
+                                    version: a.workfile.version + 1,
+                                    ..a.workfile.clone()
+                                };
+                                if ui
+                                    .button(trf("Recover as {}", &[&next.fmt_version()]))
+                                    .on_hover_text(trf(
+                                        "Copies the autosave as the next version of {}",
+                                        &[&a.workfile.name],
+                                    ))
+                                    .clicked()
+                                {
+                                    match a.adopt() {
+                                        Ok(p) => {
+                                            self.notifications.push(
+                                                Severity::Info,
+                                                trf("Recovered {}", &[&p.display()]),
+                                            );
+                                            self.refresh_files();
+                                            rescan = true;
+                                        }
+                                        Err(e) => self.notifications.push(
+                                            Severity::Error,
+                                            trf("Error recovering autosave: {}", &[&e]),
+                                        ),
+                                    }
+                                }
+                                if ui.button(tr("Folder")).clicked() {
+                                    open_folder(a.path.parent().unwrap_or(&a.path).to_path_buf());
+                                }
+                                ui.end_row();
+                            }
+                        });
+                });
+            });
+
+        if !open {
+            self.autosaves = None;
+        } else if rescan {
+            self.refresh_autosaves();
+        }
+    }
+
+    /// Asks for the name of a new variant of a workfile, which starts at v001 in the same task.
+    fn duplicate_file_window(&mut self, ctx: &egui::Context) {
+        let (project, task) = match (&self.current_project, &self.current_task) {
+            (Some(p), Some(t)) => (p.clone(), t.clone()),
+            _ => {
+                self.duplicate_file = None;
+                return;
+            }
+        };
+        let (file, mut name) = match self.duplicate_file.clone() {
+            Some(d) => d,
+            None => return,
+        };
+
+        let mut open = true;
+        let mut done = false;
+        egui::Window::new(trf("Duplicate {}", &[&file.name]))
+            .id(egui::Id::new("duplicate_file"))
+            .open(&mut open)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(tr("Variant name: "));
+                    let name_field =
+                        ui.add(egui::TextEdit::singleline(&mut name).desired_width(TEXTEDIT_WIDTH));
+                    let variant_name = sanitize_string(name.clone());
+                    let path = file.variant_path(
+                        &variant_name,
+                        &task,
+                        &project,
+                        &self.config.filename_template,
+                    );
+                    let validation = if variant_name.is_empty() {
+                        Err(ValidationError::Empty)
+                    } else {
+                        validate_file_path(&path)
+                    };
+                    let duplicate_btn =
+                        ui.add_enabled(validation.is_ok(), egui::Button::new(tr("Duplicate")));
+                    let preview = path.file_name().unwrap_or_default().to_string_lossy();
+                    Self::name_preview_label(ui, &preview, &validation, !name.is_empty());
+
+                    if validation.is_ok()
+                        && ((name_field.lost_focus()
+                            && ui.input(|i| i.key_pressed(egui::Key::Enter)))
+                            || duplicate_btn.clicked())
+                    {
+                        match file.duplicate_as(
+                            &variant_name,
+                            &task,
+                            &project,
+                            &self.config.filename_template,
+                        ) {
+                            Ok(_p) => done = true,
+                            Err(e) => self
+                                .notifications
+                                .push(Severity::Error, trf("Error duplicating file: {}", &[&e])),
+                        }
+                    }
+                });
+            });
+
+        if done {
+            self.refresh_files();
+        }
+        self.duplicate_file = if open && !done {
+            Some((file, name))
+        } else {
+            None
+        };
+    }
+
+    /// Picks the task to move or copy a workfile to, with a preview of the new filename.
+    fn file_transfer_window(&mut self, ctx: &egui::Context) {
+        let (project, tree) = match (&self.current_project, &self.current_project_task_tree) {
+            (Some(p), Some(t)) => (p.clone(), t.clone()),
+            _ => {
+                self.file_transfer = None;
+                return;
+            }
+        };
+        let mut transfer = match self.file_transfer.clone() {
+            Some(t) => t,
+            None => return,
+        };
+
+        let title = if transfer.keep_source {
+            trf("Copy {} to task", &[&transfer.file.name])
+        } else {
+            trf("Move {} to task", &[&transfer.file.name])
+        };
+        let mut open = true;
+        let mut done = false;
+        egui::Window::new(title)
+            .id(egui::Id::new("file_transfer"))
+            .open(&mut open)
+            .resizable(true)
+            .default_width(400.)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(tr("Filter"));
+                    ui.add(
+                        egui::TextEdit::singleline(&mut transfer.filter)
+                            .desired_width(TEXTEDIT_WIDTH),
+                    );
+                });
+                let tasks: Vec<TaskTreeNode> = tree.tasks().into_iter().cloned().collect();
+                let relative = |t: &TaskTreeNode| {
+                    let path = t.path.strip_prefix(&tree.path).unwrap_or(&t.path);
+                    path.display().to_string().replace('\\', "/")
+                };
+                let tasks = fuzzy_filter(&tasks, &transfer.filter, |t| {
+                    t.path
+                        .strip_prefix(&tree.path)
+                        .unwrap_or(&t.path)
+                        .to_str()
+                        .unwrap_or("")
+                });
+                egui::ScrollArea::vertical()
+                    .max_height(250.)
+                    .show(ui, |ui| {
+                        if tasks.is_empty() {
+                            ui.label(tr("No matching tasks."));
+                        }
+                        for t in &tasks {
+                            let selected =
+                                transfer.target.as_ref().map(|s| &s.path) == Some(&t.path);
+                            if ui.selectable_label(selected, relative(t)).clicked() {
+                                transfer.target = Some(t.clone());
+                            }
+                        }
+                    });
+                ui.add(egui::Separator::default());
+
+                ui.horizontal(|ui| {
+                    ui.label(tr("Version"));
+                    ui.radio_value(
+                        &mut transfer.version,
+                        VersionTransfer::Reset,
+                        tr("Start at v001"),
+                    );
+                    ui.radio_value(
+                        &mut transfer.version,
+                        VersionTransfer::Continue,
+                        trf("Keep {}", &[&transfer.file.fmt_version()]),
+                    );
+                });
+
+                let target = match &transfer.target {
+                    Some(t) => t.clone(),
+                    None => return,
+                };
+                let path = transfer.file.transfer_path(
+                    &target,
+                    &project,
+                    &self.config.filename_template,
+                    transfer.version,
+                );
+                ui.label(path.file_name().unwrap_or_default().to_string_lossy());
+                let label = if transfer.keep_source {
+                    tr("Copy")
+                } else {
+                    tr("Move")
+                };
+                if ui
+                    .add_enabled(path != transfer.file.path, egui::Button::new(label))
+                    .clicked()
+                {
+                    match transfer.file.transfer(
+                        &target,
+                        &project,
+                        &self.config.filename_template,
+                        transfer.version,
+                        transfer.keep_source,
+                    ) {
+                        Ok(p) => {
+                            let message = if transfer.keep_source {
+                                "Copied {} to {}."
+                            } else {
+                                "Moved {} to {}."
+                            };
+                            self.notifications.push(
+                                Severity::Info,
+                                trf(message, &[&transfer.file.name, &relative(&target)]),
+                            );
+                            info!("Transferred workfile to {}", p.display());
+                            done = true;
+                        }
+                        Err(e) => self
+                            .notifications
+                            .push(Severity::Error, trf("Error transferring file: {}", &[&e])),
+                    }
+                }
+            });
+
+        if done {
+            self.refresh_files();
+        }
+        self.file_transfer = if open && !done { Some(transfer) } else { None };
+    }
+
+    /// Picks a folder without a project file and maps its subfolders onto the pipeline roles.
+    fn adopt_project_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_adopt_project;
+        egui::Window::new(tr("Adopt project"))
+            .open(&mut open)
+            .resizable(true)
+            .default_width(450.)
+            .show(ctx, |ui| {
+                if self.adoption_candidates.is_empty() {
+                    ui.label(tr("All folders in the projects dir are projects."));
+                    return;
+                }
+
+                let selected = self
+                    .adoption
+                    .as_ref()
+                    .and_then(|a| a.path.file_name())
+                    .map(|n| n.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                let mut picked = None;
+                ui.horizontal(|ui| {
+                    ui.label(tr("Folder: "));
+                    egui::ComboBox::from_id_source("adopt_folder")
+                        .selected_text(selected)
+                        .width(250.)
+                        .show_ui(ui, |ui| {
+                            for c in &self.adoption_candidates {
+                                let name = c.file_name().unwrap_or_default().to_string_lossy();
+                                if ui.selectable_label(false, name).clicked() {
+                                    picked = Some(c.clone());
+                                }
+                            }
+                        });
+                });
+                if let Some(path) = picked {
+                    match ProjectAdoption::new(path, &self.config.template_project) {
+                        Ok(a) => self.adoption = Some(a),
+                        Err(e) => self
+                            .notifications
+                            .push(Severity::Error, trf("Error reading folder: {}", &[&e])),
+                    }
+                }
+
+                let adoption = match &mut self.adoption {
+                    Some(a) => a,
+                    None => return,
+                };
+                ui.add(egui::Separator::default());
+                egui::Grid::new("adopt_roles").show(ui, |ui| {
+                    for (id, role, dir_name) in [
+                        (
+                            "adopt_pipeline",
+                            tr("Pipeline"),
+                            &mut adoption.pipeline_dir_name,
+                        ),
+                        ("adopt_work", tr("Work"), &mut adoption.work_dir_name),
+                        (
+                            "adopt_dailies",
+                            tr("Dailies"),
+                            &mut adoption.dailies_dir_name,
+                        ),
+                        (
+                            "adopt_deliveries",
+                            tr("Deliveries"),
+                            &mut adoption.deliveries_dir_name,
+                        ),
+                    ] {
+                        ui.label(role);
+                        ui.add(egui::TextEdit::singleline(dir_name).desired_width(TEXTEDIT_WIDTH));
+                        egui::ComboBox::from_id_source(id)
+                            .selected_text("…")
+                            .show_ui(ui, |ui| {
+                                for s in &adoption.subfolders {
+                                    ui.selectable_value(dir_name, s.clone(), s);
+                                }
+                            });
+                        if !adoption.subfolders.contains(dir_name) {
+                            ui.label(tr("New folder"));
+                        }
+                        ui.end_row();
+                    }
+                });
+
+                ui.add_space(SPACING);
+                let valid = [
+                    &adoption.pipeline_dir_name,
+                    &adoption.work_dir_name,
+                    &adoption.dailies_dir_name,
+                    &adoption.deliveries_dir_name,
+                ]
+                .iter()
+                .all(|d| validate_name(d).is_ok());
+                if ui
+                    .add_enabled(valid, egui::Button::new(tr("Adopt")))
+                    .clicked()
+                {
+                    match adoption.adopt(&self.config.template_project) {
+                        Ok(p) => {
+                            self.notifications
+                                .push(Severity::Info, trf("Adopted project {}.", &[&p.name]));
+                            self.adoption_candidates.retain(|c| *c != adoption.path);
+                            self.adoption = None;
+                            self.refresh_projects();
+                        }
+                        Err(e) => self
+                            .notifications
+                            .push(Severity::Error, trf("Error adopting project: {}", &[&e])),
+                    }
+                }
+            });
+        self.show_adopt_project = open;
+    }
+
+    /// Uploads the project to the cloud remote as a background job.
+    fn push_project(&mut self, project: &Project) {
+        let (sync, projects_dir) = match (&self.config.cloud_sync, &self.config.projects_dir) {
+            (Some(s), Some(d)) => (s.clone(), d.clone()),
+            _ => return,
+        };
+        let project_path = project.get_path(&projects_dir);
+        self.jobs()
+            .push(&trf("Push {} to cloud", &[&project.name]), move |job| {
+                sync.push(&project_path, job)
+            });
+    }
+
+    /// Archives the project dir as a background job.
+    fn archive_project(&mut self, project: &Project) {
+        let projects_dir = match &self.config.projects_dir {
+            Some(d) => d.clone(),
+            None => return,
+        };
+        let project_path = project.get_path(&projects_dir);
+        let archive = self.config.archive.clone();
+        self.jobs()
+            .push(&trf("Archive {}", &[&project.name]), move |job| {
+                let files = archive.create(&project_path, job)?;
+                info!(
+                    "Archived {} in {} files",
+                    project_path.display(),
+                    files.len()
+                );
+                Ok(trf(
+                    "Created {}",
+                    &[&archive.archive_path(&project_path).display()],
+                ))
+            });
+    }
+
+    fn open_restore_project(&mut self) {
+        let sync = match &self.config.cloud_sync {
+            Some(s) => s,
+            None => return,
+        };
+        match sync.list_remote() {
+            Ok(p) => self.remote_projects = Some(p),
+            Err(e) => self.notifications.push(
+                Severity::Error,
+                trf("Error listing cloud projects: {}", &[&e]),
+            ),
+        }
+    }
+
+    /// Lists the projects on the cloud remote, restoring one is a background job.
+    fn restore_project_window(&mut self, ctx: &egui::Context) {
+        let remote_projects = match &self.remote_projects {
+            Some(p) => p.clone(),
+            None => return,
+        };
+        let (sync, projects_dir) = match (&self.config.cloud_sync, &self.config.projects_dir) {
+            (Some(s), Some(d)) => (s.clone(), d.clone()),
+            _ => return,
+        };
+
+        let mut open = true;
+        let mut restore = None;
+        egui::Window::new(tr("Restore from cloud"))
+            .id(egui::Id::new("restore_project"))
+            .open(&mut open)
+            .show(ctx, |ui| {
+                if remote_projects.is_empty() {
+                    ui.label(tr("No projects found."));
+                }
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for name in &remote_projects {
+                        ui.horizontal(|ui| {
+                            let exists = projects_dir.join(name).exists();
+                            let restore_btn = ui
+                                .add_enabled(!exists, egui::Button::new(tr("Restore")))
+                                .on_disabled_hover_text(tr("The project exists locally."));
+                            if restore_btn.clicked() {
+                                restore = Some(name.clone());
+                            }
+                            ui.label(name);
+                        });
+                    }
+                });
+            });
+
+        if let Some(name) = restore {
+            self.jobs()
+                .push(&trf("Restore {} from cloud", &[&name]), move |job| {
+                    sync.restore(&name, &projects_dir, job)
+                });
+            open = false;
+        }
+        if !open {
+            self.remote_projects = None;
+        }
+    }
+
+    fn run_health_check(&mut self, project: &Project) {
+        let projects_dir = match &self.config.projects_dir {
+            Some(d) => d.clone(),
+            None => return,
+        };
+        match check_project(project, &projects_dir) {
+            Ok(issues) => self.health_check = Some((project.clone(), issues)),
+            Err(e) => self.notifications.push(
+                Severity::Error,
+                trf("Error checking {}: {}", &[&project.name, &e]),
+            ),
+        }
+    }
+
+    /// Lists the issues of the health check with a fix for each, the task tree is refreshed after fixes.
+    fn health_window(&mut self, ctx: &egui::Context) {
+        let (project, issues) = match &self.health_check {
+            Some((p, i)) => (p.clone(), i.clone()),
+            None => return,
+        };
+        let projects_dir = self.config.projects_dir.clone().unwrap_or_default();
+        let project_path = project.get_path(&projects_dir);
+        let editable = self.can_edit_project(&project);
+
+        let mut open = true;
+        egui::Window::new(trf("Project health check: {}", &[&project.name]))
+            .id(egui::Id::new("health_check"))
+            .open(&mut open)
+            .resizable(true)
+            .default_width(600.)
+            .show(ctx, |ui| {
+                if issues.is_empty() {
+                    ui.label(tr("No issues found."));
+                    return;
+                }
+
+                let mut fixes: Vec<HealthIssue> = Vec::new();
+                ui.horizontal(|ui| {
+                    let missing: Vec<HealthIssue> = issues
+                        .iter()
+                        .filter(|i| matches!(i, HealthIssue::MissingDir(_)))
+                        .cloned()
+                        .collect();
+                    if ui
+                        .add_enabled(
+                            editable && !missing.is_empty(),
+                            egui::Button::new(tr("Create missing folders")),
+                        )
+                        .clicked()
+                    {
+                        fixes = missing;
+                    }
+                    let fixable = issues.iter().filter(|i| i.is_fixable()).count();
+                    if ui
+                        .add_enabled(editable && fixable > 0, egui::Button::new(tr("Fix all")))
+                        .clicked()
+                    {
+                        fixes = issues.iter().filter(|i| i.is_fixable()).cloned().collect();
+                    }
+                });
+                ui.add(egui::Separator::default());
+
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    egui::Grid::new("health_issues")
+                        .striped(true)
+                        .show(ui, |ui| {
+                            for issue in &issues {
+                                let path = issue.path();
+                                let relative = path.strip_prefix(&project_path).unwrap_or(path);
+                                match issue {
+                                    HealthIssue::MissingDir(_) => {
+                                        ui.label(tr("Missing folder"));
+                                        ui.label(relative.display().to_string());
+                                        if ui
+                                            .add_enabled(editable, egui::Button::new(tr("Create")))
+                                            .clicked()
+                                        {
+                                            fixes.push(issue.clone());
+                                        }
+                                    }
+                                    HealthIssue::NotATask(_) => {
+                                        ui.label(tr("Folder without task file"));
+                                        ui.label(relative.display().to_string());
+                                        if ui
+                                            .add_enabled(
+                                                editable,
+                                                egui::Button::new(tr("Convert to task")),
+                                            )
+                                            .clicked()
+                                        {
+                                            fixes.push(issue.clone());
+                                        }
+                                    }
+                                    HealthIssue::StrayFile(_) => {
+                                        ui.label(tr("File outside of a task"));
+                                        ui.label(relative.display().to_string());
+                                        if ui.button(tr("Reveal in Explorer")).clicked() {
+                                            open_folder(
+                                                path.parent().unwrap_or(path).to_path_buf(),
+                                            );
+                                        }
+                                    }
+                                }
+                                ui.end_row();
+                            }
+                        });
+                });
+
+                if fixes.is_empty() {
+                    return;
+                }
+                let failed = fixes
+                    .iter()
+                    .filter(|i| match i.fix(&project.task_sub_dirs()) {
+                        Ok(()) => false,
+                        Err(e) => {
+                            error!("Failed to fix {}: {}", i.path().display(), e);
+                            true
+                        }
+                    })
+                    .count();
+                if failed > 0 {
+                    self.notifications.push(
+                        Severity::Error,
+                        trf("Failed to fix {} of {} issues.", &[&failed, &fixes.len()]),
+                    );
+                }
+                self.run_health_check(&project);
+                if self.current_project.as_ref() == Some(&project) {
+                    self.refresh_tasks(ui);
+                }
+            });
+
+        if !open {
+            self.health_check = None;
+        }
+    }
+
+    fn trash_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.show_trash;
+        egui::Window::new(tr("Trash"))
+            .open(&mut open)
+            .resizable(true)
+            .default_width(500.)
+            .show(ctx, |ui| {
+                if let Some(trash) = self.project_trash() {
                     ui.horizontal(|ui| {
-                        ui.with_layout(egui::Layout::right_to_left(egui::Align::RIGHT), |ui| {
-                            let new_folder_btn = ui.add(egui::Button::new("+ Folder"));
-                            let new_task_btn = ui.add(egui::Button::new("+ Task"));
-                            ui.add_space(SPACING);
-
-                            if new_folder_btn.clicked() {
-                                self.message = None;
-                                self.new_folder_name = String::new();
-                                self.new_folder_parent = task.clone();
-                                self.open_create_folder();
+                        ui.strong(tr("Project"));
+                        let empty_btn = ui.add_enabled(
+                            !self.trash_items.is_empty() && self.can_edit(),
+                            egui::Button::new(tr("Empty trash")),
+                        );
+                        if empty_btn.clicked() {
+                            for i in &self.trash_items {
+                                if let Err(e) = trash.purge(i) {
+                                    error!("Error purging {}: {}", i.name, e);
+                                }
+                            }
+                            self.refresh_trash();
+                        }
+                    });
+                    let items = self.trash_items.clone();
+                    self.trash_items_list(ui, &trash, &items);
+                    ui.add(egui::Separator::default());
+                }
+
+                ui.strong(tr("Clients"));
+                let trash = Client::trash(&self.config.clients_path);
+                let items = self.client_trash_items.clone();
+                self.trash_items_list(ui, &trash, &items);
+            });
+        self.show_trash = open;
+    }
+
+    fn trash_items_list(&mut self, ui: &mut egui::Ui, trash: &Trash, items: &[TrashItem]) {
+        if items.is_empty() {
+            ui.label(tr("Trash is empty."));
+            return;
+        }
+
+        egui::Grid::new(trash.path.clone())
+            .striped(true)
+            .show(ui, |ui| {
+                for i in items {
+                    ui.label(&i.name)
+                        .on_hover_text(i.original_path.display().to_string());
+                    ui.label(format!("{:?}", i.kind));
+                    ui.label(format_timestamp(i.trashed_at));
+
+                    let editable = self.can_edit();
+                    if ui
+                        .add_enabled(editable, egui::Button::new(tr("Restore")))
+                        .clicked()
+                    {
+                        let result = match i.kind {
+                            TrashKind::Client => {
+                                Client::restore_client(i, &self.config.clients_path)
+                            }
+                            _ => trash.restore(i).map_err(|e| e.to_string()),
+                        };
+                        match result {
+                            Ok(()) => self
+                                .notifications
+                                .push(Severity::Info, trf("Restored {}.", &[&i.name])),
+                            Err(e) => self.notifications.push(
+                                Severity::Error,
+                                trf("Error restoring {}: {}", &[&i.name, &e]),
+                            ),
+                        }
+                        if i.kind == TrashKind::Client {
+                            self.clients = Client::get_clients(self.config.clients_path.clone())
+                                .unwrap_or_default();
+                        } else {
+                            self.refresh_tasks(ui);
+                            self.refresh_task();
+                        }
+                        self.refresh_trash();
+                    }
+                    if ui
+                        .add_enabled(editable, egui::Button::new(tr("Purge")))
+                        .clicked()
+                    {
+                        if let Err(e) = trash.purge(i) {
+                            self.notifications
+                                .push(Severity::Error, trf("Error purging {}: {}", &[&i.name, &e]));
+                        }
+                        self.refresh_trash();
+                    }
+                    ui.end_row();
+                }
+            });
+    }
+
+    /// If open_project() encounters an error when creating the task tree, this will render the error instead.
+    fn render_task_tree_error(&mut self, ui: &mut egui::Ui, error: io::Error) {
+        ui.label(error.to_string());
+    }
+
+    fn files_table(&mut self, ui: &mut egui::Ui) {
+        use egui_extras::{Column, TableBuilder};
+
+        let all_files = match &self.files {
+            Some(v) => Rc::clone(v),
+            None => return,
+        };
+        let groups = Rc::clone(&self.file_groups);
+        let file_name = |f: &File| {
+            f.path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string()
+        };
+        let files: Vec<&File> = match &self.file_tag_filter {
+            Some(tag) => all_files
+                .iter()
+                .filter(|f| self.file_notes.has_tag(&file_name(f), tag))
+                .collect(),
+            None => all_files.iter().collect(),
+        };
+        let newer = |f: &File| groups.iter().find_map(|g| g.newer_than(f)).cloned();
+        let columns = self.config.file_columns.clone();
+
+        let mut table = TableBuilder::new(ui)
+            .striped(true)
+            .resizable(true)
+            .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+            .column(Column::initial(250.0))
+            .column(Column::initial(100.0));
+        // The last column takes the remaining width.
+        if !columns.is_empty() {
+            table = table
+                .column(Column::initial(100.0))
+                .columns(Column::initial(120.0), columns.len() - 1);
+        }
+        table
+            .column(Column::remainder())
+            .min_scrolled_height(0.0)
+            .header(20., |mut header| {
+                header.col(|ui| {
+                    ui.strong(tr("Name"));
+                });
+                header.col(|ui| {
+                    ui.strong(tr("Version"));
+                });
+                header.col(|ui| {
+                    ui.strong(tr("Extension"));
+                });
+                for column in &columns {
+                    header.col(|ui| {
+                        ui.strong(column.title());
+                    });
+                }
+            })
+            .body(|body| {
+                // Only the visible rows are laid out, tasks can have thousands of files.
+                body.rows(20., files.len(), |i, mut row| {
+                    let f = files[i];
+                    let latest = newer(f);
+                    let note = self
+                        .file_notes
+                        .get(&file_name(f))
+                        .cloned()
+                        .unwrap_or_default();
+                    let publish = self.publishes.iter().find(|p| p.path == f.path).cloned();
+                    let frozen = self
+                        .publishes
+                        .iter()
+                        .any(|p| p.path == f.path && p.frozen.is_some());
+                    row.col(|ui| {
+                        if self.is_renaming(&f.path) {
+                            self.inline_rename_field(ui);
+                            return;
+                        }
+                        let mut filename_label =
+                            ui.add(egui::Label::new(&f.name).sense(egui::Sense::click()));
+                        if !note.notes.is_empty() {
+                            filename_label = filename_label.on_hover_text(&note.notes);
+                        }
+                        for tag in &note.tags {
+                            Self::tag_chip(ui, tag);
+                        }
+                        if let Some(p) = &publish {
+                            Self::review_badge(ui, p.state());
+                        }
+                        if frozen {
+                            ui.label("🔒").on_hover_text(tr("Frozen"));
+                        }
+                        // Double-clicking opens the file, with Ctrl or Cmd held it's renamed.
+                        let renaming = ui.input(|i| i.modifiers.command);
+                        if filename_label.double_clicked() && renaming && self.can_edit() {
+                            self.start_inline_rename(RenameTarget::File(f.clone()));
+                        } else if filename_label.double_clicked() && !renaming {
+                            self.file_double_clicked(f, latest.as_ref());
+                        }
+                        filename_label.context_menu(|ui| {
+                            let open_btn = ui.button(tr("Open"));
+                            if ui.button(tr("Open with…")).clicked() {
+                                self.open_with = Some(f.clone());
+                                ui.close_menu();
+                            }
+                            if let Some(l) = &latest {
+                                let open_latest_btn =
+                                    ui.button(trf("Open latest ({})", &[&l.fmt_version()]));
+                                if open_latest_btn.clicked() {
+                                    self.open_file(l);
+                                    ui.close_menu();
+                                }
                             }
-                            if new_task_btn.clicked() {
-                                self.message = None;
-                                self.new_task_name = String::new();
-                                self.new_task_parent = task.clone();
-                                self.open_create_task();
+                            let reveal_btn = ui.button(tr("Reveal in Explorer"));
+                            self.copy_path_menu(ui, &f.path);
+                            let versions: Vec<File> = groups
+                                .iter()
+                                .find(|g| g.files.contains(f))
+                                .map(|g| g.files.iter().filter(|o| *o != f).cloned().collect())
+                                .unwrap_or_default();
+                            ui.add_enabled_ui(!versions.is_empty(), |ui| {
+                                ui.menu_button(tr("Compare with"), |ui| {
+                                    for other in &versions {
+                                        if ui.button(file_name(other)).clicked() {
+                                            self.compare_versions(f, other);
+                                            ui.close_menu();
+                                        }
+                                    }
+                                });
+                            });
+                            ui.set_enabled(self.can_edit());
+                            let new_version_btn = ui
+                                .add_enabled(!frozen, egui::Button::new(tr("New version")))
+                                .on_disabled_hover_text(tr("The version is frozen."));
+
+                            if open_btn.clicked() {
+                                self.open_file_checked(f, latest.as_ref());
+                            }
+                            if new_version_btn.clicked() {
+                                match &latest {
+                                    Some(l) => {
+                                        self.stale_action =
+                                            Some((StaleAction::VersionUp, f.clone(), l.clone()))
+                                    }
+                                    None => self.commands.send(Command::VersionUp(f.clone())),
+                                }
+                            }
+                            if reveal_btn.clicked() {
+                                f.reveal();
                             }
+                            if ui.button(tr("Notes and tags…")).clicked() {
+                                self.edit_note = Some((f.clone(), note.clone(), String::new()));
+                                ui.close_menu();
+                            }
+                            if ui.button(tr("Rename")).clicked() {
+                                self.start_inline_rename(RenameTarget::File(f.clone()));
+                                ui.close_menu();
+                            }
+                            if ui.button(tr("Duplicate as…")).clicked() {
+                                self.duplicate_file = Some((f.clone(), String::new()));
+                                ui.close_menu();
+                            }
+                            for (label, keep_source) in
+                                [(tr("Move to task…"), false), (tr("Copy to task…"), true)]
+                            {
+                                if ui.button(label).clicked() {
+                                    self.file_transfer = Some(FileTransfer {
+                                        file: f.clone(),
+                                        keep_source,
+                                        target: None,
+                                        filter: String::new(),
+                                        version: VersionTransfer::default(),
+                                    });
+                                    ui.close_menu();
+                                }
+                            }
+                            let retained = match groups.iter().find(|g| g.files.contains(f)) {
+                                Some(g) => self.config.retention.check(g, f, publish.is_some()),
+                                None => Err(Retained::Latest),
+                            };
+                            let trash_btn = ui
+                                .add_enabled(
+                                    retained.is_ok(),
+                                    egui::Button::new(tr("Move to trash")),
+                                )
+                                .on_disabled_hover_text(match retained {
+                                    Err(Retained::Published) => tr("Published versions are kept."),
+                                    _ => match self.config.retention.keep_latest {
+                                        0 | 1 => tr("The latest version is kept."),
+                                        n => trf("The {} latest versions are kept.", &[&n]),
+                                    },
+                                });
+                            if trash_btn.clicked() {
+                                self.move_file_to_trash(f);
+                                ui.close_menu();
+                            }
+
+                            let project = self.current_project.clone();
+                            let task = self.current_task.clone();
+                            let mut payload = PluginPayload::new(
+                                PluginTarget::File,
+                                project.as_ref(),
+                                self.config.projects_dir.as_deref(),
+                                task.as_ref(),
+                            );
+                            payload.files.push(f);
+                            self.plugins_menu(ui, &payload);
                         });
                     });
-                    for c in &task.children {
-                        let child = c.clone();
-                        let _ = &self.tree_child(ui, child);
+                    row.col(|ui| {
+                        ui.label(f.fmt_version());
+                        if latest.is_none() {
+                            ui.label(
+                                egui::RichText::new(tr("latest"))
+                                    .small()
+                                    .color(Color32::from_rgb(80, 180, 80)),
+                            );
+                        }
+                    });
+                    row.col(|ui| {
+                        ui.label(&f.extension);
+                    });
+                    for column in &columns {
+                        row.col(|ui| {
+                            self.metadata_cell(ui, f, column);
+                        });
                     }
-                    ui.add_space(SPACING);
                 });
-        } else {
+            });
+    }
+
+    /// Opens the comparison of two versions of a workfile.
+    fn compare_versions(&mut self, a: &File, b: &File) {
+        let mut files = [a.clone(), b.clone()];
+        files.sort();
+        let stats = [&files[0], &files[1]].map(|f| FileStats::read(&f.path).ok());
+        self.compare_versions = Some(VersionComparison {
+            files,
+            stats,
+            diff: None,
+        });
+    }
+
+    /// The changed lines between the versions, for workfiles saved as text.
+    fn diff_versions(old: &File, new: &File) -> Result<Vec<DiffLine>, String> {
+        let read = |f: &File| {
+            fs::read_to_string(&f.path).map_err(|e| {
+                error!("Failed to read {}: {}", f.path.display(), e);
+                trf("Error reading {}: {}", &[&f.path.display(), &e])
+            })
+        };
+        diff_lines(&read(old)?, &read(new)?, DIFF_CONTEXT_LINES)
+            .ok_or_else(|| tr("The changes are too long to compare."))
+    }
+
+    /// Shows the sizes, dates and notes of two versions, and the changed lines of text workfiles, to
+    /// help choose the version to branch from.
+    fn compare_versions_window(&mut self, ctx: &egui::Context) {
+        let mut comparison = match self.compare_versions.take() {
+            Some(c) => c,
+            None => return,
+        };
+        let [old, new] = comparison.files.clone();
+        let name = |f: &File| {
+            f.path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string()
+        };
+
+        let mut open = true;
+        egui::Window::new(trf(
+            "Compare {} and {}",
+            &[&old.fmt_version(), &new.fmt_version()],
+        ))
+        .id(egui::Id::new("compare_versions"))
+        .open(&mut open)
+        .resizable(true)
+        .default_width(600.)
+        .show(ctx, |ui| {
+            egui::Grid::new("compare_versions_grid")
+                .num_columns(3)
+                .striped(true)
+                .show(ui, |ui| {
+                    ui.label("");
+                    ui.strong(name(&old));
+                    ui.strong(name(&new));
+                    ui.end_row();
+
+                    ui.label(tr("Size"));
+                    let [old_stats, new_stats] = comparison.stats;
+                    ui.label(old_stats.map_or(String::from("–"), |s| format_size(s.size)));
+                    ui.label(match (old_stats, new_stats) {
+                        (Some(o), Some(n)) => {
+                            let sign = if n.size < o.size { "-" } else { "+" };
+                            let delta = format_size(n.size.abs_diff(o.size));
+                            format!("{} ({}{})", format_size(n.size), sign, delta)
+                        }
+                        (_, n) => n.map_or(String::from("–"), |s| format_size(s.size)),
+                    });
+                    ui.end_row();
+
+                    ui.label(tr("Modified"));
+                    for stats in comparison.stats {
+                        ui.label(stats.map_or(String::from("–"), |s| format_timestamp(s.modified)));
+                    }
+                    ui.end_row();
+
+                    ui.label(tr("Notes"));
+                    for f in [&old, &new] {
+                        ui.label(
+                            self.file_notes
+                                .get(&name(f))
+                                .map(|n| n.notes.clone())
+                                .unwrap_or_default(),
+                        );
+                    }
+                    ui.end_row();
+
+                    ui.label(tr("Publish comment"));
+                    for f in [&old, &new] {
+                        ui.label(
+                            self.publishes
+                                .iter()
+                                .find(|p| p.path == f.path)
+                                .map(|p| p.comment.clone())
+                                .unwrap_or_default(),
+                        );
+                    }
+                    ui.end_row();
+                });
+
+            if !is_text_workfile(&old.extension) {
+                return;
+            }
             ui.add_space(SPACING);
-            ui.horizontal(|ui| {
-                let task_label = ui.add(egui::Label::new(&task.name).sense(egui::Sense::click()));
-                if task_label.clicked() {
-                    self.set_current_task(task.clone())
+            match &comparison.diff {
+                None => {
+                    if ui.button(tr("Show text diff")).clicked() {
+                        comparison.diff = Some(Self::diff_versions(&old, &new));
+                    }
                 }
-                ui.with_layout(egui::Layout::right_to_left(egui::Align::RIGHT), |ui| {
-                    let assets_btn = ui.add(egui::Button::new("Assets"));
-                    let output_btn = ui.add(egui::Button::new("Output"));
-                    let work_btn = ui.add(egui::Button::new("Work"));
-                    ui.add_space(SPACING);
+                Some(Err(e)) => {
+                    ui.label(egui::RichText::new(e).color(Color32::RED));
+                }
+                Some(Ok(lines))
+                    if lines
+                        .iter()
+                        .all(|l| matches!(l, DiffLine::Same(_) | DiffLine::Skipped(_))) =>
+                {
+                    ui.label(tr("The versions have the same text."));
+                }
+                Some(Ok(lines)) => {
+                    egui::ScrollArea::both().max_height(400.).show(ui, |ui| {
+                        for line in lines {
+                            match line {
+                                DiffLine::Same(l) => {
+                                    ui.monospace(format!("  {}", l));
+                                }
+                                DiffLine::Removed(l) => {
+                                    ui.label(
+                                        egui::RichText::new(format!("- {}", l))
+                                            .monospace()
+                                            .color(Color32::from_rgb(210, 60, 60)),
+                                    );
+                                }
+                                DiffLine::Added(l) => {
+                                    ui.label(
+                                        egui::RichText::new(format!("+ {}", l))
+                                            .monospace()
+                                            .color(Color32::from_rgb(60, 170, 80)),
+                                    );
+                                }
+                                DiffLine::Skipped(n) => {
+                                    ui.weak(trf("… {} unchanged lines", &[n]));
+                                }
+                            }
+                        }
+                    });
+                }
+            }
+        });
 
-                    if work_btn.clicked() {
-                        task.open_directory(match &self.current_project {
-                            Some(p) => p.work_sub_dirs[0].clone(),
-                            None => String::new(),
+        if open {
+            self.compare_versions = Some(comparison);
+        }
+    }
+
+    fn review_badge(ui: &mut egui::Ui, state: ReviewState) -> egui::Response {
+        let color = match state {
+            ReviewState::Pending => Color32::from_rgb(230, 170, 40),
+            ReviewState::Approved => Color32::from_rgb(60, 170, 80),
+            ReviewState::Rejected => Color32::from_rgb(210, 60, 60),
+        };
+        egui::Frame::none()
+            .fill(color)
+            .rounding(6.)
+            .inner_margin(egui::style::Margin::symmetric(4., 0.))
+            .show(ui, |ui| {
+                ui.label(
+                    egui::RichText::new(tr(&format!("{:?}", state)))
+                        .small()
+                        .color(Color32::BLACK),
+                )
+            })
+            .inner
+    }
+
+    fn publishes_table(&mut self, ui: &mut egui::Ui) {
+        use egui_extras::{Column, TableBuilder};
+
+        let publishes: Vec<Publish> = self
+            .publishes
+            .iter()
+            .filter(|p| self.publish_state_filter.map_or(true, |s| p.state() == s))
+            .cloned()
+            .collect();
+        if publishes.is_empty() {
+            ui.label(tr("No publishes."));
+            return;
+        }
+
+        TableBuilder::new(ui)
+            .striped(true)
+            .resizable(true)
+            .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+            .column(Column::initial(250.0))
+            .column(Column::initial(90.0))
+            .column(Column::initial(120.0))
+            .column(Column::initial(90.0))
+            .column(Column::remainder())
+            .min_scrolled_height(0.0)
+            .header(20., |mut header| {
+                for title in ["File", "Status", "Published", "User", "Comment"] {
+                    header.col(|ui| {
+                        ui.strong(tr(title));
+                    });
+                }
+            })
+            .body(|mut body| {
+                for p in &publishes {
+                    body.row(20., |mut row| {
+                        row.col(|ui| {
+                            let name = p.path.file_name().unwrap_or_default().to_string_lossy();
+                            let label = ui
+                                .add(egui::Label::new(name).sense(egui::Sense::click()))
+                                .on_hover_text(p.path.display().to_string());
+                            label.context_menu(|ui| {
+                                self.copy_path_menu(ui, &p.path);
+                                ui.set_enabled(self.can_edit());
+                                for s in ReviewState::ALL {
+                                    if ui
+                                        .button(trf("Mark as {}…", &[&tr(&format!("{:?}", s))]))
+                                        .clicked()
+                                    {
+                                        self.review_publish = Some((p.clone(), s, String::new()));
+                                        ui.close_menu();
+                                    }
+                                }
+                                ui.separator();
+                                if p.frozen.is_none() {
+                                    let freeze_btn = ui.button(tr("Freeze")).on_hover_text(tr(
+                                        "Makes the file read-only and stops new versions of it.",
+                                    ));
+                                    if freeze_btn.clicked() {
+                                        self.set_publish_frozen(p, true);
+                                        ui.close_menu();
+                                    }
+                                } else {
+                                    let owner = self
+                                        .current_project
+                                        .as_ref()
+                                        .map_or(false, |c| self.project_access(c) == Access::Owner);
+                                    let unfreeze_btn = ui
+                                        .add_enabled(owner, egui::Button::new(tr("Unfreeze")))
+                                        .on_disabled_hover_text(tr(
+                                            "Only owners of the project can unfreeze.",
+                                        ));
+                                    if unfreeze_btn.clicked() {
+                                        self.set_publish_frozen(p, false);
+                                        ui.close_menu();
+                                    }
+                                }
+                                self.copy_path_menu(ui, &p.path);
+                            });
+                        });
+                        row.col(|ui| {
+                            let badge = Self::review_badge(ui, p.state());
+                            if let Some(r) = &p.review {
+                                let mut text = trf(
+                                    "{} on {}",
+                                    &[&r.reviewer, &format_timestamp(r.reviewed_at)],
+                                );
+                                if !r.comment.is_empty() {
+                                    text = format!("{}\n{}", text, r.comment);
+                                }
+                                badge.on_hover_text(text);
+                            }
+                            if let Some(f) = &p.frozen {
+                                ui.label("🔒").on_hover_text(trf(
+                                    "Frozen by {} on {}",
+                                    &[&f.user, &format_timestamp(f.frozen_at)],
+                                ));
+                            }
                         });
+                        row.col(|ui| {
+                            ui.label(format_timestamp(p.published_at));
+                        });
+                        row.col(|ui| {
+                            ui.label(&p.user);
+                        });
+                        row.col(|ui| {
+                            ui.label(&p.comment);
+                        });
+                    });
+                }
+            });
+    }
+
+    /// Freezes or unfreezes a publish of the current task.
+    fn set_publish_frozen(&mut self, publish: &Publish, frozen: bool) {
+        let task_path = match &self.current_task {
+            Some(t) => t.path.clone(),
+            None => return,
+        };
+        match publish.set_frozen(&task_path, frozen) {
+            Ok(_p) => self.refresh_publishes(),
+            Err(e) => self
+                .notifications
+                .push(Severity::Error, trf("Error freezing publish: {}", &[&e])),
+        }
+    }
+
+    /// Sets the review state of a publish with an optional comment.
+    fn review_window(&mut self, ctx: &egui::Context) {
+        let task_path = match &self.current_task {
+            Some(t) => t.path.clone(),
+            None => {
+                self.review_publish = None;
+                return;
+            }
+        };
+        let (publish, mut state, mut comment) = match self.review_publish.clone() {
+            Some(r) => r,
+            None => return,
+        };
+
+        let mut open = true;
+        let mut done = false;
+        egui::Window::new(tr("Review"))
+            .id(egui::Id::new("review_publish"))
+            .open(&mut open)
+            .show(ctx, |ui| {
+                ui.label(
+                    publish
+                        .path
+                        .file_name()
+                        .unwrap_or_default()
+                        .to_string_lossy(),
+                );
+                ui.horizontal(|ui| {
+                    for s in ReviewState::ALL {
+                        ui.radio_value(&mut state, s, tr(&format!("{:?}", s)));
+                    }
+                });
+                ui.label(tr("Comment"));
+                ui.add(egui::TextEdit::multiline(&mut comment).desired_rows(3));
+                if ui.button(tr("Save")).clicked() {
+                    match publish.set_review(&task_path, state, &comment) {
+                        Ok(_p) => {
+                            let mut context = self.publish_webhook_context(&publish.path);
+                            context.event = WebhookEvent::StatusChange;
+                            context.status = tr(&format!("{:?}", state));
+                            self.fire_webhooks(context);
+                            done = true
+                        }
+                        Err(e) => self
+                            .notifications
+                            .push(Severity::Error, trf("Error saving review: {}", &[&e])),
                     }
+                }
+            });
+
+        if done {
+            self.refresh_publishes();
+        }
+        self.review_publish = if open && !done {
+            Some((publish, state, comment))
+        } else {
+            None
+        };
+    }
+
+    fn outputs_table(&mut self, ui: &mut egui::Ui) {
+        use egui_extras::{Column, TableBuilder};
+
+        let outputs = match &self.outputs {
+            Some(v) => v.clone(),
+            None => return,
+        };
+        let output_dir = match &self.current_task {
+            Some(t) => t.path.join(&t.metadata.output_dir_name),
+            None => return,
+        };
+
+        TableBuilder::new(ui)
+            .striped(true)
+            .resizable(true)
+            .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
+            .column(Column::initial(250.0))
+            .column(Column::initial(150.0))
+            .column(Column::initial(75.0))
+            .column(Column::remainder())
+            .min_scrolled_height(0.0)
+            .header(20., |mut header| {
+                header.col(|ui| {
+                    ui.strong(tr("Name"));
+                });
+                header.col(|ui| {
+                    ui.strong(tr("Frames"));
+                });
+                header.col(|ui| {
+                    ui.strong(tr("Size"));
+                });
+                header.col(|ui| {
+                    ui.strong(tr("Folder"));
+                });
+            })
+            .body(|body| {
+                body.rows(20., outputs.len(), |i, mut row| {
+                    let item = &outputs[i];
+                    row.col(|ui| {
+                        let name_label =
+                            ui.add(egui::Label::new(item.name()).sense(egui::Sense::click()));
+                        if name_label.double_clicked() {
+                            self.play_output(item);
+                        }
+                        name_label.context_menu(|ui| {
+                            if ui.button(tr("Play")).clicked() {
+                                self.play_output(item);
+                                ui.close_menu();
+                            }
+                            if ui.button(tr("Reveal in Explorer")).clicked() {
+                                let command = if cfg!(windows) { EXPLORER } else { FINDER };
+                                if let Err(e) = open::with(item.dir(), command) {
+                                    error!("Failed to open output dir: {}", e);
+                                }
+                                ui.close_menu();
+                            }
+                            ui.set_enabled(self.can_edit());
+                            if ui.button(tr("Send to dailies")).clicked() {
+                                self.send_to_dailies(item);
+                                ui.close_menu();
+                            }
+                            if self.config.transcoder.is_some() {
+                                ui.separator();
+                                if ui.button(tr("Proxy to dailies")).clicked() {
+                                    self.queue_proxy(item, false);
+                                    ui.close_menu();
+                                }
+                                if ui.button(tr("Proxy to deliveries")).clicked() {
+                                    self.queue_proxy(item, true);
+                                    ui.close_menu();
+                                }
+                            }
+                        });
+                    });
+                    row.col(|ui| {
+                        if let OutputItem::Sequence(s) = item {
+                            ui.label(s.frame_range());
+                            let missing = s.missing_frames().len();
+                            if missing > 0 {
+                                ui.label(
+                                    egui::RichText::new(format!("({} missing)", missing))
+                                        .color(Color32::RED),
+                                )
+                                .on_hover_text(s.missing_ranges());
+                            }
+                        }
+                    });
+                    row.col(|ui| {
+                        ui.label(format_size(item.size()));
+                    });
+                    row.col(|ui| {
+                        let dir = item.dir();
+                        ui.label(
+                            dir.strip_prefix(&output_dir)
+                                .unwrap_or(&dir)
+                                .display()
+                                .to_string(),
+                        );
+                    });
+                });
+            });
+    }
+
+    /// Opens the output in the configured player, or with the system default app from the first frame.
+    fn play_output(&mut self, item: &OutputItem) {
+        let result = match &self.config.player {
+            Some(p) => p.play(item),
+            None => open::that(item.first_path()).map_err(|e| e.to_string()),
+        };
+        if let Err(e) = result {
+            error!("Error playing output: {}", e);
+            self.notifications
+                .push(Severity::Error, trf("Error playing output: {}", &[&e]));
+        }
+    }
+
+    /// Lists the items in the newest dated folder of the project's dailies dir,
+    /// or in the dailies dir itself if it has no folders.
+    fn latest_dailies(&self, project: &Project) -> Vec<OutputItem> {
+        let dailies_dir = match &self.config.projects_dir {
+            Some(d) => project.get_dailies_path(d),
+            None => return Vec::new(),
+        };
+
+        let mut dirs: Vec<PathBuf> = match std::fs::read_dir(&dailies_dir) {
+            Ok(d) => d
+                .filter_map(|e| e.ok())
+                .map(|e| e.path())
+                .filter(|p| p.is_dir())
+                .collect(),
+            Err(_e) => return Vec::new(),
+        };
+        dirs.sort();
+        let dir = dirs.pop().unwrap_or(dailies_dir);
+
+        match find_files(&dir, &|n: &str| n.starts_with('.')) {
+            Ok(f) => collapse(f),
+            Err(e) => {
+                error!("Error finding dailies: {}", e);
+                Vec::new()
+            }
+        }
+    }
+
+    fn jobs(&mut self) -> &mut JobQueue {
+        self.jobs.get_or_insert_with(JobQueue::default)
+    }
+
+    /// Queues an h264 proxy of the output into today's folder in the dailies or deliveries dir.
+    fn queue_proxy(&mut self, item: &OutputItem, deliveries: bool) {
+        let transcoder = match &self.config.transcoder {
+            Some(t) => t.clone(),
+            None => return,
+        };
+        let dir = match (&self.current_project, &self.config.projects_dir) {
+            (Some(p), Some(d)) if deliveries => p.get_deliveries_path(d),
+            (Some(p), Some(d)) => p.get_dailies_path(d),
+            _ => return,
+        };
+        let output = proxy_path(item, &dir.join(format_date(now_timestamp())));
+
+        let mut context = WebhookContext::new(WebhookEvent::Delivery);
+        context.project = self
+            .current_project
+            .as_ref()
+            .map(|p| p.name.clone())
+            .unwrap_or_default();
+        context.task = self
+            .current_task
+            .as_ref()
+            .map(|t| t.name.clone())
+            .unwrap_or_default();
+        context.file = output
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        let webhooks = if deliveries {
+            self.config.webhooks.clone()
+        } else {
+            Vec::new()
+        };
+
+        let item = item.clone();
+        self.jobs()
+            .push(&trf("Proxy of {}", &[&item.name()]), move |job| {
+                let result = transcoder.transcode(&item, &output, job);
+                if result.is_ok() {
+                    // Failed webhooks are logged, the proxy itself is done.
+                    let _ = send_all(&webhooks, &context);
+                }
+                result
+            });
+    }
+
+    /// Copies the output into today's folder in the project's dailies dir, as a background job.
+    fn send_to_dailies(&mut self, item: &OutputItem) {
+        let dailies_dir = match (&self.current_project, &self.config.projects_dir) {
+            (Some(p), Some(d)) => p.get_dailies_path(d).join(format_date(now_timestamp())),
+            _ => return,
+        };
+
+        let item = item.clone();
+        self.jobs()
+            .push(&trf("Send {} to dailies", &[&item.name()]), move |job| {
+                let result = item.copy_to(&dailies_dir, |done, total| {
+                    job.set_progress(done as f32 / total.max(1) as f32);
+                    !job.is_cancelled()
+                });
+                match result {
+                    Ok(()) => Ok(trf(
+                        "Sent {} to {}",
+                        &[&item.name(), &dailies_dir.display()],
+                    )),
+                    Err(e) => Err(trf("Error sending to dailies: {}", &[&e])),
+                }
+            });
+    }
+
+    #[cfg(feature = "deliveries")]
+    fn open_package_delivery(&mut self) {
+        let (project, task, outputs) =
+            match (&self.current_project, &self.current_task, &self.outputs) {
+                (Some(p), Some(t), Some(o)) => (p, t, o.clone()),
+                _ => return,
+            };
+        let package = DeliveryPackage {
+            name: format!("{}_{}", project.name_sanitized, task.name),
+            items: outputs,
+            timeline: Some(TimelineFormat::Otio),
+            rate: self
+                .config
+                .transcoder
+                .as_ref()
+                .map_or_else(default_framerate, |t| t.framerate),
+            ffmpeg: self.config.transcoder.as_ref().map(|t| t.ffmpeg.clone()),
+            archive: None,
+        };
+        let selected = vec![true; package.items.len()];
+        self.delivery_package = Some((package, selected, None, String::new()));
+    }
+
+    /// Picks the outputs and timeline format, then copies them into today's folder in the deliveries dir.
+    #[cfg(feature = "deliveries")]
+    fn package_delivery_window(&mut self, ctx: &egui::Context) {
+        let (mut package, mut selected, mut upload, mut notes) = match &self.delivery_package {
+            Some(p) => p.clone(),
+            None => return,
+        };
+
+        let archive = self.config.archive.clone();
+        let targets = &self.config.upload_targets;
+        let mut open = true;
+        let mut done = false;
+        egui::Window::new(tr("Package delivery"))
+            .id(egui::Id::new("package_delivery"))
+            .open(&mut open)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(tr("Name"));
+                    ui.add(
+                        egui::TextEdit::singleline(&mut package.name).desired_width(TEXTEDIT_WIDTH),
+                    );
+                });
+                let name_error = validate_name(&package.name).err();
+                if let Some(e) = &name_error {
+                    ui.label(egui::RichText::new(e.to_string()).color(Color32::RED));
+                }
+
+                ui.add_space(SPACING);
+                egui::ScrollArea::vertical()
+                    .max_height(300.)
+                    .show(ui, |ui| {
+                        for (item, checked) in package.items.iter().zip(selected.iter_mut()) {
+                            ui.checkbox(checked, item.name());
+                        }
+                    });
 
-                    if output_btn.clicked() {
-                        task.open_directory(match &self.current_project {
-                            Some(p) => p.work_sub_dirs[1].clone(),
-                            None => String::new(),
+                ui.add_space(SPACING);
+                ui.horizontal(|ui| {
+                    ui.label(tr("Timeline"));
+                    let label = |f: Option<TimelineFormat>| match f {
+                        Some(TimelineFormat::Otio) => String::from("OpenTimelineIO"),
+                        Some(f) => f.extension().to_uppercase(),
+                        None => tr("None"),
+                    };
+                    egui::ComboBox::from_id_source("delivery_timeline")
+                        .selected_text(label(package.timeline))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut package.timeline, None, label(None));
+                            for f in TimelineFormat::ALL {
+                                ui.selectable_value(&mut package.timeline, Some(f), label(Some(f)));
+                            }
                         });
+                });
+                let mut archived = package.archive.is_some();
+                let archive_label = trf("Archive as {}", &[&archive.format.extension()]);
+                let archive_check = ui.checkbox(&mut archived, archive_label);
+                let archive_check = match archive.split_size_mb {
+                    0 => archive_check,
+                    size => {
+                        archive_check.on_hover_text(trf("Split into volumes of {} MB.", &[&size]))
                     }
+                };
+                if archive_check.changed() {
+                    package.archive = Some(archive.clone()).filter(|_| archived);
+                }
+                if !targets.is_empty() {
+                    ui.horizontal(|ui| {
+                        ui.label(tr("Upload to"));
+                        egui::ComboBox::from_id_source("delivery_upload")
+                            .selected_text(upload.clone().unwrap_or_else(|| tr("None")))
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut upload, None, tr("None"));
+                                for t in targets {
+                                    ui.selectable_value(&mut upload, Some(t.name.clone()), &t.name);
+                                }
+                            });
+                    });
+                }
+                ui.label(tr("Notes for the delivery note"));
+                ui.add(egui::TextEdit::multiline(&mut notes).desired_rows(3));
 
-                    if assets_btn.clicked() {
-                        task.open_directory(match &self.current_project {
-                            Some(p) => p.work_sub_dirs[2].clone(),
-                            None => String::new(),
-                        });
-                    }
-                });
+                ui.add_space(SPACING);
+                let valid = name_error.is_none() && selected.contains(&true);
+                if ui
+                    .add_enabled(valid, egui::Button::new(tr("Package")))
+                    .clicked()
+                {
+                    done = true;
+                }
             });
-            ui.add_space(SPACING);
+
+        if done {
+            package.items = package
+                .items
+                .into_iter()
+                .zip(selected.iter())
+                .filter(|(_i, s)| **s)
+                .map(|(i, _s)| i)
+                .collect();
+            self.queue_package_delivery(package, upload, &notes);
+            self.delivery_package = None;
+        } else {
+            self.delivery_package = if open {
+                Some((package, selected, upload, notes))
+            } else {
+                None
+            };
         }
     }
 
-    /// If open_project() encounters an error when creating the task tree, this will render the error instead.
-    fn render_task_tree_error(&mut self, ui: &mut egui::Ui, error: io::Error) {
-        ui.label(error.to_string());
+    #[cfg(feature = "deliveries")]
+    fn queue_package_delivery(
+        &mut self,
+        package: DeliveryPackage,
+        upload: Option<String>,
+        notes: &str,
+    ) {
+        let deliveries_path = match (&self.current_project, &self.config.projects_dir) {
+            (Some(p), Some(d)) => p.get_deliveries_path(d),
+            _ => return,
+        };
+        let dir = match find_interrupted(&deliveries_path, &package.name) {
+            Some(d) => {
+                self.notifications.push(
+                    Severity::Info,
+                    trf("Resuming the interrupted package in {}", &[&d.display()]),
+                );
+                d
+            }
+            None => deliveries_path.join(format_date(now_timestamp())),
+        };
+        let mut context = WebhookContext::new(WebhookEvent::Delivery);
+        if let Some(p) = &self.current_project {
+            context.project = p.name.clone();
+        }
+        if let Some(t) = &self.current_task {
+            context.task = t.name.clone();
+        }
+        context.file = package.name.clone();
+        let webhooks = self.config.webhooks.clone();
+        let package_dir = dir.join(&package.name);
+        let upload_path = match &package.archive {
+            Some(a) => a.archive_path(&package_dir),
+            None => package_dir.clone(),
+        };
+        let (project, client) = match &self.current_project {
+            Some(p) => (p.name.clone(), p.client_name()),
+            None => Default::default(),
+        };
+        let note = DeliveryNote::new(
+            &package,
+            &DeliveryManifest::default(),
+            &project,
+            &client,
+            notes,
+        );
+        let note_package = package.clone();
+
+        let id = self
+            .jobs()
+            .push(&trf("Package {}", &[&package.name]), move |job| {
+                let result = package.package(&dir, job);
+                if result.is_ok() {
+                    let _ = send_all(&webhooks, &context);
+                }
+                result
+            });
+        let target = self
+            .config
+            .upload_targets
+            .iter()
+            .find(|t| Some(&t.name) == upload.as_ref());
+        if let Some(target) = target {
+            self.delivery_uploads
+                .push((id, upload_path, target.clone()));
+        }
+        self.pending_delivery_notes
+            .push((id, note, note_package, package_dir));
     }
 
-    fn files_table(&mut self, ui: &mut egui::Ui) {
-        use egui_extras::{Column, TableBuilder};
+    /// Fills in the checksums from the manifest of the finished package and shows its note.
+    #[cfg(feature = "deliveries")]
+    fn open_delivery_note(&mut self, note: DeliveryNote, package: &DeliveryPackage, dir: PathBuf) {
+        let manifest = match DeliveryManifest::load(&dir) {
+            Ok(m) => m,
+            Err(e) => {
+                self.notifications.push(Severity::Error, e);
+                return;
+            }
+        };
+        let note = DeliveryNote::new(package, &manifest, &note.project, &note.client, &note.notes);
+        let text = self.render_delivery_note(&note, NoteFormat::Markdown);
+        self.delivery_note = Some((note, dir, NoteFormat::Markdown, text));
+    }
 
-        let files = match &self.files {
-            Some(v) => v.clone(),
+    /// The note in the template of its client from the pipeline template, or the built-in one.
+    #[cfg(feature = "deliveries")]
+    fn render_delivery_note(&mut self, note: &DeliveryNote, format: NoteFormat) -> String {
+        let pipeline_template = self.config.templates_dir.join(PIPELINE_TEMPLATE_DIR_NAME);
+        let template = match load_template(&pipeline_template, &note.client, format) {
+            Ok(t) => t,
+            Err(e) => {
+                self.notifications.push(
+                    Severity::Error,
+                    trf("Failed to read the delivery note template: {}", &[&e]),
+                );
+                None
+            }
+        };
+        note.render(format, template.as_deref())
+    }
+
+    /// Shows the note of a finished package, to copy into a mail or save next to the package.
+    #[cfg(feature = "deliveries")]
+    fn delivery_note_window(&mut self, ctx: &egui::Context) {
+        let (note, package_dir, mut format, mut text) = match &self.delivery_note {
+            Some(n) => n.clone(),
             None => return,
         };
 
-        TableBuilder::new(ui)
-            .striped(true)
-            .resizable(true)
-            .cell_layout(egui::Layout::left_to_right(egui::Align::Center))
-            .column(Column::initial(250.0))
-            .column(Column::initial(75.0))
-            .column(Column::remainder())
-            .min_scrolled_height(0.0)
-            .header(20., |mut header| {
-                header.col(|ui| {
-                    ui.strong("Name");
+        let mut open = true;
+        let mut format_changed = false;
+        egui::Window::new(tr("Delivery note"))
+            .id(egui::Id::new("delivery_note"))
+            .open(&mut open)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    for f in NoteFormat::ALL {
+                        let label = match f {
+                            NoteFormat::Markdown => "Markdown",
+                            NoteFormat::Html => "HTML",
+                        };
+                        if ui.selectable_value(&mut format, f, label).changed() {
+                            format_changed = true;
+                        }
+                    }
                 });
-                header.col(|ui| {
-                    ui.strong("Version");
+                ui.add_space(SPACING);
+                egui::ScrollArea::vertical()
+                    .max_height(400.)
+                    .show(ui, |ui| {
+                        ui.add(
+                            egui::TextEdit::multiline(&mut text)
+                                .code_editor()
+                                .desired_width(f32::INFINITY),
+                        );
+                    });
+                ui.add_space(SPACING);
+                ui.horizontal(|ui| {
+                    if ui.button(tr("Copy")).clicked() {
+                        ui.output_mut(|o| o.copied_text = text.clone());
+                        self.notifications
+                            .push(Severity::Info, tr("Copied the delivery note."));
+                    }
+                    if ui.button(tr("Save next to package")).clicked() {
+                        let path = note_path(&package_dir, format);
+                        match write_bytes_atomic(&path, text.as_bytes()) {
+                            Ok(()) => self
+                                .notifications
+                                .push(Severity::Info, trf("Saved {}", &[&path.display()])),
+                            Err(e) => self.notifications.push(
+                                Severity::Error,
+                                trf("Failed to save {}: {}", &[&path.display(), &e]),
+                            ),
+                        }
+                    }
                 });
-                header.col(|ui| {
-                    ui.strong("Extension");
+            });
+
+        if format_changed {
+            text = self.render_delivery_note(&note, format);
+        }
+        self.delivery_note = if open {
+            Some((note, package_dir, format, text))
+        } else {
+            None
+        };
+    }
+
+    /// Uploads the finished package, or its archive and volumes, as a job.
+    #[cfg(feature = "deliveries")]
+    fn queue_delivery_upload(&mut self, path: PathBuf, target: UploadTarget) {
+        let paths = if path.is_dir() {
+            vec![path.clone()]
+        } else {
+            archive_files(&path)
+        };
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        self.jobs().push(
+            &trf("Upload {} to {}", &[&name, &target.name]),
+            move |job| target.upload(&paths, job),
+        );
+    }
+
+    /// Versions up a large file as a job, so the copy shows its progress and can be cancelled.
+    fn version_up_in_background(&mut self, file: &File) {
+        let file = file.clone();
+        let name = file
+            .path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        let id = self
+            .jobs()
+            .push(&trf("Version up {}", &[&name]), move |job| {
+                let result = file.version_up_with_progress(&mut |done, total| {
+                    job.set_progress(done as f32 / total.max(1) as f32);
+                    !job.is_cancelled()
                 });
-            })
-            .body(|mut body| {
-                for f in &files {
-                    body.row(20., |mut row| {
-                        row.col(|ui| {
-                            let filename_label =
-                                ui.add(egui::Label::new(&f.name).sense(egui::Sense::click()));
-                            if filename_label.double_clicked() {
-                                self.open_file(&f);
-                            }
-                            filename_label.context_menu(|ui| {
-                                let open_btn = ui.button("Open");
-                                let new_version_btn = ui.button("New version");
-                                let reveal_btn = ui.button("Reveal in Explorer");
+                match result {
+                    Ok(p) => Ok(trf("Created {}", &[&p.display()])),
+                    Err(e) => Err(e.to_string()),
+                }
+            });
+        self.version_up_jobs.push(id);
+    }
 
-                                if open_btn.clicked() {
-                                    self.open_file(&f);
-                                }
-                                if new_version_btn.clicked() {
-                                    match f.version_up() {
-                                        Ok(()) => (),
-                                        Err(e) => {
-                                            self.message = Some(Message {
-                                                text: e.to_string(),
-                                                message_type: MessageType::Warning,
-                                            })
-                                        }
-                                    }
-                                    self.refresh_files();
-                                }
-                                if reveal_btn.clicked() {
-                                    f.reveal();
-                                }
-                            });
-                        });
-                        row.col(|ui| {
-                            ui.label(&f.fmt_version());
-                        });
-                        row.col(|ui| {
-                            ui.label(&f.extension);
-                        });
-                    })
+    /// Shows messages for jobs that finished since the last frame.
+    fn handle_finished_jobs(&mut self) {
+        let finished = match &self.jobs {
+            Some(j) => j.finished(),
+            None => return,
+        };
+        for status in finished {
+            let batch_id = self
+                .batch_version_up
+                .as_ref()
+                .and_then(|b| b.job.as_ref())
+                .map(|(id, _r)| *id);
+            if batch_id == Some(status.id) {
+                self.refresh_files();
+            }
+            if let Some(i) = self.version_up_jobs.iter().position(|id| *id == status.id) {
+                self.version_up_jobs.remove(i);
+                self.refresh_files();
+            }
+            #[cfg(feature = "deliveries")]
+            if let Some(i) = self
+                .delivery_uploads
+                .iter()
+                .position(|(id, _p, _t)| *id == status.id)
+            {
+                let (_id, path, target) = self.delivery_uploads.remove(i);
+                if matches!(status.state, JobState::Done(_)) {
+                    self.queue_delivery_upload(path, target);
+                }
+            }
+            #[cfg(feature = "deliveries")]
+            if let Some(i) = self
+                .pending_delivery_notes
+                .iter()
+                .position(|(id, _n, _p, _d)| *id == status.id)
+            {
+                let (_id, note, package, dir) = self.pending_delivery_notes.remove(i);
+                if matches!(status.state, JobState::Done(_)) {
+                    self.open_delivery_note(note, &package, dir);
+                }
+            }
+            if let Some(i) = self.inbox.jobs.iter().position(|(id, _p)| *id == status.id) {
+                let (_id, paths) = self.inbox.jobs.remove(i);
+                if !matches!(status.state, JobState::Done(_)) {
+                    for path in &paths {
+                        self.inbox.watcher.release(path);
+                    }
+                }
+            }
+            match status.state {
+                JobState::Done(m) => self.notifications.push(Severity::Info, m),
+                JobState::Failed(e) => self
+                    .notifications
+                    .push(Severity::Error, format!("{}: {}", status.name, e)),
+                _ => (),
+            }
+        }
+    }
+
+    /// Lists background jobs with their progress and cancel buttons.
+    fn jobs_panel(&mut self, ui: &mut egui::Ui) {
+        let statuses = match &self.jobs {
+            Some(j) => j.statuses(),
+            None => return,
+        };
+
+        ui.add_space(SPACING);
+        ui.horizontal(|ui| {
+            ui.strong(tr("Jobs"));
+            if ui.button(tr("Clear finished")).clicked() {
+                self.jobs().clear_finished();
+            }
+        });
+        for status in statuses {
+            ui.horizontal(|ui| {
+                ui.label(&status.name);
+                match &status.state {
+                    JobState::Queued => {
+                        ui.label(tr("Queued"));
+                    }
+                    JobState::Running => match status.progress {
+                        Some(p) => {
+                            ui.add(
+                                egui::ProgressBar::new(p)
+                                    .show_percentage()
+                                    .desired_width(200.),
+                            );
+                        }
+                        None => {
+                            ui.spinner();
+                        }
+                    },
+                    JobState::Done(m) => {
+                        ui.label(tr("Done")).on_hover_text(m);
+                    }
+                    JobState::Failed(e) => {
+                        ui.label(egui::RichText::new(tr("Failed")).color(Color32::RED))
+                            .on_hover_text(e);
+                    }
+                    JobState::Cancelled => {
+                        ui.label(tr("Cancelled"));
+                    }
+                }
+                if status.state.is_active() && ui.button(tr("❌ Cancel")).clicked() {
+                    self.jobs().cancel(status.id);
                 }
             });
+        }
+        ui.add_space(SPACING);
+    }
+
+    fn task_plugins_menu(&mut self, ui: &mut egui::Ui, task: &TaskTreeNode) {
+        let project = self.current_project.clone();
+        let payload = PluginPayload::new(
+            PluginTarget::Task,
+            project.as_ref(),
+            self.config.projects_dir.as_deref(),
+            Some(task),
+        );
+        self.plugins_menu(ui, &payload);
+    }
+
+    /// Adds buttons copying the path, and its forms on other OSes when a path mapping matches.
+    fn copy_path_menu(&mut self, ui: &mut egui::Ui, path: &Path) {
+        let local = path.display().to_string();
+        let mut paths = vec![("Copy path", local.clone())];
+        for (label, style) in [
+            ("Copy UNC path", PathStyle::Unc),
+            ("Copy Mac path", PathStyle::Mac),
+            ("Copy Linux path", PathStyle::Linux),
+        ] {
+            if let Some(p) = map_path(path, &self.config.path_mappings, style) {
+                if p != local {
+                    paths.push((label, p));
+                }
+            }
+        }
+
+        for (label, p) in paths {
+            if ui.button(tr(label)).on_hover_text(&p).clicked() {
+                ui.output_mut(|o| o.copied_text = p.clone());
+                self.notifications
+                    .push(Severity::Info, trf("Copied {}", &[&p]));
+                ui.close_menu();
+            }
+        }
     }
 
+    /// Adds a submenu with the plugins available for the payload target, if there are any.
+    fn plugins_menu(&mut self, ui: &mut egui::Ui, payload: &PluginPayload<'_>) {
+        let plugins: Vec<Plugin> = self
+            .plugins
+            .iter()
+            .filter(|p| p.targets.contains(&payload.target))
+            .cloned()
+            .collect();
+        if plugins.is_empty() || !self.can_edit() {
+            return;
+        }
+
+        ui.separator();
+        ui.menu_button(tr("Plugins"), |ui| {
+            for plugin in &plugins {
+                if ui.button(&plugin.label).clicked() {
+                    if let Err(e) = plugin.run(payload) {
+                        self.notifications.push(Severity::Error, e);
+                    }
+                    ui.close_menu();
+                }
+            }
+        });
+    }
+
+    /// Opens the file, asking first if it was saved with a newer app version than the project uses.
     fn open_file(&mut self, f: &File) {
-        match &f.open() {
-            Ok(()) => (),
-            Err(e) => {
-                error!("Error opening file: {}", e);
-                self.message = Some(Message {
-                    text: String::from(format!("Error opening file: {}", e)),
-                    message_type: MessageType::Warning,
-                });
+        let dcc = self
+            .dcc
+            .iter()
+            .find(|d| d.extension == format!(".{}", f.extension));
+        let saved_with = Publish::find_task_dir(&f.path).and_then(|t| {
+            let store = MetadataStore::load(&t).ok()?;
+            let key = MetadataStore::file_key(&t, &f.path);
+            store.get(&key, APP_VERSION_KEY).map(String::from)
+        });
+        if let (Some(dcc), Some(saved_with)) = (dcc, saved_with) {
+            if dcc.is_older_than(&saved_with) {
+                let version = dcc.version.clone().unwrap_or_default();
+                self.newer_app_version = Some((f.clone(), saved_with, version));
+                return;
             }
         }
+        self.launch_file(f);
+    }
+
+    fn launch_file(&mut self, f: &File) {
+        self.commands.send(Command::OpenFile(f.clone()));
+    }
+
+    /// Does what the preferences say to a double-clicked workfile.
+    fn file_double_clicked(&mut self, f: &File, latest: Option<&File>) {
+        match self.preferences.file_double_click {
+            FileDoubleClick::Open => self.open_file_checked(f, latest),
+            FileDoubleClick::OpenWith => self.open_with = Some(f.clone()),
+            FileDoubleClick::Reveal => f.reveal(),
+        }
+    }
+
+    /// Lists the DCC:s that open the extension of the workfile, and the default app of the OS.
+    fn open_with_window(&mut self, ctx: &egui::Context) {
+        let file = match &self.open_with {
+            Some(f) => f.clone(),
+            None => return,
+        };
+        let extension = format!(".{}", file.extension);
+        let apps: Vec<(String, PathBuf)> = self
+            .dcc
+            .iter()
+            .filter(|d| d.extension == extension)
+            .filter_map(|d| Some((d.name.clone(), d.executable.clone()?)))
+            .collect();
+
+        let mut open = true;
+        let mut chosen = None;
+        egui::Window::new(trf("Open {} with", &[&file.name]))
+            .id(egui::Id::new("open_with"))
+            .open(&mut open)
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                for (name, executable) in &apps {
+                    if ui
+                        .button(name)
+                        .on_hover_text(executable.display().to_string())
+                        .clicked()
+                    {
+                        chosen = Some(Some(executable.clone()));
+                    }
+                }
+                if ui.button(tr("Default app")).clicked() {
+                    chosen = Some(None);
+                }
+            });
+
+        if let Some(executable) = chosen {
+            self.commands
+                .send(Command::OpenFileWith { file, executable });
+            self.open_with = None;
+        } else if !open {
+            self.open_with = None;
+        }
     }
 
     fn filter_projects(&mut self, filter_string: String) {
+        self.project_groups = None;
         if filter_string.is_empty() {
             self.projects_filtered = self.projects.clone();
             return;
         }
 
-        let filtered: Vec<Project> = self
-            .projects
-            .iter()
-            .filter(|p| p.name.contains(filter_string.as_str()))
-            .cloned()
-            .collect();
-        self.projects_filtered = filtered;
+        self.projects_filtered = fuzzy_filter(&self.projects, &filter_string, |p| &p.name);
     }
 
     fn manage_clients_panel(&mut self, ui: &mut egui::Ui) {
         ui.add_space(SPACING);
         ui.add_space(SPACING);
-        ui.strong("Add client");
+        ui.strong(tr("Add client"));
         ui.add_space(SPACING);
         ui.horizontal(|ui| {
-            ui.label("Client full name: ");
+            ui.label(tr("Client full name: "));
             ui.add(egui::TextEdit::singleline(&mut self.new_client_fullname).desired_width(100.));
-            ui.label("Client short name: ");
+            ui.label(tr("Client short name: "));
             ui.add(egui::TextEdit::singleline(&mut self.new_client_shortname).desired_width(75.));
-            let add_client_btn = ui.add(egui::Button::new("Add client"));
+            let add_client_btn = ui.add(egui::Button::new(tr("Add client")));
 
             if add_client_btn.clicked() {
                 info!("Adding client to: {}", self.config.clients_path.display());
@@ -1073,33 +7723,27 @@ impl Rclamp {
                     &self.new_client_shortname,
                     &self.config.clients_path,
                 ) {
-                    Ok(_o) => {
-                        self.message = Some(Message {
-                            text: String::from("Successfully added client."),
-                            message_type: MessageType::Info,
-                        })
-                    }
-                    Err(e) => {
-                        self.message = Some(Message {
-                            text: String::from(format!("Failed to add client: {}", e)),
-                            message_type: MessageType::Warning,
-                        })
-                    }
+                    Ok(_o) => self
+                        .notifications
+                        .push(Severity::Info, tr("Successfully added client.")),
+                    Err(e) => self
+                        .notifications
+                        .push(Severity::Error, trf("Failed to add client: {}", &[&e])),
                 };
             }
         });
         ui.add_space(SPACING);
-        ui.strong("Remove client");
+        ui.strong(tr("Remove client"));
         ui.add_space(SPACING);
         ui.horizontal(|ui| {
             egui::ComboBox::from_id_source("remove_client_select")
-                .selected_text(format!("{}", self.remove_client.name))
+                .selected_text(self.remove_client.name.to_string())
                 .show_ui(ui, |ui| {
                     for c in &self.clients {
                         ui.selectable_value(&mut self.remove_client, c.clone(), c.name.clone());
                     }
                 });
-            let remove_client_btn = ui.add(egui::Button::new("Remove client"));
+            let remove_client_btn = ui.add(egui::Button::new(tr("Remove client")));
 
             if remove_client_btn.clicked() {
                 info!(
@@ -1107,18 +7751,12 @@ impl Rclamp {
                     self.config.clients_path.display()
                 );
                 match Client::remove_client(&self.remove_client, &self.config.clients_path) {
-                    Ok(_o) => {
-                        self.message = Some(Message {
-                            text: String::from("Successfully removed client."),
-                            message_type: MessageType::Info,
-                        })
-                    }
-                    Err(e) => {
-                        self.message = Some(Message {
-                            text: String::from(format!("Failed to remove client: {}", e)),
-                            message_type: MessageType::Warning,
-                        })
-                    }
+                    Ok(_o) => self
+                        .notifications
+                        .push(Severity::Info, tr("Successfully removed client.")),
+                    Err(e) => self
+                        .notifications
+                        .push(Severity::Error, trf("Failed to remove client: {}", &[&e])),
                 }
             }
         });
@@ -1126,68 +7764,249 @@ impl Rclamp {
         ui.add_space(SPACING);
         ui.add_space(SPACING);
 
-        if ui.button("Close").clicked() {
-            self.show_clients_panel = false;
+        if ui.button(tr("Close")).clicked() {
+            self.show_clients_panel = false;
+        }
+
+        ui.add_space(SPACING);
+        ui.add_space(SPACING);
+    }
+
+    fn open_create_folder(&mut self) {
+        self.show_create_folder = true;
+        self.show_create_project = false;
+        self.show_create_task = false;
+    }
+    fn open_create_task(&mut self) {
+        self.show_create_folder = false;
+        self.show_create_project = false;
+        self.show_create_task = true;
+        self.new_task_list = None;
+    }
+    fn open_create_structure(&mut self, parent: TaskTreeNode) {
+        self.new_structure_parent = parent;
+        self.structure_results = Vec::new();
+        self.show_create_structure = true;
+    }
+    fn open_import_tasks(&mut self, parent: TaskTreeNode) {
+        self.import_tasks_parent = parent;
+        self.import_results = Vec::new();
+        self.show_import_tasks = true;
+    }
+    fn open_or_close_create_project(&mut self) {
+        self.show_create_project = !self.show_create_project;
+        self.show_create_folder = false;
+        self.show_create_task = false;
+    }
+    fn open_adopt_project(&mut self) {
+        let projects_dir = match &self.config.projects_dir {
+            Some(d) => d.clone(),
+            None => return,
+        };
+        match ProjectAdoption::find_candidates(&projects_dir) {
+            Ok(c) => self.adoption_candidates = c,
+            Err(e) => {
+                self.notifications
+                    .push(Severity::Error, trf("Error finding projects: {}", &[&e]));
+                return;
+            }
+        }
+        self.adoption = None;
+        self.show_adopt_project = true;
+    }
+
+    /// The projects of one client with their status, due date and deliveries, for people who think in
+    /// clients rather than folders.
+    fn client_view_window(&mut self, ctx: &egui::Context) {
+        let (client, projects) = match &self.client_view {
+            Some(v) => v.clone(),
+            None => return,
+        };
+        // Clients of older projects may only be in the project names.
+        let mut clients: Vec<(String, String)> = self
+            .clients
+            .iter()
+            .map(|c| (c.short_name.clone(), c.name.clone()))
+            .collect();
+        for p in &self.projects {
+            let name = p.client_name();
+            if !name.is_empty() && !clients.iter().any(|(s, _n)| s.eq_ignore_ascii_case(&name)) {
+                clients.push((name.clone(), name));
+            }
+        }
+        clients.sort_by_key(|(_s, n)| n.to_lowercase());
+
+        let mut open = true;
+        let mut picked = None;
+        let mut open_project = None;
+        egui::Window::new(tr("Client view"))
+            .id(egui::Id::new("client_view"))
+            .open(&mut open)
+            .resizable(true)
+            .default_width(600.)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label(tr("Client: "));
+                    let selected = clients
+                        .iter()
+                        .find(|(s, _n)| *s == client)
+                        .map(|(_s, n)| n.clone())
+                        .unwrap_or_default();
+                    egui::ComboBox::from_id_source("client_view_client")
+                        .selected_text(selected)
+                        .width(250.)
+                        .show_ui(ui, |ui| {
+                            for (short_name, name) in &clients {
+                                if ui.selectable_label(*short_name == client, name).clicked() {
+                                    picked = Some(short_name.clone());
+                                }
+                            }
+                        });
+                });
+                if client.is_empty() {
+                    return;
+                }
+                ui.add_space(SPACING);
+                if projects.is_empty() {
+                    ui.label(tr("The client has no projects."));
+                    return;
+                }
+                egui::ScrollArea::vertical()
+                    .max_height(400.)
+                    .show(ui, |ui| {
+                        egui::Grid::new("client_view_grid")
+                            .striped(true)
+                            .show(ui, |ui| {
+                                for title in ["Project", "Status", "Due date", "Deliveries"] {
+                                    ui.strong(tr(title));
+                                }
+                                ui.end_row();
+                                for p in &projects {
+                                    if ui.link(&p.project.name).clicked() {
+                                        open_project = Some(p.project.clone());
+                                    }
+                                    ui.label(p.project.status.clone().unwrap_or_default());
+                                    ui.label(p.project.due_date.clone().unwrap_or_default());
+                                    let deliveries = ui.label(p.deliveries.len().to_string());
+                                    if !p.deliveries.is_empty() {
+                                        deliveries.on_hover_text(p.deliveries.join("\n"));
+                                    }
+                                    ui.end_row();
+                                }
+                            });
+                    });
+            });
+
+        if let Some(short_name) = picked {
+            let projects_dir = self.config.projects_dir.clone().unwrap_or_default();
+            let projects = client_projects(&self.projects, &short_name, &projects_dir);
+            self.client_view = Some((short_name, projects));
+        }
+        if let Some(project) = open_project {
+            self.pending_open = Some(OpenTarget {
+                project: project.name,
+                task: None,
+            });
+        }
+        if !open {
+            self.client_view = None;
         }
-
-        ui.add_space(SPACING);
-        ui.add_space(SPACING);
     }
 
-    fn open_create_folder(&mut self) {
-        self.show_create_folder = true;
-        self.show_create_project = false;
-        self.show_create_task = false;
-    }
-    fn open_create_task(&mut self) {
-        self.show_create_folder = false;
-        self.show_create_project = false;
-        self.show_create_task = true;
-    }
-    fn open_or_close_create_project(&mut self) {
-        self.show_create_project = !self.show_create_project;
-        self.show_create_folder = false;
-        self.show_create_task = false;
-    }
     fn open_manage_clients(&mut self) {
         self.show_clients_panel = !self.show_clients_panel;
     }
-}
-
-impl eframe::App for Rclamp {
-    /// Called each time the UI needs repainting, which may be many times per second.
-    ///
-    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
-        if self.config.dark_mode {
-            ctx.set_visuals(egui::Visuals::dark());
-        } else {
-            ctx.set_visuals(egui::Visuals::light());
-        }
-
-        egui::TopBottomPanel::top("menu_panel").show(ctx, |ui| {
-            // The top panel is often a good place for a menu bar:
-            ui.add_space(SPACING);
-            self.render_top_bar(ui, frame);
-            ui.add_space(SPACING);
-        });
-
-        if self.show_create_project {
-            egui::TopBottomPanel::top("create_project_panel").show(ctx, |ui| {
-                self.create_project_dialog(ui, ctx, frame);
-            });
-        }
 
-        if self.show_clients_panel {
-            egui::TopBottomPanel::bottom("manage_clients_panel").show(ctx, |ui| {
-                self.manage_clients_panel(ui);
+    /// The left panel with the project list.
+    /// The latest log lines, so problems can be looked into without relaunching from a terminal with
+    /// `RUST_LOG` set.
+    fn log_panel(&mut self, ctx: &egui::Context) {
+        egui::TopBottomPanel::bottom("log_panel")
+            .resizable(true)
+            .default_height(200.)
+            .show(ctx, |ui| {
+                let lines: Vec<LogLine> = recent_lines()
+                    .into_iter()
+                    .filter(|l| l.level <= self.log_level)
+                    .collect();
+                ui.add_space(SPACING);
+                ui.horizontal(|ui| {
+                    if ui.small_button("▼").on_hover_text(tr("Hide log")).clicked() {
+                        self.layout.show_log = false;
+                    }
+                    ui.strong(tr("Log"));
+                    ui.selectable_value(&mut self.log_level, LevelFilter::Error, tr("Errors"));
+                    ui.selectable_value(&mut self.log_level, LevelFilter::Warn, tr("Warnings"));
+                    ui.selectable_value(&mut self.log_level, LevelFilter::Info, tr("All"));
+                    ui.separator();
+                    if ui.button(tr("Copy")).clicked() {
+                        ui.output_mut(|o| o.copied_text = format_lines(&lines));
+                    }
+                    ui.add(
+                        egui::TextEdit::singleline(&mut self.log_path)
+                            .hint_text("rclamp.log")
+                            .desired_width(TEXTEDIT_WIDTH),
+                    );
+                    let path = PathBuf::from(self.log_path.trim());
+                    if ui
+                        .add_enabled(
+                            !self.log_path.trim().is_empty(),
+                            egui::Button::new(tr("Save")),
+                        )
+                        .clicked()
+                    {
+                        match fs::write(&path, format_lines(&lines)) {
+                            Ok(()) => self.notifications.push(
+                                Severity::Info,
+                                trf("Saved the log to {}", &[&path.display()]),
+                            ),
+                            Err(e) => {
+                                error!("Failed to save the log to {}: {}", path.display(), e);
+                                self.notifications
+                                    .push(Severity::Error, trf("Error saving the log: {}", &[&e]));
+                            }
+                        }
+                    }
+                    if ui.button(tr("Clear")).clicked() {
+                        clear_lines();
+                    }
+                });
+                ui.separator();
+                egui::ScrollArea::vertical()
+                    .stick_to_bottom(true)
+                    .auto_shrink([false, false])
+                    .show(ui, |ui| {
+                        if lines.is_empty() {
+                            ui.label(tr("Nothing logged."));
+                        }
+                        for line in &lines {
+                            let text = egui::RichText::new(line.to_string()).monospace();
+                            ui.label(match line.level {
+                                Level::Error => text.color(Severity::Error.color()),
+                                Level::Warn => text.color(Severity::Warning.color()),
+                                _ => text,
+                            });
+                        }
+                    });
             });
-        }
+    }
 
-        egui::SidePanel::left("first_left_panel").show(ctx, |ui| {
+    fn projects_panel(&mut self, ctx: &egui::Context) {
+        let panel = egui::SidePanel::left("first_left_panel")
+            .resizable(true)
+            .default_width(self.layout.projects_width);
+        let response = panel.show(ctx, |ui| {
             // Left panel
             ui.add_space(SPACING);
             ui.with_layout(egui::Layout::left_to_right(egui::Align::LEFT), |ui| {
-                ui.label(format!("Filter"));
+                if ui
+                    .small_button("◀")
+                    .on_hover_text(tr("Hide projects"))
+                    .clicked()
+                {
+                    self.layout.show_projects = false;
+                }
+                ui.label(tr("Filter"));
                 let filter_edit = ui.add(
                     egui::TextEdit::singleline(&mut self.project_filter)
                         .desired_width(TEXTEDIT_WIDTH),
@@ -1196,23 +8015,40 @@ impl eframe::App for Rclamp {
                     self.filter_projects(self.project_filter.clone());
                 }
             });
+            ui.horizontal(|ui| {
+                self.project_list_options(ui);
+            });
             ui.add(egui::Separator::default());
             ui.add_space(SPACING);
             egui::ScrollArea::vertical().show(ui, |ui| {
                 self.render_projects(ui);
             });
         });
+        self.layout.projects_width = response.response.rect.width();
+    }
 
-        egui::SidePanel::left("second_left_panel").show(ctx, |ui| {
+    /// The middle panel with the task tree of the current project.
+    fn tasks_panel(&mut self, ctx: &egui::Context) {
+        let panel = egui::SidePanel::left("second_left_panel")
+            .resizable(true)
+            .default_width(self.layout.tasks_width);
+        let response = panel.show(ctx, |ui| {
             // Middle panel
             ui.add_space(SPACING);
             ui.with_layout(egui::Layout::left_to_right(egui::Align::LEFT), |ui| {
+                if ui
+                    .small_button("◀")
+                    .on_hover_text(tr("Hide tasks"))
+                    .clicked()
+                {
+                    self.layout.show_tasks = false;
+                }
                 let project_name = match &self.current_project {
                     Some(p) => p.name.clone(),
                     None => String::new(),
                 };
 
-                ui.strong(format!("Current project: {}", project_name));
+                ui.strong(trf("Current project: {}", &[&project_name]));
             });
             ui.add(egui::Separator::default());
             ui.add_space(SPACING);
@@ -1239,28 +8075,463 @@ impl eframe::App for Rclamp {
                 self.render_task_tree(ui);
             });
         });
+        self.layout.tasks_width = response.response.rect.width();
+    }
 
-        egui::CentralPanel::default().show(ctx, |ui| {
-            // Right panel
+    /// The workfiles and outputs tabs, in the central panel or the detached window.
+    fn files_view(&mut self, ui: &mut egui::Ui) {
+        ui.horizontal(|ui| {
+            ui.selectable_value(
+                &mut self.central_tab,
+                CentralTab::Workfiles,
+                tr("Workfiles"),
+            );
+            ui.selectable_value(&mut self.central_tab, CentralTab::Outputs, tr("Outputs"));
+            ui.selectable_value(
+                &mut self.central_tab,
+                CentralTab::Publishes,
+                tr("Publishes"),
+            );
+            ui.selectable_value(&mut self.central_tab, CentralTab::Schedule, tr("Schedule"));
+            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                if !self.layout.detach_files
+                    && ui
+                        .small_button("⧉")
+                        .on_hover_text(tr("Show in a separate window"))
+                        .clicked()
+                {
+                    self.layout.detach_files = true;
+                }
+            });
+        });
+        ui.add(egui::Separator::default());
+        if self.files.is_none() && self.central_tab != CentralTab::Schedule {
+            if let Some(task) = self.current_task.clone() {
+                if ui.button(tr("Load files")).clicked() {
+                    self.set_current_task(task);
+                }
+            }
+        }
 
-            let task_name = match &self.current_task {
-                Some(t) => t.name.clone(),
-                None => String::new(),
-            };
+        match self.central_tab {
+            CentralTab::Workfiles => {
+                if self.can_edit() {
+                    self.create_file_dialog(ui);
+                }
+                let tags = self.file_notes.tags();
+                ui.horizontal(|ui| {
+                    if !tags.is_empty() {
+                        ui.label(tr("Tag"));
+                        egui::ComboBox::from_id_source("file_tag_filter")
+                            .selected_text(
+                                self.file_tag_filter.clone().unwrap_or_else(|| tr("All")),
+                            )
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut self.file_tag_filter, None, tr("All"));
+                                for t in tags {
+                                    ui.selectable_value(
+                                        &mut self.file_tag_filter,
+                                        Some(t.clone()),
+                                        t,
+                                    );
+                                }
+                            });
+                    }
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if ui
+                            .add_enabled(
+                                self.can_edit()
+                                    && self.files.as_ref().map_or(false, |f| !f.is_empty()),
+                                egui::Button::new(tr("Recover autosaves…")),
+                            )
+                            .clicked()
+                        {
+                            self.refresh_autosaves();
+                        }
+                    });
+                });
+                ui.add(egui::Separator::default());
+                ui.add_space(SPACING);
 
-            ui.strong(format!("Current task: {}", task_name));
-            ui.add(egui::Separator::default());
-            self.create_file_dialog(ui);
-            ui.add(egui::Separator::default());
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    self.files_table(ui);
+                });
+            }
+            CentralTab::Outputs => {
+                #[cfg(feature = "deliveries")]
+                if self.can_edit() && ui.button(tr("Package delivery…")).clicked() {
+                    self.open_package_delivery();
+                }
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    self.outputs_table(ui);
+                });
+            }
+            CentralTab::Publishes => {
+                ui.horizontal(|ui| {
+                    ui.label(tr("Status"));
+                    let label = |s: Option<ReviewState>| match s {
+                        Some(s) => tr(&format!("{:?}", s)),
+                        None => tr("All"),
+                    };
+                    egui::ComboBox::from_id_source("publish_state_filter")
+                        .selected_text(label(self.publish_state_filter))
+                        .show_ui(ui, |ui| {
+                            ui.selectable_value(&mut self.publish_state_filter, None, label(None));
+                            for s in ReviewState::ALL {
+                                ui.selectable_value(
+                                    &mut self.publish_state_filter,
+                                    Some(s),
+                                    label(Some(s)),
+                                );
+                            }
+                        });
+                });
+                ui.add(egui::Separator::default());
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    self.publishes_table(ui);
+                });
+            }
+            CentralTab::Schedule => self.schedule_view(ui),
+        }
+    }
+
+    /// Collects the due dates of all projects, and the due dates and milestones of the tasks of the
+    /// current project.
+    fn refresh_schedule(&mut self) {
+        let mut items = project_items(&self.projects);
+        if let (Some(project), Some(tree)) =
+            (&self.current_project, &self.current_project_task_tree)
+        {
+            let tasks: Vec<(String, PathBuf, TaskDetails)> = tree
+                .tasks()
+                .into_iter()
+                .filter_map(|t| Some((t.name.clone(), t.path.clone(), t.details().ok()?)))
+                .collect();
+            items.extend(task_items(&project.name, &tasks));
+        }
+        let key = self.current_project.as_ref().map(|p| p.name.clone());
+        self.schedule = Some((key, items));
+    }
+
+    /// Due dates and milestones by month, overdue ones first.
+    fn schedule_view(&mut self, ui: &mut egui::Ui) {
+        let key = self.current_project.as_ref().map(|p| p.name.clone());
+        let mut stale = !matches!(&self.schedule, Some((k, _)) if *k == key);
+        ui.horizontal(|ui| {
+            if ui.button("🔄").on_hover_text(tr("Refresh")).clicked() {
+                stale = true;
+            }
+        });
+        if stale {
+            self.refresh_schedule();
+        }
+        ui.add(egui::Separator::default());
+
+        let items = self.schedule.clone().map(|(_k, i)| i).unwrap_or_default();
+        if items.is_empty() {
+            ui.weak(tr("No due dates or milestones."));
+            return;
+        }
+        let today = (now_timestamp() / 86400) as i64;
+        let mut open: Option<ScheduleItem> = None;
+        egui::ScrollArea::vertical().show(ui, |ui| {
+            for (month, group) in group_by_month(items, today) {
+                ui.add_space(SPACING);
+                ui.strong(month.unwrap_or_else(|| tr("Overdue")));
+                for item in group {
+                    ui.horizontal(|ui| {
+                        ui.monospace(&item.date);
+                        ui.label(match item.kind {
+                            ScheduleKind::Project => "📁",
+                            ScheduleKind::Task => "📝",
+                            ScheduleKind::Milestone => "◆",
+                        });
+                        let mut text = egui::RichText::new(&item.name);
+                        if item.done {
+                            text = text.weak();
+                        } else if item.is_overdue(today) {
+                            text = text.color(Color32::RED);
+                        } else if item.is_upcoming(today) {
+                            text = text.color(Color32::from_rgb(230, 170, 40));
+                        }
+                        if ui.link(text).on_hover_text(&item.project).clicked() {
+                            open = Some(item.clone());
+                        }
+                    });
+                }
+            }
+        });
+
+        match open {
+            Some(ScheduleItem {
+                task: Some(path), ..
+            }) => {
+                self.reveal_node = Some(path.clone());
+                let node = self
+                    .current_project_task_tree
+                    .as_ref()
+                    .and_then(|t| t.path_to(&path).last().map(|n| (*n).clone()));
+                if let Some(node) = node {
+                    self.set_current_task(node);
+                }
+            }
+            Some(item) => {
+                let project = self
+                    .projects
+                    .iter()
+                    .find(|p| p.name == item.project)
+                    .cloned();
+                if let Some(project) = project {
+                    self.open_project(project, ui);
+                }
+            }
+            None => (),
+        }
+    }
+}
+
+impl eframe::App for Rclamp {
+    /// Called each time the UI needs repainting, which may be many times per second.
+    ///
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        ctx.set_visuals(self.config.theme.visuals(self.config.dark_mode));
+        self.handle_ipc_events();
+        self.handle_finished_jobs();
+        self.poll_connectivity();
+        if self.connectivity.is_offline() {
+            ctx.request_repaint_after(std::time::Duration::from_secs(RECONNECT_INTERVAL));
+        } else {
+            // Sessions and the inbox are on the share too, they wait until it's back.
+            self.heartbeat();
+            if self.session.is_some() {
+                ctx.request_repaint_after(std::time::Duration::from_secs(HEARTBEAT_INTERVAL));
+            }
+            self.poll_inbox();
+            if self.config.ingest.inbox.is_some() {
+                ctx.request_repaint_after(std::time::Duration::from_secs(INBOX_SCAN_INTERVAL));
+            }
+        }
+        if let Some(j) = &self.jobs {
+            if j.is_busy() {
+                ctx.request_repaint_after(std::time::Duration::from_millis(250));
+            }
+        }
+
+        egui::TopBottomPanel::top("menu_panel").show(ctx, |ui| {
+            // The top panel is often a good place for a menu bar:
+            ui.add_space(SPACING);
+            self.render_top_bar(ui, frame);
             ui.add_space(SPACING);
+            if let Some(target) = self.pending_open.take() {
+                self.open_target(target, ui);
+            }
+            if let Some((project, task)) = self.last_session.take() {
+                self.reopen_last_session(&project, task.as_deref(), ui);
+            }
+        });
 
-            egui::ScrollArea::vertical().show(ui, |ui| {
-                self.files_table(ui);
+        if self.show_create_project {
+            egui::TopBottomPanel::top("create_project_panel").show(ctx, |ui| {
+                self.create_project_dialog(ui, ctx, frame);
+            });
+        }
+
+        if self.show_clients_panel {
+            egui::TopBottomPanel::bottom("manage_clients_panel").show(ctx, |ui| {
+                self.manage_clients_panel(ui);
+            });
+        }
+
+        if self
+            .jobs
+            .as_ref()
+            .map_or(false, |j| !j.statuses().is_empty())
+        {
+            egui::TopBottomPanel::bottom("jobs_panel").show(ctx, |ui| {
+                self.jobs_panel(ui);
             });
+        }
+
+        if self.show_create_structure {
+            self.create_structure_window(ctx);
+        }
+
+        if self.show_import_tasks {
+            self.import_tasks_window(ctx);
+        }
+
+        if self.health_check.is_some() {
+            self.health_window(ctx);
+        }
+
+        if self.edit_project_access.is_some() {
+            self.project_access_window(ctx);
+        }
+
+        if self.show_adopt_project {
+            self.adopt_project_window(ctx);
+        }
+
+        if self.duplicate_project.is_some() {
+            self.duplicate_project_window(ctx);
+        }
+        if self.edit_project_metadata.is_some() {
+            self.edit_project_metadata_window(ctx);
+        }
+        if self.compare_versions.is_some() {
+            self.compare_versions_window(ctx);
+        }
+
+        if self.client_view.is_some() {
+            self.client_view_window(ctx);
+        }
+
+        if self.remote_projects.is_some() {
+            self.restore_project_window(ctx);
+        }
+
+        if self.mirror_log.is_some() {
+            self.mirror_log_window(ctx);
+        }
+
+        if self.naming_lint.is_some() {
+            self.naming_lint_window(ctx);
+        }
+
+        if self.batch_version_up.is_some() {
+            self.batch_version_up_window(ctx);
+        }
+
+        if self.footage_ingest.is_some() {
+            self.footage_ingest_window(ctx);
+        }
+
+        if self.templates_manager.is_some() {
+            self.templates_window(ctx);
+        }
+
+        if self.inbox.show {
+            self.inbox_window(ctx);
+        }
+
+        #[cfg(feature = "deliveries")]
+        if self.delivery_package.is_some() {
+            self.package_delivery_window(ctx);
+        }
+
+        #[cfg(feature = "deliveries")]
+        if self.delivery_note.is_some() {
+            self.delivery_note_window(ctx);
+        }
+
+        if self.file_transfer.is_some() {
+            self.file_transfer_window(ctx);
+        }
+
+        if self.duplicate_file.is_some() {
+            self.duplicate_file_window(ctx);
+        }
+
+        if self.stale_action.is_some() {
+            self.stale_version_window(ctx);
+        }
+        if self.open_with.is_some() {
+            self.open_with_window(ctx);
+        }
+
+        if self.newer_app_version.is_some() {
+            self.newer_app_version_window(ctx);
+        }
+
+        if self.crash_report.is_some() {
+            self.crash_report_window(ctx);
+        }
+
+        if !self.setup_problems.is_empty() {
+            self.setup_problems_window(ctx);
+        }
+
+        if self.autosaves.is_some() {
+            self.autosaves_window(ctx);
+        }
+
+        if self.edit_note.is_some() {
+            self.file_note_window(ctx);
+        }
+
+        if self.review_publish.is_some() {
+            self.review_window(ctx);
+        }
+
+        if self.show_trash {
+            self.trash_window(ctx);
+        }
+
+        if self.show_preferences {
+            self.preferences_window(ctx);
+        }
+
+        if self.show_notifications {
+            let mut open = true;
+            self.notifications.history_window(ctx, &mut open);
+            self.show_notifications = open;
+        }
+        self.notifications.show(ctx);
+
+        if self.layout.show_log {
+            self.log_panel(ctx);
+            // Lines logged by background threads don't trigger a repaint.
+            ctx.request_repaint_after(std::time::Duration::from_secs(1));
+        }
+        if self.layout.show_projects {
+            self.projects_panel(ctx);
+        }
+        if self.layout.show_tasks {
+            self.tasks_panel(ctx);
+        }
+
+        egui::CentralPanel::default().show(ctx, |ui| {
+            // Right panel
+
+            self.breadcrumb_bar(ui);
+            ui.add(egui::Separator::default());
+            if self.current_task.is_some() {
+                self.task_details_pane(ui);
+                ui.add(egui::Separator::default());
+            }
+            if self.layout.detach_files {
+                ui.label(tr("The files are shown in a separate window."));
+                if ui.button(tr("Attach")).clicked() {
+                    self.layout.detach_files = false;
+                }
+            } else {
+                self.files_view(ui);
+            }
         });
+
+        if self.layout.detach_files {
+            let mut open = true;
+            egui::Window::new(tr("Files"))
+                .open(&mut open)
+                .default_size([800., 500.])
+                .resizable(true)
+                .show(ctx, |ui| {
+                    self.files_view(ui);
+                });
+            self.layout.detach_files = open;
+        }
+
+        if self.handle_commands() {
+            ctx.request_repaint();
+        }
     }
 
     fn save(&mut self, storage: &mut dyn eframe::Storage) {
-        eframe::set_value(storage, eframe::APP_KEY, self);
+        eframe::set_value(storage, eframe::APP_KEY, &self.ui_prefs());
+    }
+
+    fn on_close_event(&mut self) -> bool {
+        self.end_session();
+        true
     }
 }
@@ -0,0 +1,345 @@
+use crate::jobs::JobHandle;
+use crate::vfs::{Fs, RealFs};
+use log::info;
+use std::fs;
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use zip::write::FileOptions;
+use zip::{CompressionMethod, ZipWriter};
+
+const BUFFER_SIZE: usize = 1024 * 1024;
+
+/// A path to archive, its name in the archive and whether it's a dir.
+type Entry = (PathBuf, String, bool);
+
+#[derive(Clone, Copy, serde::Deserialize, serde::Serialize, Debug, Default, PartialEq)]
+pub enum ArchiveFormat {
+    #[default]
+    #[serde(rename = "zip")]
+    Zip,
+    /// Needs the 7z binary.
+    #[serde(rename = "7z")]
+    SevenZip,
+}
+
+impl ArchiveFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ArchiveFormat::Zip => "zip",
+            ArchiveFormat::SevenZip => "7z",
+        }
+    }
+}
+
+/// How delivery packages and projects are archived.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct ArchiveSettings {
+    pub format: ArchiveFormat,
+    /// Archives are split into volumes of this size, e.g. for upload size limits. 0 doesn't split.
+    pub split_size_mb: u64,
+    /// Set from `seven_zip_win` or `seven_zip_mac`.
+    #[serde(skip)]
+    pub seven_zip: Option<PathBuf>,
+}
+
+impl ArchiveSettings {
+    /// The archive of the dir next to it, e.g. `<dir>.zip`. Split archives have volumes named like
+    /// `<dir>.zip.001`.
+    pub fn archive_path(&self, dir: &Path) -> PathBuf {
+        let mut name = dir.file_name().unwrap_or_default().to_os_string();
+        name.push(".");
+        name.push(self.format.extension());
+        dir.with_file_name(name)
+    }
+
+    fn split_size(&self) -> Option<u64> {
+        Some(self.split_size_mb * 1024 * 1024).filter(|s| *s > 0)
+    }
+
+    /// Archives the dir next to it and returns the archive files. Meant to run as a job.
+    pub fn create(&self, dir: &Path, job: &JobHandle) -> Result<Vec<PathBuf>, String> {
+        let target = self.archive_path(dir);
+        if target.exists() || volume_path(&target, 1).exists() {
+            return Err(format!("{} already exists.", target.display()));
+        }
+        info!("Archiving {} to {}", dir.display(), target.display());
+        match self.format {
+            ArchiveFormat::Zip => {
+                let mut progress = |done, total: u64| {
+                    job.set_progress(done as f32 / total.max(1) as f32);
+                    !job.is_cancelled()
+                };
+                match self.split_size() {
+                    Some(size) => create_split_zip(dir, &target, size, &mut progress),
+                    None => create_zip(dir, &target, &mut progress).map(|()| vec![target]),
+                }
+                .map_err(|e| format!("Failed to archive {}: {}", dir.display(), e))
+            }
+            ArchiveFormat::SevenZip => match &self.seven_zip {
+                Some(seven_zip) => create_7z(seven_zip, dir, &target, self.split_size(), job),
+                None => Err(String::from("7z archives need seven_zip in the config.")),
+            },
+        }
+    }
+}
+
+/// The numbered volume of a split archive, counted from 1.
+pub fn volume_path(archive: &Path, number: u32) -> PathBuf {
+    let mut name = archive.as_os_str().to_os_string();
+    name.push(format!(".{:03}", number));
+    PathBuf::from(name)
+}
+
+/// The files and dirs under the dir, with their names in the archive: the dir name and the relative path
+/// with forward slashes.
+fn collect_entries(root: &Path, dir: &Path, entries: &mut Vec<Entry>) -> Result<(), io::Error> {
+    for path in RealFs.read_dir(dir)? {
+        let name = path
+            .strip_prefix(root.parent().unwrap_or(root))
+            .unwrap_or(&path)
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect::<Vec<_>>()
+            .join("/");
+        if path.is_dir() {
+            entries.push((path.clone(), name, true));
+            collect_entries(root, &path, entries)?;
+        } else {
+            entries.push((path, name, false));
+        }
+    }
+    Ok(())
+}
+
+/// The entries of the dir, see `collect_entries`, and the size of its files.
+fn zip_entries(dir: &Path) -> Result<(Vec<Entry>, u64), io::Error> {
+    let mut entries = Vec::new();
+    collect_entries(dir, dir, &mut entries)?;
+    let total: u64 = entries
+        .iter()
+        .filter(|(_, _, is_dir)| !is_dir)
+        .filter_map(|(p, _, _)| fs::metadata(p).ok())
+        .map(|m| m.len())
+        .sum();
+    Ok((entries, total))
+}
+
+/// Zips the dir with its name as the top dir in the archive. The progress is called with the bytes
+/// read so far and in total, returning false cancels and removes the archive.
+pub fn create_zip(
+    dir: &Path,
+    target: &Path,
+    progress: &mut dyn FnMut(u64, u64) -> bool,
+) -> Result<(), io::Error> {
+    let (entries, total) = zip_entries(dir)?;
+    let file = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(target)?;
+    let result = write_zip(file, &entries, total, progress).and_then(|f| f.sync_all());
+    if result.is_err() {
+        let _ = fs::remove_file(target);
+    }
+    result
+}
+
+/// Like `create_zip`, but written as numbered volumes of the size, unless it fits in one. Split zips are
+/// opened by 7-Zip, or joined with `cat` or `copy /b` first. Returns the archive files, the volumes
+/// written so far are removed on errors.
+pub fn create_split_zip(
+    dir: &Path,
+    target: &Path,
+    volume_size: u64,
+    progress: &mut dyn FnMut(u64, u64) -> bool,
+) -> Result<Vec<PathBuf>, io::Error> {
+    if target.exists() || volume_path(target, 1).exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists.", target.display()),
+        ));
+    }
+    let (entries, total) = zip_entries(dir)?;
+    let volumes = VolumeWriter::new(target, volume_size);
+    let result = write_zip(volumes, &entries, total, progress).and_then(|v| v.finish());
+    if result.is_err() {
+        remove_archive(target);
+    }
+    result
+}
+
+fn write_zip<W: Write + Seek>(
+    writer: W,
+    entries: &[Entry],
+    total: u64,
+    progress: &mut dyn FnMut(u64, u64) -> bool,
+) -> Result<W, io::Error> {
+    let mut zip = ZipWriter::new(writer);
+    let mut buffer = vec![0; BUFFER_SIZE];
+    let mut done = 0;
+    for (path, name, is_dir) in entries {
+        let options = FileOptions::default().compression_method(CompressionMethod::Deflated);
+        if *is_dir {
+            zip.add_directory(name.as_str(), options)?;
+            continue;
+        }
+        let mut source = fs::File::open(path)?;
+        let size = source.metadata()?.len();
+        zip.start_file(name.as_str(), options.large_file(size >= u32::MAX as u64))?;
+        loop {
+            let read = source.read(&mut buffer)?;
+            if read == 0 {
+                break;
+            }
+            zip.write_all(&buffer[..read])?;
+            done += read as u64;
+            if !progress(done, total) {
+                return Err(io::Error::new(
+                    io::ErrorKind::Interrupted,
+                    "Archiving cancelled.",
+                ));
+            }
+        }
+    }
+    Ok(zip.finish()?)
+}
+
+/// Writes numbered volumes of a fixed size as if they were one file, so a split archive doesn't need
+/// room for the whole archive first. The zip writer seeks back to fill in the file headers, which may
+/// write into earlier volumes.
+struct VolumeWriter {
+    archive: PathBuf,
+    volume_size: u64,
+    volumes: Vec<fs::File>,
+    position: u64,
+    length: u64,
+}
+
+impl VolumeWriter {
+    fn new(archive: &Path, volume_size: u64) -> Self {
+        Self {
+            archive: archive.to_path_buf(),
+            volume_size: volume_size.max(1),
+            volumes: Vec::new(),
+            position: 0,
+            length: 0,
+        }
+    }
+
+    /// Syncs the volumes and returns their paths. A single volume is renamed to the archive, since it
+    /// isn't split.
+    fn finish(self) -> Result<Vec<PathBuf>, io::Error> {
+        for volume in &self.volumes {
+            volume.sync_all()?;
+        }
+        let paths: Vec<PathBuf> = (1..=self.volumes.len() as u32)
+            .map(|n| volume_path(&self.archive, n))
+            .collect();
+        if paths.len() == 1 {
+            fs::rename(&paths[0], &self.archive)?;
+            return Ok(vec![self.archive]);
+        }
+        Ok(paths)
+    }
+}
+
+impl Write for VolumeWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let index = (self.position / self.volume_size) as usize;
+        let offset = self.position % self.volume_size;
+        while self.volumes.len() <= index {
+            let path = volume_path(&self.archive, self.volumes.len() as u32 + 1);
+            let volume = fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&path)?;
+            self.volumes.push(volume);
+        }
+        let length = buf.len().min((self.volume_size - offset) as usize);
+        let volume = &mut self.volumes[index];
+        volume.seek(SeekFrom::Start(offset))?;
+        let written = volume.write(&buf[..length])?;
+        self.position += written as u64;
+        self.length = self.length.max(self.position);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        for volume in &mut self.volumes {
+            volume.flush()?;
+        }
+        Ok(())
+    }
+}
+
+impl Seek for VolumeWriter {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let (base, delta) = match pos {
+            SeekFrom::Start(p) => (p as i128, 0),
+            SeekFrom::Current(d) => (self.position as i128, d as i128),
+            SeekFrom::End(d) => (self.length as i128, d as i128),
+        };
+        let position = base + delta;
+        if position < 0 || position > u64::MAX as i128 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "Seek outside the archive.",
+            ));
+        }
+        self.position = position as u64;
+        Ok(self.position)
+    }
+}
+
+/// Archives the dir with the 7z binary, reading its progress. Cancelling kills 7z.
+fn create_7z(
+    seven_zip: &Path,
+    dir: &Path,
+    target: &Path,
+    split_size: Option<u64>,
+    job: &JobHandle,
+) -> Result<Vec<PathBuf>, String> {
+    let mut command = Command::new(seven_zip);
+    command.args(["a", "-t7z", "-bsp1", "-bso0", "-bse2"]);
+    if let Some(size) = split_size {
+        command.arg(format!("-v{}b", size));
+    }
+    let child = command
+        .arg(target)
+        .arg(dir)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|e| format!("Failed to start 7z: {}", e))?;
+    match job.wait_reading_percent(child, "7z") {
+        Ok(()) => Ok(archive_files(target)),
+        Err(e) => {
+            remove_archive(target);
+            Err(e)
+        }
+    }
+}
+
+/// The archive, or its volumes if it's split.
+pub fn archive_files(archive: &Path) -> Vec<PathBuf> {
+    if archive.exists() {
+        return vec![archive.to_path_buf()];
+    }
+    (1..)
+        .map(|n| volume_path(archive, n))
+        .take_while(|p| p.exists())
+        .collect()
+}
+
+/// Removes the archive and its volumes, e.g. what's left of an interrupted archive.
+pub fn remove_archive(archive: &Path) {
+    let _ = fs::remove_file(archive);
+    for volume in archive_files(archive) {
+        let _ = fs::remove_file(volume);
+    }
+}
@@ -0,0 +1,174 @@
+use crate::helpers::{current_host, current_user, random_hex, sanitize_string};
+use crate::retry::retry;
+use log::{error, warn};
+use serde::Serialize;
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime};
+
+const LOCK_RETRIES: u32 = 50;
+const LOCK_RETRY_DELAY: Duration = Duration::from_millis(100);
+/// Writes take well under a second, so older locks are left over from a crash and are taken over.
+const STALE_LOCK_AGE: Duration = Duration::from_secs(30);
+
+/// An advisory lock on a file on shared storage, held as `.<name>.lock` next to it until dropped.
+/// Wrap read-modify-write cycles in a lock, so concurrent users don't overwrite each other's changes.
+pub struct FileLock {
+    path: PathBuf,
+    /// Written into the lock file, so it's only released by the one holding it.
+    owner: String,
+}
+
+impl FileLock {
+    /// Takes the lock, retrying for a few seconds while another user holds it.
+    pub fn acquire(path: &Path) -> Result<FileLock, io::Error> {
+        let lock_path = sibling_path(path, "lock");
+        let owner = format!("{}@{} {}", current_user(), current_host(), random_hex());
+        for _ in 0..LOCK_RETRIES {
+            match fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(&lock_path)
+            {
+                Ok(mut f) => {
+                    f.write_all(owner.as_bytes())?;
+                    f.sync_all()?;
+                    // Someone taking over what they thought was a stale lock may have replaced it.
+                    if read_owner(&lock_path).as_deref() == Some(owner.as_str()) {
+                        return Ok(FileLock {
+                            path: lock_path,
+                            owner,
+                        });
+                    }
+                    warn!("Lost lock to another user: {}", lock_path.display());
+                    thread::sleep(LOCK_RETRY_DELAY);
+                }
+                Err(e) if e.kind() == io::ErrorKind::AlreadyExists => {
+                    if is_stale(&lock_path) {
+                        take_over(&lock_path);
+                        continue;
+                    }
+                    thread::sleep(LOCK_RETRY_DELAY);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+
+        let owner = read_owner(&lock_path).unwrap_or_default();
+        let message = format!("{} is locked by {}", path.display(), owner);
+        error!("{}", message);
+        Err(io::Error::new(io::ErrorKind::WouldBlock, message))
+    }
+}
+
+impl Drop for FileLock {
+    fn drop(&mut self) {
+        if read_owner(&self.path).as_deref() != Some(self.owner.as_str()) {
+            warn!(
+                "Lock was taken over before release: {}",
+                self.path.display()
+            );
+            return;
+        }
+        if let Err(e) = fs::remove_file(&self.path) {
+            error!("Failed to release lock {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+fn read_owner(lock_path: &Path) -> Option<String> {
+    fs::read_to_string(lock_path).ok()
+}
+
+fn is_stale(lock_path: &Path) -> bool {
+    fs::metadata(lock_path)
+        .and_then(|m| m.modified())
+        .ok()
+        .and_then(|t| SystemTime::now().duration_since(t).ok())
+        .map_or(false, |age| age > STALE_LOCK_AGE)
+}
+
+/// Removes a stale lock. It's renamed away first, which only one of several users can do, and put back
+/// if it turns out to have been replaced by a new lock in the meantime.
+fn take_over(lock_path: &Path) {
+    let owner = read_owner(lock_path);
+    let taken = sibling_path(lock_path, &format!("{}.stale", unique_suffix()));
+    if fs::rename(lock_path, &taken).is_err() {
+        return;
+    }
+    if read_owner(&taken) == owner && is_stale(&taken) {
+        warn!("Took over stale lock: {}", lock_path.display());
+        let _ = fs::remove_file(&taken);
+    } else if !lock_path.exists() {
+        let _ = fs::rename(&taken, lock_path);
+    } else {
+        let _ = fs::remove_file(&taken);
+    }
+}
+
+/// Tells apart the temp files of processes on different machines writing to the same share.
+fn unique_suffix() -> String {
+    format!(
+        "{}-{}-{}",
+        sanitize_string(current_host()),
+        std::process::id(),
+        &random_hex()[..8]
+    )
+}
+
+/// A hidden file next to the path, e.g. `.clients.yaml.lock`.
+fn sibling_path(path: &Path, extension: &str) -> PathBuf {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!(".{}.{}", name, extension))
+}
+
+/// Writes the value as YAML to a temp file next to the path, then renames it over the path.
+/// Readers see either the old or the new file, never a partly written one. Doesn't lock.
+pub fn write_atomic<T: Serialize>(path: &Path, value: &T) -> Result<(), io::Error> {
+    write_bytes_atomic(path, to_yaml(value)?.as_bytes())
+}
+
+fn to_yaml<T: Serialize>(value: &T) -> Result<String, io::Error> {
+    serde_yaml::to_string(value).map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))
+}
+
+/// Like `write_atomic`, with the contents written as they are. Transient errors are retried.
+pub fn write_bytes_atomic(path: &Path, contents: &[u8]) -> Result<(), io::Error> {
+    let temp_path = sibling_path(path, &format!("{}.tmp", unique_suffix()));
+    let result = retry(|| {
+        let mut file = fs::File::create(&temp_path)?;
+        file.write_all(contents)?;
+        file.sync_all()?;
+        fs::rename(&temp_path, path)
+    });
+    if let Err(e) = &result {
+        error!("Failed to write {}: {}", path.display(), e);
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// Locks the path and replaces it atomically with the value as YAML.
+pub fn write_yaml<T: Serialize>(path: &Path, value: &T) -> Result<(), io::Error> {
+    let _lock = FileLock::acquire(path)?;
+    write_atomic(path, value)
+}
+
+/// Like `write_yaml`, but fails with `AlreadyExists` if the file exists.
+pub fn create_yaml<T: Serialize>(path: &Path, value: &T) -> Result<(), io::Error> {
+    create_bytes(path, to_yaml(value)?.as_bytes())
+}
+
+/// Like `create_yaml`, with the contents written as they are.
+pub fn create_bytes(path: &Path, contents: &[u8]) -> Result<(), io::Error> {
+    let _lock = FileLock::acquire(path)?;
+    if path.exists() {
+        return Err(io::Error::new(
+            io::ErrorKind::AlreadyExists,
+            format!("{} already exists", path.display()),
+        ));
+    }
+    write_bytes_atomic(path, contents)
+}
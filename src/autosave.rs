@@ -0,0 +1,136 @@
+use crate::vfs::{Fs, RealFs};
+use crate::workfiles::{Dcc, File, FileGroup};
+use log::info;
+use std::env;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// An autosave of a workfile in the current task, found in the dir of the workfiles or in one of the
+/// autosave dirs of its DCC.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Autosave {
+    pub path: PathBuf,
+    /// Seconds since the unix epoch.
+    pub modified: u64,
+    /// The latest version of the workfile the autosave is of.
+    pub workfile: File,
+}
+
+/// Expands a leading `~` to the home dir and `$NAME` components to environment variables. None if a
+/// variable isn't set.
+pub fn expand_dir(dir: &Path) -> Option<PathBuf> {
+    let mut expanded = PathBuf::new();
+    for component in dir.components() {
+        let part = component.as_os_str().to_string_lossy();
+        if part == "~" {
+            let home = env::var_os("HOME").or_else(|| env::var_os("USERPROFILE"))?;
+            expanded.push(home);
+        } else if let Some(name) = part.strip_prefix('$') {
+            expanded.push(env::var_os(
+                name.trim_start_matches('{').trim_end_matches('}'),
+            )?);
+        } else {
+            expanded.push(component);
+        }
+    }
+    Some(expanded)
+}
+
+fn file_stem(path: &Path) -> String {
+    path.file_stem()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string()
+}
+
+/// The workfile group an autosave belongs to, by the workfile name in the name of the autosave, e.g.
+/// `shot_v003.nk.autosave` or `shot_v003.0004.ma`. The longest name wins, so `shot_v0030` isn't taken
+/// for `shot_v003`.
+fn matching_group<'a>(name: &str, groups: &'a [FileGroup]) -> Option<&'a FileGroup> {
+    groups
+        .iter()
+        .filter_map(|g| {
+            let longest = g
+                .files
+                .iter()
+                .map(|f| file_stem(&f.path))
+                .filter(|s| name.contains(s.as_str()))
+                .map(|s| s.len())
+                .max()?;
+            Some((longest, g))
+        })
+        .max_by_key(|(l, _g)| *l)
+        .map(|(_l, g)| g)
+}
+
+/// Looks for autosaves of the workfiles, newest first. Files with the extension of a DCC in their name
+/// are autosaves if they're named after one of the workfiles and aren't workfiles themselves.
+pub fn find_autosaves(dccs: &[Dcc], files: &[File]) -> Vec<Autosave> {
+    let groups = FileGroup::group(files);
+    let mut dirs: Vec<PathBuf> = files
+        .iter()
+        .filter_map(|f| f.path.parent().map(|p| p.to_path_buf()))
+        .collect();
+    for dcc in dccs {
+        dirs.extend(dcc.autosave_dirs.iter().filter_map(|d| expand_dir(d)));
+    }
+    dirs.sort();
+    dirs.dedup();
+
+    let mut autosaves = Vec::new();
+    for dir in dirs {
+        let entries = match fs::read_dir(&dir) {
+            Ok(e) => e,
+            Err(_e) => continue,
+        };
+        for entry in entries.filter_map(|e| e.ok()) {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !path.is_file()
+                || files.iter().any(|f| f.path == path)
+                || !dccs.iter().any(|d| name.contains(d.extension.as_str()))
+            {
+                continue;
+            }
+            let workfile = match matching_group(&name, &groups).and_then(|g| g.latest()) {
+                Some(f) => f.clone(),
+                None => continue,
+            };
+            let modified = entry
+                .metadata()
+                .and_then(|m| m.modified())
+                .ok()
+                .and_then(|m| m.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |d| d.as_secs());
+            autosaves.push(Autosave {
+                path,
+                modified,
+                workfile,
+            });
+        }
+    }
+    autosaves.sort_by(|a, b| b.modified.cmp(&a.modified).then(a.path.cmp(&b.path)));
+    autosaves
+}
+
+impl Autosave {
+    /// Copies the autosave as the version after the latest workfile, returning the new path.
+    pub fn adopt(&self) -> Result<PathBuf, io::Error> {
+        let path = self.workfile.version_path(self.workfile.version + 1)?;
+        if path.exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} already exists.", path.display()),
+            ));
+        }
+        info!(
+            "Recovering autosave {} as {}",
+            self.path.display(),
+            path.display()
+        );
+        RealFs.copy(&self.path, &path)?;
+        Ok(path)
+    }
+}
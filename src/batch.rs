@@ -0,0 +1,125 @@
+use crate::ignore::IgnorePatterns;
+use crate::jobs::JobHandle;
+use crate::naming::FilenameTemplate;
+use crate::workfiles::FileGroup;
+use crate::TaskTreeNode;
+use log::{error, info};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+/// The report of a running batch, shared with its window.
+pub type BatchReport = Arc<Mutex<Vec<String>>>;
+
+/// The outcome of versioning up one workfile.
+#[derive(Clone, Debug, PartialEq)]
+pub struct VersionUp {
+    pub task: String,
+    /// The latest file before the version up.
+    pub file: String,
+    pub result: Result<String, String>,
+}
+
+impl VersionUp {
+    pub fn report_line(&self) -> String {
+        match &self.result {
+            Ok(new_file) => format!("{}: {} → {}", self.task, self.file, new_file),
+            Err(e) => format!("{}: failed to version up {}: {}", self.task, self.file, e),
+        }
+    }
+}
+
+/// Copies the latest version of every workfile in the task as the next version.
+pub fn version_up_task(
+    task: &TaskTreeNode,
+    template: &FilenameTemplate,
+    ignore: &IgnorePatterns,
+) -> Result<Vec<VersionUp>, String> {
+    let files = task
+        .find_workfiles(task.metadata.work_dir_name.clone(), template, ignore)
+        .map_err(|e| e.to_string())?;
+
+    let mut results = Vec::new();
+    for group in FileGroup::group(&files) {
+        let latest = match group.latest() {
+            Some(l) => l,
+            None => continue,
+        };
+        let file = file_name(&latest.path);
+        let result = match latest.version_up() {
+            Ok(path) => Ok(file_name(&path)),
+            Err(e) => {
+                error!("Failed to version up {}: {}", latest.path.display(), e);
+                Err(e.to_string())
+            }
+        };
+        results.push(VersionUp {
+            task: task.name.clone(),
+            file,
+            result,
+        });
+    }
+    Ok(results)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string()
+}
+
+/// Versions up the tasks one by one, adding a line per file to the report. Meant to run as a job.
+/// Returns a summary, failing if any file failed.
+pub fn version_up_tasks(
+    tasks: &[TaskTreeNode],
+    template: &FilenameTemplate,
+    ignore: &IgnorePatterns,
+    report: &BatchReport,
+    job: &JobHandle,
+) -> Result<String, String> {
+    let push = |line: String| {
+        if let Ok(mut r) = report.lock() {
+            r.push(line);
+        }
+    };
+
+    let mut created = 0;
+    let mut failed = 0;
+    for (i, task) in tasks.iter().enumerate() {
+        if job.is_cancelled() {
+            push(String::from("Cancelled."));
+            return Err(String::from("Cancelled."));
+        }
+        match version_up_task(task, template, ignore) {
+            Ok(results) if results.is_empty() => push(format!("{}: no workfiles", task.name)),
+            Ok(results) => {
+                for r in results {
+                    match r.result {
+                        Ok(_) => created += 1,
+                        Err(_) => failed += 1,
+                    }
+                    push(r.report_line());
+                }
+            }
+            Err(e) => {
+                failed += 1;
+                push(format!("{}: {}", task.name, e));
+            }
+        }
+        job.set_progress((i + 1) as f32 / tasks.len() as f32);
+    }
+
+    let summary = format!(
+        "Versioned up {} workfiles in {} tasks, {} failed",
+        created,
+        tasks.len(),
+        failed
+    );
+    info!("{}", summary);
+    push(summary.clone());
+    if failed > 0 {
+        Err(summary)
+    } else {
+        Ok(summary)
+    }
+}
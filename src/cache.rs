@@ -0,0 +1,167 @@
+use crate::atomic::write_bytes_atomic;
+use crate::helpers::{user_config_dir, PROJECT_FILE_NAME};
+use crate::vfs::{parallel_map, Fs};
+use crate::{Project, TaskTreeNode};
+use log::{error, info};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+pub const SCAN_CACHE_FILE_NAME: &str = "scan_cache.json";
+/// The trees of the projects opened most recently are kept.
+const MAX_CACHED_TREES: usize = 20;
+
+/// A dir in the projects dir, with its project if it has a project file.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug)]
+struct ProjectEntry {
+    dir: PathBuf,
+    /// When the project file was last changed, None if there is none.
+    modified: Option<SystemTime>,
+    project: Option<Project>,
+}
+
+/// The dirs of a projects dir as of the last scan.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug)]
+struct ProjectsScan {
+    dir: PathBuf,
+    modified: Option<SystemTime>,
+    entries: Vec<ProjectEntry>,
+}
+
+/// The project list and task trees of earlier scans, kept in the user config dir. A refresh only
+/// reads the dirs and project files whose modification time changed since.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, Default)]
+#[serde(default)]
+pub struct ScanCache {
+    projects: Option<ProjectsScan>,
+    /// Most recently scanned first.
+    trees: Vec<TaskTreeNode>,
+}
+
+impl ScanCache {
+    pub fn path() -> Option<PathBuf> {
+        user_config_dir().map(|d| d.join(SCAN_CACHE_FILE_NAME))
+    }
+
+    /// Reads the cache, empty if it's missing or can't be read.
+    pub fn load(path: &Path) -> ScanCache {
+        let bytes = match fs::read(path) {
+            Ok(b) => b,
+            Err(_e) => return ScanCache::default(),
+        };
+        match serde_json::from_slice(&bytes) {
+            Ok(c) => c,
+            Err(e) => {
+                error!("Ignoring the scan cache {}: {}", path.display(), e);
+                ScanCache::default()
+            }
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), io::Error> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let bytes = serde_json::to_vec(self)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+        write_bytes_atomic(path, &bytes)
+    }
+
+    /// The projects in the dir. The dir is only listed again if it changed, and only the project files
+    /// that changed are read.
+    pub fn projects(
+        &mut self,
+        fs: &dyn Fs,
+        projects_dir: &Path,
+    ) -> Result<Vec<Project>, io::Error> {
+        let modified = fs.modified(projects_dir).ok();
+        let cached = self.projects.take().filter(|s| s.dir == projects_dir);
+        let dirs: Vec<PathBuf> = match &cached {
+            Some(s) if modified.is_some() && s.modified == modified => {
+                s.entries.iter().map(|e| e.dir.clone()).collect()
+            }
+            _ => {
+                info!("Looking for projects in: {}", projects_dir.display());
+                fs.read_dir(projects_dir)?
+            }
+        };
+
+        let entries = parallel_map(&dirs, |dir| {
+            let path = dir.join(PROJECT_FILE_NAME);
+            let modified = fs.modified(&path).ok();
+            let previous = cached
+                .as_ref()
+                .and_then(|s| s.entries.iter().find(|e| &e.dir == dir));
+            let project = match previous {
+                _ if modified.is_none() => None,
+                Some(e) if e.modified == modified => e.project.clone(),
+                _ => Project::read_project(fs, path).ok(),
+            };
+            ProjectEntry {
+                dir: dir.clone(),
+                modified,
+                project,
+            }
+        });
+
+        let mut projects: Vec<Project> = entries.iter().filter_map(|e| e.project.clone()).collect();
+        projects.sort();
+        self.projects = Some(ProjectsScan {
+            dir: projects_dir.to_path_buf(),
+            modified,
+            entries,
+        });
+        Ok(projects)
+    }
+
+    /// The projects of the last scan of the dir, for when it can't be reached.
+    pub fn cached_projects(&self, projects_dir: &Path) -> Option<Vec<Project>> {
+        let scan = self.projects.as_ref().filter(|s| s.dir == projects_dir)?;
+        let mut projects: Vec<Project> = scan
+            .entries
+            .iter()
+            .filter_map(|e| e.project.clone())
+            .collect();
+        projects.sort();
+        Some(projects)
+    }
+
+    /// The task tree of the last scan of the work dir, for when it can't be reached.
+    pub fn cached_tree(&self, work_path: &Path) -> Option<TaskTreeNode> {
+        self.trees.iter().find(|t| t.path == work_path).cloned()
+    }
+
+    /// The task tree of the work dir, with only the dirs that changed since the last scan rescanned.
+    pub fn task_tree(
+        &mut self,
+        fs: &dyn Fs,
+        work_path: &Path,
+        work_dir_name: &str,
+        output_dir_name: &str,
+    ) -> Result<TaskTreeNode, io::Error> {
+        let cached = self
+            .trees
+            .iter()
+            .position(|t| t.path == work_path)
+            .map(|i| self.trees.remove(i));
+        let tree = match cached {
+            Some(t)
+                if t.metadata.work_dir_name == work_dir_name
+                    && t.metadata.output_dir_name == output_dir_name =>
+            {
+                t.refresh_with(fs, 0)?
+            }
+            _ => TaskTreeNode::from_path_with(
+                fs,
+                work_path.to_path_buf(),
+                work_dir_name,
+                output_dir_name,
+                0,
+            )?,
+        };
+        self.trees.insert(0, tree.clone());
+        self.trees.truncate(MAX_CACHED_TREES);
+        Ok(tree)
+    }
+}
@@ -1,10 +1,14 @@
 use log::error;
 use log::info;
-use std::fs::File;
-use std::fs::OpenOptions;
-use std::path::PathBuf;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
+use crate::atomic::{write_atomic, FileLock};
 use crate::helpers::sanitize_string;
+use crate::trash::{Trash, TrashItem, TrashKind};
+use crate::vfs::{Fs, RealFs};
+use crate::Project;
 
 /// When creating a project, the user can choose from a list of clients names, which will inserted into the project name.
 /// Client consists of a full name, which appears in the UI, and a short sanitized name used for the actual project name.
@@ -15,33 +19,31 @@ pub struct Client {
 }
 
 impl Client {
-    /// Open the file containing the list of clients, read only.
-    fn open_clients_file(clients_path: PathBuf) -> Result<File, String> {
-        info!(
-            "Attempting to open: {}",
-            clients_path.clone().to_string_lossy()
-        );
-        match std::fs::File::open(clients_path.clone()) {
-            Ok(f) => return Ok(f),
+    /// Read the file containing the list of clients.
+    fn read_clients_file(fs: &dyn Fs, clients_path: &Path) -> Result<String, String> {
+        info!("Attempting to open: {}", clients_path.to_string_lossy());
+        match fs.read_to_string(clients_path) {
+            Ok(t) => Ok(t),
             Err(e) => {
                 let message = format!(
                     "Failed to open file {}: {}",
-                    clients_path.clone().to_string_lossy(),
+                    clients_path.to_string_lossy(),
                     e
                 );
                 error!("{}", message);
-                return Err(message);
+                Err(message)
             }
-        };
+        }
     }
 
     /// Parses the file, using serde_yaml, into a Vec of Client structs.
     pub fn get_clients(clients_path: PathBuf) -> Result<Vec<Client>, String> {
-        let f = match Client::open_clients_file(clients_path) {
-            Ok(f) => f,
-            Err(e) => return Err(e),
-        };
-        let clients: Vec<Client> = match serde_yaml::from_reader(f) {
+        Client::get_clients_with(&RealFs, &clients_path)
+    }
+
+    pub fn get_clients_with(fs: &dyn Fs, clients_path: &Path) -> Result<Vec<Client>, String> {
+        let text = Client::read_clients_file(fs, clients_path)?;
+        let clients: Vec<Client> = match serde_yaml::from_str(&text) {
             Ok(c) => c,
             Err(e) => {
                 let message = format!("Failed to get client list: {}", e);
@@ -50,7 +52,7 @@ impl Client {
             }
         };
 
-        return Ok(clients);
+        Ok(clients)
     }
 
     /// Creates and sanitizes a client struct, then checks for duplicates in the current client vec, then appends the new client. Finally writes to file.
@@ -59,6 +61,9 @@ impl Client {
         short_name: &String,
         clients_path: &PathBuf,
     ) -> Result<(), String> {
+        // Hold the lock until written, so clients added by others at the same time aren't lost.
+        let _lock = FileLock::acquire(clients_path).map_err(|e| e.to_string())?;
+
         // Read in clients list.
         let mut clients = match Client::get_clients(clients_path.to_owned()) {
             Ok(c) => c,
@@ -90,22 +95,11 @@ impl Client {
         Ok(())
     }
 
-    /// Writes a list of clients to a file using serde_yaml.
+    /// Writes a list of clients to a file using serde_yaml. The caller holds the lock of the file.
     fn write_clients_to_file(clients: Vec<Client>, path: PathBuf) -> Result<(), String> {
         info!("Writing: {:#?}", clients);
-        // Open the clients file for writing.
-        let f = match OpenOptions::new()
-            .write(true)
-            .truncate(true)
-            .create(true)
-            .open(path.clone())
-        {
-            Ok(f) => f,
-            Err(e) => return Err(e.to_string()),
-        };
-
         // Overwrite the current clients list file with the modified list.
-        match serde_yaml::to_writer(f, &clients) {
+        match write_atomic(&path, &clients) {
             Ok(_o) => info!("Wrote to file."),
             Err(e) => {
                 let message = format!("Failed to write file {}: {}", path.to_string_lossy(), e);
@@ -117,7 +111,7 @@ impl Client {
     }
 
     /// Returns true if duplicate is found, otherwise false.
-    fn check_for_duplicate_clients(client_list: &Vec<Client>, new_client: &Client) -> bool {
+    fn check_for_duplicate_clients(client_list: &[Client], new_client: &Client) -> bool {
         for c in client_list.iter() {
             if c.name == new_client.name || c.short_name == new_client.short_name {
                 return true;
@@ -129,11 +123,10 @@ impl Client {
     /// Takes a client struct, finds and removes clients with identical name in the file at eh supplied path, and writes to file.
     pub fn remove_client(client: &Client, clients_path: &PathBuf) -> Result<(), String> {
         info!("Attempting to remove: {}", client.name);
+        let _lock = FileLock::acquire(clients_path).map_err(|e| e.to_string())?;
+
         // Get a current list of clients.
-        let clients = match Client::get_clients(clients_path.to_owned()) {
-            Ok(c) => c,
-            Err(e) => return Err(e),
-        };
+        let clients = Client::get_clients(clients_path.to_owned())?;
 
         // Remove the selected client by filtering.
         let clients_filtered: Vec<Client> = clients
@@ -144,6 +137,21 @@ impl Client {
 
         info!("Filtered list: {:#?}", clients_filtered);
 
+        // Keep a copy in the trash, so the client can be restored.
+        match Client::trash(clients_path).put_data(
+            &client.short_name,
+            TrashKind::Client,
+            clients_path,
+            client,
+        ) {
+            Ok(_i) => (),
+            Err(e) => {
+                let message = format!("Failed to move client to trash: {}", e);
+                error!("{}", message);
+                return Err(message);
+            }
+        }
+
         // Write to file.
         match Client::write_clients_to_file(clients_filtered, clients_path.to_owned()) {
             Ok(_o) => (),
@@ -156,4 +164,79 @@ impl Client {
 
         Ok(())
     }
+
+    /// The trash for removed clients, next to the clients file.
+    pub fn trash(clients_path: &Path) -> Trash {
+        Trash::new(clients_path.parent().unwrap_or(Path::new("")))
+    }
+
+    /// Adds a client from the trash back to the clients file, and removes it from the trash.
+    pub fn restore_client(item: &TrashItem, clients_path: &PathBuf) -> Result<(), String> {
+        let trash = Client::trash(clients_path);
+        let client: Client = match trash.read_data(item) {
+            Ok(c) => c,
+            Err(e) => {
+                let message = format!("Failed to read client from trash: {}", e);
+                error!("{}", message);
+                return Err(message);
+            }
+        };
+
+        Client::add_client(&client.name, &client.short_name, clients_path)?;
+
+        match trash.purge(item) {
+            Ok(()) => Ok(()),
+            Err(e) => Err(format!(
+                "Restored client, but failed to remove it from trash: {}",
+                e
+            )),
+        }
+    }
+}
+
+/// A project of a client and what was delivered in it, for the client view.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ClientProject {
+    pub project: Project,
+    /// The names in the deliveries dir, newest first.
+    pub deliveries: Vec<String>,
+}
+
+/// The projects of the client, by the client of the project or the client in its name. Newest
+/// project numbers first.
+pub fn client_projects(
+    projects: &[Project],
+    short_name: &str,
+    projects_dir: &PathBuf,
+) -> Vec<ClientProject> {
+    let mut client_projects: Vec<ClientProject> = projects
+        .iter()
+        .filter(|p| p.client_name().eq_ignore_ascii_case(short_name))
+        .map(|p| ClientProject {
+            project: p.clone(),
+            deliveries: list_deliveries(&p.get_deliveries_path(projects_dir)),
+        })
+        .collect();
+    client_projects.sort_by(|a, b| b.project.name.cmp(&a.project.name));
+    client_projects
+}
+
+fn list_deliveries(dir: &Path) -> Vec<String> {
+    let entries = match fs::read_dir(dir) {
+        Ok(e) => e,
+        Err(_e) => return Vec::new(),
+    };
+    let mut deliveries: Vec<(SystemTime, String)> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| {
+            let modified = e.metadata().and_then(|m| m.modified());
+            (
+                modified.unwrap_or(SystemTime::UNIX_EPOCH),
+                e.file_name().to_string_lossy().to_string(),
+            )
+        })
+        .filter(|(_m, n)| !n.starts_with('.'))
+        .collect();
+    deliveries.sort_by(|a, b| b.cmp(a));
+    deliveries.into_iter().map(|(_m, n)| n).collect()
 }
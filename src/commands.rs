@@ -0,0 +1,165 @@
+use crate::context::{LaunchContext, CONTEXT_ENV_VAR};
+use crate::hooks::{HookContext, HookEvent, Hooks};
+use crate::workfiles::Dcc;
+use crate::File;
+use crate::Project;
+use crate::TaskTreeNode;
+use log::{error, info};
+use std::path::PathBuf;
+use std::sync::mpsc::{channel, Receiver, Sender};
+
+/// What a front-end asks for. The UI, IPC and scripts send commands instead of changing the files
+/// themselves, `handle` runs them and reports what happened as events.
+#[derive(Clone, Debug)]
+pub enum Command {
+    CreateTask {
+        parent: TaskTreeNode,
+        name: String,
+        project: Box<Project>,
+    },
+    OpenFile(File),
+    /// Opens the file in the executable, or the default app if there is none, instead of its DCC.
+    OpenFileWith {
+        file: File,
+        executable: Option<PathBuf>,
+    },
+    VersionUp(File),
+}
+
+/// What came of a command.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Event {
+    TaskCreated(PathBuf),
+    FileOpened(PathBuf),
+    /// The new version and its path.
+    VersionedUp(u32, PathBuf),
+    /// A hook stopped the command before anything was done.
+    Cancelled(String),
+    Failed(String),
+}
+
+/// The state of the front-end a command runs in.
+#[derive(Clone, Debug, Default)]
+pub struct CommandContext {
+    pub project: Option<Project>,
+    pub projects_dir: Option<PathBuf>,
+    pub task: Option<TaskTreeNode>,
+    pub dccs: Vec<Dcc>,
+}
+
+impl CommandContext {
+    /// Whether the pipeline of the project has scripts for the event. A hooks file that can't be read
+    /// counts, so running them reports the error.
+    pub fn has_hooks(&self, event: HookEvent) -> bool {
+        match (&self.project, &self.projects_dir) {
+            (Some(p), Some(d)) => {
+                Hooks::load(&p.get_pipeline_path(d)).map_or(true, |h| h.handles(event))
+            }
+            _ => false,
+        }
+    }
+
+    /// Runs the hooks of the project for the event, waiting for the scripts.
+    pub fn run_hooks(&self, event: HookEvent, file: Option<&File>) -> Result<(), String> {
+        let (project, projects_dir) = match (&self.project, &self.projects_dir) {
+            (Some(p), Some(d)) => (p, d),
+            _ => return Ok(()),
+        };
+        let hooks = Hooks::load(&project.get_pipeline_path(projects_dir))?;
+        hooks.run(
+            event,
+            &HookContext {
+                project: Some(project),
+                projects_dir: Some(projects_dir),
+                task: self.task.as_ref(),
+                file,
+            },
+        )
+    }
+}
+
+/// Runs the command.
+pub fn handle(command: &Command, context: &CommandContext) -> Vec<Event> {
+    info!("Handling command: {:?}", command);
+    match command {
+        Command::CreateTask {
+            parent,
+            name,
+            project,
+        } => match parent.create_task(name.clone(), (**project).clone()) {
+            Ok(()) => vec![Event::TaskCreated(parent.path.join(name))],
+            Err(e) => vec![Event::Failed(e.to_string())],
+        },
+        Command::OpenFile(file) => {
+            let executable = context
+                .dccs
+                .iter()
+                .find(|d| d.extension == format!(".{}", file.extension))
+                .and_then(|d| d.executable.clone());
+            open_file(file, executable, context)
+        }
+        Command::OpenFileWith { file, executable } => open_file(file, executable.clone(), context),
+        Command::VersionUp(file) => match file.version_up() {
+            Ok(p) => vec![Event::VersionedUp(file.version + 1, p)],
+            Err(e) => vec![Event::Failed(e.to_string())],
+        },
+    }
+}
+
+/// Opens the file in the executable, or the default app, with the context in the environment. The
+/// pre-open hooks run before, the post-open hooks are left to the front-end so it doesn't wait for them.
+fn open_file(file: &File, executable: Option<PathBuf>, context: &CommandContext) -> Vec<Event> {
+    if let Err(e) = context.run_hooks(HookEvent::PreFileOpen, Some(file)) {
+        return vec![Event::Cancelled(e)];
+    }
+
+    let launch = LaunchContext::new(
+        context.project.as_ref(),
+        context.projects_dir.as_deref(),
+        context.task.as_ref(),
+        file,
+    );
+    let result = match (launch.write(), executable) {
+        (Ok(p), Some(exe)) => file.launch_with_env(&exe, &[(CONTEXT_ENV_VAR, &p)]),
+        (Ok(p), None) => file.open_with_env(&[(CONTEXT_ENV_VAR, &p)]),
+        (Err(e), executable) => {
+            error!("Failed to write context file, opening without it: {}", e);
+            match executable {
+                Some(exe) => file.launch_with_env(&exe, &[]),
+                None => file.open(),
+            }
+        }
+    };
+    if let Err(e) = result {
+        error!("Error opening file: {}", e);
+        return vec![Event::Failed(e.to_string())];
+    }
+
+    vec![Event::FileOpened(file.path.clone())]
+}
+
+/// Commands sent from the widgets, run between frames.
+#[derive(Debug)]
+pub struct CommandChannel {
+    sender: Sender<Command>,
+    receiver: Receiver<Command>,
+}
+
+impl Default for CommandChannel {
+    fn default() -> Self {
+        let (sender, receiver) = channel();
+        Self { sender, receiver }
+    }
+}
+
+impl CommandChannel {
+    pub fn send(&self, command: Command) {
+        // The receiver lives as long as the sender here, so this can't fail.
+        let _ = self.sender.send(command);
+    }
+
+    /// The commands sent since the last call.
+    pub fn pending(&self) -> Vec<Command> {
+        self.receiver.try_iter().collect()
+    }
+}
@@ -0,0 +1,129 @@
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+/// Extensions of workfiles saved as text, which can be compared line by line.
+pub const TEXT_EXTENSIONS: [&str; 6] = ["nk", "gizmo", "ma", "usda", "comp", "py"];
+/// The changed parts of longer files aren't compared, the table of the comparison grows with the
+/// product of their line counts.
+const MAX_DIFF_CELLS: usize = 4_000_000;
+
+/// The size and modification time of a version.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct FileStats {
+    pub size: u64,
+    /// Seconds since the unix epoch.
+    pub modified: u64,
+}
+
+impl FileStats {
+    pub fn read(path: &Path) -> Result<FileStats, io::Error> {
+        let metadata = fs::metadata(path)?;
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|m| m.duration_since(UNIX_EPOCH).ok())
+            .map_or(0, |d| d.as_secs());
+        Ok(FileStats {
+            size: metadata.len(),
+            modified,
+        })
+    }
+}
+
+/// A line of a text diff, or a run of unchanged lines left out.
+#[derive(Clone, Debug, PartialEq)]
+pub enum DiffLine {
+    Same(String),
+    Removed(String),
+    Added(String),
+    Skipped(usize),
+}
+
+pub fn is_text_workfile(extension: &str) -> bool {
+    TEXT_EXTENSIONS.contains(&extension.to_lowercase().as_str())
+}
+
+/// The lines changed from one text to another, with `context` unchanged lines around each change.
+/// None if the changed parts are too long to compare.
+pub fn diff_lines(old: &str, new: &str, context: usize) -> Option<Vec<DiffLine>> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    let prefix = old.iter().zip(&new).take_while(|(a, b)| a == b).count();
+    let suffix = old[prefix..]
+        .iter()
+        .rev()
+        .zip(new[prefix..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let a = &old[prefix..old.len() - suffix];
+    let b = &new[prefix..new.len() - suffix];
+    if a.len() * b.len() > MAX_DIFF_CELLS {
+        return None;
+    }
+
+    // The longest common subsequence of the lines after each pair of positions.
+    let width = b.len() + 1;
+    let mut lcs = vec![0u32; (a.len() + 1) * width];
+    for i in (0..a.len()).rev() {
+        for j in (0..b.len()).rev() {
+            lcs[i * width + j] = if a[i] == b[j] {
+                lcs[(i + 1) * width + j + 1] + 1
+            } else {
+                lcs[(i + 1) * width + j].max(lcs[i * width + j + 1])
+            };
+        }
+    }
+
+    let mut lines: Vec<DiffLine> = old[..prefix]
+        .iter()
+        .map(|l| DiffLine::Same(String::from(*l)))
+        .collect();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() || j < b.len() {
+        if i < a.len() && j < b.len() && a[i] == b[j] {
+            lines.push(DiffLine::Same(String::from(a[i])));
+            i += 1;
+            j += 1;
+        } else if j == b.len()
+            || (i < a.len() && lcs[(i + 1) * width + j] >= lcs[i * width + j + 1])
+        {
+            lines.push(DiffLine::Removed(String::from(a[i])));
+            i += 1;
+        } else {
+            lines.push(DiffLine::Added(String::from(b[j])));
+            j += 1;
+        }
+    }
+    lines.extend(
+        old[old.len() - suffix..]
+            .iter()
+            .map(|l| DiffLine::Same(String::from(*l))),
+    );
+
+    let mut keep = vec![false; lines.len()];
+    for (i, line) in lines.iter().enumerate() {
+        if !matches!(line, DiffLine::Same(_)) {
+            let end = (i + context).min(lines.len() - 1);
+            keep[i.saturating_sub(context)..=end].fill(true);
+        }
+    }
+    let mut diff = Vec::new();
+    let mut skipped = 0;
+    for (line, keep) in lines.into_iter().zip(keep) {
+        if keep {
+            if skipped > 0 {
+                diff.push(DiffLine::Skipped(skipped));
+                skipped = 0;
+            }
+            diff.push(line);
+        } else {
+            skipped += 1;
+        }
+    }
+    if skipped > 0 {
+        diff.push(DiffLine::Skipped(skipped));
+    }
+    Some(diff)
+}
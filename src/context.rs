@@ -0,0 +1,96 @@
+use crate::atomic::write_bytes_atomic;
+use crate::helpers::{current_user, now_timestamp};
+use crate::File;
+use crate::Project;
+use crate::TaskTreeNode;
+use log::{error, info};
+use sha1::{Digest, Sha1};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Env var holding the path of the context file, for DCC startup scripts.
+pub const CONTEXT_ENV_VAR: &str = "RCLAMP_CONTEXT";
+const CONTEXT_DIR_NAME: &str = "rclamp";
+
+/// Describes the workfile being opened, written as `context.json` so DCC startup scripts can configure the session.
+#[derive(serde::Serialize, Debug, Default, PartialEq)]
+pub struct LaunchContext {
+    pub project: Option<String>,
+    pub project_path: Option<PathBuf>,
+    pub pipeline_path: Option<PathBuf>,
+    pub task: Option<String>,
+    pub task_path: Option<PathBuf>,
+    pub work_path: Option<PathBuf>,
+    pub output_path: Option<PathBuf>,
+    pub file: PathBuf,
+    pub version: u32,
+    pub user: String,
+    pub opened_at: u64,
+}
+
+impl LaunchContext {
+    pub fn new(
+        project: Option<&Project>,
+        projects_dir: Option<&Path>,
+        task: Option<&TaskTreeNode>,
+        file: &File,
+    ) -> Self {
+        let mut context = Self {
+            file: file.path.clone(),
+            version: file.version,
+            user: current_user(),
+            opened_at: now_timestamp(),
+            ..Default::default()
+        };
+
+        if let Some(p) = project {
+            context.project = Some(p.name.clone());
+            if let Some(d) = projects_dir {
+                let projects_dir = d.to_path_buf();
+                context.project_path = Some(p.get_path(&projects_dir));
+                context.pipeline_path = Some(p.get_pipeline_path(&projects_dir));
+            }
+        }
+        if let Some(t) = task {
+            context.task = Some(t.name.clone());
+            context.task_path = Some(t.path.clone());
+            context.work_path = Some(t.get_work_path());
+            context.output_path = Some(t.path.join(&t.metadata.output_dir_name));
+        }
+        context
+    }
+
+    /// Writes the context to `<temp>/rclamp-<user>/<file stem>_<hash>_context.json` and returns the path.
+    /// The temp dir is used so nothing is left next to the workfiles. The hash is of the whole path of
+    /// the workfile, so files with the same name in other projects or tasks get their own context.
+    pub fn write(&self) -> Result<PathBuf, io::Error> {
+        let dir = std::env::temp_dir().join(format!("{}-{}", CONTEXT_DIR_NAME, self.user));
+        fs::create_dir_all(&dir)?;
+        // The temp dir is shared by all users on unix.
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            fs::set_permissions(&dir, fs::Permissions::from_mode(0o700))?;
+        }
+
+        let stem = self
+            .file
+            .file_stem()
+            .and_then(|s| s.to_str())
+            .unwrap_or("workfile");
+        let hash: String = Sha1::digest(self.file.to_string_lossy().as_bytes())[..4]
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect();
+        let path = dir.join(format!("{}_{}_context.json", stem, hash));
+
+        let json = serde_json::to_vec_pretty(self).map_err(|e| {
+            error!("Failed to write context file: {}", e);
+            io::Error::new(io::ErrorKind::Other, e.to_string())
+        })?;
+        write_bytes_atomic(&path, &json)?;
+        info!("Wrote context file: {}", path.display());
+        Ok(path)
+    }
+}
@@ -0,0 +1,142 @@
+use crate::helpers::{format_timestamp, now_timestamp, user_config_dir};
+use crate::logging::{format_lines, recent_lines, LogLine};
+use log::error;
+use std::any::Any;
+use std::backtrace::Backtrace;
+use std::fs;
+use std::io;
+use std::panic::{self, Location};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Dir in the user config dir the crash reports are written to.
+pub const CRASH_DIR_NAME: &str = "crashes";
+/// Holds the time of the latest report offered at start, so each is offered once.
+const SEEN_FILE_NAME: &str = "seen";
+const REPORT_PREFIX: &str = "crash_";
+const REPORT_EXTENSION: &str = "txt";
+/// Older reports are removed when a new one is written.
+const MAX_CRASH_REPORTS: usize = 10;
+
+/// What the app was doing, set by the app when the selection changes.
+static STATE: Mutex<Option<String>> = Mutex::new(None);
+
+pub fn set_crash_state(summary: String) {
+    *STATE.lock().unwrap_or_else(|e| e.into_inner()) = Some(summary);
+}
+
+pub fn crash_dir() -> Option<PathBuf> {
+    user_config_dir().map(|d| d.join(CRASH_DIR_NAME))
+}
+
+/// The text of a crash report: the panic, the app state and the latest log lines.
+pub fn report_text(
+    time: u64,
+    panic: &str,
+    state: Option<&str>,
+    lines: &[LogLine],
+    backtrace: &str,
+) -> String {
+    let mut text = format!(
+        "Rclamp {} crashed on {} at {}\n\n{}\n\n",
+        env!("CARGO_PKG_VERSION"),
+        std::env::consts::OS,
+        format_timestamp(time),
+        panic
+    );
+    text.push_str("State:\n");
+    text.push_str(state.unwrap_or("Not set."));
+    text.push_str("\n\nLog:\n");
+    text.push_str(&format_lines(lines));
+    text.push_str("\nBacktrace:\n");
+    text.push_str(backtrace);
+    text
+}
+
+fn report_time(path: &Path) -> Option<u64> {
+    path.file_stem()?
+        .to_str()?
+        .strip_prefix(REPORT_PREFIX)?
+        .parse()
+        .ok()
+}
+
+/// The crash reports in the dir, oldest first.
+pub fn list_reports(dir: &Path) -> Vec<(u64, PathBuf)> {
+    let entries = match fs::read_dir(dir) {
+        Ok(e) => e,
+        Err(_e) => return Vec::new(),
+    };
+    let mut reports: Vec<(u64, PathBuf)> = entries
+        .filter_map(|e| e.ok())
+        .map(|e| e.path())
+        .filter(|p| p.extension().map_or(false, |e| e == REPORT_EXTENSION))
+        .filter_map(|p| Some((report_time(&p)?, p)))
+        .collect();
+    reports.sort();
+    reports
+}
+
+/// Writes the report as `crash_<time>.txt` and removes the oldest beyond `MAX_CRASH_REPORTS`.
+pub fn write_report(dir: &Path, time: u64, text: &str) -> Result<PathBuf, io::Error> {
+    fs::create_dir_all(dir)?;
+    let path = dir.join(format!("{}{}.{}", REPORT_PREFIX, time, REPORT_EXTENSION));
+    fs::write(&path, text)?;
+    let reports = list_reports(dir);
+    for (_t, old) in reports
+        .iter()
+        .take(reports.len().saturating_sub(MAX_CRASH_REPORTS))
+    {
+        let _ = fs::remove_file(old);
+    }
+    Ok(path)
+}
+
+/// The latest report if it hasn't been offered yet. It's marked as seen, so it's offered only once.
+pub fn take_pending_report(dir: &Path) -> Option<PathBuf> {
+    let seen_path = dir.join(SEEN_FILE_NAME);
+    let seen: u64 = fs::read_to_string(&seen_path)
+        .ok()
+        .and_then(|s| s.trim().parse().ok())
+        .unwrap_or(0);
+    let (time, path) = list_reports(dir).pop().filter(|(t, _p)| *t > seen)?;
+    if let Err(e) = fs::write(&seen_path, time.to_string()) {
+        error!("Failed to write {}: {}", seen_path.display(), e);
+    }
+    Some(path)
+}
+
+fn panic_message(payload: &(dyn Any + Send), location: Option<&Location<'_>>) -> String {
+    let payload = payload
+        .downcast_ref::<&str>()
+        .map(|s| s.to_string())
+        .or_else(|| payload.downcast_ref::<String>().cloned())
+        .unwrap_or_else(|| String::from("Unknown panic"));
+    match location {
+        Some(l) => format!("{} at {}:{}", payload, l.file(), l.line()),
+        None => payload,
+    }
+}
+
+/// Writes a crash report on panics before the default hook runs, so the app doesn't just vanish
+/// when the console is hidden.
+pub fn install_panic_hook() {
+    let default_hook = panic::take_hook();
+    panic::set_hook(Box::new(move |info| {
+        if let Some(dir) = crash_dir() {
+            let state = STATE.lock().unwrap_or_else(|e| e.into_inner()).clone();
+            let text = report_text(
+                now_timestamp(),
+                &panic_message(info.payload(), info.location()),
+                state.as_deref(),
+                &recent_lines(),
+                &Backtrace::force_capture().to_string(),
+            );
+            match write_report(&dir, now_timestamp(), &text) {
+                Ok(p) => error!("Wrote crash report {}", p.display()),
+                Err(e) => error!("Failed to write crash report: {}", e),
+            }
+        }
+        default_hook(info);
+    }));
+}
@@ -0,0 +1,403 @@
+use crate::archive::{remove_archive, ArchiveSettings};
+use crate::atomic::write_yaml;
+use crate::helpers::read_config_file;
+use crate::ingest::{copy_with_checksum, sha1_file};
+use crate::jobs::JobHandle;
+use crate::permissions::apply_to_output;
+use crate::sequences::OutputItem;
+use crate::vfs::{Fs, RealFs};
+use log::{info, warn};
+use serde_json::{json, Value};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+pub const DELIVERY_MANIFEST_FILE_NAME: &str = "delivery_manifest.yaml";
+/// Timelines start at the usual 01:00:00:00 record timecode.
+const RECORD_START_SECONDS: u32 = 3600;
+
+/// The editorial timeline written next to the delivered clips.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TimelineFormat {
+    Otio,
+    Edl,
+    Csv,
+}
+
+impl TimelineFormat {
+    pub const ALL: [TimelineFormat; 3] = [
+        TimelineFormat::Otio,
+        TimelineFormat::Edl,
+        TimelineFormat::Csv,
+    ];
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            TimelineFormat::Otio => "otio",
+            TimelineFormat::Edl => "edl",
+            TimelineFormat::Csv => "csv",
+        }
+    }
+}
+
+/// A delivered sequence or movie, with its path relative to the package dir.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeliveryClip {
+    pub item: OutputItem,
+    pub path: PathBuf,
+    /// Source start frame, the first frame for sequences and 0 for movies.
+    pub start: u32,
+    pub duration: u32,
+}
+
+impl DeliveryClip {
+    /// Movie durations are read with ffmpeg when there is one, otherwise movies count as one frame.
+    pub fn new(item: &OutputItem, rate: u32, ffmpeg: Option<&Path>) -> Self {
+        let (start, duration) = match item {
+            OutputItem::Sequence(s) => (s.first(), s.last() - s.first() + 1),
+            OutputItem::File { path, .. } => {
+                let frames = ffmpeg.and_then(|f| probe_frames(f, path, rate));
+                if frames.is_none() {
+                    warn!("Unknown duration of {}, using one frame.", path.display());
+                }
+                (0, frames.unwrap_or(1))
+            }
+        };
+        let path = match item {
+            OutputItem::Sequence(s) => PathBuf::from(s.pattern()),
+            OutputItem::File { .. } => PathBuf::from(item.name()),
+        };
+        Self {
+            item: item.clone(),
+            path,
+            start,
+            duration,
+        }
+    }
+}
+
+/// Reads the duration ffmpeg prints for the input, e.g. `Duration: 00:00:04.04`, as frames.
+fn probe_frames(ffmpeg: &Path, path: &Path, rate: u32) -> Option<u32> {
+    let output = Command::new(ffmpeg)
+        .arg("-hide_banner")
+        .arg("-i")
+        .arg(path)
+        .output()
+        .ok()?;
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let duration = stderr.split("Duration: ").nth(1)?.split(',').next()?;
+    let mut seconds = 0.;
+    for part in duration.trim().split(':') {
+        seconds = seconds * 60. + part.parse::<f64>().ok()?;
+    }
+    Some((seconds * rate as f64).round() as u32)
+}
+
+/// Formats frames as non-drop `HH:MM:SS:FF` timecode.
+pub fn timecode(frames: u32, rate: u32) -> String {
+    let rate = rate.max(1);
+    let seconds = frames / rate;
+    format!(
+        "{:02}:{:02}:{:02}:{:02}",
+        seconds / 3600,
+        (seconds / 60) % 60,
+        seconds % 60,
+        frames % rate
+    )
+}
+
+fn rational_time(value: u32, rate: u32) -> Value {
+    json!({"OTIO_SCHEMA": "RationalTime.1", "rate": rate as f64, "value": value as f64})
+}
+
+fn time_range(start: u32, duration: u32, rate: u32) -> Value {
+    json!({
+        "OTIO_SCHEMA": "TimeRange.1",
+        "start_time": rational_time(start, rate),
+        "duration": rational_time(duration, rate),
+    })
+}
+
+/// An OpenTimelineIO timeline with the clips in order on one video track.
+pub fn timeline_otio(name: &str, clips: &[DeliveryClip], rate: u32) -> Value {
+    let children: Vec<Value> = clips
+        .iter()
+        .map(|c| {
+            let range = time_range(c.start, c.duration, rate);
+            let reference = match &c.item {
+                OutputItem::Sequence(s) => json!({
+                    "OTIO_SCHEMA": "ImageSequenceReference.1",
+                    "target_url_base": "./",
+                    "name_prefix": s.prefix,
+                    "name_suffix": s.suffix,
+                    "start_frame": s.first(),
+                    "frame_step": 1,
+                    "rate": rate as f64,
+                    "frame_zero_padding": s.padding,
+                    "missing_frame_policy": "error",
+                    "available_range": range,
+                }),
+                OutputItem::File { .. } => json!({
+                    "OTIO_SCHEMA": "ExternalReference.1",
+                    "target_url": c.path.to_string_lossy(),
+                    "available_range": range,
+                }),
+            };
+            json!({
+                "OTIO_SCHEMA": "Clip.1",
+                "name": c.item.name(),
+                "source_range": range,
+                "media_reference": reference,
+            })
+        })
+        .collect();
+
+    json!({
+        "OTIO_SCHEMA": "Timeline.1",
+        "name": name,
+        "global_start_time": rational_time(RECORD_START_SECONDS * rate, rate),
+        "tracks": {
+            "OTIO_SCHEMA": "Stack.1",
+            "name": "tracks",
+            "children": [{
+                "OTIO_SCHEMA": "Track.1",
+                "name": "V1",
+                "kind": "Video",
+                "children": children,
+            }],
+        },
+    })
+}
+
+/// A CMX 3600 EDL, with the clip names as comments since reel names are limited to 8 characters.
+pub fn timeline_edl(name: &str, clips: &[DeliveryClip], rate: u32) -> String {
+    let mut edl = format!("TITLE: {}\nFCM: NON-DROP FRAME\n\n", name);
+    let mut record = RECORD_START_SECONDS * rate;
+    for (i, c) in clips.iter().enumerate() {
+        edl.push_str(&format!(
+            "{:03}  AX       V     C        {} {} {} {}\n* FROM CLIP NAME: {}\n\n",
+            i + 1,
+            timecode(c.start, rate),
+            timecode(c.start + c.duration, rate),
+            timecode(record, rate),
+            timecode(record + c.duration, rate),
+            c.path.display()
+        ));
+        record += c.duration;
+    }
+    edl
+}
+
+/// One row per clip, for editors without OTIO or EDL support.
+pub fn timeline_csv(clips: &[DeliveryClip], rate: u32) -> String {
+    let mut csv = String::from("clip,first,last,duration,record_in,record_out\n");
+    let mut record = RECORD_START_SECONDS * rate;
+    for c in clips {
+        csv.push_str(&format!(
+            "\"{}\",{},{},{},{},{}\n",
+            c.path.display(),
+            c.start,
+            c.start + c.duration - 1,
+            c.duration,
+            timecode(record, rate),
+            timecode(record + c.duration, rate)
+        ));
+        record += c.duration;
+    }
+    csv
+}
+
+/// A file copied into a delivery package.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, PartialEq)]
+pub struct DeliveryRecord {
+    pub source: PathBuf,
+    /// The file name in the package.
+    pub path: String,
+    pub size: u64,
+    pub sha1: String,
+}
+
+/// The files copied into a package so far. It's written after each file, so packaging an interrupted
+/// package again only copies what's missing.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct DeliveryManifest {
+    pub name: String,
+    pub files: Vec<DeliveryRecord>,
+    /// Set once all files and the timeline are written.
+    pub complete: bool,
+}
+
+impl DeliveryManifest {
+    pub fn path(package_dir: &Path) -> PathBuf {
+        package_dir.join(DELIVERY_MANIFEST_FILE_NAME)
+    }
+
+    /// The manifest of the package, empty if there is none.
+    pub fn load(package_dir: &Path) -> Result<DeliveryManifest, String> {
+        let path = Self::path(package_dir);
+        if !path.exists() {
+            return Ok(DeliveryManifest::default());
+        }
+        read_config_file(&path)
+    }
+
+    fn write(&self, package_dir: &Path) -> Result<(), String> {
+        write_yaml(&Self::path(package_dir), self).map_err(|e| e.to_string())
+    }
+
+    /// Whether the file in the package has the size and checksum it was recorded with.
+    pub fn is_copied(&self, package_dir: &Path, name: &str, size: u64) -> bool {
+        let record = match self.files.iter().find(|f| f.path == name) {
+            Some(r) if r.size == size => r,
+            _ => return false,
+        };
+        let path = package_dir.join(name);
+        fs::metadata(&path).map_or(false, |m| m.len() == size)
+            && sha1_file(&path).map_or(false, |s| s == record.sha1)
+    }
+}
+
+/// The dated dir in the deliveries with an interrupted package of the name, if there is one.
+pub fn find_interrupted(deliveries_dir: &Path, name: &str) -> Option<PathBuf> {
+    let mut dirs = RealFs.read_dir(deliveries_dir).ok()?;
+    // The latest first, the dirs are named by date.
+    dirs.reverse();
+    dirs.into_iter().find(|d| {
+        let package_dir = d.join(name);
+        DeliveryManifest::path(&package_dir).is_file()
+            && DeliveryManifest::load(&package_dir).map_or(false, |m| !m.complete)
+    })
+}
+
+/// Outputs copied together into a named dir in the deliveries, with an optional timeline.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeliveryPackage {
+    pub name: String,
+    pub items: Vec<OutputItem>,
+    pub timeline: Option<TimelineFormat>,
+    pub rate: u32,
+    pub ffmpeg: Option<PathBuf>,
+    /// Archives the package next to its dir once it's copied.
+    pub archive: Option<ArchiveSettings>,
+}
+
+impl DeliveryPackage {
+    /// Copies the outputs into `<dir>/<name>` with a manifest of their checksums, and writes the timeline.
+    /// An interrupted package is resumed, skipping the files that match the manifest. Meant to run as a
+    /// job.
+    pub fn package(&self, dir: &Path, job: &JobHandle) -> Result<String, String> {
+        let package_dir = dir.join(&self.name);
+        let mut manifest = DeliveryManifest::load(&package_dir)?;
+        // Files of an interrupted package may be partly written, other files are never overwritten.
+        let resuming = DeliveryManifest::path(&package_dir).is_file() && !manifest.complete;
+        info!(
+            "{} delivery: {}",
+            if resuming { "Resuming" } else { "Packaging" },
+            package_dir.display()
+        );
+        let sources: Vec<PathBuf> = self.items.iter().flat_map(|i| i.paths()).collect();
+        if !resuming {
+            // Checked up front, so a finished package isn't marked as interrupted.
+            if let Some(target) = sources
+                .iter()
+                .map(|s| package_dir.join(s.file_name().unwrap_or_default()))
+                .find(|t| t.exists())
+            {
+                return Err(format!("{} already exists.", target.display()));
+            }
+        }
+        RealFs
+            .create_dir_all(&package_dir)
+            .map_err(|e| format!("Failed to create {}: {}", package_dir.display(), e))?;
+        manifest.name = self.name.clone();
+        manifest.complete = false;
+        // Written before the first copy, so a package interrupted during it can be resumed.
+        manifest.write(&package_dir)?;
+
+        let total: u64 = self.items.iter().map(|i| i.size()).sum();
+        let mut copied = 0;
+        let mut skipped = 0;
+        for source in sources {
+            let name = source
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+            let target = package_dir.join(&name);
+            let size = fs::metadata(&source)
+                .map_err(|e| format!("Failed to read {}: {}", source.display(), e))?
+                .len();
+            if resuming && manifest.is_copied(&package_dir, &name, size) {
+                skipped += 1;
+                copied += size;
+                continue;
+            }
+            if target.exists() {
+                if !resuming {
+                    return Err(format!("{} already exists.", target.display()));
+                }
+                // Copied files may be read-only, which Windows won't remove.
+                #[cfg(windows)]
+                if let Ok(m) = fs::metadata(&target) {
+                    let mut permissions = m.permissions();
+                    permissions.set_readonly(false);
+                    let _ = fs::set_permissions(&target, permissions);
+                }
+                fs::remove_file(&target)
+                    .map_err(|e| format!("Failed to remove {}: {}", target.display(), e))?;
+            }
+            let (size, sha1) = copy_with_checksum(&source, &target, &mut |done, _size| {
+                job.set_progress((copied + done) as f32 / total.max(1) as f32);
+                !job.is_cancelled()
+            })
+            .map_err(|e| format!("Failed to copy {}: {}", source.display(), e))?;
+            apply_to_output(&target);
+            manifest.files.retain(|f| f.path != name);
+            manifest.files.push(DeliveryRecord {
+                source,
+                path: name,
+                size,
+                sha1,
+            });
+            manifest.write(&package_dir)?;
+            copied += size;
+        }
+
+        if let Some(format) = self.timeline {
+            let clips: Vec<DeliveryClip> = self
+                .items
+                .iter()
+                .map(|i| DeliveryClip::new(i, self.rate, self.ffmpeg.as_deref()))
+                .collect();
+            let text = match format {
+                TimelineFormat::Otio => timeline_otio(&self.name, &clips, self.rate).to_string(),
+                TimelineFormat::Edl => timeline_edl(&self.name, &clips, self.rate),
+                TimelineFormat::Csv => timeline_csv(&clips, self.rate),
+            };
+            let path = package_dir.join(format!("{}.{}", self.name, format.extension()));
+            fs::write(&path, text)
+                .map_err(|e| format!("Failed to write {}: {}", path.display(), e))?;
+        }
+        if let Some(archive) = &self.archive {
+            // Left by an interrupted package, the files may have changed since.
+            remove_archive(&archive.archive_path(&package_dir));
+            let files = archive.create(&package_dir, job)?;
+            info!(
+                "Archived {} in {} files",
+                package_dir.display(),
+                files.len()
+            );
+        }
+        manifest.complete = true;
+        manifest.write(&package_dir)?;
+        if skipped > 0 {
+            info!("Skipped {} files already in the package", skipped);
+        }
+        Ok(format!(
+            "Packaged {} outputs to {}",
+            self.items.len(),
+            package_dir.display()
+        ))
+    }
+}
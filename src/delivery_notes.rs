@@ -0,0 +1,249 @@
+use crate::deliveries::{DeliveryManifest, DeliveryPackage};
+use crate::helpers::{current_user, format_date, format_size, now_timestamp, substitute_tokens};
+use crate::naming::FilenameTemplate;
+use crate::sequences::OutputItem;
+use crate::vfs::{Fs, RealFs};
+use sha1::{Digest, Sha1};
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// The dir in the pipeline template with the note templates, `<client>.md` or `default.md` and the same
+/// for `.html`.
+pub const DELIVERY_NOTES_DIR_NAME: &str = "delivery_notes";
+
+const DEFAULT_MARKDOWN_TEMPLATE: &str = "# {package}
+
+Project: {project}
+Client: {client}
+Date: {date}
+From: {user}
+
+{items}
+
+{notes}
+";
+
+const DEFAULT_HTML_TEMPLATE: &str = "<h2>{package}</h2>
+<p>Project: {project}<br>
+Client: {client}<br>
+Date: {date}<br>
+From: {user}</p>
+{items}
+<p>{notes}</p>
+";
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NoteFormat {
+    Markdown,
+    Html,
+}
+
+impl NoteFormat {
+    pub const ALL: [NoteFormat; 2] = [NoteFormat::Markdown, NoteFormat::Html];
+
+    pub fn extension(&self) -> &'static str {
+        match self {
+            NoteFormat::Markdown => "md",
+            NoteFormat::Html => "html",
+        }
+    }
+
+    fn default_template(&self) -> &'static str {
+        match self {
+            NoteFormat::Markdown => DEFAULT_MARKDOWN_TEMPLATE,
+            NoteFormat::Html => DEFAULT_HTML_TEMPLATE,
+        }
+    }
+}
+
+/// A delivered output as listed in the note.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NoteItem {
+    pub name: String,
+    pub version: Option<u32>,
+    /// First and last frame of sequences.
+    pub frames: Option<(u32, u32)>,
+    pub size: u64,
+    /// Of the file, or of the frame checksums in order for sequences. Empty if not in the manifest.
+    pub sha1: String,
+}
+
+impl NoteItem {
+    pub fn new(item: &OutputItem, manifest: &DeliveryManifest) -> Self {
+        let stem = match item {
+            OutputItem::File { path, .. } => path
+                .file_stem()
+                .map(|s| s.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            OutputItem::Sequence(s) => s.prefix.trim_end_matches(['.', '_']).to_string(),
+        };
+        let version = FilenameTemplate::default()
+            .parse(&stem)
+            .ok()
+            .map(|p| p.tokens.version);
+        let checksums: Option<Vec<&str>> = item
+            .paths()
+            .iter()
+            .map(|p| {
+                let name = p.file_name().unwrap_or_default().to_string_lossy();
+                manifest
+                    .files
+                    .iter()
+                    .find(|f| f.path == name)
+                    .map(|f| f.sha1.as_str())
+            })
+            .collect();
+        let sha1 = match (item, checksums) {
+            (OutputItem::File { .. }, Some(c)) => c.concat(),
+            (OutputItem::Sequence(_), Some(c)) => {
+                let mut hasher = Sha1::new();
+                for checksum in c {
+                    hasher.update(checksum.as_bytes());
+                }
+                format!("{:x}", hasher.finalize())
+            }
+            (_, None) => String::new(),
+        };
+        Self {
+            name: item.name(),
+            version,
+            frames: match item {
+                OutputItem::Sequence(s) => Some((s.first(), s.last())),
+                OutputItem::File { .. } => None,
+            },
+            size: item.size(),
+            sha1,
+        }
+    }
+}
+
+/// What was delivered, for the mail to the client.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeliveryNote {
+    pub package: String,
+    pub project: String,
+    pub client: String,
+    pub date: String,
+    pub user: String,
+    pub notes: String,
+    pub items: Vec<NoteItem>,
+}
+
+impl DeliveryNote {
+    /// A note of the package, listing the items with the checksums from its manifest.
+    pub fn new(
+        package: &DeliveryPackage,
+        manifest: &DeliveryManifest,
+        project: &str,
+        client: &str,
+        notes: &str,
+    ) -> Self {
+        Self {
+            package: package.name.clone(),
+            project: String::from(project),
+            client: String::from(client),
+            date: format_date(now_timestamp()),
+            user: current_user(),
+            notes: String::from(notes),
+            items: package
+                .items
+                .iter()
+                .map(|i| NoteItem::new(i, manifest))
+                .collect(),
+        }
+    }
+
+    fn items_table(&self, format: NoteFormat) -> String {
+        let rows = self.items.iter().map(|i| {
+            [
+                i.name.clone(),
+                i.version.map(|v| format!("v{:03}", v)).unwrap_or_default(),
+                i.frames
+                    .map(|(first, last)| format!("{}-{}", first, last))
+                    .unwrap_or_default(),
+                format_size(i.size),
+                i.sha1.clone(),
+            ]
+        });
+        let header = ["Item", "Version", "Frames", "Size", "SHA-1"];
+        match format {
+            NoteFormat::Markdown => {
+                let mut table = format!("| {} |\n|---|---|---|---|---|\n", header.join(" | "));
+                for row in rows {
+                    let cells: Vec<String> = row.iter().map(|c| c.replace('|', "\\|")).collect();
+                    table.push_str(&format!("| {} |\n", cells.join(" | ")));
+                }
+                table.trim_end().to_string()
+            }
+            NoteFormat::Html => {
+                let mut table =
+                    format!("<table>\n<tr><th>{}</th></tr>\n", header.join("</th><th>"));
+                for row in rows {
+                    let cells: Vec<String> = row.iter().map(|c| escape_html(c)).collect();
+                    table.push_str(&format!("<tr><td>{}</td></tr>\n", cells.join("</td><td>")));
+                }
+                table.push_str("</table>");
+                table
+            }
+        }
+    }
+
+    /// The note from the template, the built-in one if none. Templates can use {package}, {project},
+    /// {client}, {date}, {user}, {items} and {notes}.
+    pub fn render(&self, format: NoteFormat, template: Option<&str>) -> String {
+        let escape = |s: &str| match format {
+            NoteFormat::Markdown => String::from(s),
+            NoteFormat::Html => escape_html(s),
+        };
+        let notes = match format {
+            NoteFormat::Markdown => self.notes.clone(),
+            NoteFormat::Html => escape_html(&self.notes).replace('\n', "<br>\n"),
+        };
+        let tokens = [
+            ("{package}", escape(&self.package)),
+            ("{project}", escape(&self.project)),
+            ("{client}", escape(&self.client)),
+            ("{date}", escape(&self.date)),
+            ("{user}", escape(&self.user)),
+            ("{items}", self.items_table(format)),
+            ("{notes}", notes),
+        ];
+        substitute_tokens(
+            template.unwrap_or_else(|| format.default_template()),
+            &tokens,
+        )
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// The template of the client in the pipeline template dir, or the default one there. None if neither
+/// exists.
+pub fn load_template(
+    pipeline_template: &Path,
+    client: &str,
+    format: NoteFormat,
+) -> Result<Option<String>, io::Error> {
+    let dir = pipeline_template.join(DELIVERY_NOTES_DIR_NAME);
+    for name in [client, "default"].iter().filter(|n| !n.is_empty()) {
+        let path = dir.join(format!("{}.{}", name, format.extension()));
+        if RealFs.is_file(&path) {
+            return RealFs.read_to_string(&path).map(Some);
+        }
+    }
+    Ok(None)
+}
+
+/// Where the note is saved, next to the package dir.
+pub fn note_path(package_dir: &Path, format: NoteFormat) -> PathBuf {
+    let name = package_dir
+        .file_name()
+        .unwrap_or_default()
+        .to_string_lossy();
+    package_dir.with_file_name(format!("{}_delivery_note.{}", name, format.extension()))
+}
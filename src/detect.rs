@@ -0,0 +1,220 @@
+use log::info;
+use std::cmp::Ordering;
+use std::env;
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+
+/// A DCC looked for on this machine.
+struct KnownDcc {
+    /// Names of the DCC in the templates dir, compared lowercase without spaces and punctuation.
+    names: &'static [&'static str],
+    /// Install locations. `*` matches any part of a name, the latest version is picked.
+    windows: &'static [&'static str],
+    macos: &'static [&'static str],
+    linux: &'static [&'static str],
+    /// Executable names looked for in PATH, and among the registered App Paths on Windows.
+    commands: &'static [&'static str],
+}
+
+const KNOWN_DCCS: [KnownDcc; 5] = [
+    KnownDcc {
+        names: &["maya"],
+        windows: &["C:/Program Files/Autodesk/Maya*/bin/maya.exe"],
+        macos: &["/Applications/Autodesk/maya*/Maya.app/Contents/MacOS/Maya"],
+        linux: &["/usr/autodesk/maya*/bin/maya"],
+        commands: &["maya", "maya.exe"],
+    },
+    KnownDcc {
+        names: &["houdini", "houdinifx"],
+        windows: &["C:/Program Files/Side Effects Software/Houdini*/bin/houdini.exe"],
+        macos: &["/Applications/Houdini/Houdini*/Houdini FX*.app/Contents/MacOS/houdini"],
+        linux: &["/opt/hfs*/bin/houdini"],
+        commands: &["houdini", "houdini.exe"],
+    },
+    KnownDcc {
+        names: &["nuke", "nukex"],
+        windows: &["C:/Program Files/Nuke*/Nuke*.exe"],
+        macos: &["/Applications/Nuke*/Nuke*.app/Contents/MacOS/Nuke*"],
+        linux: &["/usr/local/Nuke*/Nuke*"],
+        commands: &["Nuke*"],
+    },
+    KnownDcc {
+        names: &["blender"],
+        windows: &["C:/Program Files/Blender Foundation/Blender*/blender.exe"],
+        macos: &["/Applications/Blender.app/Contents/MacOS/Blender"],
+        linux: &["/opt/blender*/blender", "/snap/bin/blender"],
+        commands: &["blender", "blender.exe"],
+    },
+    KnownDcc {
+        names: &["aftereffects", "ae"],
+        windows: &["C:/Program Files/Adobe/Adobe After Effects*/Support Files/AfterFX.exe"],
+        macos: &["/Applications/Adobe After Effects*/Adobe After Effects*.app/Contents/MacOS/After Effects"],
+        linux: &[],
+        commands: &["AfterFX.exe"],
+    },
+];
+
+/// Lowercase without spaces and punctuation, so `After Effects` matches `aftereffects`.
+fn normalize(name: &str) -> String {
+    name.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(|c| c.to_lowercase())
+        .collect()
+}
+
+/// Matches a name against a pattern where `*` matches any run of characters.
+pub fn wildcard_match(pattern: &str, name: &str) -> bool {
+    let parts: Vec<&str> = pattern.split('*').collect();
+    if parts.len() == 1 {
+        return pattern == name;
+    }
+    let (first, last) = (parts[0], parts[parts.len() - 1]);
+    if !name.starts_with(first) || name.len() < first.len() + last.len() || !name.ends_with(last) {
+        return false;
+    }
+    let mut rest = &name[first.len()..name.len() - last.len()];
+    for part in &parts[1..parts.len() - 1] {
+        match rest.find(part) {
+            Some(i) => rest = &rest[i + part.len()..],
+            None => return false,
+        }
+    }
+    true
+}
+
+/// Compares names with runs of digits as numbers, so `Nuke13` sorts after `Nuke9`.
+pub fn natural_cmp(a: &str, b: &str) -> Ordering {
+    fn chunks(s: &str) -> Vec<(bool, String)> {
+        let mut chunks: Vec<(bool, String)> = Vec::new();
+        for c in s.chars() {
+            let digit = c.is_ascii_digit();
+            match chunks.last_mut() {
+                Some((d, chunk)) if *d == digit => chunk.push(c),
+                _ => chunks.push((digit, c.to_string())),
+            }
+        }
+        chunks
+    }
+    for (x, y) in chunks(a).iter().zip(chunks(b).iter()) {
+        let ordering = match (x, y) {
+            ((true, x), (true, y)) => {
+                let (x, y) = (x.trim_start_matches('0'), y.trim_start_matches('0'));
+                x.len().cmp(&y.len()).then_with(|| x.cmp(y))
+            }
+            ((_, x), (_, y)) => x.cmp(y),
+        };
+        if ordering != Ordering::Equal {
+            return ordering;
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+/// The files matching a path pattern, where `*` in a component matches any part of a name.
+/// Sorted so the latest version comes last.
+pub fn expand_pattern(pattern: &Path) -> Vec<PathBuf> {
+    let mut paths = vec![PathBuf::new()];
+    for component in pattern.components() {
+        let part = component.as_os_str().to_string_lossy();
+        if !matches!(component, Component::Normal(_)) || !part.contains('*') {
+            paths.iter_mut().for_each(|p| p.push(component));
+            continue;
+        }
+        let mut matched = Vec::new();
+        for dir in &paths {
+            let entries = match fs::read_dir(dir) {
+                Ok(e) => e,
+                Err(_e) => continue,
+            };
+            for entry in entries.filter_map(|e| e.ok()) {
+                if wildcard_match(&part, &entry.file_name().to_string_lossy()) {
+                    matched.push(entry.path());
+                }
+            }
+        }
+        paths = matched;
+    }
+    paths.retain(|p| p.is_file());
+    paths.sort_by(|a, b| natural_cmp(&a.to_string_lossy(), &b.to_string_lossy()));
+    paths
+}
+
+/// Executables in PATH matching the command, latest version last.
+fn find_in_path(command: &str) -> Vec<PathBuf> {
+    let path = match env::var_os("PATH") {
+        Some(p) => p,
+        None => return Vec::new(),
+    };
+    env::split_paths(&path)
+        .flat_map(|dir| expand_pattern(&dir.join(command)))
+        .collect()
+}
+
+/// The executable registered for the command under App Paths.
+#[cfg(windows)]
+fn find_in_registry(command: &str) -> Option<PathBuf> {
+    let key = format!(
+        "HKLM\\SOFTWARE\\Microsoft\\Windows\\CurrentVersion\\App Paths\\{}",
+        command
+    );
+    let output = std::process::Command::new("reg")
+        .args(["query", key.as_str(), "/ve"])
+        .output()
+        .ok()?;
+    String::from_utf8_lossy(&output.stdout)
+        .lines()
+        .find_map(|l| {
+            l.split_once("REG_SZ")
+                .map(|(_k, v)| v.trim().trim_matches('"').to_string())
+        })
+        .map(PathBuf::from)
+        .filter(|p| p.is_file())
+}
+
+#[cfg(not(windows))]
+fn find_in_registry(_command: &str) -> Option<PathBuf> {
+    None
+}
+
+fn install_patterns(dcc: &KnownDcc) -> &'static [&'static str] {
+    if cfg!(windows) {
+        dcc.windows
+    } else if cfg!(target_os = "macos") {
+        dcc.macos
+    } else {
+        dcc.linux
+    }
+}
+
+/// Looks for the common DCC:s in their install locations, PATH and on Windows the registry.
+/// Returns the names of the DCC:s found with their latest executable.
+pub fn detect_executables() -> Vec<(&'static [&'static str], PathBuf)> {
+    let mut found = Vec::new();
+    for dcc in &KNOWN_DCCS {
+        let mut candidates: Vec<PathBuf> = install_patterns(dcc)
+            .iter()
+            .flat_map(|p| expand_pattern(Path::new(p)))
+            .collect();
+        for command in dcc.commands {
+            candidates.extend(find_in_registry(command));
+            candidates.extend(find_in_path(command));
+        }
+        if let Some(executable) = candidates.pop() {
+            info!("Found {}: {}", dcc.names[0], executable.display());
+            found.push((dcc.names, executable));
+        }
+    }
+    found
+}
+
+/// The detected executable for a DCC by its name in the templates dir.
+pub fn executable_for<'a>(
+    detected: &'a [(&'static [&'static str], PathBuf)],
+    name: &str,
+) -> Option<&'a PathBuf> {
+    let name = normalize(name);
+    detected
+        .iter()
+        .find(|(names, _e)| names.iter().any(|n| *n == name))
+        .map(|(_n, e)| e)
+}
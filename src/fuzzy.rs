@@ -0,0 +1,78 @@
+const MATCH_SCORE: i64 = 1;
+const CONSECUTIVE_BONUS: i64 = 5;
+const WORD_START_BONUS: i64 = 8;
+const SUBSTRING_BONUS: i64 = 20;
+const MAX_GAP_PENALTY: i64 = 3;
+
+fn is_separator(c: char) -> bool {
+    matches!(c, '_' | '-' | ' ' | '.' | '/' | '\\')
+}
+
+/// Scores how well the pattern matches the text as a case-insensitive subsequence, e.g. `acsp` matches
+/// `2301_acme_spot`. Consecutive matches, matches at word starts and whole substrings score higher.
+/// Returns None if the text doesn't contain all pattern characters in order.
+pub fn fuzzy_score(pattern: &str, text: &str) -> Option<i64> {
+    let pattern: Vec<char> = pattern.to_lowercase().chars().collect();
+    if pattern.is_empty() {
+        return Some(0);
+    }
+    let original: Vec<char> = text.chars().collect();
+    let lower: Vec<char> = text.to_lowercase().chars().collect();
+    // Lowercasing can change the length of some characters, fall back to the lowercase text only.
+    let original = if original.len() == lower.len() {
+        original
+    } else {
+        lower.clone()
+    };
+
+    let mut score = 0;
+    let mut next = 0;
+    let mut last_match: Option<usize> = None;
+    for (i, c) in lower.iter().enumerate() {
+        if next == pattern.len() {
+            break;
+        }
+        if *c != pattern[next] {
+            continue;
+        }
+
+        score += MATCH_SCORE;
+        match last_match {
+            Some(l) if l + 1 == i => score += CONSECUTIVE_BONUS,
+            Some(l) => score -= ((i - l - 1) as i64).min(MAX_GAP_PENALTY),
+            None => (),
+        }
+        let word_start = i == 0
+            || is_separator(original[i - 1])
+            || (original[i].is_uppercase() && original[i - 1].is_lowercase());
+        if word_start {
+            score += WORD_START_BONUS;
+        }
+        last_match = Some(i);
+        next += 1;
+    }
+
+    if next < pattern.len() {
+        return None;
+    }
+    let pattern: String = pattern.iter().collect();
+    let lower: String = lower.iter().collect();
+    if lower.contains(&pattern) {
+        score += SUBSTRING_BONUS;
+    }
+    Some(score)
+}
+
+/// Keeps the items matching the pattern, best matches first. Items with equal scores keep their order.
+pub fn fuzzy_filter<T, F>(items: &[T], pattern: &str, key: F) -> Vec<T>
+where
+    T: Clone,
+    F: Fn(&T) -> &str,
+{
+    let mut scored: Vec<(i64, &T)> = items
+        .iter()
+        .filter_map(|i| Some((fuzzy_score(pattern, key(i))?, i)))
+        .collect();
+    scored.sort_by_key(|(s, _)| std::cmp::Reverse(*s));
+    scored.into_iter().map(|(_, i)| i.clone()).collect()
+}
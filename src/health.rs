@@ -0,0 +1,112 @@
+use crate::tasks::{TaskTreeNode, TASK_FILE_NAME};
+use crate::Project;
+use log::info;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+const MAX_CHECK_DEPTH: u8 = 8;
+
+/// A difference between a project on disk and its template.
+#[derive(Clone, Debug, PartialEq)]
+pub enum HealthIssue {
+    /// A standard project dir or a work sub dir of a task is missing.
+    MissingDir(PathBuf),
+    /// A folder with work sub dirs but no task file, usually created outside rclamp.
+    NotATask(PathBuf),
+    /// A file in the work root or a folder, outside of any task.
+    StrayFile(PathBuf),
+}
+
+impl HealthIssue {
+    pub fn path(&self) -> &Path {
+        match self {
+            HealthIssue::MissingDir(p) | HealthIssue::NotATask(p) | HealthIssue::StrayFile(p) => p,
+        }
+    }
+
+    /// Stray files need a decision from the user and can't be fixed automatically.
+    pub fn is_fixable(&self) -> bool {
+        !matches!(self, HealthIssue::StrayFile(_))
+    }
+
+    /// Creates the missing dir or converts the folder to a task.
+    pub fn fix(&self, work_sub_dirs: &[String]) -> Result<(), io::Error> {
+        match self {
+            HealthIssue::MissingDir(p) => fs::create_dir_all(p),
+            HealthIssue::NotATask(p) => TaskTreeNode::convert_to_task(p, work_sub_dirs),
+            HealthIssue::StrayFile(p) => Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("{} has to be moved by hand", p.display()),
+            )),
+        }
+    }
+}
+
+/// Compares the project dir with the template: missing standard dirs and task sub dirs, folders that look
+/// like tasks without a task file and files outside of tasks in the work dir.
+pub fn check_project(
+    project: &Project,
+    projects_dir: &PathBuf,
+) -> Result<Vec<HealthIssue>, io::Error> {
+    let project_path = project.get_path(projects_dir);
+    info!("Checking project health: {}", project_path.display());
+    if !project_path.is_dir() {
+        return Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("{} doesn't exist", project_path.display()),
+        ));
+    }
+
+    let mut issues = Vec::new();
+    let mut dirs = vec![
+        project.get_pipeline_path(projects_dir),
+        project.get_work_path(projects_dir),
+        project.get_dailies_path(projects_dir),
+        project.get_deliveries_path(projects_dir),
+    ];
+    dirs.extend(project.extra_dir_names.iter().map(|d| project_path.join(d)));
+    for d in dirs {
+        if !d.is_dir() {
+            issues.push(HealthIssue::MissingDir(d));
+        }
+    }
+
+    let work_path = project.get_work_path(projects_dir);
+    if work_path.is_dir() {
+        check_folder(&work_path, &project.task_sub_dirs(), 0, &mut issues)?;
+    }
+    Ok(issues)
+}
+
+fn check_folder(
+    dir: &Path,
+    work_sub_dirs: &[String],
+    depth: u8,
+    issues: &mut Vec<HealthIssue>,
+) -> Result<(), io::Error> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|e| e.ok())
+        .filter(|e| !e.file_name().to_string_lossy().starts_with('.'))
+        .map(|e| e.path())
+        .collect();
+    entries.sort();
+
+    for path in entries {
+        if path.is_file() {
+            issues.push(HealthIssue::StrayFile(path));
+        } else if path.join(TASK_FILE_NAME).exists() {
+            for sub_dir in work_sub_dirs {
+                let sub_path = path.join(sub_dir);
+                if !sub_path.is_dir() {
+                    issues.push(HealthIssue::MissingDir(sub_path));
+                }
+            }
+        } else if work_sub_dirs.iter().any(|s| path.join(s).is_dir()) {
+            issues.push(HealthIssue::NotATask(path));
+        } else if depth < MAX_CHECK_DEPTH {
+            check_folder(&path, work_sub_dirs, depth + 1, issues)?;
+        }
+    }
+    Ok(())
+}
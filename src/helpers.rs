@@ -1,26 +1,290 @@
+use log::error;
+use std::collections::hash_map::RandomState;
+use std::collections::HashMap;
+use std::fs;
+use std::hash::{BuildHasher, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+use std::time::{SystemTime, UNIX_EPOCH};
+use unicode_normalization::UnicodeNormalization;
+
 pub const EXPLORER: &str = "explorer";
 pub const FINDER: &str = "finder";
 pub const PROJECT_FILE_NAME: &str = "project.yaml";
+/// Dir of the app in the user config dir.
+pub const CONFIG_DIR_NAME: &str = "rclamp";
+/// Subdir of the templates dir, copied into the pipeline dir of new projects.
+pub const PIPELINE_TEMPLATE_DIR_NAME: &str = "pipeline";
+
+/// Transliterations applied before the Unicode decomposition pass, for characters without an ASCII base letter.
+const DEFAULT_TRANSLITERATIONS: [(char, &str); 9] = [
+    ('ß', "ss"),
+    ('æ', "ae"),
+    ('ø', "o"),
+    ('œ', "oe"),
+    ('þ', "th"),
+    ('ð', "d"),
+    ('đ', "d"),
+    ('ł', "l"),
+    ('ı', "i"),
+];
+
+/// Transliterations from the config, set once the config is loaded. Overrides the defaults.
+static TRANSLITERATIONS: RwLock<Option<HashMap<char, String>>> = RwLock::new(None);
+
+/// Sets the configured transliteration table used by `sanitize_string`. Keys are single characters.
+pub fn set_transliterations(table: &HashMap<String, String>) {
+    let table: HashMap<char, String> = table
+        .iter()
+        .filter_map(|(k, v)| Some((k.to_lowercase().chars().next()?, v.to_lowercase())))
+        .collect();
 
+    match TRANSLITERATIONS.write() {
+        Ok(mut t) => *t = Some(table),
+        Err(e) => error!("Failed to set transliterations: {}", e),
+    }
+}
+
+fn transliterate(c: char) -> Option<String> {
+    if let Ok(table) = TRANSLITERATIONS.read() {
+        if let Some(t) = table.as_ref().and_then(|t| t.get(&c)) {
+            return Some(t.clone());
+        }
+    }
+    DEFAULT_TRANSLITERATIONS
+        .iter()
+        .find(|(k, _v)| *k == c)
+        .map(|(_k, v)| String::from(*v))
+}
+
+/// Lowercases and reduces a string to ascii alphanumerics and underscores.
+/// Characters are transliterated using the configured table, then accents are stripped (é becomes e).
+/// Letters without an ascii equivalent, like CJK, are kept as their code point, e.g. u4e2d.
 pub fn sanitize_string(mut s: String) -> String {
     let mut output = String::new();
     s = s.to_lowercase();
     for c in s.chars() {
-        let mut cc = c.clone();
-        if cc.is_ascii_alphanumeric() {
-            output.push(cc);
-        } else {
-            cc = match cc {
-                '_' => '_',
-                '-' => '_',
-                'å' => 'a',
-                'ä' => 'a',
-                'ö' => 'o',
-                _ => continue,
-            };
-            output.push(cc);
+        if c.is_ascii_alphanumeric() {
+            output.push(c);
+            continue;
+        }
+
+        if c == '_' || c == '-' {
+            output.push('_');
+            continue;
+        }
+
+        if let Some(t) = transliterate(c) {
+            output.extend(
+                t.chars()
+                    .filter(|tc| tc.is_ascii_alphanumeric() || *tc == '_'),
+            );
+            continue;
+        }
+
+        let base: String = c.nfd().filter(|d| d.is_ascii_alphanumeric()).collect();
+        if !base.is_empty() {
+            output.push_str(&base);
+        } else if c.is_alphanumeric() {
+            output.push_str(&format!("u{:04x}", c as u32));
+        }
+    }
+
+    output
+}
+
+/// Splits a line of CSV into fields, handling quoted fields and escaped quotes.
+pub fn parse_csv_line(line: &str) -> Vec<String> {
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut in_quotes = false;
+    let mut chars = line.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        match c {
+            '"' if in_quotes && chars.peek() == Some(&'"') => {
+                field.push('"');
+                chars.next();
+            }
+            '"' => in_quotes = !in_quotes,
+            ',' if !in_quotes => {
+                fields.push(field.trim().to_string());
+                field = String::new();
+            }
+            _ => field.push(c),
         }
     }
+    fields.push(field.trim().to_string());
+
+    fields
+}
 
+/// Replaces the tokens in one pass over the text, so tokens in the substituted values, like a `{TASK}` in
+/// a task path or an `{items}` typed in delivery notes, are left as they are. Tokens start with `{`.
+pub fn substitute_tokens<T: AsRef<str>, V: AsRef<str>>(text: &str, tokens: &[(T, V)]) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find('{') {
+        output.push_str(&rest[..start]);
+        rest = &rest[start..];
+        match tokens
+            .iter()
+            .find(|(token, _)| rest.starts_with(token.as_ref()))
+        {
+            Some((token, value)) => {
+                output.push_str(value.as_ref());
+                rest = &rest[token.as_ref().len()..];
+            }
+            None => {
+                output.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    output.push_str(rest);
     output
 }
+
+/// Opens the folder in Explorer on Windows and Finder elsewhere.
+pub fn open_folder(path: PathBuf) {
+    let command = if cfg!(windows) { EXPLORER } else { FINDER };
+    if let Err(e) = open::with(std::ffi::OsString::from(&path), command) {
+        error!("Failed to open {}: {}", path.display(), e);
+    }
+}
+
+/// Reads a config file as TOML or JSON by extension, anything else is read as YAML.
+pub fn read_config_file<T: serde::de::DeserializeOwned>(path: &Path) -> Result<T, String> {
+    let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let extension = path
+        .extension()
+        .map(|e| e.to_string_lossy().to_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "toml" => toml::from_str(&text).map_err(|e| e.to_string()),
+        "json" => serde_json::from_str(&text).map_err(|e| e.to_string()),
+        _ => serde_yaml::from_str(&text).map_err(|e| e.to_string()),
+    }
+}
+
+/// Returns the current time as seconds since the unix epoch.
+pub fn now_timestamp() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// 32 random hex digits, for tokens and names that mustn't collide with other processes or machines.
+/// Std seeds every `RandomState` from the OS, which is enough without pulling in a rand crate.
+pub fn random_hex() -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or(0);
+    (0..2)
+        .map(|_| {
+            let mut hasher = RandomState::new().build_hasher();
+            hasher.write_u128(nanos);
+            hasher.write_u32(std::process::id());
+            format!("{:016x}", hasher.finish())
+        })
+        .collect()
+}
+
+/// The dir of the app in the user config dir: `%APPDATA%\rclamp` on Windows, `$XDG_CONFIG_HOME/rclamp`
+/// or `~/.config/rclamp` elsewhere.
+pub fn user_config_dir() -> Option<PathBuf> {
+    let dir = if cfg!(windows) {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| std::env::var_os("HOME").map(|h| PathBuf::from(h).join(".config")))
+    };
+    dir.map(|d| d.join(CONFIG_DIR_NAME))
+}
+
+/// Returns the name of the logged in user, from `USERNAME` on Windows and `USER` elsewhere.
+pub fn current_user() -> String {
+    let var = if cfg!(windows) { "USERNAME" } else { "USER" };
+    std::env::var(var).unwrap_or_default()
+}
+
+/// The name of this machine. macOS doesn't export HOSTNAME to apps, so it falls back to `hostname`.
+pub fn current_host() -> String {
+    let var = if cfg!(windows) {
+        "COMPUTERNAME"
+    } else {
+        "HOSTNAME"
+    };
+    match std::env::var(var) {
+        Ok(h) if !h.is_empty() => h,
+        _ => std::process::Command::new("hostname")
+            .output()
+            .map(|o| String::from_utf8_lossy(&o.stdout).trim().to_string())
+            .unwrap_or_default(),
+    }
+}
+
+/// Converts days since the unix epoch to a (year, month, day) date.
+pub fn date_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// Converts a date to days since the unix epoch, the inverse of `date_from_days`.
+pub fn days_from_date(year: i64, month: u32, day: u32) -> i64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year.div_euclid(400);
+    let yoe = year.rem_euclid(400);
+    let mp = if month > 2 { month - 3 } else { month + 9 } as i64;
+    let doy = (153 * mp + 2) / 5 + day as i64 - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+/// Formats a unix timestamp as `YYYY-MM-DD HH:MM` in UTC.
+pub fn format_timestamp(timestamp: u64) -> String {
+    let days = (timestamp / 86400) as i64;
+    let seconds = timestamp % 86400;
+    let (year, month, day) = date_from_days(days);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        year,
+        month,
+        day,
+        seconds / 3600,
+        (seconds % 3600) / 60
+    )
+}
+
+/// Formats a unix timestamp as a UTC date, `YYYY-MM-DD`.
+pub fn format_date(timestamp: u64) -> String {
+    let (year, month, day) = date_from_days((timestamp / 86400) as i64);
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+/// Formats a size in bytes for display, e.g. `1.5 GB`.
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 5] = ["B", "KB", "MB", "GB", "TB"];
+    let mut size = bytes as f64;
+    let mut unit = 0;
+    while size >= 1024. && unit < UNITS.len() - 1 {
+        size /= 1024.;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", size, UNITS[unit])
+    }
+}
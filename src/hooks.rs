@@ -0,0 +1,167 @@
+use crate::pathmap::to_local;
+use crate::File;
+use crate::Project;
+use crate::TaskTreeNode;
+use log::{error, info};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+pub const HOOKS_FILE_NAME: &str = "hooks.yaml";
+
+/// The points where hooks can run. Pre-hooks can cancel the operation by exiting with a non-zero status.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum HookEvent {
+    PreProjectCreate,
+    PostProjectCreate,
+    PreFileOpen,
+    PostFileOpen,
+}
+
+impl HookEvent {
+    fn name(&self) -> &'static str {
+        match self {
+            HookEvent::PreProjectCreate => "pre_project_create",
+            HookEvent::PostProjectCreate => "post_project_create",
+            HookEvent::PreFileOpen => "pre_file_open",
+            HookEvent::PostFileOpen => "post_file_open",
+        }
+    }
+}
+
+/// Scripts to run for each hook event, read from `hooks.yaml` in a pipeline dir.
+/// Relative script paths are resolved against the dir the file was read from.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct Hooks {
+    pub pre_project_create: Vec<PathBuf>,
+    pub post_project_create: Vec<PathBuf>,
+    pub pre_file_open: Vec<PathBuf>,
+    pub post_file_open: Vec<PathBuf>,
+    #[serde(skip)]
+    pub dir: PathBuf,
+}
+
+/// The context passed to hook scripts as environment variables.
+#[derive(Default)]
+pub struct HookContext<'a> {
+    pub project: Option<&'a Project>,
+    pub projects_dir: Option<&'a Path>,
+    pub task: Option<&'a TaskTreeNode>,
+    pub file: Option<&'a File>,
+}
+
+impl HookContext<'_> {
+    fn env(&self, event: HookEvent) -> HashMap<&'static str, String> {
+        let mut env = HashMap::new();
+        env.insert("RCLAMP_HOOK", String::from(event.name()));
+
+        if let Some(p) = self.project {
+            env.insert("RCLAMP_PROJECT", p.name_sanitized.clone());
+            if let Some(d) = self.projects_dir {
+                let projects_dir = d.to_path_buf();
+                env.insert("RCLAMP_PROJECTS_DIR", d.display().to_string());
+                env.insert(
+                    "RCLAMP_PROJECT_PATH",
+                    p.get_path(&projects_dir).display().to_string(),
+                );
+                env.insert(
+                    "RCLAMP_PIPELINE_PATH",
+                    p.get_pipeline_path(&projects_dir).display().to_string(),
+                );
+            }
+        }
+        if let Some(t) = self.task {
+            env.insert("RCLAMP_TASK", t.name.clone());
+            env.insert("RCLAMP_TASK_PATH", t.path.display().to_string());
+        }
+        if let Some(f) = self.file {
+            env.insert("RCLAMP_FILE", f.path.display().to_string());
+            env.insert("RCLAMP_FILE_VERSION", f.version.to_string());
+        }
+        env
+    }
+}
+
+impl Hooks {
+    /// Reads `hooks.yaml` from the dir. A missing file means no hooks.
+    pub fn load(dir: &Path) -> Result<Hooks, String> {
+        let path = dir.join(HOOKS_FILE_NAME);
+        if !path.exists() {
+            return Ok(Hooks {
+                dir: dir.to_path_buf(),
+                ..Default::default()
+            });
+        }
+
+        let file = match std::fs::File::open(&path) {
+            Ok(f) => f,
+            Err(e) => return Err(format!("Failed to open {}: {}", path.display(), e)),
+        };
+        let mut hooks: Hooks = match serde_yaml::from_reader(file) {
+            Ok(h) => h,
+            Err(e) => {
+                let message = format!("Failed to read {}: {}", path.display(), e);
+                error!("{}", message);
+                return Err(message);
+            }
+        };
+        hooks.dir = dir.to_path_buf();
+        for scripts in [
+            &mut hooks.pre_project_create,
+            &mut hooks.post_project_create,
+            &mut hooks.pre_file_open,
+            &mut hooks.post_file_open,
+        ] {
+            for script in scripts.iter_mut() {
+                *script = to_local(script);
+            }
+        }
+        Ok(hooks)
+    }
+
+    fn scripts(&self, event: HookEvent) -> &[PathBuf] {
+        match event {
+            HookEvent::PreProjectCreate => &self.pre_project_create,
+            HookEvent::PostProjectCreate => &self.post_project_create,
+            HookEvent::PreFileOpen => &self.pre_file_open,
+            HookEvent::PostFileOpen => &self.post_file_open,
+        }
+    }
+
+    /// Whether there are scripts for the event.
+    pub fn handles(&self, event: HookEvent) -> bool {
+        !self.scripts(event).is_empty()
+    }
+
+    /// Runs all scripts for the event in order, stopping at the first failure. Waits for the scripts, so
+    /// post-hooks should run as a job.
+    pub fn run(&self, event: HookEvent, context: &HookContext<'_>) -> Result<(), String> {
+        let env = context.env(event);
+
+        for script in self.scripts(event) {
+            let script = self.dir.join(script);
+            info!("Running {} hook: {}", event.name(), script.display());
+
+            let status = Command::new(&script)
+                .envs(&env)
+                .current_dir(&self.dir)
+                .status();
+
+            match status {
+                Ok(s) if s.success() => (),
+                Ok(s) => {
+                    let message = format!("Hook {} failed: {}", script.display(), s);
+                    error!("{}", message);
+                    return Err(message);
+                }
+                Err(e) => {
+                    let message = format!("Failed to run hook {}: {}", script.display(), e);
+                    error!("{}", message);
+                    return Err(message);
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,59 @@
+use log::error;
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::sync::RwLock;
+
+/// Language codes and names for the preferences. English is the source language of the UI strings.
+pub const LANGUAGES: [(&str, &str); 2] = [("en", "English"), ("sv", "Svenska")];
+pub const DEFAULT_LANGUAGE: &str = "en";
+
+/// String tables mapping the English UI strings to translations, `{}` marks arguments.
+const TABLES: [(&str, &str); 1] = [("sv", include_str!("../locales/sv.yaml"))];
+
+/// The table of the current language, None for English.
+static TABLE: RwLock<Option<HashMap<String, String>>> = RwLock::new(None);
+
+/// Sets the language of the UI strings. Unknown languages fall back to English.
+pub fn set_language(code: &str) {
+    let table = match TABLES.iter().find(|(c, _)| *c == code) {
+        Some((_, yaml)) => match serde_yaml::from_str::<HashMap<String, String>>(yaml) {
+            Ok(t) => Some(t),
+            Err(e) => {
+                error!("Failed to read string table {}: {}", code, e);
+                None
+            }
+        },
+        None => None,
+    };
+
+    match TABLE.write() {
+        Ok(mut t) => *t = table,
+        Err(e) => error!("Failed to set language: {}", e),
+    }
+}
+
+/// Translates a UI string, strings missing from the table are shown as is.
+pub fn tr(text: &str) -> String {
+    if let Ok(table) = TABLE.read() {
+        if let Some(t) = table.as_ref().and_then(|t| t.get(text)) {
+            return t.clone();
+        }
+    }
+    String::from(text)
+}
+
+/// Translates a UI string and replaces its `{}` placeholders with the arguments in order.
+pub fn trf(text: &str, args: &[&dyn Display]) -> String {
+    let translated = tr(text);
+    let mut parts = translated.split("{}");
+    let mut result = String::from(parts.next().unwrap_or_default());
+    let mut args = args.iter();
+    for part in parts {
+        match args.next() {
+            Some(a) => result.push_str(&a.to_string()),
+            None => result.push_str("{}"),
+        }
+        result.push_str(part);
+    }
+    result
+}
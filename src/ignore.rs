@@ -0,0 +1,61 @@
+use regex::{Regex, RegexBuilder};
+
+/// Prefix for patterns that are regular expressions instead of globs.
+const REGEX_PREFIX: &str = "re:";
+
+/// Filename patterns for files that shouldn't be listed, e.g. `*.autosave`, `*_backup_*` or `.*`.
+/// Globs support `*` and `?`, patterns starting with `re:` are regular expressions. Matching ignores case.
+#[derive(Clone, Debug, Default)]
+pub struct IgnorePatterns {
+    patterns: Vec<Regex>,
+}
+
+impl IgnorePatterns {
+    /// Compiles the patterns, returns an error naming the first invalid one.
+    pub fn new(patterns: &[String]) -> Result<Self, String> {
+        let mut compiled = Vec::new();
+
+        for pattern in patterns {
+            let pattern = pattern.trim();
+            if pattern.is_empty() {
+                continue;
+            }
+
+            let regex = match pattern.strip_prefix(REGEX_PREFIX) {
+                Some(r) => String::from(r),
+                None => Self::glob_to_regex(pattern),
+            };
+            match RegexBuilder::new(&regex).case_insensitive(true).build() {
+                Ok(r) => compiled.push(r),
+                Err(e) => return Err(format!("Invalid ignore pattern {}: {}", pattern, e)),
+            }
+        }
+        Ok(Self { patterns: compiled })
+    }
+
+    fn glob_to_regex(glob: &str) -> String {
+        let mut regex = String::from("^");
+        for c in glob.chars() {
+            match c {
+                '*' => regex.push_str(".*"),
+                '?' => regex.push('.'),
+                _ => regex.push_str(&regex::escape(&c.to_string())),
+            }
+        }
+        regex.push('$');
+        regex
+    }
+
+    /// Converts the old extension list to patterns, `autosave` becomes `*.autosave`.
+    pub fn from_extensions(extensions: &[String]) -> Vec<String> {
+        extensions
+            .iter()
+            .map(|e| format!("*.{}", e.trim_start_matches('.')))
+            .collect()
+    }
+
+    /// Checks a filename, without directories, against the patterns.
+    pub fn is_ignored(&self, filename: &str) -> bool {
+        self.patterns.iter().any(|p| p.is_match(filename))
+    }
+}
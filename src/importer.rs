@@ -0,0 +1,101 @@
+use crate::helpers::parse_csv_line;
+use crate::structure::{create_entry, is_header, StructureEntry};
+use crate::Project;
+use crate::TaskTreeNode;
+use log::{error, info};
+use std::fs;
+use std::path::PathBuf;
+
+/// A task read from a row in a CSV file. Excel sheets can be imported by saving them as CSV.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, PartialEq)]
+pub struct ImportRow {
+    pub row: usize,
+    pub entry: StructureEntry,
+    pub status: Option<String>,
+}
+
+/// Result of importing a single row, shown in the results dialog.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug)]
+pub struct ImportResult {
+    pub row: usize,
+    pub entry: String,
+    pub error: Option<String>,
+}
+
+impl ImportRow {
+    /// Reads a CSV file of tasks. The first column is the task name or a nested path, e.g. `sq010/sh0010/comp`,
+    /// an optional second column is the task status. A header row naming the columns is skipped.
+    pub fn read_csv(path: &PathBuf) -> Result<Vec<ImportRow>, String> {
+        info!("Reading task list: {}", path.display());
+        let text = match fs::read_to_string(path) {
+            Ok(t) => t,
+            Err(e) => {
+                let message = format!("Failed to read {}: {}", path.display(), e);
+                error!("{}", message);
+                return Err(message);
+            }
+        };
+        Ok(ImportRow::parse_csv(&text))
+    }
+
+    /// Parses CSV text into rows, see `read_csv`.
+    pub fn parse_csv(text: &str) -> Vec<ImportRow> {
+        let mut rows = Vec::new();
+
+        for (i, line) in text.lines().enumerate() {
+            let fields = parse_csv_line(line);
+            let path = fields.first().cloned().unwrap_or_default();
+
+            if i == 0 && is_header(fields.iter().map(String::as_str)) {
+                continue;
+            }
+
+            let entry = match StructureEntry::from_line(&path) {
+                Some(e) => e,
+                None => continue,
+            };
+
+            let status = fields
+                .get(1)
+                .map(|s| s.trim().to_string())
+                .filter(|s| !s.is_empty());
+
+            rows.push(ImportRow {
+                row: i + 1,
+                entry,
+                status,
+            });
+        }
+        rows
+    }
+}
+
+/// Creates the tasks of all rows under the parent node, setting statuses where given.
+pub fn import_tasks(
+    rows: &[ImportRow],
+    parent: &TaskTreeNode,
+    project: &Project,
+) -> Vec<ImportResult> {
+    let mut results = Vec::new();
+
+    for r in rows {
+        let created = create_entry(&r.entry, parent, project).and_then(|task| match &r.status {
+            Some(s) => task.set_status(s.clone()),
+            None => Ok(()),
+        });
+
+        let error = match created {
+            Ok(()) => None,
+            Err(e) => {
+                error!("Failed to import row {}: {}", r.row, e);
+                Some(e.to_string())
+            }
+        };
+        results.push(ImportResult {
+            row: r.row,
+            entry: r.entry.display(),
+            error,
+        });
+    }
+    results
+}
@@ -0,0 +1,273 @@
+use crate::atomic::write_yaml;
+use crate::helpers::{current_user, format_date, now_timestamp, sanitize_string};
+use crate::jobs::JobHandle;
+use crate::sequences::{collapse, find_files, OutputItem};
+use crate::vfs::{copy_inspecting, Fs, RealFs};
+use log::{error, info};
+use sha1::{Digest, Sha1};
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+
+pub const INGEST_MANIFEST_FILE_NAME: &str = "ingest_manifest.yaml";
+const COPY_BUFFER_SIZE: usize = 1 << 20;
+
+/// Where client footage is copied in the project, and how it's renamed.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, PartialEq)]
+#[serde(default)]
+pub struct IngestSettings {
+    /// Relative to the project dir, e.g. `01_preproduction/footage`. Each day gets a dated subdir.
+    pub dir: PathBuf,
+    /// The new name without extension and frame number. Tokens: `{project}`, `{date}`, `{name}` for the
+    /// sanitized source name and `{index}` for the position in the ingest.
+    pub template: String,
+    /// A watch folder. Files dropped here are listed for review, then ingested into the current project,
+    /// or the project named by the subdir they're in.
+    pub inbox: Option<PathBuf>,
+}
+
+impl Default for IngestSettings {
+    fn default() -> Self {
+        Self {
+            dir: PathBuf::from("footage"),
+            template: String::from("{project}_{date}_{name}"),
+            inbox: None,
+        }
+    }
+}
+
+/// A source file or sequence and the name it gets in the project.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IngestItem {
+    pub item: OutputItem,
+    pub name: String,
+}
+
+impl IngestItem {
+    /// Each source path with its new file name. Sequences keep their frame numbers and separator.
+    pub fn targets(&self) -> Vec<(PathBuf, String)> {
+        match &self.item {
+            OutputItem::File { path, .. } => {
+                let extension = path
+                    .extension()
+                    .map(|e| format!(".{}", e.to_string_lossy()))
+                    .unwrap_or_default();
+                vec![(path.clone(), format!("{}{}", self.name, extension))]
+            }
+            OutputItem::Sequence(s) => {
+                let separator = s.prefix.chars().last().unwrap_or('.');
+                s.frames
+                    .iter()
+                    .map(|f| {
+                        let target = format!(
+                            "{}{}{:0width$}{}",
+                            self.name,
+                            separator,
+                            f,
+                            s.suffix,
+                            width = s.padding
+                        );
+                        (s.frame_path(*f), target)
+                    })
+                    .collect()
+            }
+        }
+    }
+
+    /// The new name as listed, with `#` for the frame numbers of sequences.
+    pub fn target_name(&self) -> String {
+        match &self.item {
+            OutputItem::File { .. } => self.targets().pop().map(|(_s, t)| t).unwrap_or_default(),
+            OutputItem::Sequence(s) => format!(
+                "{}{}{}{}",
+                self.name,
+                s.prefix.chars().last().unwrap_or('.'),
+                "#".repeat(s.padding),
+                s.suffix
+            ),
+        }
+    }
+}
+
+/// The name of the source without extension or frame number.
+fn source_name(item: &OutputItem) -> String {
+    match item {
+        OutputItem::File { path, .. } => path
+            .file_stem()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string(),
+        OutputItem::Sequence(s) => s.prefix.trim_end_matches(['.', '_']).to_string(),
+    }
+}
+
+/// The dated dir footage ingested at the time is copied to.
+pub fn ingest_dir(project_path: &Path, settings: &IngestSettings, timestamp: u64) -> PathBuf {
+    project_path
+        .join(&settings.dir)
+        .join(format_date(timestamp))
+}
+
+/// Lists the source files and the files in source dirs, with sequences collapsed, and names them.
+pub fn plan_ingest(
+    sources: &[PathBuf],
+    template: &str,
+    project: &str,
+    timestamp: u64,
+) -> Result<Vec<IngestItem>, io::Error> {
+    let mut paths = Vec::new();
+    for source in sources {
+        if source.is_dir() {
+            paths.extend(find_files(source, &|n: &str| n.starts_with('.'))?);
+        } else if source.is_file() {
+            paths.push(source.clone());
+        } else {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} not found.", source.display()),
+            ));
+        }
+    }
+
+    let date = format_date(timestamp).replace('-', "");
+    let items = collapse(paths)
+        .into_iter()
+        .enumerate()
+        .map(|(i, item)| {
+            let name = template
+                .replace("{project}", project)
+                .replace("{date}", &date)
+                .replace("{name}", &sanitize_string(source_name(&item)))
+                .replace("{index}", &format!("{:03}", i + 1));
+            IngestItem { item, name }
+        })
+        .collect();
+    Ok(items)
+}
+
+/// An ingested file, with the checksum of the copy.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, PartialEq)]
+pub struct IngestRecord {
+    pub source: PathBuf,
+    /// The file name in the ingest dir.
+    pub path: String,
+    pub size: u64,
+    pub sha1: String,
+    pub user: String,
+    pub ingested_at: u64,
+}
+
+/// All files ingested into a dated dir, written next to them. Later ingests the same day are appended.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, Default, PartialEq)]
+pub struct IngestManifest {
+    pub files: Vec<IngestRecord>,
+}
+
+impl IngestManifest {
+    pub fn load(dir: &Path) -> Result<IngestManifest, io::Error> {
+        let path = dir.join(INGEST_MANIFEST_FILE_NAME);
+        if !path.exists() {
+            return Ok(IngestManifest::default());
+        }
+        let file = fs::File::open(&path)?;
+        serde_yaml::from_reader(file).map_err(|e| {
+            error!("Failed to read {}: {}", path.display(), e);
+            io::Error::new(io::ErrorKind::Other, e.to_string())
+        })
+    }
+
+    /// Returns the files that are missing or don't match their checksum.
+    pub fn verify(&self, dir: &Path) -> Vec<String> {
+        self.files
+            .iter()
+            .filter(|f| sha1_file(&dir.join(&f.path)).map_or(true, |s| s != f.sha1))
+            .map(|f| f.path.clone())
+            .collect()
+    }
+}
+
+pub(crate) fn sha1_file(path: &Path) -> Result<String, io::Error> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha1::new();
+    let mut buffer = vec![0; COPY_BUFFER_SIZE];
+    loop {
+        let read = file.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read]);
+    }
+    Ok(hex(&hasher.finalize()))
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Copies the file, hashing it on the way, see `copy_with_progress`. Refuses to overwrite existing files.
+pub(crate) fn copy_with_checksum(
+    source: &Path,
+    target: &Path,
+    progress: &mut dyn FnMut(u64, u64) -> bool,
+) -> Result<(u64, String), io::Error> {
+    let mut hasher = Sha1::new();
+    let size = copy_inspecting(source, target, progress, &mut |chunk| hasher.update(chunk))?;
+    Ok((size, hex(&hasher.finalize())))
+}
+
+/// Copies the items into the dir under their new names, verifies the copies and adds them to the
+/// manifest there. Files copied before an error are kept in the manifest. Meant to run as a job.
+pub fn ingest(items: &[IngestItem], dir: &Path, job: &JobHandle) -> Result<String, String> {
+    let targets: Vec<(PathBuf, String)> = items.iter().flat_map(|i| i.targets()).collect();
+    info!("Ingesting {} files into {}", targets.len(), dir.display());
+    RealFs.create_dir_all(dir).map_err(|e| e.to_string())?;
+
+    let mut records = Vec::new();
+    let mut result = Ok(());
+    for (i, (source, name)) in targets.iter().enumerate() {
+        if job.is_cancelled() {
+            result = Err(String::from("Cancelled."));
+            break;
+        }
+        match copy_with_checksum(source, &dir.join(name), &mut |_done, _size| {
+            !job.is_cancelled()
+        }) {
+            Ok((size, sha1)) => records.push(IngestRecord {
+                source: source.clone(),
+                path: name.clone(),
+                size,
+                sha1,
+                user: current_user(),
+                ingested_at: now_timestamp(),
+            }),
+            Err(e) => {
+                error!("Failed to ingest {}: {}", source.display(), e);
+                result = Err(format!("Failed to ingest {}: {}", source.display(), e));
+                break;
+            }
+        }
+        job.set_progress((i + 1) as f32 / targets.len() as f32);
+    }
+
+    if !records.is_empty() {
+        let mut manifest = IngestManifest::load(dir).map_err(|e| e.to_string())?;
+        manifest.files.extend(records.iter().cloned());
+        write_yaml(&dir.join(INGEST_MANIFEST_FILE_NAME), &manifest).map_err(|e| e.to_string())?;
+    }
+    result?;
+
+    let ingested = IngestManifest { files: records };
+    let corrupt = ingested.verify(dir);
+    if !corrupt.is_empty() {
+        return Err(format!(
+            "{} files don't match their checksum: {}",
+            corrupt.len(),
+            corrupt.join(", ")
+        ));
+    }
+    Ok(format!(
+        "Ingested {} files into {}",
+        ingested.files.len(),
+        dir.display()
+    ))
+}
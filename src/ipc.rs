@@ -0,0 +1,435 @@
+#[cfg(not(unix))]
+use crate::atomic::write_bytes_atomic;
+use crate::commands::{handle, Command, CommandContext, Event};
+use crate::helpers::current_user;
+#[cfg(not(unix))]
+use crate::helpers::{random_hex, CONFIG_DIR_NAME};
+use crate::links::OpenTarget;
+use crate::metadata::{MetadataStore, APP_VERSION_KEY};
+use crate::naming::FilenameTemplate;
+use crate::pathmap::to_local;
+use crate::publishes::Publish;
+use crate::roles::check_write_access;
+use crate::File;
+use crate::Project;
+use crate::TaskTreeNode;
+use log::{error, info};
+use serde_json::{json, Value};
+#[cfg(not(unix))]
+use sha1::{Digest, Sha1};
+use std::io::{self, BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+#[cfg(unix)]
+use std::os::unix::net::{UnixListener, UnixStream};
+
+/// Name of the socket in `$XDG_RUNTIME_DIR`, DCC plugins connect to it to talk to the running instance.
+/// Without a runtime dir the socket is `rclamp-<user>.sock` in the temp dir, which may be shared by all users.
+#[cfg(unix)]
+pub const IPC_SOCKET_NAME: &str = "rclamp.sock";
+/// Windows has no unix sockets, and named pipes aren't in std, so plugins connect to a localhost port instead.
+/// Any local user can connect to it, so requests must carry the token from the `IpcSession` file.
+#[cfg(not(unix))]
+pub const IPC_BASE_PORT: u16 = 47613;
+#[cfg(not(unix))]
+const IPC_PORT_RANGE: u16 = 1000;
+/// Ports tried after the first one is taken, e.g. by the instance of another user with the same hash.
+#[cfg(not(unix))]
+const IPC_PORT_ATTEMPTS: u16 = 20;
+/// Name of the file in `%LOCALAPPDATA%\rclamp` with the port and token of the running instance.
+#[cfg(not(unix))]
+pub const IPC_SESSION_FILE_NAME: &str = "ipc_session.json";
+
+const PARSE_ERROR: i64 = -32700;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+const SERVER_ERROR: i64 = -32000;
+const UNAUTHORIZED: i64 = -32001;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// The selection in the app, returned to plugins asking for the current context.
+#[derive(Clone, Debug, Default)]
+pub struct IpcContext {
+    pub project: Option<Project>,
+    pub projects_dir: Option<PathBuf>,
+    pub task: Option<TaskTreeNode>,
+    pub template: FilenameTemplate,
+}
+
+/// Where plugins find the running instance on Windows. The file is in the user's local app data,
+/// which other users can't read, so knowing the token proves a request comes from the same user.
+#[cfg(not(unix))]
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+pub struct IpcSession {
+    pub port: u16,
+    pub token: String,
+}
+
+#[cfg(not(unix))]
+impl IpcSession {
+    pub fn path() -> Result<PathBuf, io::Error> {
+        match std::env::var_os("LOCALAPPDATA") {
+            Some(d) if !d.is_empty() => Ok(PathBuf::from(d)
+                .join(CONFIG_DIR_NAME)
+                .join(IPC_SESSION_FILE_NAME)),
+            _ => Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "LOCALAPPDATA isn't set.",
+            )),
+        }
+    }
+
+    pub fn read() -> Result<Self, io::Error> {
+        let contents = std::fs::read(Self::path()?)?;
+        Ok(serde_json::from_slice(&contents)?)
+    }
+
+    fn write(&self) -> Result<(), io::Error> {
+        let path = Self::path()?;
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        write_bytes_atomic(&path, &serde_json::to_vec(self)?)
+    }
+}
+
+/// Things that happened through IPC that the app should react to, e.g. by refreshing the file list.
+#[derive(Clone, Debug, PartialEq)]
+pub enum IpcEvent {
+    VersionUp(PathBuf),
+    Published(PathBuf),
+    Open(OpenTarget),
+}
+
+/// Serves newline delimited JSON-RPC 2.0 requests from DCC plugins. Methods:
+/// `get_context`, `version_up` with `{"path": ...}`, `register_publish` with
+/// `{"path": ..., "comment": ..., "app_version": ...}` and `open` with `{"project": ..., "task": ...}`.
+/// On Windows each request also needs a `"token"` member with the token from the `IpcSession` file.
+#[derive(Debug)]
+pub struct IpcServer {
+    context: Arc<Mutex<IpcContext>>,
+    events: Receiver<IpcEvent>,
+}
+
+impl IpcServer {
+    /// Starts listening in a background thread. `notify` is called after each event, e.g. to repaint the ui.
+    pub fn start<F>(notify: F) -> Result<Self, io::Error>
+    where
+        F: Fn() + Send + Sync + 'static,
+    {
+        let context = Arc::new(Mutex::new(IpcContext::default()));
+        let (sender, events) = channel();
+        let handler = Handler {
+            context: context.clone(),
+            sender,
+            notify: Arc::new(notify),
+            token: None,
+        };
+
+        Self::listen(handler)?;
+        Ok(Self { context, events })
+    }
+
+    #[cfg(unix)]
+    pub fn socket_path() -> PathBuf {
+        match std::env::var_os("XDG_RUNTIME_DIR") {
+            Some(d) if !d.is_empty() => PathBuf::from(d).join(IPC_SOCKET_NAME),
+            _ => std::env::temp_dir().join(format!("rclamp-{}.sock", current_user())),
+        }
+    }
+
+    /// The first port tried: `IPC_BASE_PORT` plus the first two bytes of the SHA-1 of the user name,
+    /// big endian, modulo 1000. Plugins should read the port actually used from the `IpcSession` file.
+    #[cfg(not(unix))]
+    pub fn port() -> u16 {
+        let hash = Sha1::digest(current_user().as_bytes());
+        IPC_BASE_PORT + u16::from_be_bytes([hash[0], hash[1]]) % IPC_PORT_RANGE
+    }
+
+    #[cfg(unix)]
+    fn listen(handler: Handler) -> Result<(), io::Error> {
+        let path = Self::socket_path();
+        if path.exists() {
+            if UnixStream::connect(&path).is_ok() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AddrInUse,
+                    format!("{} is used by another instance.", path.display()),
+                ));
+            }
+            std::fs::remove_file(&path)?;
+        }
+
+        let listener = UnixListener::bind(&path)?;
+        info!("Listening for IPC on {}", path.display());
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(s) => {
+                        let handler = handler.clone();
+                        thread::spawn(move || handler.serve(&s, &s));
+                    }
+                    Err(e) => error!("IPC connection failed: {}", e),
+                }
+            }
+        });
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    fn listen(mut handler: Handler) -> Result<(), io::Error> {
+        if request("get_context", Value::Null).is_ok() {
+            return Err(io::Error::new(
+                io::ErrorKind::AddrInUse,
+                "IPC is used by another instance.",
+            ));
+        }
+
+        let first = Self::port() - IPC_BASE_PORT;
+        let mut bound = None;
+        for attempt in 0..IPC_PORT_ATTEMPTS {
+            let port = IPC_BASE_PORT + (first + attempt) % IPC_PORT_RANGE;
+            match std::net::TcpListener::bind(("127.0.0.1", port)) {
+                Ok(l) => {
+                    bound = Some((l, port));
+                    break;
+                }
+                Err(e) if e.kind() == io::ErrorKind::AddrInUse => {
+                    info!("IPC port {} is taken, trying the next one.", port);
+                }
+                Err(e) => return Err(e),
+            }
+        }
+        let (listener, port) = bound.ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::AddrInUse,
+                format!("No free IPC port after {} attempts.", IPC_PORT_ATTEMPTS),
+            )
+        })?;
+
+        let session = IpcSession {
+            port,
+            token: random_hex(),
+        };
+        session.write()?;
+        handler.token = Some(session.token);
+        info!("Listening for IPC on port {}", port);
+        thread::spawn(move || {
+            for stream in listener.incoming() {
+                match stream {
+                    Ok(s) => {
+                        let handler = handler.clone();
+                        thread::spawn(move || handler.serve(&s, &s));
+                    }
+                    Err(e) => error!("IPC connection failed: {}", e),
+                }
+            }
+        });
+        Ok(())
+    }
+
+    /// Replaces the context returned by `get_context`.
+    pub fn set_context(&self, context: IpcContext) {
+        match self.context.lock() {
+            Ok(mut c) => *c = context,
+            Err(e) => error!("Failed to update IPC context: {}", e),
+        }
+    }
+
+    /// Returns the events received since the last call.
+    pub fn events(&self) -> Vec<IpcEvent> {
+        self.events.try_iter().collect()
+    }
+}
+
+#[derive(Clone)]
+struct Handler {
+    context: Arc<Mutex<IpcContext>>,
+    sender: Sender<IpcEvent>,
+    notify: Arc<dyn Fn() + Send + Sync>,
+    /// Required in every request when set, see `IpcSession`.
+    token: Option<String>,
+}
+
+impl Handler {
+    fn serve<R: io::Read, W: io::Write>(&self, reader: R, mut writer: W) {
+        for line in BufReader::new(reader).lines() {
+            let line = match line {
+                Ok(l) => l,
+                Err(_e) => break,
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let response = self.handle(&line);
+            let written = serde_json::to_writer(&mut writer, &response)
+                .map_err(io::Error::from)
+                .and_then(|()| writer.write_all(b"\n"));
+            if let Err(e) = written {
+                error!("Failed to write IPC response: {}", e);
+                break;
+            }
+        }
+    }
+
+    fn handle(&self, line: &str) -> Value {
+        let request: Value = match serde_json::from_str(line) {
+            Ok(r) => r,
+            Err(e) => return error_response(Value::Null, PARSE_ERROR, &e.to_string()),
+        };
+        let id = request.get("id").cloned().unwrap_or(Value::Null);
+        if let Some(token) = &self.token {
+            if request.get("token").and_then(|t| t.as_str()) != Some(token.as_str()) {
+                return error_response(id, UNAUTHORIZED, "Missing or wrong token.");
+            }
+        }
+        let method = request.get("method").and_then(|m| m.as_str()).unwrap_or("");
+        let params = request.get("params").cloned().unwrap_or(Value::Null);
+        info!("IPC request: {}", method);
+
+        let result = match method {
+            "get_context" => self.get_context(),
+            "version_up" => self.version_up(&params),
+            "register_publish" => self.register_publish(&params),
+            "open" => self.open(&params),
+            _ => Err((METHOD_NOT_FOUND, format!("Unknown method: {}", method))),
+        };
+
+        match result {
+            Ok(r) => json!({"jsonrpc": "2.0", "id": id, "result": r}),
+            Err((code, message)) => error_response(id, code, &message),
+        }
+    }
+
+    fn send(&self, event: IpcEvent) {
+        if self.sender.send(event).is_ok() {
+            (self.notify)();
+        }
+    }
+
+    fn get_context(&self) -> Result<Value, (i64, String)> {
+        let context = match self.context.lock() {
+            Ok(c) => c.clone(),
+            Err(e) => return Err((SERVER_ERROR, e.to_string())),
+        };
+
+        let project_path = match (&context.project, &context.projects_dir) {
+            (Some(p), Some(d)) => Some(p.get_path(d)),
+            _ => None,
+        };
+        Ok(json!({
+            "project": context.project.as_ref().map(|p| &p.name),
+            "project_path": project_path,
+            "task": context.task.as_ref().map(|t| &t.name),
+            "task_path": context.task.as_ref().map(|t| &t.path),
+            "work_path": context.task.as_ref().map(|t| t.get_work_path()),
+        }))
+    }
+
+    fn path_param(params: &Value) -> Result<PathBuf, (i64, String)> {
+        match params.get("path").and_then(|p| p.as_str()) {
+            // DCC:s may have the file open by its UNC path or another drive than the projects dir.
+            Some(p) => Ok(to_local(Path::new(p))),
+            None => Err((INVALID_PARAMS, String::from("Missing path."))),
+        }
+    }
+
+    fn version_up(&self, params: &Value) -> Result<Value, (i64, String)> {
+        let path = Self::path_param(params)?;
+        check_write_access(&path).map_err(|e| (SERVER_ERROR, e))?;
+        let template = match self.context.lock() {
+            Ok(c) => c.template.clone(),
+            Err(e) => return Err((SERVER_ERROR, e.to_string())),
+        };
+
+        let file = match File::from_path(path.clone(), &template) {
+            Ok(f) => f,
+            Err(e) => return Err((INVALID_PARAMS, e.to_string())),
+        };
+        let version = match handle(&Command::VersionUp(file), &CommandContext::default()).pop() {
+            Some(Event::VersionedUp(v, _p)) => v,
+            Some(Event::Failed(e)) => return Err((SERVER_ERROR, e)),
+            e => return Err((SERVER_ERROR, format!("Unexpected result: {:?}", e))),
+        };
+
+        self.send(IpcEvent::VersionUp(path));
+        Ok(json!({"version": version}))
+    }
+
+    fn register_publish(&self, params: &Value) -> Result<Value, (i64, String)> {
+        let path = Self::path_param(params)?;
+        check_write_access(&path).map_err(|e| (SERVER_ERROR, e))?;
+        let comment = params.get("comment").and_then(|c| c.as_str()).unwrap_or("");
+
+        let publish = match Publish::register(&path, comment) {
+            Ok(p) => p,
+            Err(e) => return Err((SERVER_ERROR, e.to_string())),
+        };
+        let app_version = params.get("app_version").and_then(|v| v.as_str());
+        if let (Some(version), Some(task_path)) = (app_version, Publish::find_task_dir(&path)) {
+            if let Err(e) = MetadataStore::update(&task_path, &path, APP_VERSION_KEY, version) {
+                error!(
+                    "Failed to record the app version of {}: {}",
+                    path.display(),
+                    e
+                );
+            }
+        }
+
+        self.send(IpcEvent::Published(path));
+        match serde_json::to_value(publish) {
+            Ok(v) => Ok(v),
+            Err(e) => Err((SERVER_ERROR, e.to_string())),
+        }
+    }
+
+    fn open(&self, params: &Value) -> Result<Value, (i64, String)> {
+        let project = match params.get("project").and_then(|p| p.as_str()) {
+            Some(p) => String::from(p),
+            None => return Err((INVALID_PARAMS, String::from("Missing project."))),
+        };
+        let task = params
+            .get("task")
+            .and_then(|t| t.as_str())
+            .map(String::from);
+        self.send(IpcEvent::Open(OpenTarget { project, task }));
+        Ok(json!({}))
+    }
+}
+
+/// Sends a request to the running instance and returns the result.
+pub fn request(method: &str, params: Value) -> Result<Value, io::Error> {
+    #[cfg(unix)]
+    let stream = UnixStream::connect(IpcServer::socket_path())?;
+    #[cfg(not(unix))]
+    let session = IpcSession::read()?;
+    #[cfg(not(unix))]
+    let stream = std::net::TcpStream::connect(("127.0.0.1", session.port))?;
+    stream.set_read_timeout(Some(REQUEST_TIMEOUT))?;
+
+    #[allow(unused_mut)]
+    let mut request = json!({"jsonrpc": "2.0", "id": 1, "method": method, "params": params});
+    #[cfg(not(unix))]
+    {
+        request["token"] = json!(session.token);
+    }
+    let mut writer = &stream;
+    serde_json::to_writer(&mut writer, &request)?;
+    writer.write_all(b"\n")?;
+
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line)?;
+    let response: Value = serde_json::from_str(&line)?;
+    match response.get("error") {
+        Some(e) => Err(io::Error::new(io::ErrorKind::Other, e.to_string())),
+        None => Ok(response.get("result").cloned().unwrap_or(Value::Null)),
+    }
+}
+
+fn error_response(id: Value, code: i64, message: &str) -> Value {
+    json!({"jsonrpc": "2.0", "id": id, "error": {"code": code, "message": message}})
+}
@@ -0,0 +1,289 @@
+use log::{error, info};
+use regex::Regex;
+use std::io::Read;
+use std::process::Child;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+pub const DEFAULT_WORKERS: usize = 2;
+/// How often a waiting job checks if its process exited or it was cancelled.
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum JobState {
+    Queued,
+    Running,
+    Done(String),
+    Failed(String),
+    Cancelled,
+}
+
+impl JobState {
+    pub fn is_active(&self) -> bool {
+        matches!(self, JobState::Queued | JobState::Running)
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct JobStatus {
+    pub id: u64,
+    pub name: String,
+    /// Progress from 0 to 1, if the job reports it.
+    pub progress: Option<f32>,
+    pub state: JobState,
+}
+
+/// Passed to a running job, to report progress and check for cancellation.
+pub struct JobHandle {
+    id: u64,
+    statuses: Arc<Mutex<Vec<JobStatus>>>,
+    cancelled: Arc<AtomicBool>,
+}
+
+impl JobHandle {
+    pub fn set_progress(&self, progress: f32) {
+        if let Ok(mut s) = self.statuses.lock() {
+            if let Some(status) = s.iter_mut().find(|s| s.id == self.id) {
+                status.progress = Some(progress.clamp(0., 1.));
+            }
+        }
+    }
+
+    /// Long running jobs should check this regularly and stop early when set.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    /// Waits for the process, reporting the percentages it prints as progress, like ` 42% 3 + a.mov`
+    /// from 7z or `42% 10.5MB 9.2Mb/s` from ascp. Cancelling kills it.
+    pub fn wait_reading_percent(&self, mut child: Child, program: &str) -> Result<(), String> {
+        // Both pipes are read on their own threads, a process blocked on a full pipe would never exit.
+        let errors = child.stderr.take().map(|mut stderr| {
+            thread::spawn(move || {
+                let mut errors = String::new();
+                let _ = stderr.read_to_string(&mut errors);
+                errors
+            })
+        });
+        let (progress_sender, progress) = channel();
+        let output = child.stdout.take().map(|mut stdout| {
+            thread::spawn(move || {
+                // The progress is often rewritten in place with backspaces or carriage returns, not lines.
+                let percent = Regex::new(r"(\d+)%").unwrap();
+                let mut buffer = [0; 256];
+                while let Ok(read) = stdout.read(&mut buffer) {
+                    if read == 0 {
+                        break;
+                    }
+                    let text = String::from_utf8_lossy(&buffer[..read]);
+                    if let Some(p) = percent
+                        .captures_iter(&text)
+                        .last()
+                        .and_then(|c| c[1].parse::<f32>().ok())
+                    {
+                        if progress_sender.send(p).is_err() {
+                            break;
+                        }
+                    }
+                }
+            })
+        });
+
+        let status = loop {
+            if self.is_cancelled() {
+                let _ = child.kill();
+                let _ = child.wait();
+                return Err(String::from("Cancelled."));
+            }
+            if let Some(p) = progress.try_iter().last() {
+                self.set_progress(p / 100.);
+            }
+            match child.try_wait() {
+                Ok(Some(s)) => break s,
+                Ok(None) => thread::sleep(POLL_INTERVAL),
+                Err(e) => return Err(e.to_string()),
+            }
+        };
+
+        // The last progress may still be in the pipe when the process exits.
+        if let Some(o) = output {
+            let _ = o.join();
+        }
+        if let Some(p) = progress.try_iter().last() {
+            self.set_progress(p / 100.);
+        }
+        if status.success() {
+            return Ok(());
+        }
+        let errors = errors.and_then(|e| e.join().ok()).unwrap_or_default();
+        Err(format!("{} {}: {}", program, status, errors.trim()))
+    }
+}
+
+/// The work of a job, returning a message on success.
+type JobFn = Box<dyn FnOnce(&JobHandle) -> Result<String, String> + Send>;
+
+struct Job {
+    id: u64,
+    run: JobFn,
+    cancelled: Arc<AtomicBool>,
+}
+
+/// Runs long operations like copies and transcodes on a small pool of worker threads.
+pub struct JobQueue {
+    sender: Sender<Job>,
+    statuses: Arc<Mutex<Vec<JobStatus>>>,
+    cancel_flags: Vec<(u64, Arc<AtomicBool>)>,
+    finished: Receiver<JobStatus>,
+    next_id: u64,
+}
+
+impl std::fmt::Debug for JobQueue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("JobQueue")
+            .field("statuses", &self.statuses)
+            .finish()
+    }
+}
+
+impl JobQueue {
+    pub fn new(workers: usize) -> Self {
+        let (sender, receiver) = channel::<Job>();
+        let (finished_sender, finished) = channel();
+        let receiver = Arc::new(Mutex::new(receiver));
+        let statuses = Arc::new(Mutex::new(Vec::new()));
+
+        for _ in 0..workers.max(1) {
+            let receiver = receiver.clone();
+            let statuses = statuses.clone();
+            let finished_sender = finished_sender.clone();
+            thread::spawn(move || Self::work(&receiver, &statuses, &finished_sender));
+        }
+
+        Self {
+            sender,
+            statuses,
+            cancel_flags: Vec::new(),
+            finished,
+            next_id: 0,
+        }
+    }
+
+    /// Queues a job and returns its id.
+    pub fn push<F>(&mut self, name: &str, run: F) -> u64
+    where
+        F: FnOnce(&JobHandle) -> Result<String, String> + Send + 'static,
+    {
+        self.next_id += 1;
+        let id = self.next_id;
+        let cancelled = Arc::new(AtomicBool::new(false));
+
+        if let Ok(mut s) = self.statuses.lock() {
+            s.push(JobStatus {
+                id,
+                name: String::from(name),
+                progress: None,
+                state: JobState::Queued,
+            });
+        }
+        self.cancel_flags.push((id, cancelled.clone()));
+
+        info!("Queued job {}: {}", id, name);
+        let job = Job {
+            id,
+            run: Box::new(run),
+            cancelled,
+        };
+        if let Err(e) = self.sender.send(job) {
+            error!("Failed to queue job: {}", e);
+        }
+        id
+    }
+
+    pub fn cancel(&self, id: u64) {
+        if let Some((_, flag)) = self.cancel_flags.iter().find(|(i, _)| *i == id) {
+            info!("Cancelling job {}", id);
+            flag.store(true, Ordering::Relaxed);
+        }
+    }
+
+    pub fn statuses(&self) -> Vec<JobStatus> {
+        match self.statuses.lock() {
+            Ok(s) => s.clone(),
+            Err(_e) => Vec::new(),
+        }
+    }
+
+    pub fn is_busy(&self) -> bool {
+        self.statuses().iter().any(|s| s.state.is_active())
+    }
+
+    /// Returns the jobs that finished since the last call.
+    pub fn finished(&self) -> Vec<JobStatus> {
+        self.finished.try_iter().collect()
+    }
+
+    /// Removes finished, failed and cancelled jobs from the list.
+    pub fn clear_finished(&mut self) {
+        if let Ok(mut s) = self.statuses.lock() {
+            s.retain(|s| s.state.is_active());
+            self.cancel_flags
+                .retain(|(id, _)| s.iter().any(|s| s.id == *id));
+        }
+    }
+
+    fn set_state(statuses: &Mutex<Vec<JobStatus>>, id: u64, state: JobState) -> Option<JobStatus> {
+        let mut s = statuses.lock().ok()?;
+        let status = s.iter_mut().find(|s| s.id == id)?;
+        status.state = state;
+        Some(status.clone())
+    }
+
+    fn work(
+        receiver: &Mutex<Receiver<Job>>,
+        statuses: &Arc<Mutex<Vec<JobStatus>>>,
+        finished: &Sender<JobStatus>,
+    ) {
+        loop {
+            let job = match receiver.lock() {
+                Ok(r) => match r.recv() {
+                    Ok(j) => j,
+                    Err(_e) => return,
+                },
+                Err(_e) => return,
+            };
+
+            let state = if job.cancelled.load(Ordering::Relaxed) {
+                JobState::Cancelled
+            } else {
+                Self::set_state(statuses, job.id, JobState::Running);
+                let handle = JobHandle {
+                    id: job.id,
+                    statuses: statuses.clone(),
+                    cancelled: job.cancelled.clone(),
+                };
+                match (job.run)(&handle) {
+                    _ if handle.is_cancelled() => JobState::Cancelled,
+                    Ok(m) => JobState::Done(m),
+                    Err(e) => {
+                        error!("Job {} failed: {}", job.id, e);
+                        JobState::Failed(e)
+                    }
+                }
+            };
+
+            if let Some(status) = Self::set_state(statuses, job.id, state) {
+                let _ = finished.send(status);
+            }
+        }
+    }
+}
+
+impl Default for JobQueue {
+    fn default() -> Self {
+        Self::new(DEFAULT_WORKERS)
+    }
+}
@@ -1,13 +1,76 @@
 #![warn(clippy::all, rust_2018_idioms)]
 
 mod app;
+mod archive;
+mod atomic;
+mod autosave;
+mod batch;
+mod cache;
 mod clients;
+mod commands;
+mod compare;
+mod context;
+mod crash;
+#[cfg(feature = "deliveries")]
+mod deliveries;
+#[cfg(feature = "deliveries")]
+mod delivery_notes;
+mod detect;
+mod fuzzy;
+mod health;
 mod helpers;
+mod hooks;
+mod i18n;
+mod ignore;
+mod importer;
+mod ingest;
+mod ipc;
+mod jobs;
+mod links;
+mod lint;
+mod logging;
+mod metadata;
+mod mirror;
+mod naming;
+mod notes;
+mod notifications;
+mod offline;
+mod pathmap;
+mod permissions;
+/// Projects, tasks and workfiles without the UI, for other tools that use rclamp as a library.
+pub mod pipeline;
+mod player;
+mod plugins;
+mod preferences;
+mod presence;
 mod projects;
+mod publishes;
+mod report;
+mod retry;
+mod roles;
+mod schedule;
+#[cfg(feature = "python")]
+mod scripting;
+mod sequences;
+mod structure;
+mod sync;
 mod tasks;
+mod templates;
+mod theme;
+mod transcode;
+mod trash;
+#[cfg(feature = "deliveries")]
+mod uploads;
+mod validation;
+mod vfs;
+mod watch;
+mod webhooks;
 mod workfiles;
 pub use app::Rclamp;
 pub use clients::Client;
+pub use crash::install_panic_hook;
+pub use links::{OpenTarget, USAGE};
+pub use logging::init_logging;
 pub use projects::Project;
 pub use tasks::TaskTreeNode;
 pub use workfiles::File;
@@ -15,7 +78,117 @@ pub use workfiles::File;
 #[cfg(test)]
 mod tests {
 
-    use crate::helpers::sanitize_string;
+    use crate::archive::{
+        archive_files, create_split_zip, create_zip, remove_archive, volume_path, ArchiveSettings,
+    };
+    use crate::atomic::{create_yaml, write_atomic, write_yaml, FileLock};
+    use crate::autosave::{expand_dir, find_autosaves};
+    use crate::batch::version_up_task;
+    use crate::cache::ScanCache;
+    use crate::clients::client_projects;
+    use crate::commands::{handle, Command, CommandContext, Event};
+    use crate::compare::{diff_lines, is_text_workfile, DiffLine, FileStats};
+    use crate::context::LaunchContext;
+    use crate::crash::{list_reports, report_text, take_pending_report, write_report};
+    use crate::detect::{executable_for, expand_pattern, natural_cmp, wildcard_match};
+    use crate::fuzzy::{fuzzy_filter, fuzzy_score};
+    use crate::health::{check_project, HealthIssue};
+    use crate::helpers::{date_from_days, days_from_date, read_config_file, sanitize_string};
+    use crate::hooks::HookEvent;
+    use crate::i18n::{set_language, tr, trf};
+    use crate::ignore::IgnorePatterns;
+    use crate::importer::ImportRow;
+    use crate::ingest::{ingest, plan_ingest, IngestManifest};
+    use crate::jobs::{JobQueue, JobState};
+    use crate::links::{find_task, task_key, OpenTarget};
+    use crate::lint::{lint_tasks, rename_issues, NamingProblem};
+    use crate::logging::{
+        clear_lines, format_lines, push_line, recent_lines, LogLine, LOG_CAPACITY,
+    };
+    use crate::metadata::{MetadataStore, METADATA_FILE_NAME};
+    use crate::mirror::MirrorProfile;
+    use crate::naming::{FilenameTemplate, FilenameTokens};
+    use crate::notes::{FileNote, FileNotes};
+    use crate::offline::{is_reachable, Connectivity, RECONNECT_INTERVAL};
+    use crate::pathmap::{
+        is_unc, map_path, parse_net_use, set_mappings, to_local, with_detected, PathMapping,
+        PathStyle,
+    };
+    use crate::permissions::{set_create_permissions, CreatePermissions, Created};
+    use crate::pipeline::{PipelineContext, ProjectStore};
+    use crate::preferences::{push_recent, FileDoubleClick, UiPrefs, MAX_RECENT_TASKS};
+    use crate::presence::{Session, SESSION_TIMEOUT};
+    use crate::projects::{
+        episode_names, group_projects, ProjectAdoption, ProjectGrouping, ProjectSorting,
+    };
+    use crate::publishes::{Publish, ReviewState};
+    use crate::report::{report_csv, report_rows, write_project_report};
+    use crate::retry::{is_transient, RetryPolicy};
+    use crate::roles::{
+        check_write_access, project_access, Access, ProjectAccess, Role, ROLE_ENV_VAR,
+    };
+    use crate::schedule::{group_by_month, parse_date, project_items, task_items};
+    use crate::sequences::{collapse, OutputItem};
+    use crate::structure::{copy_tree, StructureEntry};
+    use crate::sync::SyncManifest;
+    use crate::tasks::{Milestone, TaskDetails};
+    use crate::templates::{
+        check_setup, fill_executables, import_template, restore_template, scan_templates,
+    };
+    use crate::theme::{parse_color, Theme};
+    use crate::transcode::Transcoder;
+    use crate::trash::{Trash, TrashItem, TrashKind};
+    use crate::validation::{
+        validate_child_names, validate_name, validate_project_name, ValidationError,
+    };
+    use crate::vfs::{copy_with_progress, parallel_map, Fs, MemoryFs, MAX_SCAN_THREADS};
+    use crate::watch::InboxWatcher;
+    use crate::webhooks::{Webhook, WebhookContext, WebhookEvent, WebhookKind};
+    use crate::workfiles::{Dcc, FileGroup, Retained, RetentionPolicy, VersionTransfer};
+    use crate::Client;
+    use crate::File;
+    use crate::Project;
+    use crate::TaskTreeNode;
+    use std::path::{Path, PathBuf};
+
+    /// A path in a temp dir of this test run, `<temp>/rclamp_tests_<pid>/rclamp_test_<name>`, so parallel
+    /// runs don't share files. Anything left at the path by an earlier run is removed.
+    fn test_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("rclamp_tests_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(format!("rclamp_test_{}", name));
+        let _ = std::fs::remove_dir_all(&path);
+        let _ = std::fs::remove_file(&path);
+        path
+    }
+
+    /// A project with the default dir names, and `01_work` and `02_output` in each task.
+    fn test_project(name: &str, projects_dir: &Path) -> Project {
+        Project::new(
+            String::from(name),
+            projects_dir.to_path_buf(),
+            String::from("00_pipeline"),
+            String::from("02_work"),
+            String::from("03_dailies"),
+            String::from("04_deliveries"),
+            Vec::new(),
+            vec![String::from("01_work"), String::from("02_output")],
+        )
+    }
+
+    /// A task in the work dir of the project, created on drive with its work dir.
+    fn test_task(project: &Project, projects_dir: &Path, name: &str) -> TaskTreeNode {
+        let path = project
+            .get_work_path(&projects_dir.to_path_buf())
+            .join(name);
+        std::fs::create_dir_all(path.join("01_work")).unwrap();
+        let mut task = TaskTreeNode::new(String::from(name), path, "01_work", "02_output");
+        task.metadata.is_task = true;
+        task
+    }
+
+    /// Held by tests that set the global create permissions, or copy outputs that would get them.
+    static CREATE_PERMISSIONS_LOCK: std::sync::Mutex<()> = std::sync::Mutex::new(());
 
     #[test]
     fn test_sanitizer() {
@@ -23,5 +196,3385 @@ mod tests {
             sanitize_string(String::from("ABC/?<-ÅÄÖ_xyz_1234-åäö%^<??<>//")),
             String::from("abc_aao_xyz_1234_aao")
         );
+        assert_eq!(
+            sanitize_string(String::from("Café Müller Straße 中")),
+            String::from("cafemullerstrasseu4e2d")
+        );
+    }
+
+    #[test]
+    fn test_structure_parse() {
+        let entries = StructureEntry::parse("ep01/sq010/sh0010/comp\nep01,sq010,sh0020,Comp\n\n");
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].folders, vec!["ep01", "sq010", "sh0010"]);
+        assert_eq!(entries[1].task, "comp");
+        assert_eq!(entries[1].display(), "ep01/sq010/sh0020/comp");
+
+        // A header row pasted from a sheet isn't an entry.
+        let entries = StructureEntry::parse("\nSequence,Shot,Task\nsq010,sh0010,comp\n");
+        assert_eq!(entries.len(), 1);
+        assert_eq!(entries[0].display(), "sq010/sh0010/comp");
+        let entries =
+            StructureEntry::parse("Episode,Sequence,Shot,Task Name\nep01,sq010,sh0010,comp\n");
+        assert_eq!(entries.len(), 1);
+        let entries = StructureEntry::parse("sq010,sh0010,comp\nsequence,shot,task\n");
+        assert_eq!(entries.len(), 2);
+        // Duplicates are only created once.
+        let entries = StructureEntry::parse("sq010/sh0010/comp\nsq010,sh0010,Comp\n");
+        assert_eq!(entries.len(), 1);
+        assert_eq!(
+            StructureEntry::from_line("task").map(|e| e.task),
+            Some(String::from("task"))
+        );
+    }
+
+    #[test]
+    fn test_import_csv() {
+        let rows = ImportRow::parse_csv(
+            "Task,Status\n\
+             sq010/sh0010/comp,In progress\n\
+             \"sq010/sh0020/light\",\"on hold, \"\"client\"\"\"\n\
+             \n\
+             fx,  \n",
+        );
+        assert_eq!(rows.len(), 3);
+        assert_eq!(rows[0].row, 2);
+        assert_eq!(rows[0].entry.display(), "sq010/sh0010/comp");
+        // Statuses are free-form and kept as typed, like the ones set in the ui.
+        assert_eq!(rows[0].status.as_deref(), Some("In progress"));
+        assert_eq!(rows[1].entry.display(), "sq010/sh0020/light");
+        assert_eq!(rows[1].status.as_deref(), Some("on hold, \"client\""));
+        assert_eq!((rows[2].row, rows[2].status.clone()), (5, None));
+
+        // A first row of column names is a header and skipped, the same names later are tasks.
+        let rows = ImportRow::parse_csv("name\ncomp\n");
+        assert_eq!(rows.len(), 1);
+        let rows = ImportRow::parse_csv("Shot Path,Task Status\nsh0010/comp,done\n");
+        assert_eq!(rows.len(), 1);
+        let rows = ImportRow::parse_csv("comp\nname\n");
+        assert_eq!(rows[1].entry.task, "name");
+        // A first row with a task in it isn't a header.
+        let rows = ImportRow::parse_csv("name,done\ncomp\n");
+        assert_eq!(rows.len(), 2);
+    }
+
+    #[test]
+    fn test_filename_template() {
+        let template = FilenameTemplate::default();
+        let mut tokens = FilenameTokens {
+            project: String::from("1234_acme_show"),
+            task: String::from("comp"),
+            name: String::new(),
+            version: 7,
+        };
+        assert_eq!(template.format(&tokens), "1234_acme_show_comp_v007");
+
+        tokens.name = String::from("main");
+        let stem = template.format(&tokens);
+        assert_eq!(stem, "1234_acme_show_comp_main_v007");
+
+        let parsed = template.parse(&stem).unwrap();
+        assert_eq!(parsed.tokens.version, 7);
+        assert_eq!(parsed.name, "1234_acme_show_comp_main");
+
+        // Known project and task names are matched whole, separators included.
+        let parsed = template.parse_in("1234_acme_show", "comp", &stem).unwrap();
+        assert_eq!(parsed.tokens, tokens);
+        // The second time the pattern is cached, also in clones.
+        let parsed = template
+            .clone()
+            .parse_in("1234_acme_show", "comp", &stem)
+            .unwrap();
+        assert_eq!(parsed.tokens, tokens);
+        let parsed = template
+            .parse_in("1234_acme_show", "comp_fix", "1234_acme_show_comp_fix_v002")
+            .unwrap();
+        assert_eq!(
+            (parsed.tokens.task.as_str(), parsed.tokens.name.as_str()),
+            ("comp_fix", "")
+        );
+        let parsed = template.parse_in("5678_other", "comp", &stem).unwrap();
+        assert_eq!(parsed.tokens.version, 7);
+        assert_ne!(parsed.tokens.project, "5678_other");
+        assert!(template.parse("comp_main").is_err());
+
+        let parsed = template.parse("asset_v12").unwrap();
+        assert_eq!((parsed.name.as_str(), parsed.tokens.version), ("asset", 12));
+
+        let parsed = template.parse("1234_acme_show_comp_v1000_wip").unwrap();
+        assert_eq!(parsed.tokens.version, 1000);
+        assert_eq!(parsed.suffix, "_wip");
+    }
+
+    #[test]
+    fn test_version_sorting() {
+        let template = FilenameTemplate::default();
+        let mut files: Vec<File> = [
+            "show_comp_v1000.nk",
+            "show_comp_v999.nk",
+            "show_comp_v002.nk",
+        ]
+        .iter()
+        .map(|p| File::from_path(PathBuf::from(p), &template).unwrap())
+        .collect();
+        files.sort();
+
+        let versions: Vec<String> = files.iter().map(|f| f.fmt_version()).collect();
+        assert_eq!(versions, vec!["v002", "v999", "v1000"]);
+    }
+
+    #[test]
+    fn test_name_validation() {
+        assert_eq!(validate_name("comp"), Ok(()));
+        assert_eq!(validate_name(""), Err(ValidationError::Empty));
+        assert_eq!(
+            validate_name("com1"),
+            Err(ValidationError::Reserved(String::from("com1")))
+        );
+        assert!(validate_name(&"a".repeat(100)).is_err());
+        assert!(validate_project_name("1234_show", &[String::from("1234_Show")]).is_err());
+
+        let dir = test_path("name_validation");
+        std::fs::create_dir_all(dir.join("comp")).unwrap();
+        let names: Vec<String> = ["comp", "grade", "con", "grade"]
+            .iter()
+            .map(|n| String::from(*n))
+            .collect();
+        assert_eq!(
+            validate_child_names(&names, &dir),
+            vec![
+                Err(ValidationError::AlreadyExists(String::from("comp"))),
+                Ok(()),
+                Err(ValidationError::Reserved(String::from("con"))),
+                Err(ValidationError::AlreadyExists(String::from("grade"))),
+            ]
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_project_create_rollback() {
+        let projects_dir = test_path("rollback");
+        std::fs::create_dir_all(&projects_dir).unwrap();
+
+        let project = Project::new(
+            String::from("show"),
+            projects_dir.clone(),
+            String::from("00_pipeline"),
+            String::from("02_work"),
+            String::from("03_dailies"),
+            String::from("04_deliveries"),
+            vec![String::from("missing/nested")],
+            vec![String::from("01_work"), String::from("02_output")],
+        );
+
+        assert!(project
+            .create(projects_dir.clone(), &projects_dir.join("templates"))
+            .is_err());
+        assert!(!project.get_path(&projects_dir).exists());
+        std::fs::remove_dir_all(&projects_dir).unwrap();
+    }
+
+    #[test]
+    fn test_publish_register() {
+        let task_dir = test_path("publish");
+        std::fs::create_dir_all(task_dir.join("01_work")).unwrap();
+        std::fs::write(task_dir.join("task.yaml"), "name: comp\n").unwrap();
+
+        let file = task_dir.join("01_work").join("show_comp_v001.nk");
+        assert!(Publish::register(&file, "").is_err());
+
+        std::fs::write(&file, "").unwrap();
+        Publish::register(&file, "first").unwrap();
+        Publish::register(&file, "second").unwrap();
+
+        let publishes = Publish::list(&task_dir).unwrap();
+        assert_eq!(publishes.len(), 2);
+        assert_eq!(publishes[1].comment, "second");
+        assert_eq!(publishes[0].path, file);
+        assert_eq!(publishes[0].state(), ReviewState::Pending);
+
+        let reviewed = publishes[0]
+            .set_review(&task_dir, ReviewState::Rejected, "Edges")
+            .unwrap();
+        assert_eq!(reviewed.review.unwrap().comment, "Edges");
+        let publishes = Publish::list(&task_dir).unwrap();
+        assert_eq!(publishes[0].state(), ReviewState::Rejected);
+        assert_eq!(publishes[1].state(), ReviewState::Pending);
+        std::fs::remove_dir_all(&task_dir).unwrap();
+    }
+
+    #[test]
+    fn test_template_token_substitution() {
+        let tokens = vec![
+            (String::from("{TASK}"), String::from("comp")),
+            (String::from("{TASK_PATH}"), String::from("/show/comp")),
+        ];
+        assert_eq!(
+            File::substitute_tokens("Root {\n name {TASK_PATH}/{TASK}\n}", &tokens),
+            "Root {\n name /show/comp/comp\n}"
+        );
+        // Tokens in the values are left as they are.
+        let tokens = vec![
+            (String::from("{TASK}"), String::from("{FILENAME}")),
+            (String::from("{FILENAME}"), String::from("comp_v001.nk")),
+        ];
+        assert_eq!(
+            File::substitute_tokens("{TASK} {FILENAME}", &tokens),
+            "{FILENAME} comp_v001.nk"
+        );
+    }
+
+    #[test]
+    fn test_transcode_sequence_with_gaps() {
+        let dir = PathBuf::from("/output/v003");
+        let paths = [1001, 1002, 1004]
+            .iter()
+            .map(|f| dir.join(format!("shot_v003.{}.exr", f)))
+            .collect();
+        let item = collapse(paths).remove(0);
+        let transcoder = Transcoder {
+            ffmpeg: PathBuf::from("ffmpeg"),
+            framerate: 24,
+            args: Vec::new(),
+        };
+        let mut jobs = JobQueue::new(1);
+        jobs.push("proxy", move |job| {
+            transcoder.transcode(&item, &dir.join("proxy/shot_v003.mp4"), job)
+        });
+        let finished = loop {
+            let finished = jobs.finished();
+            if !finished.is_empty() {
+                break finished;
+            }
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        };
+        assert_eq!(
+            finished[0].state,
+            JobState::Failed(String::from("shot_v003.####.exr is missing frames 1003."))
+        );
+    }
+
+    #[test]
+    fn test_ignore_patterns() {
+        let mut patterns = vec![
+            String::from("*.autosave"),
+            String::from("*_backup_*"),
+            String::from(".*"),
+            String::from("re:^tmp\\d+"),
+        ];
+        patterns.extend(IgnorePatterns::from_extensions(&[String::from("test")]));
+        let ignore = IgnorePatterns::new(&patterns).unwrap();
+
+        assert!(ignore.is_ignored("show_comp_v001.nk.AUTOSAVE"));
+        assert!(ignore.is_ignored("show_comp_backup_v001.nk"));
+        assert!(ignore.is_ignored(".DS_Store"));
+        assert!(ignore.is_ignored("tmp12_comp.nk"));
+        assert!(ignore.is_ignored("show_comp_v001.test"));
+        assert!(!ignore.is_ignored("show_comp_v001.nk"));
+        assert!(IgnorePatterns::new(&[String::from("re:(")]).is_err());
+    }
+
+    #[test]
+    fn test_sequence_collapse() {
+        let dir = PathBuf::from("/output/v003");
+        let mut paths: Vec<PathBuf> = (1001..=1010)
+            .map(|f| dir.join(format!("shot_v003.{}.exr", f)))
+            .collect();
+        paths.retain(|p| !p.ends_with("shot_v003.1005.exr") && !p.ends_with("shot_v003.1006.exr"));
+        paths.push(dir.join("shot_v003.mov"));
+        paths.push(dir.join("shot_v003_thumb.0001.jpg"));
+
+        let items = collapse(paths);
+        assert_eq!(items.len(), 3);
+        match &items[0] {
+            OutputItem::Sequence(s) => {
+                assert_eq!(s.pattern(), "shot_v003.####.exr");
+                assert_eq!(s.frame_range(), "1001-1010");
+                assert_eq!(s.frame_path(1005), dir.join("shot_v003.1005.exr"));
+                assert_eq!(s.missing_frames(), vec![1005, 1006]);
+                assert_eq!(s.missing_ranges(), "1005-1006");
+            }
+            i => panic!("Expected a sequence, got {:?}", i),
+        }
+        assert_eq!(items[1].first_path(), dir.join("shot_v003.mov"));
+        assert_eq!(items[2].first_path(), dir.join("shot_v003_thumb.0001.jpg"));
+        assert!(matches!(items[2], OutputItem::File { .. }));
+    }
+
+    #[test]
+    fn test_job_queue() {
+        let mut jobs = JobQueue::new(1);
+        let (sender, receiver) = std::sync::mpsc::channel::<()>();
+        let first = jobs.push("blocked", move |job| {
+            let _ = receiver.recv();
+            job.set_progress(1.);
+            Ok(String::from("done"))
+        });
+        let second = jobs.push("cancelled", |_job| Ok(String::new()));
+        jobs.cancel(second);
+        sender.send(()).unwrap();
+
+        let mut finished = Vec::new();
+        while finished.len() < 2 {
+            finished.extend(jobs.finished());
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert_eq!(finished[0].id, first);
+        assert_eq!(finished[0].state, JobState::Done(String::from("done")));
+        assert_eq!(finished[0].progress, Some(1.));
+        assert_eq!(finished[1].state, JobState::Cancelled);
+        assert!(!jobs.is_busy());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_wait_reading_percent() {
+        use std::process::{Command, Stdio};
+        let spawn = |script: &str| {
+            Command::new("sh")
+                .args(["-c", script])
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()
+                .map_err(|e| e.to_string())
+        };
+        let wait = |jobs: &JobQueue, count: usize| {
+            let mut finished = Vec::new();
+            while finished.len() < count {
+                finished.extend(jobs.finished());
+                std::thread::sleep(std::time::Duration::from_millis(10));
+            }
+            finished
+        };
+
+        // More stderr than fits in a pipe, before anything on stdout.
+        let mut jobs = JobQueue::new(1);
+        jobs.push("noisy", move |job| {
+            let child = spawn("head -c 200000 /dev/zero | tr '\\0' e >&2; echo 50%; exit 3")?;
+            job.wait_reading_percent(child, "sh")
+                .map(|()| String::new())
+        });
+        let finished = wait(&jobs, 1);
+        assert_eq!(finished[0].progress, Some(0.5));
+        match &finished[0].state {
+            JobState::Failed(e) => assert!(e.starts_with("sh ") && e.ends_with("eee")),
+            s => panic!("Expected a failure, got {:?}", s),
+        }
+
+        // Cancelling a process that prints nothing.
+        let silent = jobs.push("silent", move |job| {
+            let child = spawn("sleep 30")?;
+            job.wait_reading_percent(child, "sleep")
+                .map(|()| String::new())
+        });
+        std::thread::sleep(std::time::Duration::from_millis(100));
+        jobs.cancel(silent);
+        assert_eq!(wait(&jobs, 1)[0].state, JobState::Cancelled);
+    }
+
+    #[test]
+    fn test_path_mapping() {
+        let mappings = vec![PathMapping {
+            windows: String::from("P:\\"),
+            unc: String::from("\\\\server\\projects"),
+            mac: String::from("/Volumes/projects"),
+            linux: String::from("/mnt/projects"),
+        }];
+
+        let windows = PathBuf::from("p:\\2301_spot\\01_work\\comp.nk");
+        assert_eq!(
+            map_path(&windows, &mappings, PathStyle::Unc),
+            Some(String::from(
+                "\\\\server\\projects\\2301_spot\\01_work\\comp.nk"
+            ))
+        );
+        assert_eq!(
+            map_path(&windows, &mappings, PathStyle::Mac),
+            Some(String::from("/Volumes/projects/2301_spot/01_work/comp.nk"))
+        );
+
+        let mac = PathBuf::from("/Volumes/projects/2301_spot");
+        assert_eq!(
+            map_path(&mac, &mappings, PathStyle::Windows),
+            Some(String::from("P:\\2301_spot"))
+        );
+        let other = PathBuf::from("/Volumes/projects_old/2301_spot");
+        assert_eq!(map_path(&other, &mappings, PathStyle::Unc), None);
+
+        set_mappings(&mappings);
+        let local = to_local(&windows);
+        match PathStyle::local() {
+            PathStyle::Windows => {
+                assert_eq!(local, PathBuf::from("P:\\2301_spot\\01_work\\comp.nk"))
+            }
+            PathStyle::Mac => assert_eq!(
+                local,
+                PathBuf::from("/Volumes/projects/2301_spot/01_work/comp.nk")
+            ),
+            _ => assert_eq!(
+                local,
+                PathBuf::from("/mnt/projects/2301_spot/01_work/comp.nk")
+            ),
+        }
+        assert_eq!(to_local(&other), other);
+    }
+
+    #[test]
+    fn test_project_grouping() {
+        let projects_dir = PathBuf::from("/nonexistent");
+        let project = |name: &str, status: Option<&str>, due_date: Option<&str>| {
+            let mut p = Project::new(
+                String::from(name),
+                projects_dir.clone(),
+                String::from("00_pipeline"),
+                String::from("01_work"),
+                String::from("02_dailies"),
+                String::from("03_deliveries"),
+                Vec::new(),
+                Vec::new(),
+            );
+            p.status = status.map(String::from);
+            p.due_date = due_date.map(String::from);
+            p
+        };
+        let projects = vec![
+            project("2301_acme_spot", Some("active"), Some("2023-05-01")),
+            project("2302_bolt_promo", None, Some("2023-03-01")),
+            project("2303_acme_teaser", Some("active"), None),
+            project("misc", Some("archived"), None),
+        ];
+        let names = |groups: &Vec<(String, Vec<Project>)>| -> Vec<(String, Vec<String>)> {
+            groups
+                .iter()
+                .map(|(k, g)| (k.clone(), g.iter().map(|p| p.name.clone()).collect()))
+                .collect()
+        };
+
+        let by_client = group_projects(
+            &projects,
+            ProjectGrouping::Client,
+            Some(ProjectSorting::Name),
+            &projects_dir,
+        );
+        assert_eq!(
+            names(&by_client),
+            vec![
+                (
+                    String::from("acme"),
+                    vec![
+                        String::from("2301_acme_spot"),
+                        String::from("2303_acme_teaser")
+                    ]
+                ),
+                (String::from("bolt"), vec![String::from("2302_bolt_promo")]),
+                (String::new(), vec![String::from("misc")]),
+            ]
+        );
+
+        let by_status = group_projects(
+            &projects,
+            ProjectGrouping::Status,
+            Some(ProjectSorting::DueDate),
+            &projects_dir,
+        );
+        assert_eq!(by_status.len(), 3);
+        assert_eq!(by_status[0].0, "active");
+        assert_eq!(by_status[0].1[1].name, "2303_acme_teaser");
+        assert_eq!(by_status[2].0, "");
+
+        let ungrouped = group_projects(
+            &projects,
+            ProjectGrouping::None,
+            Some(ProjectSorting::DueDate),
+            &projects_dir,
+        );
+        assert_eq!(ungrouped.len(), 1);
+        assert_eq!(ungrouped[0].1[0].name, "2302_bolt_promo");
+
+        let names_of_episodes = episode_names("2304_acme_show", 2, 3);
+        assert_eq!(names_of_episodes[0], "2304_acme_show_S02E01");
+        assert_eq!(names_of_episodes[2], "2304_acme_show_S02E03");
+        let mut episodes = projects.clone();
+        for name in names_of_episodes.iter().rev() {
+            let mut episode = project(name, None, None);
+            episode.program = String::from("2304_acme_show");
+            episodes.push(episode);
+        }
+        let by_program = group_projects(
+            &episodes,
+            ProjectGrouping::Program,
+            Some(ProjectSorting::Name),
+            &projects_dir,
+        );
+        assert_eq!(by_program.len(), 2);
+        assert_eq!(by_program[0].0, "2304_acme_show");
+        assert_eq!(
+            by_program[0].1.iter().map(|p| &p.name).collect::<Vec<_>>(),
+            names_of_episodes.iter().collect::<Vec<_>>()
+        );
+        assert_eq!(by_program[1].1.len(), 4);
+    }
+
+    #[test]
+    fn test_fuzzy_matching() {
+        assert_eq!(fuzzy_score("", "anything"), Some(0));
+        assert!(fuzzy_score("ACSP", "2301_acme_spot").is_some());
+        assert_eq!(fuzzy_score("spota", "2301_acme_spot"), None);
+
+        // Word starts and substrings beat scattered matches.
+        let spot = fuzzy_score("spot", "2301_acme_spot").unwrap();
+        let scattered = fuzzy_score("spot", "2302_super_promo_tv").unwrap();
+        assert!(spot > scattered);
+
+        let names = vec![
+            String::from("2302_super_promo_tv"),
+            String::from("2305_bolt_teaser"),
+            String::from("2301_acme_spot"),
+        ];
+        assert_eq!(
+            fuzzy_filter(&names, "spot", |n| n.as_str()),
+            vec![
+                String::from("2301_acme_spot"),
+                String::from("2302_super_promo_tv")
+            ]
+        );
+    }
+
+    #[test]
+    fn test_task_tree_filter() {
+        let node = |path: &str, children: Vec<TaskTreeNode>| {
+            let path = PathBuf::from(path);
+            let name = path.file_name().unwrap().to_string_lossy().to_string();
+            let mut n = TaskTreeNode::new(name, path, "work", "output");
+            n.metadata.is_task = children.is_empty();
+            n.children = children;
+            n
+        };
+        let tree = node(
+            "01_work",
+            vec![
+                node(
+                    "01_work/seq010",
+                    vec![
+                        node("01_work/seq010/sh010_comp", vec![]),
+                        node("01_work/seq010/sh020_comp", vec![]),
+                    ],
+                ),
+                node(
+                    "01_work/seq020",
+                    vec![node("01_work/seq020/sh030_anim", vec![])],
+                ),
+                node(
+                    "01_work/assets",
+                    vec![node("01_work/assets/chair_model", vec![])],
+                ),
+            ],
+        );
+
+        let filtered = tree.filtered("sh020").unwrap();
+        assert_eq!(filtered.children.len(), 1);
+        assert_eq!(filtered.children[0].name, "seq010");
+        assert_eq!(filtered.children[0].children.len(), 1);
+        assert_eq!(filtered.children[0].children[0].name, "sh020_comp");
+
+        // Matching folders keep their children.
+        let filtered = tree.filtered("seq020").unwrap();
+        assert_eq!(filtered.children[0].children.len(), 1);
+
+        assert!(tree.filtered("nothing").is_none());
+        assert!(tree.has_tasks());
+        let mut folder = node("01_work/legacy", vec![]);
+        folder.metadata.is_task = false;
+        assert!(!folder.has_tasks());
+        assert_eq!(tree.filtered("").unwrap().children.len(), 3);
+
+        let crumbs: Vec<&str> = tree
+            .path_to(&PathBuf::from("01_work/seq010/sh020_comp"))
+            .iter()
+            .map(|n| n.name.as_str())
+            .collect();
+        assert_eq!(crumbs, vec!["seq010", "sh020_comp"]);
+        assert!(tree.path_to(&PathBuf::from("elsewhere")).is_empty());
+    }
+
+    #[test]
+    fn test_theme() {
+        assert_eq!(
+            parse_color("#e8a33d"),
+            Some(egui::Color32::from_rgb(0xe8, 0xa3, 0x3d))
+        );
+        assert_eq!(parse_color("e8a33d"), parse_color("#E8A33D"));
+        assert_eq!(parse_color("#e8a3"), None);
+        assert_eq!(parse_color("#e8a3zz"), None);
+
+        let theme = Theme {
+            accent: Some(String::from("#e8a33d")),
+            background: None,
+            high_contrast: true,
+        };
+        let visuals = theme.visuals(true);
+        assert_eq!(visuals.selection.bg_fill, parse_color("#e8a33d").unwrap());
+        assert_eq!(visuals.panel_fill, egui::Color32::BLACK);
+        assert_eq!(visuals.override_text_color, Some(egui::Color32::WHITE));
+        assert_eq!(Theme::default().visuals(false), egui::Visuals::light());
+    }
+
+    #[test]
+    fn test_i18n() {
+        set_language("sv");
+        assert_eq!(tr("Trash"), "Papperskorg");
+        assert_eq!(trf("Copied {}", &[&"/mnt/x"]), "Kopierade /mnt/x");
+        assert_eq!(
+            trf("Failed to create {} of {} tasks.", &[&2, &5]),
+            "Kunde inte skapa 2 av 5 uppgifter."
+        );
+        assert_eq!(tr("Not in the table"), "Not in the table");
+        set_language("en");
+        assert_eq!(tr("Trash"), "Trash");
+        assert_eq!(trf("Copied {}", &[]), "Copied {}");
+    }
+
+    #[test]
+    fn test_config_formats() {
+        let dir = test_path("config_formats");
+        std::fs::create_dir_all(&dir).unwrap();
+        let expected = Theme {
+            accent: Some(String::from("#e8a33d")),
+            background: None,
+            high_contrast: true,
+        };
+        for (name, text) in [
+            ("theme.yaml", "accent: '#e8a33d'\nhigh_contrast: true\n"),
+            ("theme.toml", "accent = \"#e8a33d\"\nhigh_contrast = true\n"),
+            (
+                "theme.json",
+                r##"{"accent": "#e8a33d", "high_contrast": true}"##,
+            ),
+        ] {
+            let path = dir.join(name);
+            std::fs::write(&path, text).unwrap();
+            assert_eq!(read_config_file::<Theme>(&path), Ok(expected.clone()));
+        }
+
+        let path = dir.join("broken.toml");
+        std::fs::write(&path, "accent = ").unwrap();
+        assert!(read_config_file::<Theme>(&path).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_health_check() {
+        let projects_dir = test_path("health");
+        let project = test_project("show", &projects_dir);
+        let project_path = project.get_path(&projects_dir);
+        let work_path = project.get_work_path(&projects_dir);
+        std::fs::create_dir_all(work_path.join("sh010").join("comp").join("01_work")).unwrap();
+        std::fs::write(
+            work_path.join("sh010").join("comp").join("task.yaml"),
+            "name: comp\n",
+        )
+        .unwrap();
+        std::fs::create_dir_all(work_path.join("sh020").join("comp").join("01_work")).unwrap();
+        std::fs::write(work_path.join("notes.txt"), "").unwrap();
+        std::fs::create_dir_all(project.get_pipeline_path(&projects_dir)).unwrap();
+
+        let issues = check_project(&project, &projects_dir).unwrap();
+        assert_eq!(
+            issues,
+            vec![
+                HealthIssue::MissingDir(project_path.join("03_dailies")),
+                HealthIssue::MissingDir(project_path.join("04_deliveries")),
+                HealthIssue::StrayFile(work_path.join("notes.txt")),
+                HealthIssue::MissingDir(work_path.join("sh010").join("comp").join("02_output")),
+                HealthIssue::NotATask(work_path.join("sh020").join("comp")),
+            ]
+        );
+
+        for i in issues.iter().filter(|i| i.is_fixable()) {
+            i.fix(&project.work_sub_dirs).unwrap();
+        }
+        assert_eq!(
+            check_project(&project, &projects_dir).unwrap(),
+            vec![HealthIssue::StrayFile(work_path.join("notes.txt"))]
+        );
+        assert!(work_path
+            .join("sh020")
+            .join("comp")
+            .join("task.yaml")
+            .exists());
+        std::fs::remove_dir_all(&projects_dir).unwrap();
+    }
+
+    #[test]
+    fn test_project_adoption() {
+        let projects_dir = test_path("adoption");
+        let legacy = projects_dir.join("Old Show");
+        std::fs::create_dir_all(legacy.join("Work_Files")).unwrap();
+        std::fs::create_dir_all(legacy.join("Dailies")).unwrap();
+        std::fs::create_dir_all(projects_dir.join(".hidden")).unwrap();
+        let template = Project::new(
+            String::new(),
+            projects_dir.clone(),
+            String::from("00_pipeline"),
+            String::from("02_work"),
+            String::from("03_dailies"),
+            String::from("04_deliveries"),
+            Vec::new(),
+            vec![String::from("01_work"), String::from("02_output")],
+        );
+
+        assert_eq!(
+            ProjectAdoption::find_candidates(&projects_dir).unwrap(),
+            vec![legacy.clone()]
+        );
+        let adoption = ProjectAdoption::new(legacy.clone(), &template).unwrap();
+        assert_eq!(adoption.work_dir_name, "Work_Files");
+        assert_eq!(adoption.dailies_dir_name, "Dailies");
+        assert_eq!(adoption.deliveries_dir_name, "04_deliveries");
+
+        let project = adoption.adopt(&template).unwrap();
+        assert_eq!(project.get_path(&projects_dir), legacy);
+        assert!(legacy.join("04_deliveries").is_dir());
+        assert!(adoption.adopt(&template).is_err());
+        assert!(ProjectAdoption::find_candidates(&projects_dir)
+            .unwrap()
+            .is_empty());
+        let found = Project::find_projects(projects_dir.clone(), template).unwrap();
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].work_dir_name, "Work_Files");
+        std::fs::remove_dir_all(&projects_dir).unwrap();
+    }
+
+    #[test]
+    fn test_workfile_transfer() {
+        let projects_dir = test_path("transfer");
+        let project = test_project("show", &projects_dir);
+        let task = |name: &str| test_task(&project, &projects_dir, name);
+        let (comp, light) = (task("comp"), task("light"));
+        let template = FilenameTemplate::default();
+
+        let source = comp.get_work_path().join("show_comp_main_v004_wip.nk");
+        std::fs::write(&source, "").unwrap();
+        let file = File::from_path(source.clone(), &template).unwrap();
+        let taken = light.get_work_path().join("show_light_main_v001_wip.nk");
+        std::fs::write(&taken, "").unwrap();
+
+        assert_eq!(
+            file.transfer_path(&light, &project, &template, VersionTransfer::Reset),
+            light.get_work_path().join("show_light_main_v002_wip.nk")
+        );
+        let moved = file
+            .transfer(
+                &light,
+                &project,
+                &template,
+                VersionTransfer::Continue,
+                false,
+            )
+            .unwrap();
+        assert_eq!(
+            moved,
+            light.get_work_path().join("show_light_main_v004_wip.nk")
+        );
+        assert!(moved.exists() && !source.exists());
+
+        // Files not following the template keep their name.
+        let hand_named = comp.get_work_path().join("old_setup_v7.nk");
+        std::fs::write(&hand_named, "").unwrap();
+        let file = File::from_path(hand_named.clone(), &template).unwrap();
+        let copied = file
+            .transfer(&light, &project, &template, VersionTransfer::Reset, true)
+            .unwrap();
+        assert_eq!(copied, light.get_work_path().join("old_setup_v1.nk"));
+        assert!(hand_named.exists());
+
+        let variant = file
+            .duplicate_as("blue", &comp, &project, &template)
+            .unwrap();
+        assert_eq!(variant, comp.get_work_path().join("show_comp_blue_v001.nk"));
+        assert!(file
+            .duplicate_as("blue", &comp, &project, &template)
+            .is_err());
+        std::fs::remove_dir_all(&projects_dir).unwrap();
+    }
+
+    #[test]
+    fn test_workfile_transfer_underscored_project() {
+        let projects_dir = test_path("transfer_underscored");
+        let project = test_project("1234_acme_show", &projects_dir);
+        let task = |name: &str| test_task(&project, &projects_dir, name);
+        let (comp, light) = (task("comp_fix"), task("light"));
+        let template = FilenameTemplate::default();
+
+        let source = comp
+            .get_work_path()
+            .join("1234_acme_show_comp_fix_main_v004.nk");
+        std::fs::write(&source, "").unwrap();
+        let file = File::from_path(source.clone(), &template).unwrap();
+        let moved = file
+            .transfer(&light, &project, &template, VersionTransfer::Reset, false)
+            .unwrap();
+        assert_eq!(
+            moved.file_name().unwrap(),
+            "1234_acme_show_light_main_v001.nk"
+        );
+        assert!(moved.exists() && !source.exists());
+        std::fs::remove_dir_all(&projects_dir).unwrap();
+    }
+
+    #[test]
+    fn test_file_groups() {
+        let template = FilenameTemplate::default();
+        let file = |name: &str| File::from_path(PathBuf::from(name), &template).unwrap();
+        let files = vec![
+            file("show_comp_main_v010.nk"),
+            file("show_comp_main_v002_wip.nk"),
+            file("show_comp_main_v009.nk"),
+            file("show_comp_main_v003.hip"),
+            file("show_comp_alt_v001.nk"),
+        ];
+
+        let groups = FileGroup::group(&files);
+        assert_eq!(groups.len(), 3);
+        assert_eq!(groups[0].name, "show_comp_alt");
+        let main = groups
+            .iter()
+            .find(|g| g.name == "show_comp_main" && g.extension == "nk")
+            .unwrap();
+        assert_eq!(main.files.len(), 3);
+        assert_eq!(main.latest().unwrap().version, 10);
+        assert_eq!(main.newer_than(&files[1]).unwrap().version, 10);
+        assert!(main.newer_than(&files[0]).is_none());
+        assert!(main.newer_than(&files[3]).is_none());
+    }
+
+    #[test]
+    fn test_file_notes() {
+        let task_dir = test_path("notes");
+        std::fs::create_dir_all(&task_dir).unwrap();
+        assert_eq!(FileNotes::load(&task_dir).unwrap(), FileNotes::default());
+
+        let mut note = FileNote::default();
+        note.add_tag(" Approved ");
+        note.add_tag("approved");
+        note.add_tag("");
+        note.add_tag("client_v2");
+        assert_eq!(note.tags, vec!["approved", "client_v2"]);
+
+        let mut notes = FileNotes::default();
+        notes.set("show_comp_v002.nk", note);
+        notes.set(
+            "show_comp_v001.nk",
+            FileNote {
+                notes: String::from("Old grade"),
+                tags: vec![String::from("wip")],
+            },
+        );
+        notes.save(&task_dir).unwrap();
+
+        let mut loaded = FileNotes::load(&task_dir).unwrap();
+        assert_eq!(loaded, notes);
+        assert_eq!(loaded.tags(), vec!["approved", "client_v2", "wip"]);
+        assert!(loaded.has_tag("show_comp_v002.nk", "approved"));
+        assert!(!loaded.has_tag("show_comp_v001.nk", "approved"));
+
+        loaded.set("show_comp_v001.nk", FileNote::default());
+        assert!(loaded.get("show_comp_v001.nk").is_none());
+        std::fs::remove_dir_all(&task_dir).unwrap();
+    }
+
+    #[test]
+    fn test_launch_context_path() {
+        let context = |path: &str| LaunchContext {
+            file: PathBuf::from(path),
+            user: String::from("rclamp_test"),
+            ..Default::default()
+        };
+        let first = context("/show/sh010/comp/work/show_comp_v001.nk")
+            .write()
+            .unwrap();
+        let second = context("/show/sh020/comp/work/show_comp_v001.nk")
+            .write()
+            .unwrap();
+        assert_ne!(first, second);
+        assert_eq!(first.parent(), second.parent());
+        assert!(first.parent().unwrap().ends_with("rclamp-rclamp_test"));
+        std::fs::remove_dir_all(first.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_webhook_payloads() {
+        let webhooks: Vec<Webhook> = serde_yaml::from_str(
+            "- url: https://hooks.slack.com/x\n  kind: slack\n  events: [publish]\n  template: \"{user}: {file} {version} in {project}/{task}\"\n- url: https://example.com/hook\n",
+        )
+        .unwrap();
+        assert_eq!(webhooks[0].kind, WebhookKind::Slack);
+        assert_eq!(webhooks[1].kind, WebhookKind::Generic);
+        assert!(webhooks[0].handles(WebhookEvent::Publish));
+        assert!(!webhooks[0].handles(WebhookEvent::Delivery));
+        assert!(webhooks[1].handles(WebhookEvent::StatusChange));
+
+        let mut context = WebhookContext::new(WebhookEvent::Publish);
+        context.project = String::from("Show");
+        context.task = String::from("comp");
+        context.file = String::from("show_comp_v003.nk");
+        context.version = String::from("v003");
+        context.user = String::from("anna");
+        assert_eq!(
+            webhooks[0].payload(&context),
+            serde_json::json!({"text": "anna: show_comp_v003.nk v003 in Show/comp"})
+        );
+
+        let payload = webhooks[1].payload(&context);
+        assert_eq!(payload["event"], "publish");
+        assert_eq!(payload["version"], "v003");
+        assert_eq!(
+            payload["message"],
+            "anna published show_comp_v003.nk (v003) in Show / comp"
+        );
+    }
+
+    #[test]
+    fn test_sessions() {
+        let pipeline_dir = test_path("sessions");
+        let session = Session::new(None);
+        assert!(session.write(&pipeline_dir).is_err());
+        std::fs::create_dir_all(&pipeline_dir).unwrap();
+        session.write(&pipeline_dir).unwrap();
+
+        let mut other = session.clone();
+        other.user = String::from("someone_else");
+        other.task = Some(String::from("comp"));
+        other.task_path = Some(PathBuf::from("/projects/show/02_work/comp"));
+        other.write(&pipeline_dir).unwrap();
+
+        let now = session.updated_at;
+        let sessions = Session::list_active(&pipeline_dir, now).unwrap();
+        assert_eq!(sessions.len(), 2);
+        assert!(sessions.contains(&other));
+
+        other.updated_at = now - SESSION_TIMEOUT;
+        other.write(&pipeline_dir).unwrap();
+        assert_eq!(
+            Session::list_active(&pipeline_dir, now).unwrap(),
+            vec![session.clone()]
+        );
+
+        session.remove(&pipeline_dir).unwrap();
+        session.remove(&pipeline_dir).unwrap();
+        assert!(Session::list_active(&pipeline_dir, now).unwrap().is_empty());
+        std::fs::remove_dir_all(&pipeline_dir).unwrap();
+    }
+
+    #[test]
+    fn test_atomic_writes() {
+        let dir = test_path("atomic");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("clients.yaml");
+
+        create_yaml(&path, &vec!["a"]).unwrap();
+        assert_eq!(
+            create_yaml(&path, &vec!["b"]).unwrap_err().kind(),
+            std::io::ErrorKind::AlreadyExists
+        );
+        write_yaml(&path, &vec!["a", "b"]).unwrap();
+        let read: Vec<String> =
+            serde_yaml::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(read, vec!["a", "b"]);
+
+        // Concurrent read-modify-write cycles under the lock don't lose updates.
+        let threads: Vec<_> = (0..4)
+            .map(|i| {
+                let path = path.clone();
+                std::thread::spawn(move || {
+                    let _lock = FileLock::acquire(&path).unwrap();
+                    let mut list: Vec<String> =
+                        serde_yaml::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+                    list.push(i.to_string());
+                    write_atomic(&path, &list).unwrap();
+                })
+            })
+            .collect();
+        for t in threads {
+            t.join().unwrap();
+        }
+        let read: Vec<String> =
+            serde_yaml::from_str(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(read.len(), 6);
+
+        // A lock someone else took over is left to them.
+        let lock = FileLock::acquire(&path).unwrap();
+        let lock_path = dir.join(".clients.yaml.lock");
+        std::fs::write(&lock_path, "other@host").unwrap();
+        drop(lock);
+        assert_eq!(std::fs::read_to_string(&lock_path).unwrap(), "other@host");
+        std::fs::remove_file(&lock_path).unwrap();
+
+        // Only the file itself is left, no temp or lock files.
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_sync_manifest() {
+        let project_dir = test_path("sync");
+        std::fs::create_dir_all(project_dir.join("02_work/comp/01_work")).unwrap();
+        std::fs::create_dir_all(project_dir.join("03_dailies")).unwrap();
+        std::fs::write(project_dir.join("project.yaml"), "name: test").unwrap();
+        std::fs::write(
+            project_dir.join("02_work/comp/01_work/comp_v001.nk"),
+            "nuke",
+        )
+        .unwrap();
+
+        let manifest = SyncManifest::build(&project_dir).unwrap();
+        assert_eq!(manifest.project, "rclamp_test_sync");
+        assert_eq!(
+            manifest.dirs,
+            vec![
+                "02_work",
+                "02_work/comp",
+                "02_work/comp/01_work",
+                "03_dailies"
+            ]
+        );
+        let files: Vec<&str> = manifest.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(
+            files,
+            vec!["02_work/comp/01_work/comp_v001.nk", "project.yaml"]
+        );
+        assert_eq!(manifest.total_size(), 14);
+        assert!(manifest.verify(&project_dir).is_empty());
+
+        std::fs::write(project_dir.join("project.yaml"), "name: changed").unwrap();
+        std::fs::remove_dir(project_dir.join("03_dailies")).unwrap();
+        assert_eq!(
+            manifest.verify(&project_dir),
+            vec!["03_dailies", "project.yaml"]
+        );
+        std::fs::remove_dir_all(&project_dir).unwrap();
+    }
+
+    #[test]
+    fn test_mirror_command() {
+        let mut profile: MirrorProfile = serde_yaml::from_str(
+            "name: Backup\nsource: /mnt/projects\ndestination: /mnt/backup nas\nexclude: [\"*.tmp\"]\n",
+        )
+        .unwrap();
+        assert!(!profile.delete);
+        let expected = if cfg!(windows) {
+            "robocopy /mnt/projects \"/mnt/backup nas\" /E /XF *.tmp /XD *.tmp /R:2 /W:5 /NP"
+        } else {
+            "rsync -a -v --human-readable --exclude=*.tmp /mnt/projects/ \"/mnt/backup nas\""
+        };
+        assert_eq!(profile.command_line(), expected);
+
+        profile.delete = true;
+        profile.include = vec![String::from("*.exr")];
+        let expected = if cfg!(windows) {
+            "robocopy /mnt/projects \"/mnt/backup nas\" *.exr /MIR /XF *.tmp /XD *.tmp /R:2 /W:5 /NP"
+        } else {
+            "rsync -a -v --human-readable --delete --exclude=*.tmp --include=*/ --include=*.exr --exclude=* --prune-empty-dirs /mnt/projects/ \"/mnt/backup nas\""
+        };
+        assert_eq!(profile.command_line(), expected);
+    }
+
+    #[cfg(feature = "deliveries")]
+    #[test]
+    fn test_delivery_timelines() {
+        use crate::deliveries::{
+            timecode, timeline_csv, timeline_edl, timeline_otio, DeliveryClip,
+        };
+        use crate::sequences::Sequence;
+
+        assert_eq!(timecode(24 * 3600 + 25, 24), "01:00:01:01");
+        let item = OutputItem::Sequence(Sequence {
+            dir: PathBuf::from("/show/comp/02_output"),
+            prefix: String::from("comp_v003."),
+            suffix: String::from(".exr"),
+            padding: 4,
+            frames: (1001..=1048).collect(),
+            size: 0,
+        });
+        let clips = vec![DeliveryClip::new(&item, 24, None)];
+        assert_eq!(clips[0].start, 1001);
+        assert_eq!(clips[0].duration, 48);
+
+        let edl = timeline_edl("show_comp", &clips, 24);
+        assert!(edl.starts_with("TITLE: show_comp\n"));
+        assert!(edl.contains(
+            "001  AX       V     C        00:00:41:17 00:00:43:17 01:00:00:00 01:00:02:00\n"
+        ));
+        assert!(edl.contains("* FROM CLIP NAME: comp_v003.####.exr"));
+
+        let csv = timeline_csv(&clips, 24);
+        assert_eq!(
+            csv.lines().nth(1),
+            Some("\"comp_v003.####.exr\",1001,1048,48,01:00:00:00,01:00:02:00")
+        );
+
+        let otio = timeline_otio("show_comp", &clips, 24);
+        let clip = &otio["tracks"]["children"][0]["children"][0];
+        assert_eq!(clip["source_range"]["duration"]["value"], 48.);
+        assert_eq!(clip["media_reference"]["name_prefix"], "comp_v003.");
+        assert_eq!(clip["media_reference"]["frame_zero_padding"], 4);
+    }
+
+    #[test]
+    fn test_naming_lint() {
+        let projects_dir = test_path("lint");
+        let project = test_project("acme_show", &projects_dir);
+        let work_path = project.get_work_path(&projects_dir);
+        let comp_path = work_path.join("comp");
+        std::fs::create_dir_all(comp_path.join("01_work")).unwrap();
+        std::fs::write(comp_path.join("task.yaml"), "name: comp").unwrap();
+        for name in [
+            "acme_show_comp_main_v001.nk",
+            "acme_show_comp_v002_wip.nk",
+            "acme_show_comp_main_v7.nk",
+            "other_comp_main_v003.nk",
+            "acme_show_light_v004.nk",
+            "Old Setup_v12.nk",
+            "notes.txt",
+            "scratch.autosave",
+        ] {
+            std::fs::write(comp_path.join("01_work").join(name), "").unwrap();
+        }
+        let tree = TaskTreeNode::from_path(work_path, "01_work", "02_output", 0).unwrap();
+        let ignore = IgnorePatterns::new(&[String::from("*.autosave")]).unwrap();
+        let template = FilenameTemplate::default();
+
+        let issues = lint_tasks(&tree, &project, &template, &ignore).unwrap();
+        let summary: Vec<(String, NamingProblem, Option<String>)> = issues
+            .iter()
+            .map(|i| {
+                (
+                    i.path.file_name().unwrap().to_string_lossy().to_string(),
+                    i.problem.clone(),
+                    i.suggestion.clone(),
+                )
+            })
+            .collect();
+        let some = |s: &str| Some(String::from(s));
+        assert_eq!(
+            summary,
+            vec![
+                (
+                    String::from("Old Setup_v12.nk"),
+                    NamingProblem::NotTemplate,
+                    some("acme_show_comp_oldsetup_v012.nk")
+                ),
+                (
+                    String::from("acme_show_comp_main_v7.nk"),
+                    NamingProblem::Format,
+                    some("acme_show_comp_main_v007.nk")
+                ),
+                (
+                    String::from("acme_show_light_v004.nk"),
+                    NamingProblem::WrongTask(String::from("light")),
+                    some("acme_show_comp_v004.nk")
+                ),
+                (String::from("notes.txt"), NamingProblem::NoVersion, None),
+                (
+                    String::from("other_comp_main_v003.nk"),
+                    NamingProblem::WrongProject(String::from("other")),
+                    some("acme_show_comp_main_v003.nk")
+                ),
+            ]
+        );
+
+        let dry_run = rename_issues(&issues, true);
+        assert_eq!(dry_run.len(), 4);
+        assert!(comp_path.join("01_work/Old Setup_v12.nk").exists());
+        let report = rename_issues(&issues, false);
+        assert!(report.iter().all(|l| l.starts_with("Renamed")));
+        assert!(comp_path
+            .join("01_work/acme_show_comp_main_v007.nk")
+            .exists());
+        let issues = lint_tasks(&tree, &project, &template, &ignore).unwrap();
+        assert_eq!(issues.len(), 1);
+        std::fs::remove_dir_all(&projects_dir).unwrap();
+    }
+
+    #[test]
+    fn test_batch_version_up() {
+        let task_path = test_path("batch/comp");
+        let work_path = task_path.join("01_work");
+        std::fs::create_dir_all(&work_path).unwrap();
+        for name in [
+            "show_comp_main_v001.nk",
+            "show_comp_main_v002.nk",
+            "show_comp_cleanup_v005.nk",
+            "notes.txt",
+        ] {
+            std::fs::write(work_path.join(name), "").unwrap();
+        }
+        let task = TaskTreeNode::new(
+            String::from("comp"),
+            task_path.clone(),
+            "01_work",
+            "02_output",
+        );
+        let template = FilenameTemplate::default();
+        let ignore = IgnorePatterns::default();
+
+        let results = version_up_task(&task, &template, &ignore).unwrap();
+        let lines: Vec<String> = results.iter().map(|r| r.report_line()).collect();
+        assert_eq!(
+            lines,
+            vec![
+                "comp: show_comp_cleanup_v005.nk → show_comp_cleanup_v006.nk",
+                "comp: show_comp_main_v002.nk → show_comp_main_v003.nk",
+            ]
+        );
+        assert!(work_path.join("show_comp_main_v003.nk").exists());
+        assert!(!work_path.join("show_comp_main_v004.nk").exists());
+
+        std::fs::remove_dir_all(&work_path).unwrap();
+        assert!(version_up_task(&task, &template, &ignore).is_err());
+        std::fs::remove_dir_all(task_path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_metadata_store() {
+        let task_path = test_path("metadata");
+        std::fs::create_dir_all(&task_path).unwrap();
+        let file = task_path.join("01_work").join("show_comp_main_v001.nk");
+        let key = MetadataStore::file_key(&task_path, &file);
+        assert_eq!(key, "01_work/show_comp_main_v001.nk");
+        assert_eq!(
+            MetadataStore::load(&task_path).unwrap(),
+            MetadataStore::default()
+        );
+
+        MetadataStore::update(&task_path, &file, "renderer", "karma").unwrap();
+        let store = MetadataStore::update(&task_path, &file, "fps", " 25 ").unwrap();
+        assert_eq!(store.get(&key, "fps"), Some("25"));
+        let store = MetadataStore::load(&task_path).unwrap();
+        assert_eq!(store.get(&key, "renderer"), Some("karma"));
+        assert_eq!(store.get(&key, "fps"), Some("25"));
+        assert_eq!(store.get("01_work/other_v001.nk", "fps"), None);
+
+        MetadataStore::update(&task_path, &file, "renderer", "").unwrap();
+        let store = MetadataStore::update(&task_path, &file, "fps", "").unwrap();
+        assert_eq!(store, MetadataStore::default());
+        assert_eq!(
+            std::fs::read_to_string(task_path.join(METADATA_FILE_NAME)).unwrap(),
+            "{}\n"
+        );
+        std::fs::remove_dir_all(&task_path).unwrap();
+    }
+
+    #[test]
+    fn test_task_details() {
+        let task_path = test_path("details/sh010");
+        let _ = std::fs::remove_dir_all(task_path.parent().unwrap());
+        std::fs::create_dir_all(&task_path).unwrap();
+        std::fs::write(task_path.join("task.yaml"), "name: sh010\nstatus: wip\n").unwrap();
+        let reference = task_path.parent().unwrap().join("board.jpg");
+        std::fs::write(&reference, "jpg").unwrap();
+        let mut task = TaskTreeNode::new(
+            String::from("sh010"),
+            task_path.clone(),
+            "01_work",
+            "02_output",
+        );
+        task.metadata.is_task = true;
+        assert_eq!(task.details().unwrap(), TaskDetails::default());
+
+        let name = task.attach_reference(&reference).unwrap();
+        assert_eq!(name, "board.jpg");
+        assert!(task.reference_path(&name).exists());
+        assert!(task.attach_reference(&reference).is_err());
+
+        let details = TaskDetails {
+            description: String::from("Remove the rig, keep the shadow."),
+            links: vec![String::from("https://tracker.example.com/shots/sh010")],
+            references: vec![name],
+            ..Default::default()
+        };
+        task.set_details(details.clone()).unwrap();
+        task.set_status(String::from("review")).unwrap();
+        assert_eq!(task.details().unwrap(), details);
+        let text = std::fs::read_to_string(task_path.join("task.yaml")).unwrap();
+        assert!(text.contains("status: review"));
+        assert!(text.contains("description: Remove the rig, keep the shadow."));
+
+        std::fs::remove_dir_all(task_path.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_ingest() {
+        let root = test_path("ingest");
+        let source = root.join("from_client");
+        std::fs::create_dir_all(source.join("A001")).unwrap();
+        std::fs::write(source.join("A001 C002.mov"), "movie").unwrap();
+        for frame in 1..=3 {
+            let name = format!("A001_plate.{:04}.dpx", frame);
+            std::fs::write(source.join("A001").join(name), "frame").unwrap();
+        }
+        std::fs::write(source.join(".DS_Store"), "").unwrap();
+
+        // 2026-03-05
+        let timestamp = 1772668800;
+        let items = plan_ingest(
+            std::slice::from_ref(&source),
+            "{project}_{date}_{name}",
+            "acme",
+            timestamp,
+        )
+        .unwrap();
+        let names: Vec<String> = items.iter().map(|i| i.target_name()).collect();
+        assert_eq!(
+            names,
+            vec![
+                "acme_20260305_a001c002.mov",
+                "acme_20260305_a001_plate.####.dpx"
+            ]
+        );
+        assert!(plan_ingest(&[root.join("missing")], "{name}", "acme", timestamp).is_err());
+
+        let dir = root.join("footage");
+        let mut jobs = JobQueue::new(1);
+        let job_items = items.clone();
+        let job_dir = dir.clone();
+        jobs.push("ingest", move |job| ingest(&job_items, &job_dir, job));
+        let mut finished = Vec::new();
+        while finished.is_empty() {
+            finished.extend(jobs.finished());
+            std::thread::sleep(std::time::Duration::from_millis(10));
+        }
+        assert!(matches!(finished[0].state, JobState::Done(_)));
+        assert!(dir.join("acme_20260305_a001_plate.0002.dpx").exists());
+
+        let manifest = IngestManifest::load(&dir).unwrap();
+        assert_eq!(manifest.files.len(), 4);
+        assert_eq!(
+            manifest.files[0].sha1,
+            "94b19a105fce9aa5d2e3270cc428e808538ef548"
+        );
+        assert!(manifest.verify(&dir).is_empty());
+        std::fs::write(dir.join("acme_20260305_a001c002.mov"), "edited").unwrap();
+        assert_eq!(manifest.verify(&dir), vec!["acme_20260305_a001c002.mov"]);
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_inbox_watcher() {
+        let inbox = test_path("inbox");
+        std::fs::create_dir_all(inbox.join("acme").join("plates")).unwrap();
+        std::fs::create_dir_all(inbox.join("unsorted")).unwrap();
+        std::fs::write(inbox.join("brief.pdf"), "pdf").unwrap();
+        std::fs::write(inbox.join("acme").join("plates").join("a.exr"), "exr").unwrap();
+        std::fs::write(inbox.join("unsorted").join("b.mov"), "mov").unwrap();
+        let projects = vec![String::from("acme")];
+
+        let mut watcher = InboxWatcher::default();
+        assert_eq!(watcher.poll(&inbox, &projects).unwrap(), 0);
+        assert_eq!(watcher.poll(&inbox, &projects).unwrap(), 3);
+        let pending: Vec<(String, Option<String>, u64)> = watcher
+            .pending()
+            .iter()
+            .map(|e| {
+                let name = e.path.file_name().unwrap().to_string_lossy().to_string();
+                (name, e.project.clone(), e.size)
+            })
+            .collect();
+        assert_eq!(
+            pending,
+            vec![
+                (String::from("plates"), Some(String::from("acme")), 3),
+                (String::from("brief.pdf"), None, 3),
+                (String::from("unsorted"), None, 3),
+            ]
+        );
+
+        // Still being copied.
+        std::fs::write(inbox.join("unsorted").join("c.mov"), "mov").unwrap();
+        assert_eq!(watcher.poll(&inbox, &projects).unwrap(), 0);
+        assert_eq!(watcher.pending().len(), 2);
+        assert_eq!(watcher.poll(&inbox, &projects).unwrap(), 1);
+
+        watcher.dismiss(&inbox.join("brief.pdf"));
+        watcher.take(&inbox.join("unsorted"));
+        assert_eq!(watcher.poll(&inbox, &projects).unwrap(), 0);
+        assert_eq!(watcher.pending().len(), 1);
+        watcher.release(&inbox.join("unsorted"));
+        watcher.poll(&inbox, &projects).unwrap();
+        assert_eq!(watcher.poll(&inbox, &projects).unwrap(), 1);
+
+        std::fs::remove_dir_all(&inbox).unwrap();
+    }
+
+    #[test]
+    fn test_schedule() {
+        for days in [-1, 0, 11016, 20517] {
+            let (year, month, day) = date_from_days(days);
+            assert_eq!(days_from_date(year, month, day), days);
+        }
+        assert_eq!(parse_date("1970-01-01"), Some(0));
+        assert_eq!(parse_date("2024-02-29"), Some(19782));
+        assert_eq!(parse_date("2023-02-29"), None);
+        assert_eq!(parse_date("2023-13-01"), None);
+        assert_eq!(parse_date("soon"), None);
+
+        let mut spot = Project::new(
+            String::from("2301_acme_spot"),
+            PathBuf::from("/nonexistent"),
+            String::from("00_pipeline"),
+            String::from("01_work"),
+            String::from("02_dailies"),
+            String::from("03_deliveries"),
+            Vec::new(),
+            Vec::new(),
+        );
+        spot.due_date = Some(String::from("2024-03-20"));
+        let mut old = spot.clone();
+        old.name = String::from("2201_acme_old");
+        old.due_date = Some(String::from("2024-01-10"));
+        old.status = Some(String::from("completed"));
+
+        let details = TaskDetails {
+            due_date: Some(String::from("2024-03-01")),
+            milestones: vec![
+                Milestone {
+                    name: String::from("Layout"),
+                    date: String::from("2024-02-10"),
+                    done: false,
+                },
+                Milestone {
+                    name: String::from("Blocking"),
+                    date: String::from("2024-02-12"),
+                    done: true,
+                },
+                Milestone {
+                    name: String::from("Typo"),
+                    date: String::from("2024-02-30"),
+                    done: false,
+                },
+            ],
+            ..Default::default()
+        };
+        let tasks = vec![(String::from("sh010"), PathBuf::from("/sh010"), details)];
+        let mut items = project_items(&[spot, old]);
+        items.extend(task_items("2301_acme_spot", &tasks));
+        assert_eq!(items.len(), 5);
+
+        let today = parse_date("2024-02-25").unwrap();
+        let groups: Vec<(Option<String>, Vec<String>)> = group_by_month(items, today)
+            .into_iter()
+            .map(|(m, g)| (m, g.into_iter().map(|i| i.name).collect()))
+            .collect();
+        assert_eq!(
+            groups,
+            vec![
+                (None, vec![String::from("sh010: Layout")]),
+                (
+                    Some(String::from("2024-01")),
+                    vec![String::from("2201_acme_old")]
+                ),
+                (
+                    Some(String::from("2024-02")),
+                    vec![String::from("sh010: Blocking")]
+                ),
+                (
+                    Some(String::from("2024-03")),
+                    vec![String::from("sh010"), String::from("2301_acme_spot")]
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_ui_prefs() {
+        let mut state = UiPrefs::default();
+        for i in 0..MAX_RECENT_TASKS + 2 {
+            push_recent(
+                &mut state.recent_tasks,
+                &PathBuf::from(format!("/work/sh{:03}", i)),
+            );
+        }
+        push_recent(&mut state.recent_tasks, &PathBuf::from("/work/sh005"));
+        assert_eq!(state.recent_tasks.len(), MAX_RECENT_TASKS);
+        assert_eq!(state.recent_tasks[0], PathBuf::from("/work/sh005"));
+        assert_eq!(state.recent_tasks[1], PathBuf::from("/work/sh011"));
+        assert!(!state.recent_tasks.contains(&PathBuf::from("/work/sh001")));
+
+        state.pinned_projects = vec![String::from("2301_acme_spot")];
+        state.project_grouping = ProjectGrouping::Client;
+        state.preferences.font_size = 16.;
+        state.layout.show_tasks = false;
+        state.last_project = Some(PathBuf::from("/projects/2301_acme_spot"));
+        state.last_task = Some(PathBuf::from("/projects/2301_acme_spot/02_work/sh010/comp"));
+        let path = test_path("state.yaml");
+        state.export(&path).unwrap();
+        assert_eq!(UiPrefs::import(&path).unwrap(), state);
+
+        // Older exports and partial files fill in the defaults.
+        std::fs::write(&path, "pinned_projects: [misc]\n").unwrap();
+        let imported = UiPrefs::import(&path).unwrap();
+        assert_eq!(imported.pinned_projects, vec![String::from("misc")]);
+        assert_eq!(imported.preferences, UiPrefs::default().preferences);
+        assert_eq!(imported.last_project, None);
+        assert!(imported.preferences.task_click_loads_files);
+        let text =
+            "preferences:\n  file_double_click: open_with\n  task_click_loads_files: false\n";
+        std::fs::write(&path, text).unwrap();
+        let imported = UiPrefs::import(&path).unwrap();
+        assert_eq!(
+            imported.preferences.file_double_click,
+            FileDoubleClick::OpenWith
+        );
+        assert!(!imported.preferences.task_click_loads_files);
+        std::fs::write(&path, "pinned_projects: misc: [\n").unwrap();
+        assert!(UiPrefs::import(&path).is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_open_links() {
+        let args = |a: &[&str]| -> Vec<String> { a.iter().map(|s| String::from(*s)).collect() };
+        assert_eq!(OpenTarget::from_args(&[]), Ok(None));
+        assert_eq!(
+            OpenTarget::from_args(&args(&["--project", "myshow", "--task", "sh0010_comp"])),
+            Ok(Some(OpenTarget {
+                project: String::from("myshow"),
+                task: Some(String::from("sh0010_comp")),
+            }))
+        );
+        assert!(OpenTarget::from_args(&args(&["--task", "sh0010_comp"])).is_err());
+        assert!(OpenTarget::from_args(&args(&["--project"])).is_err());
+        assert!(OpenTarget::from_args(&args(&["--verbose"])).is_err());
+
+        let target = OpenTarget {
+            project: String::from("2301 acme & co"),
+            task: Some(String::from("seq010/sh0010_comp")),
+        };
+        let url = target.to_url();
+        assert_eq!(
+            url,
+            "rclamp://open?project=2301%20acme%20%26%20co&task=seq010/sh0010_comp"
+        );
+        assert_eq!(
+            OpenTarget::from_args(&args(&[&url])),
+            Ok(Some(target.clone()))
+        );
+        assert_eq!(
+            OpenTarget::from_url("rclamp://open/?project=myshow"),
+            Ok(OpenTarget {
+                project: String::from("myshow"),
+                task: None,
+            })
+        );
+        assert!(OpenTarget::from_url("rclamp://open?task=sh0010").is_err());
+        assert!(OpenTarget::from_url("rclamp://open?project=%zz").is_err());
+        assert!(OpenTarget::from_url("https://example.com").is_err());
+
+        let work = PathBuf::from("/projects/myshow/01_work");
+        let task = |path: &str| {
+            let path = work.join(path);
+            let name = path.file_name().unwrap().to_string_lossy().to_string();
+            let mut t = TaskTreeNode::new(name, path, "01_work", "02_output");
+            t.metadata.is_task = true;
+            t
+        };
+        let mut seq010 = TaskTreeNode::new(
+            String::from("seq010"),
+            work.join("seq010"),
+            "01_work",
+            "02_output",
+        );
+        seq010.children = vec![task("seq010/sh0010_comp"), task("seq010/lighting")];
+        let mut seq020 = TaskTreeNode::new(
+            String::from("seq020"),
+            work.join("seq020"),
+            "01_work",
+            "02_output",
+        );
+        seq020.children = vec![task("seq020/lighting")];
+        let mut tree = TaskTreeNode::new(
+            String::from("01_work"),
+            work.clone(),
+            "01_work",
+            "02_output",
+        );
+        tree.children = vec![seq010, seq020];
+
+        assert_eq!(
+            task_key(&work, &work.join("seq010/sh0010_comp")),
+            "seq010/sh0010_comp"
+        );
+        let found = |t: &str| find_task(&tree, &work, t).map(|n| n.path.clone());
+        assert_eq!(found("sh0010_comp"), Some(work.join("seq010/sh0010_comp")));
+        assert_eq!(found("seq020/lighting"), Some(work.join("seq020/lighting")));
+        // Ambiguous names need the path.
+        assert_eq!(found("lighting"), None);
+        assert_eq!(found("seq010"), None);
+    }
+
+    #[test]
+    fn test_preferred_dcc() {
+        let dcc = |name: &str, extension: &str| Dcc {
+            name: String::from(name),
+            extension: String::from(extension),
+            template_path: PathBuf::new(),
+            substitute_tokens: false,
+            executable: None,
+            version: None,
+            autosave_dirs: Vec::new(),
+        };
+        let dccs = vec![
+            dcc("nuke", ".nk"),
+            dcc("houdini", ".hip"),
+            dcc("maya", ".ma"),
+        ];
+        let file = |extension: &str| File {
+            name: String::from("light"),
+            path: PathBuf::from(format!("/sh010/light_v001.{}", extension)),
+            extension: String::from(extension),
+            version: 1,
+            prefix: String::new(),
+            suffix: String::new(),
+            padding: 3,
+        };
+        let files = vec![file("hip"), file("nk"), file("hip"), file("txt")];
+        let name = |d: Option<&Dcc>| d.map(|d| d.name.clone());
+
+        assert_eq!(name(Dcc::preferred(&dccs, None, &[])), None);
+        assert_eq!(
+            name(Dcc::preferred(&dccs, None, &files)),
+            Some(String::from("houdini"))
+        );
+        assert_eq!(
+            name(Dcc::preferred(&dccs, Some("maya"), &files)),
+            Some(String::from("maya"))
+        );
+        // A DCC that's no longer installed falls back to the workfiles.
+        assert_eq!(
+            name(Dcc::preferred(&dccs, Some("blender"), &files)),
+            Some(String::from("houdini"))
+        );
+        assert_eq!(
+            name(Dcc::preferred(&dccs, None, &files[1..3])),
+            Some(String::from("nuke"))
+        );
+
+        let task_path = test_path("last_dcc");
+        std::fs::create_dir_all(&task_path).unwrap();
+        std::fs::write(task_path.join("task.yaml"), "name: sh010\nstatus: wip\n").unwrap();
+        let task = TaskTreeNode::new(
+            String::from("sh010"),
+            task_path.clone(),
+            "01_work",
+            "02_output",
+        );
+        assert_eq!(task.last_dcc().unwrap(), None);
+        task.set_last_dcc("houdini").unwrap();
+        assert_eq!(task.last_dcc().unwrap(), Some(String::from("houdini")));
+        let text = std::fs::read_to_string(task_path.join("task.yaml")).unwrap();
+        assert!(text.contains("status: wip"));
+        std::fs::remove_dir_all(&task_path).unwrap();
+    }
+
+    #[test]
+    fn test_templates() {
+        let root = test_path("templates");
+        let templates_dir = root.join("templates");
+        for dir in ["nuke", "houdini", "broken", "pipeline"] {
+            std::fs::create_dir_all(templates_dir.join(dir)).unwrap();
+        }
+        std::fs::write(
+            templates_dir.join("nuke/app.yaml"),
+            "name: nuke\nextension: .nk\ntemplate_path: template.nk\n",
+        )
+        .unwrap();
+        std::fs::write(templates_dir.join("nuke/template.nk"), "v1").unwrap();
+        std::fs::write(
+            templates_dir.join("houdini/app.yaml"),
+            "name: houdini\nextension: .hip\ntemplate_path: template.hip\n",
+        )
+        .unwrap();
+        std::fs::write(templates_dir.join("houdini/template.hipnc"), "").unwrap();
+        std::fs::write(templates_dir.join("broken/app.yaml"), "name: [").unwrap();
+
+        let dirs = scan_templates(&templates_dir).unwrap();
+        let names: Vec<String> = dirs.iter().map(|d| d.name()).collect();
+        assert_eq!(names, vec!["broken", "houdini", "nuke"]);
+        assert_eq!(dirs[0].dcc, None);
+        assert_eq!(dirs[0].problems.len(), 1);
+        assert_eq!(
+            dirs[1].problems,
+            vec![
+                String::from("No template.hip."),
+                String::from("template.hipnc doesn't match the extension .hip in app.yaml."),
+            ]
+        );
+        assert!(dirs[2].problems.is_empty());
+        assert_eq!(
+            dirs[2].template_path(),
+            Some(templates_dir.join("nuke/template.nk"))
+        );
+
+        // Importing over a template keeps the old one as a version.
+        let source = root.join("comp_template.nk");
+        std::fs::write(&source, "v2").unwrap();
+        import_template(&templates_dir, "nuke", &source).unwrap();
+        let nuke = templates_dir.join("nuke");
+        assert_eq!(
+            std::fs::read_to_string(nuke.join("template.nk")).unwrap(),
+            "v2"
+        );
+        let dirs = scan_templates(&templates_dir).unwrap();
+        assert_eq!(
+            dirs[2].versions,
+            vec![nuke.join("versions/template_v001.nk")]
+        );
+        assert!(import_template(&templates_dir, "houdini", &source).is_err());
+
+        restore_template(&nuke, &nuke.join("versions/template_v001.nk")).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(nuke.join("template.nk")).unwrap(),
+            "v1"
+        );
+        assert!(nuke.join("versions/template_v002.nk").exists());
+
+        // A new DCC gets a config.
+        let blend = root.join("shot.blend");
+        std::fs::write(&blend, "blend").unwrap();
+        let dcc = import_template(&templates_dir, "blender", &blend).unwrap();
+        assert_eq!(dcc.extension, ".blend");
+        let dirs = scan_templates(&templates_dir).unwrap();
+        assert!(dirs
+            .iter()
+            .any(|d| d.name() == "blender" && d.problems.is_empty()));
+        assert!(Dcc::find_dcc(&templates_dir)
+            .unwrap()
+            .iter()
+            .any(|d| d.name == "blender"));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_detect_dccs() {
+        assert!(wildcard_match("Nuke*", "Nuke13.2v4"));
+        assert!(wildcard_match("maya*", "maya2024"));
+        assert!(wildcard_match(
+            "Adobe After Effects*",
+            "Adobe After Effects 2023"
+        ));
+        assert!(!wildcard_match("hfs*", "houdini"));
+        assert!(!wildcard_match("Nuke*.exe", "Nuke13.2"));
+        assert_eq!(
+            natural_cmp("Nuke9.0v8", "Nuke13.2v4"),
+            std::cmp::Ordering::Less
+        );
+
+        let root = test_path("detect");
+        for version in ["hfs19.5.640", "hfs20.0.506", "hfs9.0"] {
+            std::fs::create_dir_all(root.join("opt").join(version).join("bin")).unwrap();
+            std::fs::write(root.join("opt").join(version).join("bin/houdini"), "").unwrap();
+        }
+        std::fs::create_dir_all(root.join("opt/hfs21.0/bin")).unwrap();
+        let found = expand_pattern(&root.join("opt/hfs*/bin/houdini"));
+        assert_eq!(found.len(), 3);
+        assert_eq!(
+            found.last(),
+            Some(&root.join("opt/hfs20.0.506/bin/houdini"))
+        );
+
+        let houdini = root.join("opt/hfs20.0.506/bin/houdini");
+        let detected: Vec<(&'static [&'static str], PathBuf)> =
+            vec![(&["houdini", "houdinifx"], houdini.clone())];
+        assert_eq!(executable_for(&detected, "Houdini FX"), Some(&houdini));
+        assert_eq!(executable_for(&detected, "nuke"), None);
+
+        let templates_dir = root.join("templates");
+        for dir in ["houdini", "nuke"] {
+            std::fs::create_dir_all(templates_dir.join(dir)).unwrap();
+        }
+        std::fs::write(
+            templates_dir.join("houdini/app.yaml"),
+            "name: Houdini\nextension: .hip\ntemplate_path: template.hip\n",
+        )
+        .unwrap();
+        std::fs::write(
+            templates_dir.join("nuke/app.yaml"),
+            "name: nuke\nextension: .nk\ntemplate_path: template.nk\n",
+        )
+        .unwrap();
+        assert_eq!(
+            fill_executables(&templates_dir, &detected).unwrap(),
+            vec![String::from("Houdini")]
+        );
+        let dirs = scan_templates(&templates_dir).unwrap();
+        assert_eq!(dirs[0].dcc.as_ref().unwrap().executable, Some(houdini));
+        assert_eq!(dirs[1].dcc.as_ref().unwrap().executable, None);
+        // A set executable is kept.
+        assert!(fill_executables(&templates_dir, &detected)
+            .unwrap()
+            .is_empty());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_app_version() {
+        let nuke = |version: Option<&str>| Dcc {
+            name: String::from("nuke"),
+            extension: String::from(".nk"),
+            template_path: PathBuf::new(),
+            substitute_tokens: false,
+            executable: None,
+            version: version.map(String::from),
+            autosave_dirs: Vec::new(),
+        };
+        assert!(!nuke(None).is_older_than("15.1v2"));
+        assert!(nuke(Some("14")).is_older_than("15.1v2"));
+        assert!(!nuke(Some("15")).is_older_than("15.1v2"));
+        assert!(nuke(Some("15.0")).is_older_than("15.1v2"));
+        assert!(!nuke(Some("15.1")).is_older_than("15.1v2"));
+        assert!(!nuke(Some("15.1")).is_older_than("14.0v5"));
+        assert!(!nuke(Some("15")).is_older_than("unknown"));
+    }
+
+    #[test]
+    fn test_autosaves() {
+        let root = test_path("autosaves");
+        let task_dir = root.join("sh010/comp");
+        let autosave_dir = root.join("autosave");
+        std::fs::create_dir_all(&task_dir).unwrap();
+        std::fs::create_dir_all(&autosave_dir).unwrap();
+        let file = |version: u32| File {
+            name: String::from("comp"),
+            path: task_dir.join(format!("comp_v{:03}.nk", version)),
+            extension: String::from("nk"),
+            version,
+            prefix: String::from("comp_v"),
+            suffix: String::new(),
+            padding: 3,
+        };
+        let files = vec![file(1), file(2)];
+        for f in &files {
+            std::fs::write(&f.path, "").unwrap();
+        }
+        std::fs::write(task_dir.join("comp_v001.nk.autosave"), "recovered").unwrap();
+        std::fs::write(task_dir.join("notes.txt"), "").unwrap();
+        std::fs::write(autosave_dir.join("comp_v002.nk.autosave"), "").unwrap();
+        std::fs::write(autosave_dir.join("other_v001.nk.autosave"), "").unwrap();
+
+        std::env::set_var("RCLAMP_TEST_AUTOSAVE", &autosave_dir);
+        assert_eq!(
+            expand_dir(&PathBuf::from("$RCLAMP_TEST_AUTOSAVE/nuke")),
+            Some(autosave_dir.join("nuke"))
+        );
+        assert_eq!(expand_dir(&PathBuf::from("$RCLAMP_TEST_UNSET")), None);
+
+        let nuke = Dcc {
+            name: String::from("nuke"),
+            extension: String::from(".nk"),
+            template_path: PathBuf::new(),
+            substitute_tokens: false,
+            executable: None,
+            version: None,
+            autosave_dirs: vec![PathBuf::from("$RCLAMP_TEST_AUTOSAVE")],
+        };
+        let mut found: Vec<PathBuf> = find_autosaves(std::slice::from_ref(&nuke), &files)
+            .into_iter()
+            .map(|a| a.path)
+            .collect();
+        found.sort();
+        assert_eq!(
+            found,
+            vec![
+                autosave_dir.join("comp_v002.nk.autosave"),
+                task_dir.join("comp_v001.nk.autosave"),
+            ]
+        );
+
+        let autosaves = find_autosaves(&[], &files);
+        assert!(autosaves.is_empty());
+        let nuke = Dcc {
+            autosave_dirs: Vec::new(),
+            ..nuke
+        };
+        let autosaves = find_autosaves(&[nuke], &files);
+        assert_eq!(autosaves.len(), 1);
+        assert_eq!(autosaves[0].workfile, file(2));
+        let recovered = autosaves[0].adopt().unwrap();
+        assert_eq!(recovered, task_dir.join("comp_v003.nk"));
+        assert_eq!(std::fs::read_to_string(&recovered).unwrap(), "recovered");
+        assert!(autosaves[0].adopt().is_err());
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_retention() {
+        let root = test_path("retention");
+        let work_dir = root.join("sh010/comp/work");
+        std::fs::create_dir_all(&work_dir).unwrap();
+        let file = |version: u32| File {
+            name: String::from("comp"),
+            path: work_dir.join(format!("comp_v{:03}.nk", version)),
+            extension: String::from("nk"),
+            version,
+            prefix: String::from("comp_v"),
+            suffix: String::new(),
+            padding: 3,
+        };
+        let files = vec![file(1), file(2), file(3)];
+        let group = &FileGroup::group(&files)[0];
+
+        let policy = RetentionPolicy::default();
+        assert_eq!(policy.check(group, &file(3), false), Err(Retained::Latest));
+        assert_eq!(policy.check(group, &file(2), false), Ok(()));
+        assert_eq!(
+            policy.check(group, &file(1), true),
+            Err(Retained::Published)
+        );
+        let policy = RetentionPolicy {
+            keep_latest: 2,
+            keep_published: false,
+        };
+        assert_eq!(policy.check(group, &file(2), false), Err(Retained::Latest));
+        assert_eq!(policy.check(group, &file(1), true), Ok(()));
+        // The latest is kept even if the policy says none.
+        let policy = RetentionPolicy {
+            keep_latest: 0,
+            keep_published: false,
+        };
+        assert_eq!(policy.check(group, &file(3), false), Err(Retained::Latest));
+
+        std::fs::write(&file(1).path, "v1").unwrap();
+        let item = file(1).move_to_trash(&root).unwrap();
+        assert!(!file(1).path.exists());
+        assert_eq!(item.kind, TrashKind::File);
+        Trash::new(&root).restore(&item).unwrap();
+        assert_eq!(std::fs::read_to_string(&file(1).path).unwrap(), "v1");
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_trash_restore_and_purge() {
+        let root = test_path("trash");
+        std::fs::create_dir_all(root.join("comp")).unwrap();
+        std::fs::write(root.join("comp/notes.txt"), "keep").unwrap();
+        let trash = Trash::new(&root);
+
+        let item = trash.put(&root.join("comp"), TrashKind::Task).unwrap();
+        assert_eq!(trash.list().unwrap(), vec![item.clone()]);
+        assert!(trash.restore(&item).is_ok());
+        assert_eq!(
+            std::fs::read_to_string(root.join("comp/notes.txt")).unwrap(),
+            "keep"
+        );
+        assert!(trash.list().unwrap().is_empty());
+
+        let item = trash.put(&root.join("comp"), TrashKind::Task).unwrap();
+        // Metadata edited on the share can't point at the trash itself or outside it.
+        for id in ["", "..", "../..", "a/b"] {
+            let tampered = TrashItem {
+                id: String::from(id),
+                ..item.clone()
+            };
+            assert!(trash.purge(&tampered).is_err());
+            assert!(trash.restore(&tampered).is_err());
+        }
+        let tampered = TrashItem {
+            name: String::from("../../comp"),
+            ..item.clone()
+        };
+        assert!(trash.restore(&tampered).is_err());
+        // Nor be restored anywhere but in the project.
+        for original in [
+            std::env::temp_dir().join("comp"),
+            root.join("../comp"),
+            trash.path.join("comp"),
+            root.clone(),
+        ] {
+            let tampered = TrashItem {
+                original_path: original,
+                ..item.clone()
+            };
+            assert!(trash.restore(&tampered).is_err());
+        }
+        assert!(trash.path.join(&item.id).join("comp").exists());
+
+        trash.purge(&item).unwrap();
+        assert!(trash.list().unwrap().is_empty());
+        assert!(trash.path.exists() && !root.join("comp").exists());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_roles() {
+        assert_eq!(Role::parse("Editor"), Role::Editor);
+        assert_eq!(Role::parse(" viewer "), Role::Viewer);
+        // Typos don't allow writes.
+        assert_eq!(Role::parse("edtor"), Role::Viewer);
+        assert!(Role::Editor.can_edit());
+        assert!(!Role::Viewer.can_edit());
+        assert_eq!(
+            serde_yaml::from_str::<Role>("viewer").unwrap(),
+            Role::Viewer
+        );
+
+        std::env::remove_var(ROLE_ENV_VAR);
+        assert_eq!(Role::from_env(Role::Viewer), Role::Viewer);
+        std::env::set_var(ROLE_ENV_VAR, "");
+        assert_eq!(Role::from_env(Role::Editor), Role::Editor);
+        std::env::set_var(ROLE_ENV_VAR, "viewer");
+        assert_eq!(Role::from_env(Role::Editor), Role::Viewer);
+        std::env::remove_var(ROLE_ENV_VAR);
+    }
+
+    #[test]
+    fn test_project_access() {
+        let access: ProjectAccess =
+            serde_yaml::from_str("owners: [Anna]\nmembers: ['@comp']\nviewers: [client]").unwrap();
+        let comp = vec![String::from("STUDIO\\Comp")];
+        assert_eq!(access.access("anna", &[]), Access::Owner);
+        // Windows groups also match without the domain.
+        assert_eq!(access.access("bo", &comp), Access::Member);
+        assert_eq!(access.access("client", &[]), Access::Viewer);
+        assert_eq!(access.access("eve", &[]), Access::None);
+        assert_eq!(access.access("", &[]), Access::None);
+        assert!(Access::Owner > Access::Member && Access::Viewer > Access::None);
+        // Projects without an access list are open to everyone.
+        assert_eq!(project_access(None, "eve", &[]), Access::Owner);
+
+        let projects_dir = test_path("project_access");
+        std::fs::create_dir_all(&projects_dir).unwrap();
+        let mut project = test_project("show", &projects_dir);
+        let file = project
+            .get_path(&projects_dir)
+            .join("02_work/shot/01_work/shot_v001.nk");
+        std::fs::create_dir_all(file.parent().unwrap()).unwrap();
+        project.save(&projects_dir).unwrap();
+        assert!(check_write_access(&file).is_ok());
+
+        project.access = Some(ProjectAccess {
+            owners: vec![String::from("@rclamp_test_nobody")],
+            ..Default::default()
+        });
+        project.save(&projects_dir).unwrap();
+        assert!(check_write_access(&file).is_err());
+        std::fs::remove_dir_all(&projects_dir).unwrap();
+    }
+
+    #[test]
+    fn test_crash_reports() {
+        let lines = vec![LogLine {
+            time: 86400 + 3600 + 62,
+            level: log::Level::Error,
+            target: String::from("rclamp::app"),
+            message: String::from("Error finding DCC:s"),
+        }];
+        assert_eq!(
+            lines[0].to_string(),
+            "1970-01-02 01:01:02 ERROR rclamp::app: Error finding DCC:s"
+        );
+        let text = report_text(0, "boom at src/app.rs:1", None, &lines, "");
+        assert!(text.contains("boom at src/app.rs:1"));
+        assert!(text.contains("State:\nNot set."));
+        assert!(text.contains("Error finding DCC:s"));
+
+        let dir = test_path("crash_reports");
+        assert_eq!(take_pending_report(&dir), None);
+        for time in 1..=12 {
+            write_report(&dir, time, &text).unwrap();
+        }
+        // Only the latest ten are kept.
+        let reports = list_reports(&dir);
+        assert_eq!(reports.len(), 10);
+        assert_eq!(reports[0].0, 3);
+        // The latest is offered once.
+        assert_eq!(take_pending_report(&dir), Some(dir.join("crash_12.txt")));
+        assert_eq!(take_pending_report(&dir), None);
+        write_report(&dir, 13, &text).unwrap();
+        assert_eq!(take_pending_report(&dir), Some(dir.join("crash_13.txt")));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_log_lines() {
+        clear_lines();
+        for i in 0..LOG_CAPACITY + 5 {
+            push_line(LogLine {
+                time: 0,
+                level: log::Level::Info,
+                target: String::from("rclamp"),
+                message: i.to_string(),
+            });
+        }
+        // The oldest are dropped.
+        let lines = recent_lines();
+        assert_eq!(lines.len(), LOG_CAPACITY);
+        assert_eq!(lines[0].message, "5");
+        assert_eq!(
+            format_lines(&lines[..2]),
+            "1970-01-01 00:00:00 INFO  rclamp: 5\n1970-01-01 00:00:00 INFO  rclamp: 6\n"
+        );
+        clear_lines();
+        assert!(recent_lines().is_empty());
+    }
+
+    #[test]
+    fn test_memory_fs() {
+        let fs = MemoryFs::new();
+        let projects_dir = PathBuf::from("/projects");
+        let templates_dir = PathBuf::from("/templates");
+        fs.add_file(&templates_dir.join("pipeline/hooks/hooks.yaml"), "[]");
+        fs.add_file(
+            &templates_dir.join("nuke/app.yaml"),
+            "name: nuke\nextension: .nk\ntemplate_path: ''\nsubstitute_tokens: true",
+        );
+        fs.add_file(&templates_dir.join("nuke/template.nk"), "{PROJECT}");
+        fs.add_file(&templates_dir.join("broken/app.yaml"), "name: [");
+        fs.create_dir_all(&projects_dir).unwrap();
+
+        let project = test_project("show", &projects_dir);
+        project
+            .create_with(&fs, projects_dir.clone(), &templates_dir)
+            .unwrap();
+        assert!(fs.is_file(
+            &project
+                .get_pipeline_path(&projects_dir)
+                .join("hooks/hooks.yaml")
+        ));
+        assert_eq!(
+            Project::find_projects_with(&fs, &projects_dir).unwrap(),
+            vec![project.clone()]
+        );
+
+        // A failed creation is rolled back.
+        let mut broken = project.clone();
+        broken.name = String::from("broken");
+        broken.extra_dir_names = vec![String::from("missing/nested")];
+        assert!(broken
+            .create_with(&fs, projects_dir.clone(), &templates_dir)
+            .is_err());
+        assert!(!fs.exists(&projects_dir.join("broken")));
+
+        let work_path = project.get_work_path(&projects_dir);
+        let root = TaskTreeNode::from_path_with(&fs, work_path.clone(), "01_work", "02_output", 0)
+            .unwrap();
+        root.create_folder_with(&fs, String::from("sh010")).unwrap();
+        let folder = TaskTreeNode::new(
+            String::from("sh010"),
+            work_path.join("sh010"),
+            "01_work",
+            "02_output",
+        );
+        folder
+            .create_task_with(&fs, String::from("comp"), project.clone())
+            .unwrap();
+        let tree = TaskTreeNode::from_path_with(&fs, work_path, "01_work", "02_output", 0).unwrap();
+        let task = tree.tasks()[0].clone();
+        assert_eq!(task.name, "comp");
+        assert!(fs.is_dir(&task.get_work_path()));
+
+        let dccs = Dcc::find_dcc_with(&fs, &templates_dir).unwrap();
+        assert_eq!(dccs.len(), 1);
+        let template = FilenameTemplate::default();
+        File::create_file_with(
+            &fs,
+            String::from("comp"),
+            task.clone(),
+            project.clone(),
+            dccs[0].clone(),
+            &template,
+        )
+        .unwrap();
+        let ignore = IgnorePatterns::default();
+        let files = task
+            .find_workfiles_with(&fs, String::from("01_work"), &template, &ignore)
+            .unwrap();
+        assert_eq!(files.len(), 1);
+        assert_eq!(fs.read_to_string(&files[0].path).unwrap(), "show");
+        files[0].version_up_with(&fs).unwrap();
+        assert!(files[0].version_up_with(&fs).is_err());
+        let files = task
+            .find_workfiles_with(&fs, String::from("01_work"), &template, &ignore)
+            .unwrap();
+        assert_eq!(
+            files.iter().map(|f| f.version).collect::<Vec<_>>(),
+            vec![1, 2]
+        );
+
+        fs.add_file(
+            &PathBuf::from("/clients.yaml"),
+            "- name: Client\n  short_name: client",
+        );
+        let clients = Client::get_clients_with(&fs, &PathBuf::from("/clients.yaml")).unwrap();
+        assert_eq!(clients[0].short_name, "client");
+        assert!(Client::get_clients_with(&fs, &PathBuf::from("/missing.yaml")).is_err());
+    }
+
+    #[test]
+    fn test_parallel_map() {
+        let items: Vec<usize> = (0..MAX_SCAN_THREADS * 5).collect();
+        let doubled = parallel_map(&items, |i| i * 2);
+        assert_eq!(doubled, items.iter().map(|i| i * 2).collect::<Vec<_>>());
+        assert!(parallel_map(&[] as &[usize], |i| *i).is_empty());
+        assert_eq!(parallel_map(&[1], |i| i + 1), vec![2]);
+    }
+
+    #[test]
+    fn test_scan_cache() {
+        let fs = MemoryFs::new();
+        let projects_dir = PathBuf::from("/projects");
+        let mut project = test_project("show", &projects_dir);
+        let project_file = projects_dir.join("show/project.yaml");
+        fs.add_file(&project_file, &serde_yaml::to_string(&project).unwrap());
+        fs.add_file(&projects_dir.join("notes.txt"), "");
+
+        let mut cache = ScanCache::default();
+        assert_eq!(
+            cache.projects(&fs, &projects_dir).unwrap(),
+            vec![project.clone()]
+        );
+
+        // A changed project file is read again, and a new project dir is found.
+        project.status = Some(String::from("archived"));
+        fs.write(
+            &project_file,
+            serde_yaml::to_string(&project).unwrap().as_bytes(),
+        )
+        .unwrap();
+        let mut other = project.clone();
+        other.name = String::from("other");
+        fs.add_file(
+            &projects_dir.join("other/project.yaml"),
+            &serde_yaml::to_string(&other).unwrap(),
+        );
+        assert_eq!(
+            cache.projects(&fs, &projects_dir).unwrap(),
+            vec![other.clone(), project.clone()]
+        );
+
+        let work_path = projects_dir.join("show/02_work");
+        fs.add_file(&work_path.join("sh010/comp/task.yaml"), "name: comp");
+        let names = |tree: &TaskTreeNode| -> Vec<String> {
+            tree.tasks().iter().map(|t| t.name.clone()).collect()
+        };
+        let tree = cache
+            .task_tree(&fs, &work_path, "01_work", "02_output")
+            .unwrap();
+        assert_eq!(names(&tree), vec!["comp"]);
+
+        fs.add_file(&work_path.join("sh010/lgt/task.yaml"), "name: lgt");
+        fs.remove_file(&work_path.join("sh010/comp/task.yaml"))
+            .unwrap();
+        let tree = cache
+            .task_tree(&fs, &work_path, "01_work", "02_output")
+            .unwrap();
+        assert_eq!(names(&tree), vec!["lgt"]);
+
+        let path = test_path("scan_cache.json");
+        cache.save(&path).unwrap();
+        let mut loaded = ScanCache::load(&path);
+        assert_eq!(
+            loaded.projects(&fs, &projects_dir).unwrap(),
+            vec![other, project]
+        );
+        let tree = loaded
+            .task_tree(&fs, &work_path, "01_work", "02_output")
+            .unwrap();
+        assert_eq!(names(&tree), vec!["lgt"]);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_commands() {
+        let dir = test_path("commands");
+        std::fs::create_dir_all(&dir).unwrap();
+        let project = test_project("show", &dir);
+        let parent = TaskTreeNode::new(String::from("sh010"), dir.clone(), "01_work", "02_output");
+        let context = CommandContext::default();
+
+        let create = Command::CreateTask {
+            parent: parent.clone(),
+            name: String::from("comp"),
+            project: Box::new(project.clone()),
+        };
+        assert_eq!(
+            handle(&create, &context),
+            vec![Event::TaskCreated(dir.join("comp"))]
+        );
+        assert!(matches!(handle(&create, &context)[..], [Event::Failed(_)]));
+
+        let path = dir.join("comp/01_work/show_comp_main_v001_wip.nk");
+        std::fs::write(&path, "").unwrap();
+        let file = File::from_path(path, &FilenameTemplate::default()).unwrap();
+        assert_eq!(
+            handle(&Command::VersionUp(file), &context),
+            vec![Event::VersionedUp(
+                2,
+                dir.join("comp/01_work/show_comp_main_v002_wip.nk")
+            )]
+        );
+
+        // Post-hooks are only queued when the pipeline has scripts for them.
+        let context = CommandContext {
+            project: Some(project.clone()),
+            projects_dir: Some(dir.clone()),
+            ..Default::default()
+        };
+        assert!(!context.has_hooks(HookEvent::PostFileOpen));
+        let pipeline = project.get_pipeline_path(&dir);
+        std::fs::create_dir_all(&pipeline).unwrap();
+        std::fs::write(pipeline.join("hooks.yaml"), "post_file_open: [notify.sh]\n").unwrap();
+        assert!(context.has_hooks(HookEvent::PostFileOpen));
+        assert!(!context.has_hooks(HookEvent::PreFileOpen));
+        std::fs::write(pipeline.join("hooks.yaml"), "post_file_open: {").unwrap();
+        assert!(context.has_hooks(HookEvent::PreFileOpen));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_pipeline() {
+        let projects_dir = test_path("pipeline");
+        std::fs::create_dir_all(&projects_dir).unwrap();
+        let project = test_project("show", &projects_dir);
+        let store = ProjectStore::new(PipelineContext::new(
+            projects_dir.clone(),
+            projects_dir.join("templates"),
+            project.clone(),
+        ));
+        store.create(&project).unwrap();
+        assert_eq!(store.find("show").unwrap(), Some(project.clone()));
+        assert_eq!(store.find("other").unwrap(), None);
+
+        let tasks = store.tasks(&project);
+        std::fs::create_dir_all(tasks.work_path().join("sh010")).unwrap();
+        let mut minimal = project.clone();
+        minimal.work_sub_dirs.truncate(1);
+        assert_eq!(store.context().output_dir_name(&minimal), "02_output");
+        minimal.work_sub_dirs.clear();
+        minimal.task_output_dir_name = String::from("renders");
+        assert_eq!(minimal.task_sub_dirs(), vec!["01_work", "renders"]);
+        assert!(minimal.check_task_dirs().is_ok());
+        minimal.task_output_dir_name = String::from("01_work");
+        assert!(minimal.check_task_dirs().is_err());
+        minimal.task_output_dir_name = String::from("out/renders");
+        assert!(minimal.check_task_dirs().is_err());
+        let task = tasks.create(Path::new("sh010"), "comp").unwrap();
+        assert_eq!(
+            tasks.find(Path::new("sh010/comp")).unwrap().map(|t| t.path),
+            Some(task.path.clone())
+        );
+
+        let workfiles = tasks.workfiles(&task);
+        for version in ["v001", "v002"] {
+            let name = format!("show_comp_main_{}_wip.nk", version);
+            std::fs::write(task.get_work_path().join(name), "").unwrap();
+        }
+        assert_eq!(workfiles.files().unwrap().len(), 2);
+        let latest = workfiles.latest().unwrap();
+        assert_eq!(latest.len(), 1);
+        assert_eq!(
+            workfiles.version_up(&latest[0]).unwrap(),
+            task.get_work_path().join("show_comp_main_v003_wip.nk")
+        );
+        std::fs::remove_dir_all(&projects_dir).unwrap();
+    }
+
+    #[test]
+    fn test_check_setup() {
+        let dir = test_path("check_setup");
+        let mut project = Project::new(
+            String::new(),
+            dir.clone(),
+            String::from("00_pipeline"),
+            String::from("02_work"),
+            String::from(""),
+            String::from("04_deliveries"),
+            Vec::new(),
+            vec![String::from("01_work"), String::from("02_output")],
+        );
+        let problems = check_setup(&dir, &project);
+        assert_eq!(problems.len(), 2);
+        assert!(problems[0].starts_with("dailies_dir_name"));
+
+        project.dailies_dir_name = String::from("03_dailies");
+        std::fs::create_dir_all(dir.join("nuke")).unwrap();
+        std::fs::write(dir.join("nuke/app.yaml"), "name: Nuke\nextension: .nk\n").unwrap();
+        assert_eq!(
+            check_setup(&dir, &project),
+            vec![String::from(
+                "nuke: app.yaml doesn't parse: missing field `template_path`"
+            )]
+        );
+
+        std::fs::write(
+            dir.join("nuke/app.yaml"),
+            "name: Nuke\nextension: .nk\ntemplate_path: template.nk\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("nuke/template.nk"), "").unwrap();
+        assert!(check_setup(&dir, &project).is_empty());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_duplicate_project() {
+        let projects_dir = test_path("duplicate_project");
+        std::fs::create_dir_all(&projects_dir).unwrap();
+        let mut project = test_project("show", &projects_dir);
+        project.client = String::from("acme");
+        project.status = Some(String::from("completed"));
+        let templates_dir = projects_dir.join("templates");
+        project
+            .create(projects_dir.clone(), &templates_dir)
+            .unwrap();
+        let work_path = project.get_work_path(&projects_dir);
+        std::fs::create_dir_all(work_path.join("sq010/sh010")).unwrap();
+        std::fs::create_dir_all(work_path.join("empty")).unwrap();
+        let shot = TaskTreeNode::new(
+            String::from("sh010"),
+            work_path.join("sq010/sh010"),
+            "01_work",
+            "02_output",
+        );
+        shot.create_task(String::from("comp"), project.clone())
+            .unwrap();
+        std::fs::write(
+            work_path.join("sq010/sh010/comp/01_work/show_comp_v001.nk"),
+            "",
+        )
+        .unwrap();
+
+        let copy = project.duplicate("show_2");
+        assert_eq!(copy.name_sanitized, "show_2");
+        assert_eq!(copy.client, "acme");
+        assert_eq!(copy.status, None);
+        copy.create(projects_dir.clone(), &templates_dir).unwrap();
+        let tree = TaskTreeNode::from_path(work_path, "01_work", "02_output", 0).unwrap();
+        let copy_work_path = copy.get_work_path(&projects_dir);
+        let root = TaskTreeNode::new(
+            String::new(),
+            copy_work_path.clone(),
+            "01_work",
+            "02_output",
+        );
+        let results = copy_tree(&tree, &root, &copy);
+        assert!(results.iter().all(|r| r.error.is_none()));
+        assert!(copy_work_path.join("empty").is_dir());
+        assert!(copy_work_path.join("sq010/sh010/comp/task.yaml").is_file());
+        assert!(copy_work_path.join("sq010/sh010/comp/02_output").is_dir());
+        assert!(!copy_work_path
+            .join("sq010/sh010/comp/01_work/show_comp_v001.nk")
+            .exists());
+        std::fs::remove_dir_all(&projects_dir).unwrap();
+    }
+
+    #[test]
+    fn test_client_projects() {
+        let projects_dir = test_path("client_projects");
+        let project = |name: &str, client: &str| {
+            let mut p = Project::new(
+                String::from(name),
+                projects_dir.clone(),
+                String::from("00_pipeline"),
+                String::from("02_work"),
+                String::from("03_dailies"),
+                String::from("04_deliveries"),
+                Vec::new(),
+                Vec::new(),
+            );
+            p.client = String::from(client);
+            p
+        };
+        let projects = vec![
+            project("2301_acme_spot", ""),
+            project("2302_bolt_promo", ""),
+            project("teaser", "ACME"),
+            project("2303_acme_film", ""),
+        ];
+        let deliveries = projects[3].get_deliveries_path(&projects_dir);
+        std::fs::create_dir_all(deliveries.join("240101_online")).unwrap();
+        std::fs::write(deliveries.join(".DS_Store"), "").unwrap();
+
+        let acme = client_projects(&projects, "acme", &projects_dir);
+        assert_eq!(
+            acme.iter()
+                .map(|p| p.project.name.as_str())
+                .collect::<Vec<_>>(),
+            vec!["teaser", "2303_acme_film", "2301_acme_spot"]
+        );
+        assert_eq!(acme[1].deliveries, vec![String::from("240101_online")]);
+        assert!(acme[2].deliveries.is_empty());
+        std::fs::remove_dir_all(&projects_dir).unwrap();
+    }
+
+    #[test]
+    fn test_rename() {
+        let dir = test_path("rename");
+        std::fs::create_dir_all(dir.join("sh010")).unwrap();
+        let project = test_project("show", &dir);
+        let folder = TaskTreeNode::new(
+            String::from("sh010"),
+            dir.join("sh010"),
+            "01_work",
+            "02_output",
+        );
+        folder.create_task(String::from("comp"), project).unwrap();
+        std::fs::create_dir(dir.join("sh020")).unwrap();
+
+        let taken = TaskTreeNode::new(
+            String::from("sh020"),
+            dir.join("sh020"),
+            "01_work",
+            "02_output",
+        );
+        assert!(taken.rename("sh010").is_err());
+        assert_eq!(folder.rename("sh030").unwrap(), dir.join("sh030"));
+        let tree = TaskTreeNode::from_path(dir.clone(), "01_work", "02_output", 0).unwrap();
+        let task = tree.tasks()[0].clone();
+        assert_eq!(task.path, dir.join("sh030").join("comp"));
+        let renamed = task.rename("grade").unwrap();
+        let task_file = std::fs::read_to_string(renamed.join("task.yaml")).unwrap();
+        assert!(task_file.contains("name: grade"));
+
+        let work = renamed.join("01_work");
+        let template = FilenameTemplate::default();
+        for name in ["show_grade_main_v002_wip.nk", "show_grade_alt_v002_wip.nk"] {
+            std::fs::write(work.join(name), "").unwrap();
+        }
+        let file = File::from_path(work.join("show_grade_main_v002_wip.nk"), &template).unwrap();
+        assert_eq!(
+            file.renamed_path("show_grade_clean").unwrap(),
+            work.join("show_grade_clean_v002_wip.nk")
+        );
+        assert!(file.rename("show_grade_alt").is_err());
+        assert!(file.rename("show_grade_clean").is_ok());
+        assert!(work.join("show_grade_clean_v002_wip.nk").exists());
+        assert!(!file.path.exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_project_report() {
+        let projects_dir = test_path("project_report");
+        std::fs::create_dir_all(&projects_dir).unwrap();
+        let project = test_project("show", &projects_dir);
+        let store = ProjectStore::new(PipelineContext::new(
+            projects_dir.clone(),
+            projects_dir.join("templates"),
+            project.clone(),
+        ));
+        store.create(&project).unwrap();
+        let tasks = store.tasks(&project);
+        std::fs::create_dir_all(tasks.work_path().join("sh010")).unwrap();
+        let comp = tasks.create(Path::new("sh010"), "comp").unwrap();
+        tasks.create(Path::new("sh010"), "grade").unwrap();
+        comp.set_status(String::from("wip, notes")).unwrap();
+        for name in ["show_comp_main_v001.nk", "show_comp_main_v002.nk"] {
+            std::fs::write(comp.get_work_path().join(name), "").unwrap();
+        }
+
+        let rows = report_rows(store.context(), &project).unwrap();
+        assert_eq!(rows.len(), 2);
+        assert_eq!(rows[0].task, "sh010/comp");
+        assert_eq!((rows[0].workfiles, rows[0].versions), (1, 2));
+        assert_eq!((rows[1].workfiles, rows[1].versions), (0, 0));
+        let csv = report_csv(&rows);
+        assert!(csv.starts_with("task,status,due date,workfiles,versions\n"));
+        assert!(csv.contains("sh010/comp,\"wip, notes\",,1,2\n"));
+        let path = write_project_report(store.context(), &project).unwrap();
+        assert!(path.starts_with(store.context().pipeline_path(&project)));
+        assert_eq!(std::fs::read_to_string(path).unwrap(), csv);
+        std::fs::remove_dir_all(&projects_dir).unwrap();
+    }
+
+    #[test]
+    fn test_compare_versions() {
+        let old = "a\nb\nc\nd\ne\nf\ng\nh\n";
+        let new = "a\nb\nc\nd\nE\nf\ng\nh\ni\n";
+        let same = |l: &str| DiffLine::Same(String::from(l));
+        assert_eq!(
+            diff_lines(old, new, 1).unwrap(),
+            vec![
+                DiffLine::Skipped(3),
+                same("d"),
+                DiffLine::Removed(String::from("e")),
+                DiffLine::Added(String::from("E")),
+                same("f"),
+                DiffLine::Skipped(1),
+                same("h"),
+                DiffLine::Added(String::from("i")),
+            ]
+        );
+        assert_eq!(diff_lines(old, old, 2).unwrap(), vec![DiffLine::Skipped(8)]);
+        assert_eq!(
+            diff_lines("", "x", 0).unwrap(),
+            vec![DiffLine::Added(String::from("x"))]
+        );
+        let long: String = (0..3000).map(|i| format!("{}\n", i)).collect();
+        let other: String = (0..3000).map(|i| format!("{}\n", i * 2)).collect();
+        assert_eq!(diff_lines(&long, &other, 3), None);
+
+        assert!(is_text_workfile("NK"));
+        assert!(!is_text_workfile("mb"));
+        let path = test_path("compare.nk");
+        std::fs::write(&path, old).unwrap();
+        assert_eq!(FileStats::read(&path).unwrap().size, 16);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_freeze_publish() {
+        let task_dir = test_path("freeze");
+        std::fs::create_dir_all(task_dir.join("01_work")).unwrap();
+        std::fs::write(task_dir.join("task.yaml"), "name: comp\n").unwrap();
+        let path = task_dir.join("01_work").join("show_comp_v001.nk");
+        std::fs::write(&path, "").unwrap();
+        let file = File::from_path(path.clone(), &FilenameTemplate::default()).unwrap();
+
+        let publish = Publish::register(&path, "master").unwrap();
+        assert!(Publish::find_frozen(&path).is_none());
+        let frozen = publish.set_frozen(&task_dir, true).unwrap();
+        assert!(frozen.frozen.is_some());
+        assert!(Publish::find_frozen(&path).is_some());
+        assert!(std::fs::metadata(&path).unwrap().permissions().readonly());
+        let error = file.version_up().unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::PermissionDenied);
+        assert!(file.rename("other").is_err());
+
+        frozen.set_frozen(&task_dir, false).unwrap();
+        assert!(Publish::find_frozen(&path).is_none());
+        assert!(!std::fs::metadata(&path).unwrap().permissions().readonly());
+        assert!(file.version_up().unwrap().exists());
+        std::fs::remove_dir_all(&task_dir).unwrap();
+    }
+
+    #[test]
+    fn test_create_permissions() {
+        let permissions = |umask: &str| CreatePermissions {
+            umask: Some(String::from(umask)),
+            ..Default::default()
+        };
+        assert_eq!(permissions("027").umask_bits(), Ok(Some(0o027)));
+        assert_eq!(permissions("0o002").umask_bits(), Ok(Some(0o002)));
+        assert_eq!(permissions(" ").umask_bits(), Ok(None));
+        assert!(permissions("089").umask_bits().is_err());
+        assert!(permissions("1777").umask_bits().is_err());
+
+        let dir = test_path("permissions");
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("show_comp_v001.nk");
+        std::fs::write(&file, "").unwrap();
+        CreatePermissions::default()
+            .apply(&file, Created::File)
+            .unwrap();
+        assert!(!std::fs::metadata(&file).unwrap().permissions().readonly());
+
+        let read_only = CreatePermissions {
+            read_only_files: true,
+            ..Default::default()
+        };
+        read_only.apply(&dir, Created::Dir).unwrap();
+        assert!(!std::fs::metadata(&dir).unwrap().permissions().readonly());
+        // Workfiles are kept writable, only outputs are read-only.
+        read_only.apply(&file, Created::File).unwrap();
+        assert!(!std::fs::metadata(&file).unwrap().permissions().readonly());
+        {
+            let _lock = CREATE_PERMISSIONS_LOCK.lock().unwrap();
+            set_create_permissions(&read_only);
+            let versioned = File::from_path(file.clone(), &FilenameTemplate::default())
+                .unwrap()
+                .version_up();
+            let output = dir.join("out");
+            let copied = OutputItem::File {
+                path: file.clone(),
+                size: 0,
+            }
+            .copy_to(&output, |_done, _total| true);
+            set_create_permissions(&CreatePermissions::default());
+            let versioned = versioned.unwrap();
+            assert!(!std::fs::metadata(&versioned)
+                .unwrap()
+                .permissions()
+                .readonly());
+            copied.unwrap();
+            let output = output.join("show_comp_v001.nk");
+            assert!(std::fs::metadata(&output).unwrap().permissions().readonly());
+        }
+        read_only.apply(&file, Created::Output).unwrap();
+        assert!(std::fs::metadata(&file).unwrap().permissions().readonly());
+
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            let mode = |p: &std::path::Path| std::fs::metadata(p).unwrap().permissions().mode();
+            permissions("027").apply(&dir, Created::Dir).unwrap();
+            assert_eq!(mode(&dir) & 0o777, 0o750);
+            permissions("027").apply(&file, Created::File).unwrap();
+            assert_eq!(mode(&file) & 0o777, 0o640);
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_mapped_drives() {
+        let output = "New connections will be remembered.\n\n\
+            Status       Local     Remote                    Network\n\
+            -------------------------------------------------------------------------------\n\
+            OK           p:        \\\\server\\projects         Microsoft Windows Network\n\
+            Unavailable  R:        \\\\server\\renders          Microsoft Windows Network\n\
+            OK                     \\\\server\\ipc$             Microsoft Windows Network\n\
+            The command completed successfully.\n";
+        let detected = parse_net_use(output);
+        assert_eq!(detected.len(), 2);
+        assert_eq!(detected[0].windows, "P:\\");
+        assert_eq!(detected[0].unc, "\\\\server\\projects");
+        assert_eq!(detected[1].windows, "R:\\");
+
+        let configured = vec![PathMapping {
+            windows: String::from("P:"),
+            mac: String::from("/Volumes/projects"),
+            ..Default::default()
+        }];
+        let mappings = with_detected(&configured, detected);
+        assert_eq!(mappings.len(), 2);
+        assert_eq!(mappings[0], configured[0]);
+        assert_eq!(mappings[1].unc, "\\\\server\\renders");
+
+        let unc = PathBuf::from("\\\\SERVER\\renders\\2301_spot\\comp.exr");
+        assert!(is_unc(&unc));
+        assert!(!is_unc(Path::new("P:\\2301_spot")));
+        assert_eq!(
+            map_path(&unc, &mappings, PathStyle::Windows),
+            Some(String::from("R:\\2301_spot\\comp.exr"))
+        );
+        assert_eq!(
+            map_path(Path::new("r:\\2301_spot"), &mappings, PathStyle::Unc),
+            Some(String::from("\\\\server\\renders\\2301_spot"))
+        );
+    }
+
+    #[test]
+    fn test_offline_mode() {
+        let fs = MemoryFs::new();
+        let projects_dir = PathBuf::from("/projects");
+        let project = test_project("show", &projects_dir);
+        fs.add_file(
+            &projects_dir.join("show/project.yaml"),
+            &serde_yaml::to_string(&project).unwrap(),
+        );
+        let work_path = projects_dir.join("show/02_work");
+        fs.add_file(&work_path.join("sh010/comp/task.yaml"), "name: comp");
+        let mut cache = ScanCache::default();
+        assert!(cache.cached_projects(&projects_dir).is_none());
+        cache.projects(&fs, &projects_dir).unwrap();
+        cache
+            .task_tree(&fs, &work_path, "01_work", "02_output")
+            .unwrap();
+        assert_eq!(
+            cache.cached_projects(&projects_dir),
+            Some(vec![project.clone()])
+        );
+        assert!(cache.cached_projects(Path::new("/other")).is_none());
+        assert_eq!(cache.cached_tree(&work_path).unwrap().tasks().len(), 1);
+
+        let dir = test_path("offline");
+        assert!(!is_reachable(&dir));
+        let mut connectivity = Connectivity::default();
+        assert!(!connectivity.poll(&dir, 0));
+        connectivity.set_offline(&dir, 100);
+        connectivity.queue(Command::VersionUp(
+            File::from_path(dir.join("show_comp_v001.nk"), &FilenameTemplate::default()).unwrap(),
+        ));
+        assert!(!connectivity.poll(&dir, 101));
+
+        std::fs::create_dir_all(&dir).unwrap();
+        assert!(is_reachable(&dir));
+        let mut now = 100 + RECONNECT_INTERVAL;
+        while !connectivity.poll(&dir, now) {
+            std::thread::sleep(std::time::Duration::from_millis(10));
+            now += 1;
+            assert!(now < 1000, "the dir was never found");
+        }
+        assert!(!connectivity.is_offline());
+        assert_eq!(connectivity.take_queued().len(), 1);
+        assert!(connectivity.queued().is_empty());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_retry_policy() {
+        let policy = RetryPolicy {
+            attempts: 3,
+            initial_delay_ms: 100,
+            max_delay_ms: 250,
+        };
+        assert_eq!(policy.delay(0).as_millis(), 100);
+        assert_eq!(policy.delay(1).as_millis(), 200);
+        assert_eq!(policy.delay(2).as_millis(), 250);
+        assert_eq!(policy.delay(100).as_millis(), 250);
+
+        let timeout = || std::io::Error::new(std::io::ErrorKind::TimedOut, "timed out");
+        assert!(is_transient(&timeout()));
+        assert!(!is_transient(&std::io::Error::new(
+            std::io::ErrorKind::NotFound,
+            "missing"
+        )));
+
+        let policy = RetryPolicy {
+            initial_delay_ms: 0,
+            ..policy
+        };
+        let mut calls = 0;
+        let result = policy.run(|| {
+            calls += 1;
+            if calls < 3 {
+                Err(timeout())
+            } else {
+                Ok(calls)
+            }
+        });
+        assert_eq!(result.unwrap(), 3);
+
+        calls = 0;
+        let result: Result<(), _> = policy.run(|| {
+            calls += 1;
+            Err(timeout())
+        });
+        assert!(result.is_err());
+        assert_eq!(calls, 3);
+
+        calls = 0;
+        let result: Result<(), _> = policy.run(|| {
+            calls += 1;
+            Err(std::io::Error::new(
+                std::io::ErrorKind::PermissionDenied,
+                "denied",
+            ))
+        });
+        assert!(result.is_err());
+        assert_eq!(calls, 1);
+    }
+
+    #[test]
+    fn test_copy_with_progress() {
+        let dir = test_path("copy_progress");
+        std::fs::create_dir_all(&dir).unwrap();
+        let source = dir.join("show_comp_v001.nk");
+        let contents = vec![7u8; (5 << 20) / 2];
+        std::fs::write(&source, &contents).unwrap();
+
+        let mut reported = Vec::new();
+        let copy = dir.join("copy.nk");
+        let size = copy_with_progress(&source, &copy, &mut |done, total| {
+            reported.push((done, total));
+            true
+        })
+        .unwrap();
+        assert_eq!(size, contents.len() as u64);
+        assert_eq!(std::fs::read(&copy).unwrap(), contents);
+        assert_eq!(reported.first(), Some(&(0, size)));
+        assert_eq!(reported.last(), Some(&(size, size)));
+        assert!(reported.windows(2).all(|w| w[0].0 < w[1].0));
+
+        // Existing files are kept, cancelled copies removed.
+        let error = copy_with_progress(&source, &copy, &mut |_d, _t| true).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::AlreadyExists);
+        assert!(copy.exists());
+        let cancelled = dir.join("cancelled.nk");
+        let error = copy_with_progress(&source, &cancelled, &mut |done, _t| done == 0).unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::Interrupted);
+        assert!(!cancelled.exists());
+
+        let file = File::from_path(source, &FilenameTemplate::default()).unwrap();
+        let mut calls = 0;
+        let path = file
+            .version_up_with_progress(&mut |_d, _t| {
+                calls += 1;
+                true
+            })
+            .unwrap();
+        assert_eq!(path, dir.join("show_comp_v002.nk"));
+        assert!(calls > 2);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(feature = "deliveries")]
+    #[test]
+    fn test_resume_delivery() {
+        use crate::deliveries::{find_interrupted, DeliveryManifest, DeliveryPackage};
+        let _lock = CREATE_PERMISSIONS_LOCK.lock().unwrap();
+
+        let root = test_path("resume_delivery");
+        let outputs = root.join("02_output");
+        std::fs::create_dir_all(&outputs).unwrap();
+        let items: Vec<OutputItem> = ["a.mov", "b.mov", "c.mov"]
+            .iter()
+            .map(|n| {
+                let path = outputs.join(n);
+                std::fs::write(&path, n.repeat(100)).unwrap();
+                OutputItem::File { path, size: 500 }
+            })
+            .collect();
+        let package = DeliveryPackage {
+            name: String::from("spot_v001"),
+            items,
+            timeline: None,
+            rate: 24,
+            ffmpeg: None,
+            archive: None,
+        };
+        let deliveries = root.join("04_deliveries");
+        let run = |dir: &std::path::Path| {
+            let mut jobs = JobQueue::new(1);
+            let (package, dir) = (package.clone(), dir.to_path_buf());
+            jobs.push("package", move |job| package.package(&dir, job));
+            loop {
+                if let Some(status) = jobs.finished().pop() {
+                    return status.state;
+                }
+                std::thread::sleep(std::time::Duration::from_millis(10));
+            }
+        };
+
+        let dir = deliveries.join("2026-03-05");
+        assert!(matches!(run(&dir), JobState::Done(_)));
+        let package_dir = dir.join("spot_v001");
+        let manifest = DeliveryManifest::load(&package_dir).unwrap();
+        assert!(manifest.complete);
+        assert_eq!(manifest.files.len(), 3);
+        assert!(manifest.is_copied(&package_dir, "a.mov", 500));
+        assert!(find_interrupted(&deliveries, "spot_v001").is_none());
+
+        // Interrupted with b partly copied and c missing.
+        let mut interrupted = manifest.clone();
+        interrupted.complete = false;
+        interrupted.files.truncate(2);
+        std::fs::write(
+            DeliveryManifest::path(&package_dir),
+            serde_yaml::to_string(&interrupted).unwrap(),
+        )
+        .unwrap();
+        std::fs::write(package_dir.join("b.mov"), "b.m").unwrap();
+        std::fs::remove_file(package_dir.join("c.mov")).unwrap();
+        assert!(!interrupted.is_copied(&package_dir, "b.mov", 500));
+        assert_eq!(
+            find_interrupted(&deliveries, "spot_v001"),
+            Some(dir.clone())
+        );
+
+        assert!(matches!(run(&dir), JobState::Done(_)));
+        assert_eq!(DeliveryManifest::load(&package_dir).unwrap(), manifest);
+        assert_eq!(
+            std::fs::read_to_string(package_dir.join("b.mov")).unwrap(),
+            "b.mov".repeat(100)
+        );
+
+        // Finished packages aren't resumed, changed sources don't overwrite the files.
+        std::fs::write(outputs.join("b.mov"), "changed").unwrap();
+        assert!(matches!(run(&dir), JobState::Failed(_)));
+        assert_eq!(DeliveryManifest::load(&package_dir).unwrap(), manifest);
+        assert_eq!(
+            std::fs::read_to_string(package_dir.join("b.mov")).unwrap(),
+            "b.mov".repeat(100)
+        );
+        std::fs::write(outputs.join("b.mov"), "b.mov".repeat(100)).unwrap();
+
+        // Packages without a manifest aren't overwritten.
+        let other = deliveries.join("2026-03-06");
+        std::fs::create_dir_all(other.join("spot_v001")).unwrap();
+        std::fs::write(other.join("spot_v001/a.mov"), "").unwrap();
+        assert!(matches!(run(&other), JobState::Failed(_)));
+
+        // Interrupted during the first file, a dir can't be read as one.
+        std::fs::rename(outputs.join("a.mov"), root.join("a.mov")).unwrap();
+        std::fs::create_dir(outputs.join("a.mov")).unwrap();
+        let first = deliveries.join("2026-03-07");
+        assert!(matches!(run(&first), JobState::Failed(_)));
+        assert_eq!(
+            find_interrupted(&deliveries, "spot_v001"),
+            Some(first.clone())
+        );
+        std::fs::remove_dir(outputs.join("a.mov")).unwrap();
+        std::fs::rename(root.join("a.mov"), outputs.join("a.mov")).unwrap();
+        assert!(matches!(run(&first), JobState::Done(_)));
+        let resumed = DeliveryManifest::load(&first.join("spot_v001")).unwrap();
+        assert_eq!(resumed, manifest);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_archive() {
+        let root = test_path("archive");
+        let dir = root.join("spot_v001");
+        std::fs::create_dir_all(dir.join("frames")).unwrap();
+        std::fs::create_dir_all(dir.join("empty")).unwrap();
+        std::fs::write(dir.join("notes.txt"), "final").unwrap();
+        let frame: Vec<u8> = (0..20000u32).map(|i| (i * 7919 % 251) as u8).collect();
+        std::fs::write(dir.join("frames/spot.1001.exr"), &frame).unwrap();
+
+        let settings = ArchiveSettings {
+            split_size_mb: 1,
+            ..ArchiveSettings::default()
+        };
+        let target = settings.archive_path(&dir);
+        assert_eq!(target, root.join("spot_v001.zip"));
+        let mut calls = 0;
+        create_zip(&dir, &target, &mut |done, total| {
+            calls += 1;
+            assert!(done <= total);
+            true
+        })
+        .unwrap();
+        assert!(calls > 0);
+
+        let mut zip = zip::ZipArchive::new(std::fs::File::open(&target).unwrap()).unwrap();
+        let mut names: Vec<&str> = zip.file_names().collect();
+        names.sort();
+        assert_eq!(
+            names,
+            vec![
+                "spot_v001/empty/",
+                "spot_v001/frames/",
+                "spot_v001/frames/spot.1001.exr",
+                "spot_v001/notes.txt"
+            ]
+        );
+        let mut read = Vec::new();
+        std::io::Read::read_to_end(
+            &mut zip.by_name("spot_v001/frames/spot.1001.exr").unwrap(),
+            &mut read,
+        )
+        .unwrap();
+        assert_eq!(read, frame);
+
+        // Split archives are written as volumes that join back into an archive.
+        std::fs::remove_file(&target).unwrap();
+        let volumes = create_split_zip(&dir, &target, 256, &mut |_done, _total| true).unwrap();
+        assert!(volumes.len() > 1);
+        assert_eq!(volumes[0], root.join("spot_v001.zip.001"));
+        assert!(!target.exists());
+        assert_eq!(archive_files(&target), volumes);
+        let joined: Vec<u8> = volumes
+            .iter()
+            .flat_map(|v| std::fs::read(v).unwrap())
+            .collect();
+        assert!(volumes[..volumes.len() - 1]
+            .iter()
+            .all(|v| std::fs::metadata(v).unwrap().len() == 256));
+        let mut zip = zip::ZipArchive::new(std::io::Cursor::new(joined)).unwrap();
+        let mut read = Vec::new();
+        std::io::Read::read_to_end(
+            &mut zip.by_name("spot_v001/frames/spot.1001.exr").unwrap(),
+            &mut read,
+        )
+        .unwrap();
+        assert_eq!(read, frame);
+        remove_archive(&target);
+        assert!(archive_files(&target).is_empty());
+
+        // Archives fitting in one volume aren't split.
+        let single = create_split_zip(&dir, &target, 1 << 20, &mut |_done, _total| true).unwrap();
+        assert_eq!(single, vec![target.clone()]);
+        assert!(!volume_path(&target, 1).exists());
+        remove_archive(&target);
+
+        // A volume that can't be written removes the ones written before it.
+        std::fs::create_dir(volume_path(&target, 3)).unwrap();
+        assert!(create_split_zip(&dir, &target, 256, &mut |_done, _total| true).is_err());
+        assert!(!volume_path(&target, 1).exists() && !volume_path(&target, 2).exists());
+        std::fs::remove_dir(volume_path(&target, 3)).unwrap();
+
+        // Cancelling removes the partial archive.
+        let cancelled = create_zip(&dir, &target, &mut |_done, _total| false);
+        assert_eq!(
+            cancelled.unwrap_err().kind(),
+            std::io::ErrorKind::Interrupted
+        );
+        assert!(!target.exists());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(feature = "deliveries")]
+    #[test]
+    fn test_upload_targets() {
+        use crate::uploads::{command_args, sftp_batch, Ftp, Transport, UploadTarget};
+        use std::io::{BufRead, Read, Write};
+        use std::net::TcpListener;
+
+        let targets: Vec<UploadTarget> = serde_yaml::from_str(
+            "- name: FTP\n  kind: ftp\n  host: localhost\n  user: crosby\n- name: Aspera\n  kind: command\n  program: ascp\n  args: [\"{path}\", \"host:/in/{name}\"]\n",
+        )
+        .unwrap();
+        assert!(matches!(
+            targets[0].transport,
+            Transport::Ftp { port: 21, .. }
+        ));
+        let path = PathBuf::from("/deliveries/spot.zip");
+        let args = match &targets[1].transport {
+            Transport::Command { args, .. } => command_args(args, &path),
+            _ => panic!(),
+        };
+        assert_eq!(args, vec!["/deliveries/spot.zip", "host:/in/spot.zip"]);
+        assert_eq!(
+            sftp_batch("in", &[path]),
+            "progress\n-mkdir \"in\"\ncd \"in\"\nput -r \"/deliveries/spot.zip\"\nbye\n"
+        );
+
+        // A server that accepts everything and keeps the stored files.
+        let root = test_path("upload_targets");
+        std::fs::create_dir_all(root.join("spot/frames")).unwrap();
+        std::fs::write(root.join("spot/notes.txt"), "final").unwrap();
+        std::fs::write(root.join("spot/frames/spot.1001.exr"), vec![7; 3000]).unwrap();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let server = std::thread::spawn(move || {
+            let (control, _) = listener.accept().unwrap();
+            let mut reader = std::io::BufReader::new(control.try_clone().unwrap());
+            let mut control = control;
+            let data = TcpListener::bind("127.0.0.1:0").unwrap();
+            let data_port = data.local_addr().unwrap().port();
+            let mut log = Vec::new();
+            let mut stored = Vec::new();
+            control.write_all(b"220-Welcome\r\n220 Ready\r\n").unwrap();
+            loop {
+                let mut line = String::new();
+                if reader.read_line(&mut line).unwrap() == 0 {
+                    break;
+                }
+                let line = line.trim_end().to_string();
+                let reply = match line.split(' ').next().unwrap() {
+                    "USER" => String::from("331 Password"),
+                    "PASS" => String::from("230 Logged in"),
+                    "TYPE" => String::from("200 Binary"),
+                    "PASV" => format!(
+                        "227 Passive (10,0,0,1,{},{})",
+                        data_port / 256,
+                        data_port % 256
+                    ),
+                    "STOR" => {
+                        control.write_all(b"150 Go\r\n").unwrap();
+                        let mut contents = Vec::new();
+                        data.accept().unwrap().0.read_to_end(&mut contents).unwrap();
+                        stored.push((line[5..].to_string(), contents));
+                        String::from("226 Done")
+                    }
+                    "MKD" if line == "MKD in" => String::from("550 Exists"),
+                    "MKD" => String::from("257 Created"),
+                    "QUIT" => String::from("221 Bye"),
+                    _ => String::from("250 OK"),
+                };
+                log.push(line);
+                control
+                    .write_all(format!("{}\r\n", reply).as_bytes())
+                    .unwrap();
+            }
+            (log, stored)
+        });
+
+        let mut ftp = Ftp::connect("127.0.0.1", port, "crosby", "secret").unwrap();
+        ftp.change_dir_all("/in").unwrap();
+        let mut sent = 0;
+        ftp.put(&root.join("spot"), &mut |read| {
+            sent += read;
+            true
+        })
+        .unwrap();
+        // A cancelled upload is removed.
+        assert!(ftp
+            .put(&root.join("spot/notes.txt"), &mut |_read| false)
+            .is_err());
+        ftp.quit();
+        drop(ftp);
+        let (log, stored) = server.join().unwrap();
+        assert_eq!(sent, 3005);
+        assert_eq!(
+            log.iter()
+                .filter(|l| !l.starts_with("PASV"))
+                .cloned()
+                .collect::<Vec<_>>(),
+            vec![
+                "USER crosby",
+                "PASS secret",
+                "TYPE I",
+                "CWD /",
+                "MKD in",
+                "CWD in",
+                "MKD spot",
+                "CWD spot",
+                "MKD frames",
+                "CWD frames",
+                "STOR spot.1001.exr",
+                "CDUP",
+                "STOR notes.txt",
+                "CDUP",
+                "STOR notes.txt",
+                "DELE notes.txt",
+                "QUIT"
+            ]
+        );
+        assert_eq!(stored[0], (String::from("spot.1001.exr"), vec![7; 3000]));
+        assert_eq!(stored[1], (String::from("notes.txt"), b"final".to_vec()));
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(feature = "deliveries")]
+    #[test]
+    fn test_delivery_note() {
+        use crate::deliveries::{DeliveryManifest, DeliveryPackage, DeliveryRecord};
+        use crate::delivery_notes::{load_template, note_path, DeliveryNote, NoteFormat};
+        use crate::sequences::Sequence;
+
+        let sequence = Sequence {
+            dir: PathBuf::from("/out"),
+            prefix: String::from("spot_comp_v003."),
+            suffix: String::from(".exr"),
+            padding: 4,
+            frames: vec![1001, 1002],
+            size: 2048,
+        };
+        let package = DeliveryPackage {
+            name: String::from("spot_v003"),
+            items: vec![
+                OutputItem::Sequence(sequence),
+                OutputItem::File {
+                    path: PathBuf::from("/out/spot_edit_v012.mov"),
+                    size: 4096,
+                },
+            ],
+            timeline: None,
+            rate: 24,
+            ffmpeg: None,
+            archive: None,
+        };
+        let record = |name: &str, sha1: &str| DeliveryRecord {
+            source: PathBuf::from("/out").join(name),
+            path: String::from(name),
+            size: 1,
+            sha1: String::from(sha1),
+        };
+        let manifest = DeliveryManifest {
+            name: package.name.clone(),
+            files: vec![
+                record("spot_comp_v003.1001.exr", "aa"),
+                record("spot_comp_v003.1002.exr", "bb"),
+                record("spot_edit_v012.mov", "cc"),
+            ],
+            complete: true,
+        };
+
+        let note = DeliveryNote::new(
+            &package,
+            &manifest,
+            "Spot",
+            "acme",
+            "Grade <b>v2</b>\nThanks",
+        );
+        assert_eq!(note.items[0].version, Some(3));
+        assert_eq!(note.items[0].frames, Some((1001, 1002)));
+        // The checksum of the frame checksums.
+        assert_eq!(
+            note.items[0].sha1,
+            "28cc5fd736aee0939ede3330c2867b31e82d9656"
+        );
+        assert_eq!(note.items[1].version, Some(12));
+        assert_eq!(note.items[1].sha1, "cc");
+        let unpackaged = DeliveryNote::new(&package, &DeliveryManifest::default(), "", "", "");
+        assert_eq!(unpackaged.items[1].sha1, "");
+
+        let markdown = note.render(NoteFormat::Markdown, None);
+        assert!(markdown.starts_with("# spot_v003\n"));
+        assert!(markdown.contains("Client: acme\n"));
+        assert!(markdown.contains("| spot_edit_v012.mov | v012 |  | 4.0 KB | cc |\n"));
+        assert!(markdown.ends_with("Grade <b>v2</b>\nThanks\n"));
+        let html = note.render(NoteFormat::Html, Some("<p>{notes}</p>{items}"));
+        assert!(html.starts_with("<p>Grade &lt;b&gt;v2&lt;/b&gt;<br>\nThanks</p><table>"));
+        assert!(html.contains("<td>spot_edit_v012.mov</td><td>v012</td>"));
+
+        // Client templates before the default one.
+        let root = test_path("delivery_note");
+        let notes_dir = root.join("delivery_notes");
+        std::fs::create_dir_all(&notes_dir).unwrap();
+        assert_eq!(
+            load_template(&root, "acme", NoteFormat::Markdown).unwrap(),
+            None
+        );
+        std::fs::write(notes_dir.join("default.md"), "default").unwrap();
+        std::fs::write(notes_dir.join("acme.md"), "acme").unwrap();
+        let template = |client| load_template(&root, client, NoteFormat::Markdown).unwrap();
+        assert_eq!(template("acme").as_deref(), Some("acme"));
+        assert_eq!(template("other").as_deref(), Some("default"));
+        assert_eq!(template("").as_deref(), Some("default"));
+        assert_eq!(
+            note_path(&root.join("2026-03-05/spot_v003"), NoteFormat::Html),
+            root.join("2026-03-05/spot_v003_delivery_note.html")
+        );
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[cfg(feature = "deliveries")]
+    #[test]
+    fn test_delivery_note_render() {
+        use crate::delivery_notes::{load_template, DeliveryNote, NoteFormat, NoteItem};
+
+        let note = DeliveryNote {
+            package: String::from("spot <final>"),
+            project: String::from("Spot"),
+            client: String::from("acme"),
+            date: String::from("2026-03-05"),
+            user: String::from("jb"),
+            notes: String::from("Replaces {items} & {package} from v2"),
+            items: vec![NoteItem {
+                name: String::from("a|b<c>.mov"),
+                version: Some(4),
+                frames: None,
+                size: 1024,
+                sha1: String::from("ff"),
+            }],
+        };
+
+        // Pipes in cells are escaped so the table keeps its columns.
+        let markdown = note.render(NoteFormat::Markdown, Some("{items}"));
+        assert_eq!(
+            markdown,
+            "| Item | Version | Frames | Size | SHA-1 |\n|---|---|---|---|---|\n\
+             | a\\|b<c>.mov | v004 |  | 1.0 KB | ff |"
+        );
+
+        let html = note.render(NoteFormat::Html, Some("<h1>{package}</h1>{items}"));
+        assert!(html.starts_with("<h1>spot &lt;final&gt;</h1><table>"));
+        assert!(html.contains("<td>a|b&lt;c&gt;.mov</td><td>v004</td>"));
+
+        // Tokens typed in the notes are not expanded again.
+        assert_eq!(
+            note.render(NoteFormat::Markdown, Some("{notes} {unknown} {")),
+            "Replaces {items} & {package} from v2 {unknown} {"
+        );
+        assert_eq!(
+            note.render(NoteFormat::Html, Some("{notes}")),
+            "Replaces {items} &amp; {package} from v2"
+        );
+
+        // The template of the client is used over the default one.
+        let root = test_path("delivery_note_render");
+        let notes_dir = root.join("delivery_notes");
+        std::fs::create_dir_all(&notes_dir).unwrap();
+        std::fs::write(notes_dir.join("default.md"), "Default for {client}").unwrap();
+        std::fs::write(notes_dir.join("acme.md"), "Hi {client}, {package}").unwrap();
+        let template = load_template(&root, &note.client, NoteFormat::Markdown).unwrap();
+        assert_eq!(
+            note.render(NoteFormat::Markdown, template.as_deref()),
+            "Hi acme, spot <final>"
+        );
+        let template = load_template(&root, "other", NoteFormat::Markdown).unwrap();
+        assert_eq!(
+            note.render(NoteFormat::Markdown, template.as_deref()),
+            "Default for acme"
+        );
+        std::fs::remove_dir_all(&root).unwrap();
     }
 }
@@ -0,0 +1,223 @@
+use crate::ipc::request;
+use crate::TaskTreeNode;
+use log::info;
+use serde_json::json;
+use std::io;
+use std::path::Path;
+
+pub const LINK_SCHEME: &str = "rclamp";
+pub const USAGE: &str =
+    "Usage: rclamp [--project <name> [--task <task>]] [rclamp://open?project=<name>&task=<task>]";
+
+/// A project and optionally a task to open, from the command line or a `rclamp://` link.
+/// The task is a task name or a path relative to the work dir with forward slashes.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct OpenTarget {
+    pub project: String,
+    pub task: Option<String>,
+}
+
+impl OpenTarget {
+    /// Reads `--project` and `--task`, or a link. None if there are no arguments.
+    pub fn from_args(args: &[String]) -> Result<Option<OpenTarget>, String> {
+        let mut project = None;
+        let mut task = None;
+        let mut args = args.iter();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "--project" => {
+                    project = Some(args.next().ok_or("--project needs a name.")?.clone())
+                }
+                "--task" => task = Some(args.next().ok_or("--task needs a name.")?.clone()),
+                // Added by older macOS when launched from the Finder.
+                a if a.starts_with("-psn_") => (),
+                a if a.starts_with(&format!("{}:", LINK_SCHEME)) => {
+                    return Self::from_url(a).map(Some)
+                }
+                a => return Err(format!("Unknown argument: {}", a)),
+            }
+        }
+        match (project, task) {
+            (Some(project), task) => Ok(Some(OpenTarget { project, task })),
+            (None, Some(_t)) => Err(String::from("--task needs --project.")),
+            (None, None) => Ok(None),
+        }
+    }
+
+    /// Reads a `rclamp://open?project=<name>&task=<task>` link.
+    pub fn from_url(url: &str) -> Result<OpenTarget, String> {
+        let query = url
+            .strip_prefix(&format!("{}://open", LINK_SCHEME))
+            .map(|q| q.trim_start_matches('/'))
+            .and_then(|q| q.strip_prefix('?'))
+            .ok_or_else(|| format!("Not a rclamp link: {}", url))?;
+        let mut target = OpenTarget::default();
+        for pair in query.split('&') {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            let value = decode(value)?;
+            match key {
+                "project" => target.project = value,
+                "task" if !value.is_empty() => target.task = Some(value),
+                _ => (),
+            }
+        }
+        if target.project.is_empty() {
+            return Err(format!("No project in link: {}", url));
+        }
+        Ok(target)
+    }
+
+    pub fn to_url(&self) -> String {
+        let mut url = format!("{}://open?project={}", LINK_SCHEME, encode(&self.project));
+        if let Some(task) = &self.task {
+            url.push_str(&format!("&task={}", encode(task)));
+        }
+        url
+    }
+
+    /// Asks an already running instance to open the target.
+    pub fn forward(&self) -> Result<(), io::Error> {
+        request("open", json!({"project": self.project, "task": self.task}))?;
+        info!("Forwarded {} to the running instance.", self.to_url());
+        Ok(())
+    }
+}
+
+/// The link key of a task, its path relative to the work dir with forward slashes.
+pub fn task_key(work_path: &Path, task_path: &Path) -> String {
+    let relative = task_path.strip_prefix(work_path).unwrap_or(task_path);
+    let parts: Vec<String> = relative
+        .components()
+        .map(|c| c.as_os_str().to_string_lossy().to_string())
+        .collect();
+    parts.join("/")
+}
+
+/// Finds a task by its link key, or by name if the name is unique in the project.
+pub fn find_task<'a>(
+    tree: &'a TaskTreeNode,
+    work_path: &Path,
+    task: &str,
+) -> Option<&'a TaskTreeNode> {
+    let tasks = tree.tasks();
+    if let Some(t) = tasks.iter().find(|t| task_key(work_path, &t.path) == task) {
+        return Some(t);
+    }
+    let mut named = tasks.into_iter().filter(|t| t.name == task);
+    match (named.next(), named.next()) {
+        (Some(t), None) => Some(t),
+        _ => None,
+    }
+}
+
+fn encode(text: &str) -> String {
+    text.bytes()
+        .map(|b| match b {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                (b as char).to_string()
+            }
+            _ => format!("%{:02X}", b),
+        })
+        .collect()
+}
+
+fn decode(text: &str) -> Result<String, String> {
+    let bytes = text.as_bytes();
+    let mut decoded = Vec::new();
+    let mut i = 0;
+    while i < bytes.len() {
+        match bytes[i] {
+            b'%' => {
+                let byte = text
+                    .get(i + 1..i + 3)
+                    .and_then(|h| u8::from_str_radix(h, 16).ok())
+                    .ok_or_else(|| format!("Invalid escape in link: {}", text))?;
+                decoded.push(byte);
+                i += 3;
+            }
+            b'+' => {
+                decoded.push(b' ');
+                i += 1;
+            }
+            b => {
+                decoded.push(b);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8(decoded).map_err(|e| e.to_string())
+}
+
+/// Registers this executable as the handler of `rclamp://` links for the current user.
+#[cfg(windows)]
+pub fn register_scheme() -> Result<(), String> {
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    let key = format!("HKCU\\Software\\Classes\\{}", LINK_SCHEME);
+    let command_key = format!("{}\\shell\\open\\command", key);
+    let command = format!("\"{}\" \"%1\"", exe.display());
+    for args in [
+        vec!["add", key.as_str(), "/ve", "/d", "URL:rclamp", "/f"],
+        vec!["add", key.as_str(), "/v", "URL Protocol", "/d", "", "/f"],
+        vec![
+            "add",
+            command_key.as_str(),
+            "/ve",
+            "/d",
+            command.as_str(),
+            "/f",
+        ],
+    ] {
+        let status = std::process::Command::new("reg")
+            .args(args.iter())
+            .status()
+            .map_err(|e| e.to_string())?;
+        if !status.success() {
+            return Err(format!("reg {} failed: {}", args.join(" "), status));
+        }
+    }
+    info!("Registered {}:// links", LINK_SCHEME);
+    Ok(())
+}
+
+/// Registers this executable as the handler of `rclamp://` links for the current user.
+#[cfg(all(unix, not(target_os = "macos")))]
+pub fn register_scheme() -> Result<(), String> {
+    let exe = std::env::current_exe().map_err(|e| e.to_string())?;
+    let applications = std::env::var_os("XDG_DATA_HOME")
+        .map(std::path::PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|h| Path::new(&h).join(".local/share")))
+        .ok_or("No home dir.")?
+        .join("applications");
+    let desktop_file = "rclamp-links.desktop";
+    std::fs::create_dir_all(&applications).map_err(|e| e.to_string())?;
+    std::fs::write(
+        applications.join(desktop_file),
+        format!(
+            "[Desktop Entry]\nType=Application\nName=Rclamp\nExec=\"{}\" %u\nNoDisplay=true\nMimeType=x-scheme-handler/{};\n",
+            exe.display(),
+            LINK_SCHEME
+        ),
+    )
+    .map_err(|e| e.to_string())?;
+    let status = std::process::Command::new("xdg-mime")
+        .args([
+            "default",
+            desktop_file,
+            &format!("x-scheme-handler/{}", LINK_SCHEME),
+        ])
+        .status()
+        .map_err(|e| format!("xdg-mime: {}", e))?;
+    if !status.success() {
+        return Err(format!("xdg-mime failed: {}", status));
+    }
+    info!("Registered {}:// links", LINK_SCHEME);
+    Ok(())
+}
+
+/// macOS hands links to the app bundle as Apple events, which eframe doesn't pass on.
+#[cfg(target_os = "macos")]
+pub fn register_scheme() -> Result<(), String> {
+    Err(String::from(
+        "Opening rclamp:// links isn't supported on macOS yet, use --project and --task.",
+    ))
+}
@@ -0,0 +1,164 @@
+use crate::helpers::sanitize_string;
+use crate::ignore::IgnorePatterns;
+use crate::naming::{FilenameTemplate, FilenameTokens};
+use crate::Project;
+use crate::TaskTreeNode;
+use log::{error, info};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Why a workfile doesn't follow the filename template.
+#[derive(Clone, Debug, PartialEq)]
+pub enum NamingProblem {
+    /// No version number, so there is no suggestion.
+    NoVersion,
+    /// Only a trailing version token, e.g. a hand-named `old_setup_v7`.
+    NotTemplate,
+    WrongProject(String),
+    WrongTask(String),
+    /// The tokens are right, but e.g. the version padding differs.
+    Format,
+}
+
+/// A workfile not following the template, with the file name it should have.
+#[derive(Clone, Debug, PartialEq)]
+pub struct NamingIssue {
+    pub path: PathBuf,
+    pub task: String,
+    pub problem: NamingProblem,
+    pub suggestion: Option<String>,
+}
+
+/// Checks the workfiles of every task in the tree against the template.
+pub fn lint_tasks(
+    tree: &TaskTreeNode,
+    project: &Project,
+    template: &FilenameTemplate,
+    ignore: &IgnorePatterns,
+) -> Result<Vec<NamingIssue>, io::Error> {
+    let mut issues = Vec::new();
+    for task in tree.tasks() {
+        let work_path = task.get_work_path();
+        if !work_path.is_dir() {
+            continue;
+        }
+        let mut paths: Vec<PathBuf> = fs::read_dir(&work_path)?
+            .filter_map(|e| e.ok())
+            .filter(|e| !ignore.is_ignored(&e.file_name().to_string_lossy()))
+            .map(|e| e.path())
+            .filter(|p| p.is_file())
+            .collect();
+        paths.sort();
+        for path in paths {
+            if let Some(issue) = lint_file(&path, task, project, template) {
+                issues.push(issue);
+            }
+        }
+    }
+    info!("Found {} misnamed workfiles", issues.len());
+    Ok(issues)
+}
+
+/// Checks one workfile, returning None if it follows the template.
+pub fn lint_file(
+    path: &Path,
+    task: &TaskTreeNode,
+    project: &Project,
+    template: &FilenameTemplate,
+) -> Option<NamingIssue> {
+    let stem = path.file_stem()?.to_string_lossy().to_string();
+    let file_name = path.file_name()?.to_string_lossy().to_string();
+    let extension = path
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+    let issue = |problem, suggestion| {
+        Some(NamingIssue {
+            path: path.to_path_buf(),
+            task: task.name.clone(),
+            problem,
+            suggestion,
+        })
+    };
+
+    let parsed = match template.parse(&stem) {
+        Ok(p) => p,
+        Err(_e) => return issue(NamingProblem::NoVersion, None),
+    };
+
+    // Project and task names may contain the separators, so the known ones are matched literally.
+    let literal = |project_name: Option<&str>, task_name: Option<&str>| {
+        let mut literal = template.as_str().to_string();
+        if let Some(p) = project_name {
+            literal = literal.replace("{project}", p);
+        }
+        if let Some(t) = task_name {
+            literal = literal.replace("{task}", t);
+        }
+        FilenameTemplate::new(&literal)
+            .ok()
+            .filter(|t| t.matches(&stem))
+            .and_then(|t| t.parse(&stem).ok())
+    };
+    let project_name = project.name_sanitized.as_str();
+    let (problem, name) = if let Some(p) = literal(Some(project_name), Some(&task.name)) {
+        (NamingProblem::Format, p.tokens.name)
+    } else if let Some(p) = literal(Some(project_name), None) {
+        (NamingProblem::WrongTask(p.tokens.task), p.tokens.name)
+    } else if let Some(p) = literal(None, Some(&task.name)) {
+        (NamingProblem::WrongProject(p.tokens.project), p.tokens.name)
+    } else if template.matches(&stem) {
+        let tokens = parsed.tokens.clone();
+        (NamingProblem::WrongProject(tokens.project), tokens.name)
+    } else {
+        (
+            NamingProblem::NotTemplate,
+            sanitize_string(parsed.name.clone()),
+        )
+    };
+
+    let tokens = FilenameTokens {
+        project: project.name_sanitized.clone(),
+        task: task.name.clone(),
+        name,
+        version: parsed.tokens.version,
+    };
+    let suggestion = format!("{}{}{}", template.format(&tokens), parsed.suffix, extension);
+    if suggestion == file_name {
+        return None;
+    }
+    issue(problem, Some(suggestion))
+}
+
+/// Renames the files to their suggestions, skipping names that are taken. A dry run only reports.
+/// Returns a line per file.
+pub fn rename_issues(issues: &[NamingIssue], dry_run: bool) -> Vec<String> {
+    let mut report = Vec::new();
+    let mut targets: Vec<PathBuf> = Vec::new();
+    for issue in issues {
+        let suggestion = match &issue.suggestion {
+            Some(s) => s,
+            None => continue,
+        };
+        let target = issue.path.with_file_name(suggestion);
+        let from = issue.path.file_name().unwrap_or_default().to_string_lossy();
+        if target.exists() || targets.contains(&target) {
+            report.push(format!("Skipped {}: {} already exists", from, suggestion));
+            continue;
+        }
+        targets.push(target.clone());
+        if dry_run {
+            report.push(format!("{} → {}", from, suggestion));
+            continue;
+        }
+        match fs::rename(&issue.path, &target) {
+            Ok(()) => report.push(format!("Renamed {} → {}", from, suggestion)),
+            Err(e) => {
+                error!("Failed to rename {}: {}", issue.path.display(), e);
+                report.push(format!("Failed to rename {}: {}", from, e));
+            }
+        }
+    }
+    report
+}
@@ -0,0 +1,113 @@
+use crate::helpers::{format_timestamp, now_timestamp};
+use log::{Level, Log, Metadata, Record};
+use std::collections::VecDeque;
+use std::env;
+use std::fmt;
+use std::sync::Mutex;
+
+/// Number of log lines kept in memory for crash reports and the log panel.
+pub const LOG_CAPACITY: usize = 500;
+/// Lines up to this level are kept whatever `RUST_LOG` is set to.
+const KEEP_LEVEL: Level = Level::Info;
+
+static RECENT: Mutex<Option<VecDeque<LogLine>>> = Mutex::new(None);
+
+/// A line of the log kept in memory.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LogLine {
+    /// Seconds since the unix epoch.
+    pub time: u64,
+    pub level: Level,
+    pub target: String,
+    pub message: String,
+}
+
+impl fmt::Display for LogLine {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{}:{:02} {:<5} {}: {}",
+            format_timestamp(self.time),
+            self.time % 60,
+            self.level,
+            self.target,
+            self.message
+        )
+    }
+}
+
+/// Adds a line, dropping the oldest beyond `LOG_CAPACITY`.
+pub fn push_line(line: LogLine) {
+    let mut recent = RECENT.lock().unwrap_or_else(|e| e.into_inner());
+    let lines = recent.get_or_insert_with(VecDeque::new);
+    if lines.len() >= LOG_CAPACITY {
+        lines.pop_front();
+    }
+    lines.push_back(line);
+}
+
+pub fn clear_lines() {
+    let mut recent = RECENT.lock().unwrap_or_else(|e| e.into_inner());
+    *recent = None;
+}
+
+/// The lines as text, one per line.
+pub fn format_lines(lines: &[LogLine]) -> String {
+    lines.iter().map(|l| format!("{}\n", l)).collect()
+}
+
+/// The kept lines, oldest first.
+pub fn recent_lines() -> Vec<LogLine> {
+    let recent = RECENT.lock().unwrap_or_else(|e| e.into_inner());
+    recent
+        .as_ref()
+        .map(|l| l.iter().cloned().collect())
+        .unwrap_or_default()
+}
+
+/// Passes records on to the terminal logger and keeps the latest in memory.
+struct RecentLogger {
+    inner: Box<dyn Log>,
+}
+
+impl Log for RecentLogger {
+    fn enabled(&self, metadata: &Metadata<'_>) -> bool {
+        metadata.level() <= KEEP_LEVEL || self.inner.enabled(metadata)
+    }
+
+    fn log(&self, record: &Record<'_>) {
+        if record.level() <= KEEP_LEVEL {
+            push_line(LogLine {
+                time: now_timestamp(),
+                level: record.level(),
+                target: String::from(record.target()),
+                message: record.args().to_string(),
+            });
+        }
+        if self.inner.enabled(record.metadata()) {
+            self.inner.log(record);
+        }
+    }
+
+    fn flush(&self) {
+        self.inner.flush();
+    }
+}
+
+/// Logs to stdout as set by `RUST_LOG`, and keeps the latest info, warnings and errors for crash
+/// reports and the log panel, as the console is hidden in release builds on Windows.
+pub fn init_logging() {
+    let mut builder = pretty_env_logger::formatted_builder();
+    if let Ok(filters) = env::var("RUST_LOG") {
+        builder.parse_filters(&filters);
+    }
+    let inner = builder.build();
+    let max_level = inner.filter().max(KEEP_LEVEL.to_level_filter());
+    if log::set_boxed_logger(Box::new(RecentLogger {
+        inner: Box::new(inner),
+    }))
+    .is_ok()
+    {
+        log::set_max_level(max_level);
+    }
+}
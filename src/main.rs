@@ -1,19 +1,31 @@
 #![warn(clippy::all, rust_2018_idioms)]
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")] // hide console window on Windows in release
 
-// When compiling natively:
-#[cfg(not(target_arch = "wasm32"))]
-use pretty_env_logger;
-
 fn main() -> eframe::Result<()> {
-    // Log to stdout (if you run with `RUST_LOG=debug`).
-    pretty_env_logger::init();
+    // Log to stdout (if you run with `RUST_LOG=debug`), keeping the latest lines for crash reports.
+    rclamp::init_logging();
+    rclamp::install_panic_hook();
+
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let target = match rclamp::OpenTarget::from_args(&args) {
+        Ok(t) => t,
+        Err(e) => {
+            eprintln!("{}\n{}", e, rclamp::USAGE);
+            std::process::exit(2);
+        }
+    };
+    // Links clicked while the app is running open in the running instance.
+    if let Some(t) = &target {
+        if t.forward().is_ok() {
+            return Ok(());
+        }
+    }
 
     let native_options = eframe::NativeOptions::default();
 
     eframe::run_native(
         "Rclamp",
         native_options,
-        Box::new(|cc| Box::new(rclamp::Rclamp::new(cc))),
+        Box::new(|cc| Box::new(rclamp::Rclamp::new(cc, target))),
     )
 }
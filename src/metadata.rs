@@ -0,0 +1,104 @@
+use crate::atomic::{write_atomic, FileLock};
+use log::{error, info};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+pub const METADATA_FILE_NAME: &str = "metadata.yaml";
+/// The version of the app a file was last saved with, recorded at publish.
+pub const APP_VERSION_KEY: &str = "app_version";
+
+/// An extra column in the files table, showing one metadata field of the files.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, PartialEq)]
+pub struct MetadataColumn {
+    /// The metadata field, e.g. `renderer` or `fps`.
+    pub key: String,
+    /// Shown in the header instead of the key.
+    #[serde(default)]
+    pub title: Option<String>,
+    /// Values picked from a list. Without choices any text can be entered.
+    #[serde(default)]
+    pub choices: Vec<String>,
+}
+
+impl MetadataColumn {
+    pub fn title(&self) -> &str {
+        self.title.as_deref().unwrap_or(&self.key)
+    }
+}
+
+/// Key-value metadata of files, by path relative to the dir of the store with forward slashes.
+/// Stored in `metadata.yaml` in the task dir, so it covers both workfiles and outputs.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, Default, PartialEq)]
+#[serde(transparent)]
+pub struct MetadataStore {
+    files: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl MetadataStore {
+    /// Reads the metadata in the dir. A missing file means no metadata.
+    pub fn load(dir: &Path) -> Result<MetadataStore, io::Error> {
+        let path = dir.join(METADATA_FILE_NAME);
+        if !path.exists() {
+            return Ok(MetadataStore::default());
+        }
+        let file = fs::File::open(&path)?;
+        serde_yaml::from_reader(file).map_err(|e| {
+            error!("Failed to read {}: {}", path.display(), e);
+            io::Error::new(io::ErrorKind::Other, e.to_string())
+        })
+    }
+
+    /// Sets one field of a file, re-reading the store under its lock so concurrent edits of other
+    /// files and fields are kept. Returns the updated store.
+    pub fn update(
+        dir: &Path,
+        file: &Path,
+        key: &str,
+        value: &str,
+    ) -> Result<MetadataStore, io::Error> {
+        let path = dir.join(METADATA_FILE_NAME);
+        let _lock = FileLock::acquire(&path)?;
+        let mut store = Self::load(dir)?;
+        store.set(&Self::file_key(dir, file), key, value);
+        info!("Writing metadata: {}", path.display());
+        write_atomic(&path, &store)?;
+        Ok(store)
+    }
+
+    /// The key of a file in the store of the dir.
+    pub fn file_key(dir: &Path, file: &Path) -> String {
+        let relative = file.strip_prefix(dir).unwrap_or(file);
+        let parts: Vec<String> = relative
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect();
+        parts.join("/")
+    }
+
+    pub fn get(&self, file_key: &str, key: &str) -> Option<&str> {
+        self.files
+            .get(file_key)
+            .and_then(|f| f.get(key))
+            .map(|v| v.as_str())
+    }
+
+    /// Sets a field, empty values are removed.
+    pub fn set(&mut self, file_key: &str, key: &str, value: &str) {
+        let value = value.trim();
+        if value.is_empty() {
+            if let Some(fields) = self.files.get_mut(file_key) {
+                fields.remove(key);
+                if fields.is_empty() {
+                    self.files.remove(file_key);
+                }
+            }
+        } else {
+            self.files
+                .entry(String::from(file_key))
+                .or_default()
+                .insert(String::from(key), String::from(value));
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use crate::jobs::JobHandle;
+use crate::pathmap::to_local;
+use log::{error, info};
+use std::io::{BufRead, BufReader};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::{Arc, Mutex};
+
+/// Robocopy exit codes below 8 mean success, with or without copied files.
+const ROBOCOPY_FAILURE: i32 = 8;
+
+/// The output of a running mirror, shared with the log window.
+pub type MirrorLog = Arc<Mutex<Vec<String>>>;
+
+/// A source and destination to mirror, e.g. the projects share to a backup NAS.
+/// Runs robocopy on Windows and rsync elsewhere. Paths are translated with the path mappings.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, PartialEq)]
+pub struct MirrorProfile {
+    pub name: String,
+    pub source: PathBuf,
+    pub destination: PathBuf,
+    /// File patterns to copy, e.g. `*.exr`. Empty copies everything.
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// File and dir patterns to skip.
+    #[serde(default)]
+    pub exclude: Vec<String>,
+    /// Also deletes files in the destination that are gone from the source.
+    #[serde(default)]
+    pub delete: bool,
+}
+
+impl MirrorProfile {
+    fn robocopy(&self) -> Command {
+        let mut command = Command::new("robocopy");
+        command
+            .arg(to_local(&self.source))
+            .arg(to_local(&self.destination));
+        command.args(&self.include);
+        command.arg(if self.delete { "/MIR" } else { "/E" });
+        if !self.exclude.is_empty() {
+            command.arg("/XF").args(&self.exclude);
+            command.arg("/XD").args(&self.exclude);
+        }
+        command.args(["/R:2", "/W:5", "/NP"]);
+        command
+    }
+
+    fn rsync(&self) -> Command {
+        let mut command = Command::new("rsync");
+        command.args(["-a", "-v", "--human-readable"]);
+        if self.delete {
+            command.arg("--delete");
+        }
+        for pattern in &self.exclude {
+            command.arg(format!("--exclude={}", pattern));
+        }
+        if !self.include.is_empty() {
+            // Dirs are included so rsync descends into them, everything else not included is skipped.
+            command.arg("--include=*/");
+            for pattern in &self.include {
+                command.arg(format!("--include={}", pattern));
+            }
+            command.args(["--exclude=*", "--prune-empty-dirs"]);
+        }
+        // The trailing slash copies the contents of the source, not the source dir itself.
+        let source = to_local(&self.source).display().to_string();
+        command
+            .arg(format!("{}/", source.trim_end_matches('/')))
+            .arg(to_local(&self.destination));
+        command
+    }
+
+    pub fn command(&self) -> Command {
+        if cfg!(windows) {
+            self.robocopy()
+        } else {
+            self.rsync()
+        }
+    }
+
+    /// The command as shown to the user.
+    pub fn command_line(&self) -> String {
+        let command = self.command();
+        let mut parts = vec![command.get_program().to_string_lossy().to_string()];
+        for arg in command.get_args() {
+            let arg = arg.to_string_lossy();
+            if arg.contains(' ') {
+                parts.push(format!("\"{}\"", arg));
+            } else {
+                parts.push(arg.to_string());
+            }
+        }
+        parts.join(" ")
+    }
+
+    /// Runs the mirror, appending the output to the log. Meant to run as a job, cancelling kills the process.
+    pub fn run(&self, log: &MirrorLog, job: &JobHandle) -> Result<String, String> {
+        let push = |line: String| {
+            if let Ok(mut l) = log.lock() {
+                l.push(line);
+            }
+        };
+        let command_line = self.command_line();
+        info!("Running mirror {}: {}", self.name, command_line);
+        push(command_line);
+
+        let mut child = self
+            .command()
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to start mirror: {}", e))?;
+
+        // Errors are read on a thread, so a full stderr pipe can't block the process.
+        let stderr = child.stderr.take().map(|stderr| {
+            let log = log.clone();
+            std::thread::spawn(move || {
+                for line in BufReader::new(stderr).lines().map_while(Result::ok) {
+                    if let Ok(mut l) = log.lock() {
+                        l.push(line);
+                    }
+                }
+            })
+        });
+        if let Some(stdout) = child.stdout.take() {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if job.is_cancelled() {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    push(String::from("Cancelled."));
+                    return Err(String::from("Cancelled."));
+                }
+                push(line);
+            }
+        }
+        if let Some(t) = stderr {
+            let _ = t.join();
+        }
+
+        let status = child.wait().map_err(|e| e.to_string())?;
+        let success = match status.code() {
+            Some(c) if cfg!(windows) => c < ROBOCOPY_FAILURE,
+            _ => status.success(),
+        };
+        if success {
+            Ok(format!("Mirrored {}", self.name))
+        } else {
+            let message = format!("Mirror {} failed: {}", self.name, status);
+            error!("{}", message);
+            push(message.clone());
+            Err(message)
+        }
+    }
+}
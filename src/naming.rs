@@ -0,0 +1,382 @@
+use regex::Regex;
+use std::collections::HashMap;
+use std::convert::TryFrom;
+use std::fmt;
+use std::sync::{Arc, RwLock};
+
+pub const DEFAULT_FILENAME_TEMPLATE: &str = "{project}_{task}_{name}_v{version:03}";
+pub const DEFAULT_VERSION_PADDING: usize = 3;
+
+/// Trailing version token with an optional suffix, used for files not following the template.
+const FALLBACK_PATTERN: &str =
+    r"^(?P<name>.+?)(?P<literal>[_.\-][vV])(?P<version>\d+)(?:[_.\-].*)?$";
+
+/// Patterns of known project and task names kept by a template, more are compiled after clearing.
+const MAX_KNOWN_PATTERNS: usize = 256;
+
+/// Optional suffix after the version, e.g. `_wip`.
+const SUFFIX_PATTERN: &str = r"(?:[_.\-].*)?";
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Project,
+    Task,
+    Name,
+    Version(usize),
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Segment {
+    Literal(String),
+    Token(Token),
+}
+
+/// Values substituted into, or parsed from, a filename template.
+#[derive(Clone, Debug, PartialEq, Default)]
+pub struct FilenameTokens {
+    pub project: String,
+    pub task: String,
+    pub name: String,
+    pub version: u32,
+}
+
+/// A filename parsed by a template. Prefix and suffix are the parts of the file stem around the version number.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ParsedFilename {
+    pub tokens: FilenameTokens,
+    pub name: String,
+    pub prefix: String,
+    pub suffix: String,
+    pub padding: usize,
+}
+
+/// Describes how workfiles are named, e.g. `{project}_{task}_{name}_v{version:03}`.
+/// The same template is used both when creating files and when reading them from drive.
+/// `{name}` is optional, when empty the separator in front of it is dropped.
+#[derive(Clone, Debug, serde::Deserialize, serde::Serialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct FilenameTemplate {
+    template: String,
+    segments: Vec<Segment>,
+    regex: Regex,
+    fallback: Regex,
+    /// Patterns compiled by `parse_in`, by project and task. Shared by clones of the template.
+    known: Arc<RwLock<HashMap<(String, String), Regex>>>,
+}
+
+impl FilenameTemplate {
+    /// Parses a template string, returns an error for unknown or malformed tokens.
+    pub fn new(template: &str) -> Result<Self, String> {
+        let segments = Self::parse_segments(template)?;
+
+        if !segments
+            .iter()
+            .any(|s| matches!(s, Segment::Token(Token::Version(_))))
+        {
+            return Err(format!("Template {} has no {{version}} token.", template));
+        }
+
+        let regex = match Regex::new(&Self::make_pattern(&segments, None)) {
+            Ok(r) => r,
+            Err(e) => return Err(format!("Invalid template {}: {}", template, e)),
+        };
+        let fallback = match Regex::new(FALLBACK_PATTERN) {
+            Ok(r) => r,
+            Err(e) => return Err(e.to_string()),
+        };
+
+        Ok(Self {
+            template: String::from(template),
+            segments,
+            regex,
+            fallback,
+            known: Arc::default(),
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.template
+    }
+
+    fn parse_segments(template: &str) -> Result<Vec<Segment>, String> {
+        let mut segments = Vec::new();
+        let mut rest = template;
+
+        while !rest.is_empty() {
+            let start = match rest.find('{') {
+                Some(i) => i,
+                None => {
+                    segments.push(Segment::Literal(String::from(rest)));
+                    break;
+                }
+            };
+            if start > 0 {
+                segments.push(Segment::Literal(String::from(&rest[..start])));
+            }
+
+            let end = match rest[start..].find('}') {
+                Some(i) => start + i,
+                None => return Err(format!("Unclosed token in template: {}", template)),
+            };
+
+            let token = &rest[start + 1..end];
+            let (token_name, format) = match token.split_once(':') {
+                Some((n, f)) => (n, Some(f)),
+                None => (token, None),
+            };
+
+            let token = match token_name {
+                "project" => Token::Project,
+                "task" => Token::Task,
+                "name" => Token::Name,
+                "version" => {
+                    let padding = match format {
+                        Some(f) => match f.parse::<usize>() {
+                            Ok(p) => p,
+                            Err(_e) => return Err(format!("Invalid version padding: {}", f)),
+                        },
+                        None => DEFAULT_VERSION_PADDING,
+                    };
+                    Token::Version(padding)
+                }
+                _ => return Err(format!("Unknown token in template: {{{}}}", token)),
+            };
+            segments.push(Segment::Token(token));
+            rest = &rest[end + 1..];
+        }
+
+        Ok(segments)
+    }
+
+    /// A literal in front of the optional name token loses its trailing separator, which moves into the optional group.
+    fn split_separator(segments: &[Segment], index: usize, literal: &str) -> (String, String) {
+        let next_is_name = matches!(segments.get(index + 1), Some(Segment::Token(Token::Name)));
+        match literal.chars().last() {
+            Some(c) if next_is_name && "_-. ".contains(c) => (
+                String::from(&literal[..literal.len() - c.len_utf8()]),
+                c.to_string(),
+            ),
+            _ => (String::from(literal), String::new()),
+        }
+    }
+
+    /// Known project and task names are matched literally, as the token groups can't tell which separators
+    /// belong to the names.
+    fn make_pattern(segments: &[Segment], known: Option<(&str, &str)>) -> String {
+        let mut pattern = String::from("^");
+        let mut separator = String::new();
+
+        for (i, segment) in segments.iter().enumerate() {
+            match segment {
+                Segment::Literal(l) => {
+                    let (literal, sep) = Self::split_separator(segments, i, l);
+                    pattern.push_str(&regex::escape(&literal));
+                    separator = sep;
+                }
+                Segment::Token(Token::Project) => match known {
+                    Some((project, _)) => {
+                        pattern.push_str(&format!("(?P<project>{})", regex::escape(project)))
+                    }
+                    None => pattern.push_str("(?P<project>.+?)"),
+                },
+                Segment::Token(Token::Task) => match known {
+                    Some((_, task)) => {
+                        pattern.push_str(&format!("(?P<task>{})", regex::escape(task)))
+                    }
+                    None => pattern.push_str("(?P<task>.+?)"),
+                },
+                Segment::Token(Token::Name) => {
+                    pattern.push_str(&format!("(?:{}(?P<name>.+?))?", regex::escape(&separator)))
+                }
+                Segment::Token(Token::Version(_)) => pattern.push_str(r"(?P<version>\d+)"),
+            }
+        }
+        pattern.push_str(SUFFIX_PATTERN);
+        pattern.push('$');
+        pattern
+    }
+
+    /// Returns a file stem from the tokens, without extension.
+    pub fn format(&self, tokens: &FilenameTokens) -> String {
+        let mut output = String::new();
+
+        for (i, segment) in self.segments.iter().enumerate() {
+            match segment {
+                Segment::Literal(l) => {
+                    let (literal, sep) = Self::split_separator(&self.segments, i, l);
+                    output.push_str(&literal);
+                    if !tokens.name.is_empty() {
+                        output.push_str(&sep);
+                    }
+                }
+                Segment::Token(Token::Project) => output.push_str(&tokens.project),
+                Segment::Token(Token::Task) => output.push_str(&tokens.task),
+                Segment::Token(Token::Name) => output.push_str(&tokens.name),
+                Segment::Token(Token::Version(p)) => {
+                    output.push_str(&format!("{:0width$}", tokens.version, width = p))
+                }
+            }
+        }
+        output
+    }
+
+    /// Whether the file stem follows the template, rather than only having a trailing version token.
+    pub fn matches(&self, stem: &str) -> bool {
+        self.regex.is_match(stem)
+    }
+
+    /// Parses a file stem, without extension. Files that don't follow the template are scanned for a trailing
+    /// version token instead, e.g. `asset_v12` or `asset_v012_wip`, so hand-named files still show up.
+    /// The project and task tokens can't contain the separators, use `parse_in` when they are known.
+    pub fn parse(&self, stem: &str) -> Result<ParsedFilename, ParseError> {
+        self.parse_with(&self.regex, stem)
+    }
+
+    /// Parses a file stem of a known project and task, e.g. `1234_acme_show_comp_main_v004` of project
+    /// `1234_acme_show`. Stems not named after them are parsed as by `parse`.
+    pub fn parse_in(
+        &self,
+        project: &str,
+        task: &str,
+        stem: &str,
+    ) -> Result<ParsedFilename, ParseError> {
+        let key = (String::from(project), String::from(task));
+        let cached = match self.known.read() {
+            Ok(k) => k.get(&key).cloned(),
+            Err(_e) => None,
+        };
+        let regex = match cached {
+            Some(r) => r,
+            None => match Regex::new(&Self::make_pattern(&self.segments, Some((project, task)))) {
+                Ok(r) => {
+                    if let Ok(mut k) = self.known.write() {
+                        if k.len() >= MAX_KNOWN_PATTERNS {
+                            k.clear();
+                        }
+                        k.insert(key, r.clone());
+                    }
+                    r
+                }
+                Err(_e) => return self.parse(stem),
+            },
+        };
+        if regex.is_match(stem) {
+            self.parse_with(&regex, stem)
+        } else {
+            self.parse(stem)
+        }
+    }
+
+    fn parse_with(&self, regex: &Regex, stem: &str) -> Result<ParsedFilename, ParseError> {
+        if stem.is_empty() {
+            return Err(ParseError::Empty);
+        }
+
+        let (captures, version_literal) = match regex.captures(stem) {
+            Some(c) => (c, self.version_literal()),
+            None => match self.fallback.captures(stem) {
+                Some(c) => {
+                    let literal = c.name("literal").map(|m| m.as_str()).unwrap_or("");
+                    (c, literal)
+                }
+                None => return Err(ParseError::NoVersion(String::from(stem))),
+            },
+        };
+
+        let version_match = match captures.name("version") {
+            Some(m) => m,
+            None => return Err(ParseError::NoVersion(String::from(stem))),
+        };
+        let version: u32 = match version_match.as_str().parse() {
+            Ok(v) => v,
+            Err(_e) => {
+                return Err(ParseError::InvalidVersion(String::from(
+                    version_match.as_str(),
+                )))
+            }
+        };
+        let get = |n: &str| {
+            captures
+                .name(n)
+                .map(|m| String::from(m.as_str()))
+                .unwrap_or_default()
+        };
+
+        let prefix = String::from(&stem[..version_match.start()]);
+        let name = String::from(prefix.strip_suffix(version_literal).unwrap_or(&prefix));
+
+        Ok(ParsedFilename {
+            tokens: FilenameTokens {
+                project: get("project"),
+                task: get("task"),
+                name: get("name"),
+                version,
+            },
+            name,
+            prefix,
+            suffix: String::from(&stem[version_match.end()..]),
+            padding: version_match.as_str().len(),
+        })
+    }
+
+    /// The literal in front of the version token, e.g. `_v`.
+    fn version_literal(&self) -> &str {
+        self.segments
+            .iter()
+            .position(|s| matches!(s, Segment::Token(Token::Version(_))))
+            .and_then(|i| i.checked_sub(1))
+            .and_then(|i| match &self.segments[i] {
+                Segment::Literal(l) => Some(l.as_str()),
+                _ => None,
+            })
+            .unwrap_or("")
+    }
+}
+
+/// Describes why a filename could not be parsed.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseError {
+    Empty,
+    NoVersion(String),
+    InvalidVersion(String),
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::Empty => write!(f, "Empty filename."),
+            ParseError::NoVersion(s) => write!(f, "No version number found in {}.", s),
+            ParseError::InvalidVersion(s) => write!(f, "Invalid version number: {}.", s),
+        }
+    }
+}
+
+impl Default for FilenameTemplate {
+    fn default() -> Self {
+        FilenameTemplate::new(DEFAULT_FILENAME_TEMPLATE).expect("default template is valid")
+    }
+}
+
+impl PartialEq for FilenameTemplate {
+    fn eq(&self, other: &Self) -> bool {
+        self.template == other.template
+    }
+}
+
+impl TryFrom<String> for FilenameTemplate {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        FilenameTemplate::new(&value)
+    }
+}
+
+impl From<FilenameTemplate> for String {
+    fn from(value: FilenameTemplate) -> Self {
+        value.template
+    }
+}
+
+pub fn default_filename_template() -> FilenameTemplate {
+    FilenameTemplate::default()
+}
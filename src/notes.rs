@@ -0,0 +1,90 @@
+use crate::atomic::write_yaml;
+use log::{error, info};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+pub const NOTES_FILE_NAME: &str = "notes.yaml";
+
+/// Freeform notes and tags of a workfile, e.g. `approved`, `wip` or `client_v2`.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct FileNote {
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub notes: String,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+}
+
+impl FileNote {
+    pub fn is_empty(&self) -> bool {
+        self.notes.trim().is_empty() && self.tags.is_empty()
+    }
+
+    /// Adds a tag, trimmed and lowercase. Empty and duplicate tags are ignored.
+    pub fn add_tag(&mut self, tag: &str) {
+        let tag = tag.trim().to_lowercase();
+        if !tag.is_empty() && !self.tags.contains(&tag) {
+            self.tags.push(tag);
+        }
+    }
+}
+
+/// The notes of the workfiles in a task by file name, stored in `notes.yaml` in the task dir.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, Default, PartialEq)]
+#[serde(transparent)]
+pub struct FileNotes {
+    files: BTreeMap<String, FileNote>,
+}
+
+impl FileNotes {
+    /// Reads the notes of a task. A missing file means no notes.
+    pub fn load(task_path: &Path) -> Result<FileNotes, io::Error> {
+        let path = task_path.join(NOTES_FILE_NAME);
+        if !path.exists() {
+            return Ok(FileNotes::default());
+        }
+        let file = fs::File::open(&path)?;
+        serde_yaml::from_reader(file).map_err(|e| {
+            error!("Failed to read {}: {}", path.display(), e);
+            io::Error::new(io::ErrorKind::Other, e.to_string())
+        })
+    }
+
+    pub fn save(&self, task_path: &Path) -> Result<(), io::Error> {
+        let path = task_path.join(NOTES_FILE_NAME);
+        info!("Writing notes: {}", path.display());
+        write_yaml(&path, self)
+    }
+
+    pub fn get(&self, file_name: &str) -> Option<&FileNote> {
+        self.files.get(file_name)
+    }
+
+    /// Replaces the note of a file, empty notes are removed.
+    pub fn set(&mut self, file_name: &str, note: FileNote) {
+        if note.is_empty() {
+            self.files.remove(file_name);
+        } else {
+            self.files.insert(String::from(file_name), note);
+        }
+    }
+
+    /// All tags in use, sorted.
+    pub fn tags(&self) -> Vec<String> {
+        let mut tags: Vec<String> = self
+            .files
+            .values()
+            .flat_map(|n| n.tags.iter().cloned())
+            .collect();
+        tags.sort();
+        tags.dedup();
+        tags
+    }
+
+    pub fn has_tag(&self, file_name: &str, tag: &str) -> bool {
+        self.get(file_name)
+            .map_or(false, |n| n.tags.iter().any(|t| t == tag))
+    }
+}
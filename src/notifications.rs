@@ -0,0 +1,145 @@
+use crate::helpers::{format_timestamp, now_timestamp};
+use crate::i18n::tr;
+use egui::Color32;
+use std::time::{Duration, Instant};
+
+const MAX_TOASTS: usize = 5;
+const MAX_HISTORY: usize = 200;
+const TOAST_WIDTH: f32 = 320.;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    pub fn color(&self) -> Color32 {
+        match self {
+            Severity::Info => Color32::from_rgb(80, 160, 230),
+            Severity::Warning => Color32::from_rgb(230, 170, 40),
+            Severity::Error => Color32::RED,
+        }
+    }
+
+    /// How long a toast stays on screen, errors stay longer so they can be copied.
+    fn duration(&self) -> Duration {
+        match self {
+            Severity::Info => Duration::from_secs(4),
+            Severity::Warning => Duration::from_secs(8),
+            Severity::Error => Duration::from_secs(15),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct Toast {
+    pub text: String,
+    pub severity: Severity,
+    pub timestamp: u64,
+    created: Instant,
+}
+
+/// Stacked, auto-expiring toasts in the bottom right corner, with a history of all notifications.
+#[derive(Debug, Default)]
+pub struct Notifications {
+    toasts: Vec<Toast>,
+    history: Vec<Toast>,
+}
+
+impl Notifications {
+    pub fn push(&mut self, severity: Severity, text: impl Into<String>) {
+        let toast = Toast {
+            text: text.into(),
+            severity,
+            timestamp: now_timestamp(),
+            created: Instant::now(),
+        };
+
+        self.history.push(toast.clone());
+        if self.history.len() > MAX_HISTORY {
+            self.history.remove(0);
+        }
+        self.toasts.push(toast);
+        if self.toasts.len() > MAX_TOASTS {
+            self.toasts.remove(0);
+        }
+    }
+
+    /// Draws the active toasts. Clicking a toast dismisses it, errors are copied to the clipboard first.
+    pub fn show(&mut self, ctx: &egui::Context) {
+        self.toasts
+            .retain(|t| t.created.elapsed() < t.severity.duration());
+        if self.toasts.is_empty() {
+            return;
+        }
+
+        let mut dismissed = None;
+        egui::Area::new("toasts")
+            .anchor(egui::Align2::RIGHT_BOTTOM, egui::vec2(-10., -10.))
+            .order(egui::Order::Foreground)
+            .show(ctx, |ui| {
+                for (i, toast) in self.toasts.iter().enumerate() {
+                    let response = egui::Frame::popup(ui.style())
+                        .stroke(egui::Stroke::new(1.5, toast.severity.color()))
+                        .show(ui, |ui| {
+                            ui.set_width(TOAST_WIDTH);
+                            ui.add(egui::Label::new(
+                                egui::RichText::new(&toast.text).color(toast.severity.color()),
+                            ))
+                        })
+                        .response
+                        .interact(egui::Sense::click());
+
+                    let response = if toast.severity == Severity::Error {
+                        response.on_hover_text(tr("Click to copy details"))
+                    } else {
+                        response
+                    };
+                    if response.clicked() {
+                        if toast.severity == Severity::Error {
+                            ctx.output_mut(|o| o.copied_text = toast.text.clone());
+                        }
+                        dismissed = Some(i);
+                    }
+                }
+            });
+
+        if let Some(i) = dismissed {
+            self.toasts.remove(i);
+        }
+        ctx.request_repaint_after(Duration::from_millis(500));
+    }
+
+    /// Lists all notifications of this session, newest first.
+    pub fn history_window(&mut self, ctx: &egui::Context, open: &mut bool) {
+        egui::Window::new(tr("Notifications"))
+            .open(open)
+            .default_width(500.)
+            .show(ctx, |ui| {
+                if self.history.is_empty() {
+                    ui.label(tr("No notifications."));
+                    return;
+                }
+                if ui.button(tr("Clear")).clicked() {
+                    self.history.clear();
+                }
+                ui.separator();
+
+                egui::ScrollArea::vertical().show(ui, |ui| {
+                    for toast in self.history.iter().rev() {
+                        ui.horizontal(|ui| {
+                            ui.label(format_timestamp(toast.timestamp));
+                            ui.label(
+                                egui::RichText::new(&toast.text).color(toast.severity.color()),
+                            );
+                            if ui.small_button("📋").on_hover_text(tr("Copy")).clicked() {
+                                ui.output_mut(|o| o.copied_text = toast.text.clone());
+                            }
+                        });
+                    }
+                });
+            });
+    }
+}
@@ -0,0 +1,101 @@
+use crate::commands::Command;
+use log::{info, warn};
+use std::io;
+use std::path::Path;
+use std::sync::mpsc::{channel, Receiver, TryRecvError};
+use std::thread;
+use std::time::Duration;
+
+/// Seconds between checks whether an unreachable projects dir is back.
+pub const RECONNECT_INTERVAL: u64 = 10;
+/// A dead network mount can hang instead of failing, so checks give up after this long.
+const CHECK_TIMEOUT: Duration = Duration::from_secs(3);
+
+/// Checks the dir on its own thread, a hung mount only blocks that thread.
+fn spawn_check(dir: &Path) -> Receiver<bool> {
+    let (sender, receiver) = channel();
+    let dir = dir.to_path_buf();
+    thread::spawn(move || {
+        let _ = sender.send(dir.is_dir());
+    });
+    receiver
+}
+
+/// Whether the dir can be reached within a few seconds.
+pub fn is_reachable(dir: &Path) -> bool {
+    spawn_check(dir)
+        .recv_timeout(CHECK_TIMEOUT)
+        .unwrap_or(false)
+}
+
+/// For data that isn't in the scan cache while offline.
+pub fn not_cached_error() -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotConnected,
+        "The projects dir can't be reached and it isn't cached.",
+    )
+}
+
+/// Whether the projects dir can be reached. While it can't, the app shows the cached projects and
+/// task trees and keeps the changes to the share for when it's back.
+#[derive(Debug, Default)]
+pub struct Connectivity {
+    offline: bool,
+    /// When the last check was started.
+    checked_at: u64,
+    check: Option<Receiver<bool>>,
+    queued: Vec<Command>,
+}
+
+impl Connectivity {
+    pub fn is_offline(&self) -> bool {
+        self.offline
+    }
+
+    /// Switches to offline mode, the dir is checked again every `RECONNECT_INTERVAL` seconds.
+    pub fn set_offline(&mut self, dir: &Path, now: u64) {
+        if !self.offline {
+            warn!("{} can't be reached, working offline", dir.display());
+        }
+        self.offline = true;
+        self.checked_at = now;
+    }
+
+    /// Checks whether the dir is back without blocking. Returns true once when it is.
+    pub fn poll(&mut self, dir: &Path, now: u64) -> bool {
+        if !self.offline {
+            return false;
+        }
+        let due = now >= self.checked_at + RECONNECT_INTERVAL;
+        match self.check.as_ref().map(|c| c.try_recv()) {
+            Some(Ok(true)) => {
+                info!("{} can be reached again", dir.display());
+                self.offline = false;
+                self.check = None;
+                return true;
+            }
+            // A hung check is given up on at the next interval.
+            Some(Err(TryRecvError::Empty)) if !due => return false,
+            _ => self.check = None,
+        }
+        if due {
+            self.checked_at = now;
+            self.check = Some(spawn_check(dir));
+        }
+        false
+    }
+
+    /// Keeps a command that changes the share, to run once it's back.
+    pub fn queue(&mut self, command: Command) {
+        info!("Queued until back online: {:?}", command);
+        self.queued.push(command);
+    }
+
+    pub fn queued(&self) -> &[Command] {
+        &self.queued
+    }
+
+    pub fn take_queued(&mut self) -> Vec<Command> {
+        std::mem::take(&mut self.queued)
+    }
+}
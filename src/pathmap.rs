@@ -0,0 +1,216 @@
+use log::error;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::RwLock;
+
+/// The same share as seen from each OS, e.g. `P:\`, `\\server\projects`, `/Volumes/projects` and
+/// `/mnt/projects`. Empty prefixes are skipped.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct PathMapping {
+    pub windows: String,
+    pub unc: String,
+    pub mac: String,
+    pub linux: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PathStyle {
+    Windows,
+    Unc,
+    Mac,
+    Linux,
+}
+
+impl PathStyle {
+    /// The style of paths on this machine.
+    pub fn local() -> PathStyle {
+        if cfg!(windows) {
+            PathStyle::Windows
+        } else if cfg!(target_os = "macos") {
+            PathStyle::Mac
+        } else {
+            PathStyle::Linux
+        }
+    }
+
+    fn separator(&self) -> char {
+        match self {
+            PathStyle::Windows | PathStyle::Unc => '\\',
+            PathStyle::Mac | PathStyle::Linux => '/',
+        }
+    }
+}
+
+impl PathMapping {
+    fn prefix(&self, style: PathStyle) -> &str {
+        match style {
+            PathStyle::Windows => &self.windows,
+            PathStyle::Unc => &self.unc,
+            PathStyle::Mac => &self.mac,
+            PathStyle::Linux => &self.linux,
+        }
+    }
+
+    /// The rest of the path after one of the prefixes, with forward slashes.
+    fn strip(&self, path: &str) -> Option<String> {
+        let path = path.replace('\\', "/");
+        [
+            PathStyle::Windows,
+            PathStyle::Unc,
+            PathStyle::Mac,
+            PathStyle::Linux,
+        ]
+        .iter()
+        .map(|s| self.prefix(*s).replace('\\', "/"))
+        .filter(|p| !p.is_empty())
+        .find_map(|prefix| {
+            let prefix = prefix.trim_end_matches('/');
+            let head = path.get(..prefix.len())?;
+            let rest = &path[prefix.len()..];
+            // Drive letters and UNC paths are case-insensitive.
+            let matches = if prefix.starts_with('/') && !prefix.starts_with("//") {
+                head == prefix
+            } else {
+                head.eq_ignore_ascii_case(prefix)
+            };
+            (matches && (rest.is_empty() || rest.starts_with('/')))
+                .then(|| rest.trim_start_matches('/').to_string())
+        })
+    }
+
+    /// Translates a path matching this mapping to the style.
+    fn map(&self, path: &str, style: PathStyle) -> Option<String> {
+        let target = self.prefix(style);
+        if target.is_empty() {
+            return None;
+        }
+        let rest = self.strip(path)?;
+        let separator = style.separator();
+        let target = target.trim_end_matches(['/', '\\']);
+        let rest = rest.replace('/', &separator.to_string());
+        if rest.is_empty() {
+            Some(target.to_string())
+        } else {
+            Some(format!("{}{}{}", target, separator, rest))
+        }
+    }
+}
+
+/// Mappings from the config, set once the config is loaded.
+static MAPPINGS: RwLock<Vec<PathMapping>> = RwLock::new(Vec::new());
+
+/// Sets the configured mappings used by `to_local`.
+pub fn set_mappings(mappings: &[PathMapping]) {
+    match MAPPINGS.write() {
+        Ok(mut m) => *m = mappings.to_vec(),
+        Err(e) => error!("Failed to set path mappings: {}", e),
+    }
+}
+
+/// Stored paths are translated to UNC paths rather than drive letters, so they compare equal to the
+/// paths scanned from a projects dir given as a UNC path.
+static PREFER_UNC: AtomicBool = AtomicBool::new(false);
+
+/// Whether the path is a UNC path like `\\server\projects`.
+pub fn is_unc(path: &Path) -> bool {
+    let path = path.to_string_lossy();
+    path.starts_with("\\\\") || path.starts_with("//")
+}
+
+/// Sets the projects dir stored paths are compared with, see `to_local`.
+pub fn set_projects_dir(dir: &Path) {
+    PREFER_UNC.store(cfg!(windows) && is_unc(dir), Ordering::Relaxed);
+}
+
+/// The network drives mapped on Windows, from `net use`. Empty on other OS:s.
+pub fn detect_mapped_drives() -> Vec<PathMapping> {
+    if !cfg!(windows) {
+        return Vec::new();
+    }
+    match Command::new("net").arg("use").output() {
+        Ok(o) if o.status.success() => parse_net_use(&String::from_utf8_lossy(&o.stdout)),
+        Ok(_o) => Vec::new(),
+        Err(e) => {
+            error!("Failed to list mapped drives: {}", e);
+            Vec::new()
+        }
+    }
+}
+
+/// Reads the drive letters and shares in the output of `net use`, with lines like
+/// `OK  P:  \\server\projects  Microsoft Windows Network`.
+pub fn parse_net_use(text: &str) -> Vec<PathMapping> {
+    text.lines()
+        .filter_map(|line| {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            words.windows(2).find_map(|w| {
+                let drive = w[0].as_bytes();
+                let is_drive =
+                    drive.len() == 2 && drive[0].is_ascii_alphabetic() && drive[1] == b':';
+                (is_drive && w[1].starts_with("\\\\")).then(|| PathMapping {
+                    windows: format!("{}\\", w[0].to_uppercase()),
+                    unc: w[1].to_string(),
+                    ..Default::default()
+                })
+            })
+        })
+        .collect()
+}
+
+/// The configured mappings followed by the detected ones whose drive or share isn't in them.
+pub fn with_detected(configured: &[PathMapping], detected: Vec<PathMapping>) -> Vec<PathMapping> {
+    let key = |prefix: &str| prefix.trim_end_matches(['/', '\\']).to_lowercase();
+    let mut mappings = configured.to_vec();
+    for mapping in detected {
+        let known = configured.iter().any(|m| {
+            (!m.windows.is_empty() && key(&m.windows) == key(&mapping.windows))
+                || (!m.unc.is_empty() && key(&m.unc) == key(&mapping.unc))
+        });
+        if !known {
+            mappings.push(mapping);
+        }
+    }
+    mappings
+}
+
+/// Translates a path to the given style with the first matching mapping. Returns None if no mapping
+/// matches or the mapping has no prefix for the style.
+pub fn map_path(path: &Path, mappings: &[PathMapping], style: PathStyle) -> Option<String> {
+    let path = path.to_string_lossy();
+    mappings.iter().find_map(|m| m.map(&path, style))
+}
+
+/// Translates a stored path, possibly written on another OS, to this machine using the configured
+/// mappings. On Windows the drive letter is preferred over the UNC path, unless the projects dir is a
+/// UNC path. Paths without a matching mapping are returned as is.
+pub fn to_local(path: &Path) -> PathBuf {
+    translate(path, PREFER_UNC.load(Ordering::Relaxed))
+}
+
+/// Like `to_local`, but always prefers drive letters, for the paths apps are started with.
+pub fn to_launch(path: &Path) -> PathBuf {
+    translate(path, false)
+}
+
+fn translate(path: &Path, prefer_unc: bool) -> PathBuf {
+    let mappings = match MAPPINGS.read() {
+        Ok(m) => m,
+        Err(_e) => return path.to_path_buf(),
+    };
+    let mut styles = vec![PathStyle::local()];
+    if cfg!(windows) {
+        styles.push(PathStyle::Unc);
+    }
+    if prefer_unc {
+        styles.reverse();
+    }
+    let path_string = path.to_string_lossy();
+
+    mappings
+        .iter()
+        .find_map(|m| styles.iter().find_map(|s| m.map(&path_string, *s)))
+        .map(PathBuf::from)
+        .unwrap_or_else(|| path.to_path_buf())
+}
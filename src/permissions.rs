@@ -0,0 +1,132 @@
+use log::error;
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::RwLock;
+
+/// Permissions given to the dirs rclamp creates and the files it copies, so they follow the policy of
+/// shared storage without scripts fixing them afterwards. The default leaves them as the OS makes them.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, Default, PartialEq)]
+pub struct CreatePermissions {
+    /// Octal bits taken from the mode of created dirs and files on Unix, like a umask, e.g. `"007"`.
+    #[serde(default)]
+    pub umask: Option<String>,
+    /// The group name or id created dirs and files are given on Unix.
+    #[serde(default)]
+    pub group: Option<String>,
+    /// Makes copied outputs, like dailies and delivered files, read-only, also on Windows. Workfiles stay
+    /// writable, DCC:s can't save over read-only files.
+    #[serde(default)]
+    pub read_only_files: bool,
+}
+
+/// What was created, decides which permissions it gets.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Created {
+    Dir,
+    /// A workfile or other file artists keep working on.
+    File,
+    /// A copied output, read-only with `read_only_files`.
+    Output,
+}
+
+impl CreatePermissions {
+    /// The umask as bits, e.g. `"0o027"` or `"027"`. None if not set.
+    pub fn umask_bits(&self) -> Result<Option<u32>, String> {
+        let umask = match &self.umask {
+            Some(u) if !u.trim().is_empty() => u.trim(),
+            _ => return Ok(None),
+        };
+        match u32::from_str_radix(umask.trim_start_matches("0o"), 8) {
+            Ok(bits) if bits <= 0o777 => Ok(Some(bits)),
+            _ => Err(format!("Invalid umask: {}", umask)),
+        }
+    }
+
+    /// Sets the permissions of a created dir or copied file.
+    pub fn apply(&self, path: &Path, created: Created) -> Result<(), io::Error> {
+        if *self == Self::default() {
+            return Ok(());
+        }
+        let is_dir = created == Created::Dir;
+        let read_only = self.read_only_files && created == Created::Output;
+        let mut permissions = fs::metadata(path)?.permissions();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            use std::process::Command;
+            let umask = self
+                .umask_bits()
+                .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+            let mut mode = permissions.mode();
+            if let Some(umask) = umask {
+                // Executable files stay executable, like dirs.
+                let full = if is_dir || mode & 0o111 != 0 {
+                    0o777
+                } else {
+                    0o666
+                };
+                mode = (mode & !0o777) | (full & !umask);
+            }
+            if read_only {
+                mode &= !0o222;
+            }
+            permissions.set_mode(mode);
+            if let Some(group) = self.group.as_deref().filter(|g| !g.trim().is_empty()) {
+                let output = Command::new("chgrp").arg(group.trim()).arg(path).output()?;
+                if !output.status.success() {
+                    return Err(io::Error::new(
+                        io::ErrorKind::PermissionDenied,
+                        String::from_utf8_lossy(&output.stderr).trim().to_string(),
+                    ));
+                }
+            }
+        }
+        #[cfg(not(unix))]
+        if read_only {
+            permissions.set_readonly(true);
+        }
+        fs::set_permissions(path, permissions)
+    }
+}
+
+/// The permissions from the config, set once the config is loaded.
+static PERMISSIONS: RwLock<CreatePermissions> = RwLock::new(CreatePermissions {
+    umask: None,
+    group: None,
+    read_only_files: false,
+});
+
+/// Sets the configured permissions used by `apply_to_dir`, `apply_to_file` and `apply_to_output`.
+pub fn set_create_permissions(permissions: &CreatePermissions) {
+    match PERMISSIONS.write() {
+        Ok(mut p) => *p = permissions.clone(),
+        Err(e) => error!("Failed to set create permissions: {}", e),
+    }
+}
+
+fn apply(path: &Path, created: Created) {
+    let permissions = match PERMISSIONS.read() {
+        Ok(p) => p.clone(),
+        Err(_e) => return,
+    };
+    // The dir or file is there, failing the whole operation over its permissions would leave it behind.
+    if let Err(e) = permissions.apply(path, created) {
+        error!("Failed to set the permissions of {}: {}", path.display(), e);
+    }
+}
+
+/// Gives a created dir the configured permissions.
+pub fn apply_to_dir(path: &Path) {
+    apply(path, Created::Dir)
+}
+
+/// Gives a copied file the configured permissions.
+pub fn apply_to_file(path: &Path) {
+    apply(path, Created::File)
+}
+
+/// Gives a copied output the configured permissions, read-only with `read_only_files`.
+pub fn apply_to_output(path: &Path) {
+    apply(path, Created::Output)
+}
@@ -0,0 +1,241 @@
+use crate::ignore::IgnorePatterns;
+use crate::vfs::RealFs;
+use crate::workfiles::FileGroup;
+use crate::{File, Project, TaskTreeNode};
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub use crate::naming::FilenameTemplate;
+pub use crate::pathmap::PathMapping;
+pub use crate::projects::ProjectCreateError;
+pub use crate::workfiles::Dcc;
+
+/// The studio setup that project, task and file operations need, made once from the config and passed
+/// along so the paths are worked out in one place. Path mappings, permissions and retries are global
+/// settings, see `pathmap::set_mappings`, and aren't repeated here.
+#[derive(Clone, Debug)]
+pub struct PipelineContext {
+    pub projects_dir: PathBuf,
+    pub templates_dir: PathBuf,
+    /// New projects get the dir names of this one.
+    pub template_project: Project,
+    pub filename_template: FilenameTemplate,
+    /// Apply to the workfiles of all projects, along with the patterns of the project.
+    pub ignore_patterns: Vec<String>,
+}
+
+impl PipelineContext {
+    /// A context with the default naming and no ignore patterns.
+    pub fn new(projects_dir: PathBuf, templates_dir: PathBuf, template_project: Project) -> Self {
+        Self {
+            projects_dir,
+            templates_dir,
+            template_project,
+            filename_template: FilenameTemplate::default(),
+            ignore_patterns: Vec::new(),
+        }
+    }
+
+    pub fn project_path(&self, project: &Project) -> PathBuf {
+        project.get_path(&self.projects_dir)
+    }
+
+    pub fn work_path(&self, project: &Project) -> PathBuf {
+        project.get_work_path(&self.projects_dir)
+    }
+
+    pub fn pipeline_path(&self, project: &Project) -> PathBuf {
+        project.get_pipeline_path(&self.projects_dir)
+    }
+
+    /// The dir in each task the workfiles are in.
+    pub fn work_dir_name<'a>(&self, project: &'a Project) -> &'a str {
+        project.task_work_dir()
+    }
+
+    /// The dir in each task renders and other outputs are written to.
+    pub fn output_dir_name<'a>(&self, project: &'a Project) -> &'a str {
+        project.task_output_dir()
+    }
+
+    /// Scans the folders and tasks in the work dir of the project.
+    pub fn task_tree(&self, project: &Project) -> Result<TaskTreeNode, io::Error> {
+        TaskTreeNode::from_path(
+            self.work_path(project),
+            self.work_dir_name(project),
+            self.output_dir_name(project),
+            0,
+        )
+    }
+
+    /// The patterns of the context combined with the ones of the project.
+    pub fn ignore_patterns(&self, project: Option<&Project>) -> Result<IgnorePatterns, String> {
+        let mut patterns = self.ignore_patterns.clone();
+        if let Some(p) = project {
+            patterns.extend(p.ignore_patterns.iter().cloned());
+        }
+        IgnorePatterns::new(&patterns)
+    }
+}
+
+/// The projects in the projects dir of the context.
+#[derive(Clone, Debug)]
+pub struct ProjectStore {
+    context: PipelineContext,
+}
+
+impl ProjectStore {
+    pub fn new(context: PipelineContext) -> Self {
+        Self { context }
+    }
+
+    pub fn context(&self) -> &PipelineContext {
+        &self.context
+    }
+
+    /// All projects in the projects dir, sorted.
+    pub fn projects(&self) -> Result<Vec<Project>, io::Error> {
+        Project::find_projects_with(&RealFs, &self.context.projects_dir)
+    }
+
+    /// The project with the name or the folder name.
+    pub fn find(&self, name: &str) -> Result<Option<Project>, io::Error> {
+        Ok(self
+            .projects()?
+            .into_iter()
+            .find(|p| p.name == name || p.name_sanitized == name))
+    }
+
+    /// Creates the project folders. Nothing is left behind if a step fails.
+    pub fn create(&self, project: &Project) -> Result<(), ProjectCreateError> {
+        project.create(
+            self.context.projects_dir.clone(),
+            &self.context.templates_dir,
+        )
+    }
+
+    pub fn path(&self, project: &Project) -> PathBuf {
+        self.context.project_path(project)
+    }
+
+    /// The tasks of the project.
+    pub fn tasks(&self, project: &Project) -> TaskStore {
+        TaskStore {
+            project: project.clone(),
+            context: self.context.clone(),
+        }
+    }
+}
+
+/// The task folders in the work dir of a project.
+#[derive(Clone, Debug)]
+pub struct TaskStore {
+    project: Project,
+    context: PipelineContext,
+}
+
+impl TaskStore {
+    pub fn work_path(&self) -> PathBuf {
+        self.context.work_path(&self.project)
+    }
+
+    /// The folders and tasks in the work dir.
+    pub fn tree(&self) -> Result<TaskTreeNode, io::Error> {
+        self.context.task_tree(&self.project)
+    }
+
+    /// All tasks in the work dir, without the folders they're in.
+    pub fn tasks(&self) -> Result<Vec<TaskTreeNode>, io::Error> {
+        Ok(self.tree()?.tasks().into_iter().cloned().collect())
+    }
+
+    /// The task at the path, relative to the work dir, e.g. `sq010/sh010/comp`.
+    pub fn find(&self, path: &Path) -> Result<Option<TaskTreeNode>, io::Error> {
+        let path = self.work_path().join(path);
+        Ok(self.tasks()?.into_iter().find(|t| t.path == path))
+    }
+
+    /// Creates a task in the folder, relative to the work dir, and returns it.
+    pub fn create(&self, folder: &Path, name: &str) -> Result<TaskTreeNode, io::Error> {
+        let work_dir_name = self.context.work_dir_name(&self.project);
+        let output_dir_name = self.context.output_dir_name(&self.project);
+        let path = self.work_path().join(folder);
+        let parent = TaskTreeNode::new(String::new(), path, work_dir_name, output_dir_name);
+        parent.create_task(String::from(name), self.project.clone())?;
+        let mut task = TaskTreeNode::new(
+            String::from(name),
+            parent.path.join(name),
+            work_dir_name,
+            output_dir_name,
+        );
+        task.metadata.is_task = true;
+        Ok(task)
+    }
+
+    /// The workfiles of the task.
+    pub fn workfiles(&self, task: &TaskTreeNode) -> WorkfileStore {
+        WorkfileStore {
+            project: self.project.clone(),
+            task: task.clone(),
+            context: self.context.clone(),
+        }
+    }
+}
+
+/// The workfiles in the work dir of a task.
+#[derive(Clone, Debug)]
+pub struct WorkfileStore {
+    project: Project,
+    task: TaskTreeNode,
+    context: PipelineContext,
+}
+
+impl WorkfileStore {
+    /// All versions of all workfiles, sorted by name and version. The ignore patterns of the context
+    /// and the project apply.
+    pub fn files(&self) -> Result<Vec<File>, io::Error> {
+        let ignore = self
+            .context
+            .ignore_patterns(Some(&self.project))
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        let mut files = self.task.find_workfiles(
+            self.task.metadata.work_dir_name.clone(),
+            &self.context.filename_template,
+            &ignore,
+        )?;
+        files.sort();
+        Ok(files)
+    }
+
+    /// The latest version of each workfile.
+    pub fn latest(&self) -> Result<Vec<File>, io::Error> {
+        Ok(FileGroup::group(&self.files()?)
+            .iter()
+            .filter_map(|g| g.latest().cloned())
+            .collect())
+    }
+
+    /// The DCC:s there are templates for.
+    pub fn dccs(&self) -> Result<Vec<Dcc>, io::Error> {
+        Dcc::find_dcc(&self.context.templates_dir)
+    }
+
+    /// Creates version 1 of a workfile from the template of the DCC, returning its path.
+    pub fn create(&self, name: &str, dcc: &Dcc) -> Result<PathBuf, io::Error> {
+        let template = &self.context.filename_template;
+        let path = File::new_file_path(name, &self.task, &self.project, dcc, template);
+        File::create_file(
+            String::from(name),
+            self.task.clone(),
+            self.project.clone(),
+            dcc.clone(),
+            template,
+        )?;
+        Ok(path)
+    }
+
+    /// Copies the file as the next version, returning the new path.
+    pub fn version_up(&self, file: &File) -> Result<PathBuf, io::Error> {
+        file.version_up()
+    }
+}
@@ -0,0 +1,60 @@
+use crate::sequences::OutputItem;
+use log::{error, info};
+use std::path::PathBuf;
+use std::process::Command;
+use std::thread;
+
+/// A review player like RV, mrv2 or djv. Arguments can use the tokens `{path}` for the file or first frame,
+/// `{sequence}` for the frame pattern with `#` padding, and `{first}`/`{last}` for the frame range.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, PartialEq)]
+pub struct Player {
+    pub command: PathBuf,
+    pub args: Vec<String>,
+}
+
+impl Player {
+    fn args(&self, item: &OutputItem) -> Vec<String> {
+        let path = item.first_path().display().to_string();
+        let (sequence, first, last) = match item {
+            OutputItem::Sequence(s) => (
+                s.dir.join(s.pattern()).display().to_string(),
+                s.first().to_string(),
+                s.last().to_string(),
+            ),
+            OutputItem::File { .. } => (path.clone(), String::new(), String::new()),
+        };
+
+        self.args
+            .iter()
+            .map(|a| {
+                a.replace("{path}", &path)
+                    .replace("{sequence}", &sequence)
+                    .replace("{first}", &first)
+                    .replace("{last}", &last)
+            })
+            .filter(|a| !a.is_empty())
+            .collect()
+    }
+
+    /// Starts the player with the item, without waiting for it to close.
+    pub fn play(&self, item: &OutputItem) -> Result<(), String> {
+        let args = self.args(item);
+        info!("Playing with {}: {:?}", self.command.display(), args);
+
+        match Command::new(&self.command).args(&args).spawn() {
+            Ok(mut c) => {
+                thread::spawn(move || c.wait());
+                Ok(())
+            }
+            Err(e) => {
+                let message = format!("Failed to start {}: {}", self.command.display(), e);
+                error!("{}", message);
+                Err(message)
+            }
+        }
+    }
+}
+
+pub fn default_player_args() -> Vec<String> {
+    vec![String::from("{path}")]
+}
@@ -0,0 +1,185 @@
+use crate::File;
+use crate::Project;
+use crate::TaskTreeNode;
+use log::{error, info};
+use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::thread;
+
+/// What a plugin can be run on, decides which context menus it shows up in.
+#[derive(Clone, Copy, serde::Deserialize, serde::Serialize, Debug, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PluginTarget {
+    Project,
+    Task,
+    File,
+}
+
+/// Optional `<plugin>.yaml` next to a plugin executable.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, Default)]
+#[serde(default)]
+struct PluginConfig {
+    label: Option<String>,
+    targets: Vec<PluginTarget>,
+}
+
+/// An executable or script in the plugins dir, shown as a context menu action.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, PartialEq)]
+pub struct Plugin {
+    pub label: String,
+    pub path: PathBuf,
+    pub targets: Vec<PluginTarget>,
+}
+
+/// Describes the selection a plugin is run on, written as JSON to the plugin's stdin.
+#[derive(serde::Serialize, Debug)]
+pub struct PluginPayload<'a> {
+    pub target: PluginTarget,
+    pub project: Option<&'a Project>,
+    pub project_path: Option<PathBuf>,
+    pub task_name: Option<&'a str>,
+    pub task_path: Option<&'a Path>,
+    pub files: Vec<&'a File>,
+}
+
+impl<'a> PluginPayload<'a> {
+    pub fn new(
+        target: PluginTarget,
+        project: Option<&'a Project>,
+        projects_dir: Option<&Path>,
+        task: Option<&'a TaskTreeNode>,
+    ) -> Self {
+        let project_path = match (project, projects_dir) {
+            (Some(p), Some(d)) => Some(p.get_path(&d.to_path_buf())),
+            _ => None,
+        };
+        Self {
+            target,
+            project,
+            project_path,
+            task_name: task.map(|t| t.name.as_str()),
+            task_path: task.map(|t| t.path.as_path()),
+            files: Vec::new(),
+        }
+    }
+}
+
+impl Plugin {
+    /// Finds executables in the plugins dir. A `<name>.yaml` next to a plugin can set its label and targets,
+    /// without one the plugin is shown for all targets.
+    pub fn find_plugins(plugins_dir: &Path) -> Result<Vec<Plugin>, io::Error> {
+        info!("Looking for plugins in: {}", plugins_dir.display());
+        let mut plugins = Vec::new();
+
+        for result in fs::read_dir(plugins_dir)? {
+            let item = match result {
+                Ok(i) => i,
+                Err(_e) => continue,
+            };
+            let path = item.path();
+            if !path.is_file() || !Self::is_executable(&path) {
+                continue;
+            }
+
+            let config = Self::read_config(&path.with_extension("yaml"));
+            let label = config.label.unwrap_or_else(|| {
+                path.file_stem()
+                    .and_then(|s| s.to_str())
+                    .unwrap_or("")
+                    .to_string()
+            });
+            let targets = if config.targets.is_empty() {
+                vec![
+                    PluginTarget::Project,
+                    PluginTarget::Task,
+                    PluginTarget::File,
+                ]
+            } else {
+                config.targets
+            };
+
+            info!("Found plugin: {}", label);
+            plugins.push(Plugin {
+                label,
+                path,
+                targets,
+            });
+        }
+
+        plugins.sort_by_key(|p| p.label.to_lowercase());
+        Ok(plugins)
+    }
+
+    fn read_config(path: &Path) -> PluginConfig {
+        let file = match fs::File::open(path) {
+            Ok(f) => f,
+            Err(_e) => return PluginConfig::default(),
+        };
+        match serde_yaml::from_reader(file) {
+            Ok(c) => c,
+            Err(e) => {
+                error!("Could not read plugin config {}: {}", path.display(), e);
+                PluginConfig::default()
+            }
+        }
+    }
+
+    #[cfg(unix)]
+    fn is_executable(path: &Path) -> bool {
+        use std::os::unix::fs::PermissionsExt;
+        match fs::metadata(path) {
+            Ok(m) => m.permissions().mode() & 0o111 != 0,
+            Err(_e) => false,
+        }
+    }
+
+    #[cfg(not(unix))]
+    fn is_executable(path: &Path) -> bool {
+        let extension = path
+            .extension()
+            .and_then(|e| e.to_str())
+            .unwrap_or("")
+            .to_lowercase();
+        ["exe", "bat", "cmd"].contains(&extension.as_str())
+    }
+
+    /// Starts the plugin with the payload as JSON on stdin. Doesn't wait for the plugin to finish,
+    /// the payload is written and the exit status logged from a background thread.
+    pub fn run(&self, payload: &PluginPayload<'_>) -> Result<(), String> {
+        let json = match serde_json::to_vec_pretty(payload) {
+            Ok(j) => j,
+            Err(e) => return Err(format!("Failed to create plugin payload: {}", e)),
+        };
+
+        info!("Running plugin: {}", self.path.display());
+        let mut child = match Command::new(&self.path)
+            .current_dir(self.path.parent().unwrap_or(Path::new("")))
+            .stdin(Stdio::piped())
+            .spawn()
+        {
+            Ok(c) => c,
+            Err(e) => {
+                let message = format!("Failed to run plugin {}: {}", self.label, e);
+                error!("{}", message);
+                return Err(message);
+            }
+        };
+
+        // A plugin that doesn't read its stdin would block the ui once the pipe is full.
+        let label = self.label.clone();
+        thread::spawn(move || {
+            if let Some(mut stdin) = child.stdin.take() {
+                if let Err(e) = stdin.write_all(&json) {
+                    error!("Failed to write to plugin {}: {}", label, e);
+                }
+            }
+            match child.wait() {
+                Ok(s) => info!("Plugin {} finished: {}", label, s),
+                Err(e) => error!("Plugin {} failed: {}", label, e),
+            }
+        });
+        Ok(())
+    }
+}
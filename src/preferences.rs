@@ -0,0 +1,186 @@
+use crate::atomic::write_yaml;
+use crate::i18n::{set_language, DEFAULT_LANGUAGE};
+use crate::pathmap::to_local;
+use crate::projects::{ProjectGrouping, ProjectSorting};
+use egui::{FontData, FontDefinitions, FontFamily, TextStyle};
+use log::{error, info};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const DEFAULT_FONT_SIZE: f32 = 12.5;
+pub const MIN_UI_SCALE: f32 = 0.5;
+pub const MAX_UI_SCALE: f32 = 3.;
+const CUSTOM_FONT_NAME: &str = "custom";
+pub const MAX_RECENT_TASKS: usize = 10;
+
+/// What double-clicking a workfile does.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Default, PartialEq, Clone, Copy)]
+#[serde(rename_all = "snake_case")]
+pub enum FileDoubleClick {
+    #[default]
+    Open,
+    /// Asks which app to open the file in.
+    OpenWith,
+    Reveal,
+}
+
+/// Per-user UI preferences, persisted with the UI state.
+#[derive(serde::Deserialize, serde::Serialize, Debug, PartialEq, Clone)]
+#[serde(default)]
+pub struct Preferences {
+    /// Pixels per point, None uses the scale of the monitor.
+    pub ui_scale: Option<f32>,
+    /// Size of body text, other text styles are scaled relative to it.
+    pub font_size: f32,
+    /// A ttf or otf file used before the default fonts.
+    pub custom_font: Option<PathBuf>,
+    /// Language code of the UI strings.
+    pub language: String,
+    /// Overrides the config once the theme is toggled.
+    pub dark_mode: Option<bool>,
+    /// Overrides the theme once changed in the preferences.
+    pub high_contrast: Option<bool>,
+    pub file_double_click: FileDoubleClick,
+    /// Off only selects a clicked task, its files are listed on request. For slow network drives.
+    pub task_click_loads_files: bool,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Self {
+            ui_scale: None,
+            font_size: DEFAULT_FONT_SIZE,
+            custom_font: None,
+            language: String::from(DEFAULT_LANGUAGE),
+            dark_mode: None,
+            high_contrast: None,
+            file_double_click: FileDoubleClick::default(),
+            task_click_loads_files: true,
+        }
+    }
+}
+
+impl Preferences {
+    /// Applies the language, scale, fonts and font size.
+    pub fn apply(&self, ctx: &egui::Context) -> Result<(), String> {
+        set_language(&self.language);
+        self.apply_scale(ctx);
+        self.apply_font_size(ctx);
+        self.apply_fonts(ctx)
+    }
+
+    pub fn apply_scale(&self, ctx: &egui::Context) {
+        if let Some(scale) = self.ui_scale {
+            ctx.set_pixels_per_point(scale.clamp(MIN_UI_SCALE, MAX_UI_SCALE));
+        }
+    }
+
+    /// Sets the text style sizes, keeping the proportions of the egui defaults.
+    pub fn apply_font_size(&self, ctx: &egui::Context) {
+        let mut style = (*ctx.style()).clone();
+        for (text_style, font_id) in style.text_styles.iter_mut() {
+            font_id.size = match text_style {
+                TextStyle::Heading => self.font_size * 1.44,
+                TextStyle::Small => self.font_size * 0.72,
+                TextStyle::Monospace => self.font_size * 0.96,
+                _ => self.font_size,
+            };
+        }
+        ctx.set_style(style);
+    }
+
+    /// Loads the custom font, or restores the default fonts if there is none.
+    pub fn apply_fonts(&self, ctx: &egui::Context) -> Result<(), String> {
+        let mut fonts = FontDefinitions::default();
+        if let Some(path) = &self.custom_font {
+            let data = std::fs::read(path)
+                .map_err(|e| format!("Failed to read font {}: {}", path.display(), e))?;
+            info!("Loading font: {}", path.display());
+            fonts
+                .font_data
+                .insert(String::from(CUSTOM_FONT_NAME), FontData::from_owned(data));
+            fonts
+                .families
+                .entry(FontFamily::Proportional)
+                .or_default()
+                .insert(0, String::from(CUSTOM_FONT_NAME));
+        }
+        ctx.set_fonts(fonts);
+        Ok(())
+    }
+}
+
+/// Widths and visibility of the panels. egui 0.21 has no native multi-viewport support, so a detached
+/// files table is shown in a floating window that can be moved and resized freely.
+#[derive(serde::Deserialize, serde::Serialize, Debug, PartialEq, Clone)]
+#[serde(default)]
+pub struct PanelLayout {
+    pub projects_width: f32,
+    pub tasks_width: f32,
+    pub show_projects: bool,
+    pub show_tasks: bool,
+    pub detach_files: bool,
+    pub show_log: bool,
+}
+
+impl Default for PanelLayout {
+    fn default() -> Self {
+        Self {
+            projects_width: 280.,
+            tasks_width: 280.,
+            show_projects: true,
+            show_tasks: true,
+            detach_files: false,
+            show_log: false,
+        }
+    }
+}
+
+/// The state kept between sessions: pinned projects, recent tasks and UI preferences. Projects, tasks
+/// and config are always read anew at start. Also exported as YAML so an artist can carry their setup
+/// to another workstation.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Default, PartialEq, Clone)]
+#[serde(default)]
+pub struct UiPrefs {
+    pub preferences: Preferences,
+    pub layout: PanelLayout,
+    pub project_grouping: ProjectGrouping,
+    pub project_sorting: ProjectSorting,
+    /// Project names.
+    pub pinned_projects: Vec<String>,
+    /// Task dirs, most recent first.
+    pub recent_tasks: Vec<PathBuf>,
+    /// The project dir open when the app was closed, reopened at start if it still exists.
+    pub last_project: Option<PathBuf>,
+    /// The task dir open when the app was closed.
+    pub last_task: Option<PathBuf>,
+}
+
+impl UiPrefs {
+    pub fn export(&self, path: &Path) -> Result<(), io::Error> {
+        info!("Exporting app state: {}", path.display());
+        write_yaml(path, self)
+    }
+
+    /// Reads an exported state. Paths written on another OS are mapped to this machine.
+    pub fn import(path: &Path) -> Result<UiPrefs, io::Error> {
+        info!("Importing app state: {}", path.display());
+        let text = fs::read_to_string(path)?;
+        let mut state: UiPrefs = serde_yaml::from_str(&text).map_err(|e| {
+            error!("Failed to read {}: {}", path.display(), e);
+            io::Error::new(io::ErrorKind::InvalidData, e.to_string())
+        })?;
+        state.recent_tasks = state.recent_tasks.iter().map(|p| to_local(p)).collect();
+        state.last_project = state.last_project.map(|p| to_local(&p));
+        state.last_task = state.last_task.map(|p| to_local(&p));
+        Ok(state)
+    }
+}
+
+/// Moves the task to the front of the recent tasks, dropping the oldest beyond `MAX_RECENT_TASKS`.
+pub fn push_recent(recent: &mut Vec<PathBuf>, task: &Path) {
+    recent.retain(|p| p != task);
+    recent.insert(0, task.to_path_buf());
+    recent.truncate(MAX_RECENT_TASKS);
+}
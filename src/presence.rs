@@ -0,0 +1,105 @@
+use crate::atomic::write_atomic;
+use crate::helpers::{current_host, current_user, now_timestamp, sanitize_string};
+use crate::pathmap::to_local;
+use crate::TaskTreeNode;
+use log::{error, info};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const SESSIONS_DIR_NAME: &str = "sessions";
+/// Seconds between writes of the own session file.
+pub const HEARTBEAT_INTERVAL: u64 = 30;
+/// Sessions not updated for this many seconds are considered closed, e.g. after a crash.
+pub const SESSION_TIMEOUT: u64 = 120;
+
+/// A user having a project open, stored as one file per user and machine in `<pipeline>/sessions`.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, PartialEq)]
+pub struct Session {
+    pub user: String,
+    pub host: String,
+    #[serde(default)]
+    pub task: Option<String>,
+    #[serde(default)]
+    pub task_path: Option<PathBuf>,
+    pub started_at: u64,
+    pub updated_at: u64,
+}
+
+impl Session {
+    /// A session of the current user on this machine.
+    pub fn new(task: Option<&TaskTreeNode>) -> Self {
+        let now = now_timestamp();
+        Self {
+            user: current_user(),
+            host: current_host(),
+            task: task.map(|t| t.name.clone()),
+            task_path: task.map(|t| t.path.clone()),
+            started_at: now,
+            updated_at: now,
+        }
+    }
+
+    pub fn is_active(&self, now: u64) -> bool {
+        now.saturating_sub(self.updated_at) < SESSION_TIMEOUT
+    }
+
+    fn path(&self, pipeline_path: &Path) -> PathBuf {
+        let name = sanitize_string(format!("{}_{}", self.user, self.host));
+        pipeline_path
+            .join(SESSIONS_DIR_NAME)
+            .join(format!("{}.yaml", name))
+    }
+
+    /// Writes the session file, creating the sessions dir if the pipeline dir exists.
+    pub fn write(&self, pipeline_path: &Path) -> Result<(), io::Error> {
+        if !pipeline_path.is_dir() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} doesn't exist", pipeline_path.display()),
+            ));
+        }
+        let path = self.path(pipeline_path);
+        fs::create_dir_all(pipeline_path.join(SESSIONS_DIR_NAME))?;
+        // Others list the sessions while they're rewritten, they mustn't see a half written file.
+        write_atomic(&path, self)
+    }
+
+    pub fn remove(&self, pipeline_path: &Path) -> Result<(), io::Error> {
+        let path = self.path(pipeline_path);
+        info!("Ending session: {}", path.display());
+        match fs::remove_file(&path) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e),
+            _ => Ok(()),
+        }
+    }
+
+    /// Reads the sessions of a project that are still active, by user. Unreadable files are skipped.
+    pub fn list_active(pipeline_path: &Path, now: u64) -> Result<Vec<Session>, io::Error> {
+        let dir = pipeline_path.join(SESSIONS_DIR_NAME);
+        if !dir.is_dir() {
+            return Ok(Vec::new());
+        }
+
+        let mut sessions = Vec::new();
+        for entry in fs::read_dir(&dir)?.filter_map(|e| e.ok()) {
+            let path = entry.path();
+            if path.extension().map_or(true, |e| e != "yaml") {
+                continue;
+            }
+            let session = fs::File::open(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|f| serde_yaml::from_reader::<_, Session>(f).map_err(|e| e.to_string()));
+            match session {
+                Ok(s) if s.is_active(now) => sessions.push(Session {
+                    task_path: s.task_path.as_deref().map(to_local),
+                    ..s
+                }),
+                Ok(_s) => (),
+                Err(e) => error!("Failed to read session {}: {}", path.display(), e),
+            }
+        }
+        sessions.sort_by(|a, b| a.user.cmp(&b.user).then(a.host.cmp(&b.host)));
+        Ok(sessions)
+    }
+}
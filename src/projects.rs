@@ -1,13 +1,58 @@
+use crate::atomic::{create_yaml, write_yaml};
 use crate::helpers;
-use crate::helpers::EXPLORER;
-use crate::helpers::FINDER;
+use crate::helpers::now_timestamp;
+use crate::helpers::open_folder;
+use crate::helpers::PIPELINE_TEMPLATE_DIR_NAME;
 use crate::helpers::PROJECT_FILE_NAME;
+use crate::pathmap::to_local;
+use crate::roles::ProjectAccess;
+use crate::vfs::{copy_dir_contents, parallel_map, Fs, RealFs};
 use log::{error, info};
-use open;
-use std::ffi::OsString;
+use std::fmt;
 use std::fs;
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// The dir of the workfiles in each task, when the config doesn't name one.
+pub const DEFAULT_TASK_WORK_DIR_NAME: &str = "01_work";
+/// The dir of renders and other outputs in each task, when the config doesn't name one.
+pub const DEFAULT_TASK_OUTPUT_DIR_NAME: &str = "02_output";
+
+/// The step of project creation that failed.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ProjectCreateStep {
+    ProjectDir,
+    Subfolder,
+    PipelineFiles,
+    ProjectFile,
+}
+
+/// Returned when creating a project fails. Anything created before the failure has been rolled back.
+#[derive(Debug)]
+pub struct ProjectCreateError {
+    pub step: ProjectCreateStep,
+    pub path: PathBuf,
+    pub error: io::Error,
+}
+
+impl fmt::Display for ProjectCreateError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let step = match self.step {
+            ProjectCreateStep::ProjectDir => "project folder",
+            ProjectCreateStep::Subfolder => "subfolder",
+            ProjectCreateStep::PipelineFiles => "pipeline files in",
+            ProjectCreateStep::ProjectFile => "project file",
+        };
+        write!(
+            f,
+            "Failed to create {} {}: {}",
+            step,
+            self.path.display(),
+            self.error
+        )
+    }
+}
 
 #[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq, PartialOrd, Ord, Clone)]
 pub struct Project {
@@ -19,9 +64,58 @@ pub struct Project {
     pub deliveries_dir_name: String,
     pub extra_dir_names: Vec<String>,
     pub work_sub_dirs: Vec<String>,
+    /// The dir of the workfiles in each task. Older projects use the first work sub dir.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub task_work_dir_name: String,
+    /// The dir of the outputs in each task. Older projects use the second work sub dir.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub task_output_dir_name: String,
+    /// Extra ignore patterns for this project, added to the global ones.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub ignore_patterns: Vec<String>,
+    /// Short name of the client, set on creation.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub client: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub status: Option<String>,
+    /// Due date as `YYYY-MM-DD`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub due_date: Option<String>,
+    /// Unix timestamp of the creation.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<u64>,
+    /// Who may see and change the project, everyone if not set.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub access: Option<ProjectAccess>,
+    /// The program, like a series, the project is an episode of.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub program: String,
+}
+
+/// How the project list is grouped.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Default, PartialEq, Clone, Copy)]
+pub enum ProjectGrouping {
+    #[default]
+    None,
+    Client,
+    Status,
+    Year,
+    Program,
+}
+
+/// How projects are sorted within a group.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Default, PartialEq, Clone, Copy)]
+pub enum ProjectSorting {
+    #[default]
+    Name,
+    LastActivity,
+    DueDate,
 }
 
+pub const PROJECT_STATUSES: [&str; 4] = ["active", "on hold", "completed", "archived"];
+
 impl Project {
+    #[allow(clippy::ptr_arg)]
     pub fn get_path(&self, projects_dir: &PathBuf) -> PathBuf {
         let mut path = projects_dir.clone();
         path.push(PathBuf::from(&self.name_sanitized));
@@ -52,112 +146,253 @@ impl Project {
         pipeline_path
     }
 
+    /// A new project with the dir layout, client, ignore patterns and access of this one. Nothing is
+    /// created on drive.
+    pub fn duplicate(&self, name: &str) -> Project {
+        Project {
+            name: String::from(name),
+            name_sanitized: helpers::sanitize_string(String::from(name)),
+            status: None,
+            due_date: None,
+            created_at: Some(now_timestamp()),
+            ..self.clone()
+        }
+    }
+
+    /// The dir of the workfiles in each task.
+    pub fn task_work_dir(&self) -> &str {
+        match (self.task_work_dir_name.as_str(), self.work_sub_dirs.first()) {
+            ("", Some(d)) => d,
+            ("", None) => DEFAULT_TASK_WORK_DIR_NAME,
+            (d, _) => d,
+        }
+    }
+
+    /// The dir of renders and other outputs in each task.
+    pub fn task_output_dir(&self) -> &str {
+        match (
+            self.task_output_dir_name.as_str(),
+            self.work_sub_dirs.get(1),
+        ) {
+            ("", Some(d)) => d,
+            ("", None) => DEFAULT_TASK_OUTPUT_DIR_NAME,
+            (d, _) => d,
+        }
+    }
+
+    /// The dirs created in each task: the work and output dirs, then the rest of the work sub dirs.
+    pub fn task_sub_dirs(&self) -> Vec<String> {
+        let mut dirs = vec![
+            String::from(self.task_work_dir()),
+            String::from(self.task_output_dir()),
+        ];
+        for d in &self.work_sub_dirs {
+            if !dirs.contains(d) {
+                dirs.push(d.clone());
+            }
+        }
+        dirs
+    }
+
+    /// Checks that the task work and output dirs are single, different dir names.
+    pub fn check_task_dirs(&self) -> Result<(), String> {
+        for dir in [self.task_work_dir(), self.task_output_dir()] {
+            if dir.trim().is_empty() || dir == "." || dir == ".." || dir.contains(['/', '\\']) {
+                return Err(format!("{} is not a valid task dir name.", dir));
+            }
+        }
+        if self.task_work_dir() == self.task_output_dir() {
+            return Err(format!(
+                "The task work and output dirs are both {}.",
+                self.task_work_dir()
+            ));
+        }
+        Ok(())
+    }
+
     /// Finds projects matching the template project in the specified directory.
     pub fn find_projects(
         projects_dir: PathBuf,
         _template_project: Project,
     ) -> Result<Vec<Project>, io::Error> {
-        info!("Looking for projects in: {}", projects_dir.display());
-
-        let mut projects: Vec<Project> = Vec::new();
-
-        let dir_listing = match fs::read_dir(projects_dir) {
-            Ok(listing) => listing,
-            Err(error) => return Err(error),
-        };
+        Self::find_projects_with(&RealFs, &projects_dir)
+    }
 
-        for result in dir_listing {
-            let item = match result {
-                Ok(i) => i,
-                Err(_e) => continue,
-            };
+    /// Finds the projects in the dir, the ones with a project file.
+    pub fn find_projects_with(fs: &dyn Fs, projects_dir: &Path) -> Result<Vec<Project>, io::Error> {
+        info!("Looking for projects in: {}", projects_dir.display());
 
-            let mut project_config_path = item.path().clone();
-            project_config_path.push(PathBuf::from(PROJECT_FILE_NAME));
+        let dir_listing = fs.read_dir(projects_dir)?;
 
-            if project_config_path.exists() {
-                let project = match Project::read_project(project_config_path) {
-                    Ok(p) => p,
-                    Err(_e) => continue,
-                };
-                projects.push(project);
+        // Each project file is a round trip on a network share, so they're read in parallel.
+        let mut projects: Vec<Project> = parallel_map(&dir_listing, |item| {
+            let project_config_path = item.join(PROJECT_FILE_NAME);
+            if !fs.exists(&project_config_path) {
+                return None;
             }
-        }
+            Project::read_project(fs, project_config_path).ok()
+        })
+        .into_iter()
+        .flatten()
+        .collect();
         projects.sort();
         info!("Found projects: {:?}", projects);
         Ok(projects)
     }
 
-    fn read_project(path: PathBuf) -> Result<Project, io::Error> {
+    pub(crate) fn read_project(fs: &dyn Fs, path: PathBuf) -> Result<Project, io::Error> {
         info!("Attempting to open project: {}", path.display());
-        let file = match std::fs::File::open(path) {
-            Ok(f) => f,
+        let text = match fs.read_to_string(&path) {
+            Ok(t) => t,
             Err(e) => {
                 error!("Could not open project: {}", e);
                 return Err(e);
             }
         };
-        let project: Project = match serde_yaml::from_reader(file) {
+        let mut project: Project = match serde_yaml::from_str(&text) {
             Ok(p) => p,
             Err(e) => {
                 error!("Could not open project: {}", e);
                 return Err(io::Error::new(io::ErrorKind::Other, e.to_string()));
             }
         };
+        // Extra dirs can be absolute paths on a share, written from another OS.
+        for dir in project.extra_dir_names.iter_mut() {
+            *dir = to_local(Path::new(dir)).display().to_string();
+        }
         Ok(project)
     }
 
-    /// Create an actual project folder with subfolder.
-    pub fn create(&self, projects_dir: PathBuf) -> Result<(), io::Error> {
-        let mut subfolders: Vec<PathBuf> = Vec::new();
-        subfolders.push(self.get_dailies_path(&projects_dir));
-        subfolders.push(self.get_deliveries_path(&projects_dir));
-        subfolders.push(self.get_work_path(&projects_dir));
-        for dir in self.extra_dir_names.clone() {
-            subfolders.push(PathBuf::from(dir));
+    /// Create an actual project folder with subfolders. Creation is transactional: if any step fails,
+    /// everything created so far is removed again and the error describes the failed step.
+    /// The contents of the `pipeline` dir in the templates dir, like hooks and per-project config,
+    /// are copied into the pipeline dir of the project.
+    pub fn create(
+        &self,
+        projects_dir: PathBuf,
+        templates_dir: &Path,
+    ) -> Result<(), ProjectCreateError> {
+        self.create_with(&RealFs, projects_dir, templates_dir)
+    }
+
+    pub fn create_with(
+        &self,
+        fs: &dyn Fs,
+        projects_dir: PathBuf,
+        templates_dir: &Path,
+    ) -> Result<(), ProjectCreateError> {
+        let mut created: Vec<PathBuf> = Vec::new();
+
+        match self.create_steps(fs, &projects_dir, templates_dir, &mut created) {
+            Ok(()) => Ok(()),
+            Err(e) => {
+                error!("{}, rolling back.", e);
+                Self::rollback(fs, &created);
+                Err(e)
+            }
         }
+    }
+
+    fn create_steps(
+        &self,
+        fs: &dyn Fs,
+        projects_dir: &PathBuf,
+        templates_dir: &Path,
+        created: &mut Vec<PathBuf>,
+    ) -> Result<(), ProjectCreateError> {
+        let project_path = self.get_path(projects_dir);
 
-        match fs::create_dir(self.get_path(&projects_dir)) {
-            Ok(()) => (),
-            Err(e) => return Err(e),
+        let mut subfolders: Vec<PathBuf> = vec![
+            self.get_pipeline_path(projects_dir),
+            self.get_dailies_path(projects_dir),
+            self.get_deliveries_path(projects_dir),
+            self.get_work_path(projects_dir),
+        ];
+        for dir in self.extra_dir_names.clone() {
+            subfolders.push(project_path.join(dir));
         }
 
+        Self::create_dir(fs, &project_path, ProjectCreateStep::ProjectDir, created)?;
+
         for f in subfolders {
-            let mut p = self.get_path(&projects_dir);
-            p.push(f);
-            match fs::create_dir(p) {
-                Ok(()) => (),
-                Err(e) => return Err(e),
+            Self::create_dir(fs, &f, ProjectCreateStep::Subfolder, created)?;
+        }
+
+        let pipeline_template = templates_dir.join(PIPELINE_TEMPLATE_DIR_NAME);
+        if fs.is_dir(&pipeline_template) {
+            info!("Copying pipeline template: {}", pipeline_template.display());
+            let pipeline_path = self.get_pipeline_path(projects_dir);
+            if let Err(error) = copy_dir_contents(fs, &pipeline_template, &pipeline_path, created) {
+                return Err(ProjectCreateError {
+                    step: ProjectCreateStep::PipelineFiles,
+                    path: pipeline_path,
+                    error,
+                });
             }
         }
 
-        let mut file_path = self.get_path(&projects_dir);
+        let mut file_path = project_path;
         file_path.push(PathBuf::from(PROJECT_FILE_NAME));
+        let step_error = |error: io::Error| ProjectCreateError {
+            step: ProjectCreateStep::ProjectFile,
+            path: file_path.clone(),
+            error,
+        };
 
-        let file = match std::fs::OpenOptions::new()
-            .write(true)
-            .create(true)
-            .open(file_path)
-        {
-            Ok(f) => f,
+        let text = match serde_yaml::to_string(self) {
+            Ok(t) => t,
             Err(e) => {
-                error!("Failed to open file for writing: {}", e);
-                return Err(e);
+                return Err(step_error(io::Error::new(
+                    io::ErrorKind::Other,
+                    e.to_string(),
+                )))
             }
         };
+        if let Err(e) = fs.create_new(&file_path, text.as_bytes()) {
+            error!("Failed to write project file: {}", e);
+            return Err(step_error(e));
+        }
+        created.push(file_path.clone());
 
-        match serde_yaml::to_writer(file, self) {
-            Ok(()) => (),
-            Err(e) => {
-                error!("Failed to write project file: {}", e);
-                return Err(io::Error::new(io::ErrorKind::Other, e.to_string()));
+        Ok(())
+    }
+
+    fn create_dir(
+        fs: &dyn Fs,
+        path: &Path,
+        step: ProjectCreateStep,
+        created: &mut Vec<PathBuf>,
+    ) -> Result<(), ProjectCreateError> {
+        match fs.create_dir(path) {
+            Ok(()) => {
+                created.push(path.to_path_buf());
+                Ok(())
             }
+            Err(error) => Err(ProjectCreateError {
+                step,
+                path: path.to_path_buf(),
+                error,
+            }),
         }
+    }
 
-        Ok(())
+    /// Removes created files and folders in reverse order. Folders are only removed if empty.
+    fn rollback(fs: &dyn Fs, created: &[PathBuf]) {
+        for path in created.iter().rev() {
+            let result = if fs.is_dir(path) {
+                fs.remove_dir(path)
+            } else {
+                fs.remove_file(path)
+            };
+            match result {
+                Ok(()) => info!("Rolled back: {}", path.display()),
+                Err(e) => error!("Failed to roll back {}: {}", path.display(), e),
+            }
+        }
     }
 
     /// Get a new project struct, does not create a project folder.
+    #[allow(clippy::too_many_arguments)]
     pub fn new(
         name: String,
         projects_dir: PathBuf,
@@ -192,27 +427,253 @@ impl Project {
             deliveries_dir_name,
             extra_dir_names,
             work_sub_dirs,
+            task_work_dir_name: String::new(),
+            task_output_dir_name: String::new(),
+            ignore_patterns: Vec::new(),
+            client: String::new(),
+            status: None,
+            due_date: None,
+            created_at: None,
+            access: None,
+            program: String::new(),
         }
     }
 
-    pub fn open_dailies_folder(&self, projects_dir: PathBuf) {
-        let path = OsString::from(self.get_dailies_path(&projects_dir));
+    /// The client short name, for older projects read from a `<number>_<client>_<name>` project name.
+    pub fn client_name(&self) -> String {
+        if !self.client.is_empty() {
+            return self.client.clone();
+        }
+        let parts: Vec<&str> = self.name_sanitized.splitn(3, '_').collect();
+        match parts[..] {
+            [_, client, _] => String::from(client),
+            _ => String::new(),
+        }
+    }
 
-        let command = if cfg!(windows) { EXPLORER } else { FINDER };
+    /// The year the project was created, from the drive for projects without a creation time.
+    pub fn year(&self, projects_dir: &PathBuf) -> Option<i64> {
+        let timestamp = match self.created_at {
+            Some(t) => t,
+            None => {
+                let metadata = fs::metadata(self.get_path(projects_dir)).ok()?;
+                let time = metadata.created().or_else(|_e| metadata.modified()).ok()?;
+                time.duration_since(UNIX_EPOCH).ok()?.as_secs()
+            }
+        };
+        Some(helpers::date_from_days((timestamp / 86400) as i64).0)
+    }
 
-        match open::with(path, command) {
-            Ok(()) => (),
-            Err(_e) => (),
+    /// The latest modification time of the project, work dir and the tasks directly in it.
+    pub fn last_activity(&self, projects_dir: &PathBuf) -> u64 {
+        let work_path = self.get_work_path(projects_dir);
+        let mut paths = vec![self.get_path(projects_dir), work_path.clone()];
+        if let Ok(listing) = fs::read_dir(&work_path) {
+            paths.extend(listing.filter_map(|i| i.ok()).map(|i| i.path()));
         }
+        paths
+            .iter()
+            .filter_map(|p| fs::metadata(p).and_then(|m| m.modified()).ok())
+            .filter_map(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map(|d| d.as_secs())
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// Overwrites the project file with the current metadata.
+    pub fn save(&self, projects_dir: &PathBuf) -> Result<(), io::Error> {
+        let path = self.get_path(projects_dir).join(PROJECT_FILE_NAME);
+        write_yaml(&path, self)
+    }
+
+    pub fn open_dailies_folder(&self, projects_dir: PathBuf) {
+        open_folder(self.get_dailies_path(&projects_dir));
     }
 
     pub fn open_deliveries_folder(&self, projects_dir: PathBuf) {
-        let path = OsString::from(self.get_deliveries_path(&projects_dir));
-        let command = if cfg!(windows) { EXPLORER } else { FINDER };
+        open_folder(self.get_deliveries_path(&projects_dir));
+    }
+
+    pub fn open_pipeline_folder(&self, projects_dir: PathBuf) {
+        open_folder(self.get_pipeline_path(&projects_dir));
+    }
+
+    pub fn open_root(&self, projects_dir: PathBuf) {
+        open_folder(self.get_path(&projects_dir));
+    }
+}
+
+/// A folder in the projects dir without a project file, with its subfolders mapped onto the pipeline roles.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub struct ProjectAdoption {
+    pub path: PathBuf,
+    pub subfolders: Vec<String>,
+    pub pipeline_dir_name: String,
+    pub work_dir_name: String,
+    pub dailies_dir_name: String,
+    pub deliveries_dir_name: String,
+}
+
+impl ProjectAdoption {
+    /// Lists the folders in the projects dir that aren't projects yet. Hidden folders are skipped.
+    pub fn find_candidates(projects_dir: &Path) -> Result<Vec<PathBuf>, io::Error> {
+        let mut candidates: Vec<PathBuf> = fs::read_dir(projects_dir)?
+            .filter_map(|e| e.ok())
+            .filter(|e| !e.file_name().to_string_lossy().starts_with('.'))
+            .map(|e| e.path())
+            .filter(|p| p.is_dir() && !p.join(PROJECT_FILE_NAME).exists())
+            .collect();
+        candidates.sort();
+        Ok(candidates)
+    }
+
+    /// Reads the subfolders and guesses their roles. Roles without a matching subfolder use the
+    /// dir names of the template project and are created on adoption.
+    pub fn new(path: PathBuf, template: &Project) -> Result<Self, io::Error> {
+        let mut subfolders: Vec<String> = fs::read_dir(&path)?
+            .filter_map(|e| e.ok())
+            .filter(|e| e.path().is_dir())
+            .map(|e| e.file_name().to_string_lossy().to_string())
+            .filter(|n| !n.starts_with('.'))
+            .collect();
+        subfolders.sort();
+
+        let guess = |default: &str, keywords: &[&str]| {
+            subfolders
+                .iter()
+                .find(|s| *s == default)
+                .or_else(|| {
+                    subfolders.iter().find(|s| {
+                        let s = s.to_lowercase();
+                        keywords.iter().any(|k| s.contains(k))
+                    })
+                })
+                .cloned()
+                .unwrap_or_else(|| String::from(default))
+        };
+        Ok(Self {
+            pipeline_dir_name: guess(&template.pipeline_dir_name, &["pipe"]),
+            work_dir_name: guess(&template.work_dir_name, &["work"]),
+            dailies_dir_name: guess(&template.dailies_dir_name, &["dail"]),
+            deliveries_dir_name: guess(&template.deliveries_dir_name, &["deliver"]),
+            path,
+            subfolders,
+        })
+    }
+
+    /// The project as it will be written. The folder name is kept as is, even if it isn't sanitized.
+    pub fn project(&self, template: &Project) -> Project {
+        let name = self
+            .path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        Project {
+            name: name.clone(),
+            name_sanitized: name,
+            pipeline_dir_name: self.pipeline_dir_name.clone(),
+            work_dir_name: self.work_dir_name.clone(),
+            dailies_dir_name: self.dailies_dir_name.clone(),
+            deliveries_dir_name: self.deliveries_dir_name.clone(),
+            extra_dir_names: Vec::new(),
+            work_sub_dirs: template.work_sub_dirs.clone(),
+            task_work_dir_name: template.task_work_dir_name.clone(),
+            task_output_dir_name: template.task_output_dir_name.clone(),
+            ignore_patterns: Vec::new(),
+            client: String::new(),
+            status: None,
+            due_date: None,
+            created_at: Some(now_timestamp()),
+            access: None,
+            program: String::new(),
+        }
+    }
+
+    /// Creates the missing role dirs and writes the project file, so the folder shows up in `find_projects`.
+    pub fn adopt(&self, template: &Project) -> Result<Project, io::Error> {
+        let file_path = self.path.join(PROJECT_FILE_NAME);
+        if file_path.exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} already exists", file_path.display()),
+            ));
+        }
+
+        let project = self.project(template);
+        for d in [
+            &project.pipeline_dir_name,
+            &project.work_dir_name,
+            &project.dailies_dir_name,
+            &project.deliveries_dir_name,
+        ] {
+            let dir = self.path.join(d);
+            if !dir.is_dir() {
+                fs::create_dir_all(dir)?;
+            }
+        }
+
+        create_yaml(&file_path, &project)?;
+        info!("Adopted project: {}", self.path.display());
+        Ok(project)
+    }
+}
+
+/// The project names of the episodes of a season of a program, e.g. `1234_acme_show_S01E01`.
+pub fn episode_names(program: &str, season: u32, episodes: u32) -> Vec<String> {
+    (1..=episodes)
+        .map(|e| format!("{}_S{:02}E{:02}", program, season, e))
+        .collect()
+}
 
-        match open::with(path, command) {
-            Ok(()) => (),
-            Err(_e) => (),
+/// Groups and sorts projects. Groups are sorted by name, years newest first, with the projects
+/// without a value for the grouping last. Without grouping there's one group with an empty name.
+/// Without sorting the projects keep their order, like the relevance order of filter results.
+pub fn group_projects(
+    projects: &[Project],
+    grouping: ProjectGrouping,
+    sorting: Option<ProjectSorting>,
+    projects_dir: &PathBuf,
+) -> Vec<(String, Vec<Project>)> {
+    let mut groups: Vec<(String, Vec<Project>)> = Vec::new();
+    for p in projects {
+        let key = match grouping {
+            ProjectGrouping::None => String::new(),
+            ProjectGrouping::Client => p.client_name(),
+            ProjectGrouping::Status => p.status.clone().unwrap_or_default(),
+            ProjectGrouping::Program => p.program.clone(),
+            ProjectGrouping::Year => p
+                .year(projects_dir)
+                .map(|y| y.to_string())
+                .unwrap_or_default(),
+        };
+        match groups.iter_mut().find(|(k, _)| *k == key) {
+            Some((_, g)) => g.push(p.clone()),
+            None => groups.push((key, vec![p.clone()])),
+        }
+    }
+
+    groups.sort_by(|(a, _), (b, _)| match (a.is_empty(), b.is_empty()) {
+        (true, false) => std::cmp::Ordering::Greater,
+        (false, true) => std::cmp::Ordering::Less,
+        _ if grouping == ProjectGrouping::Year => b.cmp(a),
+        _ => a.to_lowercase().cmp(&b.to_lowercase()),
+    });
+
+    let sorting = match sorting {
+        Some(s) => s,
+        None => return groups,
+    };
+    for (_, group) in groups.iter_mut() {
+        match sorting {
+            ProjectSorting::Name => group.sort_by_key(|p| p.name.to_lowercase()),
+            ProjectSorting::LastActivity => {
+                group.sort_by_cached_key(|p| std::cmp::Reverse(p.last_activity(projects_dir)))
+            }
+            // Projects without a due date go last.
+            ProjectSorting::DueDate => {
+                group.sort_by_key(|p| (p.due_date.is_none(), p.due_date.clone()))
+            }
         }
     }
+    groups
 }
@@ -0,0 +1,220 @@
+use crate::atomic::{write_atomic, FileLock};
+use crate::helpers::{current_user, now_timestamp};
+use crate::pathmap::to_local;
+use crate::tasks::TASK_FILE_NAME;
+use log::{error, info};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const PUBLISHES_FILE_NAME: &str = "publishes.yaml";
+
+/// Review state of a publish, publishes without a review are pending.
+#[derive(Clone, Copy, serde::Deserialize, serde::Serialize, Debug, Default, PartialEq)]
+pub enum ReviewState {
+    #[default]
+    Pending,
+    Approved,
+    Rejected,
+}
+
+impl ReviewState {
+    pub const ALL: [ReviewState; 3] = [
+        ReviewState::Pending,
+        ReviewState::Approved,
+        ReviewState::Rejected,
+    ];
+}
+
+/// The latest review of a publish.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, PartialEq)]
+pub struct Review {
+    pub state: ReviewState,
+    pub reviewer: String,
+    #[serde(default)]
+    pub comment: String,
+    pub reviewed_at: u64,
+}
+
+/// Who froze a publish and when.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, PartialEq)]
+pub struct Freeze {
+    pub user: String,
+    pub frozen_at: u64,
+}
+
+/// A published version of a file, recorded in `publishes.yaml` in the task dir.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, PartialEq)]
+pub struct Publish {
+    pub path: PathBuf,
+    pub user: String,
+    #[serde(default)]
+    pub comment: String,
+    pub published_at: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub review: Option<Review>,
+    /// Frozen files are read-only and can't be versioned up from, e.g. approved masters.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub frozen: Option<Freeze>,
+}
+
+impl Publish {
+    /// Returns the task dir a file belongs to, the first parent containing a task file.
+    pub fn find_task_dir(path: &Path) -> Option<PathBuf> {
+        path.ancestors()
+            .skip(1)
+            .find(|p| p.join(TASK_FILE_NAME).is_file())
+            .map(|p| p.to_path_buf())
+    }
+
+    /// Reads the publishes of a task, oldest first. A missing file means no publishes.
+    pub fn list(task_path: &Path) -> Result<Vec<Publish>, io::Error> {
+        let path = task_path.join(PUBLISHES_FILE_NAME);
+        if !path.exists() {
+            return Ok(Vec::new());
+        }
+
+        let file = fs::File::open(&path)?;
+        match serde_yaml::from_reader::<_, Vec<Publish>>(file) {
+            Ok(p) => Ok(p
+                .into_iter()
+                .map(|p| Publish {
+                    path: to_local(&p.path),
+                    ..p
+                })
+                .collect()),
+            Err(e) => {
+                error!("Failed to read {}: {}", path.display(), e);
+                Err(io::Error::new(io::ErrorKind::Other, e.to_string()))
+            }
+        }
+    }
+
+    /// Records a publish of the file in its task.
+    pub fn register(path: &Path, comment: &str) -> Result<Publish, io::Error> {
+        if !path.is_file() {
+            return Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                format!("{} does not exist.", path.display()),
+            ));
+        }
+        let task_path = match Self::find_task_dir(path) {
+            Some(t) => t,
+            None => {
+                return Err(io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("{} is not in a task.", path.display()),
+                ))
+            }
+        };
+
+        let publish = Publish {
+            path: path.to_path_buf(),
+            user: current_user(),
+            comment: String::from(comment),
+            published_at: now_timestamp(),
+            review: None,
+            frozen: None,
+        };
+        let _lock = FileLock::acquire(&task_path.join(PUBLISHES_FILE_NAME))?;
+        let mut publishes = Self::list(&task_path)?;
+        publishes.push(publish.clone());
+
+        info!("Registering publish: {}", path.display());
+        Self::write(&task_path, &publishes)?;
+        Ok(publish)
+    }
+
+    pub fn state(&self) -> ReviewState {
+        self.review
+            .as_ref()
+            .map_or(ReviewState::Pending, |r| r.state)
+    }
+
+    /// Reviews the publish as the current user, replacing any earlier review.
+    pub fn set_review(
+        &self,
+        task_path: &Path,
+        state: ReviewState,
+        comment: &str,
+    ) -> Result<Publish, io::Error> {
+        let _lock = FileLock::acquire(&task_path.join(PUBLISHES_FILE_NAME))?;
+        let mut publishes = Self::list(task_path)?;
+        let publish = self.find_in(&mut publishes)?;
+        publish.review = Some(Review {
+            state,
+            reviewer: current_user(),
+            comment: String::from(comment),
+            reviewed_at: now_timestamp(),
+        });
+        let reviewed = publish.clone();
+
+        info!("Reviewing publish {}: {:?}", self.path.display(), state);
+        Self::write(task_path, &publishes)?;
+        Ok(reviewed)
+    }
+
+    /// A frozen publish of the file, if it has one.
+    pub fn find_frozen(path: &Path) -> Option<Publish> {
+        let task_path = Self::find_task_dir(path)?;
+        Self::list(&task_path)
+            .ok()?
+            .into_iter()
+            .find(|p| p.path == path && p.frozen.is_some())
+    }
+
+    /// Freezes the publish as the current user, making the file read-only, or unfreezes it.
+    pub fn set_frozen(&self, task_path: &Path, frozen: bool) -> Result<Publish, io::Error> {
+        let _lock = FileLock::acquire(&task_path.join(PUBLISHES_FILE_NAME))?;
+        let mut publishes = Self::list(task_path)?;
+        let publish = self.find_in(&mut publishes)?;
+        set_read_only(&self.path, frozen)?;
+        publish.frozen = frozen.then(|| Freeze {
+            user: current_user(),
+            frozen_at: now_timestamp(),
+        });
+        let updated = publish.clone();
+
+        info!("Setting frozen of {}: {}", self.path.display(), frozen);
+        if let Err(e) = Self::write(task_path, &publishes) {
+            let _ = set_read_only(&self.path, !frozen);
+            return Err(e);
+        }
+        Ok(updated)
+    }
+
+    fn find_in<'a>(&self, publishes: &'a mut [Publish]) -> Result<&'a mut Publish, io::Error> {
+        publishes
+            .iter_mut()
+            .find(|p| p.path == self.path && p.published_at == self.published_at)
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::NotFound,
+                    format!("No publish of {} found.", self.path.display()),
+                )
+            })
+    }
+
+    /// The caller holds the lock of the publishes file.
+    fn write(task_path: &Path, publishes: &[Publish]) -> Result<(), io::Error> {
+        write_atomic(&task_path.join(PUBLISHES_FILE_NAME), &publishes)
+    }
+}
+
+/// Makes the file read-only for everyone, or writable again for its owner.
+fn set_read_only(path: &Path, read_only: bool) -> Result<(), io::Error> {
+    let mut permissions = fs::metadata(path)?.permissions();
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = permissions.mode();
+        permissions.set_mode(if read_only {
+            mode & !0o222
+        } else {
+            mode | 0o200
+        });
+    }
+    #[cfg(not(unix))]
+    permissions.set_readonly(read_only);
+    fs::set_permissions(path, permissions)
+}
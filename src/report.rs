@@ -0,0 +1,96 @@
+use crate::helpers::{format_date, now_timestamp};
+use crate::pipeline::PipelineContext;
+use crate::workfiles::FileGroup;
+use crate::Project;
+use log::{error, info};
+use std::fs;
+use std::path::PathBuf;
+
+/// A task in the project report.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ReportRow {
+    /// Relative to the work dir, e.g. `sq010/sh010/comp`.
+    pub task: String,
+    pub status: String,
+    pub due_date: String,
+    /// Workfiles by name, and all their versions.
+    pub workfiles: usize,
+    pub versions: usize,
+}
+
+/// One row per task of the project, in tree order. Tasks with unreadable files are reported empty.
+pub fn report_rows(context: &PipelineContext, project: &Project) -> Result<Vec<ReportRow>, String> {
+    let tree = context.task_tree(project).map_err(|e| e.to_string())?;
+    let ignore = context.ignore_patterns(Some(project))?;
+    let work_path = context.work_path(project);
+
+    let rows = tree
+        .tasks()
+        .into_iter()
+        .map(|task| {
+            let files = task
+                .find_workfiles(
+                    task.metadata.work_dir_name.clone(),
+                    &context.filename_template,
+                    &ignore,
+                )
+                .unwrap_or_default();
+            let details = task.details().unwrap_or_default();
+            ReportRow {
+                task: task
+                    .path
+                    .strip_prefix(&work_path)
+                    .unwrap_or(&task.path)
+                    .to_string_lossy()
+                    .replace('\\', "/"),
+                status: task.status().ok().flatten().unwrap_or_default(),
+                due_date: details.due_date.unwrap_or_default(),
+                workfiles: FileGroup::group(&files).len(),
+                versions: files.len(),
+            }
+        })
+        .collect();
+    Ok(rows)
+}
+
+/// Quotes a CSV field if it has commas, quotes or line breaks.
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        String::from(field)
+    }
+}
+
+/// The rows as CSV with a header row, to open in a spreadsheet.
+pub fn report_csv(rows: &[ReportRow]) -> String {
+    let mut csv = String::from("task,status,due date,workfiles,versions\n");
+    for row in rows {
+        csv.push_str(&format!(
+            "{},{},{},{},{}\n",
+            csv_field(&row.task),
+            csv_field(&row.status),
+            csv_field(&row.due_date),
+            row.workfiles,
+            row.versions
+        ));
+    }
+    csv
+}
+
+/// Writes the report into the pipeline dir of the project as `report_YYYY-MM-DD.csv`, returning its path.
+pub fn write_project_report(
+    context: &PipelineContext,
+    project: &Project,
+) -> Result<PathBuf, String> {
+    let rows = report_rows(context, project)?;
+    let path = context
+        .pipeline_path(project)
+        .join(format!("report_{}.csv", format_date(now_timestamp())));
+    if let Err(e) = fs::write(&path, report_csv(&rows)) {
+        error!("Failed to write report {}: {}", path.display(), e);
+        return Err(e.to_string());
+    }
+    info!("Wrote report of {}: {}", project.name, path.display());
+    Ok(path)
+}
@@ -0,0 +1,105 @@
+use log::{error, warn};
+use std::io;
+use std::sync::RwLock;
+use std::thread;
+use std::time::Duration;
+
+/// OS error codes of busy or briefly unreachable shares: sharing and lock violations, busy and lost
+/// network connections and timeouts on Windows, busy files, timeouts and stale handles elsewhere.
+#[cfg(windows)]
+const TRANSIENT_OS_ERRORS: &[i32] = &[32, 33, 54, 59, 64, 121];
+#[cfg(target_os = "macos")]
+const TRANSIENT_OS_ERRORS: &[i32] = &[16, 60, 70];
+#[cfg(all(unix, not(target_os = "macos")))]
+const TRANSIENT_OS_ERRORS: &[i32] = &[16, 110, 116];
+
+/// How often file operations are tried before their error is shown, for busy NAS:es whose errors
+/// pass after a moment. The delay doubles after each failed attempt, up to the max.
+#[derive(Clone, Copy, serde::Deserialize, serde::Serialize, Debug, PartialEq)]
+#[serde(default)]
+pub struct RetryPolicy {
+    /// 1 tries once.
+    pub attempts: u32,
+    pub initial_delay_ms: u64,
+    pub max_delay_ms: u64,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        DEFAULT_POLICY
+    }
+}
+
+const DEFAULT_POLICY: RetryPolicy = RetryPolicy {
+    attempts: 3,
+    initial_delay_ms: 100,
+    max_delay_ms: 2000,
+};
+
+/// Whether the error may pass by itself, unlike e.g. a missing file or denied access.
+pub fn is_transient(error: &io::Error) -> bool {
+    matches!(
+        error.kind(),
+        io::ErrorKind::TimedOut
+            | io::ErrorKind::Interrupted
+            | io::ErrorKind::WouldBlock
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::NotConnected
+            | io::ErrorKind::BrokenPipe
+    ) || error
+        .raw_os_error()
+        .map_or(false, |c| TRANSIENT_OS_ERRORS.contains(&c))
+}
+
+impl RetryPolicy {
+    /// The delay after the failed attempt, counted from 0.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 1u64.checked_shl(attempt).unwrap_or(u64::MAX);
+        Duration::from_millis(
+            self.initial_delay_ms
+                .saturating_mul(factor)
+                .min(self.max_delay_ms),
+        )
+    }
+
+    /// Runs the operation until it succeeds, fails with an error that isn't transient, or the attempts
+    /// run out.
+    pub fn run<T, F>(&self, mut operation: F) -> Result<T, io::Error>
+    where
+        F: FnMut() -> Result<T, io::Error>,
+    {
+        let mut attempt = 0;
+        loop {
+            match operation() {
+                Err(e) if is_transient(&e) && attempt + 1 < self.attempts => {
+                    let delay = self.delay(attempt);
+                    warn!("Retrying in {:?} after: {}", delay, e);
+                    thread::sleep(delay);
+                    attempt += 1;
+                }
+                result => return result,
+            }
+        }
+    }
+}
+
+/// The policy from the config, set once the config is loaded.
+static POLICY: RwLock<RetryPolicy> = RwLock::new(DEFAULT_POLICY);
+
+/// Sets the configured policy used by `retry`.
+pub fn set_retry_policy(policy: RetryPolicy) {
+    match POLICY.write() {
+        Ok(mut p) => *p = policy,
+        Err(e) => error!("Failed to set the retry policy: {}", e),
+    }
+}
+
+/// Runs the file operation with the configured retry policy.
+pub fn retry<T, F>(operation: F) -> Result<T, io::Error>
+where
+    F: FnMut() -> Result<T, io::Error>,
+{
+    let policy = POLICY.read().map_or(DEFAULT_POLICY, |p| *p);
+    policy.run(operation)
+}
@@ -0,0 +1,153 @@
+use crate::helpers::{current_user, read_config_file, PROJECT_FILE_NAME};
+use crate::Project;
+use log::error;
+use std::env;
+use std::path::Path;
+use std::process::Command;
+
+/// Overrides the role in the config, e.g. `RCLAMP_ROLE=viewer` on a shared machine.
+pub const ROLE_ENV_VAR: &str = "RCLAMP_ROLE";
+
+/// What the user may do. Viewers can browse projects and open files, but not create, delete or
+/// version anything.
+#[derive(Clone, Copy, serde::Deserialize, serde::Serialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    #[default]
+    Editor,
+    Viewer,
+}
+
+impl Role {
+    /// Reads a role name. Anything but `editor` is a viewer, so a typo doesn't allow writes.
+    pub fn parse(name: &str) -> Role {
+        match name.trim().to_lowercase().as_str() {
+            "editor" => Role::Editor,
+            _ => Role::Viewer,
+        }
+    }
+
+    /// The role in the environment variable if it's set, else the configured one.
+    pub fn from_env(configured: Role) -> Role {
+        match env::var(ROLE_ENV_VAR) {
+            Ok(name) if !name.trim().is_empty() => Role::parse(&name),
+            _ => configured,
+        }
+    }
+
+    pub fn can_edit(self) -> bool {
+        self == Role::Editor
+    }
+}
+
+/// How much a user may do in a project, from least to most.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Access {
+    /// Not in the access list, the project isn't shown.
+    None,
+    Viewer,
+    /// Can create, delete, version and publish.
+    Member,
+    /// Can also change the status and the access list of the project.
+    Owner,
+}
+
+/// Who may do what in a project, set in `project.yaml`. Entries are user names or `@group` for the
+/// groups of the OS user. Projects without an access list are open to everyone.
+#[derive(
+    Clone, serde::Deserialize, serde::Serialize, Debug, Default, PartialEq, Eq, PartialOrd, Ord,
+)]
+pub struct ProjectAccess {
+    #[serde(default)]
+    pub owners: Vec<String>,
+    #[serde(default)]
+    pub members: Vec<String>,
+    #[serde(default)]
+    pub viewers: Vec<String>,
+}
+
+impl ProjectAccess {
+    /// The highest access any of the entries matching the user gives.
+    pub fn access(&self, user: &str, groups: &[String]) -> Access {
+        let matches = |entries: &[String]| entries.iter().any(|e| entry_matches(e, user, groups));
+        if matches(&self.owners) {
+            Access::Owner
+        } else if matches(&self.members) {
+            Access::Member
+        } else if matches(&self.viewers) {
+            Access::Viewer
+        } else {
+            Access::None
+        }
+    }
+}
+
+/// User and group names are compared ignoring case, Windows groups also without the domain.
+fn entry_matches(entry: &str, user: &str, groups: &[String]) -> bool {
+    let entry = entry.trim();
+    match entry.strip_prefix('@') {
+        Some(group) => groups.iter().any(|g| {
+            let name = g.rsplit('\\').next().unwrap_or(g);
+            g.eq_ignore_ascii_case(group) || name.eq_ignore_ascii_case(group)
+        }),
+        None => !user.is_empty() && entry.eq_ignore_ascii_case(user),
+    }
+}
+
+/// The access of the user to a project with the access list, everyone owns projects without one.
+pub fn project_access(access: Option<&ProjectAccess>, user: &str, groups: &[String]) -> Access {
+    match access {
+        Some(a) => a.access(user, groups),
+        None => Access::Owner,
+    }
+}
+
+/// The groups of the logged in user, from `id -Gn`, or `whoami /groups` on Windows.
+pub fn current_groups() -> Vec<String> {
+    let output = if cfg!(windows) {
+        Command::new("whoami")
+            .args(["/groups", "/fo", "csv", "/nh"])
+            .output()
+    } else {
+        Command::new("id").arg("-Gn").output()
+    };
+    let text = match output {
+        Ok(o) if o.status.success() => String::from_utf8_lossy(&o.stdout).to_string(),
+        Ok(_o) => return Vec::new(),
+        Err(e) => {
+            error!("Failed to read the groups of the user: {}", e);
+            return Vec::new();
+        }
+    };
+    if cfg!(windows) {
+        text.lines()
+            .filter_map(|l| l.split(',').next())
+            .map(|g| g.trim_matches('"').to_string())
+            .filter(|g| !g.is_empty())
+            .collect()
+    } else {
+        text.split_whitespace().map(String::from).collect()
+    }
+}
+
+/// Checks that the current user may write in the project a path is in, for requests that don't go
+/// through the UI like publishes from DCC:s.
+pub fn check_write_access(path: &Path) -> Result<(), String> {
+    let project_file = match path
+        .ancestors()
+        .map(|p| p.join(PROJECT_FILE_NAME))
+        .find(|p| p.is_file())
+    {
+        Some(p) => p,
+        None => return Ok(()),
+    };
+    let project: Project = read_config_file(&project_file)?;
+    if project.access.is_none() {
+        return Ok(());
+    }
+    let user = current_user();
+    match project_access(project.access.as_ref(), &user, &current_groups()) {
+        Access::Owner | Access::Member => Ok(()),
+        _ => Err(format!("{} is not a member of {}.", user, project.name)),
+    }
+}
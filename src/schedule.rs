@@ -0,0 +1,141 @@
+use crate::helpers::days_from_date;
+use crate::tasks::TaskDetails;
+use crate::Project;
+use std::path::PathBuf;
+
+/// Items due within this many days are highlighted as upcoming.
+pub const UPCOMING_DAYS: i64 = 7;
+
+/// Parses a `YYYY-MM-DD` date to days since the unix epoch.
+pub fn parse_date(date: &str) -> Option<i64> {
+    let mut parts = date.trim().splitn(3, '-');
+    let year: i64 = parts.next()?.parse().ok()?;
+    let month: u32 = parts.next()?.parse().ok()?;
+    let day: u32 = parts.next()?.parse().ok()?;
+    let max_day = match month {
+        2 if year % 4 == 0 && (year % 100 != 0 || year % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        1..=12 => 31,
+        _ => return None,
+    };
+    if day == 0 || day > max_day {
+        return None;
+    }
+    Some(days_from_date(year, month, day))
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum ScheduleKind {
+    Project,
+    Task,
+    Milestone,
+}
+
+/// A project due date, task due date or task milestone.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScheduleItem {
+    pub date: String,
+    /// Days since the unix epoch.
+    pub day: i64,
+    pub kind: ScheduleKind,
+    pub project: String,
+    /// The task dir of task items.
+    pub task: Option<PathBuf>,
+    pub name: String,
+    /// Completed projects and done milestones are never overdue.
+    pub done: bool,
+}
+
+impl ScheduleItem {
+    pub fn is_overdue(&self, today: i64) -> bool {
+        !self.done && self.day < today
+    }
+
+    pub fn is_upcoming(&self, today: i64) -> bool {
+        !self.done && self.day >= today && self.day - today <= UPCOMING_DAYS
+    }
+
+    /// The `YYYY-MM` month of the item.
+    pub fn month(&self) -> &str {
+        self.date.get(..7).unwrap_or(&self.date)
+    }
+}
+
+/// The due dates of the projects. Dates that don't parse are skipped.
+pub fn project_items(projects: &[Project]) -> Vec<ScheduleItem> {
+    projects
+        .iter()
+        .filter_map(|p| {
+            let date = p.due_date.clone()?;
+            Some(ScheduleItem {
+                day: parse_date(&date)?,
+                date,
+                kind: ScheduleKind::Project,
+                project: p.name.clone(),
+                task: None,
+                name: p.name.clone(),
+                done: matches!(p.status.as_deref(), Some("completed" | "archived")),
+            })
+        })
+        .collect()
+}
+
+/// The due dates and milestones of the tasks of a project, by task name and dir.
+pub fn task_items(project: &str, tasks: &[(String, PathBuf, TaskDetails)]) -> Vec<ScheduleItem> {
+    let mut items = Vec::new();
+    for (name, path, details) in tasks {
+        if let Some((date, day)) = details
+            .due_date
+            .as_ref()
+            .and_then(|d| Some((d.clone(), parse_date(d)?)))
+        {
+            items.push(ScheduleItem {
+                date,
+                day,
+                kind: ScheduleKind::Task,
+                project: String::from(project),
+                task: Some(path.clone()),
+                name: name.clone(),
+                done: false,
+            });
+        }
+        for m in &details.milestones {
+            if let Some(day) = parse_date(&m.date) {
+                items.push(ScheduleItem {
+                    date: m.date.clone(),
+                    day,
+                    kind: ScheduleKind::Milestone,
+                    project: String::from(project),
+                    task: Some(path.clone()),
+                    name: format!("{}: {}", name, m.name),
+                    done: m.done,
+                });
+            }
+        }
+    }
+    items
+}
+
+/// Sorts the items by date and groups them by month. Overdue items come first in their own group.
+pub fn group_by_month(
+    mut items: Vec<ScheduleItem>,
+    today: i64,
+) -> Vec<(Option<String>, Vec<ScheduleItem>)> {
+    items.sort_by(|a, b| (a.day, &a.name).cmp(&(b.day, &b.name)));
+    let (overdue, rest): (Vec<ScheduleItem>, Vec<ScheduleItem>) =
+        items.into_iter().partition(|i| i.is_overdue(today));
+
+    let mut groups: Vec<(Option<String>, Vec<ScheduleItem>)> = Vec::new();
+    if !overdue.is_empty() {
+        groups.push((None, overdue));
+    }
+    for item in rest {
+        let month = item.month().to_string();
+        match groups.last_mut() {
+            Some((Some(m), g)) if *m == month => g.push(item),
+            _ => groups.push((Some(month), vec![item])),
+        }
+    }
+    groups
+}
@@ -0,0 +1,293 @@
+use crate::pipeline::PipelineContext;
+use crate::File;
+use crate::Project;
+use crate::TaskTreeNode;
+use log::{error, info};
+use pyo3::exceptions::{PyIOError, PyValueError};
+use pyo3::prelude::*;
+use pyo3::types::PyDict;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+fn io_error(e: io::Error) -> PyErr {
+    PyIOError::new_err(e.to_string())
+}
+
+/// A python script in the scripts dir, listed in the Scripts menu.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Script {
+    pub name: String,
+    pub path: PathBuf,
+}
+
+impl Script {
+    /// Finds `.py` files in the scripts dir.
+    pub fn find_scripts(scripts_dir: &Path) -> Result<Vec<Script>, io::Error> {
+        let mut scripts = Vec::new();
+
+        for result in fs::read_dir(scripts_dir)? {
+            let item = match result {
+                Ok(i) => i,
+                Err(_e) => continue,
+            };
+            let path = item.path();
+            if !path.is_file() || path.extension().and_then(|e| e.to_str()) != Some("py") {
+                continue;
+            }
+            let name = path
+                .file_stem()
+                .and_then(|s| s.to_str())
+                .unwrap_or("")
+                .to_string();
+            scripts.push(Script { name, path });
+        }
+
+        scripts.sort_by_key(|s| s.name.to_lowercase());
+        Ok(scripts)
+    }
+
+    /// Runs the script with the context as globals: `projects`, `project`, `task` and `files`.
+    /// `project` and `task` are `None` when nothing is selected.
+    pub fn run(&self, context: &ScriptContext) -> Result<(), String> {
+        info!("Running script: {}", self.path.display());
+        let code = match fs::read_to_string(&self.path) {
+            Ok(c) => c,
+            Err(e) => return Err(format!("Failed to read script {}: {}", self.name, e)),
+        };
+
+        Python::with_gil(|py| {
+            let result = context
+                .globals(py)
+                .and_then(|globals| py.run(&code, Some(globals), None));
+            match result {
+                Ok(()) => Ok(()),
+                Err(e) => {
+                    let message = format!("Script {} failed: {}", self.name, e);
+                    error!("{}", message);
+                    Err(message)
+                }
+            }
+        })
+    }
+}
+
+/// The app state exposed to a script.
+pub struct ScriptContext {
+    pub projects: Vec<Project>,
+    pub project: Option<Project>,
+    pub task: Option<TaskTreeNode>,
+    pub files: Vec<File>,
+    pub pipeline: PipelineContext,
+}
+
+impl ScriptContext {
+    fn py_project(&self, project: &Project) -> PyProject {
+        PyProject {
+            project: project.clone(),
+            pipeline: self.pipeline.clone(),
+        }
+    }
+
+    fn globals<'py>(&self, py: Python<'py>) -> PyResult<&'py PyDict> {
+        let globals = PyDict::new(py);
+
+        let projects: Vec<PyProject> = self.projects.iter().map(|p| self.py_project(p)).collect();
+        globals.set_item("projects", projects.into_py(py))?;
+
+        let project = self.project.as_ref().map(|p| self.py_project(p));
+        let task = match (&self.project, &self.task) {
+            (Some(p), Some(t)) => Some(PyTask {
+                task: t.clone(),
+                project: self.py_project(p),
+            }),
+            _ => None,
+        };
+        let files: Vec<PyFile> = self
+            .files
+            .iter()
+            .map(|f| PyFile { file: f.clone() })
+            .collect();
+
+        globals.set_item("project", project.into_py(py))?;
+        globals.set_item("task", task.into_py(py))?;
+        globals.set_item("files", files.into_py(py))?;
+        Ok(globals)
+    }
+}
+
+#[pyclass(name = "Project")]
+#[derive(Clone)]
+pub struct PyProject {
+    project: Project,
+    pipeline: PipelineContext,
+}
+
+#[pymethods]
+impl PyProject {
+    #[getter]
+    fn name(&self) -> String {
+        self.project.name.clone()
+    }
+
+    #[getter]
+    fn path(&self) -> PathBuf {
+        self.pipeline.project_path(&self.project)
+    }
+
+    #[getter]
+    fn work_path(&self) -> PathBuf {
+        self.pipeline.work_path(&self.project)
+    }
+
+    /// Returns all tasks in the project, folders are walked but not included.
+    fn tasks(&self) -> PyResult<Vec<PyTask>> {
+        let tree = self.pipeline.task_tree(&self.project).map_err(io_error)?;
+
+        let mut tasks = Vec::new();
+        let mut nodes = vec![tree];
+        while let Some(node) = nodes.pop() {
+            if node.metadata.is_task {
+                tasks.push(PyTask {
+                    task: node.clone(),
+                    project: self.clone(),
+                });
+            }
+            nodes.extend(node.children);
+        }
+        tasks.sort_by_key(|t| t.task.path.clone());
+        Ok(tasks)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("<Project {}>", self.project.name)
+    }
+}
+
+#[pyclass(name = "Task")]
+#[derive(Clone)]
+pub struct PyTask {
+    task: TaskTreeNode,
+    project: PyProject,
+}
+
+#[pymethods]
+impl PyTask {
+    #[getter]
+    fn name(&self) -> String {
+        self.task.name.clone()
+    }
+
+    #[getter]
+    fn path(&self) -> PathBuf {
+        self.task.path.clone()
+    }
+
+    #[getter]
+    fn is_task(&self) -> bool {
+        self.task.metadata.is_task
+    }
+
+    /// Returns the workfiles in the task's work dir.
+    fn files(&self) -> PyResult<Vec<PyFile>> {
+        let ignore = self
+            .project
+            .pipeline
+            .ignore_patterns(Some(&self.project.project))
+            .map_err(PyValueError::new_err)?;
+
+        let files = self
+            .task
+            .find_workfiles(
+                self.task.metadata.work_dir_name.clone(),
+                &self.project.pipeline.filename_template,
+                &ignore,
+            )
+            .map_err(io_error)?;
+        Ok(files.into_iter().map(|file| PyFile { file }).collect())
+    }
+
+    fn set_status(&self, status: String) -> PyResult<()> {
+        self.task.set_status(status).map_err(io_error)
+    }
+
+    fn create_task(&self, name: String) -> PyResult<()> {
+        self.task
+            .create_task(name, self.project.project.clone())
+            .map_err(io_error)
+    }
+
+    fn create_folder(&self, name: String) -> PyResult<()> {
+        self.task.create_folder(name).map_err(io_error)
+    }
+
+    fn __repr__(&self) -> String {
+        format!("<Task {}>", self.task.name)
+    }
+}
+
+#[pyclass(name = "File")]
+#[derive(Clone)]
+pub struct PyFile {
+    file: File,
+}
+
+#[pymethods]
+impl PyFile {
+    #[getter]
+    fn name(&self) -> String {
+        self.file.name.clone()
+    }
+
+    #[getter]
+    fn path(&self) -> PathBuf {
+        self.file.path.clone()
+    }
+
+    #[getter]
+    fn version(&self) -> u32 {
+        self.file.version
+    }
+
+    #[getter]
+    fn extension(&self) -> String {
+        self.file.extension.clone()
+    }
+
+    fn version_up(&self) -> PyResult<()> {
+        self.file.version_up().map(|_p| ()).map_err(io_error)
+    }
+
+    /// Renames the file on drive, keeping the extension. Refuses to overwrite existing files.
+    fn rename(&mut self, filename: String) -> PyResult<()> {
+        if filename.is_empty() || filename.contains(['/', '\\']) {
+            return Err(PyValueError::new_err(format!(
+                "Invalid filename: {}",
+                filename
+            )));
+        }
+        let new_path = self
+            .file
+            .path
+            .with_file_name(format!("{}.{}", filename, self.file.extension));
+        if new_path.exists() {
+            return Err(PyIOError::new_err(format!(
+                "{} already exists.",
+                new_path.display()
+            )));
+        }
+
+        info!(
+            "Renaming {} to {}",
+            self.file.path.display(),
+            new_path.display()
+        );
+        fs::rename(&self.file.path, &new_path).map_err(io_error)?;
+        self.file.path = new_path;
+        Ok(())
+    }
+
+    fn __repr__(&self) -> String {
+        format!("<File {}>", self.file.path.display())
+    }
+}
@@ -0,0 +1,267 @@
+use crate::permissions::apply_to_output;
+use crate::vfs::{copy_with_progress, Fs, RealFs};
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// A frame number at the end of the file stem, after a `.` or `_`, e.g. `shot_v003.1001.exr`.
+const FRAME_PATTERN: &str = r"^(?P<prefix>.*[._])(?P<frame>\d+)(?P<suffix>\.[^.]+)$";
+const MAX_OUTPUT_RECURSION_DEPTH: usize = 4;
+
+/// Dir, prefix and suffix shared by the frames of a sequence.
+type SequenceKey = (PathBuf, String, String);
+/// Frame number, number of digits, path and size of a numbered file.
+type FrameFile = (u32, usize, PathBuf, u64);
+
+/// Numbered files sharing a prefix and suffix, shown as one item.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, PartialEq)]
+pub struct Sequence {
+    pub dir: PathBuf,
+    pub prefix: String,
+    pub suffix: String,
+    pub padding: usize,
+    /// Sorted frame numbers found on drive.
+    pub frames: Vec<u32>,
+    /// Total size of all frames in bytes.
+    pub size: u64,
+}
+
+impl Sequence {
+    pub fn first(&self) -> u32 {
+        self.frames.first().copied().unwrap_or(0)
+    }
+
+    pub fn last(&self) -> u32 {
+        self.frames.last().copied().unwrap_or(0)
+    }
+
+    /// The file name with the frame number replaced by `#`, e.g. `shot_v003.####.exr`.
+    pub fn pattern(&self) -> String {
+        format!("{}{}{}", self.prefix, "#".repeat(self.padding), self.suffix)
+    }
+
+    pub fn frame_range(&self) -> String {
+        format!("{}-{}", self.first(), self.last())
+    }
+
+    /// Frames between the first and last frame that aren't on drive.
+    pub fn missing_frames(&self) -> Vec<u32> {
+        let mut missing = Vec::new();
+        for pair in self.frames.windows(2) {
+            missing.extend(pair[0] + 1..pair[1]);
+        }
+        missing
+    }
+
+    /// The missing frames as ranges, e.g. `1005-1007, 1010`.
+    pub fn missing_ranges(&self) -> String {
+        let mut ranges: Vec<String> = Vec::new();
+        for pair in self.frames.windows(2) {
+            let (start, end) = (pair[0] + 1, pair[1] - 1);
+            if start == end {
+                ranges.push(start.to_string());
+            } else if start < end {
+                ranges.push(format!("{}-{}", start, end));
+            }
+        }
+        ranges.join(", ")
+    }
+
+    pub fn frame_path(&self, frame: u32) -> PathBuf {
+        self.dir.join(format!(
+            "{}{:0width$}{}",
+            self.prefix,
+            frame,
+            self.suffix,
+            width = self.padding
+        ))
+    }
+
+    pub fn paths(&self) -> Vec<PathBuf> {
+        self.frames.iter().map(|f| self.frame_path(*f)).collect()
+    }
+}
+
+/// A file or an image sequence in a task's output dir.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, PartialEq)]
+pub enum OutputItem {
+    File { path: PathBuf, size: u64 },
+    Sequence(Sequence),
+}
+
+impl OutputItem {
+    pub fn name(&self) -> String {
+        match self {
+            OutputItem::File { path, .. } => path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default(),
+            OutputItem::Sequence(s) => s.pattern(),
+        }
+    }
+
+    pub fn dir(&self) -> PathBuf {
+        match self {
+            OutputItem::File { path, .. } => {
+                path.parent().map(|p| p.to_path_buf()).unwrap_or_default()
+            }
+            OutputItem::Sequence(s) => s.dir.clone(),
+        }
+    }
+
+    /// The file to hand to other apps, the first frame for sequences.
+    pub fn first_path(&self) -> PathBuf {
+        match self {
+            OutputItem::File { path, .. } => path.clone(),
+            OutputItem::Sequence(s) => s.frame_path(s.first()),
+        }
+    }
+
+    /// Size in bytes, of all frames for sequences.
+    pub fn size(&self) -> u64 {
+        match self {
+            OutputItem::File { size, .. } => *size,
+            OutputItem::Sequence(s) => s.size,
+        }
+    }
+
+    pub fn paths(&self) -> Vec<PathBuf> {
+        match self {
+            OutputItem::File { path, .. } => vec![path.clone()],
+            OutputItem::Sequence(s) => s.paths(),
+        }
+    }
+
+    /// Copies the file or all frames into the dir, creating it if needed. Refuses to overwrite existing files.
+    /// `progress` is called with the number of copied and total bytes, returning false stops the copy and
+    /// removes the partly copied file.
+    pub fn copy_to<F>(&self, dir: &Path, mut progress: F) -> Result<(), io::Error>
+    where
+        F: FnMut(u64, u64) -> bool,
+    {
+        RealFs.create_dir_all(dir)?;
+        let total = self.size();
+        let mut copied = 0;
+        for path in &self.paths() {
+            let target = dir.join(path.file_name().unwrap_or_default());
+            if target.exists() {
+                return Err(io::Error::new(
+                    io::ErrorKind::AlreadyExists,
+                    format!("{} already exists.", target.display()),
+                ));
+            }
+            copied += copy_with_progress(path, &target, &mut |done, _size| {
+                progress(copied + done, total)
+            })?;
+            apply_to_output(&target);
+        }
+        Ok(())
+    }
+}
+
+/// Collapses numbered files in the same dir into sequences. Numbered files without siblings stay single files,
+/// so a lone `shot_v003.1001.exr` or a movie is listed as is. Sizes are read from drive, missing files count as 0.
+pub fn collapse(paths: Vec<PathBuf>) -> Vec<OutputItem> {
+    let regex = Regex::new(FRAME_PATTERN).expect("frame pattern is valid");
+    let mut groups: BTreeMap<SequenceKey, Vec<FrameFile>> = BTreeMap::new();
+    let mut items = Vec::new();
+
+    for path in paths {
+        let size = fs::metadata(&path).map(|m| m.len()).unwrap_or(0);
+        let filename = path
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let captures = match regex.captures(&filename) {
+            Some(c) => c,
+            None => {
+                items.push(OutputItem::File { path, size });
+                continue;
+            }
+        };
+        let frame = &captures["frame"];
+        let number = match frame.parse::<u32>() {
+            Ok(n) => n,
+            Err(_e) => {
+                items.push(OutputItem::File { path, size });
+                continue;
+            }
+        };
+
+        let dir = path.parent().map(|p| p.to_path_buf()).unwrap_or_default();
+        let key = (
+            dir,
+            String::from(&captures["prefix"]),
+            String::from(&captures["suffix"]),
+        );
+        groups
+            .entry(key)
+            .or_default()
+            .push((number, frame.len(), path, size));
+    }
+
+    for ((dir, prefix, suffix), mut frames) in groups {
+        if frames.len() == 1 {
+            items.extend(
+                frames
+                    .into_iter()
+                    .map(|(_, _, path, size)| OutputItem::File { path, size }),
+            );
+            continue;
+        }
+        frames.sort();
+        items.push(OutputItem::Sequence(Sequence {
+            dir,
+            prefix,
+            suffix,
+            padding: frames.iter().map(|(_, l, _, _)| *l).min().unwrap_or(0),
+            frames: frames.iter().map(|(f, _, _, _)| *f).collect(),
+            size: frames.iter().map(|(_, _, _, s)| *s).sum(),
+        }));
+    }
+
+    items.sort_by_key(|i| (i.dir(), i.name()));
+    items
+}
+
+/// Lists files in the dir and its subdirs, for collapsing into output items. `skip` filters out file names.
+pub fn find_files<F>(dir: &Path, skip: &F) -> Result<Vec<PathBuf>, io::Error>
+where
+    F: Fn(&str) -> bool,
+{
+    let mut files = Vec::new();
+    find_files_recursive(dir, skip, 0, &mut files)?;
+    Ok(files)
+}
+
+fn find_files_recursive<F>(
+    dir: &Path,
+    skip: &F,
+    depth: usize,
+    files: &mut Vec<PathBuf>,
+) -> Result<(), io::Error>
+where
+    F: Fn(&str) -> bool,
+{
+    for result in fs::read_dir(dir)? {
+        let item = match result {
+            Ok(i) => i,
+            Err(_e) => continue,
+        };
+        if skip(&item.file_name().to_string_lossy()) {
+            continue;
+        }
+
+        let path = item.path();
+        if path.is_dir() {
+            if depth < MAX_OUTPUT_RECURSION_DEPTH {
+                find_files_recursive(&path, skip, depth + 1, files)?;
+            }
+        } else {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
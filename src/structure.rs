@@ -0,0 +1,266 @@
+use crate::helpers::sanitize_string;
+use crate::Project;
+use crate::TaskTreeNode;
+use log::{error, info};
+use std::collections::HashSet;
+use std::io;
+use std::path::PathBuf;
+
+/// One line of a shot list, e.g. `ep01/sq010/sh0010/comp`. The last component is the task, the rest are folders.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, PartialEq)]
+pub struct StructureEntry {
+    pub folders: Vec<String>,
+    pub task: String,
+}
+
+/// A node in the dry-run preview of a structure, marks whether the directory already exists on drive.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, PartialEq)]
+pub struct StructureNode {
+    pub name: String,
+    pub is_task: bool,
+    pub exists: bool,
+    pub children: Vec<StructureNode>,
+}
+
+/// Result of creating a single entry, used to report back to the user.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug)]
+pub struct StructureResult {
+    pub entry: String,
+    pub error: Option<String>,
+}
+
+/// Column names of a pasted sheet or imported CSV, a first line with only these is a header row.
+/// Cells are compared by word and lowercase, so `Task Name` and `shot_path` are column names too.
+pub const HEADER_NAMES: [&str; 11] = [
+    "episode", "ep", "sequence", "seq", "shot", "task", "name", "path", "folder", "folders",
+    "status",
+];
+
+/// What components of a line can be separated by.
+const SEPARATORS: [char; 5] = ['/', '\\', ',', ';', '\t'];
+
+/// Whether all non-empty cells are column names, e.g. `Episode,Sequence,Shot,Task Name`.
+pub fn is_header<'a>(cells: impl IntoIterator<Item = &'a str>) -> bool {
+    let mut any = false;
+    for cell in cells {
+        let cell = cell.to_lowercase();
+        let mut words = cell
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+            .peekable();
+        if words.peek().is_none() {
+            continue;
+        }
+        if !words.all(|w| HEADER_NAMES.contains(&w)) {
+            return false;
+        }
+        any = true;
+    }
+    any
+}
+
+impl StructureEntry {
+    /// Parses pasted text or CSV into entries, see `from_line`. A first line naming the columns, e.g.
+    /// `sequence,shot,task`, is a header row and skipped.
+    pub fn parse(text: &str) -> Vec<StructureEntry> {
+        let mut entries = Vec::new();
+        let mut seen = HashSet::new();
+        let mut lines = text.lines().filter(|l| !l.trim().is_empty()).peekable();
+
+        if lines
+            .peek()
+            .map_or(false, |l| is_header(l.split(SEPARATORS)))
+        {
+            lines.next();
+        }
+        for entry in lines.filter_map(Self::from_line) {
+            if seen.insert(entry.display()) {
+                entries.push(entry);
+            }
+        }
+        entries
+    }
+
+    /// Parses one line. Components can be separated by `/`, `\`, `,`, `;` or tabs.
+    pub fn from_line(line: &str) -> Option<StructureEntry> {
+        let components = Self::components(line);
+        let (task, folders) = components.split_last()?;
+        Some(StructureEntry {
+            folders: folders.to_vec(),
+            task: task.to_owned(),
+        })
+    }
+
+    fn components(line: &str) -> Vec<String> {
+        line.split(SEPARATORS)
+            .map(|c| sanitize_string(c.trim().to_string()))
+            .filter(|c| !c.is_empty())
+            .collect()
+    }
+
+    /// Returns the entry as a relative path, e.g. `ep01/sq010/sh0010/comp`.
+    pub fn display(&self) -> String {
+        let mut components = self.folders.clone();
+        components.push(self.task.clone());
+        components.join("/")
+    }
+}
+
+impl StructureNode {
+    fn new(name: String, is_task: bool, exists: bool) -> Self {
+        Self {
+            name,
+            is_task,
+            exists,
+            children: Vec::new(),
+        }
+    }
+
+    /// Builds a dry-run preview tree of the entries, relative to the parent node.
+    pub fn preview(entries: &[StructureEntry], parent: &TaskTreeNode) -> StructureNode {
+        let mut root = StructureNode::new(parent.name.clone(), false, true);
+
+        for entry in entries {
+            let mut path = parent.path.clone();
+            let mut node = &mut root;
+
+            for folder in &entry.folders {
+                path.push(PathBuf::from(folder));
+                node = node.child(folder, false, path.exists());
+            }
+
+            path.push(PathBuf::from(&entry.task));
+            node.child(&entry.task, true, path.exists());
+        }
+        root
+    }
+
+    /// Returns the child with the given name, adding it if missing.
+    fn child(&mut self, name: &str, is_task: bool, exists: bool) -> &mut StructureNode {
+        let index = match self.children.iter().position(|c| c.name == name) {
+            Some(i) => i,
+            None => {
+                self.children
+                    .push(StructureNode::new(String::from(name), is_task, exists));
+                self.children.len() - 1
+            }
+        };
+        &mut self.children[index]
+    }
+}
+
+/// Creates folders and tasks for all entries under the parent node. Existing folders are reused, existing tasks are skipped.
+pub fn create_structure(
+    entries: &[StructureEntry],
+    parent: &TaskTreeNode,
+    project: &Project,
+) -> Vec<StructureResult> {
+    let mut results = Vec::new();
+
+    for entry in entries {
+        info!("Creating structure: {}", entry.display());
+        let error = match create_entry(entry, parent, project) {
+            Ok(_t) => None,
+            Err(e) => {
+                error!("Failed to create {}: {}", entry.display(), e);
+                Some(e.to_string())
+            }
+        };
+        results.push(StructureResult {
+            entry: entry.display(),
+            error,
+        });
+    }
+    results
+}
+
+/// Makes the folders and tasks of the tree under the parent, e.g. in another project. Only the task
+/// files and the task sub dirs are created, no workfiles or outputs. Existing folders are reused,
+/// existing tasks are skipped.
+pub fn copy_tree(
+    tree: &TaskTreeNode,
+    parent: &TaskTreeNode,
+    project: &Project,
+) -> Vec<StructureResult> {
+    let mut results = Vec::new();
+    copy_children(tree, parent, project, "", &mut results);
+    results
+}
+
+fn copy_children(
+    tree: &TaskTreeNode,
+    parent: &TaskTreeNode,
+    project: &Project,
+    prefix: &str,
+    results: &mut Vec<StructureResult>,
+) {
+    for child in &tree.children {
+        let entry = format!("{}{}", prefix, child.name);
+        let node = TaskTreeNode::new(
+            child.name.clone(),
+            parent.path.join(&child.name),
+            &parent.metadata.work_dir_name,
+            &parent.metadata.output_dir_name,
+        );
+        let result = if node.path.exists() {
+            Ok(())
+        } else if child.metadata.is_task {
+            parent.create_task(child.name.clone(), project.clone())
+        } else {
+            parent.create_folder(child.name.clone())
+        };
+        if let Err(e) = &result {
+            error!("Failed to create {}: {}", entry, e);
+        }
+        let created = result.is_ok();
+        results.push(StructureResult {
+            entry: entry.clone(),
+            error: result.err().map(|e| e.to_string()),
+        });
+        if created && !child.metadata.is_task {
+            copy_children(child, &node, project, &format!("{}/", entry), results);
+        }
+    }
+}
+
+/// Creates the folders and task of a single entry, returns the task node.
+pub(crate) fn create_entry(
+    entry: &StructureEntry,
+    parent: &TaskTreeNode,
+    project: &Project,
+) -> Result<TaskTreeNode, io::Error> {
+    let mut node = parent.clone();
+
+    for folder in &entry.folders {
+        let mut path = node.path.clone();
+        path.push(PathBuf::from(folder));
+
+        if !path.exists() {
+            node.create_folder(folder.clone())?;
+        }
+
+        node = TaskTreeNode::new(
+            folder.clone(),
+            path,
+            &node.metadata.work_dir_name,
+            &node.metadata.output_dir_name,
+        );
+    }
+
+    let mut task_path = node.path.clone();
+    task_path.push(PathBuf::from(&entry.task));
+    let task = TaskTreeNode::new(
+        entry.task.clone(),
+        task_path.clone(),
+        &node.metadata.work_dir_name,
+        &node.metadata.output_dir_name,
+    );
+
+    if task_path.exists() {
+        info!("Task already exists, skipping: {}", task_path.display());
+        return Ok(task);
+    }
+
+    node.create_task(entry.task.clone(), project.clone())?;
+    Ok(task)
+}
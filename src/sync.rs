@@ -0,0 +1,269 @@
+use crate::helpers::{current_user, now_timestamp};
+use crate::jobs::JobHandle;
+use log::{error, info};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+/// Uploaded last, so a project without a manifest on the remote is an incomplete push.
+pub const MANIFEST_FILE_NAME: &str = "rclamp_manifest.yaml";
+
+/// A file of a pushed project, relative to the project dir with forward slashes.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, PartialEq)]
+pub struct ManifestEntry {
+    pub path: String,
+    pub size: u64,
+}
+
+/// The files of a project at the time it was pushed, used to verify a restore.
+/// Dirs are listed too, since object storage has no empty dirs.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, PartialEq)]
+pub struct SyncManifest {
+    pub project: String,
+    pub user: String,
+    pub pushed_at: u64,
+    #[serde(default)]
+    pub dirs: Vec<String>,
+    pub files: Vec<ManifestEntry>,
+}
+
+impl SyncManifest {
+    /// Lists all files in the project dir.
+    pub fn build(project_path: &Path) -> Result<SyncManifest, io::Error> {
+        let mut dirs = Vec::new();
+        let mut files = Vec::new();
+        collect_files(project_path, project_path, &mut dirs, &mut files)?;
+        dirs.sort();
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        Ok(SyncManifest {
+            project: project_path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string(),
+            user: current_user(),
+            pushed_at: now_timestamp(),
+            dirs,
+            files,
+        })
+    }
+
+    pub fn total_size(&self) -> u64 {
+        self.files.iter().map(|f| f.size).sum()
+    }
+
+    /// Returns the dirs and files missing in the project dir, and the files differing in size.
+    pub fn verify(&self, project_path: &Path) -> Vec<String> {
+        let dirs = self
+            .dirs
+            .iter()
+            .filter(|d| !project_path.join(d).is_dir())
+            .cloned();
+        let files = self
+            .files
+            .iter()
+            .filter(|f| {
+                fs::metadata(project_path.join(&f.path)).map_or(true, |m| m.len() != f.size)
+            })
+            .map(|f| f.path.clone());
+        dirs.chain(files).collect()
+    }
+}
+
+fn collect_files(
+    root: &Path,
+    dir: &Path,
+    dirs: &mut Vec<String>,
+    files: &mut Vec<ManifestEntry>,
+) -> Result<(), io::Error> {
+    for entry in fs::read_dir(dir)?.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        let metadata = entry.metadata()?;
+        let relative: Vec<String> = path
+            .strip_prefix(root)
+            .unwrap_or(&path)
+            .components()
+            .map(|c| c.as_os_str().to_string_lossy().to_string())
+            .collect();
+        if metadata.is_dir() {
+            dirs.push(relative.join("/"));
+            collect_files(root, &path, dirs, files)?;
+        } else if path.file_name().map_or(false, |n| n != MANIFEST_FILE_NAME) {
+            files.push(ManifestEntry {
+                path: relative.join("/"),
+                size: metadata.len(),
+            });
+        }
+    }
+    Ok(())
+}
+
+fn manifest_temp_path(project: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("{}_{}", project, MANIFEST_FILE_NAME))
+}
+
+/// Pushes projects to S3-compatible storage and restores them, using the aws cli.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, PartialEq)]
+pub struct CloudSync {
+    pub aws: PathBuf,
+    /// Where projects are stored, e.g. `s3://studio-archive/projects`.
+    pub remote: String,
+    /// Endpoint of storage other than AWS, e.g. MinIO or Wasabi.
+    pub endpoint: Option<String>,
+}
+
+impl CloudSync {
+    fn remote_url(&self, project: &str, path: &str) -> String {
+        format!("{}/{}/{}", self.remote.trim_end_matches('/'), project, path)
+    }
+
+    fn command(&self) -> Command {
+        let mut command = Command::new(&self.aws);
+        if let Some(endpoint) = &self.endpoint {
+            command.arg("--endpoint-url").arg(endpoint);
+        }
+        command.arg("s3");
+        command
+    }
+
+    fn run(&self, args: &[&str]) -> Result<String, String> {
+        let output = self
+            .command()
+            .args(args)
+            .stdin(Stdio::null())
+            .output()
+            .map_err(|e| format!("Failed to run aws: {}", e))?;
+        if output.status.success() {
+            Ok(String::from_utf8_lossy(&output.stdout).to_string())
+        } else {
+            let message = format!(
+                "aws s3 {}: {}",
+                args.first().unwrap_or(&""),
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+            error!("{}", message);
+            Err(message)
+        }
+    }
+
+    fn copy(&self, from: &str, to: &str) -> Result<(), String> {
+        self.run(&["cp", from, to, "--only-show-errors"])
+            .map(|_| ())
+    }
+
+    /// Uploads the project file by file, then the manifest. Meant to run as a job.
+    pub fn push(&self, project_path: &Path, job: &JobHandle) -> Result<String, String> {
+        let manifest = SyncManifest::build(project_path).map_err(|e| e.to_string())?;
+        info!(
+            "Pushing {} files of {} to {}",
+            manifest.files.len(),
+            project_path.display(),
+            self.remote
+        );
+
+        let total = manifest.total_size().max(1) as f32;
+        let mut done = 0;
+        for file in &manifest.files {
+            if job.is_cancelled() {
+                return Err(String::from("Cancelled."));
+            }
+            let local = project_path.join(&file.path);
+            self.copy(
+                &local.to_string_lossy(),
+                &self.remote_url(&manifest.project, &file.path),
+            )?;
+            done += file.size;
+            job.set_progress(done as f32 / total);
+        }
+
+        let manifest_path = manifest_temp_path(&manifest.project);
+        let text = serde_yaml::to_string(&manifest).map_err(|e| e.to_string())?;
+        fs::write(&manifest_path, text).map_err(|e| e.to_string())?;
+        let result = self.copy(
+            &manifest_path.to_string_lossy(),
+            &self.remote_url(&manifest.project, MANIFEST_FILE_NAME),
+        );
+        let _ = fs::remove_file(&manifest_path);
+        result?;
+
+        Ok(format!(
+            "Pushed {} files of {} to {}",
+            manifest.files.len(),
+            manifest.project,
+            self.remote
+        ))
+    }
+
+    /// Lists the project dirs on the remote.
+    pub fn list_remote(&self) -> Result<Vec<String>, String> {
+        let output = self.run(&["ls", &format!("{}/", self.remote.trim_end_matches('/'))])?;
+        Ok(output
+            .lines()
+            .filter_map(|l| l.trim().strip_prefix("PRE "))
+            .map(|p| p.trim_end_matches('/').to_string())
+            .collect())
+    }
+
+    /// Downloads a project into the projects dir and verifies it against its manifest. Meant to run as a job.
+    pub fn restore(
+        &self,
+        project: &str,
+        projects_dir: &Path,
+        job: &JobHandle,
+    ) -> Result<String, String> {
+        let project_path = projects_dir.join(project);
+        if project_path.exists() {
+            return Err(format!("{} already exists.", project_path.display()));
+        }
+
+        let manifest_path = manifest_temp_path(project);
+        self.copy(
+            &self.remote_url(project, MANIFEST_FILE_NAME),
+            &manifest_path.to_string_lossy(),
+        )
+        .map_err(|e| {
+            format!(
+                "No manifest found, the push of {} is incomplete: {}",
+                project, e
+            )
+        })?;
+        let text = fs::read_to_string(&manifest_path).map_err(|e| e.to_string())?;
+        let _ = fs::remove_file(&manifest_path);
+        let manifest: SyncManifest = serde_yaml::from_str(&text).map_err(|e| e.to_string())?;
+
+        info!("Restoring {} files of {}", manifest.files.len(), project);
+        for dir in &manifest.dirs {
+            fs::create_dir_all(project_path.join(dir)).map_err(|e| e.to_string())?;
+        }
+        let total = manifest.total_size().max(1) as f32;
+        let mut done = 0;
+        for file in &manifest.files {
+            if job.is_cancelled() {
+                return Err(String::from("Cancelled."));
+            }
+            let local = project_path.join(&file.path);
+            self.copy(
+                &self.remote_url(project, &file.path),
+                &local.to_string_lossy(),
+            )?;
+            done += file.size;
+            job.set_progress(done as f32 / total);
+        }
+
+        let missing = manifest.verify(&project_path);
+        if !missing.is_empty() {
+            return Err(format!(
+                "Restored {}, but {} files are missing or incomplete: {}",
+                project,
+                missing.len(),
+                missing.join(", ")
+            ));
+        }
+        Ok(format!(
+            "Restored {} files of {}",
+            manifest.files.len(),
+            project
+        ))
+    }
+}
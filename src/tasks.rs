@@ -1,5 +1,11 @@
+use crate::atomic::FileLock;
+use crate::fuzzy::fuzzy_score;
 use crate::helpers::EXPLORER;
 use crate::helpers::FINDER;
+use crate::ignore::IgnorePatterns;
+use crate::naming::FilenameTemplate;
+use crate::trash::{Trash, TrashItem, TrashKind};
+use crate::vfs::{parallel_map, Fs, RealFs};
 use crate::File;
 use crate::Project;
 use log::error;
@@ -7,16 +13,55 @@ use log::info;
 
 use std::ffi::OsStr;
 use std::ffi::OsString;
-use std::fs::{self, DirEntry};
+use std::fs;
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
 
-const TASK_FILE_NAME: &str = "task.yaml";
+pub const TASK_FILE_NAME: &str = "task.yaml";
+/// Reference images attached to a task are copied here, in the task dir.
+pub const REFERENCES_DIR_NAME: &str = "references";
 const MAX_FOLDER_RECURSION_DEPTH: i8 = 4;
 
 #[derive(Clone, serde::Deserialize, serde::Serialize, Debug)]
 struct Task {
     name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    status: Option<String>,
+    /// The DCC the last workfile was created with.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    dcc: Option<String>,
+    #[serde(flatten)]
+    details: TaskDetails,
+}
+
+/// The brief of a task, stored in the task file so it lives next to the work.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, Default, PartialEq)]
+#[serde(default)]
+pub struct TaskDetails {
+    #[serde(skip_serializing_if = "String::is_empty")]
+    pub description: String,
+    /// External pages, e.g. the tracker page of the shot or the brief.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub links: Vec<String>,
+    /// File names of the reference images in the references dir of the task.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub references: Vec<String>,
+    /// Due date as `YYYY-MM-DD`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub due_date: Option<String>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub milestones: Vec<Milestone>,
+}
+
+/// A dated step of a task, e.g. a client review.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, Default, PartialEq)]
+pub struct Milestone {
+    pub name: String,
+    /// As `YYYY-MM-DD`.
+    pub date: String,
+    #[serde(default)]
+    pub done: bool,
 }
 
 /// Can include additional metadata for task directories. Currently only informs whether a dir is a task or not.
@@ -34,6 +79,9 @@ pub struct TaskTreeNode {
     pub path: PathBuf,
     pub metadata: TaskNodeMetadata,
     pub children: Vec<TaskTreeNode>,
+    /// When the dir was last changed as of the scan, a dir with the same time has the same entries.
+    #[serde(default)]
+    pub modified: Option<SystemTime>,
 }
 
 impl TaskTreeNode {
@@ -43,6 +91,16 @@ impl TaskTreeNode {
         work_dir_name: &str,
         output_dir_name: &str,
         depth: i8,
+    ) -> Result<TaskTreeNode, io::Error> {
+        Self::from_path_with(&RealFs, path, work_dir_name, output_dir_name, depth)
+    }
+
+    pub fn from_path_with(
+        fs: &dyn Fs,
+        path: PathBuf,
+        work_dir_name: &str,
+        output_dir_name: &str,
+        depth: i8,
     ) -> Result<TaskTreeNode, io::Error> {
         let name = String::from(
             path.file_name()
@@ -53,62 +111,145 @@ impl TaskTreeNode {
 
         let mut node =
             TaskTreeNode::new(name.clone(), path.clone(), work_dir_name, output_dir_name);
+        node.modified = fs.modified(&path).ok();
 
         let mut check_for_task = path.clone();
         check_for_task.push(PathBuf::from(TASK_FILE_NAME));
 
-        if check_for_task.exists() {
+        if fs.exists(&check_for_task) {
             node.metadata.is_task = true;
             info!("Found task: {} at {}", &name, &path.display());
             return Ok(node);
         }
 
-        let dir_listing = match fs::read_dir(&path) {
-            Ok(v) => v,
-            Err(e) => return Err(e),
-        };
+        let dir_listing = fs.read_dir(&path)?;
 
         info!("Found folder: {} at {}", &name, &path.display());
-        for result in dir_listing {
-            let item: DirEntry = match result {
-                Ok(r) => r,
-                Err(_e) => continue,
-            };
+        info!("Depth: {}", &depth);
+        if depth >= MAX_FOLDER_RECURSION_DEPTH {
+            return Ok(node);
+        }
+        let dirs: Vec<PathBuf> = dir_listing
+            .into_iter()
+            .filter(|item| !fs.is_file(item))
+            .collect();
+        let child_from_path = |item: &PathBuf| {
+            TaskTreeNode::from_path_with(
+                fs,
+                item.clone(),
+                work_dir_name,
+                output_dir_name,
+                depth + 1,
+            )
+        };
+        // Only the top level is split over threads, which keeps the thread count bounded.
+        let children = if depth == 0 {
+            parallel_map(&dirs, child_from_path)
+        } else {
+            dirs.iter().map(child_from_path).collect()
+        };
+        node.children = children.into_iter().collect::<Result<_, _>>()?;
 
-            if item.path().is_file() {
-                continue;
-            }
+        Ok(node)
+    }
 
-            info!("Depth: {}", &depth);
-            if depth < MAX_FOLDER_RECURSION_DEPTH {
-                let child = match TaskTreeNode::from_path(
-                    item.path(),
-                    work_dir_name,
-                    output_dir_name,
-                    depth + 1,
-                ) {
-                    Ok(c) => c,
-                    Err(e) => return Err(e),
-                };
-                node.children.push(child);
-            }
+    /// Rescans only the dirs that changed since the tree was scanned, keeping the rest of it.
+    pub fn refresh_with(&self, fs: &dyn Fs, depth: i8) -> Result<TaskTreeNode, io::Error> {
+        let modified = fs.modified(&self.path).ok();
+        if modified.is_none() || modified != self.modified {
+            info!("Rescanning changed folder: {}", self.path.display());
+            return Self::from_path_with(
+                fs,
+                self.path.clone(),
+                &self.metadata.work_dir_name,
+                &self.metadata.output_dir_name,
+                depth,
+            );
         }
-
+        let refresh_child = |child: &TaskTreeNode| child.refresh_with(fs, depth + 1);
+        let children = if depth == 0 {
+            parallel_map(&self.children, refresh_child)
+        } else {
+            self.children.iter().map(refresh_child).collect()
+        };
+        let mut node = self.clone();
+        node.children = children.into_iter().collect::<Result<_, _>>()?;
         Ok(node)
     }
 
     /// Returns a new representation of a task directory.
     pub fn new(name: String, path: PathBuf, work_dir_name: &str, output_dir_name: &str) -> Self {
         Self {
-            name: name,
-            path: path,
+            name,
+            path,
             metadata: TaskNodeMetadata {
                 is_task: false,
                 work_dir_name: String::from(work_dir_name),
                 output_dir_name: String::from(output_dir_name),
             },
             children: Vec::new(),
+            modified: None,
+        }
+    }
+
+    /// Returns the tree with only the nodes fuzzy matching the pattern and their ancestors. Matching folders
+    /// keep all their children. The root is always kept, None means nothing below it matched.
+    pub fn filtered(&self, pattern: &str) -> Option<TaskTreeNode> {
+        let children: Vec<TaskTreeNode> = self
+            .children
+            .iter()
+            .filter_map(|c| match fuzzy_score(pattern, &c.name) {
+                Some(_) => Some(c.clone()),
+                None => c.filtered(pattern),
+            })
+            .collect();
+        if children.is_empty() && !pattern.is_empty() {
+            return None;
+        }
+        Some(TaskTreeNode {
+            name: self.name.clone(),
+            path: self.path.clone(),
+            metadata: self.metadata.clone(),
+            children,
+            modified: self.modified,
+        })
+    }
+
+    /// The nodes from a child of this node down to the node at the path, empty if the path isn't in the tree.
+    pub fn path_to(&self, path: &Path) -> Vec<&TaskTreeNode> {
+        for c in &self.children {
+            if c.path == path {
+                return vec![c];
+            }
+            if path.starts_with(&c.path) {
+                let mut nodes = c.path_to(path);
+                if !nodes.is_empty() {
+                    nodes.insert(0, c);
+                    return nodes;
+                }
+            }
         }
+        Vec::new()
+    }
+
+    /// All tasks below this node, depth first.
+    pub fn tasks(&self) -> Vec<&TaskTreeNode> {
+        let mut tasks = Vec::new();
+        for c in &self.children {
+            if c.metadata.is_task {
+                tasks.push(c);
+            } else {
+                tasks.extend(c.tasks());
+            }
+        }
+        tasks
+    }
+
+    /// Whether there are tasks anywhere below this node.
+    pub fn has_tasks(&self) -> bool {
+        self.children
+            .iter()
+            .any(|c| c.metadata.is_task || c.has_tasks())
     }
 
     /// Opens the specified directory in Explorer or Finder.
@@ -133,42 +274,36 @@ impl TaskTreeNode {
 
     /// Create a task folder and subfolders on drive. Remember to refresh task tree in ui.
     pub fn create_task(&self, name: String, project: Project) -> Result<(), io::Error> {
+        self.create_task_with(&RealFs, name, project)
+    }
+
+    pub fn create_task_with(
+        &self,
+        fs: &dyn Fs,
+        name: String,
+        project: Project,
+    ) -> Result<(), io::Error> {
         let mut task_path = self.path.clone();
         task_path.push(PathBuf::from(&name));
 
-        match fs::create_dir(&task_path) {
+        match fs.create_dir(&task_path) {
             Ok(()) => (),
             Err(e) => return Err(e),
         };
 
-        let task = Task { name: name };
-        let mut file_path = task_path.clone();
-        file_path.push(PathBuf::from(TASK_FILE_NAME));
-        let file = match std::fs::OpenOptions::new()
-            .write(true)
-            .create(true)
-            .open(file_path)
-        {
-            Ok(f) => f,
-            Err(e) => {
-                error!("Failed to open file for writing: {}", e);
-                return Err(e);
-            }
+        let task = Task {
+            name,
+            status: None,
+            dcc: None,
+            details: TaskDetails::default(),
         };
+        Self::write_task_file(fs, &task_path, &task)?;
 
-        match serde_yaml::to_writer(file, &task) {
-            Ok(()) => (),
-            Err(e) => {
-                error!("Failed to write project file: {}", e);
-                return Err(io::Error::new(io::ErrorKind::Other, e.to_string()));
-            }
-        }
-
-        for d in project.work_sub_dirs {
+        for d in project.task_sub_dirs() {
             let mut dir = task_path.clone();
             dir.push(PathBuf::from(d));
 
-            match fs::create_dir(dir) {
+            match fs.create_dir(&dir) {
                 Ok(()) => (),
                 Err(e) => return Err(e),
             }
@@ -176,44 +311,203 @@ impl TaskTreeNode {
         Ok(())
     }
 
+    /// Turns an existing folder into a task by writing the task file and creating the missing work sub dirs.
+    /// Existing files are left as they are.
+    pub fn convert_to_task(path: &Path, work_sub_dirs: &[String]) -> Result<(), io::Error> {
+        if !path.join(TASK_FILE_NAME).exists() {
+            let name = path
+                .file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default();
+            let task = Task {
+                name,
+                status: None,
+                dcc: None,
+                details: TaskDetails::default(),
+            };
+            Self::write_task_file(&RealFs, path, &task)?;
+        }
+        for d in work_sub_dirs {
+            let dir = path.join(d);
+            if !dir.is_dir() {
+                RealFs.create_dir(&dir)?;
+            }
+        }
+        info!("Converted folder to task: {}", path.display());
+        Ok(())
+    }
+
+    /// Sets the status of an existing task, stored in the task file.
+    pub fn set_status(&self, status: String) -> Result<(), io::Error> {
+        let _lock = FileLock::acquire(&self.path.join(TASK_FILE_NAME))?;
+        let mut task = self.read_task_file()?;
+        task.status = Some(status);
+        Self::write_task_file(&RealFs, &self.path, &task)
+    }
+
+    pub fn status(&self) -> Result<Option<String>, io::Error> {
+        Ok(self.read_task_file()?.status)
+    }
+
+    /// The name of the DCC the last workfile in the task was created with.
+    pub fn last_dcc(&self) -> Result<Option<String>, io::Error> {
+        Ok(self.read_task_file()?.dcc)
+    }
+
+    pub fn set_last_dcc(&self, dcc: &str) -> Result<(), io::Error> {
+        let _lock = FileLock::acquire(&self.path.join(TASK_FILE_NAME))?;
+        let mut task = self.read_task_file()?;
+        task.dcc = Some(String::from(dcc));
+        Self::write_task_file(&RealFs, &self.path, &task)
+    }
+
+    /// The description, links and references of the task.
+    pub fn details(&self) -> Result<TaskDetails, io::Error> {
+        Ok(self.read_task_file()?.details)
+    }
+
+    /// Replaces the description, links and references, keeping the rest of the task file.
+    pub fn set_details(&self, details: TaskDetails) -> Result<(), io::Error> {
+        let _lock = FileLock::acquire(&self.path.join(TASK_FILE_NAME))?;
+        let mut task = self.read_task_file()?;
+        task.details = details;
+        info!("Writing details of task: {}", self.path.display());
+        Self::write_task_file(&RealFs, &self.path, &task)
+    }
+
+    /// Copies a reference image into the references dir and returns its file name.
+    /// Doesn't add it to the details, existing files are never overwritten.
+    pub fn attach_reference(&self, source: &Path) -> Result<String, io::Error> {
+        let name = source
+            .file_name()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "Not a file."))?
+            .to_string_lossy()
+            .to_string();
+        let dir = self.path.join(REFERENCES_DIR_NAME);
+        RealFs.create_dir_all(&dir)?;
+        let path = dir.join(&name);
+        if path.try_exists()? {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} already exists.", path.display()),
+            ));
+        }
+        RealFs.copy(source, &path)?;
+        info!("Attached reference {} to {}", name, self.path.display());
+        Ok(name)
+    }
+
+    pub fn reference_path(&self, name: &str) -> PathBuf {
+        self.path.join(REFERENCES_DIR_NAME).join(name)
+    }
+
+    fn read_task_file(&self) -> Result<Task, io::Error> {
+        let file = std::fs::File::open(self.path.join(TASK_FILE_NAME))?;
+        serde_yaml::from_reader(file).map_err(|e| {
+            error!("Failed to read task file: {}", e);
+            io::Error::new(io::ErrorKind::Other, e.to_string())
+        })
+    }
+
+    fn write_task_file(fs: &dyn Fs, task_path: &Path, task: &Task) -> Result<(), io::Error> {
+        let mut file_path = task_path.to_path_buf();
+        file_path.push(PathBuf::from(TASK_FILE_NAME));
+        let text = serde_yaml::to_string(task)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e.to_string()))?;
+        fs.write(&file_path, text.as_bytes())
+    }
+
     /// Create a folder on drive. Remember to refresh task tree in ui.
     pub fn create_folder(&self, name: String) -> Result<(), io::Error> {
+        self.create_folder_with(&RealFs, name)
+    }
+
+    pub fn create_folder_with(&self, fs: &dyn Fs, name: String) -> Result<(), io::Error> {
         let mut folder_path = self.path.clone();
         folder_path.push(PathBuf::from(name));
 
-        match fs::create_dir(&folder_path) {
+        match fs.create_dir(&folder_path) {
             Ok(()) => (),
             Err(e) => return Err(e),
         };
         Ok(())
     }
 
+    /// Renames the folder or task on drive, and the name in the task file. Refuses to overwrite existing
+    /// entries. Returns the new path, remember to refresh task tree in ui.
+    pub fn rename(&self, name: &str) -> Result<PathBuf, io::Error> {
+        let path = self.path.with_file_name(name);
+        if path.try_exists()? {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} already exists.", path.display()),
+            ));
+        }
+        info!("Renaming {} to {}", self.path.display(), path.display());
+        fs::rename(&self.path, &path)?;
+
+        if self.metadata.is_task {
+            let renamed = TaskTreeNode::new(
+                String::from(name),
+                path.clone(),
+                &self.metadata.work_dir_name,
+                &self.metadata.output_dir_name,
+            );
+            let _lock = FileLock::acquire(&path.join(TASK_FILE_NAME))?;
+            let mut task = renamed.read_task_file()?;
+            task.name = String::from(name);
+            Self::write_task_file(&RealFs, &path, &task)?;
+        }
+        Ok(path)
+    }
+
+    /// Moves the task or folder into the project trash. Remember to refresh task tree in ui.
+    pub fn move_to_trash(&self, project_path: &Path) -> Result<TrashItem, io::Error> {
+        let kind = if self.metadata.is_task {
+            TrashKind::Task
+        } else {
+            TrashKind::Folder
+        };
+        Trash::new(project_path).put(&self.path, kind)
+    }
+
     /// Returns a list of workfiles in the tasks work-folder.
-    pub fn find_workfiles(&self, work_dir_name: String) -> Result<Vec<File>, io::Error> {
+    pub fn find_workfiles(
+        &self,
+        work_dir_name: String,
+        template: &FilenameTemplate,
+        ignore: &IgnorePatterns,
+    ) -> Result<Vec<File>, io::Error> {
+        self.find_workfiles_with(&RealFs, work_dir_name, template, ignore)
+    }
+
+    pub fn find_workfiles_with(
+        &self,
+        fs: &dyn Fs,
+        work_dir_name: String,
+        template: &FilenameTemplate,
+        ignore: &IgnorePatterns,
+    ) -> Result<Vec<File>, io::Error> {
         let mut work_dir = self.path.clone();
         let mut files = Vec::new();
         work_dir.push(PathBuf::from(work_dir_name));
 
-        let dir_listing = match fs::read_dir(work_dir) {
-            Ok(d) => d,
-            Err(e) => return Err(e),
-        };
+        let dir_listing = fs.read_dir(&work_dir)?;
 
-        for i in dir_listing {
-            let item = match i {
-                Ok(f) => f,
-                Err(_e) => continue,
-            };
-
-            if item.path().is_dir() {
+        for item in dir_listing {
+            let name = item.file_name().unwrap_or_default().to_string_lossy();
+            if fs.is_dir(&item) || ignore.is_ignored(&name) {
                 continue;
             }
 
-            match File::from_path(item.path()) {
+            match File::from_path(item.clone(), template) {
                 Ok(f) => {
                     files.push(f);
                 }
-                Err(_e) => continue,
+                Err(e) => {
+                    info!("Skipping {}: {}", item.display(), e);
+                    continue;
+                }
             };
         }
 
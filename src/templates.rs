@@ -0,0 +1,328 @@
+use crate::atomic::{create_yaml, write_yaml};
+use crate::detect::executable_for;
+use crate::helpers::PIPELINE_TEMPLATE_DIR_NAME;
+use crate::workfiles::Dcc;
+use crate::Project;
+use log::info;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const APP_CONFIG_FILE_NAME: &str = "app.yaml";
+/// Replaced templates are kept here in the DCC dir, as `template_v001.nk` and so on.
+pub const TEMPLATE_VERSIONS_DIR_NAME: &str = "versions";
+const TEMPLATE_STEM: &str = "template";
+
+/// A DCC dir in the templates dir, with what's wrong with it if anything.
+#[derive(Clone, Debug, PartialEq)]
+pub struct TemplateDir {
+    pub dir: PathBuf,
+    /// None if the config is missing or doesn't parse.
+    pub dcc: Option<Dcc>,
+    /// The template files in the dir, whatever their extension.
+    pub templates: Vec<PathBuf>,
+    /// Earlier templates, oldest first.
+    pub versions: Vec<PathBuf>,
+    pub problems: Vec<String>,
+}
+
+impl TemplateDir {
+    /// The DCC name, or the dir name if the config couldn't be read.
+    pub fn name(&self) -> String {
+        match &self.dcc {
+            Some(d) => d.name.clone(),
+            None => self
+                .dir
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string(),
+        }
+    }
+
+    /// The template new workfiles are made from.
+    pub fn template_path(&self) -> Option<PathBuf> {
+        let dcc = self.dcc.as_ref()?;
+        let path = self.dir.join(format!("{}{}", TEMPLATE_STEM, dcc.extension));
+        Some(path).filter(|p| p.is_file())
+    }
+}
+
+fn list_dir(dir: &Path) -> Result<Vec<PathBuf>, io::Error> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|e| e.ok())
+        .filter(|e| !e.file_name().to_string_lossy().starts_with('.'))
+        .map(|e| e.path())
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string()
+}
+
+/// Lists the DCC dirs in the templates dir and checks that each has a config, and a template with the
+/// extension in the config.
+pub fn scan_templates(templates_dir: &Path) -> Result<Vec<TemplateDir>, io::Error> {
+    let mut dirs = Vec::new();
+    for dir in list_dir(templates_dir)? {
+        if !dir.is_dir() || file_name(&dir) == PIPELINE_TEMPLATE_DIR_NAME {
+            continue;
+        }
+        let templates: Vec<PathBuf> = list_dir(&dir)?
+            .into_iter()
+            .filter(|p| p.is_file() && p.file_stem().map_or(false, |s| s == TEMPLATE_STEM))
+            .collect();
+        let mut entry = TemplateDir {
+            versions: template_versions(&dir)?,
+            dir,
+            dcc: None,
+            templates,
+            problems: Vec::new(),
+        };
+        check_template_dir(&mut entry);
+        dirs.push(entry);
+    }
+    Ok(dirs)
+}
+
+fn check_template_dir(entry: &mut TemplateDir) {
+    let config_path = entry.dir.join(APP_CONFIG_FILE_NAME);
+    let text = match fs::read_to_string(&config_path) {
+        Ok(t) => t,
+        Err(_e) => {
+            entry.problems.push(format!("No {}.", APP_CONFIG_FILE_NAME));
+            return;
+        }
+    };
+    let dcc: Dcc = match serde_yaml::from_str(&text) {
+        Ok(d) => d,
+        Err(e) => {
+            entry
+                .problems
+                .push(format!("{} doesn't parse: {}", APP_CONFIG_FILE_NAME, e));
+            return;
+        }
+    };
+
+    if !dcc.extension.starts_with('.') {
+        entry.problems.push(format!(
+            "The extension {} in {} should start with a dot.",
+            dcc.extension, APP_CONFIG_FILE_NAME
+        ));
+    }
+    let expected = format!("{}{}", TEMPLATE_STEM, dcc.extension);
+    if !entry.templates.iter().any(|t| file_name(t) == expected) {
+        entry.problems.push(format!("No {}.", expected));
+    }
+    for t in entry.templates.iter().map(|t| file_name(t)) {
+        if t != expected {
+            entry.problems.push(format!(
+                "{} doesn't match the extension {} in {}.",
+                t, dcc.extension, APP_CONFIG_FILE_NAME
+            ));
+        }
+    }
+    entry.dcc = Some(dcc);
+}
+
+/// Checks the setup the config points to: that the templates dir exists, that each DCC dir in it has a
+/// config that parses and a template, and that the template project names all its dirs. Returns
+/// everything that's wrong, so it can be shown at once.
+pub fn check_setup(templates_dir: &Path, template_project: &Project) -> Vec<String> {
+    let mut problems = Vec::new();
+    let dir_names = [
+        ("pipeline_dir_name", &template_project.pipeline_dir_name),
+        ("work_dir_name", &template_project.work_dir_name),
+        ("dailies_dir_name", &template_project.dailies_dir_name),
+        ("deliveries_dir_name", &template_project.deliveries_dir_name),
+    ];
+    for (field, name) in dir_names {
+        if name.trim().is_empty() {
+            problems.push(format!("{} in the config is empty.", field));
+        }
+    }
+    let empty_sub_dir = template_project
+        .extra_dir_names
+        .iter()
+        .chain(&template_project.work_sub_dirs)
+        .any(|d| d.trim().is_empty());
+    if empty_sub_dir {
+        problems.push(String::from(
+            "extra_dir_names or work_sub_dirs in the config has an empty entry.",
+        ));
+    }
+
+    if !templates_dir.is_dir() {
+        problems.push(format!(
+            "The templates dir {} doesn't exist.",
+            templates_dir.display()
+        ));
+        return problems;
+    }
+    match scan_templates(templates_dir) {
+        Ok(dirs) if dirs.is_empty() => problems.push(format!(
+            "There are no DCC templates in {}.",
+            templates_dir.display()
+        )),
+        Ok(dirs) => {
+            for dir in dirs {
+                let name = dir.name();
+                problems.extend(dir.problems.iter().map(|p| format!("{}: {}", name, p)));
+            }
+        }
+        Err(e) => problems.push(format!(
+            "Failed to read the templates dir {}: {}",
+            templates_dir.display(),
+            e
+        )),
+    }
+    problems
+}
+
+/// The earlier templates of a DCC dir, oldest first.
+pub fn template_versions(dir: &Path) -> Result<Vec<PathBuf>, io::Error> {
+    let versions_dir = dir.join(TEMPLATE_VERSIONS_DIR_NAME);
+    if !versions_dir.is_dir() {
+        return Ok(Vec::new());
+    }
+    let mut versions: Vec<(u32, PathBuf)> = list_dir(&versions_dir)?
+        .into_iter()
+        .filter_map(|p| Some((version_number(&p)?, p)))
+        .collect();
+    versions.sort();
+    Ok(versions.into_iter().map(|(_v, p)| p).collect())
+}
+
+fn version_number(path: &Path) -> Option<u32> {
+    path.file_stem()?
+        .to_string_lossy()
+        .strip_prefix(&format!("{}_v", TEMPLATE_STEM))?
+        .parse()
+        .ok()
+}
+
+/// Moves the template into the versions dir under the next version number.
+fn archive_template(dir: &Path, template: &Path) -> Result<PathBuf, io::Error> {
+    let next = template_versions(dir)?
+        .last()
+        .and_then(|p| version_number(p))
+        .unwrap_or(0)
+        + 1;
+    let extension = template
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+    let versions_dir = dir.join(TEMPLATE_VERSIONS_DIR_NAME);
+    fs::create_dir_all(&versions_dir)?;
+    let target = versions_dir.join(format!("{}_v{:03}{}", TEMPLATE_STEM, next, extension));
+    info!(
+        "Archiving template {} as {}",
+        template.display(),
+        target.display()
+    );
+    fs::rename(template, &target)?;
+    Ok(target)
+}
+
+/// Makes the file the template of the DCC, keeping the current template as a version. A new DCC dir
+/// with a config is created if there's none by the name.
+pub fn import_template(templates_dir: &Path, name: &str, source: &Path) -> Result<Dcc, io::Error> {
+    let extension = match source.extension() {
+        Some(e) => format!(".{}", e.to_string_lossy()),
+        None => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                "The template needs an extension.",
+            ))
+        }
+    };
+    let dir = templates_dir.join(name);
+    let config_path = dir.join(APP_CONFIG_FILE_NAME);
+    let dcc = if config_path.exists() {
+        let text = fs::read_to_string(&config_path)?;
+        let dcc: Dcc = serde_yaml::from_str(&text)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))?;
+        if dcc.extension != extension {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                format!("{} templates are {} files.", dcc.name, dcc.extension),
+            ));
+        }
+        dcc
+    } else {
+        fs::create_dir_all(&dir)?;
+        let dcc = Dcc {
+            name: String::from(name),
+            extension: extension.clone(),
+            template_path: PathBuf::from(format!("{}{}", TEMPLATE_STEM, extension)),
+            substitute_tokens: false,
+            executable: None,
+            version: None,
+            autosave_dirs: Vec::new(),
+        };
+        create_yaml(&config_path, &dcc)?;
+        dcc
+    };
+
+    let template = dir.join(format!("{}{}", TEMPLATE_STEM, extension));
+    if template.exists() {
+        archive_template(&dir, &template)?;
+    }
+    info!(
+        "Importing template {} to {}",
+        source.display(),
+        template.display()
+    );
+    fs::copy(source, &template)?;
+    Ok(dcc)
+}
+
+/// Makes an earlier version the template again, keeping the current template as a version.
+pub fn restore_template(dir: &Path, version: &Path) -> Result<(), io::Error> {
+    let extension = version
+        .extension()
+        .map(|e| format!(".{}", e.to_string_lossy()))
+        .unwrap_or_default();
+    let template = dir.join(format!("{}{}", TEMPLATE_STEM, extension));
+    if template.exists() {
+        archive_template(dir, &template)?;
+    }
+    info!("Restoring template {}", version.display());
+    fs::copy(version, &template)?;
+    Ok(())
+}
+
+/// Sets the detected executables in the configs of the DCC:s that don't have one yet. Returns the
+/// names of the DCC:s that were updated.
+pub fn fill_executables(
+    templates_dir: &Path,
+    detected: &[(&'static [&'static str], PathBuf)],
+) -> Result<Vec<String>, io::Error> {
+    let mut updated = Vec::new();
+    for entry in scan_templates(templates_dir)? {
+        let mut dcc = match entry.dcc {
+            Some(d) if d.executable.is_none() => d,
+            _ => continue,
+        };
+        let executable = match executable_for(detected, &dcc.name)
+            .or_else(|| executable_for(detected, &file_name(&entry.dir)))
+        {
+            Some(e) => e.clone(),
+            None => continue,
+        };
+        info!(
+            "Setting the {} executable to {}",
+            dcc.name,
+            executable.display()
+        );
+        dcc.executable = Some(executable);
+        write_yaml(&entry.dir.join(APP_CONFIG_FILE_NAME), &dcc)?;
+        updated.push(dcc.name);
+    }
+    Ok(updated)
+}
@@ -0,0 +1,103 @@
+use egui::{Color32, Stroke, Visuals};
+use log::{error, info};
+use std::path::Path;
+
+/// Studio theme file, read from the pipeline template dir when the config has no theme.
+pub const THEME_FILE_NAME: &str = "theme.yaml";
+
+/// Colors on top of the dark or light egui visuals. Colors are hex strings like `#e8a33d`.
+#[derive(serde::Deserialize, serde::Serialize, Debug, Default, PartialEq, Clone)]
+#[serde(default)]
+pub struct Theme {
+    /// Used for selections, links and active widgets.
+    pub accent: Option<String>,
+    /// Background of the panels.
+    pub background: Option<String>,
+    /// Black or white backgrounds, pure text colors and stronger widget outlines.
+    pub high_contrast: bool,
+}
+
+/// Parses `#rrggbb` or `rrggbb`.
+pub fn parse_color(hex: &str) -> Option<Color32> {
+    let hex = hex.trim().trim_start_matches('#');
+    if hex.len() != 6 {
+        return None;
+    }
+    let channel = |i: usize| u8::from_str_radix(hex.get(i..i + 2)?, 16).ok();
+    Some(Color32::from_rgb(channel(0)?, channel(2)?, channel(4)?))
+}
+
+impl Theme {
+    /// Reads `theme.yaml` from the dir. A missing file means no theme.
+    pub fn load(dir: &Path) -> Result<Option<Theme>, String> {
+        let path = dir.join(THEME_FILE_NAME);
+        if !path.exists() {
+            return Ok(None);
+        }
+        info!("Reading theme: {}", path.display());
+        let file = std::fs::File::open(&path)
+            .map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+        match serde_yaml::from_reader(file) {
+            Ok(t) => Ok(Some(t)),
+            Err(e) => {
+                let message = format!("Failed to read {}: {}", path.display(), e);
+                error!("{}", message);
+                Err(message)
+            }
+        }
+    }
+
+    pub fn visuals(&self, dark_mode: bool) -> Visuals {
+        let mut visuals = if dark_mode {
+            Visuals::dark()
+        } else {
+            Visuals::light()
+        };
+
+        if let Some(background) = self.background.as_deref().and_then(parse_color) {
+            visuals.panel_fill = background;
+            visuals.window_fill = background;
+        }
+
+        if self.high_contrast {
+            let (background, text) = if dark_mode {
+                (Color32::BLACK, Color32::WHITE)
+            } else {
+                (Color32::WHITE, Color32::BLACK)
+            };
+            visuals.panel_fill = background;
+            visuals.window_fill = background;
+            visuals.extreme_bg_color = background;
+            visuals.override_text_color = Some(text);
+            for widget in [
+                &mut visuals.widgets.noninteractive,
+                &mut visuals.widgets.inactive,
+                &mut visuals.widgets.hovered,
+                &mut visuals.widgets.active,
+                &mut visuals.widgets.open,
+            ] {
+                widget.bg_stroke = Stroke::new(widget.bg_stroke.width.max(1.5), text);
+                widget.fg_stroke.color = text;
+            }
+        }
+
+        if let Some(accent) = self.accent.as_deref().and_then(parse_color) {
+            visuals.selection.bg_fill = accent;
+            visuals.selection.stroke.color = contrasting(accent);
+            visuals.hyperlink_color = accent;
+            visuals.widgets.active.bg_fill = accent;
+            visuals.widgets.hovered.bg_stroke.color = accent;
+        }
+        visuals
+    }
+}
+
+/// Black or white, whichever reads better on the color.
+fn contrasting(color: Color32) -> Color32 {
+    let luminance = 0.299 * color.r() as f32 + 0.587 * color.g() as f32 + 0.114 * color.b() as f32;
+    if luminance > 140. {
+        Color32::BLACK
+    } else {
+        Color32::WHITE
+    }
+}
@@ -0,0 +1,131 @@
+use crate::jobs::JobHandle;
+use crate::sequences::OutputItem;
+use log::info;
+use std::io::{BufRead, BufReader, Read};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+pub const PROXY_EXTENSION: &str = "mp4";
+
+/// Runs a configurable ffmpeg binary to make h264 proxies of outputs.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, PartialEq)]
+pub struct Transcoder {
+    pub ffmpeg: PathBuf,
+    /// Frame rate used to read image sequences.
+    pub framerate: u32,
+    /// Encoding arguments, placed between the input and the output.
+    pub args: Vec<String>,
+}
+
+impl Transcoder {
+    fn command(&self, input: &OutputItem, output: &Path) -> Command {
+        let mut command = Command::new(&self.ffmpeg);
+        command.args(["-hide_banner", "-loglevel", "error", "-nostats", "-n"]);
+        command.args(["-progress", "pipe:1"]);
+
+        match input {
+            OutputItem::Sequence(s) => {
+                let pattern = format!("{}%0{}d{}", s.prefix, s.padding, s.suffix);
+                command
+                    .arg("-framerate")
+                    .arg(self.framerate.to_string())
+                    .arg("-start_number")
+                    .arg(s.first().to_string())
+                    .arg("-i")
+                    .arg(s.dir.join(pattern));
+            }
+            OutputItem::File { path, .. } => {
+                command.arg("-i").arg(path);
+            }
+        }
+        command.args(&self.args).arg(output);
+        command
+    }
+
+    /// Runs ffmpeg, reporting progress for sequences. Meant to run as a job, cancelling kills ffmpeg.
+    /// Sequences with missing frames fail without running it.
+    pub fn transcode(
+        &self,
+        input: &OutputItem,
+        output: &Path,
+        job: &JobHandle,
+    ) -> Result<String, String> {
+        // ffmpeg stops reading a sequence at the first missing frame, the proxy would be cut short.
+        if let OutputItem::Sequence(s) = input {
+            if !s.missing_frames().is_empty() {
+                return Err(format!(
+                    "{} is missing frames {}.",
+                    input.name(),
+                    s.missing_ranges()
+                ));
+            }
+        }
+        if let Some(parent) = output.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+
+        info!("Transcoding {} to {}", input.name(), output.display());
+        let mut child = self
+            .command(input, output)
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to start ffmpeg: {}", e))?;
+
+        let total_frames = match input {
+            OutputItem::Sequence(s) => s.frames.len() as f32,
+            OutputItem::File { .. } => 0.,
+        };
+        if let Some(stdout) = child.stdout.take() {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if job.is_cancelled() {
+                    let _ = child.kill();
+                    let _ = child.wait();
+                    let _ = std::fs::remove_file(output);
+                    return Err(String::from("Cancelled."));
+                }
+                let frame = match line.strip_prefix("frame=") {
+                    Some(f) => f.trim().parse::<f32>().unwrap_or(0.),
+                    None => continue,
+                };
+                if total_frames > 0. {
+                    job.set_progress(frame / total_frames);
+                }
+            }
+        }
+
+        let mut errors = String::new();
+        if let Some(mut stderr) = child.stderr.take() {
+            let _ = stderr.read_to_string(&mut errors);
+        }
+        match child.wait() {
+            Ok(s) if s.success() => Ok(format!("Created {}", output.display())),
+            Ok(s) => Err(format!("ffmpeg {}: {}", s, errors.trim())),
+            Err(e) => Err(e.to_string()),
+        }
+    }
+}
+
+pub fn default_proxy_args() -> Vec<String> {
+    ["-c:v", "libx264", "-pix_fmt", "yuv420p", "-crf", "18"]
+        .iter()
+        .map(|a| a.to_string())
+        .collect()
+}
+
+pub fn default_framerate() -> u32 {
+    24
+}
+
+/// The output path of a proxy, `<dir>/<item name>.mp4` with the frame padding removed.
+pub fn proxy_path(item: &OutputItem, dir: &Path) -> PathBuf {
+    let name = match item {
+        OutputItem::Sequence(s) => s.prefix.trim_end_matches(['.', '_']).to_string(),
+        OutputItem::File { path, .. } => path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default(),
+    };
+    dir.join(format!("{}.{}", name, PROXY_EXTENSION))
+}
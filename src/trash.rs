@@ -0,0 +1,238 @@
+use crate::atomic::write_yaml;
+use crate::helpers::now_timestamp;
+use crate::vfs::move_path;
+use log::{error, info};
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::cmp::Reverse;
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const TRASH_DIR_NAME: &str = ".trash";
+const TRASH_ITEM_FILE_NAME: &str = "trash.yaml";
+const TRASH_DATA_FILE_NAME: &str = "data.yaml";
+
+/// What kind of item was trashed, decides how it is restored.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, PartialEq)]
+pub enum TrashKind {
+    File,
+    Folder,
+    Task,
+    Client,
+}
+
+/// Metadata for a trashed item, stored next to it in the trash so it can be restored to its original location.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, PartialEq)]
+pub struct TrashItem {
+    pub id: String,
+    pub name: String,
+    pub kind: TrashKind,
+    pub original_path: PathBuf,
+    pub trashed_at: u64,
+}
+
+/// A trash directory. Projects have their own trash in the project root, clients use one next to the clients file.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, PartialEq)]
+pub struct Trash {
+    pub path: PathBuf,
+}
+
+impl Trash {
+    /// Returns the trash inside the given root dir, e.g. a project dir.
+    pub fn new(root: &Path) -> Self {
+        Self {
+            path: root.join(TRASH_DIR_NAME),
+        }
+    }
+
+    /// The dir of the item in the trash. The id and name are read from the shared drive, so anything but a
+    /// plain file name is refused rather than joined, an empty id or `..` would point at the trash or outside it.
+    fn item_path(&self, item: &TrashItem) -> Result<PathBuf, io::Error> {
+        for part in [&item.id, &item.name] {
+            let mut components = Path::new(part).components();
+            if !matches!(
+                (components.next(), components.next()),
+                (Some(Component::Normal(_)), None)
+            ) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Invalid trash item: {:?}", part),
+                ));
+            }
+        }
+        Ok(self.path.join(&item.id))
+    }
+
+    /// The original path is read from the shared drive too, it has to be in the dir the trash belongs to,
+    /// e.g. the project, and not in the trash.
+    fn check_original_path(&self, item: &TrashItem) -> Result<(), io::Error> {
+        let root = self.path.parent().unwrap_or(&self.path);
+        let inside = match item.original_path.strip_prefix(root) {
+            Ok(relative) => {
+                relative
+                    .components()
+                    .all(|c| matches!(c, Component::Normal(_)))
+                    && relative.components().next().is_some()
+                    && !item.original_path.starts_with(&self.path)
+            }
+            Err(_e) => false,
+        };
+        if inside {
+            Ok(())
+        } else {
+            Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Can't restore to {}, it's not in {}.",
+                    item.original_path.display(),
+                    root.display()
+                ),
+            ))
+        }
+    }
+
+    /// Creates a new item dir in the trash and returns the item metadata.
+    fn new_item(
+        &self,
+        name: &str,
+        kind: TrashKind,
+        original_path: &Path,
+    ) -> Result<TrashItem, io::Error> {
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+
+        let item = TrashItem {
+            id: format!("{}_{}", nanos, name),
+            name: String::from(name),
+            kind,
+            original_path: original_path.to_path_buf(),
+            trashed_at: now_timestamp(),
+        };
+        fs::create_dir_all(self.item_path(&item)?)?;
+        Ok(item)
+    }
+
+    fn write_item(&self, item: &TrashItem) -> Result<(), io::Error> {
+        let path = self.item_path(item)?.join(TRASH_ITEM_FILE_NAME);
+        match write_yaml(&path, item) {
+            Ok(()) => Ok(()),
+            Err(e) => {
+                error!("Failed to write trash item: {}", e);
+                Err(e)
+            }
+        }
+    }
+
+    /// Moves a file or folder into the trash.
+    pub fn put(&self, path: &Path, kind: TrashKind) -> Result<TrashItem, io::Error> {
+        let name = path
+            .file_name()
+            .and_then(|n| n.to_str())
+            .unwrap_or("")
+            .to_string();
+        info!("Moving to trash: {}", path.display());
+
+        let item = self.new_item(&name, kind, path)?;
+        let item_path = self.item_path(&item)?;
+        let result = move_path(path, &item_path.join(&name)).and_then(|()| self.write_item(&item));
+
+        match result {
+            Ok(()) => Ok(item),
+            Err(e) => {
+                error!("Failed to move {} to trash: {}", path.display(), e);
+                let _ = fs::remove_dir_all(&item_path);
+                Err(e)
+            }
+        }
+    }
+
+    /// Stores serializable data in the trash, for items that aren't files on their own, like clients.
+    pub fn put_data<T: Serialize>(
+        &self,
+        name: &str,
+        kind: TrashKind,
+        original_path: &Path,
+        data: &T,
+    ) -> Result<TrashItem, io::Error> {
+        let item = self.new_item(name, kind, original_path)?;
+        let item_path = self.item_path(&item)?;
+
+        if let Err(e) = write_yaml(&item_path.join(TRASH_DATA_FILE_NAME), data) {
+            error!("Failed to write trash data: {}", e);
+            let _ = fs::remove_dir_all(&item_path);
+            return Err(e);
+        }
+        self.write_item(&item)?;
+        Ok(item)
+    }
+
+    /// Reads data stored by `put_data`.
+    pub fn read_data<T: DeserializeOwned>(&self, item: &TrashItem) -> Result<T, io::Error> {
+        let file = fs::File::open(self.item_path(item)?.join(TRASH_DATA_FILE_NAME))?;
+        match serde_yaml::from_reader(file) {
+            Ok(d) => Ok(d),
+            Err(e) => Err(io::Error::new(io::ErrorKind::Other, e.to_string())),
+        }
+    }
+
+    /// Lists the items in the trash, newest first. A missing trash dir is an empty trash.
+    pub fn list(&self) -> Result<Vec<TrashItem>, io::Error> {
+        let mut items = Vec::new();
+        if !self.path.exists() {
+            return Ok(items);
+        }
+
+        for result in fs::read_dir(&self.path)? {
+            let entry = match result {
+                Ok(e) => e,
+                Err(_e) => continue,
+            };
+            let file = match fs::File::open(entry.path().join(TRASH_ITEM_FILE_NAME)) {
+                Ok(f) => f,
+                Err(_e) => continue,
+            };
+            match serde_yaml::from_reader::<_, TrashItem>(file) {
+                Ok(i) => items.push(i),
+                Err(e) => error!(
+                    "Could not read trash item {}: {}",
+                    entry.path().display(),
+                    e
+                ),
+            }
+        }
+
+        items.sort_by_key(|i| Reverse(i.trashed_at));
+        Ok(items)
+    }
+
+    /// Moves a trashed file or folder back to its original location. Data items have to be restored by the caller,
+    /// using `read_data` followed by `purge`.
+    pub fn restore(&self, item: &TrashItem) -> Result<(), io::Error> {
+        let item_path = self.item_path(item)?;
+        self.check_original_path(item)?;
+        if item.original_path.exists() {
+            return Err(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!("{} already exists.", item.original_path.display()),
+            ));
+        }
+        if let Some(parent) = item.original_path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        info!("Restoring from trash: {}", item.original_path.display());
+        move_path(&item_path.join(&item.name), &item.original_path)?;
+        self.purge(item)
+    }
+
+    /// Permanently deletes an item from the trash.
+    pub fn purge(&self, item: &TrashItem) -> Result<(), io::Error> {
+        let item_path = self.item_path(item)?;
+        info!("Purging from trash: {}", item.name);
+        fs::remove_dir_all(item_path)
+    }
+}
@@ -0,0 +1,340 @@
+use crate::jobs::JobHandle;
+use crate::vfs::{Fs, RealFs};
+use log::{info, warn};
+use std::env;
+use std::fs;
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{IpAddr, SocketAddr, TcpStream};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::Duration;
+
+const FTP_TIMEOUT: Duration = Duration::from_secs(60);
+const BUFFER_SIZE: usize = 1024 * 1024;
+
+fn default_ftp_port() -> u16 {
+    21
+}
+
+fn default_sftp_port() -> u16 {
+    22
+}
+
+/// How a package gets to the client.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "lowercase")]
+pub enum Transport {
+    /// Plain FTP in passive mode. The password is read from the environment variable, none logs in
+    /// without one.
+    Ftp {
+        host: String,
+        #[serde(default = "default_ftp_port")]
+        port: u16,
+        user: String,
+        #[serde(default)]
+        password_env: Option<String>,
+        #[serde(default)]
+        dir: String,
+    },
+    /// The OpenSSH sftp client, logging in with a key since it can't ask for passwords.
+    Sftp {
+        host: String,
+        #[serde(default = "default_sftp_port")]
+        port: u16,
+        user: String,
+        #[serde(default)]
+        dir: String,
+        /// The sftp on the PATH if not set.
+        #[serde(default)]
+        sftp: Option<PathBuf>,
+    },
+    /// Runs a command for each uploaded path, e.g. Aspera's ascp or the MASV CLI. The args can use
+    /// `{path}` and `{name}`, the path and its file name.
+    Command { program: PathBuf, args: Vec<String> },
+}
+
+/// A client endpoint that delivery packages can be uploaded to.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, PartialEq)]
+pub struct UploadTarget {
+    pub name: String,
+    #[serde(flatten)]
+    pub transport: Transport,
+}
+
+impl UploadTarget {
+    /// Uploads the files and dirs into the remote dir. Meant to run as a job.
+    pub fn upload(&self, paths: &[PathBuf], job: &JobHandle) -> Result<String, String> {
+        info!("Uploading {} paths to {}", paths.len(), self.name);
+        match &self.transport {
+            Transport::Ftp {
+                host,
+                port,
+                user,
+                password_env,
+                dir,
+            } => {
+                let password = match password_env {
+                    Some(var) => env::var(var)
+                        .map_err(|_e| format!("Set {} to the password of {}.", var, self.name))?,
+                    None => String::new(),
+                };
+                let total: u64 = paths.iter().map(|p| total_size(p)).sum();
+                let mut done = 0;
+                let mut ftp = Ftp::connect(host, *port, user, &password)
+                    .map_err(|e| format!("Failed to connect to {}: {}", self.name, e))?;
+                let result = ftp.change_dir_all(dir).and_then(|()| {
+                    for path in paths {
+                        ftp.put(path, &mut |read| {
+                            done += read;
+                            job.set_progress(done as f32 / total.max(1) as f32);
+                            !job.is_cancelled()
+                        })?;
+                    }
+                    Ok(())
+                });
+                ftp.quit();
+                result.map_err(|e| format!("Failed to upload to {}: {}", self.name, e))?;
+            }
+            Transport::Sftp {
+                host,
+                port,
+                user,
+                dir,
+                sftp,
+            } => {
+                // One session per path, so there is progress between paths even when sftp doesn't
+                // print its meter, which it only does on a terminal.
+                for (i, path) in paths.iter().enumerate() {
+                    let mut child = Command::new(sftp.as_deref().unwrap_or(Path::new("sftp")))
+                        .arg("-P")
+                        .arg(port.to_string())
+                        .args(["-b", "-"])
+                        .arg(format!("{}@{}", user, host))
+                        .stdin(Stdio::piped())
+                        .stdout(Stdio::piped())
+                        .stderr(Stdio::piped())
+                        .spawn()
+                        .map_err(|e| format!("Failed to start sftp: {}", e))?;
+                    if let Some(mut stdin) = child.stdin.take() {
+                        stdin
+                            .write_all(sftp_batch(dir, std::slice::from_ref(path)).as_bytes())
+                            .map_err(|e| format!("Failed to write to sftp: {}", e))?;
+                    }
+                    job.wait_reading_percent(child, "sftp")?;
+                    job.set_progress((i + 1) as f32 / paths.len() as f32);
+                }
+            }
+            Transport::Command { program, args } => {
+                for (i, path) in paths.iter().enumerate() {
+                    let child = Command::new(program)
+                        .args(command_args(args, path))
+                        .stdin(Stdio::null())
+                        .stdout(Stdio::piped())
+                        .stderr(Stdio::piped())
+                        .spawn()
+                        .map_err(|e| format!("Failed to start {}: {}", program.display(), e))?;
+                    job.wait_reading_percent(child, &program.display().to_string())?;
+                    job.set_progress((i + 1) as f32 / paths.len() as f32);
+                }
+            }
+        }
+        Ok(format!("Uploaded {} paths to {}", paths.len(), self.name))
+    }
+}
+
+fn total_size(path: &Path) -> u64 {
+    if path.is_dir() {
+        let paths = RealFs.read_dir(path).unwrap_or_default();
+        return paths.iter().map(|p| total_size(p)).sum();
+    }
+    fs::metadata(path).map_or(0, |m| m.len())
+}
+
+/// The args with `{path}` and `{name}` replaced.
+pub fn command_args(args: &[String], path: &Path) -> Vec<String> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    args.iter()
+        .map(|a| {
+            a.replace("{path}", &path.to_string_lossy())
+                .replace("{name}", &name)
+        })
+        .collect()
+}
+
+/// The commands sftp runs: turning on the progress meter, which batch mode turns off, into the dir,
+/// creating it if needed, then a recursive put of each path.
+pub fn sftp_batch(dir: &str, paths: &[PathBuf]) -> String {
+    let quote = |s: &str| format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""));
+    let mut batch = String::from("progress\n");
+    if !dir.is_empty() {
+        // The leading dash ignores the error of an existing dir.
+        batch.push_str(&format!("-mkdir {}\ncd {}\n", quote(dir), quote(dir)));
+    }
+    for path in paths {
+        batch.push_str(&format!("put -r {}\n", quote(&path.to_string_lossy())));
+    }
+    batch.push_str("bye\n");
+    batch
+}
+
+/// Copies the source to the data connection. The progress is called like in `Ftp::put`.
+fn send(
+    source: &mut dyn Read,
+    data: &mut TcpStream,
+    progress: &mut dyn FnMut(u64) -> bool,
+) -> Result<(), io::Error> {
+    let mut buffer = vec![0; BUFFER_SIZE];
+    loop {
+        let read = source.read(&mut buffer)?;
+        if read == 0 {
+            return Ok(());
+        }
+        data.write_all(&buffer[..read])?;
+        if !progress(read as u64) {
+            return Err(io::Error::new(
+                io::ErrorKind::Interrupted,
+                "Upload cancelled.",
+            ));
+        }
+    }
+}
+
+/// A passive FTP session, enough to create dirs and store files.
+pub struct Ftp {
+    control: BufReader<TcpStream>,
+}
+
+impl Ftp {
+    pub fn connect(host: &str, port: u16, user: &str, password: &str) -> Result<Ftp, io::Error> {
+        let stream = TcpStream::connect((host, port))?;
+        stream.set_read_timeout(Some(FTP_TIMEOUT))?;
+        stream.set_write_timeout(Some(FTP_TIMEOUT))?;
+        let mut ftp = Ftp {
+            control: BufReader::new(stream),
+        };
+        ftp.expect_reply(&[220])?;
+        if ftp.command(&format!("USER {}", user), &[230, 331])?.0 == 331 {
+            ftp.command(&format!("PASS {}", password), &[230, 202])?;
+        }
+        ftp.command("TYPE I", &[200])?;
+        Ok(ftp)
+    }
+
+    /// Reads a reply, which may span lines like `150-...` up to `150 ...`.
+    fn read_reply(&mut self) -> Result<(u32, String), io::Error> {
+        let mut line = String::new();
+        self.control.read_line(&mut line)?;
+        let code = line
+            .get(..3)
+            .and_then(|c| c.parse::<u32>().ok())
+            .ok_or_else(|| {
+                io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    format!("Invalid reply: {}", line.trim()),
+                )
+            })?;
+        if line.as_bytes().get(3) == Some(&b'-') {
+            let end = format!("{} ", code);
+            loop {
+                let mut next = String::new();
+                if self.control.read_line(&mut next)? == 0 || next.starts_with(&end) {
+                    break;
+                }
+            }
+        }
+        Ok((code, line.trim_end().to_string()))
+    }
+
+    fn expect_reply(&mut self, codes: &[u32]) -> Result<(u32, String), io::Error> {
+        let (code, line) = self.read_reply()?;
+        if codes.contains(&code) {
+            Ok((code, line))
+        } else {
+            Err(io::Error::new(io::ErrorKind::Other, line))
+        }
+    }
+
+    fn command(&mut self, command: &str, codes: &[u32]) -> Result<(u32, String), io::Error> {
+        let stream = self.control.get_mut();
+        stream.write_all(command.as_bytes())?;
+        stream.write_all(b"\r\n")?;
+        self.expect_reply(codes)
+    }
+
+    /// Opens a data connection. The address in the reply is ignored for the one of the server, it's
+    /// often wrong behind NAT.
+    fn passive(&mut self) -> Result<TcpStream, io::Error> {
+        let (_code, line) = self.command("PASV", &[227])?;
+        let numbers: Vec<u16> = line
+            .split(['(', ')'])
+            .nth(1)
+            .unwrap_or_default()
+            .split(',')
+            .filter_map(|n| n.trim().parse().ok())
+            .collect();
+        if numbers.len() != 6 {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, line));
+        }
+        let ip: IpAddr = self.control.get_ref().peer_addr()?.ip();
+        let stream = TcpStream::connect(SocketAddr::new(ip, numbers[4] * 256 + numbers[5]))?;
+        stream.set_write_timeout(Some(FTP_TIMEOUT))?;
+        Ok(stream)
+    }
+
+    /// Changes to the dir, creating the missing dirs on the way.
+    pub fn change_dir_all(&mut self, dir: &str) -> Result<(), io::Error> {
+        if dir.starts_with('/') {
+            self.command("CWD /", &[250])?;
+        }
+        for part in dir.split('/').filter(|p| !p.is_empty()) {
+            self.make_dir(part)?;
+            self.command(&format!("CWD {}", part), &[250])?;
+        }
+        Ok(())
+    }
+
+    /// Creates the dir, an existing dir is fine.
+    fn make_dir(&mut self, name: &str) -> Result<(), io::Error> {
+        self.command(&format!("MKD {}", name), &[257, 550])
+            .map(|_| ())
+    }
+
+    /// Uploads the file or dir into the current dir. The progress is called with the bytes sent since
+    /// the last call, returning false cancels.
+    pub fn put(
+        &mut self,
+        path: &Path,
+        progress: &mut dyn FnMut(u64) -> bool,
+    ) -> Result<(), io::Error> {
+        let name = path.file_name().unwrap_or_default().to_string_lossy();
+        if path.is_dir() {
+            self.make_dir(&name)?;
+            self.command(&format!("CWD {}", name), &[250])?;
+            for child in RealFs.read_dir(path)? {
+                self.put(&child, progress)?;
+            }
+            self.command("CDUP", &[200, 250])?;
+            return Ok(());
+        }
+
+        let mut source = fs::File::open(path)?;
+        let mut data = self.passive()?;
+        self.command(&format!("STOR {}", name), &[125, 150])?;
+        let sent = send(&mut source, &mut data, progress);
+        // Closing the data connection ends the file.
+        drop(data);
+        let stored = self.expect_reply(&[226, 250]).map(|_| ());
+        if let Err(e) = sent.and(stored) {
+            // A truncated file could be mistaken for a delivered one.
+            if let Err(d) = self.command(&format!("DELE {}", name), &[250]) {
+                warn!("Failed to remove the partial upload {}: {}", name, d);
+            }
+            return Err(e);
+        }
+        Ok(())
+    }
+
+    pub fn quit(&mut self) {
+        let _ = self.command("QUIT", &[221]);
+    }
+}
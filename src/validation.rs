@@ -0,0 +1,124 @@
+use std::fmt;
+use std::path::Path;
+
+pub const MAX_NAME_LENGTH: usize = 64;
+pub const MAX_FILENAME_LENGTH: usize = 255;
+
+/// Names Windows refuses to use for files and folders, regardless of extension.
+const RESERVED_NAMES: [&str; 22] = [
+    "con", "prn", "aux", "nul", "com1", "com2", "com3", "com4", "com5", "com6", "com7", "com8",
+    "com9", "lpt1", "lpt2", "lpt3", "lpt4", "lpt5", "lpt6", "lpt7", "lpt8", "lpt9",
+];
+
+/// Describes why a name can't be used.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ValidationError {
+    Empty,
+    TooLong(usize, usize),
+    Reserved(String),
+    TrailingDotOrSpace,
+    AlreadyExists(String),
+}
+
+impl fmt::Display for ValidationError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ValidationError::Empty => write!(f, "Name is empty."),
+            ValidationError::TooLong(l, max) => {
+                write!(f, "Name is too long ({} of max {} characters).", l, max)
+            }
+            ValidationError::Reserved(n) => write!(f, "{} is a reserved name on Windows.", n),
+            ValidationError::TrailingDotOrSpace => {
+                write!(f, "Name can't end with a dot or a space.")
+            }
+            ValidationError::AlreadyExists(n) => write!(f, "{} already exists.", n),
+        }
+    }
+}
+
+/// Checks a sanitized name for a project, task or folder against the basic rules.
+pub fn validate_name(name: &str) -> Result<(), ValidationError> {
+    if name.is_empty() {
+        return Err(ValidationError::Empty);
+    }
+
+    let length = name.chars().count();
+    if length > MAX_NAME_LENGTH {
+        return Err(ValidationError::TooLong(length, MAX_NAME_LENGTH));
+    }
+
+    validate_path_component(name)
+}
+
+/// Checks rules that apply to any file or folder name on Windows.
+fn validate_path_component(name: &str) -> Result<(), ValidationError> {
+    if name.ends_with('.') || name.ends_with(' ') {
+        return Err(ValidationError::TrailingDotOrSpace);
+    }
+
+    let stem = name.split('.').next().unwrap_or("").to_lowercase();
+    if RESERVED_NAMES.contains(&stem.as_str()) {
+        return Err(ValidationError::Reserved(stem));
+    }
+    Ok(())
+}
+
+/// Checks a new project name, including collisions with existing projects. Comparison ignores case,
+/// since Windows and macOS drives usually do.
+pub fn validate_project_name(name: &str, existing: &[String]) -> Result<(), ValidationError> {
+    validate_name(name)?;
+
+    if existing
+        .iter()
+        .any(|e| e.to_lowercase() == name.to_lowercase())
+    {
+        return Err(ValidationError::AlreadyExists(String::from(name)));
+    }
+    Ok(())
+}
+
+/// Checks a new task or folder name, including collisions with existing entries in the parent directory.
+pub fn validate_child_name(name: &str, parent: &Path) -> Result<(), ValidationError> {
+    validate_name(name)?;
+
+    if parent.join(name).exists() {
+        return Err(ValidationError::AlreadyExists(String::from(name)));
+    }
+    Ok(())
+}
+
+/// Checks a list of new task or folder names. Names listed more than once are taken by the first.
+pub fn validate_child_names(names: &[String], parent: &Path) -> Vec<Result<(), ValidationError>> {
+    names
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            validate_child_name(name, parent)?;
+            if names[..i].contains(name) {
+                return Err(ValidationError::AlreadyExists(name.clone()));
+            }
+            Ok(())
+        })
+        .collect()
+}
+
+/// Checks the full path of a new file.
+pub fn validate_file_path(path: &Path) -> Result<(), ValidationError> {
+    let filename = path
+        .file_name()
+        .and_then(|f| f.to_str())
+        .unwrap_or("")
+        .to_string();
+
+    let length = filename.chars().count();
+    if length > MAX_FILENAME_LENGTH {
+        return Err(ValidationError::TooLong(length, MAX_FILENAME_LENGTH));
+    }
+
+    validate_path_component(&filename)?;
+
+    if path.exists() {
+        return Err(ValidationError::AlreadyExists(filename));
+    }
+    Ok(())
+}
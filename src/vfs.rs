@@ -0,0 +1,503 @@
+use crate::atomic::{create_bytes, write_bytes_atomic};
+use crate::permissions::{apply_to_dir, apply_to_file};
+use crate::retry::retry;
+use std::fs;
+use std::io;
+use std::panic;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::SystemTime;
+
+/// Scans run on at most this many threads, enough to hide the latency of a NAS without flooding it
+/// with requests.
+pub const MAX_SCAN_THREADS: usize = 8;
+
+/// The file operations used to scan and create projects, tasks, workfiles and clients. `RealFs` works
+/// on the disk, `MemoryFs` keeps everything in memory for tests.
+pub trait Fs: Send + Sync {
+    /// The paths of the entries in the dir, sorted.
+    fn read_dir(&self, path: &Path) -> Result<Vec<PathBuf>, io::Error>;
+    fn is_dir(&self, path: &Path) -> bool;
+    fn is_file(&self, path: &Path) -> bool;
+    fn exists(&self, path: &Path) -> bool {
+        self.is_dir(path) || self.is_file(path)
+    }
+    fn read_to_string(&self, path: &Path) -> Result<String, io::Error>;
+    /// Replaces the file atomically. Doesn't lock.
+    fn write(&self, path: &Path, contents: &[u8]) -> Result<(), io::Error>;
+    /// Like `write`, but fails with `AlreadyExists` if the file exists.
+    fn create_new(&self, path: &Path, contents: &[u8]) -> Result<(), io::Error>;
+    fn create_dir(&self, path: &Path) -> Result<(), io::Error>;
+    fn create_dir_all(&self, path: &Path) -> Result<(), io::Error>;
+    fn remove_file(&self, path: &Path) -> Result<(), io::Error>;
+    /// Only removes empty dirs.
+    fn remove_dir(&self, path: &Path) -> Result<(), io::Error>;
+    fn copy(&self, from: &Path, to: &Path) -> Result<(), io::Error>;
+    fn rename(&self, from: &Path, to: &Path) -> Result<(), io::Error>;
+    fn modified(&self, path: &Path) -> Result<SystemTime, io::Error>;
+}
+
+/// The disk. Operations failing with transient errors, common on busy shares, are retried.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct RealFs;
+
+impl Fs for RealFs {
+    fn read_dir(&self, path: &Path) -> Result<Vec<PathBuf>, io::Error> {
+        let mut paths: Vec<PathBuf> = retry(|| {
+            fs::read_dir(path).map(|r| r.filter_map(|e| e.ok()).map(|e| e.path()).collect())
+        })?;
+        paths.sort();
+        Ok(paths)
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        path.is_dir()
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        path.is_file()
+    }
+
+    fn read_to_string(&self, path: &Path) -> Result<String, io::Error> {
+        retry(|| fs::read_to_string(path))
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> Result<(), io::Error> {
+        write_bytes_atomic(path, contents)
+    }
+
+    fn create_new(&self, path: &Path, contents: &[u8]) -> Result<(), io::Error> {
+        create_bytes(path, contents)
+    }
+
+    fn create_dir(&self, path: &Path) -> Result<(), io::Error> {
+        retry(|| fs::create_dir(path))?;
+        apply_to_dir(path);
+        Ok(())
+    }
+
+    fn create_dir_all(&self, path: &Path) -> Result<(), io::Error> {
+        let missing: Vec<&Path> = path.ancestors().take_while(|p| !p.exists()).collect();
+        retry(|| fs::create_dir_all(path))?;
+        for dir in missing.into_iter().rev() {
+            apply_to_dir(dir);
+        }
+        Ok(())
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<(), io::Error> {
+        retry(|| fs::remove_file(path))
+    }
+
+    fn remove_dir(&self, path: &Path) -> Result<(), io::Error> {
+        retry(|| fs::remove_dir(path))
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> Result<(), io::Error> {
+        retry(|| fs::copy(from, to))?;
+        apply_to_file(to);
+        Ok(())
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<(), io::Error> {
+        retry(|| fs::rename(from, to))
+    }
+
+    fn modified(&self, path: &Path) -> Result<SystemTime, io::Error> {
+        retry(|| fs::metadata(path)?.modified())
+    }
+}
+
+#[cfg(test)]
+#[derive(Clone, Debug)]
+struct MemoryEntry {
+    /// None for dirs.
+    contents: Option<Vec<u8>>,
+    modified: SystemTime,
+}
+
+#[cfg(test)]
+/// Files and dirs in memory, for testing the scanning and creation logic without touching the disk.
+/// The root and dirs without a parent always exist. Like on disk, a dir counts as modified when an
+/// entry is added to or removed from it.
+#[derive(Debug, Default)]
+pub struct MemoryFs {
+    entries: std::sync::Mutex<std::collections::BTreeMap<PathBuf, MemoryEntry>>,
+    /// Modification times are counted up, so two changes never get the same time.
+    clock: AtomicUsize,
+}
+
+#[cfg(test)]
+fn not_found(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::NotFound,
+        format!("{} not found", path.display()),
+    )
+}
+
+#[cfg(test)]
+fn already_exists(path: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::AlreadyExists,
+        format!("{} already exists", path.display()),
+    )
+}
+
+#[cfg(test)]
+impl MemoryFs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a file and its parent dirs.
+    pub fn add_file(&self, path: &Path, contents: &str) {
+        if let Some(parent) = path.parent() {
+            let _ = self.create_dir_all(parent);
+        }
+        self.insert(path, Some(contents.as_bytes().to_vec()));
+    }
+
+    fn entries(
+        &self,
+    ) -> std::sync::MutexGuard<'_, std::collections::BTreeMap<PathBuf, MemoryEntry>> {
+        self.entries.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn tick(&self) -> SystemTime {
+        let nanos = self.clock.fetch_add(1, Ordering::Relaxed) as u64 + 1;
+        SystemTime::UNIX_EPOCH + std::time::Duration::from_nanos(nanos)
+    }
+
+    fn insert(&self, path: &Path, contents: Option<Vec<u8>>) {
+        let entry = MemoryEntry {
+            contents,
+            modified: self.tick(),
+        };
+        let added = self.entries().insert(path.to_path_buf(), entry).is_none();
+        if added {
+            self.touch_parent(path);
+        }
+    }
+
+    fn remove(&self, path: &Path) {
+        self.entries().remove(path);
+        self.touch_parent(path);
+    }
+
+    fn touch_parent(&self, path: &Path) {
+        let modified = self.tick();
+        let mut entries = self.entries();
+        if let Some(entry) = path.parent().and_then(|p| entries.get_mut(p)) {
+            entry.modified = modified;
+        }
+    }
+
+    fn is_root(path: &Path) -> bool {
+        path.as_os_str().is_empty() || path.parent().is_none()
+    }
+
+    fn check_parent(&self, path: &Path) -> Result<(), io::Error> {
+        match path.parent() {
+            Some(p) if !Self::is_root(p) && !self.is_dir(p) => Err(not_found(p)),
+            _ => Ok(()),
+        }
+    }
+
+    fn file_contents(&self, path: &Path) -> Result<Vec<u8>, io::Error> {
+        self.entries()
+            .get(path)
+            .and_then(|e| e.contents.clone())
+            .ok_or_else(|| not_found(path))
+    }
+}
+
+#[cfg(test)]
+impl Fs for MemoryFs {
+    fn read_dir(&self, path: &Path) -> Result<Vec<PathBuf>, io::Error> {
+        if !Self::is_root(path) && !self.is_dir(path) {
+            return Err(not_found(path));
+        }
+        Ok(self
+            .entries()
+            .keys()
+            .filter(|p| p.parent() == Some(path))
+            .cloned()
+            .collect())
+    }
+
+    fn is_dir(&self, path: &Path) -> bool {
+        Self::is_root(path)
+            || self
+                .entries()
+                .get(path)
+                .map_or(false, |e| e.contents.is_none())
+    }
+
+    fn is_file(&self, path: &Path) -> bool {
+        self.entries()
+            .get(path)
+            .map_or(false, |e| e.contents.is_some())
+    }
+
+    fn read_to_string(&self, path: &Path) -> Result<String, io::Error> {
+        String::from_utf8(self.file_contents(path)?)
+            .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e.to_string()))
+    }
+
+    fn write(&self, path: &Path, contents: &[u8]) -> Result<(), io::Error> {
+        self.check_parent(path)?;
+        if self.is_dir(path) {
+            return Err(already_exists(path));
+        }
+        self.insert(path, Some(contents.to_vec()));
+        Ok(())
+    }
+
+    fn create_new(&self, path: &Path, contents: &[u8]) -> Result<(), io::Error> {
+        if self.exists(path) {
+            return Err(already_exists(path));
+        }
+        self.write(path, contents)
+    }
+
+    fn create_dir(&self, path: &Path) -> Result<(), io::Error> {
+        self.check_parent(path)?;
+        if self.exists(path) {
+            return Err(already_exists(path));
+        }
+        self.insert(path, None);
+        Ok(())
+    }
+
+    fn create_dir_all(&self, path: &Path) -> Result<(), io::Error> {
+        for dir in path.ancestors().collect::<Vec<_>>().into_iter().rev() {
+            if self.is_file(dir) {
+                return Err(already_exists(dir));
+            }
+            if !self.is_dir(dir) {
+                self.insert(dir, None);
+            }
+        }
+        Ok(())
+    }
+
+    fn remove_file(&self, path: &Path) -> Result<(), io::Error> {
+        if !self.is_file(path) {
+            return Err(not_found(path));
+        }
+        self.remove(path);
+        Ok(())
+    }
+
+    fn remove_dir(&self, path: &Path) -> Result<(), io::Error> {
+        if !self.read_dir(path)?.is_empty() {
+            return Err(io::Error::new(
+                io::ErrorKind::Other,
+                format!("{} is not empty", path.display()),
+            ));
+        }
+        self.remove(path);
+        Ok(())
+    }
+
+    fn copy(&self, from: &Path, to: &Path) -> Result<(), io::Error> {
+        let contents = self.file_contents(from)?;
+        self.write(to, &contents)
+    }
+
+    fn rename(&self, from: &Path, to: &Path) -> Result<(), io::Error> {
+        if !self.exists(from) {
+            return Err(not_found(from));
+        }
+        self.check_parent(to)?;
+        {
+            let mut entries = self.entries();
+            let moved: Vec<PathBuf> = entries
+                .keys()
+                .filter(|p| p.starts_with(from))
+                .cloned()
+                .collect();
+            for path in moved {
+                if let Some(entry) = entries.remove(&path) {
+                    let target = match path.strip_prefix(from) {
+                        Ok(rest) if !rest.as_os_str().is_empty() => to.join(rest),
+                        _ => to.to_path_buf(),
+                    };
+                    entries.insert(target, entry);
+                }
+            }
+        }
+        self.touch_parent(from);
+        self.touch_parent(to);
+        Ok(())
+    }
+
+    fn modified(&self, path: &Path) -> Result<SystemTime, io::Error> {
+        self.entries()
+            .get(path)
+            .map(|e| e.modified)
+            .ok_or_else(|| not_found(path))
+    }
+}
+
+/// Maps the items on up to `MAX_SCAN_THREADS` threads, keeping their order. Each thread takes the next
+/// item when done, so a slow dir doesn't hold up the rest.
+pub fn parallel_map<T: Sync, R: Send>(items: &[T], f: impl Fn(&T) -> R + Sync) -> Vec<R> {
+    let threads = MAX_SCAN_THREADS.min(items.len());
+    if threads <= 1 {
+        return items.iter().map(f).collect();
+    }
+    let next = AtomicUsize::new(0);
+    let mut results: Vec<(usize, R)> = thread::scope(|s| {
+        let workers: Vec<_> = (0..threads)
+            .map(|_| {
+                s.spawn(|| {
+                    let mut done = Vec::new();
+                    loop {
+                        let i = next.fetch_add(1, Ordering::Relaxed);
+                        match items.get(i) {
+                            Some(item) => done.push((i, f(item))),
+                            None => return done,
+                        }
+                    }
+                })
+            })
+            .collect();
+        workers
+            .into_iter()
+            .flat_map(|w| w.join().unwrap_or_else(|e| panic::resume_unwind(e)))
+            .collect()
+    });
+    results.sort_by_key(|(i, _r)| *i);
+    results.into_iter().map(|(_i, r)| r).collect()
+}
+
+const COPY_BUFFER_SIZE: usize = 1 << 20;
+
+/// Copies the file in chunks, calling `progress` with the bytes copied so far and the size of the file.
+/// Returning false from it cancels the copy. Existing files aren't overwritten, a partly written copy
+/// is removed when the copy is cancelled or fails. Returns the size.
+pub fn copy_with_progress(
+    from: &Path,
+    to: &Path,
+    progress: &mut dyn FnMut(u64, u64) -> bool,
+) -> Result<u64, io::Error> {
+    copy_inspecting(from, to, progress, &mut |_chunk| ())
+}
+
+/// Like `copy_with_progress`, passing each chunk to `inspect` too, e.g. to hash the file on the way.
+pub fn copy_inspecting(
+    from: &Path,
+    to: &Path,
+    progress: &mut dyn FnMut(u64, u64) -> bool,
+    inspect: &mut dyn FnMut(&[u8]),
+) -> Result<u64, io::Error> {
+    match copy_chunks(from, to, progress, inspect) {
+        Ok(size) => {
+            apply_to_file(to);
+            Ok(size)
+        }
+        Err(e) if e.kind() == io::ErrorKind::AlreadyExists => Err(e),
+        Err(e) => {
+            let _ = fs::remove_file(to);
+            Err(e)
+        }
+    }
+}
+
+fn copy_chunks(
+    from: &Path,
+    to: &Path,
+    progress: &mut dyn FnMut(u64, u64) -> bool,
+    inspect: &mut dyn FnMut(&[u8]),
+) -> Result<u64, io::Error> {
+    use std::io::{Read, Write};
+
+    let mut input = retry(|| fs::File::open(from))?;
+    let metadata = input.metadata()?;
+    let mut output = fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(to)?;
+    let mut buffer = vec![0; COPY_BUFFER_SIZE];
+    let mut copied = 0;
+    loop {
+        if !progress(copied, metadata.len()) {
+            return Err(io::Error::new(
+                io::ErrorKind::Interrupted,
+                "Copy cancelled.",
+            ));
+        }
+        let read = input.read(&mut buffer)?;
+        if read == 0 {
+            break;
+        }
+        inspect(&buffer[..read]);
+        output.write_all(&buffer[..read])?;
+        copied += read as u64;
+    }
+    output.sync_all()?;
+    // Like `fs::copy`.
+    output.set_permissions(metadata.permissions())?;
+    Ok(copied)
+}
+
+/// Renames the file or dir. Between drives, where renames fail, it's copied and the original removed;
+/// a partial copy is removed if that fails.
+pub fn move_path(from: &Path, to: &Path) -> Result<(), io::Error> {
+    match RealFs.rename(from, to) {
+        Err(e) if is_cross_device(&e) => {
+            let copied = if from.is_dir() {
+                RealFs
+                    .create_dir(to)
+                    .and_then(|()| copy_dir_contents(&RealFs, from, to, &mut Vec::new()))
+            } else {
+                RealFs.copy(from, to)
+            };
+            if let Err(e) = copied {
+                let _ = if to.is_dir() {
+                    fs::remove_dir_all(to)
+                } else {
+                    fs::remove_file(to)
+                };
+                return Err(e);
+            }
+            if from.is_dir() {
+                fs::remove_dir_all(from)
+            } else {
+                fs::remove_file(from)
+            }
+        }
+        result => result,
+    }
+}
+
+/// EXDEV on unix, ERROR_NOT_SAME_DEVICE on Windows.
+fn is_cross_device(error: &io::Error) -> bool {
+    let code = if cfg!(windows) { 17 } else { 18 };
+    error.raw_os_error() == Some(code)
+}
+
+/// Copies the contents of a dir recursively into an existing dir, recording every created path so they
+/// can be rolled back.
+pub fn copy_dir_contents(
+    fs: &dyn Fs,
+    source: &Path,
+    destination: &Path,
+    created: &mut Vec<PathBuf>,
+) -> Result<(), io::Error> {
+    for path in fs.read_dir(source)? {
+        let target = destination.join(path.file_name().unwrap_or_default());
+
+        if fs.is_dir(&path) {
+            fs.create_dir(&target)?;
+            created.push(target.clone());
+            copy_dir_contents(fs, &path, &target, created)?;
+        } else {
+            fs.copy(&path, &target)?;
+            created.push(target);
+        }
+    }
+    Ok(())
+}
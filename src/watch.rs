@@ -0,0 +1,147 @@
+use crate::sequences::find_files;
+use log::info;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Seconds between scans of the inbox.
+pub const INBOX_SCAN_INTERVAL: u64 = 5;
+
+/// A file or dir dropped into the inbox, waiting for review before it's ingested.
+#[derive(Clone, Debug, PartialEq)]
+pub struct InboxEntry {
+    pub path: PathBuf,
+    /// The project named by the subdir the entry was dropped in.
+    pub project: Option<String>,
+    /// Total size, of all files for dirs.
+    pub size: u64,
+}
+
+fn entry_size(path: &Path) -> Result<u64, io::Error> {
+    if path.is_dir() {
+        let files = find_files(path, &|n: &str| n.starts_with('.'))?;
+        Ok(files
+            .iter()
+            .filter_map(|f| fs::metadata(f).ok())
+            .map(|m| m.len())
+            .sum())
+    } else {
+        Ok(fs::metadata(path)?.len())
+    }
+}
+
+fn list_dir(dir: &Path) -> Result<Vec<PathBuf>, io::Error> {
+    let mut paths: Vec<PathBuf> = fs::read_dir(dir)?
+        .filter_map(|e| e.ok())
+        .filter(|e| !e.file_name().to_string_lossy().starts_with('.'))
+        .map(|e| e.path())
+        .collect();
+    paths.sort();
+    Ok(paths)
+}
+
+/// Lists what's in the inbox. Subdirs named like a project hold entries for that project,
+/// everything else in the inbox is an entry without a project.
+pub fn scan_inbox(inbox: &Path, projects: &[String]) -> Result<Vec<InboxEntry>, io::Error> {
+    let mut entries = Vec::new();
+    for path in list_dir(inbox)? {
+        let name = path
+            .file_name()
+            .unwrap_or_default()
+            .to_string_lossy()
+            .to_string();
+        if path.is_dir() && projects.contains(&name) {
+            for p in list_dir(&path)? {
+                entries.push(InboxEntry {
+                    size: entry_size(&p)?,
+                    path: p,
+                    project: Some(name.clone()),
+                });
+            }
+        } else {
+            entries.push(InboxEntry {
+                size: entry_size(&path)?,
+                path,
+                project: None,
+            });
+        }
+    }
+    Ok(entries)
+}
+
+/// Keeps the entries of the inbox waiting for review. Entries are only listed once their size is the
+/// same in two scans in a row, so files still being copied into the inbox aren't picked up.
+#[derive(Debug, Default)]
+pub struct InboxWatcher {
+    sizes: HashMap<PathBuf, u64>,
+    pending: Vec<InboxEntry>,
+    /// Entries removed from the review queue without ingesting, ignored until they change.
+    dismissed: HashSet<PathBuf>,
+    /// Entries being ingested, removed from the inbox when done.
+    ingesting: HashSet<PathBuf>,
+}
+
+impl InboxWatcher {
+    /// Scans the inbox and returns the number of new entries waiting for review.
+    pub fn poll(&mut self, inbox: &Path, projects: &[String]) -> Result<usize, io::Error> {
+        let entries = scan_inbox(inbox, projects)?;
+        let mut new = 0;
+        let mut sizes = HashMap::new();
+        let mut pending = Vec::new();
+        self.ingesting
+            .retain(|p| entries.iter().any(|e| &e.path == p));
+        for entry in entries {
+            if self.ingesting.contains(&entry.path) {
+                continue;
+            }
+            let stable = self.sizes.get(&entry.path) == Some(&entry.size);
+            sizes.insert(entry.path.clone(), entry.size);
+            if !stable {
+                self.dismissed.remove(&entry.path);
+                continue;
+            }
+            if self.dismissed.contains(&entry.path) {
+                continue;
+            }
+            if !self.pending.iter().any(|p| p.path == entry.path) {
+                info!("New in inbox: {}", entry.path.display());
+                new += 1;
+            }
+            pending.push(entry);
+        }
+        self.sizes = sizes;
+        self.pending = pending;
+        Ok(new)
+    }
+
+    pub fn pending(&self) -> &[InboxEntry] {
+        &self.pending
+    }
+
+    /// Removes the entry from the review queue until it changes or is dropped again.
+    pub fn dismiss(&mut self, path: &Path) {
+        self.pending.retain(|p| p.path != path);
+        self.dismissed.insert(path.to_path_buf());
+    }
+
+    /// Removes the entry from the review queue while it's ingested.
+    pub fn take(&mut self, path: &Path) {
+        self.pending.retain(|p| p.path != path);
+        self.ingesting.insert(path.to_path_buf());
+    }
+
+    /// Puts an entry whose ingest failed back in the review queue at the next scan.
+    pub fn release(&mut self, path: &Path) {
+        self.ingesting.remove(path);
+    }
+}
+
+/// Deletes an ingested file or dir from the inbox.
+pub fn remove_entry(path: &Path) -> Result<(), io::Error> {
+    if path.is_dir() {
+        fs::remove_dir_all(path)
+    } else {
+        fs::remove_file(path)
+    }
+}
@@ -0,0 +1,185 @@
+use crate::helpers::current_user;
+use log::{error, info};
+use serde_json::{json, Value};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// The events webhooks can be fired on.
+#[derive(Clone, Copy, serde::Deserialize, serde::Serialize, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookEvent {
+    Publish,
+    Delivery,
+    StatusChange,
+}
+
+impl WebhookEvent {
+    fn name(&self) -> &'static str {
+        match self {
+            WebhookEvent::Publish => "publish",
+            WebhookEvent::Delivery => "delivery",
+            WebhookEvent::StatusChange => "status_change",
+        }
+    }
+
+    fn default_template(&self) -> &'static str {
+        match self {
+            WebhookEvent::Publish => "{user} published {file} ({version}) in {project} / {task}",
+            WebhookEvent::Delivery => "{user} delivered {file} in {project}",
+            WebhookEvent::StatusChange => "{user} set {project} {task} to {status}",
+        }
+    }
+}
+
+/// Slack and Teams get the message as `text`, generic endpoints get the whole context as JSON.
+#[derive(Clone, Copy, serde::Deserialize, serde::Serialize, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum WebhookKind {
+    Slack,
+    Teams,
+    #[default]
+    Generic,
+}
+
+/// An incoming webhook from the config, posted to on the listed events.
+#[derive(Clone, serde::Deserialize, serde::Serialize, Debug, PartialEq)]
+pub struct Webhook {
+    pub url: String,
+    #[serde(default)]
+    pub kind: WebhookKind,
+    /// No events means all events.
+    #[serde(default)]
+    pub events: Vec<WebhookEvent>,
+    /// The message, with the tokens {event}, {project}, {task}, {file}, {version}, {status} and {user}.
+    /// Empty uses a default message for the event.
+    #[serde(default)]
+    pub template: String,
+}
+
+/// What happened, filled in as far as it is known.
+#[derive(Clone, Debug, PartialEq)]
+pub struct WebhookContext {
+    pub event: WebhookEvent,
+    pub project: String,
+    pub task: String,
+    pub file: String,
+    pub version: String,
+    pub status: String,
+    pub user: String,
+}
+
+impl WebhookContext {
+    pub fn new(event: WebhookEvent) -> Self {
+        Self {
+            event,
+            project: String::new(),
+            task: String::new(),
+            file: String::new(),
+            version: String::new(),
+            status: String::new(),
+            user: current_user(),
+        }
+    }
+
+    fn tokens(&self) -> [(&'static str, &str); 7] {
+        [
+            ("{event}", self.event.name()),
+            ("{project}", &self.project),
+            ("{task}", &self.task),
+            ("{file}", &self.file),
+            ("{version}", &self.version),
+            ("{status}", &self.status),
+            ("{user}", &self.user),
+        ]
+    }
+}
+
+impl Webhook {
+    pub fn handles(&self, event: WebhookEvent) -> bool {
+        self.events.is_empty() || self.events.contains(&event)
+    }
+
+    pub fn message(&self, context: &WebhookContext) -> String {
+        let template = if self.template.is_empty() {
+            context.event.default_template()
+        } else {
+            &self.template
+        };
+        let mut message = String::from(template);
+        for (token, value) in context.tokens() {
+            message = message.replace(token, value);
+        }
+        message
+    }
+
+    pub fn payload(&self, context: &WebhookContext) -> Value {
+        let message = self.message(context);
+        match self.kind {
+            WebhookKind::Slack | WebhookKind::Teams => json!({ "text": message }),
+            WebhookKind::Generic => {
+                let mut payload = json!({ "message": message });
+                for (token, value) in context.tokens() {
+                    payload[token.trim_matches(|c| c == '{' || c == '}')] = json!(value);
+                }
+                payload
+            }
+        }
+    }
+
+    /// Posts the payload with curl, which ships with Windows 10, macOS and most Linux distros.
+    pub fn send(&self, context: &WebhookContext) -> Result<(), String> {
+        info!("Sending {} webhook: {}", context.event.name(), self.url);
+        let mut child = Command::new("curl")
+            .args(["--silent", "--show-error", "--fail", "-X", "POST"])
+            .args([
+                "-H",
+                "Content-Type: application/json",
+                "--data-binary",
+                "@-",
+            ])
+            .arg(&self.url)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::null())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| format!("Failed to run curl: {}", e))?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            let body = self.payload(context).to_string();
+            if let Err(e) = stdin.write_all(body.as_bytes()) {
+                error!("Failed to write webhook payload: {}", e);
+            }
+        }
+        let output = child
+            .wait_with_output()
+            .map_err(|e| format!("Failed to run curl: {}", e))?;
+        if output.status.success() {
+            Ok(())
+        } else {
+            let message = format!(
+                "Webhook {} failed: {}",
+                self.url,
+                String::from_utf8_lossy(&output.stderr).trim()
+            );
+            error!("{}", message);
+            Err(message)
+        }
+    }
+}
+
+/// Sends the context to every webhook handling its event, returning the number sent.
+pub fn send_all(webhooks: &[Webhook], context: &WebhookContext) -> Result<usize, String> {
+    let mut errors = Vec::new();
+    let mut sent = 0;
+    for webhook in webhooks.iter().filter(|w| w.handles(context.event)) {
+        match webhook.send(context) {
+            Ok(()) => sent += 1,
+            Err(e) => errors.push(e),
+        }
+    }
+    if errors.is_empty() {
+        Ok(sent)
+    } else {
+        Err(errors.join("\n"))
+    }
+}
@@ -1,76 +1,245 @@
+use crate::helpers::substitute_tokens;
 use crate::helpers::EXPLORER;
 use crate::helpers::FINDER;
+use crate::helpers::PIPELINE_TEMPLATE_DIR_NAME;
+use crate::naming::{FilenameTemplate, FilenameTokens, ParseError, DEFAULT_VERSION_PADDING};
+use crate::pathmap::to_launch;
+use crate::publishes::Publish;
+use crate::trash::{Trash, TrashItem, TrashKind};
+use crate::vfs::{copy_with_progress, Fs, RealFs};
 use crate::{Project, TaskTreeNode};
 use log::{error, info};
+use std::cmp::Ordering;
 use std::ffi::OsString;
 use std::fs::{self};
 use std::io::{Error, ErrorKind};
+use std::process::{Command, Stdio};
 use std::{ffi::OsStr, io, path::Path, path::PathBuf};
 
+/// How a workfile moved or copied to another task is versioned.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum VersionTransfer {
+    /// Start over at version 1.
+    #[default]
+    Reset,
+    /// Keep the version number of the file.
+    Continue,
+}
+
+/// Why a workfile version can't be deleted.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Retained {
+    Latest,
+    Published,
+}
+
+/// Which workfile versions are kept when deleting.
+#[derive(Clone, Copy, serde::Deserialize, serde::Serialize, Debug, PartialEq)]
+#[serde(default)]
+pub struct RetentionPolicy {
+    /// How many of the latest versions of each workfile are kept. The latest is always kept.
+    pub keep_latest: u32,
+    pub keep_published: bool,
+}
+
+impl Default for RetentionPolicy {
+    fn default() -> Self {
+        Self {
+            keep_latest: 1,
+            keep_published: true,
+        }
+    }
+}
+
+impl RetentionPolicy {
+    /// Checks if a file of the group may be deleted.
+    pub fn check(&self, group: &FileGroup, file: &File, published: bool) -> Result<(), Retained> {
+        if self.keep_published && published {
+            return Err(Retained::Published);
+        }
+        let newer = group
+            .files
+            .iter()
+            .filter(|f| f.version > file.version)
+            .count();
+        if newer < self.keep_latest.max(1) as usize {
+            return Err(Retained::Latest);
+        }
+        Ok(())
+    }
+}
+
+/// The versions of one workfile: files with the same name and extension.
+#[derive(Debug, PartialEq, Clone)]
+pub struct FileGroup {
+    pub name: String,
+    pub extension: String,
+    /// Sorted by version, oldest first.
+    pub files: Vec<File>,
+}
+
+impl FileGroup {
+    /// Groups files by name and extension, groups are sorted like the files.
+    pub fn group(files: &[File]) -> Vec<FileGroup> {
+        let mut sorted = files.to_vec();
+        sorted.sort();
+        let mut groups: Vec<FileGroup> = Vec::new();
+        for f in sorted {
+            match groups.last_mut() {
+                Some(g) if g.name == f.name && g.extension == f.extension => g.files.push(f),
+                _ => groups.push(FileGroup {
+                    name: f.name.clone(),
+                    extension: f.extension.clone(),
+                    files: vec![f],
+                }),
+            }
+        }
+        groups
+    }
+
+    /// The file with the highest version.
+    pub fn latest(&self) -> Option<&File> {
+        self.files.iter().max_by_key(|f| f.version)
+    }
+
+    /// The latest file, if the file is in the group but isn't the latest.
+    pub fn newer_than(&self, file: &File) -> Option<&File> {
+        self.latest()
+            .filter(|l| l.version > file.version && self.files.contains(file))
+    }
+}
+
 /// Represents a workfile found on drive.
-#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, PartialOrd, Ord, Eq, Clone)]
+#[derive(serde::Serialize, serde::Deserialize, Debug, PartialEq, Eq, Clone)]
 pub struct File {
     pub name: String,
     pub path: PathBuf,
     pub extension: String,
     pub version: u32,
+    #[serde(default)]
+    pub prefix: String,
+    #[serde(default)]
+    pub suffix: String,
+    #[serde(default)]
+    pub padding: usize,
+}
+
+/// Files are ordered by name, then numerically by version, so v1000 sorts after v999.
+impl Ord for File {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.name
+            .cmp(&other.name)
+            .then(self.extension.cmp(&other.extension))
+            .then(self.version.cmp(&other.version))
+            .then(self.suffix.cmp(&other.suffix))
+            .then(self.path.cmp(&other.path))
+    }
+}
+
+impl PartialOrd for File {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
 }
 
 impl File {
-    /// Returns the version number in a presentable format: v###.
+    /// Returns the version number in a presentable format: v###, padded like the filename and growing past v999.
     pub fn fmt_version(&self) -> String {
-        format!("v{:03}", self.version)
+        format!("v{:0width$}", self.version, width = self.padding())
+    }
+
+    /// Zero-padding of the version number, falls back to the template default for files read by older versions.
+    fn padding(&self) -> usize {
+        if self.padding == 0 {
+            DEFAULT_VERSION_PADDING
+        } else {
+            self.padding
+        }
     }
 
     /// Create a new representation of a workfile, from an existing file path.
-    pub fn from_path(path: PathBuf) -> Result<Self, String> {
+    pub fn from_path(path: PathBuf, template: &FilenameTemplate) -> Result<Self, ParseError> {
         let extension = String::from(
             path.extension()
                 .unwrap_or(OsStr::new(""))
                 .to_str()
                 .unwrap_or(""),
         );
-        let name = String::from(
+        let stem = String::from(
             path.file_stem()
                 .unwrap_or(OsStr::new(""))
                 .to_str()
                 .unwrap_or(""),
         );
-        let mut version_string = name.clone();
-
-        let mut version_offset = 0;
-
-        if name.len() > 5 {
-            version_offset = name.len() - 5;
-        }
 
-        let name = version_string.drain(..version_offset).collect();
+        let parsed = template.parse(&stem)?;
 
-        if !(&version_string.chars().nth(0).unwrap_or('0') == &'_'
-            && &version_string.chars().nth(1).unwrap_or('0') == &'v')
-        {
-            return Err(String::from("Not a valid filename."));
-        }
-        version_string.remove(0);
-        version_string.remove(0);
-        let version: u32 = version_string.parse().unwrap_or(1);
         Ok(Self {
-            name: name,
-            path: path,
-            version: version,
-            extension: extension,
+            name: parsed.name,
+            path,
+            version: parsed.tokens.version,
+            extension,
+            prefix: parsed.prefix,
+            suffix: parsed.suffix,
+            padding: parsed.padding,
         })
     }
 
     /// Open the file using system default application.
     pub fn open(&self) -> Result<(), io::Error> {
-        match open::that(&self.path) {
+        match open::that(to_launch(&self.path)) {
             Ok(()) => (),
             Err(e) => return Err(e),
         }
         Ok(())
     }
 
+    /// Open the file using system default application, with extra environment variables for the launched app.
+    /// On macOS apps are started by launch services, so running apps won't see the variables.
+    pub fn open_with_env(&self, env: &[(&str, &Path)]) -> Result<(), io::Error> {
+        let mut last_error = io::Error::new(ErrorKind::Other, "No launcher found.");
+        for mut command in open::commands(to_launch(&self.path)) {
+            let status = command
+                .envs(env.iter().copied())
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status();
+            match status {
+                Ok(s) if s.success() => return Ok(()),
+                Ok(s) => last_error = io::Error::new(ErrorKind::Other, s.to_string()),
+                Err(e) => last_error = e,
+            }
+        }
+        Err(last_error)
+    }
+
+    /// Start the executable with the file, with extra environment variables for the launched app.
+    pub fn launch_with_env(
+        &self,
+        executable: &Path,
+        env: &[(&str, &Path)],
+    ) -> Result<(), io::Error> {
+        info!(
+            "Opening {} with {}",
+            self.path.display(),
+            executable.display()
+        );
+        Command::new(to_launch(executable))
+            .arg(to_launch(&self.path))
+            .envs(env.iter().copied())
+            .stdin(Stdio::null())
+            .stdout(Stdio::null())
+            .stderr(Stdio::null())
+            .spawn()?;
+        Ok(())
+    }
+
+    /// Moves the file into the project trash.
+    pub fn move_to_trash(&self, project_path: &Path) -> Result<TrashItem, io::Error> {
+        Trash::new(project_path).put(&self.path, TrashKind::File)
+    }
+
     /// Reveal the file in Explorer or Finder.
     pub fn reveal(&self) {
         let path: PathBuf = self.path.clone();
@@ -85,13 +254,42 @@ impl File {
         }
     }
 
-    /// Copy the file with incremented version number.
-    pub fn version_up(&self) -> Result<(), io::Error> {
+    /// Copy the file with incremented version number, returning the new path.
+    pub fn version_up(&self) -> Result<PathBuf, io::Error> {
+        self.check_not_frozen()?;
+        self.version_up_with(&RealFs)
+    }
+
+    pub fn version_up_with(&self, fs: &dyn Fs) -> Result<PathBuf, io::Error> {
+        let mut new_version = self.clone();
+        new_version.increase_version_number();
+        self.copy_as_version_with(fs, new_version.version)
+    }
+
+    /// Like `version_up`, for large files, see `copy_with_progress`.
+    pub fn version_up_with_progress(
+        &self,
+        progress: &mut dyn FnMut(u64, u64) -> bool,
+    ) -> Result<PathBuf, io::Error> {
+        self.check_not_frozen()?;
         let mut new_version = self.clone();
         new_version.increase_version_number();
+        let new_path = self.version_path(new_version.version)?;
+        if new_path.exists() {
+            return Err(Error::new(ErrorKind::Other, "File already exists!"));
+        }
+        copy_with_progress(&self.path, &new_path, progress)?;
+        Ok(new_path)
+    }
+
+    /// The path of the given version of the file, next to the file.
+    pub fn version_path(&self, version: u32) -> Result<PathBuf, io::Error> {
+        let new_version = File {
+            version,
+            ..self.clone()
+        };
 
-        let mut new_path = self.path.clone();
-        new_path = match new_path.parent() {
+        let mut new_path = match self.path.parent() {
             Some(p) => p.to_path_buf(),
             None => {
                 return Err(io::Error::new(
@@ -100,20 +298,25 @@ impl File {
                 ))
             }
         };
-
         new_path.push(PathBuf::from(new_version.make_filename_from_self()));
+        Ok(new_path)
+    }
 
-        match new_path.try_exists() {
-            Ok(b) => {
-                if b {
-                    return Err(Error::new(ErrorKind::Other, "File already exists!"));
-                }
-            }
-            Err(e) => return Err(e),
+    /// Copy the file as the given version, e.g. to branch from an older version past the latest one.
+    pub fn copy_as_version(&self, version: u32) -> Result<PathBuf, io::Error> {
+        self.check_not_frozen()?;
+        self.copy_as_version_with(&RealFs, version)
+    }
+
+    pub fn copy_as_version_with(&self, fs: &dyn Fs, version: u32) -> Result<PathBuf, io::Error> {
+        let new_path = self.version_path(version)?;
+
+        if fs.exists(&new_path) {
+            return Err(Error::new(ErrorKind::Other, "File already exists!"));
         }
 
-        match fs::copy(&self.path, &new_path) {
-            Ok(_u) => return Ok(()),
+        match fs.copy(&self.path, &new_path) {
+            Ok(()) => Ok(new_path),
             Err(e) => {
                 error!(
                     "Failed to copy {} to {}: {}",
@@ -121,11 +324,176 @@ impl File {
                     &new_path.display(),
                     e.to_string()
                 );
-                return Err(e);
+                Err(e)
             }
         }
     }
 
+    /// The path of the file in another task. Files named after the project by the template are renamed for
+    /// the task, other files only get the new version. Versions already taken in the task are skipped.
+    pub fn transfer_path(
+        &self,
+        task: &TaskTreeNode,
+        project: &Project,
+        template: &FilenameTemplate,
+        version: VersionTransfer,
+    ) -> PathBuf {
+        let stem = self
+            .path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        // The file is in the work dir of its task.
+        let source_task = self
+            .path
+            .parent()
+            .and_then(|p| p.parent())
+            .and_then(|p| p.file_name())
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let parsed = template
+            .parse_in(&project.name_sanitized, &source_task, &stem)
+            .ok()
+            .filter(|p| p.tokens.project == project.name_sanitized && p.tokens.task == source_task);
+
+        let mut version = match version {
+            VersionTransfer::Reset => 1,
+            VersionTransfer::Continue => self.version,
+        };
+        loop {
+            let filename = match &parsed {
+                Some(p) => {
+                    let tokens = FilenameTokens {
+                        project: project.name_sanitized.clone(),
+                        task: task.name.clone(),
+                        name: p.tokens.name.clone(),
+                        version,
+                    };
+                    format!(
+                        "{}{}.{}",
+                        template.format(&tokens),
+                        self.suffix,
+                        self.extension
+                    )
+                }
+                None => File {
+                    version,
+                    ..self.clone()
+                }
+                .make_filename_from_self(),
+            };
+            let path = Self::make_path(task, filename);
+            if !path.exists() || path == self.path {
+                return path;
+            }
+            version += 1;
+        }
+    }
+
+    /// Copies or moves the file to another task, see `transfer_path`. Returns the new path.
+    pub fn transfer(
+        &self,
+        task: &TaskTreeNode,
+        project: &Project,
+        template: &FilenameTemplate,
+        version: VersionTransfer,
+        keep_source: bool,
+    ) -> Result<PathBuf, io::Error> {
+        let path = self.transfer_path(task, project, template, version);
+        if path == self.path {
+            return Err(Error::new(ErrorKind::Other, "The file is already there."));
+        }
+        RealFs.create_dir_all(&task.get_work_path())?;
+
+        if keep_source {
+            RealFs.copy(&self.path, &path)?;
+        } else if fs::rename(&self.path, &path).is_err() {
+            // Renaming fails across drives, copy and remove instead.
+            RealFs.copy(&self.path, &path)?;
+            fs::remove_file(&self.path)?;
+        }
+        info!("Transferred {} to {}", self.path.display(), path.display());
+        Ok(path)
+    }
+
+    /// The path of a copy under a new descriptive name at v001 in the task, e.g. for a lookdev variant.
+    pub fn variant_path(
+        &self,
+        name: &str,
+        task: &TaskTreeNode,
+        project: &Project,
+        template: &FilenameTemplate,
+    ) -> PathBuf {
+        let tokens = FilenameTokens {
+            project: project.name_sanitized.clone(),
+            task: task.name.clone(),
+            name: String::from(name),
+            version: 1,
+        };
+        Self::make_path(
+            task,
+            format!("{}.{}", template.format(&tokens), self.extension),
+        )
+    }
+
+    /// Copies the file as a new variant, see `variant_path`. Existing files are never overwritten.
+    pub fn duplicate_as(
+        &self,
+        name: &str,
+        task: &TaskTreeNode,
+        project: &Project,
+        template: &FilenameTemplate,
+    ) -> Result<PathBuf, io::Error> {
+        let path = self.variant_path(name, task, project, template);
+        if path.try_exists()? {
+            return Err(Error::new(
+                ErrorKind::AlreadyExists,
+                format!("{} already exists.", path.display()),
+            ));
+        }
+        RealFs.copy(&self.path, &path)?;
+        info!("Duplicated {} as {}", self.path.display(), path.display());
+        Ok(path)
+    }
+
+    /// The path of the file under a new name, keeping the version, suffix and extension.
+    pub fn renamed_path(&self, name: &str) -> Result<PathBuf, io::Error> {
+        // The literal between the name and the version, e.g. `_v`.
+        let literal = self.prefix.strip_prefix(self.name.as_str()).unwrap_or("");
+        let renamed = File {
+            name: String::from(name),
+            prefix: format!("{}{}", name, literal),
+            ..self.clone()
+        };
+        renamed.version_path(self.version)
+    }
+
+    /// Renames the file, see `renamed_path`. Existing files are never overwritten.
+    pub fn rename(&self, name: &str) -> Result<PathBuf, io::Error> {
+        self.check_not_frozen()?;
+        let path = self.renamed_path(name)?;
+        if path.try_exists()? {
+            return Err(Error::new(
+                ErrorKind::AlreadyExists,
+                format!("{} already exists.", path.display()),
+            ));
+        }
+        info!("Renaming {} to {}", self.path.display(), path.display());
+        fs::rename(&self.path, &path)?;
+        Ok(path)
+    }
+
+    /// Frozen publishes are kept as they are, see `Publish::set_frozen`.
+    fn check_not_frozen(&self) -> Result<(), io::Error> {
+        match Publish::find_frozen(&self.path).and_then(|p| p.frozen) {
+            Some(f) => Err(Error::new(
+                ErrorKind::PermissionDenied,
+                format!("{} was frozen by {}.", self.path.display(), f.user),
+            )),
+            None => Ok(()),
+        }
+    }
+
     /// Increment version
     fn increase_version_number(&mut self) {
         self.version += 1;
@@ -136,11 +504,23 @@ impl File {
         task: TaskTreeNode,
         project: Project,
         dcc: Dcc,
+        template: &FilenameTemplate,
     ) -> Result<(), io::Error> {
-        let filename = Self::make_filename(&name, &task, &project, &dcc);
-        let path = Self::make_path(task, filename);
+        Self::create_file_with(&RealFs, name, task, project, dcc, template)
+    }
 
-        match Self::copy_file(path, dcc) {
+    pub fn create_file_with(
+        fs: &dyn Fs,
+        name: String,
+        task: TaskTreeNode,
+        project: Project,
+        dcc: Dcc,
+        template: &FilenameTemplate,
+    ) -> Result<(), io::Error> {
+        let path = Self::new_file_path(&name, &task, &project, &dcc, template);
+        let tokens = Self::template_tokens(&task, &project, &path);
+
+        match Self::copy_file(fs, path, dcc, &tokens) {
             Ok(()) => (),
             Err(e) => return Err(e),
         }
@@ -148,55 +528,101 @@ impl File {
     }
 
     fn make_filename_from_self(&self) -> String {
-        String::from(format!(
-            "{}_{}.{}",
-            self.name,
-            self.fmt_version(),
-            self.extension
-        ))
-    }
-
-    fn make_filename(name: &String, task: &TaskTreeNode, project: &Project, dcc: &Dcc) -> String {
-        if name.len() > 0 {
-            return String::from(format!(
-                "{}_{}_{}_v001{}",
-                project.name_sanitized, task.name, name, dcc.extension
-            ));
-        } else {
-            return String::from(format!(
-                "{}_{}_v001{}",
-                project.name_sanitized, task.name, dcc.extension
-            ));
-        }
+        format!(
+            "{}{:0width$}{}.{}",
+            self.prefix,
+            self.version,
+            self.suffix,
+            self.extension,
+            width = self.padding()
+        )
+    }
+
+    fn make_filename(
+        name: &str,
+        task: &TaskTreeNode,
+        project: &Project,
+        dcc: &Dcc,
+        template: &FilenameTemplate,
+    ) -> String {
+        let tokens = FilenameTokens {
+            project: project.name_sanitized.clone(),
+            task: task.name.clone(),
+            name: String::from(name),
+            version: 1,
+        };
+        format!("{}{}", template.format(&tokens), dcc.extension)
     }
 
-    fn make_path(task: TaskTreeNode, name: String) -> PathBuf {
+    /// Returns the path a new workfile would be created at, without creating it.
+    pub fn new_file_path(
+        name: &str,
+        task: &TaskTreeNode,
+        project: &Project,
+        dcc: &Dcc,
+        template: &FilenameTemplate,
+    ) -> PathBuf {
+        let filename = Self::make_filename(name, task, project, dcc, template);
+        Self::make_path(task, filename)
+    }
+
+    fn make_path(task: &TaskTreeNode, name: String) -> PathBuf {
         let mut path = task.get_work_path();
         path.push(PathBuf::from(name));
         path
     }
 
-    fn copy_file(path: PathBuf, dcc: Dcc) -> Result<(), io::Error> {
-        match path.try_exists() {
-            Ok(b) => {
-                if b {
-                    return Err(Error::new(ErrorKind::Other, "File already exists!"));
-                }
-            }
-            Err(e) => return Err(e),
+    /// Tokens replaced inside text templates. Paths use forward slashes, which all supported DCC:s accept.
+    fn template_tokens(
+        task: &TaskTreeNode,
+        project: &Project,
+        path: &Path,
+    ) -> Vec<(String, String)> {
+        let fmt_path = |p: &Path| p.display().to_string().replace('\\', "/");
+        let filename = path
+            .file_name()
+            .and_then(|f| f.to_str())
+            .unwrap_or("")
+            .to_string();
+        vec![
+            (String::from("{PROJECT}"), project.name_sanitized.clone()),
+            (String::from("{TASK}"), task.name.clone()),
+            (String::from("{TASK_PATH}"), fmt_path(&task.path)),
+            (String::from("{WORK_PATH}"), fmt_path(&task.get_work_path())),
+            (
+                String::from("{OUTPUT_PATH}"),
+                fmt_path(&task.path.join(&task.metadata.output_dir_name)),
+            ),
+            (String::from("{FILENAME}"), filename),
+        ]
+    }
+
+    /// Replaces each token in the text with its value, see `helpers::substitute_tokens`.
+    pub fn substitute_tokens(text: &str, tokens: &[(String, String)]) -> String {
+        substitute_tokens(text, tokens)
+    }
+
+    fn copy_file(
+        fs: &dyn Fs,
+        path: PathBuf,
+        dcc: Dcc,
+        tokens: &[(String, String)],
+    ) -> Result<(), io::Error> {
+        if fs.exists(&path) {
+            return Err(Error::new(ErrorKind::Other, "File already exists!"));
         }
 
-        match dcc.template_path.try_exists() {
-            Ok(b) => {
-                if !b {
-                    return Err(Error::new(ErrorKind::Other, "Template file not found."));
-                }
-            }
-            Err(e) => return Err(e),
+        if !fs.exists(&dcc.template_path) {
+            return Err(Error::new(ErrorKind::Other, "Template file not found."));
+        }
+
+        if dcc.substitute_tokens {
+            let text = fs.read_to_string(&dcc.template_path)?;
+            return fs.create_new(&path, Self::substitute_tokens(&text, tokens).as_bytes());
         }
 
-        match fs::copy(&dcc.template_path, &path) {
-            Ok(_u) => return Ok(()),
+        match fs.copy(&dcc.template_path, &path) {
+            Ok(()) => Ok(()),
             Err(e) => {
                 error!(
                     "Failed to copy {} to {}: {}",
@@ -204,7 +630,7 @@ impl File {
                     path.display(),
                     e.to_string()
                 );
-                return Err(e);
+                Err(e)
             }
         }
     }
@@ -216,42 +642,99 @@ pub struct Dcc {
     pub name: String,
     pub extension: String,
     pub template_path: PathBuf,
+    /// Replace tokens like `{PROJECT}` in the template when creating files. Only for text formats,
+    /// e.g. .nk or ASCII .hip/.ma, binary templates are copied as is.
+    #[serde(default)]
+    pub substitute_tokens: bool,
+    /// Workfiles are opened with this instead of the application the OS associates with the extension.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub executable: Option<PathBuf>,
+    /// The app version used in the project, e.g. `15` or `15.1`. Opening a workfile saved with a
+    /// newer version asks first.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub version: Option<String>,
+    /// Where the DCC writes autosaves, e.g. `~/maya/projects/default/autosave` or `$HOUDINI_TEMP_DIR`.
+    /// Autosaves next to the workfiles are found without this.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub autosave_dirs: Vec<PathBuf>,
 }
 
 impl Dcc {
-    /// Search specified directory for config files and templates, return list of Dcc:s.
-    pub fn find_dcc(path: &PathBuf) -> Result<Vec<Dcc>, io::Error> {
-        let mut dcc: Vec<Dcc> = Vec::new();
+    /// True if the file was saved with a newer version than the one in the config. Only as many
+    /// numbers as the config has are compared, so `15` doesn't warn about `15.1v2`.
+    pub fn is_older_than(&self, saved_with: &str) -> bool {
+        let numbers = |v: &str| -> Vec<u64> {
+            v.split(|c: char| !c.is_ascii_digit())
+                .filter_map(|n| n.parse().ok())
+                .collect()
+        };
+        let version = match &self.version {
+            Some(v) => numbers(v),
+            None => return false,
+        };
+        let saved_with: Vec<u64> = numbers(saved_with)
+            .into_iter()
+            .take(version.len())
+            .collect();
+        !saved_with.is_empty() && saved_with > version
+    }
 
-        info!("Looking for DCC in: {}", path.display());
-        let dir_listing = match fs::read_dir(path) {
-            Ok(listing) => listing,
-            Err(e) => return Err(e),
+    /// The DCC to preselect for a new workfile in a task: the one used last, or else the one most of
+    /// the workfiles are made with.
+    pub fn preferred<'a>(dccs: &'a [Dcc], last: Option<&str>, files: &[File]) -> Option<&'a Dcc> {
+        if let Some(d) = last.and_then(|name| dccs.iter().find(|d| d.name == name)) {
+            return Some(d);
+        }
+        let count = |d: &Dcc| {
+            files
+                .iter()
+                .filter(|f| format!(".{}", f.extension) == d.extension)
+                .count()
         };
+        // The first of equally common DCC:s wins.
+        dccs.iter()
+            .map(|d| (count(d), d))
+            .filter(|(n, _d)| *n > 0)
+            .fold(None, |best: Option<(usize, &Dcc)>, (n, d)| match best {
+                Some((m, _)) if m >= n => best,
+                _ => Some((n, d)),
+            })
+            .map(|(_n, d)| d)
+    }
 
-        for l in dir_listing {
-            let item = match l {
-                Ok(d) => d,
-                Err(_e) => continue,
-            };
+    /// Search specified directory for config files and templates, return list of Dcc:s.
+    pub fn find_dcc(path: &Path) -> Result<Vec<Dcc>, io::Error> {
+        Self::find_dcc_with(&RealFs, path)
+    }
+
+    pub fn find_dcc_with(fs: &dyn Fs, path: &Path) -> Result<Vec<Dcc>, io::Error> {
+        let mut dcc: Vec<Dcc> = Vec::new();
 
-            if item.path().is_file() {
+        info!("Looking for DCC in: {}", path.display());
+        let dir_listing = fs.read_dir(path)?;
+
+        for item in dir_listing {
+            if fs.is_file(&item)
+                || item
+                    .file_name()
+                    .map_or(false, |n| n == PIPELINE_TEMPLATE_DIR_NAME)
+            {
                 continue;
             }
 
-            let mut app_config = item.path().clone();
+            let mut app_config = item.clone();
             app_config.push(PathBuf::from("app.yaml"));
 
             info!("Looking for dcc config: {}", app_config.display());
-            let file = match std::fs::File::open(app_config) {
-                Ok(f) => f,
+            let text = match fs.read_to_string(&app_config) {
+                Ok(t) => t,
                 Err(e) => {
                     error!("Could not load config: {}", e);
                     continue;
                 }
             };
 
-            let mut dcc_config: Dcc = match serde_yaml::from_reader(file) {
+            let mut dcc_config: Dcc = match serde_yaml::from_str(&text) {
                 Ok(c) => c,
                 Err(e) => {
                     error!("Could not load dcc: {}", e);
@@ -259,9 +742,9 @@ impl Dcc {
                 }
             };
 
-            let mut template_path = item.path().clone();
+            let mut template_path = item.clone();
             template_path.push(PathBuf::from(format!("template{}", dcc_config.extension)));
-            if !template_path.exists() {
+            if !fs.exists(&template_path) {
                 error!("Template file not found: {}", template_path.display());
                 continue;
             }